@@ -0,0 +1,119 @@
+//! Benchmarks of the RustHound hot paths
+//!
+//! `cargo bench` runs every group, `cargo bench -- secdesc` only one of them.
+//! Compare two branches with `cargo bench -- --save-baseline main` then `cargo bench -- --baseline main`,
+//! criterion reports the regressions. The synthetic domain size is set with `RUSTHOUND_BENCH_OBJECTS`.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::HashMap;
+
+use rusthound::enums::acl::parse_ntsecuritydescriptor;
+use rusthound::enums::secdesc::{Acl, LdapSid, SecurityDescriptor};
+use rusthound::enums::sid::sid_maker;
+use rusthound::json::parser::bh_41::{parse_computer, parse_group, parse_user};
+use rusthound::json::templates::bh_41::prepare_final_json_file_template;
+
+mod synthetic;
+use synthetic::*;
+
+/// Security descriptor, ACL and SID parsing.
+fn bench_secdesc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("secdesc");
+    for ace_count in [10, 50, 200] {
+        let secdesc = make_security_descriptor(512, ace_count);
+        group.throughput(Throughput::Bytes(secdesc.len() as u64));
+        group.bench_with_input(BenchmarkId::new("security_descriptor", ace_count), &secdesc, |b, secdesc| {
+            b.iter(|| {
+                let sd = SecurityDescriptor::parse(black_box(secdesc)).unwrap().1;
+                Acl::parse(&secdesc[sd.offset_dacl as usize..]).unwrap().1
+            })
+        });
+    }
+    group.finish();
+}
+
+/// ACE to BloodHound relations conversion.
+fn bench_aces(c: &mut Criterion) {
+    let mut group = c.benchmark_group("aces");
+    let domain = DOMAIN.to_string();
+    let attrs: HashMap<String, Vec<String>> = HashMap::new();
+    let bin_attrs: HashMap<String, Vec<Vec<u8>>> = HashMap::new();
+    for ace_count in [10, 50, 200] {
+        let secdesc = make_security_descriptor(512, ace_count);
+        group.throughput(Throughput::Elements(ace_count as u64));
+        group.bench_with_input(BenchmarkId::new("parse_ntsecuritydescriptor", ace_count), &secdesc, |b, secdesc| {
+            b.iter(|| {
+                let mut object = serde_json::json!({"Properties": {}});
+                parse_ntsecuritydescriptor(&mut object, black_box(secdesc), "group".to_string(), &attrs, &bin_attrs, &domain)
+            })
+        });
+    }
+    group.finish();
+}
+
+/// SID formatting.
+fn bench_sid(c: &mut Criterion) {
+    let domain = DOMAIN.to_string();
+    let domain_sid = LdapSid::parse(&make_domain_sid(1104)).unwrap().1;
+    let builtin_sid = LdapSid::parse(&make_sid(&[32, 544])).unwrap().1;
+    c.bench_function("sid/domain_sid", |b| b.iter(|| sid_maker(black_box(domain_sid.to_owned()), &domain)));
+    c.bench_function("sid/builtin_sid", |b| b.iter(|| sid_maker(black_box(builtin_sid.to_owned()), &domain)));
+}
+
+/// LDAP entries to BloodHound objects, on the synthetic domain.
+fn bench_parser(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parser");
+    group.sample_size(10);
+    let domain = DOMAIN.to_string();
+    let (users, groups, computers) = make_domain(get_bench_objects());
+
+    group.throughput(Throughput::Elements(users.len() as u64));
+    group.bench_function(BenchmarkId::new("users", users.len()), |b| {
+        b.iter_batched(|| users.to_owned(), |users| {
+            let (mut dn_sid, mut sid_type) = (HashMap::new(), HashMap::new());
+            users.into_iter().map(|user| parse_user(user, &domain, &mut dn_sid, &mut sid_type, false)).collect::<Vec<_>>()
+        }, criterion::BatchSize::LargeInput)
+    });
+    group.throughput(Throughput::Elements(groups.len() as u64));
+    group.bench_function(BenchmarkId::new("groups", groups.len()), |b| {
+        b.iter_batched(|| groups.to_owned(), |groups| {
+            let (mut dn_sid, mut sid_type) = (HashMap::new(), HashMap::new());
+            groups.into_iter().map(|group| parse_group(group, &domain, &mut dn_sid, &mut sid_type)).collect::<Vec<_>>()
+        }, criterion::BatchSize::LargeInput)
+    });
+    group.throughput(Throughput::Elements(computers.len() as u64));
+    group.bench_function(BenchmarkId::new("computers", computers.len()), |b| {
+        b.iter_batched(|| computers.to_owned(), |computers| {
+            let (mut dn_sid, mut sid_type, mut fqdn_sid, mut fqdn_ip) = (HashMap::new(), HashMap::new(), HashMap::new(), HashMap::new());
+            computers.into_iter()
+                .map(|computer| parse_computer(computer, &domain, &mut dn_sid, &mut sid_type, &mut fqdn_sid, &mut fqdn_ip))
+                .collect::<Vec<_>>()
+        }, criterion::BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+/// Final .json file building and serialization.
+fn bench_json(c: &mut Criterion) {
+    let mut group = c.benchmark_group("json");
+    group.sample_size(10);
+    let domain = DOMAIN.to_string();
+    let (users, _, _) = make_domain(get_bench_objects());
+    let (mut dn_sid, mut sid_type) = (HashMap::new(), HashMap::new());
+    let vec_users: Vec<serde_json::value::Value> = users.into_iter()
+        .map(|user| parse_user(user, &domain, &mut dn_sid, &mut sid_type, false))
+        .collect();
+
+    group.throughput(Throughput::Elements(vec_users.len() as u64));
+    group.bench_function(BenchmarkId::new("users", vec_users.len()), |b| {
+        b.iter_batched(|| vec_users.to_owned(), |vec_users| {
+            let mut final_json = prepare_final_json_file_template(5, "users".to_string());
+            final_json["meta"]["count"] = vec_users.len().into();
+            final_json["data"] = vec_users.into();
+            serde_json::to_vec(&final_json).unwrap()
+        }, criterion::BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_secdesc, bench_aces, bench_sid, bench_parser, bench_json);
+criterion_main!(benches);
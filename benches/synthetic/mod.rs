@@ -0,0 +1,181 @@
+//! Synthetic Active Directory domain for the benchmarks
+//!
+//! Builds LDAP entries like the ones received from a DC: users, groups and computers with their objectSid and
+//! one nTSecurityDescriptor per object (owner, DACL with allowed and object ACEs), so the parser hot paths
+//! run on representative data without a lab. Sizes are set with `RUSTHOUND_BENCH_OBJECTS` (default 10000).
+#![allow(dead_code)]
+
+use ldap3::SearchEntry;
+use std::collections::HashMap;
+
+pub const DOMAIN: &str = "ESSOS.LOCAL";
+pub const DOMAIN_SID: [u32; 4] = [21, 1_000_001, 2_000_002, 3_000_003];
+
+/// Number of objects of the large synthetic domain.
+pub fn get_bench_objects() -> usize {
+    std::env::var("RUSTHOUND_BENCH_OBJECTS").ok()
+        .and_then(|objects| objects.parse::<usize>().ok())
+        .unwrap_or(10_000)
+}
+
+/// Binary SID, revision 1 and authority 5 (NT AUTHORITY).
+pub fn make_sid(sub_authorities: &[u32]) -> Vec<u8> {
+    let mut sid = vec![1, sub_authorities.len() as u8, 0, 0, 0, 0, 0, 5];
+    for sub_authority in sub_authorities {
+        sid.extend_from_slice(&sub_authority.to_le_bytes());
+    }
+    sid
+}
+
+/// Binary SID of one domain principal.
+pub fn make_domain_sid(rid: u32) -> Vec<u8> {
+    let mut sub_authorities = DOMAIN_SID.to_vec();
+    sub_authorities.push(rid);
+    make_sid(&sub_authorities)
+}
+
+/// Binary GUID (mixed endian) from its string `bf9679c0-0de6-11d0-a285-00aa003049e2`.
+pub fn make_guid(guid: &str) -> Vec<u8> {
+    let hex: String = guid.chars().filter(|c| *c != '-').collect();
+    let bytes: Vec<u8> = (0..16).map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap()).collect();
+    let mut guid = Vec::with_capacity(16);
+    guid.extend(bytes[0..4].iter().rev());
+    guid.extend(bytes[4..6].iter().rev());
+    guid.extend(bytes[6..8].iter().rev());
+    guid.extend_from_slice(&bytes[8..16]);
+    guid
+}
+
+/// ACCESS_ALLOWED_ACE
+pub fn make_ace(flags: u8, mask: u32, sid: &[u8]) -> Vec<u8> {
+    let mut ace = vec![0x00, flags];
+    ace.extend_from_slice(&((8 + sid.len()) as u16).to_le_bytes());
+    ace.extend_from_slice(&mask.to_le_bytes());
+    ace.extend_from_slice(sid);
+    ace
+}
+
+/// ACCESS_ALLOWED_OBJECT_ACE with one object type.
+pub fn make_object_ace(flags: u8, mask: u32, object_type: &str, sid: &[u8]) -> Vec<u8> {
+    let mut ace = vec![0x05, flags];
+    ace.extend_from_slice(&((28 + sid.len()) as u16).to_le_bytes());
+    ace.extend_from_slice(&mask.to_le_bytes());
+    // ACE_OBJECT_TYPE_PRESENT
+    ace.extend_from_slice(&1u32.to_le_bytes());
+    ace.extend_from_slice(&make_guid(object_type));
+    ace.extend_from_slice(sid);
+    ace
+}
+
+/// Self-relative security descriptor with one owner and one DACL of `ace_count` ACEs.
+pub fn make_security_descriptor(owner_rid: u32, ace_count: usize) -> Vec<u8> {
+    let owner = make_domain_sid(owner_rid);
+    let mut aces: Vec<u8> = Vec::new();
+    for i in 0..ace_count {
+        let sid = make_domain_sid(1000 + (i as u32 % 50));
+        let ace = match i % 5 {
+            // GenericAll
+            0 => make_ace(0x00, 0x000f01ff, &sid),
+            // WriteDacl + WriteOwner
+            1 => make_ace(0x12, 0x000c0000, &sid),
+            // AddMember
+            2 => make_object_ace(0x00, 0x00000020, "bf9679c0-0de6-11d0-a285-00aa003049e2", &sid),
+            // ForceChangePassword
+            3 => make_object_ace(0x10, 0x00000100, "00299570-246d-11d0-a768-00aa006e0529", &sid),
+            // Read only, no edge
+            _ => make_ace(0x12, 0x00020094, &make_sid(&[11])),
+        };
+        aces.extend(ace);
+    }
+    let mut acl = vec![0x04, 0x00];
+    acl.extend_from_slice(&((8 + aces.len()) as u16).to_le_bytes());
+    acl.extend_from_slice(&(ace_count as u16).to_le_bytes());
+    acl.extend_from_slice(&[0x00, 0x00]);
+    acl.extend(aces);
+
+    // revision, sbz1, control SE_DACL_PRESENT | SE_SELF_RELATIVE
+    let mut secdesc = vec![0x01, 0x00, 0x04, 0x80];
+    let offset_dacl = 20u32;
+    let offset_owner = offset_dacl + acl.len() as u32;
+    secdesc.extend_from_slice(&offset_owner.to_le_bytes());
+    secdesc.extend_from_slice(&0u32.to_le_bytes());
+    secdesc.extend_from_slice(&0u32.to_le_bytes());
+    secdesc.extend_from_slice(&offset_dacl.to_le_bytes());
+    secdesc.extend(acl);
+    secdesc.extend(owner);
+    secdesc
+}
+
+fn make_entry(dn: String, attrs: Vec<(&str, Vec<String>)>, rid: u32, ace_count: usize) -> SearchEntry {
+    let mut bin_attrs: HashMap<String, Vec<Vec<u8>>> = HashMap::new();
+    bin_attrs.insert("objectSid".to_string(), vec![make_domain_sid(rid)]);
+    bin_attrs.insert("nTSecurityDescriptor".to_string(), vec![make_security_descriptor(512, ace_count)]);
+    SearchEntry {
+        dn,
+        attrs: attrs.into_iter().map(|(name, values)| (name.to_string(), values)).collect(),
+        bin_attrs,
+    }
+}
+
+/// One user, about 20 ACEs like a default user object.
+pub fn make_user(i: usize) -> SearchEntry {
+    let name = format!("user{}", i);
+    make_entry(
+        format!("CN={},OU=Users,DC=ESSOS,DC=LOCAL", name.to_uppercase()),
+        vec![
+            ("objectClass", vec!["top".to_string(), "person".to_string(), "organizationalPerson".to_string(), "user".to_string()]),
+            ("sAMAccountName", vec![name.to_owned()]),
+            ("sAMAccountType", vec!["805306368".to_string()]),
+            ("userAccountControl", vec![if i % 10 == 0 { "66048" } else { "512" }.to_string()]),
+            ("description", vec![format!("Synthetic user {}", i)]),
+            ("whenCreated", vec!["20230101000000.0Z".to_string()]),
+            ("pwdLastSet", vec!["133444736000000000".to_string()]),
+            ("lastLogonTimestamp", vec!["133444736000000000".to_string()]),
+            ("servicePrincipalName", if i % 50 == 0 { vec![format!("HTTP/{}.essos.local", name)] } else { Vec::new() }),
+        ],
+        10_000 + i as u32,
+        20,
+    )
+}
+
+/// One group with `members` users.
+pub fn make_group(i: usize, members: usize) -> SearchEntry {
+    let name = format!("group{}", i);
+    make_entry(
+        format!("CN={},OU=Groups,DC=ESSOS,DC=LOCAL", name.to_uppercase()),
+        vec![
+            ("objectClass", vec!["top".to_string(), "group".to_string()]),
+            ("sAMAccountName", vec![name]),
+            ("member", (0..members).map(|member| format!("CN=USER{},OU=Users,DC=ESSOS,DC=LOCAL", (i * 7 + member) % 1000)).collect()),
+            ("whenCreated", vec!["20230101000000.0Z".to_string()]),
+        ],
+        5_000 + i as u32,
+        15,
+    )
+}
+
+/// One computer account.
+pub fn make_computer(i: usize) -> SearchEntry {
+    let name = format!("WKS{:05}", i);
+    make_entry(
+        format!("CN={},OU=Workstations,DC=ESSOS,DC=LOCAL", name),
+        vec![
+            ("objectClass", vec!["top".to_string(), "person".to_string(), "organizationalPerson".to_string(), "user".to_string(), "computer".to_string()]),
+            ("sAMAccountName", vec![format!("{}$", name)]),
+            ("dNSHostName", vec![format!("{}.essos.local", name.to_lowercase())]),
+            ("operatingSystem", vec!["Windows 10 Enterprise".to_string()]),
+            ("userAccountControl", vec!["4096".to_string()]),
+            ("whenCreated", vec!["20230101000000.0Z".to_string()]),
+        ],
+        100_000 + i as u32,
+        25,
+    )
+}
+
+/// Users, groups and computers of one domain with `objects` objects: 60% users, 10% groups and 30% computers.
+pub fn make_domain(objects: usize) -> (Vec<SearchEntry>, Vec<SearchEntry>, Vec<SearchEntry>) {
+    let users = (0..objects * 6 / 10).map(make_user).collect();
+    let groups = (0..objects / 10).map(|i| make_group(i, 1 + i % 40)).collect();
+    let computers = (0..objects * 3 / 10).map(make_computer).collect();
+    (users, groups, computers)
+}
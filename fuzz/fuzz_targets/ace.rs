@@ -0,0 +1,13 @@
+//! One ACE parsing and the accessors used by the ACE to edge conversion.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rusthound::enums::secdesc::{Ace, AceFormat};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((_, ace)) = Ace::parse(data) {
+        let _ = AceFormat::get_mask(ace.data.to_owned());
+        let _ = AceFormat::get_flags(ace.data.to_owned());
+        let _ = AceFormat::get_object_type(ace.data.to_owned());
+        let _ = AceFormat::get_sid(ace.data);
+    }
+});
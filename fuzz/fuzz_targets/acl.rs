@@ -0,0 +1,8 @@
+//! ACL parsing, SACL or DACL.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rusthound::enums::secdesc::Acl;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Acl::parse(data);
+});
@@ -0,0 +1,13 @@
+//! Full nTSecurityDescriptor parsing, header then owner, group, SACL and DACL at their offsets.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rusthound::enums::secdesc::SecurityDescriptor;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((_, secdesc)) = SecurityDescriptor::parse(data) {
+        let _ = secdesc.parse_owner(data);
+        let _ = secdesc.parse_group(data);
+        let _ = secdesc.parse_sacl(data);
+        let _ = secdesc.parse_dacl(data);
+    }
+});
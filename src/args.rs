@@ -1,6 +1,14 @@
 //! Parsing arguments
 #[cfg(not(feature = "noargs"))]
-use clap::{Arg, ArgAction, value_parser, Command};
+use clap::{Arg, ArgAction, ArgMatches, value_parser, Command};
+#[cfg(not(feature = "noargs"))]
+use crate::ldap::{parse_bandwidth, parse_collection_methods};
+#[cfg(not(feature = "noargs"))]
+use crate::json::maker::wordlists::parse_users_formats;
+#[cfg(not(feature = "noargs"))]
+use crate::daemon::parse_schedule;
+#[cfg(not(feature = "noargs"))]
+use crate::json::checker::privileged::parse_privileged_groups;
 
 #[cfg(feature = "noargs")]
 use winreg::{RegKey,{enums::*}};
@@ -18,16 +26,185 @@ pub struct Options {
     pub ip: String,
     pub port: String,
     pub name_server: String,
+    pub timeout: u64,
+    pub search_timeout: u64,
+    pub dns_timeout: u64,
+    pub max_runtime: u64,
+    pub max_bandwidth: u64,
+    pub parse_workers: usize,
+    pub disk_store: String,
+    pub acl_benign_sids: String,
+    pub explain_aces: String,
+    pub edge_provenance: String,
+    pub privileged_groups: String,
     pub path: String,
+    pub output_format: String,
+    pub output_layout: String,
+    pub http_url: String,
+    pub http_token: String,
+    pub http_cert: String,
+    pub splunk_url: String,
+    pub splunk_token: String,
+    pub elastic_url: String,
+    pub elastic_api_key: String,
+    pub neo4j_url: String,
+    pub neo4j_user: String,
+    pub neo4j_password: String,
+    pub upload_url: String,
+    pub upload_token: String,
+    pub krbtgt_max_age: i64,
+    pub trust_max_age: i64,
+    pub stale_days: i64,
+    pub stale_skip_probes: bool,
+    pub password_stdin: bool,
+    pub fail_on_partial: bool,
+    pub credentials_file: String,
+    pub schedule: String,
+    pub schedule_keep: usize,
+    pub health_port: u16,
+    pub attack_paths: i64,
+    pub export_users_format: String,
     pub ldaps: bool,
     pub dns_tcp: bool,
+    pub follow_referrals: bool,
+    pub global_catalog: bool,
     pub fqdn_resolver: bool,
     pub adcs: bool,
+    pub mssql_probe: bool,
+    pub probe_coercion: bool,
+    pub probe_smb: bool,
+    pub probe_winrm: bool,
+    pub probe_rdp: bool,
+    pub shares: bool,
+    pub sysvol: bool,
+    pub sysvol_acls: bool,
     pub old_bloodhound: bool,
     pub dc_only: bool,
+    pub collection_methods: String,
     pub kerberos: bool,
     pub zip: bool,
+    pub ndjson: bool,
+    pub sarif: bool,
+    pub relay_targets: bool,
+    pub audit: bool,
+    pub acl_skip_benign: bool,
+    pub neo4j_upsert: bool,
+    pub sharphound_ids: bool,
+    pub no_disk: bool,
+    pub stdout: bool,
     pub verbose: log::LevelFilter,
+    pub graph: Option<GraphOptions>,
+    pub merge: Option<MergeOptions>,
+    pub query: Option<QueryOptions>,
+    pub profile_save: Option<ProfileOptions>,
+    pub add_computer: Option<AddComputerOptions>,
+    pub set_rbcd: Option<SetRbcdOptions>,
+    pub set_keycredential: Option<SetKeyCredentialOptions>,
+    pub set_password: Option<SetPasswordOptions>,
+    pub acl_backup: Option<AclOptions>,
+    pub acl_restore: Option<AclOptions>,
+    pub dry_run: bool,
+    pub journal: String,
+    pub profile: String,
+    pub selftest: bool,
+}
+
+/// Options of the `rusthound graph` mode.
+#[derive(Clone, Debug)]
+pub struct GraphOptions {
+    pub target: String,
+    pub input: String,
+    pub format: String,
+    pub depth: i64,
+    pub output: String,
+}
+
+/// Options of the `rusthound merge` mode.
+#[derive(Clone, Debug)]
+pub struct MergeOptions {
+    pub inputs: Vec<String>,
+    pub output: String,
+}
+
+/// Options of the `rusthound query` mode.
+#[derive(Clone, Debug)]
+pub struct QueryOptions {
+    pub pack: String,
+    pub input: String,
+    pub output: String,
+}
+
+/// Options of the `rusthound profile save` mode.
+#[derive(Clone, Debug)]
+pub struct ProfileOptions {
+    pub name: String,
+    pub domain: String,
+    pub username: String,
+    pub password_stdin: bool,
+}
+
+/// Options of the `rusthound add-computer` write helper.
+#[derive(Clone, Debug)]
+pub struct AddComputerOptions {
+    pub name: String,
+    pub password: String,
+    pub ou: String,
+}
+
+/// Options of the `rusthound set-rbcd` write helper.
+#[derive(Clone, Debug)]
+pub struct SetRbcdOptions {
+    pub target: String,
+    pub grant: String,
+    pub revert: bool,
+}
+
+/// Options of the `rusthound set-keycredential` write helper.
+#[derive(Clone, Debug)]
+pub struct SetKeyCredentialOptions {
+    pub target: String,
+    pub remove: String,
+}
+
+/// Options of the `rusthound set-password` write helper.
+#[derive(Clone, Debug)]
+pub struct SetPasswordOptions {
+    pub target: String,
+    pub new_password: String,
+    pub old_password: String,
+}
+
+/// Options of the `rusthound acl backup` and `rusthound acl restore` write helpers.
+#[derive(Clone, Debug)]
+pub struct AclOptions {
+    pub objects: Vec<String>,
+    pub file: String,
+}
+
+/// Function to make the options of the subcommands writing to the directory.
+#[cfg(not(feature = "noargs"))]
+fn write_args() -> [Arg; 2] {
+    [
+        Arg::new("dry-run")
+            .long("dry-run")
+            .help("Print the LDAP add or modify with its values and SDDL without sending it")
+            .required(false)
+            .action(ArgAction::SetTrue),
+        Arg::new("journal")
+            .long("journal")
+            .help("Journal of the changes sent, to clean them up, default: rusthound_journal.jsonl in the output directory")
+            .required(false)
+            .value_parser(value_parser!(String)),
+    ]
+}
+
+/// Function to get the matches of the deepest subcommand, like `restore` of `rusthound acl restore`.
+#[cfg(not(feature = "noargs"))]
+fn get_subcommand_matches(matches: &ArgMatches) -> &ArgMatches {
+    match matches.subcommand() {
+        Some((_, subcommand_matches)) => get_subcommand_matches(subcommand_matches),
+        None => matches,
+    }
 }
 
 #[cfg(not(feature = "noargs"))]
@@ -40,12 +217,244 @@ fn cli() -> Command {
             .help("Set the level of verbosity")
             .action(ArgAction::Count),
         )
+        .subcommand_negates_reqs(true)
+        .subcommand(Command::new("graph")
+            .about("Render the inbound control subgraph of one object from collected .json files in DOT or Mermaid")
+            .arg(Arg::new("target")
+                .short('t')
+                .long("target")
+                .help("Object name or SID like: \"Domain Admins\" or DOMAIN ADMINS@DOMAIN.LOCAL")
+                .required(true)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("input")
+                .short('i')
+                .long("input")
+                .help("Directory with the .json files collected by RustHound [default: ./]")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("format")
+                .long("format")
+                .help("Output format [default: dot]")
+                .required(false)
+                .value_parser(["dot", "mermaid"])
+            )
+            .arg(Arg::new("depth")
+                .long("depth")
+                .help("Maximum number of edges between the principals and the target [default: 3]")
+                .required(false)
+                .value_parser(value_parser!(i64))
+            )
+            .arg(Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Output file, standard output if not set")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+        )
+        .subcommand(Command::new("merge")
+            .about("Merge .json files collected by SharpHound and RustHound, objects are deduplicated by objectid")
+            .arg(Arg::new("inputs")
+                .help("Directories with the .json files to merge")
+                .required(true)
+                .num_args(2..)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Output directory for the merged .json files [default: ./merged]")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+        )
+        .subcommand(Command::new("query")
+            .about("Run a TOML pack of custom queries against collected .json files")
+            .arg(Arg::new("pack")
+                .short('p')
+                .long("pack")
+                .help("TOML file with the [[query]] tables to run")
+                .required(true)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("input")
+                .short('i')
+                .long("input")
+                .help("Directory with the .json files collected by RustHound [default: ./]")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Output .json file with the matches, standard output if not set")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+        )
+        .subcommand(Command::new("profile")
+            .about("Manage collection profiles with credentials protected by DPAPI (Windows only)")
+            .subcommand_required(true)
+            .subcommand(Command::new("save")
+                .about("Save the domain, username and password of one collection, the password is prompted")
+                .arg(Arg::new("name")
+                    .help("Profile name used with --profile")
+                    .required(true)
+                    .value_parser(value_parser!(String))
+                )
+                .arg(Arg::new("domain")
+                    .short('d')
+                    .long("domain")
+                    .help("Domain name like: DOMAIN.LOCAL")
+                    .required(true)
+                    .value_parser(value_parser!(String))
+                )
+                .arg(Arg::new("ldapusername")
+                    .short('u')
+                    .long("ldapusername")
+                    .help("LDAP username, like: user@domain.local")
+                    .required(true)
+                    .value_parser(value_parser!(String))
+                )
+                .arg(Arg::new("password-stdin")
+                    .long("password-stdin")
+                    .help("Read the LDAP password from the first line of the standard input instead of a prompt")
+                    .required(false)
+                    .action(ArgAction::SetTrue)
+                )
+            )
+        )
+        .subcommand(Command::new("add-computer")
+            .about("Create one machine account over LDAPS within the MachineAccountQuota, with the connection options given before the subcommand")
+            .args(write_args())
+            .arg(Arg::new("name")
+                .long("name")
+                .help("Computer name, with or without $, like: ATTACKER")
+                .required(true)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("computer-pass")
+                .long("computer-pass")
+                .help("Password of the machine account")
+                .required(true)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("computer-ou")
+                .long("computer-ou")
+                .help("Distinguished name of the container of the machine account [default: CN=Computers of the domain]")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+        )
+        .subcommand(Command::new("set-rbcd")
+            .about("Allow one account to delegate to a computer with msDS-AllowedToActOnBehalfOfOtherIdentity over LDAPS, with the connection options given before the subcommand")
+            .args(write_args())
+            .arg(Arg::new("target")
+                .long("target")
+                .help("sAMAccountName of the computer to write, like: PC01$")
+                .required(true)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("grant")
+                .long("grant")
+                .help("sAMAccountName of the account allowed to delegate, like: ATTACKER$")
+                .required(true)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("revert")
+                .long("revert")
+                .help("Remove the account from msDS-AllowedToActOnBehalfOfOtherIdentity instead")
+                .required(false)
+                .action(ArgAction::SetTrue)
+            )
+        )
+        .subcommand(Command::new("set-keycredential")
+            .about("Add one shadow credential to msDS-KeyCredentialLink over LDAPS and save the certificate and key for PKINIT, with the connection options given before the subcommand")
+            .args(write_args())
+            .arg(Arg::new("target")
+                .long("target")
+                .help("sAMAccountName of the account to write, like: BRAAVOS$ or jorah.mormont")
+                .required(true)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("remove")
+                .long("remove")
+                .value_name("DEVICE_ID")
+                .help("Remove the key credential of this DeviceId instead")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+        )
+        .subcommand(Command::new("set-password")
+            .about("Reset or change the password of one user over LDAPS, with the connection options given before the subcommand")
+            .args(write_args())
+            .arg(Arg::new("target")
+                .long("target")
+                .help("sAMAccountName of the user, like: jorah.mormont")
+                .required(true)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("new-pass")
+                .long("new-pass")
+                .help("New password of the user, prompted if not set")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+            .arg(Arg::new("old-pass")
+                .long("old-pass")
+                .help("Current password of the user to change it instead of resetting it")
+                .required(false)
+                .value_parser(value_parser!(String))
+            )
+        )
+        .subcommand(Command::new("acl")
+            .about("Save and restore the owner and DACL of objects over LDAPS, with the connection options given before the subcommand")
+            .subcommand_required(true)
+            .subcommand(Command::new("backup")
+                .about("Save the nTSecurityDescriptor of objects before modifying their ACL")
+                .arg(Arg::new("object")
+                    .long("object")
+                    .help("DN or sAMAccountName of one object to save, can be repeated")
+                    .required(true)
+                    .action(ArgAction::Append)
+                    .value_parser(value_parser!(String))
+                )
+                .arg(Arg::new("file")
+                    .long("file")
+                    .help("Backup file to write, default: acl_backup.json")
+                    .required(false)
+                    .value_parser(value_parser!(String))
+                )
+            )
+            .subcommand(Command::new("restore")
+                .about("Write back the DACL, and the owner if changed, of the objects saved")
+                .args(write_args())
+                .arg(Arg::new("object")
+                    .long("object")
+                    .help("DN or sAMAccountName of one object to restore, can be repeated, all the objects of the file if not set")
+                    .required(false)
+                    .action(ArgAction::Append)
+                    .value_parser(value_parser!(String))
+                )
+                .arg(Arg::new("file")
+                    .long("file")
+                    .help("Backup file to read, default: acl_backup.json")
+                    .required(false)
+                    .value_parser(value_parser!(String))
+                )
+            )
+        )
+        .subcommand(Command::new("selftest")
+            .about("Run the parser and output pipeline on an embedded fixture domain and check the .json files with golden files")
+        )
         .next_help_heading("REQUIRED VALUES")
         .arg(Arg::new("domain")
                 .short('d')
                 .long("domain")
                 .help("Domain name like: DOMAIN.LOCAL")
-                .required(true)
+                .required_unless_present("profile")
                 .value_parser(value_parser!(String))
             )
         .next_help_heading("OPTIONAL VALUES")
@@ -63,6 +472,20 @@ fn cli() -> Command {
             .required(false)
             .value_parser(value_parser!(String))
         )
+        .arg(Arg::new("profile")
+            .long("profile")
+            .help("Use the domain, username and password of one profile saved with: rusthound profile save")
+            .required(false)
+            .value_parser(value_parser!(String))
+            .conflicts_with("ldappassword")
+        )
+        .arg(Arg::new("credentials-file")
+            .long("credentials-file")
+            .help("Read username= and password= lines from a file, named pipe or file descriptor like: /dev/fd/3, instead of the command line")
+            .required(false)
+            .value_parser(value_parser!(String))
+            .conflicts_with("ldappassword")
+        )
         .arg(Arg::new("ldapfqdn")
             .short('f')
             .long("ldapfqdn")
@@ -87,7 +510,44 @@ fn cli() -> Command {
         .arg(Arg::new("name-server")
             .short('n')
             .long("name-server")
-            .help("Alternative IP address name server to use for DNS queries")
+            .visible_alias("dns-server")
+            .help("DNS server IP address (optionally with :port) used for DC discovery, FQDN resolution and probing modules instead of the system resolver")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("timeout")
+            .long("timeout")
+            .help("LDAP connection and bind timeout in seconds [default: 30]")
+            .required(false)
+            .value_parser(value_parser!(u64))
+        )
+        .arg(Arg::new("search-timeout")
+            .long("search-timeout")
+            .help("Maximum wait in seconds for each LDAP search response, 0 to wait forever [default: 300]")
+            .required(false)
+            .value_parser(value_parser!(u64))
+        )
+        .arg(Arg::new("max-runtime")
+            .long("max-runtime")
+            .help("Time budget in seconds for the collection, LDAP searches and network modules stop once reached and partial results are written, 0 for no limit [default: 0]")
+            .required(false)
+            .value_parser(value_parser!(u64))
+        )
+        .arg(Arg::new("max-bandwidth")
+            .long("max-bandwidth")
+            .help("Maximum bytes per second received from LDAP like: 500K or 2M")
+            .required(false)
+            .value_parser(parse_bandwidth)
+        )
+        .arg(Arg::new("parse-workers")
+            .long("parse-workers")
+            .help("Threads parsing LDAP entries while they are received, 0 to parse them after the collection [default: number of CPUs]")
+            .required(false)
+            .value_parser(value_parser!(usize))
+        )
+        .arg(Arg::new("disk-store")
+            .long("disk-store")
+            .help("Keep LDAP entries in an on-disk store in this directory instead of memory, for very large domains")
             .required(false)
             .value_parser(value_parser!(String))
         )
@@ -98,7 +558,150 @@ fn cli() -> Command {
             .required(false)
             .value_parser(value_parser!(String))
         )
+        .arg(Arg::new("output-format")
+            .long("output-format")
+            .help("Output format: json for BloodHound, csv for nodes.csv and edges.csv [default: json]")
+            .required(false)
+            .value_parser(["json", "csv"])
+        )
+        .arg(Arg::new("output-layout")
+            .long("output-layout")
+            .help("Output layout: flat for all files in the output directory, domain for <output>/<domain>/<datetime>/ with a latest link and run.json [default: flat]")
+            .required(false)
+            .value_parser(["flat", "domain"])
+        )
+        .arg(Arg::new("krbtgt-max-age")
+            .long("krbtgt-max-age")
+            .help("Maximum krbtgt password age in days before flagging golden ticket risk in report [default: 180]")
+            .required(false)
+            .value_parser(value_parser!(i64))
+        )
+        .arg(Arg::new("trust-max-age")
+            .long("trust-max-age")
+            .help("Maximum trust account password age in days before flagging it in report [default: 60]")
+            .required(false)
+            .value_parser(value_parser!(i64))
+        )
+        .arg(Arg::new("stale-days")
+            .long("stale-days")
+            .help("Flag users and computers without logon for N days as dormant (lastLogonTimestamp)")
+            .required(false)
+            .value_parser(value_parser!(i64))
+        )
+        .arg(Arg::new("attack-paths")
+            .long("attack-paths")
+            .help("Write the N shortest attack paths from Domain Users, Authenticated Users and Everyone to Tier-0 in Markdown and HTML reports")
+            .required(false)
+            .value_parser(value_parser!(i64))
+        )
+        .arg(Arg::new("export-users-format")
+            .long("export-users-format")
+            .help("Write lists of the enabled users in these comma separated formats and machines.txt: sam, domain (DOMAIN\\sam), upn")
+            .required(false)
+            .value_parser(parse_users_formats)
+        )
+        .arg(Arg::new("http-url")
+            .long("http-url")
+            .help("Send all files with POST requests to this endpoint like: https://collector.domain.local/upload")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("http-token")
+            .long("http-token")
+            .help("Bearer token for the --http-url endpoint")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("http-cert")
+            .long("http-cert")
+            .help("PEM file with the client certificate and private key for the --http-url endpoint")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("splunk-url")
+            .long("splunk-url")
+            .help("Send nodes, edges and findings to Splunk HTTP Event Collector like: https://splunk.domain.local:8088")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("splunk-token")
+            .long("splunk-token")
+            .help("HTTP Event Collector token for the --splunk-url endpoint")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("elastic-url")
+            .long("elastic-url")
+            .help("Send nodes, edges and findings to Elasticsearch bulk API like: https://elastic.domain.local:9200")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("elastic-api-key")
+            .long("elastic-api-key")
+            .help("Encoded API key for the --elastic-url endpoint")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("neo4j-url")
+            .long("neo4j-url")
+            .help("Import nodes and edges in Neo4j with the HTTP API like: http://neo4j.domain.local:7474")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("neo4j-user")
+            .long("neo4j-user")
+            .help("Neo4j username for the --neo4j-url endpoint [default: neo4j]")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("neo4j-password")
+            .long("neo4j-password")
+            .help("Neo4j password for the --neo4j-url endpoint")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("upload")
+            .long("upload")
+            .help("Upload the .json files in BloodHound CE with the API and wait for the ingest like: https://bloodhound.domain.local")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("token")
+            .long("token")
+            .help("BloodHound CE JWT for the --upload endpoint, sent as bearer token (API token id/key pairs are not supported)")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("schedule")
+            .long("schedule")
+            .help("Stay running and collect on a cron schedule (minute hour day-of-month month day-of-week) like: \"0 3 * * *\", runs are saved with --output-layout domain")
+            .required(false)
+            .value_parser(parse_schedule)
+        )
+        .arg(Arg::new("schedule-keep")
+            .long("schedule-keep")
+            .help("Number of run directories kept per domain with --schedule, the oldest ones are removed, 0 to keep all [default: 7]")
+            .required(false)
+            .value_parser(value_parser!(usize))
+            .requires("schedule")
+        )
+        .arg(Arg::new("health-port")
+            .long("health-port")
+            .help("Local port of the health and Prometheus metrics endpoint with --schedule: http://127.0.0.1:<port>/health and /metrics")
+            .required(false)
+            .value_parser(value_parser!(u16))
+            .requires("schedule")
+        )
         .next_help_heading("OPTIONAL FLAGS")
+        .arg(Arg::new("password-stdin")
+            .long("password-stdin")
+            .help("Read the LDAP password from the first line of the standard input")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+            .requires("ldapusername")
+            .conflicts_with_all(["ldappassword", "credentials-file"])
+        )
         .arg(Arg::new("ldaps")
             .long("ldaps")
             .help("Force LDAPS using for request like: ldaps://DOMAIN.LOCAL/")
@@ -121,6 +724,32 @@ fn cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .global(false)
             )
+        .arg(Arg::new("dns-timeout")
+                .long("dns-timeout")
+                .help("Timeout of each DNS query in seconds [default: 5]")
+                .required(false)
+                .value_parser(value_parser!(u64))
+            )
+        .arg(Arg::new("follow-referrals")
+            .long("follow-referrals")
+            .help("Follow LDAP referrals to other domains with the same credentials to resolve cross-domain members")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("global-catalog")
+            .long("global-catalog")
+            .help("Request the Global Catalog (port 3268, 3269 with ldaps) for the principals of the forest and the universal groups of other domains with members in this domain")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("collection-methods")
+            .long("collection-methods")
+            .help("Comma separated collection methods requesting only their attributes in the domain naming context: all, group (members), acl (security descriptors), objectprops (properties), site (sites, subnets and site links) [default: all]")
+            .required(false)
+            .value_parser(parse_collection_methods)
+        )
         .arg(Arg::new("dc-only")
             .long("dc-only")
             .help("Collects data only from the domain controller. Will not try to retrieve CA security/configuration or check for Web Enrollment")
@@ -128,6 +757,13 @@ fn cli() -> Command {
             .action(ArgAction::SetTrue)
             .global(false)
         )
+        .arg(Arg::new("sharphound-ids")
+            .long("sharphound-ids")
+            .help("Use the same uppercase identifiers as SharpHound (objectGUID for GPOs, OUs and containers, SID for principals) to merge both collections without duplicate nodes")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
         .arg(Arg::new("old-bloodhound")
             .long("old-bloodhound")
             .help("For ADCS only. Output result as BloodHound data for the original BloodHound version from @BloodHoundAD without PKI support")
@@ -143,10 +779,105 @@ fn cli() -> Command {
             .action(ArgAction::SetTrue)
             .global(false)
         )
+        .arg(Arg::new("ndjson")
+            .long("ndjson")
+            .help("Write all objects in one NDJSON file too, one object per line")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("sarif")
+            .long("sarif")
+            .help("Write the findings report in SARIF too for security pipelines")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("relay-targets")
+            .long("relay-targets")
+            .help("Write NTLM relay target lists for ntlmrelayx: hosts without SMB signing (probe-smb results if any), domain controllers for LDAP and ADCS web enrollment endpoints")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("stale-skip-probes")
+            .long("stale-skip-probes")
+            .help("Don't resolve or probe dormant computers found with --stale-days in the network modules")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+            .requires("stale-days")
+        )
+        .arg(Arg::new("fail-on-partial")
+            .long("fail-on-partial")
+            .help("Exit with code 10 without writing or uploading anything when some objects or attributes are missing (naming context search, referral, security descriptor, parser or SYSVOL failure)")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("audit")
+            .long("audit")
+            .help("Compare the ACL of the domain, AdminSDHolder and Domain Controllers OU with a default baseline in the findings report")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("acl-skip-benign")
+            .long("acl-skip-benign")
+            .help("Drop ACEs of built-in admin trustees (SYSTEM, Administrators, Domain Admins, Enterprise Admins, domain controllers) from the output, after the findings report")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("acl-benign-sids")
+            .long("acl-benign-sids")
+            .help("Comma separated SIDs or RIDs like -512 of the trustees dropped by --acl-skip-benign, instead of the built-in list")
+            .required(false)
+            .requires("acl-skip-benign")
+        )
+        .arg(Arg::new("edge-provenance")
+            .long("edge-provenance")
+            .help("Annotate each ACE edge with the ACE index, raw mask, object type GUID and inherited flag: inline in the Aces of the .json files, sidecar for edges_provenance.json")
+            .required(false)
+            .value_parser(["inline", "sidecar"])
+        )
+        .arg(Arg::new("privileged-groups")
+            .long("privileged-groups")
+            .help("TOML mapping file of third-party privileged groups, accounts and server products tagged as high value, added to the built-in pack (Veeam, Commvault, VMware, NetBackup, SCCM...)")
+            .required(false)
+            .value_parser(parse_privileged_groups)
+        )
+        .arg(Arg::new("explain-aces")
+            .long("explain-aces")
+            .help("Print each ACE of one object with its trustee, decoded mask and the edge made or why it was skipped, object SID, GUID or distinguished name like: CN=DC01,OU=Domain Controllers,DC=ESSOS,DC=LOCAL")
+            .required(false)
+            .value_parser(value_parser!(String))
+        )
+        .arg(Arg::new("neo4j-upsert")
+            .long("neo4j-upsert")
+            .help("Merge nodes and edges in Neo4j and remove edges not found anymore since the previous run")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("no-disk")
+            .long("no-disk")
+            .help("Don't write any file on disk, results are only sent to --http-url, --splunk-url, --elastic-url, --neo4j-url or --upload")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("stdout")
+            .long("stdout")
+            .help("Write results in standard output instead of files, one JSON file per line or one object per line with --ndjson")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
         .next_help_heading("OPTIONAL MODULES")
         .arg(Arg::new("fqdn-resolver")
             .long("fqdn-resolver")
-            .help("Use fqdn-resolver module to get computers IP address")
+            .help("Use fqdn-resolver module to get computers IP address, needed to map computers to sites")
             .required(false)
             .action(ArgAction::SetTrue)
             .global(false)
@@ -158,6 +889,62 @@ fn cli() -> Command {
             .action(ArgAction::SetTrue)
             .global(false)
         )
+        .arg(Arg::new("mssql-probe")
+            .long("mssql-probe")
+            .help("Use mssql-probe module to send a TDS pre-login request to MSSQL instances found in MSSQLSvc spn and get their version")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("probe-coercion")
+            .long("probe-coercion")
+            .help("Use probe-coercion module to check spooler and WebClient services on computers over SMB")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("probe-smb")
+            .long("probe-smb")
+            .help("Use probe-smb module to get SMB signing, SMBv1 and dialect on computers and write relay targets CSV")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("probe-winrm")
+            .long("probe-winrm")
+            .help("Use probe-winrm module to check WinRM and WMI ports on computers and if PSRemoting accepts the credentials with WSMan Identify")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("probe-rdp")
+            .long("probe-rdp")
+            .help("Use probe-rdp module to check RDP availability, NLA enforcement and TLS certificate CN on computers and write rdp.csv")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("shares")
+            .long("shares")
+            .help("Use shares module to list shares of servers over SMB with their share permissions and write shares.json with DFS namespaces")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("sysvol")
+            .long("sysvol")
+            .help("Use sysvol module to read GPO files in SYSVOL and get local groups changed by GPOs (Groups.xml, ScheduledTasks.xml, GptTmpl.inf)")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
+        .arg(Arg::new("sysvol-acls")
+            .long("sysvol-acls")
+            .help("Use sysvol-acls module to read NTFS permissions of GPO folders and NETLOGON, files writable by non-admins are written in shares.json and the findings report")
+            .required(false)
+            .action(ArgAction::SetTrue)
+            .global(false)
+        )
 }
 
 #[cfg(not(feature = "noargs"))]
@@ -168,7 +955,7 @@ pub fn extract_args() -> Options {
     let matches = cli().get_matches();
 
     // Now get values
-    let d = matches.get_one::<String>("domain").map(|s| s.as_str()).unwrap();
+    let d = matches.get_one::<String>("domain").map(|s| s.as_str()).unwrap_or("not set");
     let u = matches.get_one::<String>("ldapusername").map(|s| s.as_str()).unwrap_or("not set");
     let p = matches.get_one::<String>("ldappassword").map(|s| s.as_str()).unwrap_or("not set");
     let f = matches.get_one::<String>("ldapfqdn").map(|s| s.as_str()).unwrap_or("not set");
@@ -176,14 +963,129 @@ pub fn extract_args() -> Options {
     let port = matches.get_one::<String>("ldapport").map(|s| s.as_str()).unwrap_or("not set");
     let n = matches.get_one::<String>("name-server").map(|s| s.as_str()).unwrap_or("not set");
     let path = matches.get_one::<String>("output").map(|s| s.as_str()).unwrap_or("./");
+    let output_format = matches.get_one::<String>("output-format").map(|s| s.as_str()).unwrap_or("json");
+    let output_layout = matches.get_one::<String>("output-layout").map(|s| s.as_str()).unwrap_or("flat");
+    let http_url = matches.get_one::<String>("http-url").map(|s| s.as_str()).unwrap_or("not set");
+    let http_token = matches.get_one::<String>("http-token").map(|s| s.as_str()).unwrap_or("not set");
+    let http_cert = matches.get_one::<String>("http-cert").map(|s| s.as_str()).unwrap_or("not set");
+    let splunk_url = matches.get_one::<String>("splunk-url").map(|s| s.as_str()).unwrap_or("not set");
+    let splunk_token = matches.get_one::<String>("splunk-token").map(|s| s.as_str()).unwrap_or("not set");
+    let elastic_url = matches.get_one::<String>("elastic-url").map(|s| s.as_str()).unwrap_or("not set");
+    let elastic_api_key = matches.get_one::<String>("elastic-api-key").map(|s| s.as_str()).unwrap_or("not set");
+    let neo4j_url = matches.get_one::<String>("neo4j-url").map(|s| s.as_str()).unwrap_or("not set");
+    let neo4j_user = matches.get_one::<String>("neo4j-user").map(|s| s.as_str()).unwrap_or("neo4j");
+    let neo4j_password = matches.get_one::<String>("neo4j-password").map(|s| s.as_str()).unwrap_or("not set");
+    let upload_url = matches.get_one::<String>("upload").map(|s| s.as_str()).unwrap_or("not set");
+    let upload_token = matches.get_one::<String>("token").map(|s| s.as_str()).unwrap_or("not set");
+    let krbtgt_max_age = matches.get_one::<i64>("krbtgt-max-age").map(|s| s.to_owned()).unwrap_or(180);
+    let trust_max_age = matches.get_one::<i64>("trust-max-age").map(|s| s.to_owned()).unwrap_or(60);
+    let stale_days = matches.get_one::<i64>("stale-days").map(|s| s.to_owned()).unwrap_or(0);
+    let stale_skip_probes = matches.get_one::<bool>("stale-skip-probes").map(|s| s.to_owned()).unwrap_or(false);
+    let password_stdin = matches.get_one::<bool>("password-stdin").map(|s| s.to_owned()).unwrap_or(false);
+    let fail_on_partial = matches.get_one::<bool>("fail-on-partial").map(|s| s.to_owned()).unwrap_or(false);
+    let credentials_file = matches.get_one::<String>("credentials-file").map(|s| s.to_owned()).unwrap_or("not set".to_string());
+    let schedule = matches.get_one::<String>("schedule").map(|s| s.to_owned()).unwrap_or("not set".to_string());
+    let schedule_keep = matches.get_one::<usize>("schedule-keep").map(|s| s.to_owned()).unwrap_or(7);
+    let health_port = matches.get_one::<u16>("health-port").map(|s| s.to_owned()).unwrap_or(0);
+    let timeout = matches.get_one::<u64>("timeout").map(|s| s.to_owned()).unwrap_or(30);
+    let search_timeout = matches.get_one::<u64>("search-timeout").map(|s| s.to_owned()).unwrap_or(300);
+    let dns_timeout = matches.get_one::<u64>("dns-timeout").map(|s| s.to_owned()).unwrap_or(5);
+    let max_runtime = matches.get_one::<u64>("max-runtime").map(|s| s.to_owned()).unwrap_or(0);
+    let max_bandwidth = matches.get_one::<u64>("max-bandwidth").map(|s| s.to_owned()).unwrap_or(0);
+    let parse_workers = matches.get_one::<usize>("parse-workers").map(|s| s.to_owned()).unwrap_or(default_parse_workers());
+    let disk_store = matches.get_one::<String>("disk-store").map(|s| s.to_owned()).unwrap_or("not set".to_string());
+    let attack_paths = matches.get_one::<i64>("attack-paths").map(|s| s.to_owned()).unwrap_or(0);
+    let export_users_format = matches.get_one::<String>("export-users-format").map(|s| s.to_owned()).unwrap_or("not set".to_string());
     let ldaps = matches.get_one::<bool>("ldaps").map(|s| s.to_owned()).unwrap_or(false);
     let dns_tcp = matches.get_one::<bool>("dns-tcp").map(|s| s.to_owned()).unwrap_or(false);
+    let follow_referrals = matches.get_one::<bool>("follow-referrals").map(|s| s.to_owned()).unwrap_or(false);
+    let global_catalog = matches.get_one::<bool>("global-catalog").map(|s| s.to_owned()).unwrap_or(false);
     let dc_only = matches.get_one::<bool>("dc-only").map(|s| s.to_owned()).unwrap_or(false);
+    let collection_methods = matches.get_one::<String>("collection-methods").map(|s| s.to_owned()).unwrap_or("all".to_string());
     let old_bh = matches.get_one::<bool>("old-bloodhound").map(|s| s.to_owned()).unwrap_or(false);
     let z = matches.get_one::<bool>("zip").map(|s| s.to_owned()).unwrap_or(false);
+    let ndjson = matches.get_one::<bool>("ndjson").map(|s| s.to_owned()).unwrap_or(false);
+    let sarif = matches.get_one::<bool>("sarif").map(|s| s.to_owned()).unwrap_or(false);
+    let relay_targets = matches.get_one::<bool>("relay-targets").map(|s| s.to_owned()).unwrap_or(false);
+    let audit = matches.get_one::<bool>("audit").map(|s| s.to_owned()).unwrap_or(false);
+    let acl_skip_benign = matches.get_one::<bool>("acl-skip-benign").map(|s| s.to_owned()).unwrap_or(false);
+    let acl_benign_sids = matches.get_one::<String>("acl-benign-sids").map(|s| s.to_owned()).unwrap_or("not set".to_string());
+    let edge_provenance = matches.get_one::<String>("edge-provenance").map(|s| s.to_owned()).unwrap_or("not set".to_string());
+    let privileged_groups = matches.get_one::<String>("privileged-groups").map(|s| s.to_owned()).unwrap_or("not set".to_string());
+    let explain_aces = matches.get_one::<String>("explain-aces").map(|s| s.to_owned()).unwrap_or("not set".to_string());
+    let neo4j_upsert = matches.get_one::<bool>("neo4j-upsert").map(|s| s.to_owned()).unwrap_or(false);
+    let sharphound_ids = matches.get_one::<bool>("sharphound-ids").map(|s| s.to_owned()).unwrap_or(false);
+    let no_disk = matches.get_one::<bool>("no-disk").map(|s| s.to_owned()).unwrap_or(false);
+    let stdout = matches.get_one::<bool>("stdout").map(|s| s.to_owned()).unwrap_or(false);
     let fqdn_resolver = matches.get_one::<bool>("fqdn-resolver").map(|s| s.to_owned()).unwrap_or(false);
     let adcs = matches.get_one::<bool>("adcs").map(|s| s.to_owned()).unwrap_or(false);
+    let mssql_probe = matches.get_one::<bool>("mssql-probe").map(|s| s.to_owned()).unwrap_or(false);
+    let probe_coercion = matches.get_one::<bool>("probe-coercion").map(|s| s.to_owned()).unwrap_or(false);
+    let probe_smb = matches.get_one::<bool>("probe-smb").map(|s| s.to_owned()).unwrap_or(false);
+    let probe_winrm = matches.get_one::<bool>("probe-winrm").map(|s| s.to_owned()).unwrap_or(false);
+    let probe_rdp = matches.get_one::<bool>("probe-rdp").map(|s| s.to_owned()).unwrap_or(false);
+    let shares = matches.get_one::<bool>("shares").map(|s| s.to_owned()).unwrap_or(false);
+    let sysvol = matches.get_one::<bool>("sysvol").map(|s| s.to_owned()).unwrap_or(false);
+    let sysvol_acls = matches.get_one::<bool>("sysvol-acls").map(|s| s.to_owned()).unwrap_or(false);
     let kerberos = matches.get_one::<bool>("kerberos").map(|s| s.to_owned()).unwrap_or(false);
+    let graph = matches.subcommand_matches("graph").map(|graph_matches| GraphOptions {
+        target: graph_matches.get_one::<String>("target").map(|s| s.to_owned()).unwrap(),
+        input: graph_matches.get_one::<String>("input").map(|s| s.to_owned()).unwrap_or("./".to_string()),
+        format: graph_matches.get_one::<String>("format").map(|s| s.to_owned()).unwrap_or("dot".to_string()),
+        depth: graph_matches.get_one::<i64>("depth").map(|s| s.to_owned()).unwrap_or(3),
+        output: graph_matches.get_one::<String>("output").map(|s| s.to_owned()).unwrap_or("not set".to_string()),
+    });
+    let profile_save = matches.subcommand_matches("profile")
+        .and_then(|profile_matches| profile_matches.subcommand_matches("save"))
+        .map(|save_matches| ProfileOptions {
+            name: save_matches.get_one::<String>("name").map(|s| s.to_owned()).unwrap(),
+            domain: save_matches.get_one::<String>("domain").map(|s| s.to_owned()).unwrap(),
+            username: save_matches.get_one::<String>("ldapusername").map(|s| s.to_owned()).unwrap(),
+            password_stdin: save_matches.get_one::<bool>("password-stdin").map(|s| s.to_owned()).unwrap_or(false),
+        });
+    let profile = matches.get_one::<String>("profile").map(|s| s.to_owned()).unwrap_or("not set".to_string());
+    let selftest = matches.subcommand_matches("selftest").is_some();
+    let merge = matches.subcommand_matches("merge").map(|merge_matches| MergeOptions {
+        inputs: merge_matches.get_many::<String>("inputs").map(|inputs| inputs.cloned().collect()).unwrap_or_default(),
+        output: merge_matches.get_one::<String>("output").map(|s| s.to_owned()).unwrap_or("./merged".to_string()),
+    });
+    let query = matches.subcommand_matches("query").map(|query_matches| QueryOptions {
+        pack: query_matches.get_one::<String>("pack").map(|s| s.to_owned()).unwrap(),
+        input: query_matches.get_one::<String>("input").map(|s| s.to_owned()).unwrap_or("./".to_string()),
+        output: query_matches.get_one::<String>("output").map(|s| s.to_owned()).unwrap_or("not set".to_string()),
+    });
+    let add_computer = matches.subcommand_matches("add-computer").map(|add_computer_matches| AddComputerOptions {
+        name: add_computer_matches.get_one::<String>("name").map(|s| s.to_owned()).unwrap(),
+        password: add_computer_matches.get_one::<String>("computer-pass").map(|s| s.to_owned()).unwrap(),
+        ou: add_computer_matches.get_one::<String>("computer-ou").map(|s| s.to_owned()).unwrap_or("not set".to_string()),
+    });
+    let set_rbcd = matches.subcommand_matches("set-rbcd").map(|set_rbcd_matches| SetRbcdOptions {
+        target: set_rbcd_matches.get_one::<String>("target").map(|s| s.to_owned()).unwrap(),
+        grant: set_rbcd_matches.get_one::<String>("grant").map(|s| s.to_owned()).unwrap(),
+        revert: set_rbcd_matches.get_one::<bool>("revert").map(|s| s.to_owned()).unwrap_or(false),
+    });
+    let set_keycredential = matches.subcommand_matches("set-keycredential").map(|set_keycredential_matches| SetKeyCredentialOptions {
+        target: set_keycredential_matches.get_one::<String>("target").map(|s| s.to_owned()).unwrap(),
+        remove: set_keycredential_matches.get_one::<String>("remove").map(|s| s.to_owned()).unwrap_or("not set".to_string()),
+    });
+    let set_password = matches.subcommand_matches("set-password").map(|set_password_matches| SetPasswordOptions {
+        target: set_password_matches.get_one::<String>("target").map(|s| s.to_owned()).unwrap(),
+        new_password: set_password_matches.get_one::<String>("new-pass").map(|s| s.to_owned()).unwrap_or("not set".to_string()),
+        old_password: set_password_matches.get_one::<String>("old-pass").map(|s| s.to_owned()).unwrap_or("not set".to_string()),
+    });
+    let acl_options = |acl_matches: &ArgMatches| AclOptions {
+        objects: acl_matches.get_many::<String>("object").map(|objects| objects.cloned().collect()).unwrap_or_default(),
+        file: acl_matches.get_one::<String>("file").map(|s| s.to_owned()).unwrap_or("acl_backup.json".to_string()),
+    };
+    let acl_backup = matches.subcommand_matches("acl")
+        .and_then(|acl_matches| acl_matches.subcommand_matches("backup"))
+        .map(acl_options);
+    let acl_restore = matches.subcommand_matches("acl")
+        .and_then(|acl_matches| acl_matches.subcommand_matches("restore"))
+        .map(acl_options);
+    let write_matches = get_subcommand_matches(&matches);
+    let dry_run = write_matches.try_get_one::<bool>("dry-run").ok().flatten().map(|s| s.to_owned()).unwrap_or(false);
+    let journal = write_matches.try_get_one::<String>("journal").ok().flatten().map(|s| s.to_owned()).unwrap_or("not set".to_string());
     let v = match matches.get_count("v") {
         0 => log::LevelFilter::Info,
         1 => log::LevelFilter::Debug,
@@ -200,18 +1102,94 @@ pub fn extract_args() -> Options {
         port: port.to_string(),
         name_server: n.to_string(),
         path: path.to_string(),
+        output_format: output_format.to_string(),
+        output_layout: output_layout.to_string(),
+        http_url: http_url.to_string(),
+        http_token: http_token.to_string(),
+        http_cert: http_cert.to_string(),
+        splunk_url: splunk_url.to_string(),
+        splunk_token: splunk_token.to_string(),
+        elastic_url: elastic_url.to_string(),
+        elastic_api_key: elastic_api_key.to_string(),
+        neo4j_url: neo4j_url.to_string(),
+        neo4j_user: neo4j_user.to_string(),
+        neo4j_password: neo4j_password.to_string(),
+        upload_url: upload_url.to_string(),
+        upload_token: upload_token.to_string(),
+        krbtgt_max_age: krbtgt_max_age,
+        trust_max_age: trust_max_age,
+        stale_days: stale_days,
+        stale_skip_probes: stale_skip_probes,
+        password_stdin: password_stdin,
+        fail_on_partial: fail_on_partial,
+        credentials_file: credentials_file,
+        schedule: schedule,
+        schedule_keep: schedule_keep,
+        health_port: health_port,
+        attack_paths: attack_paths,
+        export_users_format: export_users_format,
+        timeout: timeout,
+        search_timeout: search_timeout,
+        dns_timeout: dns_timeout,
+        max_runtime: max_runtime,
+        max_bandwidth: max_bandwidth,
+        parse_workers: parse_workers,
+        disk_store: disk_store,
+        acl_benign_sids: acl_benign_sids,
+        explain_aces: explain_aces,
+        edge_provenance: edge_provenance,
+        privileged_groups: privileged_groups,
         ldaps: ldaps,
         dns_tcp: dns_tcp,
+        follow_referrals: follow_referrals,
+        global_catalog: global_catalog,
         dc_only: dc_only,
+        collection_methods: collection_methods,
         old_bloodhound: old_bh,
         fqdn_resolver: fqdn_resolver,
         adcs: adcs,
+        mssql_probe: mssql_probe,
+        probe_coercion: probe_coercion,
+        probe_smb: probe_smb,
+        probe_winrm: probe_winrm,
+        probe_rdp: probe_rdp,
+        shares: shares,
+        sysvol: sysvol,
+        sysvol_acls: sysvol_acls,
         kerberos: kerberos,
         zip: z,
+        ndjson: ndjson,
+        sarif: sarif,
+        relay_targets: relay_targets,
+        audit: audit,
+        acl_skip_benign: acl_skip_benign,
+        neo4j_upsert: neo4j_upsert,
+        sharphound_ids: sharphound_ids,
+        no_disk: no_disk,
+        stdout: stdout,
         verbose: v,
+        graph: graph,
+        merge: merge,
+        query: query,
+        profile_save: profile_save,
+        add_computer: add_computer,
+        set_rbcd: set_rbcd,
+        set_keycredential: set_keycredential,
+        set_password: set_password,
+        acl_backup: acl_backup,
+        acl_restore: acl_restore,
+        dry_run: dry_run,
+        journal: journal,
+        profile: profile,
+        selftest: selftest,
     }
 }
 
+/// Function to get the default number of parser workers, one per CPU.
+pub fn default_parse_workers() -> usize {
+    std::thread::available_parallelism().map(|workers| workers.get()).unwrap_or(1)
+}
+
 #[cfg(feature = "noargs")]
 /// Function to automatically get all informations needed and put it in 'Options' structure.
 pub fn auto_args() -> Options {
@@ -254,14 +1232,85 @@ pub fn auto_args() -> Options {
         port: port.to_string(),
         name_server: "127.0.0.1".to_string(),
         path: "./output".to_string(),
+        output_format: "json".to_string(),
+        output_layout: "flat".to_string(),
+        http_url: "not set".to_string(),
+        http_token: "not set".to_string(),
+        http_cert: "not set".to_string(),
+        splunk_url: "not set".to_string(),
+        splunk_token: "not set".to_string(),
+        elastic_url: "not set".to_string(),
+        elastic_api_key: "not set".to_string(),
+        neo4j_url: "not set".to_string(),
+        neo4j_user: "neo4j".to_string(),
+        neo4j_password: "not set".to_string(),
+        upload_url: "not set".to_string(),
+        upload_token: "not set".to_string(),
+        krbtgt_max_age: 180,
+        trust_max_age: 60,
+        stale_days: 0,
+        stale_skip_probes: false,
+        password_stdin: false,
+        fail_on_partial: false,
+        credentials_file: "not set".to_string(),
+        schedule: "not set".to_string(),
+        schedule_keep: 7,
+        health_port: 0,
+        attack_paths: 0,
+        export_users_format: "not set".to_string(),
+        timeout: 30,
+        search_timeout: 300,
+        dns_timeout: 5,
+        max_runtime: 0,
+        max_bandwidth: 0,
+        parse_workers: default_parse_workers(),
+        disk_store: "not set".to_string(),
+        acl_benign_sids: "not set".to_string(),
+        explain_aces: "not set".to_string(),
+        edge_provenance: "not set".to_string(),
+        privileged_groups: "not set".to_string(),
         ldaps: ldaps,
         dns_tcp: false,
+        follow_referrals: false,
+        global_catalog: false,
         dc_only: false,
+        collection_methods: "all".to_string(),
         old_bloodhound: false,
         fqdn_resolver: false,
         adcs: true,
+        mssql_probe: false,
+        probe_coercion: false,
+        probe_smb: false,
+        probe_winrm: false,
+        probe_rdp: false,
+        shares: false,
+        sysvol: false,
+        sysvol_acls: false,
         kerberos: true,
         zip: true,
+        ndjson: false,
+        sarif: false,
+        relay_targets: false,
+        audit: false,
+        acl_skip_benign: false,
+        neo4j_upsert: false,
+        sharphound_ids: false,
+        no_disk: false,
+        stdout: false,
         verbose: log::LevelFilter::Info,
+        graph: None,
+        merge: None,
+        query: None,
+        profile_save: None,
+        add_computer: None,
+        set_rbcd: None,
+        set_keycredential: None,
+        set_password: None,
+        acl_backup: None,
+        acl_restore: None,
+        dry_run: false,
+        journal: "not set".to_string(),
+        profile: "not set".to_string(),
+        selftest: false,
     }
 }
@@ -0,0 +1,202 @@
+//! ACL backup and restore (`rusthound acl backup` / `rusthound acl restore`)
+//!
+//! Before one ACL is modified during an engagement (WriteDacl, WriteOwner, GenericAll edges...), the
+//! nTSecurityDescriptor of the objects is saved with its owner, group and DACL in one JSON file, the binary
+//! descriptor in base64. The restore writes the saved DACL back, and the owner only when it was changed so
+//! WriteOwner isn't needed otherwise. Objects whose descriptor didn't change are skipped. The SACL is never
+//! read nor written, it would need SeSecurityPrivilege.
+//!
+//! Objects are given by distinguished name (OUs, GPOs, containers...) or by sAMAccountName.
+use base64::Engine;
+use colored::Colorize;
+use ldap3::{Mod, Scope, SearchEntry};
+use log::{info, error};
+
+use std::fs;
+
+use crate::args::{AclOptions, Options};
+use crate::attack::{connect, find_account, get_domain_dn, sd_flags_control, write, WriteOperation};
+use crate::enums::secdesc::SecurityDescriptor;
+use crate::enums::sid::Sid;
+use crate::errors::{Result, RustHoundError};
+
+/// OWNER_SECURITY_INFORMATION
+const OWNER_SECURITY_INFORMATION: u8 = 0x01;
+/// GROUP_SECURITY_INFORMATION
+const GROUP_SECURITY_INFORMATION: u8 = 0x02;
+/// DACL_SECURITY_INFORMATION
+const DACL_SECURITY_INFORMATION: u8 = 0x04;
+
+/// Function to get the owner and the number of ACEs in the DACL of one descriptor, checking it can be parsed.
+pub fn describe_security_descriptor(nt: &[u8]) -> std::result::Result<(Option<Sid>, usize), String>
+{
+   let (_, descriptor) = SecurityDescriptor::parse(nt).map_err(|err| format!("Can't parse security descriptor: {}", err))?;
+   let owner = descriptor.parse_owner(nt)?.map(|owner| Sid::from(&owner));
+   let aces = descriptor.parse_dacl(nt)?.map_or(0, |dacl| dacl.ace_count as usize);
+   Ok((owner, aces))
+}
+
+/// Function to get the SD flags of the restore: None if the descriptor didn't change,
+/// the DACL and the owner only if it was changed.
+pub fn get_restore_flags(backup: &[u8], current: &[u8]) -> std::result::Result<Option<u8>, String>
+{
+   if backup == current {
+      return Ok(None)
+   }
+   let (backup_owner, _) = describe_security_descriptor(backup)?;
+   let (current_owner, _) = describe_security_descriptor(current)?;
+   match backup_owner.is_some() && backup_owner != current_owner {
+      true => Ok(Some(DACL_SECURITY_INFORMATION | OWNER_SECURITY_INFORMATION)),
+      false => Ok(Some(DACL_SECURITY_INFORMATION)),
+   }
+}
+
+/// Function to get the distinguished name of one object given by DN or sAMAccountName.
+async fn get_object_dn(ldap: &mut ldap3::Ldap, domain_dn: &String, object: &String) -> Result<String>
+{
+   match object.contains('=') {
+      true => Ok(object.to_owned()),
+      false => Ok(find_account(ldap, domain_dn, object, vec!["distinguishedName"]).await?.dn),
+   }
+}
+
+/// Function to read the owner, group and DACL of one object.
+async fn read_security_descriptor(ldap: &mut ldap3::Ldap, dn: &str) -> Result<Vec<u8>>
+{
+   let (entries, _res) = ldap
+      .with_controls(sd_flags_control(OWNER_SECURITY_INFORMATION | GROUP_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION))
+      .search(dn, Scope::Base, "(objectClass=*)", vec!["nTSecurityDescriptor"])
+      .await?
+      .success()?;
+   entries.into_iter()
+      .map(SearchEntry::construct)
+      .find_map(|entry| entry.bin_attrs.get("nTSecurityDescriptor").and_then(|values| values.first()).cloned())
+      .ok_or(RustHoundError::WriteError {
+         dn: dn.to_string(),
+         reason: "nTSecurityDescriptor not readable".to_string(),
+      })
+}
+
+/// Function to save the nTSecurityDescriptor of the objects in the backup file.
+pub async fn acl_backup(common_args: &Options, acl_args: &AclOptions) -> Result<()>
+{
+   let domain_dn = get_domain_dn(&common_args.domain);
+   let mut ldap = connect(common_args).await?;
+
+   let mut objects = Vec::new();
+   for object in &acl_args.objects {
+      let dn = get_object_dn(&mut ldap, &domain_dn, object).await?;
+      let nt = read_security_descriptor(&mut ldap, &dn).await?;
+      let (owner, aces) = describe_security_descriptor(&nt).map_err(|reason| RustHoundError::WriteError { dn: dn.to_owned(), reason })?;
+      info!("Saved ACL of {}: owner {}, {} ACEs", dn.bold(), owner.as_ref().map(|sid| sid.to_string()).unwrap_or_default(), aces);
+      objects.push(serde_json::json!({
+         "dn": dn,
+         "owner": owner,
+         "aces": aces,
+         "ntsecuritydescriptor": base64::engine::general_purpose::STANDARD.encode(&nt),
+      }));
+   }
+   ldap.unbind().await?;
+
+   let backup = serde_json::json!({
+      "domain": common_args.domain.to_uppercase(),
+      "date": chrono::Utc::now().to_rfc3339(),
+      "objects": objects,
+   });
+   fs::write(&acl_args.file, serde_json::to_string_pretty(&backup).unwrap_or_default())?;
+   info!("ACL of {} objects saved in {}", objects.len(), acl_args.file.bold().green());
+   Ok(())
+}
+
+/// Function to write back the nTSecurityDescriptor of the backup file, for all its objects or the ones given.
+pub async fn acl_restore(common_args: &Options, acl_args: &AclOptions) -> Result<()>
+{
+   let content = fs::read_to_string(&acl_args.file)?;
+   let backup: serde_json::Value = serde_json::from_str(&content).map_err(|err| RustHoundError::WriteError {
+      dn: acl_args.file.to_owned(),
+      reason: err.to_string(),
+   })?;
+   let domain_dn = get_domain_dn(&common_args.domain);
+   let mut ldap = connect(common_args).await?;
+
+   // Objects given by sAMAccountName are restored by their DN
+   let mut selected = Vec::new();
+   for object in &acl_args.objects {
+      selected.push(get_object_dn(&mut ldap, &domain_dn, object).await?.to_lowercase());
+   }
+
+   let mut failed = 0;
+   for object in backup["objects"].as_array().unwrap_or(&Vec::new()) {
+      let dn = object["dn"].as_str().unwrap_or("").to_string();
+      if dn.is_empty() || (!selected.is_empty() && !selected.contains(&dn.to_lowercase())) {
+         continue
+      }
+      let result = async {
+         let nt = base64::engine::general_purpose::STANDARD
+            .decode(object["ntsecuritydescriptor"].as_str().unwrap_or(""))
+            .map_err(|err| err.to_string())
+            .and_then(|nt| describe_security_descriptor(&nt).map(|_| nt))
+            .map_err(|reason| RustHoundError::WriteError { dn: dn.to_owned(), reason })?;
+         let current = read_security_descriptor(&mut ldap, &dn).await?;
+         let flags = match get_restore_flags(&nt, &current).map_err(|reason| RustHoundError::WriteError { dn: dn.to_owned(), reason })? {
+            Some(flags) => flags,
+            None => {
+               info!("ACL of {} unchanged, skipped", dn);
+               return Ok(())
+            }
+         };
+         let operation = WriteOperation {
+            add: false,
+            dn: dn.to_owned(),
+            mods: vec![Mod::Replace(b"nTSecurityDescriptor".to_vec(), vec![nt].into_iter().collect())],
+            sd_flags: Some(flags),
+            cleanup: format!("ACL restored from {}, nothing to clean up", acl_args.file),
+         };
+         write(&mut ldap, common_args, &operation).await?;
+         if !common_args.dry_run {
+            info!("ACL of {} restored{}", dn.bold().green(), if flags & OWNER_SECURITY_INFORMATION != 0 { " with its owner" } else { "" });
+         }
+         Ok::<(), RustHoundError>(())
+      }.await;
+      if let Err(err) = result {
+         error!("{err}");
+         failed += 1;
+      }
+   }
+   ldap.unbind().await?;
+
+   if failed > 0 {
+      return Err(RustHoundError::WriteError {
+         dn: acl_args.file.to_owned(),
+         reason: format!("{} objects not restored", failed),
+      })
+   }
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_restore_flags() {
+   use crate::enums::secdesc::{serialize_security_descriptor, SimpleAce};
+   use std::str::FromStr;
+
+   let administrators = Sid::from_str("S-1-5-32-544").unwrap();
+   let attacker = Sid::from_str("S-1-5-21-1-2-3-1105").unwrap();
+   let domain_admins = Sid::from_str("S-1-5-21-1-2-3-512").unwrap();
+   let aces = vec![SimpleAce::allowed(0x000F01FF, domain_admins.to_owned())];
+
+   let backup = serialize_security_descriptor(Some(&administrators), Some(&domain_admins), &aces);
+   assert_eq!(describe_security_descriptor(&backup), Ok((Some(administrators.to_owned()), 1)));
+   assert_eq!(get_restore_flags(&backup, &backup), Ok(None));
+
+   // DACL changed
+   let mut granted = aces.to_owned();
+   granted.push(SimpleAce::allowed(0x000F01FF, attacker.to_owned()));
+   let current = serialize_security_descriptor(Some(&administrators), Some(&domain_admins), &granted);
+   assert_eq!(get_restore_flags(&backup, &current), Ok(Some(DACL_SECURITY_INFORMATION)));
+
+   // Owner taken
+   let current = serialize_security_descriptor(Some(&attacker), Some(&domain_admins), &granted);
+   assert_eq!(get_restore_flags(&backup, &current), Ok(Some(DACL_SECURITY_INFORMATION | OWNER_SECURITY_INFORMATION)));
+   assert!(describe_security_descriptor(&[0x01, 0x00]).is_err());
+}
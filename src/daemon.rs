@@ -0,0 +1,331 @@
+//! Daemon mode (**--schedule**)
+//!
+//! RustHound stays running and collects on every occurrence of a cron expression, like `0 3 * * *` for every day
+//! at 03:00 local time. Runs are saved with `--output-layout domain` in `<output>/<domain>/<datetime>/`, the oldest
+//! ones are pruned with **--schedule-keep** and **--health-port** exposes the state of the last run on
+//! `http://127.0.0.1:<port>/health` for supervisors and container probes, and Prometheus metrics on
+//! `http://127.0.0.1:<port>/metrics`: runs, failures, warnings, duration, objects per second and LDAP latency quantiles.
+use chrono::{DateTime, Datelike, Duration, Local, Timelike};
+use log::{debug, trace};
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::json::stats::warnings_count;
+
+/// Quantiles of the LDAP response latency in the metrics
+const LATENCY_QUANTILES: [f64; 4] = [0.5, 0.9, 0.95, 0.99];
+/// Read and write timeout of one health endpoint connection, idle clients don't block the next ones
+const HEALTH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Cron expression with the standard 5 fields: minute, hour, day of month, month and day of week.
+/// Fields support `*`, values, ranges `1-5`, lists `1,15` and steps `*/15` or `0-30/10`.
+#[derive(Debug, PartialEq)]
+pub struct Schedule {
+   minutes: Vec<u32>,
+   hours: Vec<u32>,
+   days: Vec<u32>,
+   months: Vec<u32>,
+   weekdays: Vec<u32>,
+   /// Day of month and day of week both restricted, one of them is enough like cron
+   days_or_weekdays: bool,
+}
+
+/// Function to parse one cron field into its sorted values.
+fn parse_field(field: &str, min: u32, max: u32) -> std::result::Result<Vec<u32>, String>
+{
+   let mut values: Vec<u32> = Vec::new();
+   for part in field.split(',') {
+      let (range, step) = match part.split_once('/') {
+         Some((range, step)) => (range, step.parse::<u32>().map_err(|_| format!("invalid step in '{}'", part))?),
+         None => (part, 1),
+      };
+      let (start, end) = match range {
+         "*" => (min, max),
+         range => match range.split_once('-') {
+            Some((start, end)) => (
+               start.parse::<u32>().map_err(|_| format!("invalid value in '{}'", part))?,
+               end.parse::<u32>().map_err(|_| format!("invalid value in '{}'", part))?,
+            ),
+            // `5/10` is `5-max/10`
+            None => {
+               let start = range.parse::<u32>().map_err(|_| format!("invalid value in '{}'", part))?;
+               (start, if part.contains('/') { max } else { start })
+            },
+         },
+      };
+      if step == 0 || start < min || end > max || start > end {
+         return Err(format!("'{}' out of range {}-{}", part, min, max))
+      }
+      values.extend((start..=end).step_by(step as usize));
+   }
+   values.sort_unstable();
+   values.dedup();
+   Ok(values)
+}
+
+impl Schedule {
+   /// Function to parse one cron expression.
+   pub fn parse(expression: &str) -> std::result::Result<Self, String>
+   {
+      let fields: Vec<&str> = expression.split_whitespace().collect();
+      if fields.len() != 5 {
+         return Err(format!("'{}' must have 5 fields: minute hour day-of-month month day-of-week", expression))
+      }
+      // Sunday is 0 or 7
+      let mut weekdays: Vec<u32> = parse_field(fields[4], 0, 7)?.into_iter().map(|day| day % 7).collect();
+      weekdays.sort_unstable();
+      weekdays.dedup();
+      Ok(Schedule {
+         minutes: parse_field(fields[0], 0, 59)?,
+         hours: parse_field(fields[1], 0, 23)?,
+         days: parse_field(fields[2], 1, 31)?,
+         months: parse_field(fields[3], 1, 12)?,
+         weekdays,
+         days_or_weekdays: fields[2] != "*" && fields[4] != "*",
+      })
+   }
+
+   /// Function to check if one minute matches the expression.
+   fn matches(&self, date: &DateTime<Local>) -> bool
+   {
+      let day = self.days.contains(&date.day());
+      let weekday = self.weekdays.contains(&date.weekday().num_days_from_sunday());
+      self.minutes.contains(&date.minute())
+         && self.hours.contains(&date.hour())
+         && self.months.contains(&date.month())
+         && if self.days_or_weekdays { day || weekday } else { day && weekday }
+   }
+
+   /// Function to get the next occurrence strictly after one date, None if nothing matches within 4 years (`0 0 31 2 *`).
+   pub fn next_after(&self, date: DateTime<Local>) -> Option<DateTime<Local>>
+   {
+      let mut next = date.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+      let limit = date + Duration::days(4 * 366);
+      while next < limit {
+         if !self.months.contains(&next.month()) || !self.hours.contains(&next.hour()) {
+            // Next hour, one minute per iteration would take too long for yearly schedules
+            next = next + Duration::minutes(60 - next.minute() as i64);
+            continue
+         }
+         if self.matches(&next) {
+            return Some(next)
+         }
+         next = next + Duration::minutes(1);
+      }
+      None
+   }
+}
+
+/// Function to check the cron expression of --schedule.
+pub fn parse_schedule(value: &str) -> std::result::Result<String, String>
+{
+   Schedule::parse(value).map(|_| value.trim().to_string())
+}
+
+/// Function to remove the oldest run directories of one domain directory and keep the `keep` newest ones.
+/// Run directories are named with their datetime, the `latest` link isn't a run.
+pub fn prune_runs(domain_directory: &Path, keep: usize) -> io::Result<Vec<String>>
+{
+   if !domain_directory.is_dir() {
+      return Ok(Vec::new())
+   }
+   let mut runs: Vec<String> = fs::read_dir(domain_directory)?
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
+      .map(|entry| entry.file_name().to_string_lossy().to_string())
+      .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()))
+      .collect();
+   runs.sort();
+   let count = runs.len().saturating_sub(keep);
+   let pruned: Vec<String> = runs.drain(..count).collect();
+   for run in &pruned {
+      fs::remove_dir_all(domain_directory.join(run))?;
+   }
+   Ok(pruned)
+}
+
+/// State of the daemon exposed on the health endpoint.
+#[derive(Default)]
+pub struct HealthState {
+   pub runs: u64,
+   pub failures: u64,
+   pub last_run: Option<String>,
+   pub last_duration: Option<f64>,
+   pub last_error: Option<String>,
+   pub next_run: Option<String>,
+   /// Last run start, seconds since epoch
+   pub last_timestamp: i64,
+   /// Objects collected in the last run
+   pub last_objects: usize,
+   pub last_ldap_bytes: u64,
+   /// Wait for every LDAP search response of the last run in seconds, sorted
+   pub ldap_latencies: Vec<f64>,
+}
+
+/// Function to get one quantile of sorted values, 0 without value.
+fn quantile(sorted: &[f64], q: f64) -> f64
+{
+   if sorted.is_empty() {
+      return 0.0
+   }
+   sorted[((sorted.len() - 1) as f64 * q).round() as usize]
+}
+
+impl HealthState {
+   /// Function to make the health JSON document, failing when the last run returned an error.
+   pub fn to_json(&self) -> serde_json::value::Value
+   {
+      serde_json::json!({
+         "status": if self.last_error.is_some() { "failing" } else { "ok" },
+         "runs": self.runs,
+         "failures": self.failures,
+         "last_run": self.last_run,
+         "last_duration": self.last_duration,
+         "last_error": self.last_error,
+         "next_run": self.next_run,
+      })
+   }
+
+   /// Function to make the metrics in the Prometheus text format.
+   pub fn to_metrics(&self) -> String
+   {
+      let duration = self.last_duration.unwrap_or(0.0);
+      let objects_per_second = if duration > 0.0 { self.last_objects as f64 / duration } else { 0.0 };
+      let mut metrics: Vec<String> = Vec::new();
+      let mut push = |name: &str, kind: &str, help: &str, values: Vec<(String, String)>| {
+         metrics.push(format!("# HELP {} {}", name, help));
+         metrics.push(format!("# TYPE {} {}", name, kind));
+         for (labels, value) in values {
+            metrics.push(format!("{}{} {}", name, labels, value));
+         }
+      };
+      push("rusthound_runs_total", "counter", "Collections run since the daemon started.", vec![(String::new(), self.runs.to_string())]);
+      push("rusthound_run_failures_total", "counter", "Collections ended with an error.", vec![(String::new(), self.failures.to_string())]);
+      push("rusthound_warnings_total", "counter", "Warnings and errors logged since the daemon started.", vec![(String::new(), warnings_count().to_string())]);
+      push("rusthound_last_run_success", "gauge", "1 if the last collection succeeded.", vec![(String::new(), ((self.runs > 0 && self.last_error.is_none()) as u8).to_string())]);
+      push("rusthound_last_run_timestamp_seconds", "gauge", "Start of the last collection.", vec![(String::new(), self.last_timestamp.to_string())]);
+      push("rusthound_last_run_duration_seconds", "gauge", "Duration of the last collection.", vec![(String::new(), duration.to_string())]);
+      push("rusthound_last_run_objects", "gauge", "Objects collected in the last collection.", vec![(String::new(), self.last_objects.to_string())]);
+      push("rusthound_last_run_objects_per_second", "gauge", "Objects collected per second in the last collection.", vec![(String::new(), objects_per_second.to_string())]);
+      push("rusthound_last_run_ldap_bytes", "gauge", "Bytes received from LDAP in the last collection.", vec![(String::new(), self.last_ldap_bytes.to_string())]);
+      let mut latency: Vec<(String, String)> = LATENCY_QUANTILES.iter()
+         .map(|q| (format!("{{quantile=\"{}\"}}", q), quantile(&self.ldap_latencies, *q).to_string()))
+         .collect();
+      latency.push(("_sum".to_string(), self.ldap_latencies.iter().sum::<f64>().to_string()));
+      latency.push(("_count".to_string(), self.ldap_latencies.len().to_string()));
+      push("rusthound_ldap_latency_seconds", "summary", "Wait for LDAP search responses in the last collection.", latency);
+      metrics.join("\n") + "\n"
+   }
+}
+
+/// Function to answer one HTTP request of the health endpoint.
+fn make_response(request: &str, state: &Arc<Mutex<HealthState>>) -> String
+{
+   let path = request.split_whitespace().nth(1).unwrap_or("");
+   let (status, content_type, body) = match path {
+      "/health" | "/" => {
+         let health = state.lock().map(|state| state.to_json()).unwrap_or_default();
+         let status = if health["status"] == "ok" { "200 OK" } else { "503 Service Unavailable" };
+         (status, "application/json", health.to_string())
+      },
+      "/metrics" => ("200 OK", "text/plain; version=0.0.4", state.lock().map(|state| state.to_metrics()).unwrap_or_default()),
+      _ => ("404 Not Found", "application/json", "{\"error\":\"not found\"}".to_string()),
+   };
+   format!("HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, content_type, body.len(), body)
+}
+
+/// Function to start the health and metrics endpoint on 127.0.0.1 in its own thread.
+pub fn serve_health(port: u16, state: Arc<Mutex<HealthState>>) -> io::Result<()>
+{
+   let listener = TcpListener::bind(("127.0.0.1", port))?;
+   thread::spawn(move || {
+      for stream in listener.incoming() {
+         let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+               debug!("Health endpoint connection error: {}", err);
+               continue
+            },
+         };
+         if let Err(err) = stream.set_read_timeout(Some(HEALTH_TIMEOUT)).and_then(|_| stream.set_write_timeout(Some(HEALTH_TIMEOUT))) {
+            debug!("Health endpoint connection error: {}", err);
+            continue
+         }
+         let mut buffer = [0u8; 1024];
+         let size = stream.read(&mut buffer).unwrap_or(0);
+         let request = String::from_utf8_lossy(&buffer[..size]).to_string();
+         trace!("Health endpoint request: {:?}", request.lines().next());
+         stream.write_all(make_response(&request, &state).as_bytes()).ok();
+      }
+   });
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_schedule() {
+   use chrono::TimeZone;
+
+   let schedule = Schedule::parse("0 3 * * *").unwrap();
+   let date = Local.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+   assert_eq!(schedule.next_after(date), Some(Local.with_ymd_and_hms(2024, 1, 2, 3, 0, 0).unwrap()));
+
+   let schedule = Schedule::parse("*/15 8-18 * * 1-5").unwrap();
+   // Saturday
+   let date = Local.with_ymd_and_hms(2024, 1, 6, 9, 7, 30).unwrap();
+   assert_eq!(schedule.next_after(date), Some(Local.with_ymd_and_hms(2024, 1, 8, 8, 0, 0).unwrap()));
+   let date = Local.with_ymd_and_hms(2024, 1, 8, 9, 7, 30).unwrap();
+   assert_eq!(schedule.next_after(date), Some(Local.with_ymd_and_hms(2024, 1, 8, 9, 15, 0).unwrap()));
+
+   // Day of month or Sunday
+   let schedule = Schedule::parse("30 1 15 * 7").unwrap();
+   let date = Local.with_ymd_and_hms(2024, 1, 8, 0, 0, 0).unwrap();
+   assert_eq!(schedule.next_after(date), Some(Local.with_ymd_and_hms(2024, 1, 14, 1, 30, 0).unwrap()));
+
+   assert_eq!(Schedule::parse("0 0 31 2 *").unwrap().next_after(date), None);
+   assert!(parse_schedule("0 3 * *").is_err());
+   assert!(parse_schedule("60 * * * *").is_err());
+   assert!(parse_schedule("*/0 * * * *").is_err());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_prune_runs() {
+   let domain_directory = std::env::temp_dir().join("rusthound_test_prune_runs").join("essos.local");
+   for run in ["20240101030000", "20240102030000", "20240103030000"] {
+      fs::create_dir_all(domain_directory.join(run)).unwrap();
+   }
+   fs::create_dir_all(domain_directory.join("notes")).unwrap();
+   assert_eq!(prune_runs(&domain_directory, 2).unwrap(), vec!["20240101030000".to_string()]);
+   assert!(domain_directory.join("20240102030000").is_dir());
+   assert!(domain_directory.join("notes").is_dir());
+   fs::remove_dir_all(domain_directory.parent().unwrap()).ok();
+
+   let state = Arc::new(Mutex::new(HealthState { runs: 2, failures: 1, last_error: Some("LDAP Error".to_string()), ..Default::default() }));
+   assert!(make_response("GET /health HTTP/1.1\r\n\r\n", &state).starts_with("HTTP/1.1 503"));
+   assert!(make_response("GET /other HTTP/1.1\r\n\r\n", &state).starts_with("HTTP/1.1 404"));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_metrics() {
+   let state = HealthState {
+      runs: 1,
+      last_duration: Some(10.0),
+      last_objects: 500,
+      ldap_latencies: vec![0.001, 0.002, 0.003, 0.004, 0.5],
+      ..Default::default()
+   };
+   let metrics = state.to_metrics();
+   assert!(metrics.contains("rusthound_last_run_success 1\n"));
+   assert!(metrics.contains("rusthound_last_run_objects_per_second 50\n"));
+   assert!(metrics.contains("rusthound_ldap_latency_seconds{quantile=\"0.5\"} 0.003\n"));
+   assert!(metrics.contains("rusthound_ldap_latency_seconds{quantile=\"0.99\"} 0.5\n"));
+   assert!(metrics.contains("rusthound_ldap_latency_seconds_count 5\n"));
+   assert!(make_response("GET /metrics HTTP/1.1\r\n\r\n", &Arc::new(Mutex::new(state))).contains("# TYPE rusthound_runs_total counter"));
+}
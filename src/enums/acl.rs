@@ -1,14 +1,18 @@
 extern crate lazy_static;
 
+use colored::Colorize;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::enums::constants::*;
 use crate::enums::secdesc::*;
-use crate::enums::sid::{bin_to_string, sid_maker};
+use crate::enums::sid::{bin_to_string, decode_guid, sid_maker};
+use crate::json::parser::schema::set_property;
+use crate::json::stats::mark_partial;
 use crate::json::templates::*;
-use bitflags::bitflags;
-use log::{trace,error};
+use log::{info,warn,trace,error};
 
 /// This function allows to parse the attribut nTSecurityDescriptor from secdesc.rs
 /// <http://www.selfadsi.org/deep-inside/ad-security-descriptors.htm#SecurityDescriptorStructure>
@@ -21,83 +25,68 @@ pub fn parse_ntsecuritydescriptor(
     domain: &String,
 ) -> Vec<serde_json::value::Value> {
     let mut relations_dacl: Vec<serde_json::value::Value> = Vec::new();
-    let relations_sacl: Vec<serde_json::value::Value> = Vec::new();
-    let secdesc: SecurityDescriptor;
-    let mut owner_sid: String = "".to_string();
+    let mut owner_sid: Option<String> = None;
 
-    secdesc = SecurityDescriptor::parse(&nt).unwrap().1;
+    // Security descriptors can come from a hostile DC or an untrusted dump
+    let secdesc = match SecurityDescriptor::parse(&nt) {
+        Ok((_, secdesc)) => secdesc,
+        Err(err) => {
+            error!("Can't parse nTSecurityDescriptor of {}. Reason: {err}", valjson["Properties"]["name"].as_str().unwrap_or(""));
+            mark_partial(format!("nTSecurityDescriptor of {} not parsed", valjson["Properties"]["name"].as_str().unwrap_or("")));
+            return relations_dacl
+        }
+    };
     trace!("SECURITY-DESCRIPTOR: {:?}", secdesc);
 
     // Check for ACL protected for Bloodhound4.1+
     // IsACLProtected
-    let acl_is_protected = has_control(secdesc.control, SecurityDescriptorFlags::DACL_PROTECTED);
+    let acl_is_protected = secdesc.dacl_protected();
     //trace!("{} acl_is_protected: {:?}",valjson["Properties"]["name"],acl_is_protected);
 
     if !vec!["ca","template"].contains(&entry_type.as_str()) 
     {
         valjson["IsACLProtected"] = acl_is_protected.into();
+        // Also as property for Cypher queries, SDProp sets it on AdminSDHolder protected objects
+        set_property(valjson, "isaclprotected", acl_is_protected);
     }
 
-    if secdesc.offset_owner as usize != 0 
-    {
-        owner_sid = sid_maker(LdapSid::parse(&nt[secdesc.offset_owner as usize..]).unwrap().1,domain);
-        trace!("OWNER-SID: {:?}", owner_sid);
+    match secdesc.parse_owner(nt) {
+        Ok(Some(sid)) => owner_sid = Some(sid_maker(sid, domain)),
+        Ok(None) => {},
+        Err(err) => error!("{}", err),
     }
+    trace!("OWNER-SID: {:?}", owner_sid);
 
-    if secdesc.offset_group as usize != 0 
-    {
-        let group_sid = sid_maker(LdapSid::parse(&nt[secdesc.offset_group as usize..]).unwrap().1,domain);
-        trace!("GROUP-SID: {:?}", group_sid);
+    match secdesc.parse_group(nt) {
+        Ok(Some(sid)) => trace!("GROUP-SID: {:?}", sid_maker(sid, domain)),
+        Ok(None) => {},
+        Err(err) => error!("{}", err),
     }
 
-    if secdesc.offset_sacl as usize != 0 
-    {
-        let res = Acl::parse(&nt[secdesc.offset_sacl as usize..]);
-        match res {
-            Ok(_res) => {
-                let sacl = _res.1;
-                trace!("SACL: {:?}", sacl);
-                //let aces = sacl.data;
-                /*ace_maker(
-                    valjson,
-                    domain,
-                    &mut relations_sacl,
-                    &owner_sid,
-                    aces,
-                    &entry_type,
-                    result_attrs,
-                    result_bin,
-                );*/
-                trace!("RESULT: {:?}", relations_sacl);
-            },
-            Err(err) => error!("Error. Reason: {err}")
-        }
-        return relations_sacl;
+    // SACL isn't requested by default and isn't used for the edges
+    match secdesc.parse_sacl(nt) {
+        Ok(Some(sacl)) => trace!("SACL: {:?}", sacl),
+        Ok(None) => {},
+        Err(err) => error!("{}", err),
     }
 
-    if secdesc.offset_dacl as usize != 0 
-    {
-        let res = Acl::parse(&nt[secdesc.offset_dacl as usize..]);    
-        match res {
-            Ok(_res) => {
-                let dacl = _res.1;
-                trace!("DACL: {:?}", dacl);
-                let aces = dacl.data;
-                ace_maker(
-                    valjson,
-                    domain,
-                    &mut relations_dacl,
-                    &owner_sid,
-                    aces,
-                    &entry_type,
-                    result_attrs,
-                    result_bin,
-                );
-                trace!("RESULT: {:?}", relations_dacl);
-            },
-            Err(err) => error!("Error. Reason: {err}")
-        }
-        return relations_dacl;
+    match secdesc.parse_dacl(nt) {
+        Ok(Some(dacl)) => {
+            trace!("DACL: {:?}", dacl);
+            ace_maker(
+                valjson,
+                domain,
+                &mut relations_dacl,
+                owner_sid.as_ref(),
+                dacl.data,
+                &entry_type,
+                result_attrs,
+                result_bin,
+            );
+            trace!("RESULT: {:?}", relations_dacl);
+        },
+        Ok(None) => {},
+        Err(err) => error!("{}", err),
     }
     return relations_dacl;
 }
@@ -105,295 +94,439 @@ pub fn parse_ntsecuritydescriptor(
 /// Parse ace in acl and get correct values (thanks fox-it for bloodhound.py works)
 /// <https://github.com/fox-it/BloodHound.py/blob/master/bloodhound/enumeration/acls.py>
 fn ace_maker(
-    valjson: &serde_json::value::Value,
+    valjson: &mut serde_json::value::Value,
     domain: &String,
     relations: &mut Vec<serde_json::value::Value>,
-    osid: &String,
+    osid: Option<&String>,
     aces: Vec<Ace>,
     entry_type: &String,
-    #[warn(unused_variables)]
-    _result_attrs: &HashMap<String, Vec<String>>,
-    _result_bin: &HashMap<String, Vec<Vec<u8>>>,
+    result_attrs: &HashMap<String, Vec<String>>,
+    result_bin: &HashMap<String, Vec<Vec<u8>>>,
 ) {
-    trace!("ACL/ACE FOR ENTRY: {:?}",valjson["Properties"]["name"].as_str().unwrap().to_string());
+    trace!("ACL/ACE FOR ENTRY: {:?}",valjson["Properties"]["name"].as_str().unwrap_or(""));
+    let explained = is_explained(result_attrs, result_bin, domain);
     // Ignore Creator Owner or Local System
     let ignoresids = [
         "S-1-3-0".to_string(),
         "S-1-5-18".to_string(),
         "S-1-5-10".to_string(),
     ]; //, "S-1-1-0".to_string(), "S-1-5-10".to_string(), "S-1-5-11".to_string()];
-    if ignoresids.iter().any(|i| !osid.contains(i)) 
+    // No Owns edge without owner in the security descriptor
+    if let Some(osid) = osid {
+        if ignoresids.iter().any(|i| !osid.contains(i)) 
+        {
+            let mut relation = build_relation(osid,"Owns".to_string(),"Base".to_string(),false,);
+            if EDGE_PROVENANCE.load(Ordering::Relaxed) {
+                relation["Provenance"] = serde_json::json!({"source": "owner"});
+            }
+            relations.push(relation);
+        }
+        if explained {
+            add_owner_explanation(valjson, osid);
+        }
+    }
+
+    // Most specific class of the object, last objectClass value, like inetOrgPerson for users synced from HR systems
+    let object_class = result_attrs.get("objectClass")
+        .and_then(|classes| classes.last())
+        .map(|class| class.to_lowercase())
+        .unwrap_or_default();
+    // Writes to property sets, made in attribute edges once the schema is parsed
+    let mut property_writes: Vec<serde_json::value::Value> = Vec::new();
+    for (index, ace) in aces.iter().enumerate() {
+        let ace_writes = property_writes.len();
+        let result = ace_relations(valjson, domain, ace, entry_type, &object_class, &ignoresids, &mut property_writes);
+        if explained {
+            add_explanation(valjson, index, ace, domain, &result);
+        }
+        if let Ok(mut ace_relations) = result {
+            if EDGE_PROVENANCE.load(Ordering::Relaxed) {
+                let provenance = ace_provenance(index, ace);
+                for relation in ace_relations.iter_mut().chain(property_writes[ace_writes..].iter_mut()) {
+                    relation["Provenance"] = provenance.to_owned();
+                }
+            }
+            relations.extend(ace_relations);
+        }
+    }
+    if !property_writes.is_empty() {
+        match valjson["PropertySetWrites"].as_array_mut() {
+            Some(writes) => writes.append(&mut property_writes),
+            None => valjson["PropertySetWrites"] = property_writes.into(),
+        }
+    }
+}
+
+/// Function to make the relations of one ACE, or the reason why it doesn't make any.
+fn ace_relations(
+    valjson: &serde_json::value::Value,
+    domain: &String,
+    ace: &Ace,
+    entry_type: &String,
+    object_class: &String,
+    ignoresids: &[String],
+    property_writes: &mut Vec<serde_json::value::Value>,
+) -> Result<Vec<serde_json::value::Value>, String> {
+    let mut relations: Vec<serde_json::value::Value> = Vec::new();
+
+    if ace.ace_type != 0x05 && ace.ace_type != 0x00
+    {
+        trace!("Don't care about acetype {:?}", ace.ace_type);
+        return Err(format!("ACE type {:#04x} not handled, only access allowed ACEs make edges", ace.ace_type))
+    }
+
+    let sid = sid_maker(AceFormat::get_sid(ace.data.to_owned()).unwrap(), domain);
+    trace!("SID for this ACE: {}", &sid);
+
+    // Check if sid is in the ignored list
+    if ignoresids.iter().any(|i| sid.contains(i))
     {
-        relations.push(build_relation(osid,"Owns".to_string(),"Base".to_string(),false,));
+        return Err(format!("trustee {} ignored", sid))
     }
 
-    for ace in aces {
-        if ace.ace_type != 0x05 && ace.ace_type != 0x00
+    // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L74
+    if ace.ace_type == 0x05 {
+        trace!("TYPE: 0x05");
+        // GUID : inherited_object_type
+        let inherited_object_type = match AceFormat::get_inherited_object_type(ace.data.to_owned()) 
         {
-            trace!("Don't care about acetype {:?}", ace.ace_type);
-            continue
-        }
+            Some(inherited_object_type) => inherited_object_type,
+            None => 0,
+        };
+        // GUID : object_type
+        let object_type = match AceFormat::get_object_type(ace.data.to_owned()) 
+        {
+            Some(object_type) => object_type,
+            None => 0,
+        };
+        // Get and check ace.ace_flags object content INHERITED_ACE and return boolean
+        let is_inherited = ace.ace_flags & INHERITED_ACE == INHERITED_ACE;
 
-        let sid = sid_maker(AceFormat::get_sid(ace.data.to_owned()).unwrap(), domain);
-        trace!("SID for this ACE: {}", &sid);
+        // Get the Flag for the ace.datas
+        let flags = AceFormat::get_flags(ace.data.to_owned()).unwrap().bits();
 
-        // Check if sid is in the ignored list
-        if ignoresids.iter().any(|i| sid.contains(i))
+        // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L77
+        if (ace.ace_flags & INHERITED_ACE != INHERITED_ACE)
+        && (ace.ace_flags & INHERIT_ONLY_ACE == INHERIT_ONLY_ACE) 
         {
-            continue
+            // ACE is set on this object, but only inherited, so not applicable to us
+            return Err("inherit-only ACE, only applies to child objects".to_string())
         }
 
-        // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L74
-        if ace.ace_type == 0x05 {
-            trace!("TYPE: 0x05");
-            // GUID : inherited_object_type
-            let inherited_object_type = match AceFormat::get_inherited_object_type(ace.data.to_owned()) 
-            {
-                Some(inherited_object_type) => inherited_object_type,
-                None => 0,
-            };
-            // GUID : object_type
-            let object_type = match AceFormat::get_object_type(ace.data.to_owned()) 
+        // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L82
+        if (ace.ace_flags & INHERITED_ACE == INHERITED_ACE) 
+        && (&flags & ACE_INHERITED_OBJECT_TYPE_PRESENT == ACE_INHERITED_OBJECT_TYPE_PRESENT)
+        {
+            // ACE is set on this object, but only inherited, so not applicable to us
+            // need to verify if the ACE applies to this object type #todo
+            // Verify if the ACE applies to this object type
+            // if not ace_applies(ace_object.acedata.get_inherited_object_type().lower(), entrytype, objecttype_guid_map):
+            // continue
+            // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L85
+            let ace_guid = bin_to_string(&inherited_object_type.to_be_bytes().to_vec()).to_lowercase();
+            // Inherited for the class of the object too, like inetOrgPerson ACEs on inetOrgPerson users
+            if !(ace_applies(&ace_guid, &entry_type)) && !(ace_applies(&ace_guid, &object_class))
             {
-                Some(object_type) => object_type,
-                None => 0,
-            };
-            // Get and check ace.ace_flags object content INHERITED_ACE and return boolean
-            let is_inherited = ace.ace_flags & INHERITED_ACE == INHERITED_ACE;
+                return Err(format!("inherited object type {} doesn't apply to {} objects", ace_guid, entry_type))
+            }
+        }
 
-            // Get the Flag for the ace.datas
-            let flags = AceFormat::get_flags(ace.data.to_owned()).unwrap().bits();
+        let mask = match AceFormat::get_mask(ace.data.to_owned()) {
+            Some(mask) => AccessMask::new(mask),
+            None => return Err("no access mask".to_string()),
+        };
+        trace!("ACE MASK for ACETYPE 0x05: {}", mask.describe());
 
-            // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L77
-            if (ace.ace_flags & INHERITED_ACE != INHERITED_ACE)
-            && (ace.ace_flags & INHERIT_ONLY_ACE == INHERIT_ONLY_ACE) 
-            {
-                // ACE is set on this object, but only inherited, so not applicable to us
-                continue
-            }
+        let ace_guid = bin_to_string(&object_type.to_be_bytes().to_vec()).to_lowercase();
+        trace!("ACE GUID for ACETYPE 0x05: {:?}", ace_guid);
 
-            // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L82
-            if (ace.ace_flags & INHERITED_ACE == INHERITED_ACE) 
-            && (&flags & ACE_INHERITED_OBJECT_TYPE_PRESENT == ACE_INHERITED_OBJECT_TYPE_PRESENT)
+        // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L92
+        if (mask.contains(AccessMask::GENERIC_ALL))
+        || (mask.contains(AccessMask::WRITE_DACL))
+        || (mask.contains(AccessMask::WRITE_OWNER))
+        || (mask.contains(AccessMask::GENERIC_WRITE))
+        {
+            trace!("ACE MASK contain: GENERIC_ALL or WRITE_DACL or WRITE_OWNER or GENERIC_WRITE");
+            if (&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT) && !(ace_applies(&ace_guid, &entry_type))
             {
-                // ACE is set on this object, but only inherited, so not applicable to us
-                // need to verify if the ACE applies to this object type #todo
-                // Verify if the ACE applies to this object type
-                // if not ace_applies(ace_object.acedata.get_inherited_object_type().lower(), entrytype, objecttype_guid_map):
-                // continue
-                // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L85
-                let ace_guid = bin_to_string(&inherited_object_type.to_be_bytes().to_vec()).to_lowercase();
-                if !(ace_applies(&ace_guid, &entry_type)) 
-                {
-                    continue
-                }
+                return Err(format!("object type {} doesn't apply to {} objects", ace_guid, entry_type))
             }
-
-            let mask = match AceFormat::get_mask(ace.data.to_owned()) {
-                Some(mask) => mask,
-                None => continue,
-            };
-            trace!("ACE MASK for ACETYPE 0x05: {:?}", mask);
-
-            let ace_guid = bin_to_string(&object_type.to_be_bytes().to_vec()).to_lowercase();
-            trace!("ACE GUID for ACETYPE 0x05: {:?}", ace_guid);
-
-            // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L92
-            if ((MaskFlags::GENERIC_ALL.bits() | mask) == mask)
-            || ((MaskFlags::WRITE_DACL.bits() | mask) == mask)
-            || ((MaskFlags::WRITE_OWNER.bits() | mask) == mask)
-            || ((MaskFlags::GENERIC_WRITE.bits() | mask) == mask)
+            if mask.contains(AccessMask::GENERIC_ALL) 
             {
-                trace!("ACE MASK contain: GENERIC_ALL or WRITE_DACL or WRITE_OWNER or GENERIC_WRITE");
-                if (&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT) && !(ace_applies(&ace_guid, &entry_type))
+                if entry_type == "computer" && (&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
+                && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) 
                 {
-                    continue
-                }
-                if (MaskFlags::GENERIC_ALL.bits() | mask) == mask 
-                {
-                    if entry_type == "computer" && (&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
-                    && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) 
+                    if &ace_guid == OBJECTTYPE_GUID_HASHMAP.get("ms-mcs-admpwd").unwrap_or(&String::from("GUID-NOT-FOUND")) 
                     {
-                        if &ace_guid == OBJECTTYPE_GUID_HASHMAP.get("ms-mcs-admpwd").unwrap_or(&String::from("GUID-NOT-FOUND")) 
-                        {
-                            relations.push(build_relation(&sid,"ReadLAPSPassword".to_string(),"".to_string(),is_inherited));
-                        }
-                    } else {
-                        relations.push(build_relation(&sid,"GenericAll".to_string(),"".to_string(),is_inherited));
+                        relations.push(build_relation(&sid,"ReadLAPSPassword".to_string(),"".to_string(),is_inherited));
                     }
-                    continue
+                } else {
+                    relations.push(build_relation(&sid,"GenericAll".to_string(),"".to_string(),is_inherited));
                 }
-                if (MaskFlags::GENERIC_WRITE.bits() | mask) == mask 
+                return Ok(relations)
+            }
+            if mask.contains(AccessMask::GENERIC_WRITE) 
+            {
+                relations.push(build_relation(&sid,"GenericWrite".to_string(),"".to_string(),is_inherited));
+                if (entry_type != "domain") && (entry_type != "computer") 
                 {
-                    relations.push(build_relation(&sid,"GenericWrite".to_string(),"".to_string(),is_inherited));
-                    if (entry_type != "domain") && (entry_type != "computer") 
-                    {
-                        continue
-                    }
-                }
-                if (MaskFlags::WRITE_DACL.bits() | mask) == mask {
-                    relations.push(build_relation(&sid,"WriteDacl".to_string(),"".to_string(),is_inherited));
-                }
-                if (MaskFlags::WRITE_OWNER.bits() | mask) == mask {
-                    relations.push(build_relation(&sid,"WriteOwner".to_string(),"".to_string(),is_inherited));
+                    return Ok(relations)
                 }
             }
+            if mask.contains(AccessMask::WRITE_DACL) {
+                relations.push(build_relation(&sid,"WriteDacl".to_string(),"".to_string(),is_inherited));
+            }
+            if mask.contains(AccessMask::WRITE_OWNER) {
+                relations.push(build_relation(&sid,"WriteOwner".to_string(),"".to_string(),is_inherited));
+            }
+        }
 
-            // Property write privileges
-            // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L126
-            if (MaskFlags::ADS_RIGHT_DS_WRITE_PROP.bits() | mask) == mask {
+        // Property write privileges
+        // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L126
+        if mask.contains(AccessMask::ADS_RIGHT_DS_WRITE_PROP) {
 
-                if ((entry_type == "user") || (entry_type == "group") || (entry_type == "computer"))
-                && !(&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
-                {
-                    relations.push(build_relation(&sid,"GenericWrite".to_string(),"".to_string(),is_inherited));
-                }
-                if entry_type == "group" && can_write_property(&ace, WRITE_MEMBER)
-                {
-                    relations.push(build_relation(&sid,"AddMember".to_string(),"".to_string(),is_inherited));
-                }
-                if entry_type == "computer" && can_write_property(&ace, ALLOWED_TO_ACT)
-                {
-                    relations.push(build_relation(&sid,"AddAllowedToAct".to_string(),"".to_string(),is_inherited));
-                }
-                if entry_type == "computer" && can_write_property(&ace, USER_ACCOUNT_RESTRICTIONS_SET) && !&sid.ends_with("-512")
-                {
-                    relations.push(build_relation(&sid,"WriteAccountRestrictions".to_string(),"".to_string(),is_inherited));
-                }
-
-                // Since BloodHound 4.1
-                // AddKeyCredentialLink write access
-                if ((entry_type == "user") || (entry_type == "computer"))
-                && (&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
-                && (&ace_guid == OBJECTTYPE_GUID_HASHMAP.get("ms-ds-key-credential-link").unwrap_or(&String::from("GUID-NOT-FOUND")))
-                {
-                    relations.push(build_relation(&sid,"AddKeyCredentialLink".to_string(),"".to_string(),is_inherited));
-                }
-                if (entry_type == "user")
-                && (&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT) 
-                && (&ace_guid == OBJECTTYPE_GUID_HASHMAP.get("service-principal-name").unwrap_or(&String::from("GUID-NOT-FOUND")))
-                {
-                    relations.push(build_relation(&sid,"WriteSPN".to_string(),"".to_string(),is_inherited));
-                }
-            } 
-            else if (MaskFlags::ADS_RIGHT_DS_SELF.bits() | mask) == mask 
+            if ((entry_type == "user") || (entry_type == "group") || (entry_type == "computer"))
+            && !(&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
             {
-                if (entry_type == "group") && (&ace_guid == WRITE_MEMBER)
-                {
-                    relations.push(build_relation(&sid,"AddSelf".to_string(),"".to_string(),is_inherited));
-                }
+                relations.push(build_relation(&sid,"GenericWrite".to_string(),"".to_string(),is_inherited));
+            }
+            if entry_type == "group" && can_write_property(&ace, WRITE_MEMBER)
+            {
+                relations.push(build_relation(&sid,"AddMember".to_string(),"".to_string(),is_inherited));
+            }
+            if entry_type == "computer" && can_write_property(&ace, ALLOWED_TO_ACT)
+            {
+                relations.push(build_relation(&sid,"AddAllowedToAct".to_string(),"".to_string(),is_inherited));
+            }
+            if entry_type == "computer" && can_write_property(&ace, USER_ACCOUNT_RESTRICTIONS_SET) && !&sid.ends_with("-512")
+            {
+                relations.push(build_relation(&sid,"WriteAccountRestrictions".to_string(),"".to_string(),is_inherited));
             }
 
-            // Property read privileges
-            // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L138
-            if (MaskFlags::ADS_RIGHT_DS_READ_PROP.bits() | mask) == mask 
+            // Since BloodHound 4.1
+            // Write access to one attribute, the edge gives the abuse primitive instead of GenericWrite
+            if &flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT
             {
-                if (entry_type == "computer")
-                && (&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
-                && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) == true
+                for (property, right, entry_types) in PROPERTY_WRITE_RIGHTS.iter()
                 {
-                    if &ace_guid == OBJECTTYPE_GUID_HASHMAP.get("ms-mcs-admpwd").unwrap_or(&String::from("GUID-NOT-FOUND"))
+                    if entry_types.contains(&entry_type.as_str())
+                    && (&ace_guid == OBJECTTYPE_GUID_HASHMAP.get(*property).unwrap_or(&String::from("GUID-NOT-FOUND")))
                     {
-                        relations.push(build_relation(&sid,"ReadLAPSPassword".to_string(),"".to_string(),is_inherited));
+                        relations.push(build_relation(&sid,right.to_string(),"".to_string(),is_inherited));
                     }
                 }
+                // ObjectType can be a property set, like Personal-Information, expanded with the schema
+                if vec!["user","group","computer"].contains(&entry_type.as_str())
+                {
+                    property_writes.push(serde_json::json!({
+                        "PrincipalSID": sid,
+                        "ObjectType": ace_guid,
+                        "IsInherited": is_inherited,
+                    }));
+                }
+            }
+        } 
+        else if mask.contains(AccessMask::ADS_RIGHT_DS_SELF) 
+        {
+            if (entry_type == "group") && (&ace_guid == WRITE_MEMBER)
+            {
+                relations.push(build_relation(&sid,"AddSelf".to_string(),"".to_string(),is_inherited));
             }
+        }
 
-            // Extended rights
-            // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L146
-            if (MaskFlags::ADS_RIGHT_DS_CONTROL_ACCESS.bits() | mask) == mask 
+        // Property read privileges
+        // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L138
+        if mask.contains(AccessMask::ADS_RIGHT_DS_READ_PROP) 
+        {
+            if (entry_type == "computer")
+            && (&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
+            && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) == true
             {
-                // All Extended
-                if vec!["user","domain"].contains(&entry_type.as_str()) && !(&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
-                {
-                    relations.push(build_relation(&sid,"AllExtendedRights".to_string(),"".to_string(),is_inherited));
-                }
-                if (entry_type == "computer")
-                && !(&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
-                && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) == true
-                {
-                    relations.push(build_relation(&sid,"AllExtendedRights".to_string(),"".to_string(),is_inherited));
-                }
-                if (entry_type == "domain") && has_extended_right(&ace, GET_CHANGES) 
+                if &ace_guid == OBJECTTYPE_GUID_HASHMAP.get("ms-mcs-admpwd").unwrap_or(&String::from("GUID-NOT-FOUND"))
                 {
-                    relations.push(build_relation(&sid,"GetChanges".to_string(),"".to_string(),is_inherited));
-                }
-                if (entry_type == "domain") && has_extended_right(&ace, GET_CHANGES_ALL) 
-                {
-                    relations.push(build_relation(&sid,"GetChangesAll".to_string(),"".to_string(),is_inherited));
-                }
-                if (entry_type == "domain") && has_extended_right(&ace, GET_CHANGES_IN_FILTERED_SET)
-                {
-                    relations.push(build_relation(&sid,"GetChangesInFilteredSet".to_string(),"".to_string(),is_inherited));
-                }
-                if (entry_type == "user") && has_extended_right(&ace, USER_FORCE_CHANGE_PASSWORD)
-                {
-                    relations.push(build_relation(&sid,"ForceChangePassword".to_string(),"".to_string(),is_inherited));
-                }
-                if vec!["ca","template"].contains(&entry_type.as_str()) && has_extended_right(&ace, ENROLL)
-                {
-                    relations.push(build_relation(&sid,"Enroll".to_string(),"".to_string(),is_inherited));
-                }
-                if vec!["ca","template"].contains(&entry_type.as_str()) && has_extended_right(&ace, AUTO_ENROLL)
-                {
-                    relations.push(build_relation(&sid,"AutoEnroll".to_string(),"".to_string(),is_inherited));
+                    relations.push(build_relation(&sid,"ReadLAPSPassword".to_string(),"".to_string(),is_inherited));
                 }
             }
         }
 
-        // For AceType == 0x00
-        // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L162
-        if ace.ace_type == 0x00 {
-            trace!("TYPE: 0x00");
-            let is_inherited = ace.ace_flags & INHERITED_ACE == INHERITED_ACE;
-
-            let mask = match AceFormat::get_mask(ace.data.to_owned()) {
-                Some(mask) => mask,
-                None => continue,
-            };
-            trace!("ACE MASK for ACETYPE 0x00: {:?}", mask);
-
-            if (MaskFlags::GENERIC_ALL.bits() | mask) == mask 
+        // Extended rights
+        // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L146
+        // Without ObjectType the ACE gives all the extended rights and only AllExtendedRights is made,
+        // the rights below need the GUID of this extended right in ObjectType.
+        if mask.contains(AccessMask::ADS_RIGHT_DS_CONTROL_ACCESS) 
+        {
+            // All Extended
+            if vec!["user","domain"].contains(&entry_type.as_str()) && !(&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
             {
-                relations.push(build_relation(&sid,"GenericAll".to_string(),"".to_string(),is_inherited));
-                continue
+                relations.push(build_relation(&sid,"AllExtendedRights".to_string(),"".to_string(),is_inherited));
             }
-            if (MaskFlags::ADS_RIGHT_DS_WRITE_PROP.bits() | mask) == mask 
+            if (entry_type == "computer")
+            && !(&flags & ACE_OBJECT_TYPE_PRESENT == ACE_OBJECT_TYPE_PRESENT)
+            && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) == true
             {
-                relations.push(build_relation(&sid,"GenericWrite".to_string(),"".to_string(),is_inherited));
+                relations.push(build_relation(&sid,"AllExtendedRights".to_string(),"".to_string(),is_inherited));
             }
-            if (MaskFlags::WRITE_OWNER.bits() | mask) == mask
+            // ms-Mcs-AdmPwd is confidential, reading it needs the control access right on the attribute
+            if (entry_type == "computer")
+            && !mask.contains(AccessMask::ADS_RIGHT_DS_READ_PROP)
+            && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) == true
+            && has_specific_extended_right(&ace, OBJECTTYPE_GUID_HASHMAP.get("ms-mcs-admpwd").unwrap_or(&String::from("GUID-NOT-FOUND")))
             {
-                relations.push(build_relation(&sid,"WriteOwner".to_string(),"".to_string(),is_inherited));
+                relations.push(build_relation(&sid,"ReadLAPSPassword".to_string(),"".to_string(),is_inherited));
             }
-            // For users and domain, check extended rights
-            if ((entry_type == "user") || (entry_type == "domain"))
-                && ((MaskFlags::ADS_RIGHT_DS_CONTROL_ACCESS.bits() | mask) == mask)
+            if (entry_type == "domain") && has_specific_extended_right(&ace, GET_CHANGES) 
             {
-                relations.push(build_relation(&sid,"AllExtendedRights".to_string(),"".to_string(),is_inherited));
+                relations.push(build_relation(&sid,"GetChanges".to_string(),"".to_string(),is_inherited));
             }
-            // For computer
-            if (entry_type == "computer")
-                && ((MaskFlags::ADS_RIGHT_DS_CONTROL_ACCESS.bits() | mask) == mask)
-                && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) == true
+            if (entry_type == "domain") && has_specific_extended_right(&ace, GET_CHANGES_ALL) 
             {
-                relations.push(build_relation(&sid,"AllExtendedRights".to_string(),"".to_string(),is_inherited));
+                relations.push(build_relation(&sid,"GetChangesAll".to_string(),"".to_string(),is_inherited));
             }
-            if (MaskFlags::WRITE_DACL.bits() | mask) == mask 
+            if (entry_type == "domain") && has_specific_extended_right(&ace, GET_CHANGES_IN_FILTERED_SET)
             {
-                relations.push(build_relation(&sid,"WriteDacl".to_string(),"".to_string(),is_inherited));
+                relations.push(build_relation(&sid,"GetChangesInFilteredSet".to_string(),"".to_string(),is_inherited));
+            }
+            if (entry_type == "user") && has_specific_extended_right(&ace, USER_FORCE_CHANGE_PASSWORD)
+            {
+                relations.push(build_relation(&sid,"ForceChangePassword".to_string(),"".to_string(),is_inherited));
             }
-            // ADCS
-            if (entry_type == "ca") && (MaskFlags::MANAGE_CA.bits() | mask) == mask
+            // ADCS, all the extended rights include Certificate-Enrollment
+            if vec!["ca","template"].contains(&entry_type.as_str())
+            && has_extended_right(&ace, ENROLL)
             {
-                relations.push(build_relation(&sid,"ManageCa".to_string(),"".to_string(),is_inherited));
+                relations.push(build_relation(&sid,"Enroll".to_string(),"".to_string(),is_inherited));
             }
-            if (entry_type == "ca") && (MaskFlags::MANAGE_CERTIFICATES.bits() | mask) == mask
+            if (entry_type == "template") && has_specific_extended_right(&ace, AUTO_ENROLL)
             {
-                relations.push(build_relation(&sid,"ManageCertificates".to_string(),"".to_string(),is_inherited));
+                relations.push(build_relation(&sid,"AutoEnroll".to_string(),"".to_string(),is_inherited));
             }
         }
     }
+
+    // For AceType == 0x00
+    // https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L162
+    if ace.ace_type == 0x00 {
+        trace!("TYPE: 0x00");
+        let is_inherited = ace.ace_flags & INHERITED_ACE == INHERITED_ACE;
+
+        let mask = match AceFormat::get_mask(ace.data.to_owned()) {
+            Some(mask) => AccessMask::new(mask),
+            None => return Err("no access mask".to_string()),
+        };
+        trace!("ACE MASK for ACETYPE 0x00: {}", mask.describe());
+
+        if mask.contains(AccessMask::GENERIC_ALL) 
+        {
+            relations.push(build_relation(&sid,"GenericAll".to_string(),"".to_string(),is_inherited));
+            return Ok(relations)
+        }
+        if mask.contains(AccessMask::ADS_RIGHT_DS_WRITE_PROP) 
+        {
+            relations.push(build_relation(&sid,"GenericWrite".to_string(),"".to_string(),is_inherited));
+        }
+        if mask.contains(AccessMask::WRITE_OWNER)
+        {
+            relations.push(build_relation(&sid,"WriteOwner".to_string(),"".to_string(),is_inherited));
+        }
+        // For users and domain, check extended rights
+        if ((entry_type == "user") || (entry_type == "domain"))
+            && (mask.contains(AccessMask::ADS_RIGHT_DS_CONTROL_ACCESS))
+        {
+            relations.push(build_relation(&sid,"AllExtendedRights".to_string(),"".to_string(),is_inherited));
+        }
+        // For computer
+        if (entry_type == "computer")
+            && (mask.contains(AccessMask::ADS_RIGHT_DS_CONTROL_ACCESS))
+            && valjson["Properties"]["haslaps"].as_bool().unwrap_or(false) == true
+        {
+            relations.push(build_relation(&sid,"AllExtendedRights".to_string(),"".to_string(),is_inherited));
+        }
+        // For ADCS, all the extended rights include Certificate-Enrollment
+        if vec!["ca","template"].contains(&entry_type.as_str())
+            && (mask.contains(AccessMask::ADS_RIGHT_DS_CONTROL_ACCESS))
+        {
+            relations.push(build_relation(&sid,"Enroll".to_string(),"".to_string(),is_inherited));
+        }
+        if mask.contains(AccessMask::WRITE_DACL) 
+        {
+            relations.push(build_relation(&sid,"WriteDacl".to_string(),"".to_string(),is_inherited));
+        }
+        // ADCS
+        if (entry_type == "ca") && mask.contains(AccessMask::MANAGE_CA)
+        {
+            relations.push(build_relation(&sid,"ManageCa".to_string(),"".to_string(),is_inherited));
+        }
+        if (entry_type == "ca") && mask.contains(AccessMask::MANAGE_CERTIFICATES)
+        {
+            relations.push(build_relation(&sid,"ManageCertificates".to_string(),"".to_string(),is_inherited));
+        }
+    }
+    Ok(relations)
+}
+
+/// Attribute from OBJECTTYPE_GUID_HASHMAP, edge made for write access to this attribute and entry types.
+const PROPERTY_WRITE_RIGHTS: [(&str, &str, &[&str]); 4] = [
+    ("ms-ds-key-credential-link", "AddKeyCredentialLink", &["user","computer"]),
+    ("service-principal-name", "WriteSPN", &["user","computer"]),
+    ("user-account-control", "WriteUserAccountControl", &["user","computer"]),
+    ("script-path", "WriteLogonScript", &["user"]),
+];
+
+/// Attribute from OBJECTTYPE_GUID_HASHMAP, edge made for write access to a property set with this attribute and entry types.
+/// The attributes of PROPERTY_WRITE_RIGHTS are checked too.
+const PROPERTY_SET_RIGHTS: [(&str, &str, &[&str]); 2] = [
+    ("member", "AddMember", &["group"]),
+    ("ms-ds-allowed-to-act-on-behalf-of-other-identity", "AddAllowedToAct", &["computer"]),
+];
+
+/// Function to make the attribute edges of the writes to property sets saved while parsing the security descriptors.
+/// `property_sets` links the GUID of each property set to the schemaIDGUID of its attributes (attributeSecurityGUID in the schema).
+/// Returns the number of edges made, the writes are removed from the objects even without schema.
+pub fn add_property_set_edges(
+    vec_objects: &mut Vec<serde_json::value::Value>,
+    entry_type: &str,
+    property_sets: &HashMap<String, Vec<String>>,
+) -> usize {
+    let mut count = 0;
+    for object in vec_objects.iter_mut() {
+        let writes = match object.as_object_mut().and_then(|object| object.remove("PropertySetWrites")) {
+            Some(serde_json::value::Value::Array(writes)) => writes,
+            _ => continue,
+        };
+        for write in writes {
+            let attributes = match write["ObjectType"].as_str().and_then(|guid| property_sets.get(guid)) {
+                Some(attributes) => attributes,
+                None => continue,
+            };
+            for (property, right, entry_types) in PROPERTY_WRITE_RIGHTS.iter().chain(PROPERTY_SET_RIGHTS.iter()) {
+                if !entry_types.contains(&entry_type) {
+                    continue
+                }
+                match OBJECTTYPE_GUID_HASHMAP.get(*property) {
+                    Some(guid) if attributes.contains(guid) => {},
+                    _ => continue,
+                }
+                let sid = write["PrincipalSID"].as_str().unwrap_or("").to_string();
+                let inherited = write["IsInherited"].as_bool().unwrap_or(false);
+                let aces = match object["Aces"].as_array_mut() {
+                    Some(aces) => aces,
+                    None => continue,
+                };
+                if aces.iter().any(|ace| ace["PrincipalSID"] == sid.as_str() && ace["RightName"] == *right && ace["IsInherited"] == inherited) {
+                    continue
+                }
+                let mut relation = build_relation(&sid, right.to_string(), "".to_string(), inherited);
+                if write["Provenance"].is_object() {
+                    relation["Provenance"] = write["Provenance"].to_owned();
+                    relation["Provenance"]["propertyset"] = write["ObjectType"].to_owned();
+                }
+                trace!("{} made from property set {} for {}", right, write["ObjectType"], sid);
+                aces.push(relation);
+                count += 1;
+            }
+        }
+    }
+    count
 }
 
 /// Make Relation
@@ -424,11 +557,11 @@ fn can_write_property(ace: &Ace, bin_property: &str) -> bool {
 
     // If not found, then assume can't write. Should not happen, but missing some parsers.
     let mask = match AceFormat::get_mask(ace.data.to_owned()) {
-        Some(mask) => mask,
+        Some(mask) => AccessMask::new(mask),
         None => return false,
     };
 
-    if (MaskFlags::ADS_RIGHT_DS_WRITE_PROP.bits() | mask) != mask {
+    if !mask.contains(AccessMask::ADS_RIGHT_DS_WRITE_PROP) {
         //if not ace_object.acedata.mask.has_priv(ACCESS_MASK.ADS_RIGHT_DS_WRITE_PROP):
         return false;
     }
@@ -467,10 +600,10 @@ fn has_extended_right(ace: &Ace, bin_right_guid: &str) -> bool {
     // [MS-ADTS] section 5.1.3.2: https://msdn.microsoft.com/en-us/library/cc223511.aspx
 
     let mask = match AceFormat::get_mask(ace.data.to_owned()) {
-        Some(mask) => mask,
+        Some(mask) => AccessMask::new(mask),
         None => return false,
     };
-    if (MaskFlags::ADS_RIGHT_DS_CONTROL_ACCESS.bits() | mask) != mask {
+    if !mask.contains(AccessMask::ADS_RIGHT_DS_CONTROL_ACCESS) {
         // if not ace_object.acedata.mask.has_priv(ACCESS_MASK.ADS_RIGHT_DS_CONTROL_ACCESS):
         trace!("has_extended_right : return false for ADS_RIGHT_DS_CONTROL_ACCESS != mask");
         return false;
@@ -502,6 +635,20 @@ fn has_extended_right(ace: &Ace, bin_right_guid: &str) -> bool {
     return false;
 }
 
+/// Checks if the ACE gives the control access right with the given GUID in ObjectType.
+/// Unlike [`has_extended_right`], an ACE without ObjectType doesn't match: it gives all the
+/// extended rights and is already made as AllExtendedRights.
+fn has_specific_extended_right(ace: &Ace, bin_right_guid: &str) -> bool {
+    let flags = match AceFormat::get_flags(ace.data.to_owned()) {
+        Some(flags) => flags.bits(),
+        None => return false,
+    };
+    if !((&flags & ACE_OBJECT_TYPE_PRESENT) == ACE_OBJECT_TYPE_PRESENT) {
+        return false;
+    }
+    has_extended_right(ace, bin_right_guid)
+}
+
 /// Check if an ACE applies to this object.
 /// <https://github.com/fox-it/BloodHound.py/blob/645082e3462c93f31b571db945cde1fd7b837fb9/bloodhound/enumeration/acls.py#L229>
 fn ace_applies(ace_guid: &String, entry_type: &String) -> bool {
@@ -527,76 +674,192 @@ pub fn parse_gmsa(
             continue
         }
         processed_aces[i]["RightName"] = "ReadGMSAPassword".to_string().into();
+        if processed_aces[i]["Provenance"].is_object() {
+            processed_aces[i]["Provenance"]["attribute"] = "msDS-GroupMSAMembership".into();
+        }
         relations_ace_b.push(processed_aces[i].to_owned());
     }
 }
 
-// Access Mask contain value?
-bitflags! {
-    struct MaskFlags: u32 {
-        // These constants are only used when WRITING
-        // and are then translated into their actual rights
-        const SET_GENERIC_READ        = 0x80000000;
-        const SET_GENERIC_WRITE       = 0x04000000;
-        const SET_GENERIC_EXECUTE     = 0x20000000;
-        const SET_GENERIC_ALL         = 0x10000000;
-        // When reading, these constants are actually represented by
-        // the following for Active Directory specific Access Masks
-        // Reference: https://docs.microsoft.com/en-us/dotnet/api/system.directoryservices.activedirectoryrights?view=netframework-4.7.2
-        const GENERIC_READ            = 0x00020094;
-        const GENERIC_WRITE           = 0x00020028;
-        const GENERIC_EXECUTE         = 0x00020004;
-        const GENERIC_ALL             = 0x000F01FF;
+/// ACE relations annotated with the ACE they come from (--edge-provenance)
+static EDGE_PROVENANCE: AtomicBool = AtomicBool::new(false);
 
-        // These are actual rights (for all ACE types)
-        const MAXIMUM_ALLOWED         = 0x02000000;
-        const ACCESS_SYSTEM_SECURITY  = 0x01000000;
-        const SYNCHRONIZE             = 0x00100000;
-        const WRITE_OWNER             = 0x00080000;
-        const WRITE_DACL              = 0x00040000;
-        const READ_CONTROL            = 0x00020000;
-        const DELETE                  = 0x00010000;
+/// Enable or disable the provenance of the ACE relations made by the next parsing.
+pub fn set_edge_provenance(enabled: bool) {
+    EDGE_PROVENANCE.store(enabled, Ordering::Relaxed);
+}
 
-        // ACE type specific mask constants (for ACCESS_ALLOWED_OBJECT_ACE)
-        // Note that while not documented, these also seem valid
-        // for ACCESS_ALLOWED_ACE types
-        const ADS_RIGHT_DS_CONTROL_ACCESS         = 0x00000100;
-        const ADS_RIGHT_DS_CREATE_CHILD           = 0x00000001;
-        const ADS_RIGHT_DS_DELETE_CHILD           = 0x00000002;
-        const ADS_RIGHT_DS_READ_PROP              = 0x00000010;
-        const ADS_RIGHT_DS_WRITE_PROP             = 0x00000020;
-        const ADS_RIGHT_DS_SELF                   = 0x00000008;
-        
-        // ADCS
-        const MANAGE_CA = 1;
-        const MANAGE_CERTIFICATES = 2;
+/// Function to make the provenance of the relations made from one ACE: index in the DACL, raw mask, object types and inherited flag.
+fn ace_provenance(index: usize, ace: &Ace) -> serde_json::value::Value {
+    let guid = |guid: u128| bin_to_string(&guid.to_be_bytes().to_vec()).to_lowercase();
+    serde_json::json!({
+        "source": "dacl",
+        "aceindex": index,
+        "acetype": ace.ace_type,
+        "mask": AceFormat::get_mask(ace.data.to_owned()).map(|mask| format!("{:#010x}", mask)),
+        "objecttype": AceFormat::get_object_type(ace.data.to_owned()).map(guid),
+        "inheritedobjecttype": AceFormat::get_inherited_object_type(ace.data.to_owned()).map(guid),
+        "inherited": ace.ace_flags & INHERITED_ACE == INHERITED_ACE,
+    })
+}
+
+/// Object explained with --explain-aces, objectid or distinguished name in uppercase
+static EXPLAIN_TARGET: Mutex<Option<String>> = Mutex::new(None);
+/// ACEs of the object explained, parser workers add them from their own thread
+static EXPLAINED_ACES: Mutex<Vec<AceExplanation>> = Mutex::new(Vec::new());
+
+/// Structure containing the decision made for one ACE of the object explained.
+pub struct AceExplanation {
+    /// Object name
+    pub object: String,
+    /// Position in the DACL, None for the owner
+    pub index: Option<usize>,
+    /// ACE type, flags, decoded mask and object types
+    pub description: String,
+    /// Trustee SID, resolved to its name when printed
+    pub trustee: String,
+    /// Edges made or the reason the ACE was skipped
+    pub result: Result<Vec<String>, String>,
+}
+
+/// Set the object to explain for the next parsing, None to disable it. The previous explanations are forgotten (daemon mode).
+pub fn set_explain_target(target: Option<String>) {
+    if let Ok(mut current) = EXPLAIN_TARGET.lock() {
+        *current = target.map(|target| target.to_uppercase());
+    }
+    if let Ok(mut explained) = EXPLAINED_ACES.lock() {
+        explained.clear();
+    }
+}
+
+/// Check if the entry is the object to explain from its objectSid, objectGUID or distinguishedName.
+fn is_explained(
+    result_attrs: &HashMap<String, Vec<String>>,
+    result_bin: &HashMap<String, Vec<Vec<u8>>>,
+    domain: &String,
+) -> bool {
+    let target = match EXPLAIN_TARGET.lock().ok().and_then(|target| target.to_owned()) {
+        Some(target) => target,
+        None => return false,
+    };
+    if result_attrs.get("distinguishedName").and_then(|dn| dn.first()).map(|dn| dn.to_uppercase() == target).unwrap_or(false) {
+        return true
+    }
+    if let Some(Ok((_, sid))) = result_bin.get("objectSid").and_then(|sid| sid.first()).map(|sid| LdapSid::parse(sid)) {
+        if sid_maker(sid, domain).to_uppercase() == target {
+            return true
+        }
     }
+    result_bin.get("objectGUID")
+        .and_then(|guid| guid.first())
+        .map(|guid| guid.len() == 16 && decode_guid(guid).to_uppercase() == target)
+        .unwrap_or(false)
 }
 
-bitflags! {
-    struct SecurityDescriptorFlags: u16 {
-        const SELF_RELATIVE = 0b1000000000000000;
-        const RM_CONTROL_VALID = 0b0100000000000000;
-        const SACL_PROTECTED = 0b0010000000000000;
-        const DACL_PROTECTED = 0b0001000000000000;
-        const SACL_INHERITED = 0b0000100000000000;
-        const DACL_INHERITED = 0b0000010000000000;
-        const SACL_COMPUTED_INHERITANCE_REQUIRED = 0b0000001000000000;
-        const DACL_COMPUTED_INHERITANCE_REQUIRED = 0b0000000100000000;
-        const SERVER_SECURITY = 0b0000000010000000;
-        const DACL_TRUSTED = 0b0000000001000000;
-        const SACL_DEFAULT = 0b0000000000100000;
-        const SACL_PRESENT = 0b0000000000010000;
-        const DACL_DEFAULT = 0b0000000000001000;
-        const DACL_PRESENT = 0b0000000000000100;
-        const GROUP_DEFAULT = 0b0000000000000010;
-        const OWNER_DEFAULT = 0b0000000000000001;
+/// Get the attribute or class name of one GUID from OBJECTTYPE_GUID_HASHMAP, the GUID itself if unknown.
+fn describe_guid(guid: u128) -> String {
+    let guid = bin_to_string(&guid.to_be_bytes().to_vec()).to_lowercase();
+    match OBJECTTYPE_GUID_HASHMAP.iter().find(|(_, value)| **value == guid) {
+        Some((name, _)) => format!("{} ({})", guid, name),
+        None => guid,
     }
 }
 
-fn has_control(secdesc_control: u16, flag: SecurityDescriptorFlags) -> bool {
-    let flags = SecurityDescriptorFlags::from_bits(secdesc_control).unwrap();
-    flags.contains(flag)
+/// Save the decision made for one ACE of the object explained.
+fn add_explanation(
+    valjson: &serde_json::value::Value,
+    index: usize,
+    ace: &Ace,
+    domain: &String,
+    result: &Result<Vec<serde_json::value::Value>, String>,
+) {
+    let ace_type = match ace.ace_type {
+        ACCESS_ALLOWED_ACE_TYPE => "ACCESS_ALLOWED",
+        ACCESS_DENIED_ACE_TYPE => "ACCESS_DENIED",
+        0x02 => "SYSTEM_AUDIT",
+        ACCESS_ALLOWED_OBJECT_ACE_TYPE => "ACCESS_ALLOWED_OBJECT",
+        ACCESS_DENIED_OBJECT_ACE_TYPE => "ACCESS_DENIED_OBJECT",
+        0x07 => "SYSTEM_AUDIT_OBJECT",
+        _ => "UNKNOWN",
+    };
+    let mut flags: Vec<&str> = Vec::new();
+    for (flag, name) in [(OBJECT_INHERIT_ACE, "OBJECT_INHERIT"), (CONTAINER_INHERIT_ACE, "CONTAINER_INHERIT"), (NO_PROPAGATE_INHERIT_ACE, "NO_PROPAGATE_INHERIT"), (INHERIT_ONLY_ACE, "INHERIT_ONLY"), (INHERITED_ACE, "INHERITED")] {
+        if ace.ace_flags & flag == flag {
+            flags.push(name);
+        }
+    }
+    let mut description = format!("{} [{}]", ace_type, flags.join("|"));
+    if let Some(mask) = AceFormat::get_mask(ace.data.to_owned()) {
+        description.push_str(&format!(" mask {:#010x} {}", mask, AccessMask::new(mask).describe()));
+    }
+    if let Some(object_type) = AceFormat::get_object_type(ace.data.to_owned()) {
+        description.push_str(&format!(" object type {}", describe_guid(object_type)));
+    }
+    if let Some(inherited_object_type) = AceFormat::get_inherited_object_type(ace.data.to_owned()) {
+        description.push_str(&format!(" inherited object type {}", describe_guid(inherited_object_type)));
+    }
+    let explanation = AceExplanation {
+        object: valjson["Properties"]["name"].as_str().unwrap_or("").to_string(),
+        index: Some(index),
+        description: description,
+        trustee: AceFormat::get_sid(ace.data.to_owned()).map(|sid| sid_maker(sid, domain)).unwrap_or_default(),
+        result: result.as_ref()
+            .map(|relations| relations.iter().map(|relation| relation["RightName"].as_str().unwrap_or("").to_string()).collect())
+            .map_err(|reason| reason.to_owned()),
+    };
+    if let Ok(mut explained) = EXPLAINED_ACES.lock() {
+        explained.push(explanation);
+    }
+}
+
+/// Save the Owns edge made from the owner of the object explained.
+fn add_owner_explanation(valjson: &serde_json::value::Value, osid: &String) {
+    if let Ok(mut explained) = EXPLAINED_ACES.lock() {
+        explained.push(AceExplanation {
+            object: valjson["Properties"]["name"].as_str().unwrap_or("").to_string(),
+            index: None,
+            description: "owner".to_string(),
+            trustee: osid.to_owned(),
+            result: Ok(vec!["Owns".to_string()]),
+        });
+    }
+}
+
+/// Print the ACEs of the object explained with --explain-aces, trustees resolved with the names of all the objects collected.
+pub fn log_explained_aces(objects: &[&Vec<serde_json::value::Value>]) {
+    let target = EXPLAIN_TARGET.lock().ok().and_then(|target| target.to_owned()).unwrap_or_default();
+    let explained = match EXPLAINED_ACES.lock() {
+        Ok(explained) => explained,
+        Err(_) => return,
+    };
+    if explained.is_empty() {
+        warn!("No security descriptor parsed for {}, check the objectid or distinguished name given to --explain-aces", target);
+        return
+    }
+    let mut names: HashMap<&str, &str> = HashMap::new();
+    for vec_objects in objects {
+        for object in vec_objects.iter() {
+            if let (Some(sid), Some(name)) = (object["ObjectIdentifier"].as_str(), object["Properties"]["name"].as_str()) {
+                names.insert(sid, name);
+            }
+        }
+    }
+    info!("ACEs of {} ({} entries):", target.bold(), explained.len());
+    for explanation in explained.iter() {
+        let trustee = match names.get(explanation.trustee.as_str()) {
+            Some(name) => format!("{} ({})", explanation.trustee, name),
+            None => explanation.trustee.to_owned(),
+        };
+        let result = match &explanation.result {
+            Ok(edges) if edges.is_empty() => "no edge for this mask and object type".to_string(),
+            Ok(edges) => format!("{}", edges.join(", ").green().bold()),
+            Err(reason) => format!("{} {}", "skipped,".yellow(), reason),
+        };
+        match explanation.index {
+            Some(index) => info!("{} ACE #{}: {} trustee {} => {}", explanation.object, index, explanation.description, trustee, result),
+            None => info!("{} {}: trustee {} => {}", explanation.object, explanation.description, trustee, result),
+        }
+    }
 }
 
 // OBJECTTYPE_GUID_HASHMAP with all know guid
@@ -7681,4 +7944,205 @@ lazy_static! {
         );
         map
     };
-}
\ No newline at end of file
+}
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_ntsecuritydescriptor_malformed() {
+    let domain = "ESSOS.LOCAL".to_string();
+    let (attrs, bin_attrs) = (HashMap::new(), HashMap::new());
+    let mut valjson = serde_json::json!({"Properties": {"name": "JON@ESSOS.LOCAL"}});
+    // Truncated header
+    assert!(parse_ntsecuritydescriptor(&mut valjson, &vec![0x01, 0x00], "user".to_string(), &attrs, &bin_attrs, &domain).is_empty());
+    // Owner and DACL offsets out of the descriptor
+    let nt = vec![0x01, 0x00, 0x04, 0x8c, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00];
+    assert!(parse_ntsecuritydescriptor(&mut valjson, &nt, "user".to_string(), &attrs, &bin_attrs, &domain).is_empty());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_ntsecuritydescriptor_without_owner() {
+    let domain = "ESSOS.LOCAL".to_string();
+    let (attrs, bin_attrs) = (HashMap::new(), HashMap::new());
+    let mut valjson = serde_json::json!({"Properties": {"name": "JON@ESSOS.LOCAL"}});
+    // Self-relative with DACL present, offset_owner and offset_group 0, DACL at 20
+    let mut nt = vec![0x01, 0x00, 0x04, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00];
+    // ACL with one ACCESS_ALLOWED_ACE GenericAll for S-1-5-21-1-2-3-1104
+    nt.extend_from_slice(&[0x02, 0x00, 0x2c, 0x00, 0x01, 0x00, 0x00, 0x00]);
+    nt.extend_from_slice(&[0x00, 0x00, 0x24, 0x00, 0xff, 0x01, 0x0f, 0x00]);
+    nt.extend_from_slice(&[0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x50, 0x04, 0x00, 0x00]);
+    let relations = parse_ntsecuritydescriptor(&mut valjson, &nt, "user".to_string(), &attrs, &bin_attrs, &domain);
+    assert!(!relations.is_empty());
+    assert!(relations.iter().all(|relation| relation["RightName"] != "Owns"));
+    assert!(relations.iter().all(|relation| relation["PrincipalSID"] != ""));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_object_ace_rights() {
+    // S-1-5-21-1-2-3-1104
+    let sid: Vec<u8> = vec![0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x50, 0x04, 0x00, 0x00];
+    // ACCESS_ALLOWED_OBJECT_ACE with the mask and the ObjectType if any
+    let object_ace = |mask: u32, object_type: Option<[u8; 16]>| -> Ace {
+        let mut data = vec![0x05, 0x00, 0x00, 0x00];
+        data.extend_from_slice(&mask.to_le_bytes());
+        match object_type {
+            Some(object_type) => {
+                data.extend_from_slice(&1u32.to_le_bytes());
+                data.extend_from_slice(&object_type);
+            },
+            None => data.extend_from_slice(&0u32.to_le_bytes()),
+        }
+        data.extend_from_slice(&sid);
+        let size = data.len() as u16;
+        data[2..4].copy_from_slice(&size.to_le_bytes());
+        Ace::parse(&data).unwrap().1
+    };
+    let rights = |aces: Vec<Ace>, entry_type: &str| -> Vec<String> {
+        let mut relations = Vec::new();
+        let mut valjson = serde_json::json!({"Properties": {"name": "JON@ESSOS.LOCAL"}});
+        ace_maker(&mut valjson, &"ESSOS.LOCAL".to_string(), &mut relations, Some(&"S-1-5-18".to_string()), aces, &entry_type.to_string(), &HashMap::new(), &HashMap::new());
+        relations.iter()
+            .filter(|relation| relation["RightName"] != "Owns")
+            .map(|relation| relation["RightName"].as_str().unwrap().to_string())
+            .collect()
+    };
+    // User-Force-Change-Password 00299570-246d-11d0-a768-00aa006e0529
+    let force_change_password = [0x70, 0x95, 0x29, 0x00, 0x6d, 0x24, 0xd0, 0x11, 0xa7, 0x68, 0x00, 0xaa, 0x00, 0x6e, 0x05, 0x29];
+    // Certificate-AutoEnrollment a05b8cc2-17bc-4802-a710-e7c15ab866a2
+    let auto_enroll = [0xc2, 0x8c, 0x5b, 0xa0, 0xbc, 0x17, 0x02, 0x48, 0xa7, 0x10, 0xe7, 0xc1, 0x5a, 0xb8, 0x66, 0xa2];
+    // Service-Principal-Name f3a64788-5306-11d1-a9c5-0000f80367c1
+    let service_principal_name = [0x88, 0x47, 0xa6, 0xf3, 0x06, 0x53, 0xd1, 0x11, 0xa9, 0xc5, 0x00, 0x00, 0xf8, 0x03, 0x67, 0xc1];
+    // User-Account-Control bf967a68-0de6-11d0-a285-00aa003049e2
+    let user_account_control = [0x68, 0x7a, 0x96, 0xbf, 0xe6, 0x0d, 0xd0, 0x11, 0xa2, 0x85, 0x00, 0xaa, 0x00, 0x30, 0x49, 0xe2];
+
+    assert_eq!(rights(vec![object_ace(0x100, Some(force_change_password))], "user"), vec!["ForceChangePassword"]);
+    assert_eq!(rights(vec![object_ace(0x100, None)], "user"), vec!["AllExtendedRights"]);
+    assert_eq!(rights(vec![object_ace(0x100, None)], "domain"), vec!["AllExtendedRights"]);
+    assert_eq!(rights(vec![object_ace(0x100, None)], "template"), vec!["Enroll"]);
+    assert_eq!(rights(vec![object_ace(0x100, Some(auto_enroll))], "template"), vec!["AutoEnroll"]);
+
+    // ADS_RIGHT_DS_WRITE_PROP
+    assert_eq!(rights(vec![object_ace(0x20, Some(service_principal_name))], "computer"), vec!["WriteSPN"]);
+    assert_eq!(rights(vec![object_ace(0x20, Some(user_account_control))], "user"), vec!["WriteUserAccountControl"]);
+    assert_eq!(rights(vec![object_ace(0x20, None)], "user"), vec!["GenericWrite"]);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_inherited_object_class() {
+    // S-1-5-21-1-2-3-1104
+    let sid: Vec<u8> = vec![0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x50, 0x04, 0x00, 0x00];
+    // Inherited ACCESS_ALLOWED_OBJECT_ACE with full control for inetOrgPerson 4828cc14-1437-45bc-9b07-ad6f015e5f28 objects
+    let mut data = vec![0x05, INHERITED_ACE, 0x00, 0x00];
+    data.extend_from_slice(&0x000F01FFu32.to_le_bytes());
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&[0x14, 0xcc, 0x28, 0x48, 0x37, 0x14, 0xbc, 0x45, 0x9b, 0x07, 0xad, 0x6f, 0x01, 0x5e, 0x5f, 0x28]);
+    data.extend_from_slice(&sid);
+    let size = data.len() as u16;
+    data[2..4].copy_from_slice(&size.to_le_bytes());
+
+    let rights = |object_class: &[&str]| -> Vec<String> {
+        let mut relations = Vec::new();
+        let mut valjson = serde_json::json!({"Properties": {"name": "JON@ESSOS.LOCAL"}});
+        let attrs = HashMap::from([("objectClass".to_string(), object_class.iter().map(|class| class.to_string()).collect())]);
+        ace_maker(&mut valjson, &"ESSOS.LOCAL".to_string(), &mut relations, Some(&"S-1-5-18".to_string()), vec![Ace::parse(&data).unwrap().1], &"user".to_string(), &attrs, &HashMap::new());
+        relations.iter()
+            .filter(|relation| relation["RightName"] != "Owns")
+            .map(|relation| relation["RightName"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(rights(&["top", "person", "organizationalPerson", "user", "inetOrgPerson"]), vec!["GenericAll"]);
+    assert!(rights(&["top", "person", "organizationalPerson", "user"]).is_empty());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_explain_aces() {
+    // S-1-5-21-1-2-3-1104
+    let sid: Vec<u8> = vec![0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x50, 0x04, 0x00, 0x00];
+    // Service-Principal-Name f3a64788-5306-11d1-a9c5-0000f80367c1
+    let service_principal_name = [0x88, 0x47, 0xa6, 0xf3, 0x06, 0x53, 0xd1, 0x11, 0xa9, 0xc5, 0x00, 0x00, 0xf8, 0x03, 0x67, 0xc1];
+    // ACCESS_ALLOWED_OBJECT_ACE with ADS_RIGHT_DS_WRITE_PROP on servicePrincipalName
+    let object_ace = |ace_flags: u8| -> Ace {
+        let mut data = vec![0x05, ace_flags, 0x00, 0x00];
+        data.extend_from_slice(&0x20u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&service_principal_name);
+        data.extend_from_slice(&sid);
+        let size = data.len() as u16;
+        data[2..4].copy_from_slice(&size.to_le_bytes());
+        Ace::parse(&data).unwrap().1
+    };
+    let mut attrs = HashMap::new();
+    attrs.insert("distinguishedName".to_string(), vec!["CN=explained,CN=Computers,DC=ESSOS,DC=LOCAL".to_string()]);
+    let mut valjson = serde_json::json!({"Properties": {"name": "EXPLAINED.ESSOS.LOCAL"}});
+    set_explain_target(Some("cn=explained,cn=computers,dc=essos,dc=local".to_string()));
+    let mut relations = Vec::new();
+    ace_maker(&mut valjson, &"ESSOS.LOCAL".to_string(), &mut relations, Some(&"S-1-5-21-1-2-3-512".to_string()), vec![object_ace(0x00), object_ace(INHERIT_ONLY_ACE)], &"computer".to_string(), &attrs, &HashMap::new());
+    {
+        let explained = EXPLAINED_ACES.lock().unwrap();
+        assert_eq!(explained.len(), 3);
+        assert_eq!(explained[0].index, None);
+        assert_eq!(explained[0].result, Ok(vec!["Owns".to_string()]));
+        assert_eq!(explained[1].trustee, "S-1-5-21-1-2-3-1104");
+        assert_eq!(explained[1].result, Ok(vec!["WriteSPN".to_string()]));
+        assert!(explained[1].description.contains("service-principal-name"));
+        assert_eq!(explained[2].index, Some(1));
+        assert!(explained[2].result.as_ref().unwrap_err().starts_with("inherit-only"));
+    }
+    set_explain_target(None);
+    assert!(EXPLAINED_ACES.lock().unwrap().is_empty());
+    assert!(!is_explained(&attrs, &HashMap::new(), &"ESSOS.LOCAL".to_string()));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_ace_provenance() {
+    // ACCESS_ALLOWED_OBJECT_ACE inherited with ADS_RIGHT_DS_CONTROL_ACCESS on User-Force-Change-Password for S-1-5-21-1-2-3-1104
+    let mut data = vec![0x05, INHERITED_ACE, 0x00, 0x00];
+    data.extend_from_slice(&0x100u32.to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&[0x70, 0x95, 0x29, 0x00, 0x6d, 0x24, 0xd0, 0x11, 0xa7, 0x68, 0x00, 0xaa, 0x00, 0x6e, 0x05, 0x29]);
+    data.extend_from_slice(&[0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x15, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x50, 0x04, 0x00, 0x00]);
+    let size = data.len() as u16;
+    data[2..4].copy_from_slice(&size.to_le_bytes());
+    let ace = Ace::parse(&data).unwrap().1;
+
+    let provenance = ace_provenance(3, &ace);
+    assert_eq!(provenance["aceindex"], 3);
+    assert_eq!(provenance["acetype"], 5);
+    assert_eq!(provenance["mask"], "0x00000100");
+    assert_eq!(provenance["objecttype"], "00299570-246d-11d0-a768-00aa006e0529");
+    assert!(provenance["inheritedobjecttype"].is_null());
+    assert_eq!(provenance["inherited"], true);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_add_property_set_edges() {
+    let mut property_sets: HashMap<String, Vec<String>> = HashMap::new();
+    // Membership property set with the member attribute
+    property_sets.insert("bc0ac240-79a9-11d0-9020-00c04fc2d4cf".to_string(), vec![WRITE_MEMBER.to_string()]);
+    let write = |sid: &str, guid: &str| serde_json::json!({"PrincipalSID": sid, "ObjectType": guid, "IsInherited": false});
+    let mut vec_groups = vec![serde_json::json!({
+        "ObjectIdentifier": "S-1-5-21-1-2-3-1105",
+        "Aces": [{"RightName": "AddMember", "IsInherited": false, "PrincipalSID": "S-1-5-21-1-2-3-1106", "PrincipalType": ""}],
+        "PropertySetWrites": [
+            write("S-1-5-21-1-2-3-1104", "bc0ac240-79a9-11d0-9020-00c04fc2d4cf"),
+            // Already made from the ACE
+            write("S-1-5-21-1-2-3-1106", "bc0ac240-79a9-11d0-9020-00c04fc2d4cf"),
+            // Not a property set
+            write("S-1-5-21-1-2-3-1107", "f3a64788-5306-11d1-a9c5-0000f80367c1"),
+        ],
+    })];
+    assert_eq!(add_property_set_edges(&mut vec_groups, "group", &property_sets), 1);
+    assert!(vec_groups[0].get("PropertySetWrites").is_none());
+    let aces = vec_groups[0]["Aces"].as_array().unwrap();
+    assert_eq!(aces.len(), 2);
+    assert_eq!(aces[1]["RightName"], "AddMember");
+    assert_eq!(aces[1]["PrincipalSID"], "S-1-5-21-1-2-3-1104");
+    // No member attribute for users
+    let mut vec_users = vec![serde_json::json!({"Aces": [], "PropertySetWrites": [write("S-1-5-21-1-2-3-1104", "bc0ac240-79a9-11d0-9020-00c04fc2d4cf")]})];
+    assert_eq!(add_property_set_edges(&mut vec_users, "user", &property_sets), 0);
+    assert!(vec_users[0].get("PropertySetWrites").is_none());
+}
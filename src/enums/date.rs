@@ -1,6 +1,13 @@
 use chrono::{NaiveDateTime, Local};
+use std::sync::Mutex;
 //use log::trace;
 
+/// Maximum clock skew tolerated by Kerberos by default, in seconds.
+pub const MAX_CLOCK_SKEW: i64 = 5*60;
+
+/// Clock skew of the domain controller (DC time - local time) in seconds, None until RootDSE is read.
+static CLOCK_SKEW: Mutex<Option<i64>> = Mutex::new(None);
+
 /// Change date timestamp format to epoch format.
 pub fn convert_timestamp(timestamp: i64) -> i64
 {
@@ -37,4 +44,96 @@ pub fn return_current_fulldate() -> String
 {
     let now = Local::now();
     return now.format("%Y%m%d%H%M%S").to_string()
-}
\ No newline at end of file
+}
+
+/// Function to return current epoch of the domain controller,
+/// the local time corrected by the clock skew so ages of AD timestamps are accurate.
+pub fn return_current_epoch() -> i64
+{
+    let now = Local::now();
+    return now.timestamp() + get_clock_skew().unwrap_or(0)
+}
+
+/// Function to set the clock skew of the domain controller in seconds.
+pub fn set_clock_skew(skew: i64)
+{
+    if let Ok(mut clock_skew) = CLOCK_SKEW.lock() {
+        *clock_skew = Some(skew);
+    }
+}
+
+/// Function to get the clock skew of the domain controller in seconds, None if unknown.
+pub fn get_clock_skew() -> Option<i64>
+{
+    CLOCK_SKEW.lock().ok().and_then(|clock_skew| *clock_skew)
+}
+
+/// Function to compute the clock skew from the RootDSE currentTime (yyyyMMddHHmmss.0Z) and one local epoch.
+pub fn compute_clock_skew(current_time: &str, local_epoch: i64) -> Option<i64>
+{
+    let date = current_time.split('.').next()?;
+    let date = NaiveDateTime::parse_from_str(date, "%Y%m%d%H%M%S").ok()?;
+    Some(date.timestamp() - local_epoch)
+}
+
+/// Interval value of one attribute set to "never", like maxPwdAge for passwords which never expire.
+pub const INTERVAL_NEVER: i64 = i64::MIN;
+
+/// Function to convert one interval attribute (maxPwdAge, lockoutDuration...) to seconds.
+/// Intervals are stored as negative numbers of 100 nanoseconds, None for "never" or a positive value
+/// which isn't a valid interval.
+pub fn interval_to_seconds(interval: i64) -> Option<i64>
+{
+    if interval == INTERVAL_NEVER || interval > 0 {
+        return None
+    }
+    Some(-(interval / 10000000))
+}
+
+/// Function to convert one interval attribute to a friendly duration like "42 days" or "1 hour, 30 minutes".
+/// None for a positive value which isn't a valid interval.
+pub fn interval_to_string(interval: i64) -> Option<String>
+{
+    if interval == INTERVAL_NEVER {
+        return Some("Never".to_string())
+    }
+    let seconds = interval_to_seconds(interval)?;
+    if seconds == 0 {
+        return Some("None".to_string())
+    }
+    let mut parts: Vec<String> = Vec::new();
+    let mut remaining = seconds;
+    for (unit, length) in [("day", 86400), ("hour", 3600), ("minute", 60), ("second", 1)] {
+        let count = remaining / length;
+        remaining %= length;
+        if count > 0 {
+            parts.push(format!("{} {}{}", count, unit, if count > 1 { "s" } else { "" }));
+        }
+    }
+    Some(parts.join(", "))
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_compute_clock_skew() {
+    // 2026-10-16 12:00:00 UTC
+    assert_eq!(compute_clock_skew("20261016120000.0Z", 1792152000), Some(0));
+    assert_eq!(compute_clock_skew("20261016121000.0Z", 1792152000), Some(600));
+    assert_eq!(compute_clock_skew("20261016115500.0Z", 1792152000), Some(-300));
+    assert_eq!(compute_clock_skew("not a date", 1792152000), None);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_interval_to_string() {
+    // maxPwdAge 42 days
+    assert_eq!(interval_to_seconds(-36288000000000), Some(3628800));
+    assert_eq!(interval_to_string(-36288000000000), Some("42 days".to_string()));
+    // lockoutDuration 30 minutes
+    assert_eq!(interval_to_string(-18000000000), Some("30 minutes".to_string()));
+    assert_eq!(interval_to_string(-54000000000), Some("1 hour, 30 minutes".to_string()));
+    assert_eq!(interval_to_string(0), Some("None".to_string()));
+    assert_eq!(interval_to_string(INTERVAL_NEVER), Some("Never".to_string()));
+    assert_eq!(interval_to_seconds(36288000000000), None);
+    assert_eq!(interval_to_string(36288000000000), None);
+}
@@ -0,0 +1,114 @@
+//! Distinguished names helpers
+//!
+//! RDN values can contain escaped characters (`CN=Doe\, John`, `OU=\#Admins`, `CN=Caf\C3\A9`)
+//! so a DN can't be split on every comma.
+//! <https://learn.microsoft.com/en-us/previous-versions/windows/desktop/ldap/distinguished-names>
+use log::warn;
+
+/// Function to split one DN in its RDNs, escaped commas are kept in the RDN.
+/// `CN=Doe\, John,OU=Users,DC=ESSOS,DC=LOCAL` => [`CN=Doe\, John`, `OU=Users`, `DC=ESSOS`, `DC=LOCAL`]
+pub fn split_dn(dn: &str) -> Vec<&str> {
+   let mut rdns: Vec<&str> = Vec::new();
+   let mut start = 0;
+   let mut escaped = false;
+   for (i, c) in dn.char_indices() {
+      match c {
+         _ if escaped => escaped = false,
+         '\\' => escaped = true,
+         ',' => {
+            rdns.push(dn[start..i].trim());
+            start = i + 1;
+         },
+         _ => {},
+      }
+   }
+   if start < dn.len() {
+      rdns.push(dn[start..].trim());
+   }
+   rdns
+}
+
+/// Function to get the parent DN, None for a DN with only one RDN.
+/// `CN=Doe\, John,OU=Users,DC=ESSOS,DC=LOCAL` => `OU=Users,DC=ESSOS,DC=LOCAL`
+pub fn get_parent_dn(dn: &str) -> Option<String> {
+   let rdns = split_dn(dn);
+   if rdns.len() < 2 {
+      return None
+   }
+   Some(rdns[1..].join(","))
+}
+
+/// Function to get the unescaped value of the first RDN.
+/// `CN=Doe\, John,OU=Users,DC=ESSOS,DC=LOCAL` => `Doe, John`
+pub fn get_rdn_value(dn: &str) -> String {
+   let rdn = split_dn(dn).first().map(|rdn| rdn.to_string()).unwrap_or_default();
+   match rdn.split_once('=') {
+      Some((_, value)) => unescape_rdn_value(value),
+      None => unescape_rdn_value(&rdn),
+   }
+}
+
+/// Function to get the domain name from the DC components of one DN.
+/// `CN=Users,DC=CHILD,DC=ESSOS,DC=LOCAL` => `CHILD.ESSOS.LOCAL`
+pub fn get_domain_from_dn(dn: &str) -> String {
+   split_dn(dn).iter()
+      .filter(|rdn| rdn.len() > 3 && rdn[..3].eq_ignore_ascii_case("DC="))
+      .map(|rdn| unescape_rdn_value(&rdn[3..]))
+      .collect::<Vec<String>>()
+      .join(".")
+}
+
+/// Function to check if one DN is a conflict object, renamed by replication when the same RDN was created on two
+/// domain controllers: `CN=John\0ACNF:<objectGUID>,CN=Users,DC=ESSOS,DC=LOCAL`. The parent can be the conflict object too.
+pub fn is_conflict_dn(dn: &str) -> bool {
+   split_dn(dn).iter().any(|rdn| rdn.to_uppercase().contains("\\0ACNF:"))
+}
+
+/// Function to unescape one RDN value: `\,` `\#` `\+` `\\`... and hex pairs like `\C3\A9` for UTF-8 bytes.
+/// Invalid UTF-8 sequences are replaced by U+FFFD with a warning.
+pub fn unescape_rdn_value(value: &str) -> String {
+   let bytes = value.as_bytes();
+   let mut unescaped: Vec<u8> = Vec::new();
+   let mut i = 0;
+   while i < bytes.len() {
+      if bytes[i] == b'\\' && i + 1 < bytes.len() {
+         let hex = if i + 2 < bytes.len() { std::str::from_utf8(&bytes[i + 1..i + 3]).ok() } else { None };
+         match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) => {
+               unescaped.push(byte);
+               i += 3;
+            },
+            None => {
+               unescaped.push(bytes[i + 1]);
+               i += 2;
+            },
+         }
+         continue
+      }
+      unescaped.push(bytes[i]);
+      i += 1;
+   }
+   match String::from_utf8(unescaped) {
+      Ok(unescaped) => unescaped,
+      Err(err) => {
+         warn!("Invalid UTF-8 in RDN value {}, invalid bytes replaced", value);
+         String::from_utf8_lossy(err.as_bytes()).to_string()
+      },
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_split_dn() {
+   let dn = r"CN=Doe\, John,OU=\#Admins,OU=Société,DC=ESSOS,DC=LOCAL";
+   assert_eq!(split_dn(dn), vec![r"CN=Doe\, John", r"OU=\#Admins", "OU=Société", "DC=ESSOS", "DC=LOCAL"]);
+   assert_eq!(get_parent_dn(dn).unwrap(), r"OU=\#Admins,OU=Société,DC=ESSOS,DC=LOCAL");
+   assert_eq!(get_parent_dn("DC=LOCAL"), None);
+   assert_eq!(get_rdn_value(dn), "Doe, John");
+   assert_eq!(get_rdn_value(r"OU=Caf\C3\A9,DC=ESSOS,DC=LOCAL"), "Café");
+   assert_eq!(get_rdn_value(r"CN=Back\\slash\,,DC=ESSOS,DC=LOCAL"), r"Back\slash,");
+   assert_eq!(get_domain_from_dn("CN=Users,DC=CHILD,DC=ESSOS,DC=LOCAL"), "CHILD.ESSOS.LOCAL");
+   assert!(is_conflict_dn(r"CN=John\0ACNF:8a4e9c3b-1f2d-4c5e-9b7a-0d1e2f3a4b5c,CN=Users,DC=ESSOS,DC=LOCAL"));
+   assert!(is_conflict_dn(r"CN=John,OU=Admins\0acnf:8a4e9c3b-1f2d-4c5e-9b7a-0d1e2f3a4b5c,DC=ESSOS,DC=LOCAL"));
+   assert!(!is_conflict_dn(r"CN=CNF\, John,CN=Users,DC=ESSOS,DC=LOCAL"));
+}
@@ -0,0 +1,90 @@
+//! Kerberos encryption types
+//!
+//! Decoder for the `msDS-SupportedEncryptionTypes` attribute of users, computers and trusts.
+//! <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-kile/6cfc7b50-11ed-4b4d-846d-6f08f0812919>
+use bitflags::bitflags;
+
+bitflags! {
+    /// Flags of "msDS-SupportedEncryptionTypes".
+    pub struct SupportedEncryptionTypes: u32 {
+        const DES_CBC_CRC = 0x00000001;
+        const DES_CBC_MD5 = 0x00000002;
+        const RC4_HMAC_MD5 = 0x00000004;
+        const AES128_CTS_HMAC_SHA1_96 = 0x00000008;
+        const AES256_CTS_HMAC_SHA1_96 = 0x00000010;
+        const AES256_CTS_HMAC_SHA1_96_SK = 0x00000020;
+        const FAST_SUPPORTED = 0x00010000;
+        const COMPOUND_IDENTITY_SUPPORTED = 0x00020000;
+        const CLAIMS_SUPPORTED = 0x00040000;
+        const RESOURCE_SID_COMPRESSION_DISABLED = 0x00080000;
+    }
+}
+
+const ENCRYPTION_TYPE_NAMES: [(SupportedEncryptionTypes, &str); 10] = [
+    (SupportedEncryptionTypes::DES_CBC_CRC, "DES-CBC-CRC"),
+    (SupportedEncryptionTypes::DES_CBC_MD5, "DES-CBC-MD5"),
+    (SupportedEncryptionTypes::RC4_HMAC_MD5, "RC4-HMAC-MD5"),
+    (SupportedEncryptionTypes::AES128_CTS_HMAC_SHA1_96, "AES128-CTS-HMAC-SHA1-96"),
+    (SupportedEncryptionTypes::AES256_CTS_HMAC_SHA1_96, "AES256-CTS-HMAC-SHA1-96"),
+    (SupportedEncryptionTypes::AES256_CTS_HMAC_SHA1_96_SK, "AES256-CTS-HMAC-SHA1-96-SK"),
+    (SupportedEncryptionTypes::FAST_SUPPORTED, "FAST-Supported"),
+    (SupportedEncryptionTypes::COMPOUND_IDENTITY_SUPPORTED, "Compound-Identity-Supported"),
+    (SupportedEncryptionTypes::CLAIMS_SUPPORTED, "Claims-Supported"),
+    (SupportedEncryptionTypes::RESOURCE_SID_COMPRESSION_DISABLED, "Resource-SID-Compression-Disabled"),
+];
+
+impl SupportedEncryptionTypes {
+    /// Function to get the flags from the attribute value, unknown bits are ignored.
+    pub fn parse(value: &str) -> Self
+    {
+        SupportedEncryptionTypes::from_bits_truncate(value.trim().parse::<i64>().unwrap_or(0) as u32)
+    }
+
+    /// Function to get the readable names of the flags.
+    pub fn names(&self) -> Vec<String>
+    {
+        ENCRYPTION_TYPE_NAMES.iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| name.to_string())
+            .collect()
+    }
+
+    /// No encryption type set, the KDC uses the domain default (RC4 allowed).
+    pub fn is_default(&self) -> bool
+    {
+        !self.intersects(Self::DES_CBC_CRC | Self::DES_CBC_MD5 | Self::RC4_HMAC_MD5 | Self::AES128_CTS_HMAC_SHA1_96 | Self::AES256_CTS_HMAC_SHA1_96)
+    }
+
+    pub fn has_des(&self) -> bool
+    {
+        self.intersects(Self::DES_CBC_CRC | Self::DES_CBC_MD5)
+    }
+
+    pub fn has_rc4(&self) -> bool
+    {
+        self.contains(Self::RC4_HMAC_MD5)
+    }
+
+    pub fn has_aes(&self) -> bool
+    {
+        self.intersects(Self::AES128_CTS_HMAC_SHA1_96 | Self::AES256_CTS_HMAC_SHA1_96)
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_supported_encryption_types() {
+    let rc4_only = SupportedEncryptionTypes::parse("4");
+    assert!(rc4_only.has_rc4() && !rc4_only.has_aes() && !rc4_only.has_des());
+    assert_eq!(rc4_only.names(), vec!["RC4-HMAC-MD5"]);
+
+    let legacy = SupportedEncryptionTypes::parse("31");
+    assert!(legacy.has_des() && legacy.has_rc4() && legacy.has_aes());
+    assert_eq!(legacy.names().len(), 5);
+
+    let fast_only = SupportedEncryptionTypes::parse("65536");
+    assert!(fast_only.is_default());
+    assert_eq!(fast_only.names(), vec!["FAST-Supported"]);
+    assert!(SupportedEncryptionTypes::parse("").is_default());
+    assert!(!SupportedEncryptionTypes::parse("24").is_default());
+}
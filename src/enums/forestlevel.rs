@@ -2,6 +2,7 @@
 pub fn get_forest_level(level: String) -> String
 {
     match level.as_str() {
+        "10" => { return "2025".to_string(); },
         "7" => { return "2016".to_string(); },
         "6" => { return "2012 R2".to_string(); },
         "5" => { return "2012".to_string(); },
@@ -12,4 +13,23 @@ pub fn get_forest_level(level: String) -> String
         "0" => { return "2000 Mixed/Native".to_string(); },
         _ => { return "Unknown".to_string(); },
     }
+}
+
+/// Get the Windows Server version from the schema "objectVersion" LDAP attribut.
+/// <https://learn.microsoft.com/en-us/windows-server/identity/ad-ds/deploy/find-active-directory-schema>
+pub fn get_schema_version(version: String) -> String
+{
+    match version.as_str() {
+        "91" => { return "2025".to_string(); },
+        "88" => { return "2019/2022".to_string(); },
+        "87" => { return "2016".to_string(); },
+        "69" => { return "2012 R2".to_string(); },
+        "56" => { return "2012".to_string(); },
+        "47" => { return "2008 R2".to_string(); },
+        "44" => { return "2008".to_string(); },
+        "31" => { return "2003 R2".to_string(); },
+        "30" => { return "2003".to_string(); },
+        "13" => { return "2000".to_string(); },
+        _ => { return "Unknown".to_string(); },
+    }
 }
\ No newline at end of file
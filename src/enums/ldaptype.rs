@@ -1,5 +1,6 @@
 use ldap3::SearchEntry;
 use std::collections::HashMap;
+use crate::enums::dn::get_rdn_value;
 //use log::trace;
 
 /// Enum to get ldap object type.
@@ -12,13 +13,42 @@ pub enum Type {
     Gpo,
     ForeignSecurityPrincipal,
     Container,
+    Contact,
     Trust,
     AdcsAuthority,
     AdcsTemplate,
+    Site,
+    Subnet,
+    SiteLink,
+    Schema,
+    Partitions,
+    CrossRef,
+    ServiceConnectionPoint,
+    PrintQueue,
+    AuthNPolicySilo,
+    AuthNPolicy,
+    ShadowPrincipal,
+    ClaimType,
+    Dfs,
+    AttributeSchema,
     Unknown
 }
 
-/// Get object type, like ("user","group","computer","ou", "container", "gpo", "domain" "trust").
+/// sAMAccountType of the security principals.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/e742be45-665d-4576-b872-0bc99d1e1fbe>
+pub const SAM_GROUP_OBJECT: i64 = 0x10000000;
+pub const SAM_NON_SECURITY_GROUP_OBJECT: i64 = 0x10000001;
+pub const SAM_ALIAS_OBJECT: i64 = 0x20000000;
+pub const SAM_NON_SECURITY_ALIAS_OBJECT: i64 = 0x20000001;
+pub const SAM_USER_OBJECT: i64 = 0x30000000;
+pub const SAM_MACHINE_ACCOUNT: i64 = 0x30000001;
+pub const SAM_TRUST_ACCOUNT: i64 = 0x30000002;
+pub const SAM_APP_BASIC_GROUP: i64 = 0x40000000;
+pub const SAM_APP_QUERY_GROUP: i64 = 0x40000001;
+
+/// Get object type, like ("user","group","computer","ou", "container", "gpo", "domain" "trust", "site", "subnet").
+/// Security principals are classified by their sAMAccountType, other objects by their objectCategory,
+/// and objectClass is the fallback when none of them is returned.
 pub fn get_type(result: SearchEntry) -> std::result::Result<Type, Type>
 {
     let result_attrs: HashMap<String, Vec<String>>;
@@ -26,69 +56,238 @@ pub fn get_type(result: SearchEntry) -> std::result::Result<Type, Type>
 
     //trace!("{:?}",&result_attrs);
 
-    // For all entries I checked if is an user,group,computer,ou,domain
-    for (key, value) in &result_attrs 
-    {
-        // Type is user
-        if key == "objectClass" && value.contains(&String::from("person")) && value.contains(&String::from("user")) && !value.contains(&String::from("computer")) && !value.contains(&String::from("group"))
-        {
-            return Ok(Type::User)
-        }
-        // Type is user if is service-account
-        if key == "objectClass" && value.contains(&String::from("msDS-GroupManagedServiceAccount"))
-        {
-            return Ok(Type::User)
-        }
-        // Type is group
-        if key == "objectClass" && value.contains(&String::from("group"))
-        {
-            return Ok(Type::Group)
-        }
-        // Type is computer
-        if key == "objectClass" && value.contains(&String::from("computer"))
-        {
-            return Ok(Type::Computer)
-        }
-        // Type is ou
-        if key == "objectClass" && value.contains(&String::from("organizationalUnit"))
-        {
-            return Ok(Type::Ou)
-        }
-        // Type is domain
-        if key == "objectClass" && value.contains(&String::from("domain"))
-        {
-            return Ok(Type::Domain)     
-        }
-        // Type is Gpo
-        if key == "objectClass" && value.contains(&String::from("groupPolicyContainer"))
-        {
-            return Ok(Type::Gpo)
-        }
-        // Type is foreignSecurityPrincipal
-        if key == "objectClass" && value.contains(&String::from("top")) && value.contains(&String::from("foreignSecurityPrincipal"))
-        {
-            return Ok(Type::ForeignSecurityPrincipal)
-        }
-        // Type is Container
-        if key == "objectClass" && (value.contains(&String::from("top")) && value.contains(&String::from("container"))) && !value.contains(&String::from("groupPolicyContainer"))
-        {
-            return Ok(Type::Container)
-        }
-        // Type is Trust domain
-        if key == "objectClass" && value.contains(&String::from("trustedDomain"))
-        {
-            return Ok(Type::Trust)
-        }
-        // Type is ADCS Certificate Authority
-        if key == "objectClass" && value.contains(&String::from("pKIEnrollmentService"))
-        {
-            return Ok(Type::AdcsAuthority)
-        }
-        // Type is ADCS Certificate Template
-        if key == "objectClass" && value.contains(&String::from("pKICertificateTemplate"))
-        {
-            return Ok(Type::AdcsTemplate)
+    let object_class = result_attrs.get("objectClass").cloned().unwrap_or_default();
+    let sam_account_type = result_attrs.get("sAMAccountType")
+        .and_then(|value| value.first())
+        .and_then(|value| value.parse::<i64>().ok());
+    if let Some(atype) = sam_account_type.and_then(|value| get_type_from_sam_account_type(value, &object_class)) {
+        return Ok(atype)
+    }
+    let object_category = result_attrs.get("objectCategory").and_then(|value| value.first());
+    if let Some(atype) = object_category.and_then(|value| get_type_from_object_category(value, &object_class)) {
+        return Ok(atype)
+    }
+    get_type_from_object_class(&object_class)
+}
+
+/// Get the type of one security principal from its sAMAccountType.
+/// Managed service accounts are machine accounts but BloodHound expects them as users, like trust accounts.
+fn get_type_from_sam_account_type(sam_account_type: i64, object_class: &Vec<String>) -> Option<Type>
+{
+    match sam_account_type {
+        SAM_GROUP_OBJECT | SAM_NON_SECURITY_GROUP_OBJECT | SAM_ALIAS_OBJECT | SAM_NON_SECURITY_ALIAS_OBJECT
+        | SAM_APP_BASIC_GROUP | SAM_APP_QUERY_GROUP => Some(Type::Group),
+        SAM_USER_OBJECT | SAM_TRUST_ACCOUNT => Some(Type::User),
+        SAM_MACHINE_ACCOUNT => {
+            if object_class.contains(&String::from("msDS-GroupManagedServiceAccount"))
+            || object_class.contains(&String::from("msDS-ManagedServiceAccount"))
+            {
+                Some(Type::User)
+            } else {
+                Some(Type::Computer)
+            }
         }
+        _ => None,
+    }
+}
+
+/// Get the type of one object from the class in its objectCategory, `CN=Organizational-Unit,CN=Schema,CN=Configuration,...`.
+/// Contacts share the Person category with users and are only users with the `user` class.
+fn get_type_from_object_category(object_category: &str, object_class: &Vec<String>) -> Option<Type>
+{
+    match get_rdn_value(object_category).to_lowercase().as_str() {
+        "person" if object_class.contains(&String::from("user")) => Some(Type::User),
+        "person" if object_class.contains(&String::from("contact")) => Some(Type::Contact),
+        "ms-ds-managed-service-account" | "ms-ds-group-managed-service-account" => Some(Type::User),
+        "computer" => Some(Type::Computer),
+        "group" => Some(Type::Group),
+        "organizational-unit" => Some(Type::Ou),
+        "domain-dns" => Some(Type::Domain),
+        "group-policy-container" => Some(Type::Gpo),
+        "foreign-security-principal" => Some(Type::ForeignSecurityPrincipal),
+        "container" => Some(Type::Container),
+        "trusted-domain" => Some(Type::Trust),
+        "pki-enrollment-service" => Some(Type::AdcsAuthority),
+        "pki-certificate-template" => Some(Type::AdcsTemplate),
+        "site" => Some(Type::Site),
+        "subnet" => Some(Type::Subnet),
+        "site-link" => Some(Type::SiteLink),
+        "dmd" => Some(Type::Schema),
+        "cross-ref-container" => Some(Type::Partitions),
+        "cross-ref" => Some(Type::CrossRef),
+        "service-connection-point" => Some(Type::ServiceConnectionPoint),
+        "print-queue" => Some(Type::PrintQueue),
+        "ms-ds-authn-policy-silo" => Some(Type::AuthNPolicySilo),
+        "ms-ds-authn-policy" => Some(Type::AuthNPolicy),
+        "ms-ds-shadow-principal" => Some(Type::ShadowPrincipal),
+        "ms-ds-claim-type" => Some(Type::ClaimType),
+        "ft-dfs" | "ms-dfs-namespace-v2" | "ms-dfs-link-v2" => Some(Type::Dfs),
+        "attribute-schema" => Some(Type::AttributeSchema),
+        _ => None,
+    }
+}
+
+/// Get the type of one object from its objectClass values.
+fn get_type_from_object_class(value: &Vec<String>) -> std::result::Result<Type, Type>
+{
+    // Type is user
+    if value.contains(&String::from("person")) && value.contains(&String::from("user")) && !value.contains(&String::from("computer")) && !value.contains(&String::from("group"))
+    {
+        return Ok(Type::User)
+    }
+    // Type is user if is service-account, standalone or group managed
+    if value.contains(&String::from("msDS-GroupManagedServiceAccount")) || value.contains(&String::from("msDS-ManagedServiceAccount"))
+    {
+        return Ok(Type::User)
+    }
+    // Type is group
+    if value.contains(&String::from("group"))
+    {
+        return Ok(Type::Group)
+    }
+    // Type is computer
+    if value.contains(&String::from("computer"))
+    {
+        return Ok(Type::Computer)
+    }
+    // Type is ou
+    if value.contains(&String::from("organizationalUnit"))
+    {
+        return Ok(Type::Ou)
+    }
+    // Type is domain
+    if value.contains(&String::from("domain"))
+    {
+        return Ok(Type::Domain)     
+    }
+    // Type is Gpo
+    if value.contains(&String::from("groupPolicyContainer"))
+    {
+        return Ok(Type::Gpo)
+    }
+    // Type is foreignSecurityPrincipal
+    if value.contains(&String::from("top")) && value.contains(&String::from("foreignSecurityPrincipal"))
+    {
+        return Ok(Type::ForeignSecurityPrincipal)
+    }
+    // Type is Contact, person without account synced from HR or mail systems
+    if value.contains(&String::from("contact"))
+    {
+        return Ok(Type::Contact)
+    }
+    // Type is Container
+    if (value.contains(&String::from("top")) && value.contains(&String::from("container"))) && !value.contains(&String::from("groupPolicyContainer"))
+    {
+        return Ok(Type::Container)
+    }
+    // Type is Trust domain
+    if value.contains(&String::from("trustedDomain"))
+    {
+        return Ok(Type::Trust)
+    }
+    // Type is ADCS Certificate Authority
+    if value.contains(&String::from("pKIEnrollmentService"))
+    {
+        return Ok(Type::AdcsAuthority)
+    }
+    // Type is ADCS Certificate Template
+    if value.contains(&String::from("pKICertificateTemplate"))
+    {
+        return Ok(Type::AdcsTemplate)
+    }
+    // Type is Site
+    if value.contains(&String::from("site"))
+    {
+        return Ok(Type::Site)
+    }
+    // Type is Subnet
+    if value.contains(&String::from("subnet"))
+    {
+        return Ok(Type::Subnet)
+    }
+    // Type is Site Link
+    if value.contains(&String::from("siteLink"))
+    {
+        return Ok(Type::SiteLink)
+    }
+    // Type is Schema naming context head
+    if value.contains(&String::from("dMD"))
+    {
+        return Ok(Type::Schema)
+    }
+    // Type is Partitions container (forest functional level)
+    if value.contains(&String::from("crossRefContainer"))
+    {
+        return Ok(Type::Partitions)
+    }
+    // Type is Cross-Reference of one naming context (NetBIOS and DNS names of domains)
+    if value.contains(&String::from("crossRef"))
+    {
+        return Ok(Type::CrossRef)
+    }
+    // Type is Service Connection Point
+    if value.contains(&String::from("serviceConnectionPoint"))
+    {
+        return Ok(Type::ServiceConnectionPoint)
+    }
+    // Type is Printer published in the directory
+    if value.contains(&String::from("printQueue"))
+    {
+        return Ok(Type::PrintQueue)
+    }
+    // Type is Authentication Policy Silo
+    if value.contains(&String::from("msDS-AuthNPolicySilo"))
+    {
+        return Ok(Type::AuthNPolicySilo)
+    }
+    // Type is Authentication Policy
+    if value.contains(&String::from("msDS-AuthNPolicy"))
+    {
+        return Ok(Type::AuthNPolicy)
+    }
+    // Type is Shadow Principal of a bastion forest
+    if value.contains(&String::from("msDS-ShadowPrincipal"))
+    {
+        return Ok(Type::ShadowPrincipal)
+    }
+    // Type is Claim Type
+    if value.contains(&String::from("msDS-ClaimType"))
+    {
+        return Ok(Type::ClaimType)
+    }
+    // Type is DFS namespace or DFS link
+    if value.contains(&String::from("fTDfs")) || value.contains(&String::from("msDFS-Namespacev2")) || value.contains(&String::from("msDFS-Linkv2"))
+    {
+        return Ok(Type::Dfs)
+    }
+    // Type is attribute of the schema in a property set
+    if value.contains(&String::from("attributeSchema"))
+    {
+        return Ok(Type::AttributeSchema)
     }
     return Err(Type::Unknown)
-}
\ No newline at end of file
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_type() {
+    let entry = |attrs: Vec<(&str, Vec<&str>)>| SearchEntry {
+        dn: "CN=TEST,DC=ESSOS,DC=LOCAL".to_string(),
+        attrs: attrs.into_iter().map(|(key, values)| (key.to_string(), values.into_iter().map(|value| value.to_string()).collect())).collect(),
+        bin_attrs: HashMap::new(),
+    };
+    // inetOrgPerson user
+    assert!(matches!(get_type(entry(vec![("objectClass", vec!["top", "person", "organizationalPerson", "user", "inetOrgPerson"]), ("sAMAccountType", vec!["805306368"])])), Ok(Type::User)));
+    // Standalone managed service account is a machine account but a user for BloodHound
+    assert!(matches!(get_type(entry(vec![("objectClass", vec!["top", "person", "organizationalPerson", "user", "computer", "msDS-ManagedServiceAccount"]), ("sAMAccountType", vec!["805306369"])])), Ok(Type::User)));
+    assert!(matches!(get_type(entry(vec![("objectClass", vec!["top", "person", "organizationalPerson", "user", "computer"]), ("sAMAccountType", vec!["805306369"])])), Ok(Type::Computer)));
+    // Trust account
+    assert!(matches!(get_type(entry(vec![("objectClass", vec!["top", "person", "organizationalPerson", "user"]), ("sAMAccountType", vec!["805306370"])])), Ok(Type::User)));
+    // Built-in alias
+    assert!(matches!(get_type(entry(vec![("objectClass", vec!["top", "group"]), ("sAMAccountType", vec!["536870912"])])), Ok(Type::Group)));
+    // objectCategory without sAMAccountType, contacts are persons without the user class
+    assert!(matches!(get_type(entry(vec![("objectClass", vec!["top", "organizationalUnit"]), ("objectCategory", vec!["CN=Organizational-Unit,CN=Schema,CN=Configuration,DC=ESSOS,DC=LOCAL"])])), Ok(Type::Ou)));
+    assert!(matches!(get_type(entry(vec![("objectClass", vec!["top", "person", "organizationalPerson", "contact"]), ("objectCategory", vec!["CN=Person,CN=Schema,CN=Configuration,DC=ESSOS,DC=LOCAL"])])), Ok(Type::Contact)));
+    // objectClass fallback
+    assert!(matches!(get_type(entry(vec![("objectClass", vec!["top", "groupPolicyContainer"])])), Ok(Type::Gpo)));
+}
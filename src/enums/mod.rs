@@ -19,6 +19,7 @@ pub use gplink::*;
 pub mod uacflags;
 pub mod ldaptype;
 pub mod date;
+pub mod dn;
 pub mod sid;
 pub mod forestlevel;
 pub mod acl;
@@ -26,4 +27,8 @@ pub mod secdesc;
 pub mod spntasks;
 pub mod gplink;
 pub mod constants;
-pub mod trusts;
\ No newline at end of file
+pub mod trusts;
+pub mod subnet;
+pub mod pkiflags;
+pub mod encryption;
+pub mod os;
\ No newline at end of file
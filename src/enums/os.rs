@@ -0,0 +1,109 @@
+//! Operating systems of computers
+//!
+//! Normalization of the free text `operatingSystem` and `operatingSystemVersion` attributes like
+//! "Windows Server 2019 Datacenter" and "10.0 (17763)" into one family, one release and the build number.
+//! Windows 10, 11 and the servers after 2016 share version 10.0, the release is taken from the build.
+//! <https://learn.microsoft.com/en-us/windows/release-health/windows-server-release-info>
+
+/// Normalized operating system of one computer.
+#[derive(Debug, PartialEq)]
+pub struct OperatingSystem {
+    /// "Windows Server", "Windows", "Linux", "macOS" or "Other"
+    pub family: String,
+    /// Like "Windows Server 2012 R2" or "Windows 10", the attribute value if unknown
+    pub release: String,
+    /// Build number of "operatingSystemVersion", 0 if not set
+    pub build: u32,
+    /// Release without security updates anymore
+    pub eol: bool,
+}
+
+/// Windows Server releases sharing version 10.0, first build.
+const SERVER_BUILDS: [(u32, &str); 4] = [
+    (26100, "Windows Server 2025"),
+    (20348, "Windows Server 2022"),
+    (17763, "Windows Server 2019"),
+    (14393, "Windows Server 2016"),
+];
+
+/// Windows Server releases by "operatingSystem" name.
+const SERVER_RELEASES: [&str; 9] = ["2025", "2022", "2019", "2016", "2012 R2", "2012", "2008 R2", "2008", "2003"];
+
+/// Releases without extended support, Windows 10 since October 2025 except LTSC.
+const EOL_RELEASES: [&str; 10] = [
+    "Windows 2000",
+    "Windows XP",
+    "Windows Vista",
+    "Windows 7",
+    "Windows 8",
+    "Windows 8.1",
+    "Windows 10",
+    "Windows Server 2003",
+    "Windows Server 2008",
+    "Windows Server 2012",
+];
+
+impl OperatingSystem {
+    /// Function to normalize "operatingSystem" and "operatingSystemVersion" values.
+    pub fn parse(name: &str, version: &str) -> Self
+    {
+        // "10.0 (17763)"
+        let build = version.split(|c: char| c == '(' || c == ')').nth(1).and_then(|build| build.trim().parse::<u32>().ok()).unwrap_or(0);
+        let lower = name.to_lowercase();
+
+        let (family, release) = if lower.contains("windows") && lower.contains("server") {
+            let release = SERVER_RELEASES.iter()
+                .find(|release| lower.contains(&release.to_lowercase()))
+                .map(|release| format!("Windows Server {}", release))
+                .or_else(|| SERVER_BUILDS.iter().find(|(first, _)| build >= *first).map(|(_, release)| release.to_string()))
+                .unwrap_or(name.trim().to_string());
+            ("Windows Server", release)
+        } else if lower.contains("windows") {
+            let release = ["2000", "xp", "vista", "7", "8.1", "8", "10", "11"].iter()
+                .find(|release| lower.split(|c: char| c.is_whitespace()).any(|word| word == **release))
+                .map(|release| match *release {
+                    "xp" => "Windows XP".to_string(),
+                    "vista" => "Windows Vista".to_string(),
+                    // Windows 11 reports "Windows 10" in old builds of the agent
+                    "10" if build >= 22000 => "Windows 11".to_string(),
+                    release => format!("Windows {}", release),
+                })
+                .unwrap_or(name.trim().to_string());
+            ("Windows", release)
+        } else if lower.contains("mac") {
+            ("macOS", name.trim().to_string())
+        } else if ["linux", "ubuntu", "debian", "red hat", "centos", "suse"].iter().any(|linux| lower.contains(linux)) {
+            ("Linux", name.trim().to_string())
+        } else {
+            ("Other", name.trim().to_string())
+        };
+
+        let ltsc = lower.contains("ltsc") || lower.contains("ltsb");
+        let eol = EOL_RELEASES.iter().any(|eol| release == *eol || release.starts_with(&format!("{} ", eol)))
+            && !(release == "Windows 10" && ltsc);
+
+        OperatingSystem { family: family.to_string(), release, build, eol }
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_operating_system() {
+    let os = OperatingSystem::parse("Windows Server 2008 R2 Standard", "6.1 (7601)");
+    assert_eq!(os, OperatingSystem { family: "Windows Server".to_string(), release: "Windows Server 2008 R2".to_string(), build: 7601, eol: true });
+
+    let os = OperatingSystem::parse("Windows Server 2019 Datacenter", "10.0 (17763)");
+    assert_eq!((os.release.as_str(), os.eol), ("Windows Server 2019", false));
+    assert_eq!(OperatingSystem::parse("Windows Server Datacenter", "10.0 (20348)").release, "Windows Server 2022");
+    assert_eq!(OperatingSystem::parse("Windows Server 2012 R2 Standard", "6.3 (9600)").release, "Windows Server 2012 R2");
+    assert!(OperatingSystem::parse("Windows Server 2012 R2 Standard", "6.3 (9600)").eol);
+
+    assert_eq!(OperatingSystem::parse("Windows 10 Enterprise", "10.0 (22631)").release, "Windows 11");
+    assert!(OperatingSystem::parse("Windows 7 Professional", "6.1 (7601)").eol);
+    assert!(OperatingSystem::parse("Windows 10 Pro", "10.0 (19045)").eol);
+    assert!(!OperatingSystem::parse("Windows 10 Enterprise LTSC", "10.0 (17763)").eol);
+    assert!(!OperatingSystem::parse("Windows 11 Enterprise", "10.0 (26100)").eol);
+
+    let os = OperatingSystem::parse("Ubuntu 22.04", "");
+    assert_eq!((os.family.as_str(), os.build, os.eol), ("Linux", 0, false));
+}
@@ -0,0 +1,292 @@
+//! Certificate template flags and extended key usages
+//!
+//! Decoders for the `msPKI-Certificate-Name-Flag`, `msPKI-Enrollment-Flag` and `msPKI-Private-Key-Flag`
+//! attributes and the `pKIExtendedKeyUsage` / `msPKI-RA-Application-Policies` OIDs of certificate templates.
+//! Every decoder returns the readable names exported in the template properties.
+//! <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-crtd/4c6950e4-1dc2-4ae3-98c3-b8919bb73822>
+use bitflags::bitflags;
+
+bitflags! {
+    /// Flags of "msPKI-Certificate-Name-Flag".
+    /// <https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-crtd/1192823c-d839-4bc3-9b6b-fa8c53507ae1>
+    pub struct PkiCertificateNameFlag: u32 {
+        const ENROLLEE_SUPPLIES_SUBJECT = 0x00000001;
+        const ADD_EMAIL = 0x00000002;
+        const ADD_OBJ_GUID = 0x00000004;
+        const OLD_CERT_SUPPLIES_SUBJECT_AND_ALT_NAME = 0x00000008;
+        const ADD_DIRECTORY_PATH = 0x00000100;
+        const ENROLLEE_SUPPLIES_SUBJECT_ALT_NAME = 0x00010000;
+        const SUBJECT_ALT_REQUIRE_DOMAIN_DNS = 0x00400000;
+        const SUBJECT_ALT_REQUIRE_SPN = 0x00800000;
+        const SUBJECT_ALT_REQUIRE_DIRECTORY_GUID = 0x01000000;
+        const SUBJECT_ALT_REQUIRE_UPN = 0x02000000;
+        const SUBJECT_ALT_REQUIRE_EMAIL = 0x04000000;
+        const SUBJECT_ALT_REQUIRE_DNS = 0x08000000;
+        const SUBJECT_REQUIRE_DNS_AS_CN = 0x10000000;
+        const SUBJECT_REQUIRE_EMAIL = 0x20000000;
+        const SUBJECT_REQUIRE_COMMON_NAME = 0x40000000;
+        const SUBJECT_REQUIRE_DIRECTORY_PATH = 0x80000000;
+    }
+}
+
+const CERTIFICATE_NAME_FLAG_NAMES: [(PkiCertificateNameFlag, &str); 16] = [
+    (PkiCertificateNameFlag::ENROLLEE_SUPPLIES_SUBJECT, "EnrolleeSuppliesSubject"),
+    (PkiCertificateNameFlag::ADD_EMAIL, "AddEmail"),
+    (PkiCertificateNameFlag::ADD_OBJ_GUID, "AddObjGuid"),
+    (PkiCertificateNameFlag::OLD_CERT_SUPPLIES_SUBJECT_AND_ALT_NAME, "OldCertSuppliesSubjectAndAltName"),
+    (PkiCertificateNameFlag::ADD_DIRECTORY_PATH, "AddDirectoryPath"),
+    (PkiCertificateNameFlag::ENROLLEE_SUPPLIES_SUBJECT_ALT_NAME, "EnrolleeSuppliesSubjectAltName"),
+    (PkiCertificateNameFlag::SUBJECT_ALT_REQUIRE_DOMAIN_DNS, "SubjectAltRequireDomainDns"),
+    (PkiCertificateNameFlag::SUBJECT_ALT_REQUIRE_SPN, "SubjectAltRequireSpn"),
+    (PkiCertificateNameFlag::SUBJECT_ALT_REQUIRE_DIRECTORY_GUID, "SubjectAltRequireDirectoryGuid"),
+    (PkiCertificateNameFlag::SUBJECT_ALT_REQUIRE_UPN, "SubjectAltRequireUpn"),
+    (PkiCertificateNameFlag::SUBJECT_ALT_REQUIRE_EMAIL, "SubjectAltRequireEmail"),
+    (PkiCertificateNameFlag::SUBJECT_ALT_REQUIRE_DNS, "SubjectAltRequireDns"),
+    (PkiCertificateNameFlag::SUBJECT_REQUIRE_DNS_AS_CN, "SubjectRequireDnsAsCn"),
+    (PkiCertificateNameFlag::SUBJECT_REQUIRE_EMAIL, "SubjectRequireEmail"),
+    (PkiCertificateNameFlag::SUBJECT_REQUIRE_COMMON_NAME, "SubjectRequireCommonName"),
+    (PkiCertificateNameFlag::SUBJECT_REQUIRE_DIRECTORY_PATH, "SubjectRequireDirectoryPath"),
+];
+
+impl PkiCertificateNameFlag {
+    /// Function to get the flags from the attribute value, unknown bits are ignored.
+    pub fn parse(value: &str) -> Self
+    {
+        PkiCertificateNameFlag::from_bits_truncate(parse_flag_value(value))
+    }
+
+    /// Function to get the readable names of the flags.
+    pub fn names(&self) -> Vec<String>
+    {
+        flag_names(&CERTIFICATE_NAME_FLAG_NAMES, |flag| self.contains(flag))
+    }
+}
+
+bitflags! {
+    /// Flags of "msPKI-Enrollment-Flag".
+    /// <https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-crtd/ec71fd43-61c2-407b-83c9-b52272dec8a1>
+    pub struct PkiEnrollmentFlag: u32 {
+        const INCLUDE_SYMMETRIC_ALGORITHMS = 0x00000001;
+        const PEND_ALL_REQUESTS = 0x00000002;
+        const PUBLISH_TO_KRA_CONTAINER = 0x00000004;
+        const PUBLISH_TO_DS = 0x00000008;
+        const AUTO_ENROLLMENT_CHECK_USER_DS_CERTIFICATE = 0x00000010;
+        const AUTO_ENROLLMENT = 0x00000020;
+        const PREVIOUS_APPROVAL_VALIDATE_REENROLLMENT = 0x00000040;
+        const DOMAIN_AUTHENTICATION_NOT_REQUIRED = 0x00000080;
+        const USER_INTERACTION_REQUIRED = 0x00000100;
+        const ADD_TEMPLATE_NAME = 0x00000200;
+        const REMOVE_INVALID_CERTIFICATE_FROM_PERSONAL_STORE = 0x00000400;
+        const ALLOW_ENROLL_ON_BEHALF_OF = 0x00000800;
+        const ADD_OCSP_NOCHECK = 0x00001000;
+        const ENABLE_KEY_REUSE_ON_NT_TOKEN_KEYSET_STORAGE_FULL = 0x00002000;
+        const NOREVOCATIONINFOINISSUEDCERTS = 0x00004000;
+        const INCLUDE_BASIC_CONSTRAINTS_FOR_EE_CERTS = 0x00008000;
+        const ALLOW_PREVIOUS_APPROVAL_KEYBASEDRENEWAL_VALIDATE_REENROLLMENT = 0x00010000;
+        const ISSUANCE_POLICIES_FROM_REQUEST = 0x00020000;
+        const SKIP_AUTO_RENEWAL = 0x00040000;
+        const NO_SECURITY_EXTENSION = 0x00080000;
+    }
+}
+
+const ENROLLMENT_FLAG_NAMES: [(PkiEnrollmentFlag, &str); 20] = [
+    (PkiEnrollmentFlag::INCLUDE_SYMMETRIC_ALGORITHMS, "IncludeSymmetricAlgorithms"),
+    (PkiEnrollmentFlag::PEND_ALL_REQUESTS, "PendAllRequests"),
+    (PkiEnrollmentFlag::PUBLISH_TO_KRA_CONTAINER, "PublishToKraContainer"),
+    (PkiEnrollmentFlag::PUBLISH_TO_DS, "PublishToDs"),
+    (PkiEnrollmentFlag::AUTO_ENROLLMENT_CHECK_USER_DS_CERTIFICATE, "AutoEnrollmentCheckUserDsCertificate"),
+    (PkiEnrollmentFlag::AUTO_ENROLLMENT, "AutoEnrollment"),
+    (PkiEnrollmentFlag::PREVIOUS_APPROVAL_VALIDATE_REENROLLMENT, "PreviousApprovalValidateReenrollment"),
+    (PkiEnrollmentFlag::DOMAIN_AUTHENTICATION_NOT_REQUIRED, "DomainAuthenticationNotRequired"),
+    (PkiEnrollmentFlag::USER_INTERACTION_REQUIRED, "UserInteractionRequired"),
+    (PkiEnrollmentFlag::ADD_TEMPLATE_NAME, "AddTemplateName"),
+    (PkiEnrollmentFlag::REMOVE_INVALID_CERTIFICATE_FROM_PERSONAL_STORE, "RemoveInvalidCertificateFromPersonalStore"),
+    (PkiEnrollmentFlag::ALLOW_ENROLL_ON_BEHALF_OF, "AllowEnrollOnBehalfOf"),
+    (PkiEnrollmentFlag::ADD_OCSP_NOCHECK, "AddOcspNocheck"),
+    (PkiEnrollmentFlag::ENABLE_KEY_REUSE_ON_NT_TOKEN_KEYSET_STORAGE_FULL, "EnableKeyReuseOnNtTokenKeysetStorageFull"),
+    (PkiEnrollmentFlag::NOREVOCATIONINFOINISSUEDCERTS, "NoRevocationInfoInIssuedCerts"),
+    (PkiEnrollmentFlag::INCLUDE_BASIC_CONSTRAINTS_FOR_EE_CERTS, "IncludeBasicConstraintsForEeCerts"),
+    (PkiEnrollmentFlag::ALLOW_PREVIOUS_APPROVAL_KEYBASEDRENEWAL_VALIDATE_REENROLLMENT, "AllowPreviousApprovalKeybasedrenewalValidateReenrollment"),
+    (PkiEnrollmentFlag::ISSUANCE_POLICIES_FROM_REQUEST, "IssuancePoliciesFromRequest"),
+    (PkiEnrollmentFlag::SKIP_AUTO_RENEWAL, "SkipAutoRenewal"),
+    (PkiEnrollmentFlag::NO_SECURITY_EXTENSION, "NoSecurityExtension"),
+];
+
+impl PkiEnrollmentFlag {
+    /// Function to get the flags from the attribute value, unknown bits are ignored.
+    pub fn parse(value: &str) -> Self
+    {
+        PkiEnrollmentFlag::from_bits_truncate(parse_flag_value(value))
+    }
+
+    /// Function to get the readable names of the flags.
+    pub fn names(&self) -> Vec<String>
+    {
+        flag_names(&ENROLLMENT_FLAG_NAMES, |flag| self.contains(flag))
+    }
+}
+
+bitflags! {
+    /// Flags of "msPKI-Private-Key-Flag".
+    /// <https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-crtd/f6122d87-b999-4b92-bff8-f465e8949667>
+    pub struct PkiPrivateKeyFlag: u32 {
+        const REQUIRE_PRIVATE_KEY_ARCHIVAL = 0x00000001;
+        const EXPORTABLE_KEY = 0x00000010;
+        const STRONG_KEY_PROTECTION_REQUIRED = 0x00000020;
+        const REQUIRE_ALTERNATE_SIGNATURE_ALGORITHM = 0x00000040;
+        const REQUIRE_SAME_KEY_RENEWAL = 0x00000080;
+        const USE_LEGACY_PROVIDER = 0x00000100;
+        const EK_TRUST_ON_USE = 0x00000200;
+        const EK_VALIDATE_CERT = 0x00000400;
+        const EK_VALIDATE_KEY = 0x00000800;
+        const ATTEST_PREFERRED = 0x00001000;
+        const ATTEST_REQUIRED = 0x00002000;
+        const ATTESTATION_WITHOUT_POLICY = 0x00004000;
+        const HELLO_LOGON_KEY = 0x00200000;
+    }
+}
+
+const PRIVATE_KEY_FLAG_NAMES: [(PkiPrivateKeyFlag, &str); 13] = [
+    (PkiPrivateKeyFlag::REQUIRE_PRIVATE_KEY_ARCHIVAL, "RequirePrivateKeyArchival"),
+    (PkiPrivateKeyFlag::EXPORTABLE_KEY, "ExportableKey"),
+    (PkiPrivateKeyFlag::STRONG_KEY_PROTECTION_REQUIRED, "StrongKeyProtectionRequired"),
+    (PkiPrivateKeyFlag::REQUIRE_ALTERNATE_SIGNATURE_ALGORITHM, "RequireAlternateSignatureAlgorithm"),
+    (PkiPrivateKeyFlag::REQUIRE_SAME_KEY_RENEWAL, "RequireSameKeyRenewal"),
+    (PkiPrivateKeyFlag::USE_LEGACY_PROVIDER, "UseLegacyProvider"),
+    (PkiPrivateKeyFlag::EK_TRUST_ON_USE, "EkTrustOnUse"),
+    (PkiPrivateKeyFlag::EK_VALIDATE_CERT, "EkValidateCert"),
+    (PkiPrivateKeyFlag::EK_VALIDATE_KEY, "EkValidateKey"),
+    (PkiPrivateKeyFlag::ATTEST_PREFERRED, "AttestPreferred"),
+    (PkiPrivateKeyFlag::ATTEST_REQUIRED, "AttestRequired"),
+    (PkiPrivateKeyFlag::ATTESTATION_WITHOUT_POLICY, "AttestationWithoutPolicy"),
+    (PkiPrivateKeyFlag::HELLO_LOGON_KEY, "HelloLogonKey"),
+];
+
+impl PkiPrivateKeyFlag {
+    /// Function to get the flags from the attribute value, unknown bits are ignored.
+    pub fn parse(value: &str) -> Self
+    {
+        PkiPrivateKeyFlag::from_bits_truncate(parse_flag_value(value))
+    }
+
+    /// Function to get the readable names of the flags, `AttestNone` without attestation flag.
+    pub fn names(&self) -> Vec<String>
+    {
+        let mut names = flag_names(&PRIVATE_KEY_FLAG_NAMES, |flag| self.contains(flag));
+        if !self.intersects(PkiPrivateKeyFlag::ATTEST_PREFERRED | PkiPrivateKeyFlag::ATTEST_REQUIRED) {
+            names.push("AttestNone".to_string());
+        }
+        names
+    }
+}
+
+/// Function to parse one flag attribute, stored as a signed 32 bits integer in LDAP.
+fn parse_flag_value(value: &str) -> u32
+{
+    value.trim().parse::<i64>().unwrap_or(0) as u32
+}
+
+/// Function to get the names of the flags set.
+fn flag_names<T: Copy>(names: &[(T, &str)], contains: impl Fn(T) -> bool) -> Vec<String>
+{
+    names.iter()
+        .filter(|(flag, _)| contains(*flag))
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Extended key usage and application policy OIDs to names.
+/// <https://www.pkisolutions.com/object-identifiers-oid-in-pki/>
+/// <https://github.com/ly4k/Certipy/blob/main/certipy/lib/constants.py#L145>
+pub const EKU_NAMES: [(&str, &str); 61] = [
+    ("1.3.6.1.4.1.311.76.6.1", "Windows Update"),
+    ("1.3.6.1.4.1.311.10.3.11", "Key Recovery"),
+    ("1.3.6.1.4.1.311.10.3.25", "Windows Third Party Application Component"),
+    ("1.3.6.1.4.1.311.21.6", "Key Recovery Agent"),
+    ("1.3.6.1.4.1.311.10.3.6", "Windows System Component Verification"),
+    ("1.3.6.1.4.1.311.61.4.1", "Early Launch Antimalware Drive"),
+    ("1.3.6.1.4.1.311.10.3.23", "Windows TCB Component"),
+    ("1.3.6.1.4.1.311.61.1.1", "Kernel Mode Code Signing"),
+    ("1.3.6.1.4.1.311.10.3.26", "Windows Software Extension Verification"),
+    ("2.23.133.8.3", "Attestation Identity Key Certificate"),
+    ("1.3.6.1.4.1.311.76.3.1", "Windows Store"),
+    ("1.3.6.1.4.1.311.10.6.1", "Key Pack Licenses"),
+    ("1.3.6.1.4.1.311.20.2.2", "Smart Card Logon"),
+    ("1.3.6.1.5.2.3.5", "KDC Authentication"),
+    ("1.3.6.1.5.5.7.3.7", "IP security use"),
+    ("1.3.6.1.4.1.311.10.3.8", "Embedded Windows System Component Verification"),
+    ("1.3.6.1.4.1.311.10.3.20", "Windows Kits Component"),
+    ("1.3.6.1.5.5.7.3.6", "IP security tunnel termination"),
+    ("1.3.6.1.4.1.311.10.3.5", "Windows Hardware Driver Verification"),
+    ("1.3.6.1.5.5.8.2.2", "IP security IKE intermediate"),
+    ("1.3.6.1.4.1.311.10.3.39", "Windows Hardware Driver Extended Verification"),
+    ("1.3.6.1.4.1.311.10.6.2", "License Server Verification"),
+    ("1.3.6.1.4.1.311.10.3.5.1", "Windows Hardware Driver Attested Verification"),
+    ("1.3.6.1.4.1.311.76.5.1", "Dynamic Code Generato"),
+    ("1.3.6.1.5.5.7.3.8", "Time Stamping"),
+    ("1.3.6.1.4.1.311.10.3.4.1", "File Recovery"),
+    ("1.3.6.1.4.1.311.2.6.1", "SpcRelaxedPEMarkerCheck"),
+    ("2.23.133.8.1", "Endorsement Key Certificate"),
+    ("1.3.6.1.4.1.311.2.6.2", "SpcEncryptedDigestRetryCount"),
+    ("1.3.6.1.4.1.311.10.3.4", "Encrypting File System"),
+    ("1.3.6.1.5.5.7.3.1", "Server Authentication"),
+    ("1.3.6.1.4.1.311.61.5.1", "HAL Extension"),
+    ("1.3.6.1.5.5.7.3.4", "Secure Email"),
+    ("1.3.6.1.5.5.7.3.5", "IP security end system"),
+    ("1.3.6.1.4.1.311.10.3.9", "Root List Signe"),
+    ("1.3.6.1.4.1.311.10.3.30", "Disallowed List"),
+    ("1.3.6.1.4.1.311.10.3.19", "Revoked List Signe"),
+    ("1.3.6.1.4.1.311.10.3.21", "Windows RT Verification"),
+    ("1.3.6.1.4.1.311.10.3.10", "Qualified Subordination"),
+    ("1.3.6.1.4.1.311.10.3.12", "Document Signing"),
+    ("1.3.6.1.4.1.311.10.3.24", "Protected Process Verification"),
+    ("1.3.6.1.4.1.311.80.1", "Document Encryption"),
+    ("1.3.6.1.4.1.311.10.3.22", "Protected Process Light Verification"),
+    ("1.3.6.1.4.1.311.21.19", "Directory Service Email Replication"),
+    ("1.3.6.1.4.1.311.21.5", "Private Key Archival"),
+    ("1.3.6.1.4.1.311.10.5.1", "Digital Rights"),
+    ("1.3.6.1.4.1.311.10.3.27", "Preview Build Signing"),
+    ("1.3.6.1.4.1.311.20.2.1", "Certificate Request Agent"),
+    ("2.23.133.8.2", "Platform Certificate"),
+    ("1.3.6.1.4.1.311.20.1", "CTL Usage"),
+    ("1.3.6.1.5.5.7.3.9", "OCSP Signing"),
+    ("1.3.6.1.5.5.7.3.3", "Code Signing"),
+    ("1.3.6.1.4.1.311.10.3.1", "Microsoft Trust List Signing"),
+    ("1.3.6.1.4.1.311.10.3.2", "Microsoft Time Stamping"),
+    ("1.3.6.1.4.1.311.76.8.1", "Microsoft Publisher"),
+    ("1.3.6.1.5.5.7.3.2", "Client Authentication"),
+    ("1.3.6.1.5.2.3.4", "PKINIT Client Authentication"),
+    ("1.3.6.1.4.1.311.10.3.13", "Lifetime Signing"),
+    ("2.5.29.37.0", "Any Purpose"),
+    ("1.3.6.1.4.1.311.64.1.1", "Server Trust"),
+    ("1.3.6.1.4.1.311.10.3.7", "OEM Windows System Component Verification"),
+];
+
+/// Function to get the name of one extended key usage OID, None if unknown.
+pub fn get_eku_name(oid: &str) -> Option<&'static str>
+{
+    EKU_NAMES.iter().find(|(eku, _)| *eku == oid).map(|(_, name)| *name)
+}
+
+/// Function to get the readable names of "pKIExtendedKeyUsage" or "msPKI-RA-Application-Policies" values.
+/// Unknown OIDs are kept as they are.
+pub fn decode_ekus(oids: &[String]) -> Vec<String>
+{
+    oids.iter()
+        .map(|oid| get_eku_name(oid).map(|name| name.to_string()).unwrap_or(oid.to_owned()))
+        .collect()
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_pki_flags() {
+    // ENROLLEE_SUPPLIES_SUBJECT | SUBJECT_ALT_REQUIRE_UPN
+    assert_eq!(PkiCertificateNameFlag::parse("33554433").names(), vec!["EnrolleeSuppliesSubject", "SubjectAltRequireUpn"]);
+    // Negative values for the high bit
+    assert!(PkiCertificateNameFlag::parse("-2147483648").contains(PkiCertificateNameFlag::SUBJECT_REQUIRE_DIRECTORY_PATH));
+    assert!(PkiEnrollmentFlag::parse("41").contains(PkiEnrollmentFlag::AUTO_ENROLLMENT));
+    assert_eq!(PkiEnrollmentFlag::parse("0").names(), Vec::<String>::new());
+    assert_eq!(PkiPrivateKeyFlag::parse("16").names(), vec!["ExportableKey", "AttestNone"]);
+    assert_eq!(PkiPrivateKeyFlag::parse("8192").names(), vec!["AttestRequired"]);
+    assert_eq!(decode_ekus(&["1.3.6.1.5.5.7.3.2".to_string(), "1.2.3.4".to_string()]), vec!["Client Authentication", "1.2.3.4"]);
+}
@@ -3,21 +3,26 @@ use crate::enums::constants::*;
 use nom7::number::complete::{*,{le_u16, le_u32, le_u8}};
 use nom7::bytes::streaming::take;
 use nom7::combinator::cond;
+use nom7::error::{Error, ErrorKind};
 use nom7::multi::count;
 use nom7::IResult;
+use crate::enums::sid::{decode_guid, Sid};
 
 // https://github.com/fox-it/dissect.cstruct/blob/master/examples/secdesc.py
 // http://www.selfadsi.org/deep-inside/ad-security-descriptors.htm#SecurityDescriptorStructure
 // https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/20233ed8-a6c6-4097-aafa-dd545ed24428?redirectedfrom=MSDN
 // https://github.com/OISF/suricata/blob/master/rust/src/dcerpc/parser.rs
 
+/// Size of the self-relative security descriptor header, components offsets start after it
+pub const SECURITY_DESCRIPTOR_HEADER_SIZE: usize = 20;
+
 /// Structure for Security Descriptor network packet.
 /// <https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/7d4dac05-9cef-4563-a058-f108abecce1d>
 #[derive(Debug)]
 pub struct SecurityDescriptor {
     pub revision: u8,
     pub sbz1: u8,
-    pub control: u16,
+    pub control: SdControl,
     pub offset_owner: u32,
     pub offset_group: u32,
     pub offset_sacl: u32,
@@ -29,7 +34,7 @@ impl SecurityDescriptor {
     {
         let (i, revision) = le_u8(i)?;
         let (i, sbz1) = le_u8(i)?;
-        let (i, control) = le_u16(i)?;
+        let (i, control) = SdControl::parse(i)?;
         let (i, offset_owner) = le_u32(i)?;
         let (i, offset_group) = le_u32(i)?;
         let (i, offset_sacl) = le_u32(i)?;
@@ -46,6 +51,82 @@ impl SecurityDescriptor {
         };
         Ok((i, nt))
     }
+
+    /// Function to get one component of the self-relative descriptor from its offset in the full buffer.
+    /// Ok(None) if the component is absent (offset 0), Err if the offset is in the header or out of the buffer.
+    /// Components can be in any order after the header, they don't always follow it.
+    fn get_component<'a>(nt: &'a [u8], offset: u32, component: &str) -> Result<Option<&'a [u8]>, String>
+    {
+        if offset == 0 {
+            return Ok(None)
+        }
+        if (offset as usize) < SECURITY_DESCRIPTOR_HEADER_SIZE || offset as usize >= nt.len() {
+            return Err(format!("{} offset {} out of the security descriptor of {} bytes", component, offset, nt.len()))
+        }
+        Ok(Some(&nt[offset as usize..]))
+    }
+
+    /// Function to parse one SID component, owner or group.
+    fn parse_sid(nt: &[u8], offset: u32, component: &str) -> Result<Option<LdapSid>, String>
+    {
+        match SecurityDescriptor::get_component(nt, offset, component)? {
+            Some(data) => LdapSid::parse(data).map(|(_, sid)| Some(sid)).map_err(|err| format!("Can't parse {} SID: {}", component, err)),
+            None => Ok(None),
+        }
+    }
+
+    /// Function to parse one ACL component, SACL or DACL, limited to its acl_size.
+    fn parse_acl(nt: &[u8], offset: u32, component: &str) -> Result<Option<Acl>, String>
+    {
+        let data = match SecurityDescriptor::get_component(nt, offset, component)? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        if data.len() < 8 {
+            return Err(format!("{} truncated at offset {}", component, offset))
+        }
+        let acl_size = u16::from_le_bytes([data[2], data[3]]) as usize;
+        if acl_size < 8 || acl_size > data.len() {
+            return Err(format!("{} size {} out of the security descriptor of {} bytes", component, acl_size, nt.len()))
+        }
+        Acl::parse(&data[..acl_size]).map(|(_, acl)| Some(acl)).map_err(|err| format!("Can't parse {}: {}", component, err))
+    }
+
+    /// Function to parse the owner SID of the full descriptor `nt`.
+    pub fn parse_owner(&self, nt: &[u8]) -> Result<Option<LdapSid>, String>
+    {
+        SecurityDescriptor::parse_sid(nt, self.offset_owner, "owner")
+    }
+
+    /// Function to parse the group SID of the full descriptor `nt`.
+    pub fn parse_group(&self, nt: &[u8]) -> Result<Option<LdapSid>, String>
+    {
+        SecurityDescriptor::parse_sid(nt, self.offset_group, "group")
+    }
+
+    /// Function to parse the SACL of the full descriptor `nt`, None if SE_SACL_PRESENT isn't set.
+    pub fn parse_sacl(&self, nt: &[u8]) -> Result<Option<Acl>, String>
+    {
+        if !self.control.contains(SdControl::SE_SACL_PRESENT) {
+            return Ok(None)
+        }
+        SecurityDescriptor::parse_acl(nt, self.offset_sacl, "SACL")
+    }
+
+    /// Function to parse the DACL of the full descriptor `nt`, None if SE_DACL_PRESENT isn't set.
+    pub fn parse_dacl(&self, nt: &[u8]) -> Result<Option<Acl>, String>
+    {
+        if !self.control.contains(SdControl::SE_DACL_PRESENT) {
+            return Ok(None)
+        }
+        SecurityDescriptor::parse_acl(nt, self.offset_dacl, "DACL")
+    }
+
+    /// DACL protected from inheritance (AdminSDHolder protected objects, manual inheritance break...).
+    pub fn dacl_protected(&self) -> bool
+    {
+        self.control.contains(SdControl::SE_DACL_PROTECTED)
+    }
 }
 
 /// Strcuture for Sid Identified Authority network packet.
@@ -82,6 +163,9 @@ impl LdapSid {
     {
         let (i, revision) = le_u8(i)?;
         let (i, sub_authority_count) = le_u8(i)?;
+        if sub_authority_count as usize > crate::enums::sid::MAX_SUB_AUTHORITIES {
+            return Err(nom7::Err::Error(Error::new(i, ErrorKind::Verify)))
+        }
         let (i, identifier_authority) = LdapSidIdentifiedAuthority::parse(i)?;
         let (i, sub_authority) = count(le_u32, sub_authority_count as usize)(i)?;
 
@@ -116,6 +200,10 @@ impl Acl {
         let (i, acl_size) = le_u16(i)?;
         let (i, ace_count) = le_u16(i)?;
         let (i, sbz2) = le_u16(i)?;
+        // Each ACE has at least its 4 bytes header, don't trust ace_count from hostile data
+        if ace_count as usize * 4 > i.len() {
+            return Err(nom7::Err::Error(Error::new(i, ErrorKind::Count)))
+        }
         let (i, data) = count(Ace::parse, ace_count as usize)(i)?;
 
         let acl = Acl {
@@ -146,6 +234,10 @@ impl Ace {
         let (i, ace_type) = le_u8(i)?;
         let (i, ace_flags) = le_u8(i)?;
         let (i, ace_size) = le_u16(i)?;
+        // ace_size includes the header
+        if ace_size < 4 {
+            return Err(nom7::Err::Error(Error::new(i, ErrorKind::LengthValue)))
+        }
         let (i, data) = take(ace_size as usize - 4)(i)?;
         let (_j,ace_data_formated) = AceFormat::parse(data, ace_type)?;
 
@@ -187,7 +279,8 @@ impl AceFormat {
             Ok((i, data))
         }
         else {
-            panic!("Error during ACE data parsing to AceFormat!")
+            // Audit, callback and other ACE types aren't used
+            Ok((i, AceFormat::Empty))
         }
     }
     
@@ -290,6 +383,153 @@ impl AccessAllowedObjectAce {
     }
 }
 
+bitflags! {
+    /// Security descriptor control flags.
+    /// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/7d4dac05-9cef-4563-a058-f108abecce1d>
+    pub struct SdControl : u16 {
+        const SE_OWNER_DEFAULTED = 0x0001;
+        const SE_GROUP_DEFAULTED = 0x0002;
+        const SE_DACL_PRESENT = 0x0004;
+        const SE_DACL_DEFAULTED = 0x0008;
+        const SE_SACL_PRESENT = 0x0010;
+        const SE_SACL_DEFAULTED = 0x0020;
+        const SE_DACL_TRUSTED = 0x0040;
+        const SE_SERVER_SECURITY = 0x0080;
+        const SE_DACL_COMPUTED_INHERITANCE_REQUIRED = 0x0100;
+        const SE_SACL_COMPUTED_INHERITANCE_REQUIRED = 0x0200;
+        const SE_DACL_AUTO_INHERITED = 0x0400;
+        const SE_SACL_AUTO_INHERITED = 0x0800;
+        const SE_DACL_PROTECTED = 0x1000;
+        const SE_SACL_PROTECTED = 0x2000;
+        const SE_RM_CONTROL_VALID = 0x4000;
+        const SE_SELF_RELATIVE = 0x8000;
+    }
+}
+
+impl SdControl {
+    pub fn parse(i: &[u8]) -> IResult<&[u8], SdControl>
+    {
+        let (i, control) = le_u16(i)?;
+        Ok((i, SdControl::from_bits_truncate(control)))
+    }
+}
+
+bitflags! {
+    /// Access mask of one ACE.
+    /// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/7a53f60e-e730-4dfe-bbe9-b21b62eb790b>
+    /// <https://learn.microsoft.com/en-us/dotnet/api/system.directoryservices.activedirectoryrights>
+    pub struct AccessMask : u32 {
+        // Generic rights, only in the ACEs written by some tools, mapped to the rights below by map_generic()
+        const SET_GENERIC_READ                  = 0x80000000;
+        const SET_GENERIC_WRITE                 = 0x40000000;
+        const SET_GENERIC_EXECUTE               = 0x20000000;
+        const SET_GENERIC_ALL                   = 0x10000000;
+
+        // Generic rights as Active Directory stores them
+        const GENERIC_READ                      = 0x00020094;
+        const GENERIC_WRITE                     = 0x00020028;
+        const GENERIC_EXECUTE                   = 0x00020004;
+        const GENERIC_ALL                       = 0x000F01FF;
+
+        // Standard rights, for all ACE types
+        const MAXIMUM_ALLOWED                   = 0x02000000;
+        const ACCESS_SYSTEM_SECURITY            = 0x01000000;
+        const SYNCHRONIZE                       = 0x00100000;
+        const WRITE_OWNER                       = 0x00080000;
+        const WRITE_DACL                        = 0x00040000;
+        const READ_CONTROL                      = 0x00020000;
+        const DELETE                            = 0x00010000;
+
+        // Directory service object specific rights
+        const ADS_RIGHT_DS_CREATE_CHILD         = 0x00000001;
+        const ADS_RIGHT_DS_DELETE_CHILD         = 0x00000002;
+        const ADS_RIGHT_ACTRL_DS_LIST           = 0x00000004;
+        const ADS_RIGHT_DS_SELF                 = 0x00000008;
+        const ADS_RIGHT_DS_READ_PROP            = 0x00000010;
+        const ADS_RIGHT_DS_WRITE_PROP           = 0x00000020;
+        const ADS_RIGHT_DS_DELETE_TREE          = 0x00000040;
+        const ADS_RIGHT_DS_LIST_OBJECT          = 0x00000080;
+        const ADS_RIGHT_DS_CONTROL_ACCESS       = 0x00000100;
+
+        // Certificate authority specific rights, same bits as CREATE_CHILD and DELETE_CHILD
+        const MANAGE_CA                         = 0x00000001;
+        const MANAGE_CERTIFICATES               = 0x00000002;
+    }
+}
+
+/// Names of the single rights for mask decoding, generic rights are decoded after mapping.
+const ACCESS_MASK_NAMES: [(AccessMask, &str); 16] = [
+    (AccessMask::MAXIMUM_ALLOWED, "MaximumAllowed"),
+    (AccessMask::ACCESS_SYSTEM_SECURITY, "AccessSystemSecurity"),
+    (AccessMask::SYNCHRONIZE, "Synchronize"),
+    (AccessMask::WRITE_OWNER, "WriteOwner"),
+    (AccessMask::WRITE_DACL, "WriteDacl"),
+    (AccessMask::READ_CONTROL, "ReadControl"),
+    (AccessMask::DELETE, "Delete"),
+    (AccessMask::ADS_RIGHT_DS_CREATE_CHILD, "CreateChild"),
+    (AccessMask::ADS_RIGHT_DS_DELETE_CHILD, "DeleteChild"),
+    (AccessMask::ADS_RIGHT_ACTRL_DS_LIST, "ListChildren"),
+    (AccessMask::ADS_RIGHT_DS_SELF, "Self"),
+    (AccessMask::ADS_RIGHT_DS_READ_PROP, "ReadProperty"),
+    (AccessMask::ADS_RIGHT_DS_WRITE_PROP, "WriteProperty"),
+    (AccessMask::ADS_RIGHT_DS_DELETE_TREE, "DeleteTree"),
+    (AccessMask::ADS_RIGHT_DS_LIST_OBJECT, "ListObject"),
+    (AccessMask::ADS_RIGHT_DS_CONTROL_ACCESS, "ExtendedRight"),
+];
+
+impl AccessMask {
+    /// Access mask from the raw ACE mask, with the generic rights mapped.
+    pub fn new(mask: u32) -> AccessMask
+    {
+        AccessMask::from_bits_truncate(mask).map_generic()
+    }
+
+    /// Function to map the generic rights to the Active Directory specific rights.
+    /// <https://learn.microsoft.com/en-us/windows/win32/secauthz/generic-access-rights>
+    pub fn map_generic(self) -> AccessMask
+    {
+        let mut mask = self - (AccessMask::SET_GENERIC_READ | AccessMask::SET_GENERIC_WRITE | AccessMask::SET_GENERIC_EXECUTE | AccessMask::SET_GENERIC_ALL);
+        if self.contains(AccessMask::SET_GENERIC_READ) {
+            mask |= AccessMask::GENERIC_READ;
+        }
+        if self.contains(AccessMask::SET_GENERIC_WRITE) {
+            mask |= AccessMask::GENERIC_WRITE;
+        }
+        if self.contains(AccessMask::SET_GENERIC_EXECUTE) {
+            mask |= AccessMask::GENERIC_EXECUTE;
+        }
+        if self.contains(AccessMask::SET_GENERIC_ALL) {
+            mask |= AccessMask::GENERIC_ALL;
+        }
+        mask
+    }
+
+    /// Function to decode the mask in right names: `GenericAll`, `WriteDacl|WriteOwner`...
+    pub fn describe(&self) -> String
+    {
+        if self.contains(AccessMask::GENERIC_ALL) {
+            return "GenericAll".to_string()
+        }
+        let mut names: Vec<&str> = Vec::new();
+        let mut rest = *self;
+        for (generic, name) in [(AccessMask::GENERIC_WRITE, "GenericWrite"), (AccessMask::GENERIC_READ, "GenericRead")] {
+            if self.contains(generic) {
+                names.push(name);
+                rest -= generic;
+            }
+        }
+        for (right, name) in ACCESS_MASK_NAMES.iter() {
+            if rest.contains(*right) {
+                names.push(name);
+            }
+        }
+        if names.is_empty() {
+            return "None".to_string()
+        }
+        names.join("|")
+    }
+}
+
 bitflags! {
     /// AceFlags
     pub struct ObjectAceFlags : u32 {
@@ -302,11 +542,151 @@ impl ObjectAceFlags {
     pub fn parse(i: &[u8]) -> IResult<&[u8], ObjectAceFlags>
     {
         let (i, flags) = le_u32(i)?;
-        let object_ace_flags = ObjectAceFlags::from_bits(flags).unwrap();
+        let object_ace_flags = ObjectAceFlags::from_bits_truncate(flags);
         Ok((i, object_ace_flags))
     }
 }
 
+/// ACL revision of the ACLs without object ACE
+pub const ACL_REVISION: u8 = 2;
+
+/// Access allowed or denied ACE without object type, to serialize descriptors
+/// like msDS-AllowedToActOnBehalfOfOtherIdentity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimpleAce {
+    pub ace_type: u8,
+    pub ace_flags: u8,
+    pub mask: u32,
+    pub sid: Sid,
+}
+
+impl SimpleAce {
+    /// Function to make one access allowed ACE.
+    pub fn allowed(mask: u32, sid: Sid) -> SimpleAce
+    {
+        SimpleAce { ace_type: ACCESS_ALLOWED_ACE_TYPE, ace_flags: 0, mask, sid }
+    }
+
+    /// Function to serialize the ACE with its header.
+    pub fn to_bytes(&self) -> Vec<u8>
+    {
+        let sid = self.sid.to_bytes();
+        let mut ace = vec![self.ace_type, self.ace_flags];
+        ace.extend(((8 + sid.len()) as u16).to_le_bytes());
+        ace.extend(self.mask.to_le_bytes());
+        ace.extend(sid);
+        ace
+    }
+}
+
+/// Function to serialize one ACL with its header.
+pub fn serialize_acl(aces: &[SimpleAce]) -> Vec<u8>
+{
+    let data: Vec<u8> = aces.iter().flat_map(|ace| ace.to_bytes()).collect();
+    let mut acl = vec![ACL_REVISION, 0];
+    acl.extend(((8 + data.len()) as u16).to_le_bytes());
+    acl.extend((aces.len() as u16).to_le_bytes());
+    acl.extend([0u8; 2]);
+    acl.extend(data);
+    acl
+}
+
+/// Function to serialize one self-relative security descriptor with its owner, group and DACL.
+/// Components follow the header in this order, the SACL is never written.
+pub fn serialize_security_descriptor(owner: Option<&Sid>, group: Option<&Sid>, dacl: &[SimpleAce]) -> Vec<u8>
+{
+    let owner = owner.map(|sid| sid.to_bytes()).unwrap_or_default();
+    let group = group.map(|sid| sid.to_bytes()).unwrap_or_default();
+    let offset = |component: &Vec<u8>, offset: usize| if component.is_empty() { 0u32 } else { offset as u32 };
+    let offset_owner = offset(&owner, SECURITY_DESCRIPTOR_HEADER_SIZE);
+    let offset_group = offset(&group, SECURITY_DESCRIPTOR_HEADER_SIZE + owner.len());
+    let offset_dacl = SECURITY_DESCRIPTOR_HEADER_SIZE + owner.len() + group.len();
+
+    let control = SdControl::SE_SELF_RELATIVE | SdControl::SE_DACL_PRESENT;
+    let mut nt = vec![1u8, 0];
+    nt.extend(control.bits().to_le_bytes());
+    nt.extend(offset_owner.to_le_bytes());
+    nt.extend(offset_group.to_le_bytes());
+    nt.extend(0u32.to_le_bytes());
+    nt.extend((offset_dacl as u32).to_le_bytes());
+    nt.extend(owner);
+    nt.extend(group);
+    nt.extend(serialize_acl(dacl));
+    nt
+}
+
+/// Function to parse the DACL of one descriptor with only access allowed and denied ACEs without object type.
+/// Err for other ACEs, they would be lost by a descriptor serialized again.
+pub fn parse_simple_dacl(nt: &[u8]) -> Result<Vec<SimpleAce>, String>
+{
+    let descriptor = SecurityDescriptor::parse(nt).map_err(|err| err.to_string())?.1;
+    let dacl = match descriptor.parse_dacl(nt)? {
+        Some(dacl) => dacl,
+        None => return Ok(Vec::new()),
+    };
+    let mut aces: Vec<SimpleAce> = Vec::new();
+    for ace in dacl.data {
+        match ace.data {
+            AceFormat::AceAllowed(allowed) if ace.ace_type == ACCESS_ALLOWED_ACE_TYPE || ace.ace_type == ACCESS_DENIED_ACE_TYPE => {
+                aces.push(SimpleAce { ace_type: ace.ace_type, ace_flags: ace.ace_flags, mask: allowed.mask, sid: Sid::from(&allowed.sid) });
+            },
+            _ => return Err(format!("ACE of type {} can't be kept", ace.ace_type)),
+        }
+    }
+    Ok(aces)
+}
+
+/// SDDL names of the ACE flags
+const SDDL_ACE_FLAGS: [(u8, &str); 5] = [
+    (OBJECT_INHERIT_ACE, "OI"),
+    (CONTAINER_INHERIT_ACE, "CI"),
+    (NO_PROPAGATE_INHERIT_ACE, "NP"),
+    (INHERIT_ONLY_ACE, "IO"),
+    (INHERITED_ACE, "ID"),
+];
+
+/// Function to render the owner, group and DACL of one descriptor as SDDL, with SIDs and masks not aliased.
+/// ACEs other than access allowed and denied ones are rendered with their type only.
+/// <https://learn.microsoft.com/en-us/windows/win32/secauthz/security-descriptor-string-format>
+pub fn to_sddl(nt: &[u8]) -> Result<String, String>
+{
+    let descriptor = SecurityDescriptor::parse(nt).map_err(|err| err.to_string())?.1;
+    let mut sddl = String::new();
+    if let Some(owner) = descriptor.parse_owner(nt)? {
+        sddl.push_str(&format!("O:{}", Sid::from(&owner)));
+    }
+    if let Some(group) = descriptor.parse_group(nt)? {
+        sddl.push_str(&format!("G:{}", Sid::from(&group)));
+    }
+    let dacl = match descriptor.parse_dacl(nt)? {
+        Some(dacl) => dacl,
+        None => return Ok(sddl),
+    };
+    sddl.push_str("D:");
+    if descriptor.dacl_protected() {
+        sddl.push('P');
+    }
+    if descriptor.control.contains(SdControl::SE_DACL_AUTO_INHERITED) {
+        sddl.push_str("AI");
+    }
+    for ace in dacl.data {
+        let flags: String = SDDL_ACE_FLAGS.iter().filter(|(flag, _)| ace.ace_flags & flag == *flag).map(|(_, name)| *name).collect();
+        let guid = |guid: Option<u128>| guid.map(|guid| decode_guid(&guid.to_le_bytes().to_vec()).to_lowercase()).unwrap_or_default();
+        match ace.data {
+            AceFormat::AceAllowed(allowed) => {
+                let ace_type = if ace.ace_type == ACCESS_DENIED_ACE_TYPE { "D" } else { "A" };
+                sddl.push_str(&format!("({};{};{:#010x};;;{})", ace_type, flags, allowed.mask, Sid::from(&allowed.sid)));
+            },
+            AceFormat::AceObjectAllowed(object) => {
+                let ace_type = if ace.ace_type == ACCESS_DENIED_OBJECT_ACE_TYPE { "OD" } else { "OA" };
+                sddl.push_str(&format!("({};{};{:#010x};{};{};{})", ace_type, flags, object.mask, guid(object.object_type), guid(object.inherited_object_type), Sid::from(&object.sid)));
+            },
+            AceFormat::Empty => sddl.push_str(&format!("({:#04x};{};;;;)", ace.ace_type, flags)),
+        }
+    }
+    Ok(sddl)
+}
+
 /// Test functions
 #[test]
 #[rustfmt::skip]
@@ -333,7 +713,9 @@ pub fn test_security_descriptor() {
     let nt = SecurityDescriptor::parse(&original).unwrap().1;
     assert_eq!(nt.revision, 1);
     assert_eq!(nt.sbz1, 0);
-    assert_eq!(nt.control, 35844);
+    assert_eq!(nt.control.bits(), 35844);
+    assert!(nt.control.contains(SdControl::SE_DACL_PRESENT | SdControl::SE_SELF_RELATIVE));
+    assert!(!nt.dacl_protected());
     assert_eq!(nt.offset_owner, 2424);
     assert_eq!(nt.offset_group, 0);
     assert_eq!(nt.offset_sacl, 0);
@@ -414,4 +796,102 @@ pub fn test_acl_admin() {
         println!("[{} ACE.DATA] {:?}\n", count, &ace.data);
         count +=1;
     }
-}
\ No newline at end of file
+}
+#[test]
+#[rustfmt::skip]
+pub fn test_malformed_descriptors() {
+    // ace_size lower than the ACE header
+    assert!(Ace::parse(&[0x00, 0x00, 0x02, 0x00, 0xff, 0x01, 0x0f, 0x00]).is_err());
+    // Truncated SID, 2 sub authorities announced and only one
+    assert!(Ace::parse(&[0x00, 0x00, 0x14, 0x00, 0xff, 0x01, 0x0f, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00]).is_err());
+    // 255 sub authorities
+    assert!(LdapSid::parse(&[0x01, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00]).is_err());
+    // Unknown ACE type and unknown object ACE flags
+    let ace = Ace::parse(&[0x11, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00]).unwrap().1;
+    assert!(AceFormat::get_sid(ace.data).is_none());
+    assert!(ObjectAceFlags::parse(&[0xff, 0xff, 0xff, 0xff]).is_ok());
+    // 65535 ACEs announced in an ACL of 8 bytes
+    assert!(Acl::parse(&[0x04, 0x00, 0x08, 0x00, 0xff, 0xff, 0x00, 0x00]).is_err());
+    // Truncated security descriptor
+    assert!(SecurityDescriptor::parse(&[0x01, 0x00, 0x04, 0x8c, 0x78]).is_err());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_security_descriptor_offsets() {
+    let owner = vec![0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00, 0x20, 0x02, 0x00, 0x00];
+    let dacl = vec![
+        0x04, 0x00, 0x24, 0x00, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x1c, 0x00, 0xff, 0x01, 0x0f, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x20, 0x00, 0x00, 0x00, 0x20, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    // Owner before the DACL, no group and no SACL
+    let mut nt = vec![0x01, 0x00, 0x04, 0x80, 20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 36, 0x00, 0x00, 0x00];
+    nt.extend(&owner);
+    nt.extend(&dacl);
+    // Trailing data after the DACL isn't parsed as ACEs
+    nt.extend(&[0xff; 8]);
+    let secdesc = SecurityDescriptor::parse(&nt).unwrap().1;
+    assert_eq!(secdesc.parse_owner(&nt).unwrap().unwrap().sub_authority, vec![32, 544]);
+    assert!(secdesc.parse_group(&nt).unwrap().is_none());
+    assert!(secdesc.parse_sacl(&nt).unwrap().is_none());
+    assert_eq!(secdesc.parse_dacl(&nt).unwrap().unwrap().ace_count, 1);
+
+    // Offsets in the header or out of the buffer
+    let mut secdesc = SecurityDescriptor { revision: 1, sbz1: 0, control: SdControl::SE_DACL_PRESENT | SdControl::SE_SELF_RELATIVE, offset_owner: 4, offset_group: 0, offset_sacl: 0, offset_dacl: 4096 };
+    assert!(secdesc.parse_owner(&nt).is_err());
+    assert!(secdesc.parse_dacl(&nt).is_err());
+    // DACL absent, the offset isn't used
+    secdesc.control = SdControl::SE_SELF_RELATIVE;
+    assert!(secdesc.parse_dacl(&nt).unwrap().is_none());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_access_mask() {
+    assert_eq!(AccessMask::new(0x000f01ff).describe(), "GenericAll");
+    assert_eq!(AccessMask::new(0x10000000).describe(), "GenericAll");
+    assert!(AccessMask::new(0x40000000).contains(AccessMask::GENERIC_WRITE));
+    assert!(!AccessMask::new(0x40000000).contains(AccessMask::SET_GENERIC_WRITE));
+    assert_eq!(AccessMask::new(0x000c0000).describe(), "WriteOwner|WriteDacl");
+    assert_eq!(AccessMask::new(0x00020028).describe(), "GenericWrite");
+    assert_eq!(AccessMask::new(0x000200bc).describe(), "GenericWrite|GenericRead");
+    assert_eq!(AccessMask::new(0x00000130).describe(), "ReadProperty|WriteProperty|ExtendedRight");
+    assert_eq!(AccessMask::new(0).describe(), "None");
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_serialize_security_descriptor() {
+    use std::str::FromStr;
+    let owner = Sid::from_str("S-1-5-32-544").unwrap();
+    let attacker = Sid::from_str("S-1-5-21-1-2-3-1105").unwrap();
+    let nt = serialize_security_descriptor(Some(&owner), None, &[SimpleAce::allowed(AccessMask::GENERIC_ALL.bits(), attacker.to_owned())]);
+
+    let descriptor = SecurityDescriptor::parse(&nt).unwrap().1;
+    assert!(descriptor.control.contains(SdControl::SE_DACL_PRESENT | SdControl::SE_SELF_RELATIVE));
+    assert_eq!(descriptor.offset_owner, 20);
+    assert_eq!(descriptor.offset_group, 0);
+    assert_eq!(descriptor.offset_dacl, 36);
+    assert_eq!(Sid::from(&descriptor.parse_owner(&nt).unwrap().unwrap()), owner);
+    // ACL header 8 bytes, ACE header and mask 8 bytes, SID 28 bytes
+    assert_eq!(nt.len(), 36 + 8 + 8 + 28);
+
+    let aces = parse_simple_dacl(&nt).unwrap();
+    assert_eq!(aces, vec![SimpleAce::allowed(0x000F01FF, attacker)]);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_to_sddl() {
+    use std::str::FromStr;
+    let owner = Sid::from_str("S-1-5-32-544").unwrap();
+    let group = Sid::from_str("S-1-5-21-1-2-3-512").unwrap();
+    let attacker = Sid::from_str("S-1-5-21-1-2-3-1105").unwrap();
+    let mut ace = SimpleAce::allowed(AccessMask::GENERIC_ALL.bits(), attacker);
+    ace.ace_flags = CONTAINER_INHERIT_ACE | INHERITED_ACE;
+
+    let nt = serialize_security_descriptor(Some(&owner), Some(&group), &[ace]);
+    assert_eq!(to_sddl(&nt).unwrap(), "O:S-1-5-32-544G:S-1-5-21-1-2-3-512D:(A;CIID;0x000f01ff;;;S-1-5-21-1-2-3-1105)");
+    assert_eq!(to_sddl(&serialize_security_descriptor(None, None, &[])).unwrap(), "D:");
+    assert!(to_sddl(&[0x01]).is_err());
+}
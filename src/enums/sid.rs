@@ -1,20 +1,259 @@
 use crate::enums::secdesc::LdapSid;
 use log::{trace,error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use std::convert::TryInto;
+use std::fmt;
+use std::str::FromStr;
+
+/// Maximum value of the 48 bits identifier authority
+pub const MAX_IDENTIFIER_AUTHORITY: u64 = (1 << 48) - 1;
+/// Maximum number of sub authorities in one SID
+pub const MAX_SUB_AUTHORITIES: usize = 15;
+
+/// Security identifier, `S-1-5-21-1004336348-1177238915-682003330-512`.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-dtyp/c92a27b1-c772-4fa7-a432-15df5f1b66a1>
+///
+/// The identifier authority is a 48 bits big endian value, written in decimal below 2^32
+/// and in hexadecimal (`0x` and 12 digits) above, like Windows does.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Sid {
+    pub revision: u8,
+    pub identifier_authority: u64,
+    pub sub_authorities: Vec<u32>,
+}
+
+/// Error when parsing one SID.
+#[derive(Debug, Error, PartialEq)]
+pub enum SidError {
+    #[error("SID doesn't start with S-: {0}")]
+    InvalidPrefix(String),
+    #[error("Invalid SID component {0}")]
+    InvalidComponent(String),
+    #[error("SID identifier authority out of 48 bits: {0}")]
+    AuthorityOutOfRange(u64),
+    #[error("SID with {0} sub authorities, 15 maximum")]
+    TooManySubAuthorities(usize),
+    #[error("Binary SID truncated")]
+    Truncated,
+}
+
+impl Sid {
+    /// Function to parse one binary SID (objectSid, ACE trustee...).
+    pub fn from_bytes(raw_sid: &[u8]) -> Result<Sid, SidError> {
+        if raw_sid.len() < 8 {
+            return Err(SidError::Truncated)
+        }
+        let count = raw_sid[1] as usize;
+        if count > MAX_SUB_AUTHORITIES {
+            return Err(SidError::TooManySubAuthorities(count))
+        }
+        if raw_sid.len() < 8 + 4 * count {
+            return Err(SidError::Truncated)
+        }
+        let identifier_authority = raw_sid[2..8].iter().fold(0u64, |authority, byte| (authority << 8) | *byte as u64);
+        let sub_authorities = raw_sid[8..8 + 4 * count]
+            .chunks(4)
+            .map(|sub_authority| u32::from_le_bytes(sub_authority.try_into().unwrap()))
+            .collect();
+        Ok(Sid { revision: raw_sid[0], identifier_authority, sub_authorities })
+    }
+
+    /// Function to get the binary SID.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut raw_sid = vec![self.revision, self.sub_authorities.len() as u8];
+        raw_sid.extend_from_slice(&self.identifier_authority.to_be_bytes()[2..]);
+        for sub_authority in &self.sub_authorities {
+            raw_sid.extend_from_slice(&sub_authority.to_le_bytes());
+        }
+        raw_sid
+    }
+
+    /// Function to get the domain SID `S-1-5-21-x-y-z`, None for SIDs with less than 4 sub authorities.
+    pub fn domain_sid(&self) -> Option<Sid> {
+        if self.sub_authorities.len() < 4 {
+            return None
+        }
+        Some(Sid { revision: self.revision, identifier_authority: self.identifier_authority, sub_authorities: self.sub_authorities[..4].to_vec() })
+    }
+
+    /// Function to get the RID, the last sub authority.
+    pub fn rid(&self) -> Option<u32> {
+        self.sub_authorities.last().copied()
+    }
+}
+
+impl From<&LdapSid> for Sid {
+    fn from(sid: &LdapSid) -> Sid {
+        let identifier_authority = sid.identifier_authority.value.iter().fold(0u64, |authority, byte| (authority << 8) | *byte as u64);
+        Sid { revision: sid.revision, identifier_authority, sub_authorities: sid.sub_authority.to_owned() }
+    }
+}
+
+impl fmt::Display for Sid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "S-{}-", self.revision)?;
+        if self.identifier_authority >= 1 << 32 {
+            write!(f, "0x{:012X}", self.identifier_authority)?;
+        } else {
+            write!(f, "{}", self.identifier_authority)?;
+        }
+        for sub_authority in &self.sub_authorities {
+            write!(f, "-{}", sub_authority)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Sid {
+    type Err = SidError;
+
+    fn from_str(sid: &str) -> Result<Sid, SidError> {
+        let components = match sid.strip_prefix("S-").or_else(|| sid.strip_prefix("s-")) {
+            Some(components) => components.split('-').collect::<Vec<&str>>(),
+            None => return Err(SidError::InvalidPrefix(sid.to_string())),
+        };
+        if components.len() < 2 {
+            return Err(SidError::InvalidComponent(sid.to_string()))
+        }
+        let revision = components[0].parse::<u8>().map_err(|_| SidError::InvalidComponent(components[0].to_string()))?;
+        let identifier_authority = match components[1].strip_prefix("0x").or_else(|| components[1].strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => components[1].parse::<u64>(),
+        }.map_err(|_| SidError::InvalidComponent(components[1].to_string()))?;
+        if identifier_authority > MAX_IDENTIFIER_AUTHORITY {
+            return Err(SidError::AuthorityOutOfRange(identifier_authority))
+        }
+        if components.len() - 2 > MAX_SUB_AUTHORITIES {
+            return Err(SidError::TooManySubAuthorities(components.len() - 2))
+        }
+        let sub_authorities = components[2..].iter()
+            .map(|sub_authority| sub_authority.parse::<u32>().map_err(|_| SidError::InvalidComponent(sub_authority.to_string())))
+            .collect::<Result<Vec<u32>, SidError>>()?;
+        Ok(Sid { revision, identifier_authority, sub_authorities })
+    }
+}
+
+impl Serialize for Sid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Sid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Sid, D::Error> {
+        let sid = String::deserialize(deserializer)?;
+        sid.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Function to get the domain SID of one SID string, None if it isn't a domain principal SID.
+/// `S-1-5-21-1-2-3-1104` => `S-1-5-21-1-2-3`
+pub fn get_domain_sid(sid: &str) -> Option<String> {
+    sid.parse::<Sid>().ok()?.domain_sid().map(|domain_sid| domain_sid.to_string())
+}
+
+/// Well-known groups by localized name and RID of the domain group or built-in SID.
+/// Only used when the SID isn't known, like members of trusted domains referenced by DN or names given by the user.
+/// Japanese and Chinese domains keep the English names.
+/// <https://learn.microsoft.com/en-us/windows-server/identity/ad-ds/manage/understand-security-identifiers>
+pub const WELL_KNOWN_GROUPS: [(&str, &str); 75] = [
+    // English
+    ("Domain Admins", "-512"),
+    ("Domain Users", "-513"),
+    ("Domain Guests", "-514"),
+    ("Domain Computers", "-515"),
+    ("Domain Controllers", "-516"),
+    ("Cert Publishers", "-517"),
+    ("Schema Admins", "-518"),
+    ("Enterprise Admins", "-519"),
+    ("Group Policy Creator Owners", "-520"),
+    ("Read-only Domain Controllers", "-521"),
+    ("Key Admins", "-526"),
+    ("Enterprise Key Admins", "-527"),
+    ("Administrators", "S-1-5-32-544"),
+    ("Account Operators", "S-1-5-32-548"),
+    ("Server Operators", "S-1-5-32-549"),
+    ("Print Operators", "S-1-5-32-550"),
+    ("Backup Operators", "S-1-5-32-551"),
+    ("Remote Desktop Users", "S-1-5-32-555"),
+    ("Distributed COM Users", "S-1-5-32-562"),
+    ("Remote Management Users", "S-1-5-32-580"),
+    // French
+    ("Admins du domaine", "-512"),
+    ("Administrateurs du domaine", "-512"),
+    ("Utilisateurs du domaine", "-513"),
+    ("Invités du domaine", "-514"),
+    ("Invites de domaine", "-514"),
+    ("Ordinateurs du domaine", "-515"),
+    ("Ordinateurs de domaine", "-515"),
+    ("Contrôleurs de domaine", "-516"),
+    ("Éditeurs de certificats", "-517"),
+    ("Editeurs de certificats", "-517"),
+    ("Administrateurs du schéma", "-518"),
+    ("Administrateurs du schema", "-518"),
+    ("Administrateurs de l'entreprise", "-519"),
+    ("Administrateurs", "S-1-5-32-544"),
+    ("Opérateurs de compte", "S-1-5-32-548"),
+    ("Opérateurs de serveur", "S-1-5-32-549"),
+    ("Opérateurs d'impression", "S-1-5-32-550"),
+    ("Opérateurs de sauvegarde", "S-1-5-32-551"),
+    ("Utilisateurs du Bureau à distance", "S-1-5-32-555"),
+    ("Utilisateurs du modèle COM distribué", "S-1-5-32-562"),
+    ("Utilisateurs de gestion à distance", "S-1-5-32-580"),
+    // German
+    ("Domänen-Admins", "-512"),
+    ("Domänen-Benutzer", "-513"),
+    ("Domänen-Gäste", "-514"),
+    ("Domänencomputer", "-515"),
+    ("Domänencontroller", "-516"),
+    ("Zertifikatherausgeber", "-517"),
+    ("Schema-Admins", "-518"),
+    ("Organisations-Admins", "-519"),
+    ("Richtlinien-Ersteller-Besitzer", "-520"),
+    ("Schreibgeschützte Domänencontroller", "-521"),
+    ("Administratoren", "S-1-5-32-544"),
+    ("Konten-Operatoren", "S-1-5-32-548"),
+    ("Server-Operatoren", "S-1-5-32-549"),
+    ("Druck-Operatoren", "S-1-5-32-550"),
+    ("Sicherungs-Operatoren", "S-1-5-32-551"),
+    ("Remotedesktopbenutzer", "S-1-5-32-555"),
+    ("Distributed COM-Benutzer", "S-1-5-32-562"),
+    ("Remoteverwaltungsbenutzer", "S-1-5-32-580"),
+    // Spanish
+    ("Admins. del dominio", "-512"),
+    ("Usuarios del dominio", "-513"),
+    ("Invitados del dominio", "-514"),
+    ("Equipos del dominio", "-515"),
+    ("Controladores de dominio", "-516"),
+    ("Publicadores de certificados", "-517"),
+    ("Administradores de esquema", "-518"),
+    ("Administradores de empresas", "-519"),
+    ("Administradores", "S-1-5-32-544"),
+    ("Opers. de cuentas", "S-1-5-32-548"),
+    ("Operadores de servidores", "S-1-5-32-549"),
+    ("Opers. de impresión", "S-1-5-32-550"),
+    ("Operadores de copia de seguridad", "S-1-5-32-551"),
+    ("Usuarios de escritorio remoto", "S-1-5-32-555"),
+    ("Usuarios COM distribuidos", "S-1-5-32-562"),
+    ("Usuarios de administración remota", "S-1-5-32-580"),
+];
+
+/// Function to get the RID of one well-known group from its name in any supported language, None if unknown.
+/// `Domänen-Admins` or `DOMÄNEN-ADMINS@CORP.LOCAL` => `-512`, `Administrateurs` => `S-1-5-32-544`
+pub fn get_well_known_rid(name: &str) -> Option<&'static str> {
+    let name = name.split('@').next().unwrap_or("").trim().to_uppercase();
+    WELL_KNOWN_GROUPS.iter()
+        .find(|(group, _)| group.to_uppercase() == name)
+        .map(|(_, rid)| *rid)
+}
 
 /// Function to make SID String from ldap_sid struct
+/// Well-known SIDs (`S-1-5-32-544`) are prefixed by the domain name like BloodHound expects.
 pub fn sid_maker(sid: LdapSid, domain: &String) -> String {
-    let mut sub: String = "".to_owned();
     trace!("sid_maker before: {:?}",&sid);
-    for v in &sid.sub_authority {
-        sub.push_str(&"-".to_owned());
-        sub.push_str(&v.to_string());
-    }
-
-    let mut result: String = "S-".to_owned();
-    result.push_str(&sid.revision.to_string());
-    result.push_str(&"-");
-    result.push_str(&sid.identifier_authority.value[5].to_string());
-    result.push_str(&sub);
+    let result = Sid::from(&sid).to_string();
 
     let mut final_sid: String = "".to_owned();
     if result.len() <= 16 {
@@ -215,3 +454,57 @@ pub fn decode_sid(raw_sid: &Vec<u8>, domain: &String) -> String
     return str_sid   
 }
 */
+
+#[test]
+#[rustfmt::skip]
+pub fn test_sid() {
+    let sid: Sid = "S-1-5-21-1004336348-1177238915-682003330-512".parse().unwrap();
+    assert_eq!(sid.identifier_authority, 5);
+    assert_eq!(sid.rid(), Some(512));
+    assert_eq!(sid.domain_sid().unwrap().to_string(), "S-1-5-21-1004336348-1177238915-682003330");
+    assert_eq!(Sid::from_bytes(&sid.to_bytes()).unwrap(), sid);
+    assert_eq!(serde_json::to_string(&sid).unwrap(), "\"S-1-5-21-1004336348-1177238915-682003330-512\"");
+
+    // 48 bits authority
+    let sid = Sid::from_bytes(&[1, 1, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 1, 0, 0, 0]).unwrap();
+    assert_eq!(sid.identifier_authority, 0x123456789abc);
+    assert_eq!(sid.to_string(), "S-1-0x123456789ABC-1");
+    assert_eq!("S-1-0x123456789ABC-1".parse::<Sid>().unwrap(), sid);
+    let ldap_sid = LdapSid::parse(&sid.to_bytes()).unwrap().1;
+    assert_eq!(Sid::from(&ldap_sid), sid);
+
+    assert_eq!("S-1-281474976710656".parse::<Sid>(), Err(SidError::AuthorityOutOfRange(1 << 48)));
+    assert!("1-5-32-544".parse::<Sid>().is_err());
+    assert!("S-1-5-x".parse::<Sid>().is_err());
+    assert_eq!(Sid::from_bytes(&[1, 2, 0, 0, 0, 0, 0, 5, 32, 0, 0, 0]), Err(SidError::Truncated));
+    assert_eq!(get_domain_sid("S-1-5-21-1-2-3-1104"), Some("S-1-5-21-1-2-3".to_string()));
+    assert_eq!(get_domain_sid("ESSOS.LOCAL-S-1-5-32-544"), None);
+    assert_eq!(sid_maker(LdapSid::parse(&"S-1-5-32-544".parse::<Sid>().unwrap().to_bytes()).unwrap().1, &"essos.local".to_string()), "ESSOS.LOCAL-S-1-5-32-544");
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_well_known_rid() {
+    assert_eq!(get_well_known_rid("Domain Admins"), Some("-512"));
+    assert_eq!(get_well_known_rid("DOMÄNEN-ADMINS@CORP.LOCAL"), Some("-512"));
+    assert_eq!(get_well_known_rid("contrôleurs de domaine"), Some("-516"));
+    assert_eq!(get_well_known_rid("Administradores"), Some("S-1-5-32-544"));
+    assert_eq!(get_well_known_rid("Remotedesktopbenutzer"), Some("S-1-5-32-555"));
+    assert_eq!(get_well_known_rid("Domain Admins Backup"), None);
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_sid_round_trip(
+        revision in proptest::prelude::any::<u8>(),
+        identifier_authority in 0..=MAX_IDENTIFIER_AUTHORITY,
+        sub_authorities in proptest::collection::vec(proptest::prelude::any::<u32>(), 0..=MAX_SUB_AUTHORITIES),
+    ) {
+        let sid = Sid { revision, identifier_authority, sub_authorities };
+        proptest::prop_assert_eq!(sid.to_string().parse::<Sid>().unwrap(), sid.to_owned());
+        proptest::prop_assert_eq!(Sid::from_bytes(&sid.to_bytes()).unwrap(), sid.to_owned());
+        let json = serde_json::to_string(&sid).unwrap();
+        proptest::prop_assert_eq!(serde_json::from_str::<Sid>(&json).unwrap(), sid);
+    }
+}
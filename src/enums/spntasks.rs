@@ -1,5 +1,5 @@
 use serde_json::json;
-use crate::json::templates::bh_41::prepare_mssqlsvc_spn_json_template;
+use crate::json::templates::bh_41::{prepare_mssqlsvc_spn_json_template,prepare_mssql_instance_json_template};
 //use log::trace;
 
 /// Function to check if spns start with mssqlsvc to make SPNTargets
@@ -49,4 +49,41 @@ pub fn check_spn(serviceprincipalname: &String) -> serde_json::value::Value
       mssqlsvc_spn = json!({});
    }
    return mssqlsvc_spn
+}
+
+/// Function to parse MSSQLSvc spn to get the host, the instance name and the port.
+/// MSSQLSvc/host.domain.local, MSSQLSvc/host.domain.local:1433 or MSSQLSvc/host.domain.local:INSTANCE
+/// <https://learn.microsoft.com/en-us/sql/database-engine/configure-windows/register-a-service-principal-name-for-kerberos-connections>
+pub fn parse_mssql_spn(serviceprincipalname: &String) -> Option<serde_json::value::Value>
+{
+   if !serviceprincipalname.to_lowercase().starts_with("mssqlsvc/")
+   {
+      return None
+   }
+   let mut mssql_instance = prepare_mssql_instance_json_template();
+
+   let split = serviceprincipalname.splitn(2,"/");
+   let vec = split.collect::<Vec<&str>>();
+   if vec.len() < 2 || vec[1].is_empty()
+   {
+      return None
+   }
+
+   let split = vec[1].splitn(2,":");
+   let vec = split.collect::<Vec<&str>>();
+   mssql_instance["Host"] = vec[0].to_uppercase().into();
+   if vec.len() == 2
+   {
+      // Port number or named instance without static port
+      match vec[1].parse::<u16>() {
+         Ok(port) => {
+            mssql_instance["Port"] = port.into();
+         }
+         Err(_err) => {
+            mssql_instance["Instance"] = vec[1].to_uppercase().into();
+            mssql_instance["Port"] = serde_json::Value::Null;
+         }
+      }
+   }
+   return Some(mssql_instance)
 }
\ No newline at end of file
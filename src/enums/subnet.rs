@@ -0,0 +1,50 @@
+use std::net::IpAddr;
+
+/// Function to check if an IP address is in a subnet like "192.168.56.0/24".
+/// Return the prefix length of the subnet if the IP address match, to keep the most specific subnet.
+pub fn ip_in_subnet(ip: &String, subnet: &String) -> Option<u8>
+{
+   let split = subnet.splitn(2,"/");
+   let vec = split.collect::<Vec<&str>>();
+   if vec.len() != 2 {
+      return None
+   }
+   let network = vec[0].parse::<IpAddr>().ok()?;
+   let prefix = vec[1].parse::<u8>().ok()?;
+   let address = ip.parse::<IpAddr>().ok()?;
+
+   match (network, address) {
+      (IpAddr::V4(network), IpAddr::V4(address)) => {
+         if prefix > 32 {
+            return None
+         }
+         let mask: u32 = if prefix == 0 { 0 } else { u32::MAX << (32 - prefix) };
+         if u32::from(network) & mask == u32::from(address) & mask {
+            return Some(prefix)
+         }
+      }
+      (IpAddr::V6(network), IpAddr::V6(address)) => {
+         if prefix > 128 {
+            return None
+         }
+         let mask: u128 = if prefix == 0 { 0 } else { u128::MAX << (128 - prefix) };
+         if u128::from(network) & mask == u128::from(address) & mask {
+            return Some(prefix)
+         }
+      }
+      _ => {}
+   }
+   return None
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_ip_in_subnet() {
+   assert_eq!(ip_in_subnet(&"192.168.56.10".to_string(), &"192.168.56.0/24".to_string()), Some(24));
+   assert_eq!(ip_in_subnet(&"192.168.57.10".to_string(), &"192.168.56.0/24".to_string()), None);
+   assert_eq!(ip_in_subnet(&"10.1.2.3".to_string(), &"10.0.0.0/8".to_string()), Some(8));
+   assert_eq!(ip_in_subnet(&"10.1.2.3".to_string(), &"0.0.0.0/0".to_string()), Some(0));
+   assert_eq!(ip_in_subnet(&"fd00::1".to_string(), &"fd00::/64".to_string()), Some(64));
+   assert_eq!(ip_in_subnet(&"fd00::1".to_string(), &"10.0.0.0/8".to_string()), None);
+   assert_eq!(ip_in_subnet(&"10.1.2.3".to_string(), &"10.0.0.0".to_string()), None);
+}
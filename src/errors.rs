@@ -1,144 +1,114 @@
 //! Errors management
+//!
+//! Every error returned by the RustHound library is a [`RustHoundError`].
+//! The CLI maps each variant to a process exit code with [`RustHoundError::exit_code`],
+//! so automation can decide to trust the output without reading the logs.
+//!
+//! | Exit code | Meaning |
+//! |-----------|---------|
+//! | 0 | Complete collection |
+//! | 10 | Partial collection, PartialError with **--fail-on-partial**, ParseError, NoData |
+//! | 20 | AuthError, bind refused by the LDAP server |
+//! | 30 | LdapError (connection, network, LDAP protocol) |
+//! | 40 | IoError (output files, sinks) |
+//! | 50 | WriteError, change of one write helper refused or failed |
+//! | 60 | `selftest` golden objects don't match |
+//! | 130 | Interrupted with Ctrl-C, partial results written |
+//!
+//! Partial collections are written anyway, their reasons are in the `partial` field of stats.json.
+//! With **--fail-on-partial** nothing is written or uploaded from a partial collection.
 use ldap3::LdapError;
-use std::error::Error as StdError;
-use std::fmt;
-//use std::num::ParseIntError;
-use std::sync::Arc;
+use thiserror::Error;
 
 /// This is a shorthand for `rusthound`-based error results
-pub type Result<T> = std::result::Result<T, Error>;
-pub type Cause = Arc<dyn StdError + Send + Sync>;
-pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+pub type Result<T> = std::result::Result<T, RustHoundError>;
+/// Kept for compatibility with the previous error type name
+pub type Error = RustHoundError;
+
+/// Exit code of a partial collection
+pub const EXIT_PARTIAL: i32 = 10;
+/// Exit code of an authentication failure
+pub const EXIT_AUTH: i32 = 20;
+/// Exit code of a network or LDAP failure
+pub const EXIT_NETWORK: i32 = 30;
+/// Exit code of an output failure
+pub const EXIT_OUTPUT: i32 = 40;
+/// Exit code of a write helper failure
+pub const EXIT_WRITE: i32 = 50;
+/// Exit code of a failed self-test
+pub const EXIT_SELFTEST: i32 = 60;
+/// Exit code of a collection interrupted with Ctrl-C
+pub const EXIT_INTERRUPTED: i32 = 130;
 
 /// RustHound error's type
-pub struct Error {
-    kind: Kind,
-    cause: Option<Cause>,
-    desc: Option<String>,
+#[derive(Debug, Error)]
+pub enum RustHoundError {
+    /// LDAP connection or request error
+    #[error("LDAP Error: {0}")]
+    LdapError(#[from] LdapError),
+    /// Failed to parse one attribute of one LDAP object
+    #[error("Failed to parse {attr} for {dn}")]
+    ParseError {
+        dn: String,
+        attr: String,
+    },
+    /// Output files or sinks error
+    #[error("IO Error: {0}")]
+    IoError(#[from] std::io::Error),
+    /// Bind failed or authentication method not available
+    #[error("Failed to authenticate to {domain} Active Directory. Reason: {reason}")]
+    AuthError {
+        domain: String,
+        reason: String,
+    },
+    /// LDAP search returned no object
+    #[error("No data collected on {domain} Active Directory")]
+    NoData {
+        domain: String,
+    },
+    /// Collection partial with --fail-on-partial, nothing written
+    #[error("Partial collection, nothing written with --fail-on-partial: {reasons}")]
+    PartialError {
+        reasons: String,
+    },
+    /// Change of one write helper refused by a check or by the LDAP server
+    #[error("Failed to write {dn}. Reason: {reason}")]
+    WriteError {
+        dn: String,
+        reason: String,
+    },
 }
 
-#[derive(Debug)]
-pub enum Kind {
-    /// Connection
-    Connection(Connection),
-    /// LdapError
-    LdapError,
-    /// Parse Error
-    ParseError,
-    /// Other
-    Other,
-}
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Connection {
-    Login,
-    Host,
-}
-
-impl Error {
-    /// Construct an error from scratch
-    /// You can chain this method to `with`, as shown below.
-    /// ```
-    /// Error::new(Kind::Other).with()
-    /// ```
-    pub fn new(kind: Kind) -> Error {
-        Error {
-            kind,
-            cause: None,
-            desc: None,
-        }
-    }
-
-    /// Specify a cause
-    pub fn with<C: StdError + Send + Sync + 'static>(mut self, cause: C) -> Error {
-        self.cause = Some(Arc::new(cause));
-        self
-    }
-
-    pub fn desc<D: Into<String>>(mut self, desc: D) -> Error {
-        self.desc = Some(desc.into());
-        self
-    }
-
-    /// Get error kind
-    pub fn kind(&self) -> &Kind {
-        &self.kind
-    }
-
-    pub fn new_login() -> Error {
-        Error::new(Kind::Connection(Connection::Login))
-    }
-
-    pub fn new_host() -> Error {
-        Error::new(Kind::Connection(Connection::Host))
-    }
-
-    pub fn new_ldap_error(error: LdapError) -> Error {
-        Error::new(Kind::LdapError).with(error)
-    }
-
-    /// Internally used for Display trait
-    fn kind_description(&self) -> &str {
-        match self.kind {
-            Kind::Connection(Connection::Login) => "[!] LDAP Connection Failed, Invalid Credentials.\n",
-            Kind::Connection(Connection::Host) => "[!] LDAP Connection Failed, No Route To Host.\n",
-            Kind::LdapError => &"LDAP Error.\n",
-            Kind::ParseError => &"Parsing Json Error.\n",
-            Kind::Other => "[!] Other Error\n",
-        }
-    }
-
-    /// Backtrace error source to find a cause matching given type
-    pub fn find_source<E: StdError + 'static>(&self) -> Option<&E> {
-        let mut source = self.source();
-        while let Some(err) = source {
-            if let Some(ref original) = err.downcast_ref() {
-                return Some(original);
-            }
-            source = err.source();
-        }
-        // Not found
-        None
-    }
-}
-
-impl fmt::Debug for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.kind_description())?;
-        if let Some(ref desc) = self.desc {
-            write!(f, ": {}", desc)?;
-        }
-        if let Some(ref cause) = self.cause {
-            write!(f, ": {:?}", cause)?;
+impl RustHoundError {
+    /// Process exit code for the error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            // Bind refused: strongerAuthRequired, inappropriateAuthentication, invalidCredentials
+            RustHoundError::LdapError(LdapError::LdapResult { result }) if [8, 48, 49].contains(&result.rc) => EXIT_AUTH,
+            RustHoundError::LdapError(_) => EXIT_NETWORK,
+            RustHoundError::AuthError { .. } => EXIT_AUTH,
+            RustHoundError::ParseError { .. } => EXIT_PARTIAL,
+            RustHoundError::IoError(_) => EXIT_OUTPUT,
+            RustHoundError::NoData { .. } => EXIT_PARTIAL,
+            RustHoundError::PartialError { .. } => EXIT_PARTIAL,
+            RustHoundError::WriteError { .. } => EXIT_WRITE,
         }
-        Ok(())
     }
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.kind_description())?;
-        if let Some(ref desc) = self.desc {
-            write!(f, ": {}", desc)?;
-        }
-        if let Some(ref cause) = self.cause {
-            write!(f, ": {}", cause)?;
-        }
-        Ok(())
-    }
-}
-
-impl StdError for Error {
-    fn source(&self) -> Option<&(dyn StdError + 'static)> {
-        self.cause
-            .as_ref()
-            .map(|cause| &**cause as &(dyn StdError + 'static))
-    }
-}
-
-/// Converting from `LdapsearchError`
-impl From<LdapError> for Error {
-    fn from(err: LdapError) -> Error {
-        Error::new(Kind::LdapError).with(err)
-    }
+#[test]
+#[rustfmt::skip]
+pub fn test_exit_code() {
+    let err = RustHoundError::AuthError { domain: "ESSOS.LOCAL".to_string(), reason: "invalidCredentials".to_string() };
+    assert_eq!(err.exit_code(), 20);
+    let err: RustHoundError = std::io::Error::new(std::io::ErrorKind::Other, "sink").into();
+    assert_eq!(err.exit_code(), 40);
+    let err = RustHoundError::ParseError { dn: "CN=JORAH.MORMONT,CN=Users,DC=ESSOS,DC=LOCAL".to_string(), attr: "sIDHistory".to_string() };
+    assert_eq!(err.exit_code(), EXIT_PARTIAL);
+    let err = RustHoundError::NoData { domain: "ESSOS.LOCAL".to_string() };
+    assert_eq!(err.exit_code(), EXIT_PARTIAL);
+    let err = RustHoundError::PartialError { reasons: "SYSVOL failure".to_string() };
+    assert_eq!(err.exit_code(), EXIT_PARTIAL);
+    let err = RustHoundError::WriteError { dn: "CN=ATTACKER,CN=Computers,DC=ESSOS,DC=LOCAL".to_string(), reason: "MachineAccountQuota reached".to_string() };
+    assert_eq!(err.exit_code(), EXIT_WRITE);
 }
@@ -3,6 +3,10 @@ use regex::Regex;
 //use log::{info,debug,trace};
 use crate::json::templates::*;
 use crate::ldap::prepare_ldap_dc;
+use crate::enums::subnet::ip_in_subnet;
+use crate::enums::dn::{get_domain_from_dn,get_parent_dn,get_rdn_value,is_conflict_dn,split_dn};
+use crate::enums::sid::{get_domain_sid,get_well_known_rid};
+use crate::json::report::membership::MembershipResolver;
 use indicatif::ProgressBar;
 use crate::banner::progress_bar;
 use std::convert::TryInto;
@@ -31,13 +35,9 @@ pub fn add_default_groups(vec_groups: &mut Vec<serde_json::value::Value>, vec_co
         {
             template_member["ObjectIdentifier"] = computer["ObjectIdentifier"].as_str().unwrap().to_string().into();
             vec_members.push(template_member.to_owned());
-            let re = Regex::new(r"^S-[0-9]{1}-[0-9]{1}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}").unwrap();
-            let mut sids: Vec<String> = Vec::new();
-            for sid in re.captures_iter(&computer["ObjectIdentifier"].as_str().unwrap().to_string())
-            {
-                sids.push(sid[0].to_owned().to_string());
+            if let Some(sid) = get_domain_sid(computer["ObjectIdentifier"].as_str().unwrap_or("")) {
+                domain_sid = sid;
             }
-            domain_sid = sids[0].to_string();
         }
     }
 
@@ -241,71 +241,35 @@ pub fn add_childobjects_members(vec_replaced: &mut Vec<serde_json::value::Value>
         let mut affected_computers: Vec<serde_json::value::Value> = Vec::new();
 
         let null: String = "NULL".to_string();
-        let dn = object["Properties"]["distinguishedname"].as_str().unwrap().to_string().to_uppercase();
-        let mut name = object["Properties"]["name"].as_str().unwrap().to_string();
+        // Same form as the parent DNs, without spaces after commas
+        let dn = split_dn(&object["Properties"]["distinguishedname"].as_str().unwrap().to_uppercase()).join(",");
         let sid = dn_sid.get(&object["Properties"]["distinguishedname"].as_str().unwrap().to_string()).unwrap_or(&null);
-        let otype = sid_type.get(sid).unwrap();
+        let otype = sid_type.get(sid).map(|otype| otype.as_str()).unwrap_or("Unknown");
         //trace!("SID OBJECT: {:?} : {:?} : {:?}",&dn,&sid,&otype);
 
-        if otype != "Domain"
-        {
-            let split = name.split("@");
-            let vec = split.collect::<Vec<&str>>();
-            name = vec[0].to_owned();
-        }
-
         for value in dn_sid 
         {
-            let dn_object = value.0.to_string().to_uppercase();
-            //trace!("{:?}", &dn_object);
-            let split = dn_object.split(",");
-            let vec = split.collect::<Vec<&str>>();
-            let mut first = vec[1].to_owned();
-            //trace!("{:?}", &first);
-            let split = first.split("=");
-            let vec = split.collect::<Vec<&str>>();
-            if vec.len() >= 2 {
-                //trace!("{:?}", &vec.len());
-                first = vec[1].to_owned();
-            }
-            else
+            // Direct child objects only, RDN values can contain escaped commas like CN=Doe\, John
+            let parent = match get_parent_dn(&value.0.to_uppercase()) {
+                Some(parent) => parent,
+                None => continue,
+            };
+            if parent == dn
             {
-                continue
-            }
-            //trace!("{:?}", &first);
+                let mut object = bh_41::prepare_member_json_template();
+                object["ObjectIdentifier"] = value.1.as_str().to_string().into();
+                let object_type = sid_type.get(&value.1.as_str().to_string()).map(|object_type| object_type.as_str()).unwrap_or("Unknown");
+                object["ObjectType"] = object_type.into();
+                direct_members.push(object.to_owned());
 
-            if otype != "Domain"{
-                if (dn_object.contains(&dn)) && (&dn_object != &dn) && (&first == &name)
+                // if the direct object is one computer add it in affected_computers to push it in OU 
+                if otype != "Domain" && object_type == "Computer" 
                 {
-                    let mut object = bh_41::prepare_member_json_template();
-                    object["ObjectIdentifier"] = value.1.as_str().to_string().into();
-                    let object_type = sid_type.get(&value.1.as_str().to_string()).unwrap();
-                    object["ObjectType"] = object_type.to_string().into();
-                    direct_members.push(object.to_owned());
-
-                    // if the direct object is one computer add it in affected_computers to push it in OU 
-                    if object_type.to_string() == "Computer" 
-                    {
-                        affected_computers.push(object.to_owned());
-                    }
-                }
-            }
-            else
-            {
-                let mut object = bh_41::prepare_member_json_template();                 
-                let split = name.split(".");
-                let vec = split.collect::<Vec<&str>>();
-                let cn = vec[0].to_owned();
-                if first.contains(&cn)
-                {
-                    object["ObjectIdentifier"] = value.1.as_str().to_string().into();
-                    let object_type = sid_type.get(&value.1.as_str().to_string()).unwrap();
-                    object["ObjectType"] = object_type.to_string().into();
-                    direct_members.push(object);
+                    affected_computers.push(object.to_owned());
                 }
             }
         }
-        //trace!("direct_members for Object '{}': {:?}",name,direct_members);
+        //trace!("direct_members for Object '{}': {:?}",dn,direct_members);
         
         object["ChildObjects"] = direct_members.into();
         if otype == "OU"
@@ -338,7 +302,8 @@ pub fn replace_guid_gplink(vec_replaced: &mut Vec<serde_json::value::Value>, dn_
                 for value in dn_sid 
                 {
                   //trace!("{:?}",&vec_replaced[i]["Links"][j]["Guid"].as_str().unwrap().to_string());
-                  if value.0.contains(&vec_replaced[i]["Links"][j]["GUID"].as_str().unwrap().to_string())
+                  // gPLink GUIDs can be lowercase, DNs are uppercase
+                  if value.0.contains(&vec_replaced[i]["Links"][j]["GUID"].as_str().unwrap().to_uppercase())
                   {
                         vec_replaced[i]["Links"][j]["GUID"] = value.1.to_owned().into();
                   }
@@ -354,6 +319,10 @@ pub fn add_domain_sid(
     vec_replaced: &mut Vec<serde_json::value::Value>, 
     dn_sid: &HashMap<String, String>)
 {
+    // Nothing to do, and the progress bar can't divide by zero
+    if vec_replaced.is_empty() {
+        return
+    }
     // Needed for progress bar stats
     let pb = ProgressBar::new(1);
     let mut count = 0;
@@ -368,11 +337,8 @@ pub fn add_domain_sid(
         progress_bar(pb.to_owned(),"Getting domain SID".to_string(),pourcentage.try_into().unwrap(),"%".to_string());
 
         let sid = value.1.to_owned();
-        let re = Regex::new(r"^S-[0-9]{1}-[0-9]{1}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}").unwrap();
-        for value in re.captures_iter(&sid) 
-        {
-            domain_sid = value[0].to_owned().to_string();
-            break
+        if let Some(value) = get_domain_sid(&sid) {
+            domain_sid = value;
         }
         if domain_sid.len() > 0 {
             break
@@ -457,7 +423,9 @@ pub fn replace_fqdn_by_sid(vec_src: &mut Vec<serde_json::value::Value>, fqdn_sid
 }
 
 /// This function is to check and replace object name by SID in group members.
-pub fn replace_sid_members(vec_groups: &mut Vec<serde_json::value::Value>, dn_sid: &HashMap<String, String>, sid_type: &HashMap<String, String>, vec_trusts: &Vec<serde_json::value::Value>)
+/// Members of the collected domain not found are orphaned references (deleted or unreadable objects), they are
+/// removed from `Members` and kept in `orphanedmembers` instead of making edges to unknown nodes.
+pub fn replace_sid_members(vec_groups: &mut Vec<serde_json::value::Value>, dn_sid: &HashMap<String, String>, sid_type: &HashMap<String, String>, vec_trusts: &Vec<serde_json::value::Value>, domain: &String)
 {
     // Needed for progress bar stats
     let pb = ProgressBar::new(1);
@@ -473,15 +441,20 @@ pub fn replace_sid_members(vec_groups: &mut Vec<serde_json::value::Value>, dn_si
         progress_bar(pb.to_owned(),"Replacing SID for groups".to_string(),pourcentage.try_into().unwrap(),"%".to_string());
 
         // MEMBER by MEMBER
-        if vec_groups[i]["Members"].as_array().unwrap().len() != 0 {
+        let mut orphaned_members: Vec<String> = Vec::new();
+        if vec_groups[i]["Members"].as_array().map(|members| members.len()).unwrap_or(0) != 0 {
             for j in 0..vec_groups[i]["Members"].as_array().unwrap().len()
             {
                 let null: String = "NULL".to_string();
-                let sid = dn_sid.get(&vec_groups[i]["Members"][j]["ObjectIdentifier"].as_str().unwrap().to_string()).unwrap_or(&null);
+                let dn = vec_groups[i]["Members"][j]["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+                let sid = dn_sid.get(&dn).unwrap_or(&null);
                 if sid.contains("NULL"){
-                    let dn = &vec_groups[i]["Members"][j]["ObjectIdentifier"].as_str().unwrap().to_string();
+                    if is_orphaned_member(&dn, domain) {
+                        orphaned_members.push(dn);
+                        continue
+                    }
                     // Check if DN match trust domain to get SID and Type
-                    let sid = sid_maker_from_another_domain(vec_trusts, dn);
+                    let sid = sid_maker_from_another_domain(vec_trusts, &dn);
                     let type_object = "Group".to_string();
                     vec_groups[i]["Members"][j]["ObjectIdentifier"] = sid.to_owned().into();
                     vec_groups[i]["Members"][j]["ObjectType"] = type_object.to_owned().into();
@@ -495,20 +468,52 @@ pub fn replace_sid_members(vec_groups: &mut Vec<serde_json::value::Value>, dn_si
 
             }
         }
+        if !orphaned_members.is_empty() {
+            if let Some(members) = vec_groups[i]["Members"].as_array_mut() {
+                members.retain(|member| !orphaned_members.iter().any(|dn| member["ObjectIdentifier"] == dn.as_str()));
+            }
+            vec_groups[i]["Properties"]["orphanedmembers"] = orphaned_members.into();
+        }
     }
     pb.finish_and_clear();
 }
+
+/// Function to check if one member DN not found in the collection is an orphaned reference of the collected domain.
+/// Foreign security principals (`CN=S-...`) and members of other domains are resolved with the trusts.
+fn is_orphaned_member(dn: &str, domain: &String) -> bool
+{
+    let rdn_value = split_dn(dn).first().map(|rdn| rdn.to_uppercase()).unwrap_or_default();
+    !rdn_value.starts_with("CN=S-") && get_domain_from_dn(dn).eq_ignore_ascii_case(domain)
+}
+
+/// This function flag conflict objects renamed by replication (`CNF:` in their DN) with `conflictobject`.
+pub fn add_conflict_flag(vec_objects: &mut Vec<serde_json::value::Value>)
+{
+    for object in vec_objects.iter_mut() {
+        if is_conflict_dn(object["Properties"]["distinguishedname"].as_str().unwrap_or("")) {
+            object["Properties"]["conflictobject"] = true.into();
+        }
+    }
+}
 // Make the SID from domain present in trust
 fn sid_maker_from_another_domain(vec_trusts: &Vec<serde_json::value::Value>, object_identifier: &String) -> String
 {
     for i in 0..vec_trusts.len() {
-        let ldap_dc = prepare_ldap_dc(&vec_trusts[i]["TargetDomainName"].as_str().unwrap().to_string(),false);
+        let target_domain = vec_trusts[i]["TargetDomainName"].as_str().unwrap_or("").to_string();
+        if target_domain.is_empty() {
+            continue
+        }
+        let ldap_dc = prepare_ldap_dc(&target_domain,false);
         //trace!("LDAP_DC TRUSTED {:?}: {:?}", &i,&vec_trusts[i]);
         if object_identifier.contains(ldap_dc[0].as_str())
         {
             //trace!("object_identifier '{}' contains trust domain '{}'",&object_identifier, &ldap_dc);
             let id = get_id_from_objectidentifier(object_identifier);
-            let sid = vec_trusts[i]["TargetDomainSid"].as_str().unwrap().to_string() + id.as_str();
+            // Built-in groups are prefixed by the domain name like the collected ones
+            if id.starts_with("S-1-5-32-") {
+                return format!("{}-{}",target_domain.to_uppercase(),id)
+            }
+            let sid = vec_trusts[i]["TargetDomainSid"].as_str().unwrap_or("").to_string() + id.as_str();
             return sid
         }
     }
@@ -522,37 +527,14 @@ fn sid_maker_from_another_domain(vec_trusts: &Vec<serde_json::value::Value>, obj
     return object_identifier.to_string()
 }
 
-// Get id from objectidentifier for all common group (Administrators ...)
+// Get id from objectidentifier for all common group (Administrators ...) by their localized name
 // https://learn.microsoft.com/en-us/windows-server/identity/ad-ds/manage/understand-security-identifiers
 fn get_id_from_objectidentifier(object_identifier: &String) -> String
 {
-    // Hashmap to link GROUP NAME to RID
-    let mut name_to_rid = HashMap::new();
-    name_to_rid.insert("DOMAIN ADMINS".to_string(), "-512".to_string());
-    name_to_rid.insert("ADMINISTRATEURS DU DOMAINE".to_string(), "-512".to_string());
-    name_to_rid.insert("DOMAIN USERS".to_string(), "-513".to_string());
-    name_to_rid.insert("UTILISATEURS DU DOMAINE".to_string(), "-513".to_string());
-    name_to_rid.insert("DOMAIN GUESTS".to_string(), "-514".to_string());
-    name_to_rid.insert("INVITES DE DOMAINE".to_string(), "-514".to_string());
-    name_to_rid.insert("DOMAIN COMPUTERS".to_string(), "-515".to_string());
-    name_to_rid.insert("ORDINATEURS DE DOMAINE".to_string(), "-515".to_string());
-    name_to_rid.insert("DOMAIN CONTROLLERS".to_string(), "-516".to_string());
-    name_to_rid.insert("CONTRÔLEURS DE DOMAINE".to_string(), "-516".to_string());
-    name_to_rid.insert("CERT PUBLISHERS".to_string(), "-517".to_string());
-    name_to_rid.insert("EDITEURS DE CERTIFICATS".to_string(), "-517".to_string());
-    name_to_rid.insert("SCHEMA ADMINS".to_string(), "-518".to_string());
-    name_to_rid.insert("ADMINISTRATEURS DU SCHEMA".to_string(), "-518".to_string());
-    name_to_rid.insert("ENTERPRISE ADMINS".to_string(), "-519".to_string());
-    name_to_rid.insert("ADMINISTRATEURS DE L'ENTREPRISE".to_string(), "-519".to_string());
-
-    for value in name_to_rid {
-        if object_identifier.contains(value.0.as_str())
-        {
-            //trace!("name_to_rid: {:?}", value);
-            return value.1.to_string()
-        }
+    match get_well_known_rid(&get_rdn_value(object_identifier)) {
+        Some(rid) => rid.to_string(),
+        None => "NULL_ID1".to_string(),
     }
-    return "NULL_ID1".to_string()
 }
 
 /// This function push trust domain values in domain
@@ -565,4 +547,1077 @@ pub fn add_trustdomain(vec_domains: &mut Vec<serde_json::value::Value>, vec_trus
         }
         vec_domains[0]["Trusts"] = trusts.to_owned().into();
     }
-}
\ No newline at end of file
+}
+
+/// This function push MSSQL instances found in users and computers MSSQLSvc spn to the hosting computer.
+pub fn add_mssql_instances(vec_users: &mut Vec<serde_json::value::Value>, vec_computers: &mut Vec<serde_json::value::Value>)
+{
+    // Service account SID for each instance
+    let mut vec_instances: Vec<serde_json::value::Value> = Vec::new();
+    for user in vec_users.iter_mut() {
+        let service_account = user["ObjectIdentifier"].to_owned();
+        if let Some(instances) = user["Properties"]["mssqlinstances"].as_array_mut() {
+            for instance in instances.iter_mut() {
+                instance["ServiceAccountSID"] = service_account.to_owned();
+                vec_instances.push(instance.to_owned());
+            }
+        }
+    }
+    for computer in vec_computers.iter_mut() {
+        let service_account = computer["ObjectIdentifier"].to_owned();
+        if let Some(instances) = computer["Properties"]["mssqlinstances"].as_array_mut() {
+            for instance in instances.iter_mut() {
+                instance["ServiceAccountSID"] = service_account.to_owned();
+            }
+        }
+    }
+
+    // Instance by instance
+    for instance in vec_instances {
+        let host = instance["Host"].as_str().unwrap_or("").to_string();
+        for computer in vec_computers.iter_mut() {
+            let name = computer["Properties"]["name"].as_str().unwrap_or("").to_string();
+            let samaccountname = computer["Properties"]["samaccountname"].as_str().unwrap_or("").to_uppercase();
+            if name != host && !name.starts_with(&format!("{}.",host)) && samaccountname != format!("{}$",host) {
+                continue
+            }
+            let instances = computer["Properties"]["mssqlinstances"].as_array_mut().unwrap();
+            let exists = instances.iter().any(|i| i["Instance"] == instance["Instance"] && i["Port"] == instance["Port"]);
+            if !exists {
+                instances.push(instance.to_owned());
+            }
+            break
+        }
+    }
+}
+
+/// This function push subnets and site links in sites.
+pub fn add_site_subnets(vec_sites: &mut Vec<serde_json::value::Value>, vec_subnets: &Vec<serde_json::value::Value>, vec_sitelinks: &Vec<serde_json::value::Value>)
+{
+    for site in vec_sites.iter_mut() {
+        let site_dn = site["Properties"]["distinguishedname"].as_str().unwrap_or("").to_string();
+
+        let mut subnets: Vec<String> = Vec::new();
+        for subnet in vec_subnets {
+            if subnet["Properties"]["site"].as_str().unwrap_or("") == site_dn {
+                subnets.push(subnet["Properties"]["name"].as_str().unwrap_or("").to_string());
+            }
+        }
+        site["Subnets"] = subnets.into();
+
+        let mut sitelinks: Vec<serde_json::value::Value> = Vec::new();
+        for sitelink in vec_sitelinks {
+            if sitelink["Sites"].as_array().unwrap_or(&Vec::new()).iter().any(|s| s.as_str().unwrap_or("") == site_dn) {
+                sitelinks.push(sitelink["Properties"].to_owned());
+            }
+        }
+        site["SiteLinks"] = sitelinks.into();
+    }
+}
+
+/// This function map computers to sites with their IP address and the sites subnets.
+/// The most specific subnet is used if the IP address match several subnets.
+pub fn add_computers_sites(vec_sites: &mut Vec<serde_json::value::Value>, vec_computers: &mut Vec<serde_json::value::Value>, fqdn_ip: &HashMap<String, String>)
+{
+    for computer in vec_computers.iter_mut() {
+        let ip = fqdn_ip.get(computer["Properties"]["name"].as_str().unwrap_or("")).unwrap_or(&"".to_string()).to_owned();
+        if ip.is_empty() {
+            continue
+        }
+        // Site index and prefix length
+        let mut matched: Option<(usize, u8)> = None;
+        for i in 0..vec_sites.len() {
+            for subnet in vec_sites[i]["Subnets"].as_array().unwrap_or(&Vec::new()) {
+                if let Some(prefix) = ip_in_subnet(&ip, &subnet.as_str().unwrap_or("").to_string()) {
+                    if matched.map(|m| prefix > m.1).unwrap_or(true) {
+                        matched = Some((i, prefix));
+                    }
+                }
+            }
+        }
+        if let Some((i, _prefix)) = matched {
+            computer["Properties"]["site"] = vec_sites[i]["Properties"]["name"].to_owned();
+            let mut member = bh_41::prepare_member_json_template();
+            member["ObjectIdentifier"] = computer["ObjectIdentifier"].to_owned();
+            member["ObjectType"] = "Computer".into();
+            vec_sites[i]["Computers"].as_array_mut().unwrap().push(member);
+        }
+    }
+}
+
+
+/// This function push krbtgt password last set in domain.
+pub fn add_krbtgt_pwdlastset(vec_domains: &mut Vec<serde_json::value::Value>, vec_users: &Vec<serde_json::value::Value>)
+{
+    for domain in vec_domains.iter_mut() {
+        let krbtgt_sid = format!("{}-502",domain["ObjectIdentifier"].as_str().unwrap_or("SID"));
+        for user in vec_users {
+            if user["ObjectIdentifier"].as_str().unwrap_or("") == krbtgt_sid {
+                domain["Properties"]["krbtgtpasswordlastset"] = user["Properties"]["pwdlastset"].to_owned();
+                break
+            }
+        }
+    }
+}
+
+/// This function return all spns set on several accounts with accounts ObjectIdentifier.
+/// <https://learn.microsoft.com/en-us/troubleshoot/windows-server/windows-security/kerberos-authentication-troubleshooting-guidance#duplicate-spn>
+pub fn get_duplicate_spns(vec_users: &Vec<serde_json::value::Value>, vec_computers: &Vec<serde_json::value::Value>) -> HashMap<String, Vec<String>>
+{
+    let mut spn_accounts: HashMap<String, Vec<String>> = HashMap::new();
+    for object in vec_users.iter().chain(vec_computers.iter()) {
+        let sid = object["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+        for spn in object["Properties"]["serviceprincipalnames"].as_array().unwrap_or(&Vec::new()) {
+            let accounts = spn_accounts.entry(spn.as_str().unwrap_or("").to_lowercase()).or_insert(Vec::new());
+            if !accounts.contains(&sid) {
+                accounts.push(sid.to_owned());
+            }
+        }
+    }
+    spn_accounts.retain(|_spn, accounts| accounts.len() > 1);
+    spn_accounts
+}
+
+/// This function flag users and computers with duplicate spns.
+pub fn add_duplicate_spn_flag(vec_users: &mut Vec<serde_json::value::Value>, vec_computers: &mut Vec<serde_json::value::Value>)
+{
+    let duplicates = get_duplicate_spns(vec_users, vec_computers);
+    let sids: Vec<&String> = duplicates.values().flatten().collect();
+    for object in vec_users.iter_mut().chain(vec_computers.iter_mut()) {
+        let sid = object["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+        if sids.contains(&&sid) {
+            object["Properties"]["duplicatespn"] = true.into();
+        }
+    }
+}
+
+/// This function flag sAMAccountName and sAMAccountType oddities like noPac (CVE-2021-42278) indicators.
+/// User accounts ending with '$' which are not trust accounts and computer accounts without '$'.
+/// <https://support.microsoft.com/en-us/topic/kb5008102-active-directory-security-accounts-manager-hardening-changes-cve-2021-42278-5975b463-4c95-45e1-831a-d120004e258e>
+pub fn add_samaccountname_anomaly_flag(vec_users: &mut Vec<serde_json::value::Value>, vec_computers: &mut Vec<serde_json::value::Value>)
+{
+    // SAM_NORMAL_USER_ACCOUNT and SAM_MACHINE_ACCOUNT
+    let sam_normal_user_account: i64 = 805306368;
+    let sam_machine_account: i64 = 805306369;
+
+    for user in vec_users.iter_mut() {
+        let samaccountname = user["Properties"]["samaccountname"].as_str().unwrap_or("").to_string();
+        let samaccounttype = user["Properties"]["samaccounttype"].as_i64().unwrap_or(0);
+        if samaccountname.ends_with("$")
+        && !user["Properties"]["trustaccount"].as_bool().unwrap_or(false)
+        && samaccounttype == sam_normal_user_account
+        {
+            user["Properties"]["samaccountnameanomaly"] = true.into();
+        }
+    }
+    for computer in vec_computers.iter_mut() {
+        let samaccountname = computer["Properties"]["samaccountname"].as_str().unwrap_or("").to_string();
+        let samaccounttype = computer["Properties"]["samaccounttype"].as_i64().unwrap_or(0);
+        if !samaccountname.is_empty() && !samaccountname.ends_with("$") {
+            computer["Properties"]["samaccountnameanomaly"] = true.into();
+        }
+        if samaccounttype != 0 && samaccounttype != sam_machine_account {
+            computer["Properties"]["samaccountnameanomaly"] = true.into();
+        }
+    }
+}
+
+/// Function to check if computer is a domain controller with primary group "Domain Controllers" or "Read-only Domain Controllers".
+pub fn is_domain_controller(computer: &serde_json::value::Value) -> bool
+{
+    let primary_group = computer["PrimaryGroupSID"].as_str().unwrap_or("");
+    primary_group.ends_with("-516") || primary_group.ends_with("-521")
+}
+
+/// This function flag users and computers without logon for `stale_days` days with `dormant`.
+/// lastLogonTimestamp is replicated every 9 to 14 days so the last logon can be up to 14 days older.
+/// Accounts never used are dormant once created for more than `stale_days` days.
+/// Return the number of dormant objects.
+pub fn add_dormant_flag(
+    vec_users: &mut Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    stale_days: i64,
+    now: i64,
+) -> usize
+{
+    let limit = now - stale_days * 86400;
+    let mut count = 0;
+    for object in vec_users.iter_mut().chain(vec_computers.iter_mut()) {
+        let lastlogon = object["Properties"]["lastlogontimestamp"].as_i64().unwrap_or(-1)
+            .max(object["Properties"]["lastlogon"].as_i64().unwrap_or(-1));
+        let dormant = match lastlogon > 0 {
+            true => lastlogon < limit,
+            false => object["Properties"]["whencreated"].as_i64().unwrap_or(-1) < limit,
+        };
+        object["Properties"]["dormant"] = dormant.into();
+        if dormant {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Privileged RIDs of any domain (Domain Admins, Schema Admins, Enterprise Admins) and built-in Administrators
+/// which should never be found in one sIDHistory.
+pub const PRIVILEGED_HISTORY_RIDS: [&str; 4] = ["-512", "-518", "-519", "S-1-5-32-544"];
+
+/// This function flag principals with a suspicious sIDHistory, likely SID history injection (mimikatz `sid::add`):
+/// `sidhistorysamedomain` for SIDs of their own domain, migrations only add SIDs of other domains,
+/// `sidhistoryprivileged` for SIDs of privileged groups.
+/// <https://adsecurity.org/?p=1772>
+pub fn add_sid_history_flags(vec_objects: &mut Vec<serde_json::value::Value>)
+{
+    for object in vec_objects.iter_mut() {
+        let sid_history: Vec<String> = object["Properties"]["sidhistory"].as_array().unwrap_or(&Vec::new()).iter()
+            .filter_map(|sid| sid.as_str().map(|sid| sid.to_uppercase()))
+            .collect();
+        if sid_history.is_empty() {
+            continue
+        }
+        let domain_sid = object["Properties"]["domainsid"].as_str()
+            .map(|sid| sid.to_uppercase())
+            .or_else(|| object["ObjectIdentifier"].as_str().and_then(get_domain_sid))
+            .unwrap_or_default();
+        let same_domain = !domain_sid.is_empty()
+            && sid_history.iter().any(|sid| get_domain_sid(sid).map(|history_domain| history_domain == domain_sid).unwrap_or(false));
+        let privileged = sid_history.iter().any(|sid| PRIVILEGED_HISTORY_RIDS.iter().any(|rid| sid.ends_with(rid)));
+        object["Properties"]["sidhistorysamedomain"] = same_domain.into();
+        object["Properties"]["sidhistoryprivileged"] = privileged.into();
+    }
+}
+
+/// This function flag Entra Connect (Azure AD Connect) sync accounts and servers.
+/// MSOL_ accounts have DCSync rights and their description gives the server running Entra Connect.
+/// AZUREADSSOACC$ computer is used by Seamless SSO, its Kerberos key can forge tickets for any synced user.
+/// <https://learn.microsoft.com/en-us/entra/identity/hybrid/connect/reference-connect-accounts-permissions>
+pub fn add_entra_sync_flags(vec_users: &mut Vec<serde_json::value::Value>, vec_computers: &mut Vec<serde_json::value::Value>)
+{
+    let re = Regex::new(r"(?i)running on computer ([^\s.]+)").unwrap();
+    let mut sync_servers: Vec<String> = Vec::new();
+    for user in vec_users.iter_mut() {
+        let samaccountname = user["Properties"]["samaccountname"].as_str().unwrap_or("").to_uppercase();
+        if !samaccountname.starts_with("MSOL_") {
+            continue
+        }
+        user["Properties"]["entrasyncaccount"] = true.into();
+        let description = user["Properties"]["description"].as_str().unwrap_or("").to_string();
+        if let Some(captures) = re.captures(&description) {
+            sync_servers.push(format!("{}$",captures[1].to_uppercase()));
+        }
+    }
+    for computer in vec_computers.iter_mut() {
+        let samaccountname = computer["Properties"]["samaccountname"].as_str().unwrap_or("").to_uppercase();
+        if samaccountname == "AZUREADSSOACC$" {
+            computer["Properties"]["entraseamlesssso"] = true.into();
+        }
+        if sync_servers.contains(&samaccountname) {
+            computer["Properties"]["entrasyncserver"] = true.into();
+        }
+    }
+}
+
+/// This function flag computers hosting a DirSync or ADSync service connection point as Entra Connect servers.
+pub fn add_entra_sync_scp_flag(vec_computers: &mut Vec<serde_json::value::Value>, vec_scps: &Vec<serde_json::value::Value>)
+{
+    let mut sync_servers: Vec<String> = Vec::new();
+    for scp in vec_scps {
+        let values = format!("{} {} {}", scp["name"], scp["serviceclassname"], scp["keywords"]).to_uppercase();
+        if values.contains("DIRSYNC") || values.contains("ADSYNC") {
+            sync_servers.push(scp["parent"].as_str().unwrap_or("").to_string());
+        }
+    }
+    if sync_servers.is_empty() {
+        return
+    }
+    for computer in vec_computers.iter_mut() {
+        let dn = computer["Properties"]["distinguishedname"].as_str().unwrap_or("").to_uppercase();
+        if sync_servers.contains(&dn) {
+            computer["Properties"]["entrasyncserver"] = true.into();
+        }
+    }
+}
+
+/// Products found in the name, class, keywords and bindings of service connection points, uppercase.
+pub const SERVICE_PRODUCTS: [(&str, &str); 17] = [
+    ("EXCHANGE", "Exchange"),
+    ("77378F46-2C66-4AA9-A6A6-3E7A48B19596", "Exchange"),
+    ("SMS_", "SCCM"),
+    ("CONFIGMGR", "SCCM"),
+    ("VMWARE", "VMware"),
+    ("VCENTER", "VMware"),
+    ("HYPER-V", "Hyper-V"),
+    ("WINDOWS VIRTUAL MACHINE", "Hyper-V"),
+    ("VEEAM", "Backup"),
+    ("COMMVAULT", "Backup"),
+    ("NETBACKUP", "Backup"),
+    ("BACKUP EXEC", "Backup"),
+    ("ARCSERVE", "Backup"),
+    ("DIRSYNC", "Entra Connect"),
+    ("ADSYNC", "Entra Connect"),
+    ("MSSQL", "SQL Server"),
+    ("TERMSERVLICENSING", "RDS Licensing"),
+];
+
+/// Function to get the host of one service binding, URL, UNC path or `host:port`.
+/// `ldap://SCCM01.ESSOS.LOCAL:389/` => `SCCM01.ESSOS.LOCAL`, `\\PRN01\HP` => `PRN01`
+fn get_binding_host(binding: &str) -> Option<String>
+{
+    let binding = binding.split_once("://").map(|(_, rest)| rest).unwrap_or(binding);
+    let host = binding.trim_start_matches('\\')
+        .split(|c| c == '/' || c == ':' || c == '\\')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_uppercase();
+    if host.chars().any(|c| c.is_ascii_alphabetic()) && !host.contains(' ') {
+        Some(host)
+    } else {
+        None
+    }
+}
+
+/// Function to find the computer hosting one service connection point or printer.
+fn find_service_computer<'a>(
+    service: &serde_json::value::Value,
+    vec_computers: &'a Vec<serde_json::value::Value>,
+) -> Option<&'a serde_json::value::Value>
+{
+    let parent = service["parent"].as_str().unwrap_or("").to_uppercase();
+    if let Some(computer) = vec_computers.iter().find(|computer| computer["Properties"]["distinguishedname"].as_str().unwrap_or("").to_uppercase() == parent) {
+        return Some(computer)
+    }
+    let mut hosts: Vec<String> = Vec::new();
+    for key in ["servicednsname", "servername", "uncname"] {
+        hosts.extend(service[key].as_str().and_then(get_binding_host));
+    }
+    for binding in service["servicebindinginformation"].as_array().unwrap_or(&Vec::new()) {
+        hosts.extend(binding.as_str().and_then(get_binding_host));
+    }
+    vec_computers.iter().find(|computer| {
+        let name = computer["Properties"]["name"].as_str().unwrap_or("").to_uppercase();
+        let samaccountname = computer["Properties"]["samaccountname"].as_str().unwrap_or("").to_uppercase();
+        hosts.iter().any(|host| *host == name || format!("{}$",host) == samaccountname)
+    })
+}
+
+/// This function make the inventory of service connection points and printers with their hosting computer.
+/// The host is the parent object when the service is published under its computer, else the computer of the DNS name,
+/// server name or bindings of the service.
+pub fn make_service_inventory(
+    vec_scps: &Vec<serde_json::value::Value>,
+    vec_printers: &Vec<serde_json::value::Value>,
+    vec_computers: &Vec<serde_json::value::Value>,
+) -> Vec<serde_json::value::Value>
+{
+    let mut vec_services: Vec<serde_json::value::Value> = Vec::new();
+    for (kind, vec_objects) in [("ServiceConnectionPoint", vec_scps), ("PrintQueue", vec_printers)] {
+        for object in vec_objects {
+            let mut service = object.to_owned();
+            service["kind"] = kind.into();
+            let values = format!("{} {} {} {}", object["name"], object["serviceclassname"], object["keywords"], object["servicebindinginformation"]).to_uppercase();
+            service["product"] = match kind {
+                "PrintQueue" => "Printer".into(),
+                _ => SERVICE_PRODUCTS.iter()
+                    .find(|(pattern, _)| values.contains(pattern))
+                    .map(|(_, product)| product.to_string().into())
+                    .unwrap_or(serde_json::value::Value::Null),
+            };
+            let computer = find_service_computer(object, vec_computers);
+            service["computer"] = computer.map(|computer| computer["ObjectIdentifier"].to_owned()).unwrap_or(serde_json::value::Value::Null);
+            service["computername"] = computer.map(|computer| computer["Properties"]["name"].to_owned()).unwrap_or(serde_json::value::Value::Null);
+            vec_services.push(service);
+        }
+    }
+    vec_services
+}
+
+/// This function add the authentication policy and silo of users and computers.
+/// Silo policies are used for the accounts in one silo, else the policy assigned to the account.
+pub fn add_authn_policies(
+    vec_users: &mut Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    vec_authn: &Vec<serde_json::value::Value>,
+)
+{
+    if vec_authn.is_empty() {
+        return
+    }
+    let dns = |object: &serde_json::value::Value, key: &str| -> Vec<String> {
+        object[key].as_array().unwrap_or(&Vec::new()).iter().filter_map(|dn| dn.as_str().map(|dn| dn.to_string())).collect()
+    };
+    let policies: Vec<&serde_json::value::Value> = vec_authn.iter().filter(|authn| authn["type"] == "policy").collect();
+    let get_policy = |dn: &serde_json::value::Value| policies.iter().find(|policy| policy["distinguishedname"] == *dn);
+    let set_policy = |object: &mut serde_json::value::Value, policy: &serde_json::value::Value| {
+        object["Properties"]["authnpolicy"] = policy["name"].to_owned();
+        object["Properties"]["authnpolicyenforced"] = policy["enforced"].to_owned();
+    };
+
+    for (vec_objects, silo_policy) in [(vec_users, "userpolicy"), (vec_computers, "computerpolicy")] {
+        for object in vec_objects.iter_mut() {
+            let dn = object["Properties"]["distinguishedname"].as_str().unwrap_or("").to_uppercase();
+            for policy in &policies {
+                if dns(policy, "assigned").contains(&dn) {
+                    set_policy(object, policy);
+                }
+            }
+            for silo in vec_authn.iter().filter(|authn| authn["type"] == "silo") {
+                // Accounts must be members and assigned, assigned accounts are unknown if the backlink isn't readable
+                let assigned = dns(silo, "assigned");
+                if !dns(silo, "members").contains(&dn) || (!assigned.is_empty() && !assigned.contains(&dn)) {
+                    continue
+                }
+                object["Properties"]["authnpolicysilo"] = silo["name"].to_owned();
+                object["Properties"]["authnpolicysiloenforced"] = silo["enforced"].to_owned();
+                if let Some(policy) = get_policy(&silo[silo_policy]) {
+                    set_policy(object, policy);
+                }
+            }
+        }
+    }
+}
+
+/// This function make the nodes of authentication policies and silos, run after [`add_authn_policies`].
+/// Accounts are the users and computers assigned to them, silos are linked to the policies they apply.
+pub fn make_authn_nodes(
+    vec_users: &Vec<serde_json::value::Value>,
+    vec_computers: &Vec<serde_json::value::Value>,
+    vec_authn: &Vec<serde_json::value::Value>,
+) -> Vec<serde_json::value::Value>
+{
+    let mut vec_nodes: Vec<serde_json::value::Value> = Vec::new();
+    for authn in vec_authn {
+        let mut authn_json = bh_41::prepare_authn_json_template();
+        authn_json["ObjectIdentifier"] = authn["objectid"].to_owned();
+        for key in ["domain", "distinguishedname", "type", "enforced", "description", "whencreated"] {
+            authn_json["Properties"][key] = authn[key].to_owned();
+        }
+        authn_json["Properties"]["name"] = format!("{}@{}", authn["name"].as_str().unwrap_or(""), authn["domain"].as_str().unwrap_or("")).to_uppercase().into();
+
+        let (property, policies) = if authn["type"] == "silo" {
+            ("authnpolicysilo", vec!["userpolicy", "computerpolicy", "servicepolicy"])
+        } else {
+            ("authnpolicy", Vec::new())
+        };
+        for (vec_objects, object_type) in [(vec_users, "User"), (vec_computers, "Computer")] {
+            for object in vec_objects.iter().filter(|object| object["Properties"][property] == authn["name"]) {
+                let mut member = bh_41::prepare_member_json_template();
+                member["ObjectIdentifier"] = object["ObjectIdentifier"].to_owned();
+                member["ObjectType"] = object_type.into();
+                authn_json["Accounts"].as_array_mut().unwrap().push(member);
+            }
+        }
+        for policy in vec_authn.iter().filter(|policy| policy["type"] == "policy") {
+            if policies.iter().any(|key| authn[*key] == policy["distinguishedname"]) {
+                authn_json["Policies"].as_array_mut().unwrap().push(policy["objectid"].to_owned());
+            }
+        }
+        vec_nodes.push(authn_json);
+    }
+    vec_nodes
+}
+
+/// This function add the SIDs of the production forest given by shadow principals (PAM trust) to their members in HasSIDHistory,
+/// members of nested groups get them too. Members are still DNs, so it runs before they are replaced by SIDs.
+/// <https://learn.microsoft.com/en-us/microsoft-identity-manager/pam/privileged-identity-management-for-active-directory-domain-services>
+pub fn add_shadow_principals(
+    vec_users: &mut Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    vec_groups: &Vec<serde_json::value::Value>,
+    vec_shadows: &Vec<serde_json::value::Value>,
+)
+{
+    for shadow in vec_shadows {
+        let sid = match shadow["shadowsid"].as_str() {
+            Some(sid) => sid,
+            None => continue,
+        };
+        // Direct and nested members
+        let mut members: Vec<String> = shadow["members"].as_array().unwrap_or(&Vec::new()).iter()
+            .filter_map(|dn| dn.as_str().map(|dn| dn.to_string()))
+            .collect();
+        let mut i = 0;
+        while i < members.len() {
+            if let Some(group) = vec_groups.iter().find(|group| group["Properties"]["distinguishedname"] == members[i].as_str()) {
+                for member in group["Members"].as_array().unwrap_or(&Vec::new()) {
+                    let dn = member["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+                    if !members.contains(&dn) {
+                        members.push(dn);
+                    }
+                }
+            }
+            i += 1;
+        }
+        for object in vec_users.iter_mut().chain(vec_computers.iter_mut()) {
+            let dn = object["Properties"]["distinguishedname"].as_str().unwrap_or("").to_uppercase();
+            if !members.contains(&dn) {
+                continue
+            }
+            let has_sid_history = serde_json::json!({"ObjectIdentifier": sid, "ObjectType": "Base"});
+            match object["HasSIDHistory"].as_array_mut() {
+                Some(sids) if !sids.contains(&has_sid_history) => sids.push(has_sid_history),
+                Some(_) => {},
+                None => object["HasSIDHistory"] = vec![has_sid_history].into(),
+            }
+            match object["Properties"]["shadowprincipals"].as_array_mut() {
+                Some(names) => names.push(shadow["name"].to_owned()),
+                None => object["Properties"]["shadowprincipals"] = vec![shadow["name"].to_owned()].into(),
+            }
+        }
+    }
+}
+
+/// This function flag the users in Protected Users, directly or through nested groups.
+/// Their credentials are not cached, NTLM, DES, RC4 and delegation are not allowed and TGTs last 4 hours.
+/// <https://learn.microsoft.com/en-us/windows-server/security/credentials-protection-and-management/protected-users-security-group>
+pub fn add_protected_users_flag(vec_users: &mut Vec<serde_json::value::Value>, vec_groups: &Vec<serde_json::value::Value>)
+{
+    let protected_users: Vec<&str> = vec_groups.iter()
+        .filter_map(|group| group["ObjectIdentifier"].as_str())
+        .filter(|sid| sid.starts_with("S-1-5-21-") && sid.ends_with("-525"))
+        .collect();
+    if protected_users.is_empty() {
+        return
+    }
+    let resolver = MembershipResolver::new(vec_users, vec_groups, &Vec::new());
+    let members: Vec<String> = protected_users.iter().flat_map(|sid| resolver.effective_accounts(sid)).collect();
+    for user in vec_users.iter_mut() {
+        let protected = members.iter().any(|sid| user["ObjectIdentifier"] == sid.as_str());
+        user["Properties"]["protecteduser"] = protected.into();
+    }
+}
+
+/// This function add the creator name and type for computers created with MachineAccountQuota (mS-DS-CreatorSID).
+/// Owner of these computers is Domain Admins, the creator rights come from the CREATOR OWNER ACEs
+/// of the default security descriptor and are already in the computer ACEs.
+pub fn add_computers_creator(
+    vec_users: &Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    sid_type: &HashMap<String, String>,
+)
+{
+    let mut sid_name: HashMap<String, String> = HashMap::new();
+    for object in vec_users.iter().chain(vec_computers.iter()) {
+        if let Some(sid) = object["ObjectIdentifier"].as_str() {
+            sid_name.insert(sid.to_string(), object["Properties"]["name"].as_str().unwrap_or("").to_string());
+        }
+    }
+    for computer in vec_computers.iter_mut() {
+        let creator = computer["Properties"]["creatorsid"].as_str().unwrap_or("").to_string();
+        if creator.is_empty() {
+            continue
+        }
+        match sid_name.get(&creator) {
+            Some(name) => computer["Properties"]["creatorname"] = name.to_owned().into(),
+            None => {
+                // Deleted or foreign creator
+                let creator_type = sid_type.get(&creator).map(|t| t.as_str()).unwrap_or("Unknown");
+                computer["Properties"]["creatorname"] = format!("{} ({})",creator,creator_type).into();
+            }
+        }
+    }
+}
+
+/// This function add the RODC relations from the Password Replication Policy parsed in computers.
+/// `managedBy` principal is added as local administrator of the RODC.
+/// Edges `RODCAllowedToCache` (groups allowed and not denied) and `RODCCached` (principals with cached secrets) are added
+/// from the RODC to these principals, secrets of them can be taken from a compromised RODC or with the key list attack.
+pub fn add_rodc_relations(
+    vec_users: &mut Vec<serde_json::value::Value>,
+    vec_groups: &mut Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    dn_sid: &HashMap<String, String>,
+    sid_type: &HashMap<String, String>,
+)
+{
+    // (principal SID, RightName, RODC SID)
+    let mut relations: Vec<(String, String, String)> = Vec::new();
+    for computer in vec_computers.iter_mut() {
+        if computer["Properties"]["isrodc"] != true {
+            continue
+        }
+        let rodc_sid = computer["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+
+        if let Some(sid) = computer["Properties"]["managedby"].as_str().and_then(|dn| dn_sid.get(dn)) {
+            let mut local_admin = bh_41::prepare_member_json_template();
+            local_admin["ObjectIdentifier"] = sid.to_owned().into();
+            local_admin["ObjectType"] = sid_type.get(sid).unwrap_or(&"Group".to_string()).to_owned().into();
+            computer["LocalAdmins"]["Results"] = vec![local_admin].into();
+            computer["LocalAdmins"]["Collected"] = true.into();
+        }
+
+        let get_dns = |property: &str| -> Vec<String> {
+            computer["Properties"][property].as_array().unwrap_or(&Vec::new()).iter()
+                .filter_map(|dn| dn.as_str().map(|dn| dn.to_string())).collect()
+        };
+        let never_reveal = get_dns("neverrevealgroups");
+        for dn in get_dns("revealondemandgroups") {
+            if never_reveal.contains(&dn) {
+                continue
+            }
+            if let Some(sid) = dn_sid.get(&dn) {
+                relations.push((sid.to_owned(), "RODCAllowedToCache".to_string(), rodc_sid.to_owned()));
+            }
+        }
+        for dn in get_dns("revealedusers") {
+            if let Some(sid) = dn_sid.get(&dn) {
+                relations.push((sid.to_owned(), "RODCCached".to_string(), rodc_sid.to_owned()));
+            }
+        }
+    }
+    if relations.is_empty() {
+        return
+    }
+
+    for object in vec_users.iter_mut().chain(vec_groups.iter_mut()).chain(vec_computers.iter_mut()) {
+        let sid = object["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+        for (principal, right, rodc_sid) in &relations {
+            if *principal != sid {
+                continue
+            }
+            let mut relation = bh_41::prepare_acl_relation_template();
+            relation["RightName"] = right.to_owned().into();
+            relation["PrincipalSID"] = rodc_sid.to_owned().into();
+            relation["PrincipalType"] = "Computer".into();
+            if let Some(aces) = object["Aces"].as_array_mut() {
+                aces.push(relation);
+            }
+        }
+    }
+}
+
+/// This function flag the krbtgt_<number> accounts of RODCs and link them to their RODC computer.
+/// Accounts without RODC (RODC removed without cleanup) are flagged too.
+/// These accounts are Tier-0, their keys can forge tickets accepted by the RODC and used in the key list attack.
+pub fn add_rodc_krbtgt_accounts(vec_users: &mut Vec<serde_json::value::Value>, vec_computers: &mut Vec<serde_json::value::Value>)
+{
+    let mut krbtgt_rodc: HashMap<String, String> = HashMap::new();
+    for computer in vec_computers.iter() {
+        if let Some(dn) = computer["Properties"]["krbtgtlink"].as_str() {
+            krbtgt_rodc.insert(dn.to_string(), computer["Properties"]["name"].as_str().unwrap_or("").to_string());
+        }
+    }
+    let re = Regex::new(r"^(?i)krbtgt_[0-9]+$").unwrap();
+    for user in vec_users.iter_mut() {
+        let dn = user["Properties"]["distinguishedname"].as_str().unwrap_or("").to_string();
+        let samaccountname = user["Properties"]["samaccountname"].as_str().unwrap_or("").to_string();
+        let rodc = krbtgt_rodc.get(&dn);
+        if rodc.is_none() && !re.is_match(&samaccountname) {
+            continue
+        }
+        user["Properties"]["rodckrbtgt"] = true.into();
+        user["Properties"]["highvalue"] = true.into();
+        if let Some(name) = rodc {
+            user["Properties"]["rodcname"] = name.to_owned().into();
+        }
+    }
+}
+
+/// This function add the organizational relations from the `manager` attribute of users.
+/// Manager DN is replaced by the manager name and a `ManagerOf` edge is added from the manager to the user.
+pub fn add_manager_relations(
+    vec_users: &mut Vec<serde_json::value::Value>,
+    dn_sid: &HashMap<String, String>,
+    sid_type: &HashMap<String, String>,
+)
+{
+    let mut dn_name: HashMap<String, String> = HashMap::new();
+    for user in vec_users.iter() {
+        if let (Some(dn), Some(name)) = (user["Properties"]["distinguishedname"].as_str(), user["Properties"]["name"].as_str()) {
+            dn_name.insert(dn.to_string(), name.to_string());
+        }
+    }
+    for user in vec_users.iter_mut() {
+        let manager = match user["Properties"]["manager"].as_str() {
+            Some(manager) => manager.to_string(),
+            None => continue,
+        };
+        // Manager from another domain without followed referrals stays a DN
+        if let Some(name) = dn_name.get(&manager) {
+            user["Properties"]["manager"] = name.to_owned().into();
+        }
+        if let Some(sid) = dn_sid.get(&manager) {
+            let mut relation = bh_41::prepare_acl_relation_template();
+            relation["RightName"] = "ManagerOf".into();
+            relation["PrincipalSID"] = sid.to_owned().into();
+            relation["PrincipalType"] = sid_type.get(sid).unwrap_or(&"User".to_string()).to_owned().into();
+            if let Some(aces) = user["Aces"].as_array_mut() {
+                aces.push(relation);
+            }
+        }
+    }
+}
+
+/// This function tag Tier-0 objects with `isTierZero` and `system_tags` like BloodHound CE, and set them as high value.
+/// Tier-0 objects are privileged groups (high value) and all their nested members, writable domain controllers,
+/// krbtgt accounts, AdminSDHolder protected objects, DCSync principals and ADCS enterprise CAs with their hosts.
+pub fn add_tier_zero_tags(
+    vec_users: &mut Vec<serde_json::value::Value>,
+    vec_groups: &mut Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    vec_domains: &mut Vec<serde_json::value::Value>,
+    vec_cas: &mut Vec<serde_json::value::Value>,
+)
+{
+    fn sid_of(object: &serde_json::value::Value) -> String {
+        object["ObjectIdentifier"].as_str().unwrap_or("").to_string()
+    }
+    let mut tier_zero: Vec<String> = Vec::new();
+
+    // Privileged groups, AdminSDHolder protected and already high value objects
+    for object in vec_users.iter().chain(vec_groups.iter()).chain(vec_computers.iter()) {
+        if object["Properties"]["highvalue"].as_bool().unwrap_or(false)
+        || object["Properties"]["admincount"].as_bool().unwrap_or(false)
+        {
+            tier_zero.push(sid_of(object));
+        }
+    }
+    // krbtgt accounts
+    for user in vec_users.iter() {
+        if sid_of(user).ends_with("-502") || user["Properties"]["rodckrbtgt"].as_bool().unwrap_or(false) {
+            tier_zero.push(sid_of(user));
+        }
+    }
+    // Writable domain controllers and ADCS CA hosts
+    let ca_hosts: Vec<String> = vec_cas.iter()
+        .filter_map(|ca| ca["Properties"]["DNS Name"].as_str().map(|name| name.to_uppercase()))
+        .collect();
+    for computer in vec_computers.iter() {
+        let name = computer["Properties"]["name"].as_str().unwrap_or("").to_uppercase();
+        if (is_domain_controller(computer) && !computer["Properties"]["isrodc"].as_bool().unwrap_or(false))
+        || ca_hosts.contains(&name)
+        {
+            tier_zero.push(sid_of(computer));
+        }
+    }
+    // DCSync principals
+    for domain in vec_domains.iter() {
+        let aces = domain["Aces"].as_array().map(|aces| aces.to_owned()).unwrap_or_default();
+        let principals = |right: &str| -> Vec<String> {
+            aces.iter()
+                .filter(|ace| ace["RightName"] == right)
+                .filter_map(|ace| ace["PrincipalSID"].as_str().map(|sid| sid.to_string()))
+                .collect()
+        };
+        let get_changes = principals("GetChanges");
+        for sid in principals("GetChangesAll") {
+            if get_changes.contains(&sid) {
+                tier_zero.push(sid);
+            }
+        }
+        // AllExtendedRights includes both replication rights
+        tier_zero.extend(principals("AllExtendedRights"));
+    }
+
+    // Nested members of Tier-0 groups
+    loop {
+        let mut added = false;
+        for group in vec_groups.iter() {
+            if !tier_zero.contains(&sid_of(group)) {
+                continue
+            }
+            for member in group["Members"].as_array().unwrap_or(&Vec::new()) {
+                let sid = member["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+                if !sid.is_empty() && !tier_zero.contains(&sid) {
+                    tier_zero.push(sid);
+                    added = true;
+                }
+            }
+        }
+        if !added {
+            break
+        }
+    }
+
+    let tag = |object: &mut serde_json::value::Value| {
+        object["Properties"]["highvalue"] = true.into();
+        object["Properties"]["isTierZero"] = true.into();
+        object["Properties"]["system_tags"] = "admin_tier_0".into();
+    };
+    for object in vec_users.iter_mut().chain(vec_groups.iter_mut()).chain(vec_computers.iter_mut()) {
+        if tier_zero.contains(&sid_of(&*object)) {
+            tag(object);
+        }
+    }
+    for object in vec_domains.iter_mut().chain(vec_cas.iter_mut()) {
+        tag(object);
+    }
+}
+
+/// Built-in admin trustees dropped by --acl-skip-benign, RID of domain principals or well-known SID
+pub const BENIGN_TRUSTEES: [&str; 7] = [
+    // SYSTEM, Administrators, Enterprise Domain Controllers
+    "S-1-5-18", "S-1-5-32-544", "S-1-5-9",
+    // Domain Admins, Enterprise Admins, Domain Controllers, Read-only Domain Controllers
+    "-512", "-519", "-516", "-521",
+];
+
+/// Function to get the trustees dropped by --acl-skip-benign, the built-in list if no SIDs given.
+pub fn get_benign_trustees(acl_benign_sids: &str) -> Vec<String>
+{
+    if acl_benign_sids.contains("not set") {
+        return BENIGN_TRUSTEES.iter().map(|sid| sid.to_string()).collect()
+    }
+    acl_benign_sids.split(',')
+        .map(|sid| sid.trim().to_uppercase())
+        .filter(|sid| !sid.is_empty())
+        .collect()
+}
+
+/// Function to drop the ACEs of benign trustees (--acl-skip-benign), these edges to admins
+/// are on almost every object and add no attack path. Return the number of ACEs dropped.
+/// PrincipalSID can be prefixed by the domain for well-known SIDs, so SIDs are matched on their end.
+pub fn remove_benign_aces(vec_objects: &mut Vec<serde_json::value::Value>, benign_trustees: &[String]) -> usize
+{
+    let mut count = 0;
+    for object in vec_objects.iter_mut() {
+        if let Some(aces) = object["Aces"].as_array_mut() {
+            let before = aces.len();
+            aces.retain(|ace| {
+                let sid = ace["PrincipalSID"].as_str().unwrap_or("").to_uppercase();
+                !benign_trustees.iter().any(|trustee| sid.ends_with(trustee.as_str()))
+            });
+            count += before - aces.len();
+        }
+    }
+    count
+}
+
+/// Keys of identifiers and references to identifiers in BloodHound objects
+const IDENTIFIER_KEYS: [&str; 5] = ["ObjectIdentifier", "PrincipalSID", "GUID", "UserSID", "ComputerSID"];
+
+/// Function to make identifiers consistent with SharpHound (--sharphound-ids).
+/// objectGUID for GPOs, OUs and containers, SID for principals, always uppercase and without braces,
+/// in the objects and in every reference to them (members, ACEs, links, child objects, sessions...).
+pub fn normalize_identifiers(vec_objects: &mut Vec<serde_json::value::Value>)
+{
+    fn normalize(value: &mut serde_json::value::Value) {
+        match value {
+            serde_json::value::Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if IDENTIFIER_KEYS.contains(&key.as_str()) {
+                        if let Some(identifier) = value.as_str() {
+                            *value = identifier.trim_start_matches('{').trim_end_matches('}').to_uppercase().into();
+                            continue
+                        }
+                    }
+                    // Properties are not identifiers
+                    if key != "Properties" {
+                        normalize(value);
+                    }
+                }
+            }
+            serde_json::value::Value::Array(values) => {
+                for value in values.iter_mut() {
+                    normalize(value);
+                }
+            }
+            _ => {}
+        }
+    }
+    for object in vec_objects.iter_mut() {
+        normalize(object);
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_normalize_identifiers() {
+    let mut vec_objects = vec![serde_json::json!({
+        "ObjectIdentifier": "{6ac1786c-016f-11d2-945f-00c04fb984f9}",
+        "Properties": {"name": "Default Domain Controllers Policy"},
+        "Links": [{"GUID": "31b2f340-016d-11d2-945f-00c04fb984f9", "IsEnforced": false}],
+        "Aces": [{"PrincipalSID": "essos.local-S-1-5-32-544", "RightName": "GenericAll"}],
+    })];
+    normalize_identifiers(&mut vec_objects);
+    assert_eq!(vec_objects[0]["ObjectIdentifier"], "6AC1786C-016F-11D2-945F-00C04FB984F9");
+    assert_eq!(vec_objects[0]["Links"][0]["GUID"], "31B2F340-016D-11D2-945F-00C04FB984F9");
+    assert_eq!(vec_objects[0]["Aces"][0]["PrincipalSID"], "ESSOS.LOCAL-S-1-5-32-544");
+    assert_eq!(vec_objects[0]["Properties"]["name"], "Default Domain Controllers Policy");
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_remove_benign_aces() {
+    let mut vec_objects = vec![serde_json::json!({
+        "Aces": [
+            {"PrincipalSID": "ESSOS.LOCAL-S-1-5-32-544", "RightName": "GenericAll"},
+            {"PrincipalSID": "S-1-5-21-1-2-3-512", "RightName": "WriteDacl"},
+            {"PrincipalSID": "S-1-5-21-1-2-3-1512", "RightName": "GenericWrite"},
+            {"PrincipalSID": "S-1-5-21-1-2-3-1104", "RightName": "Owns"},
+        ],
+    })];
+    assert_eq!(remove_benign_aces(&mut vec_objects, &get_benign_trustees("not set")), 2);
+    assert_eq!(vec_objects[0]["Aces"].as_array().unwrap().len(), 2);
+    assert_eq!(remove_benign_aces(&mut vec_objects, &get_benign_trustees("s-1-5-21-1-2-3-1104, -1512")), 2);
+    assert!(vec_objects[0]["Aces"].as_array().unwrap().is_empty());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_add_authn_policies() {
+    let mut vec_users = vec![
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "Properties": {"distinguishedname": "CN=DAENERYS,CN=USERS,DC=ESSOS,DC=LOCAL"}}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1105", "Properties": {"distinguishedname": "CN=VISERYS,CN=USERS,DC=ESSOS,DC=LOCAL"}}),
+    ];
+    let vec_authn = vec![
+        serde_json::json!({
+            "objectid": "8A4E9C3B-1F2D-4C5E-9B7A-0D1E2F3A4B5C", "domain": "ESSOS.LOCAL",
+            "name": "TIER0", "type": "silo", "enforced": true, "distinguishedname": "CN=TIER0,CN=AUTHN SILOS,CN=AUTHN POLICY CONFIGURATION,CN=SERVICES,CN=CONFIGURATION,DC=ESSOS,DC=LOCAL",
+            "members": ["CN=DAENERYS,CN=USERS,DC=ESSOS,DC=LOCAL", "CN=VISERYS,CN=USERS,DC=ESSOS,DC=LOCAL"],
+            "assigned": ["CN=DAENERYS,CN=USERS,DC=ESSOS,DC=LOCAL"],
+            "userpolicy": "CN=TIER0 USERS,CN=AUTHN POLICIES,CN=AUTHN POLICY CONFIGURATION,CN=SERVICES,CN=CONFIGURATION,DC=ESSOS,DC=LOCAL",
+        }),
+        serde_json::json!({
+            "objectid": "1F2D4C5E-9B7A-4C5E-9B7A-0D1E2F3A4B5C", "domain": "ESSOS.LOCAL",
+            "name": "TIER0 USERS", "type": "policy", "enforced": false, "distinguishedname": "CN=TIER0 USERS,CN=AUTHN POLICIES,CN=AUTHN POLICY CONFIGURATION,CN=SERVICES,CN=CONFIGURATION,DC=ESSOS,DC=LOCAL",
+            "members": [], "assigned": [],
+        }),
+    ];
+    add_authn_policies(&mut vec_users, &mut Vec::new(), &vec_authn);
+    assert_eq!(vec_users[0]["Properties"]["authnpolicysilo"], "TIER0");
+    assert_eq!(vec_users[0]["Properties"]["authnpolicysiloenforced"], true);
+    assert_eq!(vec_users[0]["Properties"]["authnpolicy"], "TIER0 USERS");
+    // Member of the silo but not assigned to it
+    assert!(vec_users[1]["Properties"]["authnpolicysilo"].is_null());
+
+    let vec_nodes = make_authn_nodes(&vec_users, &Vec::new(), &vec_authn);
+    assert_eq!(vec_nodes.len(), 2);
+    assert_eq!(vec_nodes[0]["Accounts"], serde_json::json!([{"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "ObjectType": "User"}]));
+    assert_eq!(vec_nodes[0]["Policies"], serde_json::json!(["1F2D4C5E-9B7A-4C5E-9B7A-0D1E2F3A4B5C"]));
+    assert_eq!(vec_nodes[0]["Properties"]["name"], "TIER0@ESSOS.LOCAL");
+    assert_eq!(vec_nodes[1]["Accounts"], serde_json::json!([{"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "ObjectType": "User"}]));
+
+    let vec_groups = vec![
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-525", "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1200", "ObjectType": "Group"}]}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1200", "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1105", "ObjectType": "User"}]}),
+    ];
+    add_protected_users_flag(&mut vec_users, &vec_groups);
+    assert_eq!(vec_users[0]["Properties"]["protecteduser"], false);
+    assert_eq!(vec_users[1]["Properties"]["protecteduser"], true);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_add_dormant_flag() {
+    let now = 1_700_000_000;
+    let mut vec_users = vec![
+        serde_json::json!({"Properties": {"lastlogontimestamp": now - 10 * 86400, "whencreated": now - 900 * 86400}}),
+        serde_json::json!({"Properties": {"lastlogontimestamp": now - 200 * 86400, "lastlogon": now - 100 * 86400}}),
+        // Never used
+        serde_json::json!({"Properties": {"lastlogontimestamp": -1, "whencreated": now - 5 * 86400}}),
+        serde_json::json!({"Properties": {"lastlogontimestamp": -1, "whencreated": now - 500 * 86400}}),
+    ];
+    let mut vec_computers = vec![serde_json::json!({"Properties": {"lastlogontimestamp": now - 91 * 86400}})];
+    assert_eq!(add_dormant_flag(&mut vec_users, &mut vec_computers, 90, now), 3);
+    assert_eq!(vec_users.iter().map(|user| user["Properties"]["dormant"].as_bool().unwrap()).collect::<Vec<bool>>(), vec![false, true, false, true]);
+    assert_eq!(vec_computers[0]["Properties"]["dormant"], true);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_add_sid_history_flags() {
+    let mut vec_users = vec![
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "Properties": {"domainsid": "S-1-5-21-1-2-3", "sidhistory": ["S-1-5-21-1-2-3-500"]}}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1105", "Properties": {"sidhistory": ["S-1-5-21-7-8-9-519"]}}),
+        // Migrated from another domain
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1106", "Properties": {"domainsid": "S-1-5-21-1-2-3", "sidhistory": ["S-1-5-21-7-8-9-1106"]}}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1107", "Properties": {"sidhistory": []}}),
+    ];
+    add_sid_history_flags(&mut vec_users);
+    assert_eq!(vec_users[0]["Properties"]["sidhistorysamedomain"], true);
+    assert_eq!(vec_users[0]["Properties"]["sidhistoryprivileged"], false);
+    assert_eq!(vec_users[1]["Properties"]["sidhistorysamedomain"], false);
+    assert_eq!(vec_users[1]["Properties"]["sidhistoryprivileged"], true);
+    assert_eq!(vec_users[2]["Properties"]["sidhistorysamedomain"], false);
+    assert_eq!(vec_users[2]["Properties"]["sidhistoryprivileged"], false);
+    assert!(vec_users[3]["Properties"]["sidhistorysamedomain"].is_null());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_replace_sid_members_orphaned() {
+    let mut dn_sid: HashMap<String, String> = HashMap::new();
+    dn_sid.insert("CN=JON,CN=USERS,DC=ESSOS,DC=LOCAL".to_string(), "S-1-5-21-1-2-3-1104".to_string());
+    let mut sid_type: HashMap<String, String> = HashMap::new();
+    sid_type.insert("S-1-5-21-1-2-3-1104".to_string(), "User".to_string());
+    let mut vec_groups = vec![serde_json::json!({
+        "Properties": {"name": "HELPDESK@ESSOS.LOCAL"},
+        "Members": [
+            {"ObjectIdentifier": "CN=JON,CN=USERS,DC=ESSOS,DC=LOCAL", "ObjectType": "Base"},
+            {"ObjectIdentifier": "CN=DELETED\\0ACNF:8A4E9C3B-1F2D-4C5E-9B7A-0D1E2F3A4B5C,CN=USERS,DC=ESSOS,DC=LOCAL", "ObjectType": "Base"},
+            {"ObjectIdentifier": "CN=S-1-5-21-7-8-9-1105,CN=FOREIGNSECURITYPRINCIPALS,DC=ESSOS,DC=LOCAL", "ObjectType": "Base"},
+        ],
+    })];
+    replace_sid_members(&mut vec_groups, &dn_sid, &sid_type, &Vec::new(), &"essos.local".to_string());
+    assert_eq!(vec_groups[0]["Members"], serde_json::json!([
+        {"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "ObjectType": "User"},
+        {"ObjectIdentifier": "S-1-5-21-7-8-9-1105", "ObjectType": "Group"},
+    ]));
+    assert_eq!(vec_groups[0]["Properties"]["orphanedmembers"], serde_json::json!(["CN=DELETED\\0ACNF:8A4E9C3B-1F2D-4C5E-9B7A-0D1E2F3A4B5C,CN=USERS,DC=ESSOS,DC=LOCAL"]));
+
+    // Localized groups of a trusted domain, resolved by their RID
+    let vec_trusts = vec![serde_json::json!({"TargetDomainName": "CORP.LOCAL", "TargetDomainSid": "S-1-5-21-4-5-6"})];
+    let mut vec_groups = vec![serde_json::json!({
+        "Properties": {"name": "ADMINS@ESSOS.LOCAL"},
+        "Members": [
+            {"ObjectIdentifier": "CN=Domänen-Admins,CN=Users,DC=CORP,DC=LOCAL", "ObjectType": "Base"},
+            {"ObjectIdentifier": "CN=Administratoren,CN=Builtin,DC=CORP,DC=LOCAL", "ObjectType": "Base"},
+        ],
+    })];
+    replace_sid_members(&mut vec_groups, &dn_sid, &sid_type, &vec_trusts, &"essos.local".to_string());
+    assert_eq!(vec_groups[0]["Members"][0]["ObjectIdentifier"], "S-1-5-21-4-5-6-512");
+    assert_eq!(vec_groups[0]["Members"][1]["ObjectIdentifier"], "CORP.LOCAL-S-1-5-32-544");
+
+    let mut vec_objects = vec![serde_json::json!({"Properties": {"distinguishedname": "CN=DELETED\\0ACNF:8A4E9C3B-1F2D-4C5E-9B7A-0D1E2F3A4B5C,CN=USERS,DC=ESSOS,DC=LOCAL"}})];
+    add_conflict_flag(&mut vec_objects);
+    assert_eq!(vec_objects[0]["Properties"]["conflictobject"], true);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_add_shadow_principals() {
+    let mut vec_users = vec![
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "Properties": {"distinguishedname": "CN=JORAH.MORMONT,CN=USERS,DC=BASTION,DC=LOCAL"}, "HasSIDHistory": []}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1105", "Properties": {"distinguishedname": "CN=MISSANDEI,CN=USERS,DC=BASTION,DC=LOCAL"}, "HasSIDHistory": []}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1106", "Properties": {"distinguishedname": "CN=DAARIO.NAHARIS,CN=USERS,DC=BASTION,DC=LOCAL"}, "HasSIDHistory": []}),
+    ];
+    let vec_groups = vec![
+        serde_json::json!({"Properties": {"distinguishedname": "CN=PAM OPERATORS,CN=USERS,DC=BASTION,DC=LOCAL"}, "Members": [{"ObjectIdentifier": "CN=MISSANDEI,CN=USERS,DC=BASTION,DC=LOCAL"}]}),
+    ];
+    let vec_shadows = vec![serde_json::json!({
+        "name": "PROD-Domain Admins",
+        "shadowsid": "S-1-5-21-7-8-9-512",
+        "members": ["CN=JORAH.MORMONT,CN=USERS,DC=BASTION,DC=LOCAL", "CN=PAM OPERATORS,CN=USERS,DC=BASTION,DC=LOCAL"],
+    })];
+    add_shadow_principals(&mut vec_users, &mut Vec::new(), &vec_groups, &vec_shadows);
+    assert_eq!(vec_users[0]["HasSIDHistory"], serde_json::json!([{"ObjectIdentifier": "S-1-5-21-7-8-9-512", "ObjectType": "Base"}]));
+    assert_eq!(vec_users[0]["Properties"]["shadowprincipals"], serde_json::json!(["PROD-Domain Admins"]));
+    assert_eq!(vec_users[1]["HasSIDHistory"], serde_json::json!([{"ObjectIdentifier": "S-1-5-21-7-8-9-512", "ObjectType": "Base"}]));
+    assert_eq!(vec_users[2]["HasSIDHistory"], serde_json::json!([]));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_make_service_inventory() {
+    let vec_computers = vec![
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1001", "Properties": {"name": "SCCM01.ESSOS.LOCAL", "samaccountname": "SCCM01$", "distinguishedname": "CN=SCCM01,OU=SERVERS,DC=ESSOS,DC=LOCAL"}}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1002", "Properties": {"name": "PRN01.ESSOS.LOCAL", "samaccountname": "PRN01$", "distinguishedname": "CN=PRN01,OU=SERVERS,DC=ESSOS,DC=LOCAL"}}),
+    ];
+    let vec_scps = vec![
+        serde_json::json!({"name": "VMWARE VCENTER", "parent": "CN=SCCM01,OU=SERVERS,DC=ESSOS,DC=LOCAL", "serviceclassname": null, "keywords": ["VCENTER"], "servicebindinginformation": []}),
+        serde_json::json!({"name": "SMS-MP", "parent": "CN=SYSTEM MANAGEMENT,CN=SYSTEM,DC=ESSOS,DC=LOCAL", "serviceclassname": "SMS_SITE", "keywords": [], "servicebindinginformation": ["http://sccm01.essos.local:80/sms_mp"]}),
+    ];
+    let vec_printers = vec![
+        serde_json::json!({"name": "PRN01-HP", "parent": "CN=OTHER,DC=ESSOS,DC=LOCAL", "servername": "prn01.essos.local", "uncname": "\\\\PRN01\\HP"}),
+    ];
+    let vec_services = make_service_inventory(&vec_scps, &vec_printers, &vec_computers);
+    assert_eq!(vec_services.len(), 3);
+    assert_eq!(vec_services[0]["product"], "VMware");
+    assert_eq!(vec_services[0]["computer"], "S-1-5-21-1-2-3-1001");
+    assert_eq!(vec_services[1]["product"], "SCCM");
+    assert_eq!(vec_services[1]["computername"], "SCCM01.ESSOS.LOCAL");
+    assert_eq!(vec_services[2]["kind"], "PrintQueue");
+    assert_eq!(vec_services[2]["computer"], "S-1-5-21-1-2-3-1002");
+    assert_eq!(get_binding_host("\\\\PRN01\\HP"), Some("PRN01".to_string()));
+    assert_eq!(get_binding_host("ldap://SCCM01.ESSOS.LOCAL:389/"), Some("SCCM01.ESSOS.LOCAL".to_string()));
+}
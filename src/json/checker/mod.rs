@@ -5,6 +5,8 @@ use crate::banner::progress_bar;
 use std::convert::TryInto;
 
 pub mod bh_41;
+pub mod netbios;
+pub mod privileged;
 
 /// Functions to replace and add missing values
 pub fn check_all_result(
@@ -18,7 +20,11 @@ pub fn check_all_result(
    vec_gpos: &mut Vec<serde_json::value::Value>,
    _vec_fsps: &mut Vec<serde_json::value::Value>,
    vec_containers: &mut Vec<serde_json::value::Value>,
+   vec_contacts: &mut Vec<serde_json::value::Value>,
    vec_trusts: &mut Vec<serde_json::value::Value>,
+   vec_sites: &mut Vec<serde_json::value::Value>,
+   vec_subnets: &mut Vec<serde_json::value::Value>,
+   vec_sitelinks: &mut Vec<serde_json::value::Value>,
 
    dn_sid: &mut HashMap<String, String>,
    sid_type: &mut HashMap<String, String>,
@@ -30,9 +36,29 @@ pub fn check_all_result(
     debug!("Replace SID with checker.rs started");
     bh_41::replace_fqdn_by_sid(vec_users, &fqdn_sid);
     bh_41::replace_fqdn_by_sid(vec_computers, &fqdn_sid);
-    bh_41::replace_sid_members(vec_groups, &dn_sid, &sid_type, &vec_trusts);
+    bh_41::replace_sid_members(vec_groups, &dn_sid, &sid_type, &vec_trusts, domain);
     debug!("Replace SID finished!");
 
+    debug!("Flagging conflict objects");
+    for vec_objects in [&mut *vec_users, &mut *vec_groups, &mut *vec_computers, &mut *vec_ous, &mut *vec_gpos, &mut *vec_containers, &mut *vec_contacts] {
+        bh_41::add_conflict_flag(vec_objects);
+    }
+    debug!("Conflict objects flagged!");
+
+    debug!("Flagging suspicious SID history");
+    bh_41::add_sid_history_flags(vec_users);
+    bh_41::add_sid_history_flags(vec_groups);
+    bh_41::add_sid_history_flags(vec_computers);
+    debug!("Suspicious SID history flagged!");
+
+    debug!("Flagging Protected Users members");
+    bh_41::add_protected_users_flag(vec_users, &vec_groups);
+    debug!("Protected Users members flagged!");
+
+    debug!("Adding MSSQL instances to hosting computers");
+    bh_41::add_mssql_instances(vec_users, vec_computers);
+    debug!("MSSQL instances added!");
+
     debug!("Adding defaults groups and default users");
     bh_41::add_default_groups(vec_groups, &vec_computers, domain.to_owned());
     bh_41::add_default_users(vec_users, domain.to_owned());
@@ -46,6 +72,7 @@ pub fn check_all_result(
     add_type_for_ace(vec_ous, &sid_type);
     add_type_for_ace(vec_domains, &sid_type);
     add_type_for_ace(vec_containers, &sid_type);
+    add_type_for_ace(vec_contacts, &sid_type);
     add_type_for_allowtedtoact(vec_computers, &sid_type);
     debug!("PrincipalType for ACEs added!");
 
@@ -60,6 +87,7 @@ pub fn check_all_result(
     bh_41::add_domain_sid(vec_gpos, &dn_sid);
     bh_41::add_domain_sid(vec_ous, &dn_sid);
     bh_41::add_domain_sid(vec_containers, &dn_sid);
+    bh_41::add_domain_sid(vec_contacts, &dn_sid);
     debug!("domainsid added!");
         
     debug!("Adding affected computers in domain GpoChanges");
@@ -71,11 +99,45 @@ pub fn check_all_result(
     bh_41::replace_guid_gplink(vec_domains, &dn_sid);
     debug!("guid for gplinks added!");
 
+    debug!("Flagging duplicate spns and sAMAccountName anomalies");
+    bh_41::add_duplicate_spn_flag(vec_users, vec_computers);
+    bh_41::add_samaccountname_anomaly_flag(vec_users, vec_computers);
+    debug!("Duplicate spns and sAMAccountName anomalies flagged!");
+
+    debug!("Flagging Entra Connect accounts and servers");
+    bh_41::add_entra_sync_flags(vec_users, vec_computers);
+    debug!("Entra Connect accounts and servers flagged!");
+
+    debug!("Adding creator of computers created with MachineAccountQuota");
+    bh_41::add_computers_creator(vec_users, vec_computers, sid_type);
+    debug!("Creator of computers added!");
+
+    debug!("Adding RODC Password Replication Policy relations");
+    bh_41::add_rodc_relations(vec_users, vec_groups, vec_computers, &dn_sid, &sid_type);
+    debug!("RODC relations added!");
+
+    debug!("Adding manager relations");
+    bh_41::add_manager_relations(vec_users, &dn_sid, &sid_type);
+    debug!("Manager relations added!");
+
+    debug!("Flagging RODC krbtgt accounts");
+    bh_41::add_rodc_krbtgt_accounts(vec_users, vec_computers);
+    debug!("RODC krbtgt accounts flagged!");
+
+    debug!("Adding krbtgt password last set in domain");
+    bh_41::add_krbtgt_pwdlastset(vec_domains, &vec_users);
+    debug!("krbtgt password last set added!");
+
     if vec_trusts.len() > 0 {
         debug!("Adding trust domain relation");
         bh_41::add_trustdomain(vec_domains, vec_trusts);
         debug!("Trust domain relation added!");
     }
+    if vec_sites.len() > 0 {
+        debug!("Adding subnets and site links in sites");
+        bh_41::add_site_subnets(vec_sites, &vec_subnets, &vec_sitelinks);
+        debug!("Subnets and site links added!");
+    }
     info!("Checking and replacing some values finished!");
 }
 
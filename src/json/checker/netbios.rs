@@ -0,0 +1,94 @@
+//! NetBIOS and DNS domain names
+//!
+//! Account names like `NORTH\jon.snow` (GPO files, msDS-PrincipalName, ntlmrelayx) use the NetBIOS name of
+//! the domain, which can be anything: `CONTOSO` for `corp.contoso-group.com`. The mapping is taken from the
+//! cross-references of the Partitions container (`netbiosname` of domain objects) and from the trusted domain
+//! objects (`flatName`), the first label of the DNS name is only a fallback.
+use std::collections::HashMap;
+
+/// NetBIOS name to DNS name of the collected and trusted domains, uppercase.
+#[derive(Default)]
+pub struct DomainNames {
+    netbios_dns: HashMap<String, String>,
+    dns_netbios: HashMap<String, String>,
+}
+
+impl DomainNames {
+    /// Function to make the mapping from the domain objects and their trusts.
+    pub fn new(vec_domains: &Vec<serde_json::value::Value>) -> Self
+    {
+        let mut names = DomainNames::default();
+        for domain in vec_domains {
+            names.insert(
+                domain["Properties"]["name"].as_str().unwrap_or(""),
+                domain["Properties"]["netbiosname"].as_str().unwrap_or(""),
+            );
+            for trust in domain["Trusts"].as_array().unwrap_or(&Vec::new()) {
+                names.insert(
+                    trust["TargetDomainName"].as_str().unwrap_or(""),
+                    trust["TargetDomainNetBIOSName"].as_str().unwrap_or(""),
+                );
+            }
+        }
+        names
+    }
+
+    /// Function to add one domain, names already known are kept (cross-references before trusts).
+    fn insert(&mut self, dns: &str, netbios: &str)
+    {
+        if dns.is_empty() || netbios.is_empty() {
+            return
+        }
+        self.netbios_dns.entry(netbios.to_uppercase()).or_insert(dns.to_uppercase());
+        self.dns_netbios.entry(dns.to_uppercase()).or_insert(netbios.to_uppercase());
+    }
+
+    /// Function to get the DNS name of one domain from its NetBIOS name, DNS names are returned as they are.
+    pub fn get_dns(&self, netbios: &str) -> Option<String>
+    {
+        let netbios = netbios.to_uppercase();
+        if self.dns_netbios.contains_key(&netbios) {
+            return Some(netbios)
+        }
+        self.netbios_dns.get(&netbios).cloned()
+    }
+
+    /// Function to get the NetBIOS name of one domain from its DNS name, the first label if unknown.
+    pub fn get_netbios(&self, dns: &str) -> String
+    {
+        let dns = dns.to_uppercase();
+        match self.dns_netbios.get(&dns) {
+            Some(netbios) => netbios.to_owned(),
+            None => dns.split('.').next().unwrap_or("").to_string(),
+        }
+    }
+
+    /// Function to get the BloodHound name `NAME@DOMAIN.LOCAL` of one `DOMAIN\name` account.
+    /// None without domain part or for an unknown domain.
+    pub fn get_principal_name(&self, account: &str) -> Option<String>
+    {
+        let (netbios, name) = account.split_once('\\')?;
+        Some(format!("{}@{}", name.to_uppercase(), self.get_dns(netbios)?))
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_domain_names() {
+    let vec_domains = vec![serde_json::json!({
+        "Properties": {"name": "CORP.CONTOSO-GROUP.COM", "netbiosname": "CONTOSO"},
+        "Trusts": [
+            {"TargetDomainName": "FABRIKAM.LOCAL", "TargetDomainNetBIOSName": "FAB"},
+            {"TargetDomainName": "OLD.LOCAL"},
+        ],
+    })];
+    let names = DomainNames::new(&vec_domains);
+    assert_eq!(names.get_dns("contoso"), Some("CORP.CONTOSO-GROUP.COM".to_string()));
+    assert_eq!(names.get_dns("FAB"), Some("FABRIKAM.LOCAL".to_string()));
+    assert_eq!(names.get_dns("fabrikam.local"), Some("FABRIKAM.LOCAL".to_string()));
+    assert_eq!(names.get_dns("CORP"), None);
+    assert_eq!(names.get_netbios("corp.contoso-group.com"), "CONTOSO");
+    assert_eq!(names.get_netbios("OLD.LOCAL"), "OLD");
+    assert_eq!(names.get_principal_name("CONTOSO\\jon.snow"), Some("JON.SNOW@CORP.CONTOSO-GROUP.COM".to_string()));
+    assert_eq!(names.get_principal_name("jon.snow"), None);
+}
@@ -0,0 +1,204 @@
+//! Third-party privileged groups and accounts
+//!
+//! Backup and virtualization products run with domain wide rights (Veeam, Commvault, NetBackup service
+//! accounts, `ESX Admins`, `vCenter Admins`, `SMS Admins`), their groups are as sensitive as Domain Admins but
+//! unknown to BloodHound. Groups and accounts are matched on their name and on the servers of the product found
+//! in services.json (`VCENTER01 Admins`), then tagged as high value so their members are Tier-0 too.
+//!
+//! The built-in pack is extended with **--privileged-groups** and one TOML mapping file:
+//!
+//! ```toml
+//! # Products of services.json whose hosting computers are tagged
+//! servers = ["Veeam", "Citrix"]
+//!
+//! [[principal]]
+//! pattern = "CTX Farm Admins"
+//! product = "Citrix"
+//! ```
+//!
+//! Patterns are case insensitive substrings of the name or sAMAccountName.
+use log::info;
+use std::fs;
+
+/// Built-in name patterns of third-party privileged groups and accounts, uppercase.
+const PRIVILEGED_PRINCIPALS: [(&str, &str); 14] = [
+    ("VEEAM", "Veeam"),
+    ("COMMVAULT", "Commvault"),
+    ("CVBACKUP", "Commvault"),
+    ("ESX ADMINS", "VMware"),
+    ("VCENTER ADMINS", "VMware"),
+    ("VSPHERE ADMINS", "VMware"),
+    ("VMWARE ADMINS", "VMware"),
+    ("NETBACKUP", "NetBackup"),
+    ("BACKUP EXEC", "Backup Exec"),
+    ("BACKUPEXEC", "Backup Exec"),
+    ("ARCSERVE", "Arcserve"),
+    ("SMS ADMINS", "SCCM"),
+    ("SCCM ADMINS", "SCCM"),
+    ("MECM ADMINS", "SCCM"),
+];
+
+/// Built-in products of services.json whose hosting computers are tagged.
+const PRIVILEGED_SERVERS: [&str; 7] = ["Veeam", "Commvault", "VMware", "NetBackup", "Backup Exec", "Arcserve", "SCCM"];
+
+/// Name patterns and server products of the detection pack.
+#[derive(Clone, Debug, Default)]
+pub struct PrivilegedPack {
+    pub principals: Vec<(String, String)>,
+    pub servers: Vec<String>,
+}
+
+impl PrivilegedPack {
+    /// Function to make the built-in pack.
+    pub fn new() -> Self
+    {
+        PrivilegedPack {
+            principals: PRIVILEGED_PRINCIPALS.iter().map(|(pattern, product)| (pattern.to_string(), product.to_string())).collect(),
+            servers: PRIVILEGED_SERVERS.iter().map(|product| product.to_string()).collect(),
+        }
+    }
+
+    /// Function to add the principals and servers of one TOML mapping file.
+    pub fn extend(&mut self, content: &str) -> std::result::Result<(), String>
+    {
+        let mapping: toml::Value = toml::from_str(content).map_err(|err| err.to_string())?;
+        let mapping = serde_json::to_value(mapping).map_err(|err| err.to_string())?;
+        for (i, principal) in mapping["principal"].as_array().unwrap_or(&Vec::new()).iter().enumerate() {
+            let pattern = principal["pattern"].as_str().filter(|pattern| !pattern.trim().is_empty()).ok_or(format!("principal {} without pattern", i + 1))?;
+            let product = principal["product"].as_str().unwrap_or("Custom");
+            self.principals.push((pattern.trim().to_uppercase(), product.to_string()));
+        }
+        for product in mapping["servers"].as_array().unwrap_or(&Vec::new()) {
+            let product = product.as_str().ok_or("servers must be a list of products".to_string())?;
+            if !self.servers.iter().any(|server| server.eq_ignore_ascii_case(product)) {
+                self.servers.push(product.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Function to get the product of one principal name.
+    fn get_product(&self, names: &[String]) -> Option<String>
+    {
+        self.principals.iter()
+            .find(|(pattern, _)| names.iter().any(|name| name.contains(pattern.as_str())))
+            .map(|(_, product)| product.to_owned())
+    }
+}
+
+/// Function to load the built-in pack and the mapping file of --privileged-groups.
+pub fn load_privileged_pack(path: &str) -> std::result::Result<PrivilegedPack, String>
+{
+    let mut pack = PrivilegedPack::new();
+    if !path.contains("not set") {
+        let content = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        pack.extend(&content).map_err(|err| format!("{}: {}", path, err))?;
+    }
+    Ok(pack)
+}
+
+/// Function to check the mapping file of --privileged-groups.
+pub fn parse_privileged_groups(value: &str) -> std::result::Result<String, String>
+{
+    load_privileged_pack(value).map(|_| value.to_string())
+}
+
+/// Function to get the uppercase names of one object without the domain.
+fn get_names(object: &serde_json::value::Value) -> Vec<String>
+{
+    ["name", "samaccountname"].iter()
+        .filter_map(|key| object["Properties"][key].as_str())
+        .map(|name| name.split('@').next().unwrap_or(name).to_uppercase())
+        .collect()
+}
+
+/// Function to tag the third-party privileged groups, accounts and servers as high value.
+/// Groups named after one server of a product (`VCENTER01 Admins`) are tagged with its product.
+/// Return the number of objects tagged.
+pub fn add_privileged_tags(
+    vec_users: &mut Vec<serde_json::value::Value>,
+    vec_groups: &mut Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    vec_services: &Vec<serde_json::value::Value>,
+    pack: &PrivilegedPack,
+) -> usize
+{
+    fn tag(object: &mut serde_json::value::Value, product: &str) {
+        object["Properties"]["highvalue"] = true.into();
+        object["Properties"]["privilegedproduct"] = product.into();
+    }
+    let mut count = 0;
+
+    // Servers of the products by SCP correlation
+    let mut servers: Vec<(String, String)> = Vec::new();
+    for service in vec_services {
+        let product = service["product"].as_str().unwrap_or("");
+        if let Some(sid) = service["computer"].as_str() {
+            if pack.servers.iter().any(|server| server.eq_ignore_ascii_case(product)) {
+                servers.push((sid.to_string(), product.to_string()));
+            }
+        }
+    }
+    let mut hostnames: Vec<(String, String)> = Vec::new();
+    for computer in vec_computers.iter_mut() {
+        let sid = computer["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+        if let Some((_, product)) = servers.iter().find(|(server, _)| *server == sid) {
+            let hostname = computer["Properties"]["name"].as_str().unwrap_or("").split('.').next().unwrap_or("").to_uppercase();
+            if !hostname.is_empty() {
+                hostnames.push((hostname, product.to_owned()));
+            }
+            tag(computer, product);
+            count += 1;
+        }
+    }
+
+    // Groups and accounts by name
+    for object in vec_groups.iter_mut().chain(vec_users.iter_mut()) {
+        let names = get_names(object);
+        let product = pack.get_product(&names).or_else(|| {
+            hostnames.iter()
+                .find(|(hostname, _)| names.iter().any(|name| name.contains(hostname.as_str()) && name.contains("ADMIN")))
+                .map(|(_, product)| product.to_owned())
+        });
+        if let Some(product) = product {
+            tag(object, &product);
+            count += 1;
+        }
+    }
+    info!("{} third-party privileged objects tagged", count);
+    count
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_add_privileged_tags() {
+    let mut vec_users = vec![
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1101", "Properties": {"name": "SVC_VEEAM@ESSOS.LOCAL", "samaccountname": "svc_veeam"}}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1102", "Properties": {"name": "JON.SNOW@ESSOS.LOCAL", "samaccountname": "jon.snow"}}),
+    ];
+    let mut vec_groups = vec![
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1201", "Properties": {"name": "ESX ADMINS@ESSOS.LOCAL"}}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1202", "Properties": {"name": "VC01 LOCAL ADMINS@ESSOS.LOCAL"}}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1203", "Properties": {"name": "CTX FARM ADMINS@ESSOS.LOCAL"}}),
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1204", "Properties": {"name": "VC01 USERS@ESSOS.LOCAL"}}),
+    ];
+    let mut vec_computers = vec![
+        serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1001", "Properties": {"name": "VC01.ESSOS.LOCAL"}}),
+    ];
+    let vec_services = vec![
+        serde_json::json!({"kind": "ServiceConnectionPoint", "product": "VMware", "computer": "S-1-5-21-1-2-3-1001"}),
+    ];
+    let mut pack = PrivilegedPack::new();
+    pack.extend("[[principal]]\npattern = \"ctx farm admins\"\nproduct = \"Citrix\"\n").unwrap();
+    assert!(pack.extend("[[principal]]\nproduct = \"Citrix\"\n").is_err());
+
+    let count = add_privileged_tags(&mut vec_users, &mut vec_groups, &mut vec_computers, &vec_services, &pack);
+    assert_eq!(count, 5);
+    assert_eq!(vec_users[0]["Properties"]["privilegedproduct"], "Veeam");
+    assert!(vec_users[1]["Properties"]["highvalue"].is_null());
+    assert_eq!(vec_groups[0]["Properties"]["privilegedproduct"], "VMware");
+    assert_eq!(vec_groups[1]["Properties"]["privilegedproduct"], "VMware");
+    assert_eq!(vec_groups[2]["Properties"]["privilegedproduct"], "Citrix");
+    assert!(vec_groups[3]["Properties"]["highvalue"].is_null());
+    assert_eq!(vec_computers[0]["Properties"]["highvalue"], true);
+}
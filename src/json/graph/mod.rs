@@ -0,0 +1,397 @@
+//! In-memory graph of the collected objects
+//!
+//! Nodes are the collected objects (ObjectIdentifier) and edges the BloodHound relations
+//! (MemberOf, abusable ACEs, AdminTo, Contains, GPLink, delegation...),
+//! so attack paths can be computed offline without Neo4j.
+pub mod render;
+
+use log::info;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+use crate::args::GraphOptions;
+use crate::enums::sid::get_well_known_rid;
+
+/// ACEs giving control over the target object
+const ABUSABLE_RIGHTS: [&str; 21] = [
+   "GenericAll",
+   "GenericWrite",
+   "WriteOwner",
+   "WriteDacl",
+   "Owns",
+   "AddMember",
+   "AddSelf",
+   "ForceChangePassword",
+   "AllExtendedRights",
+   "AddKeyCredentialLink",
+   "AddAllowedToAct",
+   "WriteSPN",
+   "WriteAccountRestrictions",
+   "WriteUserAccountControl",
+   "WriteLogonScript",
+   "ReadLAPSPassword",
+   "ReadGMSAPassword",
+   "GetChangesAll",
+   "ManageCa",
+   "ManageCertificates",
+   "ManagerOf",
+];
+
+/// Structure containing one node of the graph.
+pub struct Node {
+   pub object_identifier: String,
+   pub name: String,
+   pub kind: String,
+   pub tier_zero: bool,
+}
+
+/// Structure containing one relation between two objects.
+pub struct Edge {
+   pub source: String,
+   pub source_kind: String,
+   pub target: String,
+   pub kind: String,
+   pub ace: bool,
+}
+
+/// Structure containing one attack path: nodes and edges names from the source to the Tier-0 target.
+pub struct AttackPath {
+   pub nodes: Vec<NodeIndex>,
+   pub edges: Vec<String>,
+}
+
+/// Structure containing the graph of all collected objects.
+pub struct AdGraph {
+   pub graph: DiGraph<Node, String>,
+   index: HashMap<String, NodeIndex>,
+}
+
+impl AdGraph {
+   /// Build the graph from the collected objects, `objects` is a list of (node kind, objects).
+   pub fn build(objects: &[(&str, &Vec<serde_json::value::Value>)]) -> AdGraph {
+      let mut ad_graph = AdGraph { graph: DiGraph::new(), index: HashMap::new() };
+      for (kind, vec_objects) in objects {
+         for object in vec_objects.iter() {
+            ad_graph.add_node(object, kind);
+         }
+      }
+      for (_, vec_objects) in objects {
+         for object in vec_objects.iter() {
+            ad_graph.add_edges(object);
+         }
+      }
+      ad_graph
+   }
+
+   fn add_node(&mut self, object: &serde_json::value::Value, kind: &str) {
+      let object_identifier = object["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+      if object_identifier.is_empty() || self.index.contains_key(&object_identifier) {
+         return
+      }
+      let node = Node {
+         object_identifier: object_identifier.to_owned(),
+         name: object["Properties"]["name"].as_str().unwrap_or(&object_identifier).to_string(),
+         kind: kind.to_string(),
+         tier_zero: object["Properties"]["isTierZero"].as_bool().unwrap_or(false)
+            || object["Properties"]["highvalue"].as_bool().unwrap_or(false),
+      };
+      let index = self.graph.add_node(node);
+      self.index.insert(object_identifier, index);
+   }
+
+   /// Get the node for an ObjectIdentifier, unknown principals (foreign, well-known...) are added as nodes.
+   fn node(&mut self, object_identifier: &str, kind: &str) -> NodeIndex {
+      if let Some(index) = self.index.get(object_identifier) {
+         return *index
+      }
+      let index = self.graph.add_node(Node {
+         object_identifier: object_identifier.to_string(),
+         name: object_identifier.to_string(),
+         kind: kind.to_string(),
+         tier_zero: false,
+      });
+      self.index.insert(object_identifier.to_string(), index);
+      index
+   }
+
+   fn add_edge(&mut self, source: &str, source_kind: &str, target: &str, kind: &str) {
+      if source.is_empty() || target.is_empty() || source == target {
+         return
+      }
+      let source = self.node(source, source_kind);
+      let target = self.node(target, "Base");
+      self.graph.update_edge(source, target, kind.to_string());
+   }
+
+   fn add_edges(&mut self, object: &serde_json::value::Value) {
+      for edge in get_edges(object) {
+         if edge.ace && !ABUSABLE_RIGHTS.contains(&edge.kind.as_str()) {
+            continue
+         }
+         self.add_edge(&edge.source, &edge.source_kind, &edge.target, &edge.kind);
+      }
+   }
+
+   /// Get the node index from ObjectIdentifier.
+   pub fn get(&self, object_identifier: &str) -> Option<NodeIndex> {
+      self.index.get(object_identifier).copied()
+   }
+
+   /// Get the node index from its name (`Domain Admins` or `DOMAIN ADMINS@ESSOS.LOCAL`) or ObjectIdentifier.
+   pub fn find(&self, name: &str) -> Option<NodeIndex> {
+      let rid = get_well_known_rid(name);
+      let name = name.to_uppercase();
+      self.graph.node_indices().find(|index| {
+         let node = &self.graph[*index];
+         let node_name = node.name.to_uppercase();
+         node_name == name
+            || node_name.split('@').next() == Some(name.as_str())
+            || node.object_identifier.to_uppercase() == name
+      })
+      // Well-known groups by RID, "Domain Admins" is "Domänen-Admins" in a German domain
+      .or_else(|| self.graph.node_indices().find(|index| rid.map_or(false, |rid| self.graph[*index].object_identifier.ends_with(rid))))
+   }
+
+   /// Inbound control subgraph of one node: every principal with a path to it in `depth` edges maximum.
+   pub fn inbound_subgraph(&self, target: NodeIndex, depth: usize) -> (Vec<NodeIndex>, Vec<EdgeIndex>) {
+      let mut distance: HashMap<NodeIndex, usize> = HashMap::from([(target, 0)]);
+      let mut nodes: Vec<NodeIndex> = vec![target];
+      let mut edges: HashSet<EdgeIndex> = HashSet::new();
+      let mut queue: VecDeque<NodeIndex> = VecDeque::from([target]);
+      while let Some(current) = queue.pop_front() {
+         if distance[&current] >= depth {
+            continue
+         }
+         for edge in self.graph.edges_directed(current, Direction::Incoming) {
+            let source = edge.source();
+            edges.insert(edge.id());
+            if !distance.contains_key(&source) {
+               distance.insert(source, distance[&current] + 1);
+               nodes.push(source);
+               queue.push_back(source);
+            }
+         }
+      }
+      let mut edges: Vec<EdgeIndex> = edges.into_iter().collect();
+      edges.sort();
+      (nodes, edges)
+   }
+
+   /// Sources of attack paths: Domain Users, Authenticated Users and Everyone.
+   pub fn default_sources(&self) -> Vec<NodeIndex> {
+      self.graph.node_indices()
+         .filter(|index| {
+            let sid = &self.graph[*index].object_identifier;
+            sid.ends_with("-513") || sid.ends_with("S-1-5-11") || sid.ends_with("S-1-1-0")
+         })
+         .collect()
+   }
+
+   /// Shortest paths from the sources to every reachable Tier-0 node, sorted by length.
+   pub fn paths_to_tier_zero(&self, sources: &[NodeIndex], max_paths: usize) -> Vec<AttackPath> {
+      let mut paths: Vec<AttackPath> = Vec::new();
+      for source in sources {
+         // Breadth-first search, first hit is the shortest path
+         let mut previous: HashMap<NodeIndex, (NodeIndex, String)> = HashMap::new();
+         let mut queue: VecDeque<NodeIndex> = VecDeque::from([*source]);
+         while let Some(current) = queue.pop_front() {
+            if current != *source && self.graph[current].tier_zero {
+               paths.push(self.make_path(*source, current, &previous));
+               // Don't go through a Tier-0 node, the path to it is enough
+               continue
+            }
+            for edge in self.graph.edges(current) {
+               let next = edge.target();
+               if next != *source && !previous.contains_key(&next) {
+                  previous.insert(next, (current, edge.weight().to_owned()));
+                  queue.push_back(next);
+               }
+            }
+         }
+      }
+      paths.sort_by_key(|path| path.nodes.len());
+      paths.truncate(max_paths);
+      paths
+   }
+
+   fn make_path(&self, source: NodeIndex, target: NodeIndex, previous: &HashMap<NodeIndex, (NodeIndex, String)>) -> AttackPath {
+      let mut nodes = vec![target];
+      let mut edges: Vec<String> = Vec::new();
+      let mut current = target;
+      while current != source {
+         let (node, edge) = &previous[&current];
+         nodes.push(*node);
+         edges.push(edge.to_owned());
+         current = *node;
+      }
+      nodes.reverse();
+      edges.reverse();
+      AttackPath { nodes, edges }
+   }
+}
+
+/// Function to get the node type from the BloodHound .json file type (users => User).
+pub fn get_node_kind(name: &str) -> Option<&'static str> {
+   match name {
+      "users" => Some("User"),
+      "groups" => Some("Group"),
+      "computers" => Some("Computer"),
+      "ous" => Some("OU"),
+      "domains" => Some("Domain"),
+      "gpos" => Some("GPO"),
+      "containers" => Some("Container"),
+      "contacts" => Some("Base"),
+      "cas" => Some("EnterpriseCA"),
+      "templates" => Some("CertTemplate"),
+      "sites" => Some("Site"),
+      "authnpolicies" => Some("AuthNPolicy"),
+      _ => None,
+   }
+}
+
+/// Function to load the objects from the BloodHound .json files of one directory.
+pub fn load_directory(path: &str) -> std::io::Result<Vec<(&'static str, Vec<serde_json::value::Value>)>> {
+   let mut objects: Vec<(&'static str, Vec<serde_json::value::Value>)> = Vec::new();
+   for entry in fs::read_dir(path)? {
+      let path = entry?.path();
+      if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+         continue
+      }
+      let final_json: serde_json::value::Value = match serde_json::from_str(&fs::read_to_string(&path)?) {
+         Ok(final_json) => final_json,
+         Err(_) => continue,
+      };
+      // Only BloodHound files, not report.json or service_accounts.json
+      if let (Some(kind), Some(data)) = (get_node_kind(final_json["meta"]["type"].as_str().unwrap_or("")), final_json["data"].as_array()) {
+         objects.push((kind, data.to_owned()));
+      }
+   }
+   Ok(objects)
+}
+
+/// Function to render the inbound control subgraph of the `--target` object from collected files (`rusthound graph`).
+pub fn render_graph(graph_args: &GraphOptions) -> crate::errors::Result<()> {
+   let objects = load_directory(&graph_args.input)?;
+   if objects.is_empty() {
+      return Err(Error::new(ErrorKind::NotFound, format!("No BloodHound .json file in {}", graph_args.input)).into())
+   }
+   let objects: Vec<(&str, &Vec<serde_json::value::Value>)> = objects.iter().map(|(kind, data)| (*kind, data)).collect();
+   let graph = AdGraph::build(&objects);
+   let target = graph.find(&graph_args.target)
+      .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("{} not found in collected objects", graph_args.target)))?;
+   let (nodes, edges) = graph.inbound_subgraph(target, graph_args.depth as usize);
+   let content = match graph_args.format.as_str() {
+      "mermaid" => render::make_mermaid(&graph, &nodes, &edges),
+      _ => render::make_dot(&graph, &nodes, &edges),
+   };
+   if graph_args.output.contains("not set") {
+      println!("{}", content);
+   } else {
+      fs::write(&graph_args.output, content)?;
+      info!("{} created!", &graph_args.output);
+   }
+   Ok(())
+}
+
+/// Function to get all relations of one collected object.
+/// Edges from ACEs are flagged since only abusable rights are kept in the graph.
+pub fn get_edges(object: &serde_json::value::Value) -> Vec<Edge> {
+   let mut edges: Vec<Edge> = Vec::new();
+   let target = object["ObjectIdentifier"].as_str().unwrap_or("");
+   let empty = Vec::new();
+   let array = |value: &serde_json::value::Value| value.as_array().unwrap_or(&empty).to_owned();
+   let string = |value: &serde_json::value::Value| value.as_str().unwrap_or("").to_string();
+   let mut push = |source: String, source_kind: String, target: String, kind: &str, ace: bool| {
+      if !source.is_empty() && !target.is_empty() && source != target {
+         edges.push(Edge { source, source_kind, target, kind: kind.to_string(), ace });
+      }
+   };
+
+   for member in array(&object["Members"]) {
+      push(string(&member["ObjectIdentifier"]), string(&member["ObjectType"]), target.to_string(), "MemberOf", false);
+   }
+   for ace in array(&object["Aces"]) {
+      push(string(&ace["PrincipalSID"]), string(&ace["PrincipalType"]), target.to_string(), &string(&ace["RightName"]), true);
+   }
+   for member in array(&object["AllowedToAct"]) {
+      push(string(&member["ObjectIdentifier"]), string(&member["ObjectType"]), target.to_string(), "AllowedToAct", false);
+   }
+   for delegated in array(&object["AllowedToDelegate"]) {
+      push(target.to_string(), "Base".to_string(), string(&delegated["ObjectIdentifier"]), "AllowedToDelegate", false);
+   }
+   for (key, edge) in [("LocalAdmins", "AdminTo"), ("RemoteDesktopUsers", "CanRDP"), ("DcomUsers", "ExecuteDCOM"), ("PSRemoteUsers", "CanPSRemote")] {
+      for member in array(&object[key]["Results"]) {
+         push(string(&member["ObjectIdentifier"]), string(&member["ObjectType"]), target.to_string(), edge, false);
+      }
+      // GPO local groups on OUs and domains
+      for member in array(&object["GPOChanges"][key]) {
+         for computer in array(&object["GPOChanges"]["AffectedComputers"]) {
+            push(string(&member["ObjectIdentifier"]), string(&member["ObjectType"]), string(&computer["ObjectIdentifier"]), edge, false);
+         }
+      }
+   }
+   for session in array(&object["Sessions"]["Results"]) {
+      push(target.to_string(), "Computer".to_string(), string(&session["UserSID"]), "HasSession", false);
+   }
+   for child in array(&object["ChildObjects"]) {
+      push(target.to_string(), "Base".to_string(), string(&child["ObjectIdentifier"]), "Contains", false);
+   }
+   for link in array(&object["Links"]) {
+      push(string(&link["GUID"]), "GPO".to_string(), target.to_string(), "GPLink", false);
+   }
+   // Users and computers assigned to one authentication policy or silo
+   let assigned = if object["Properties"]["type"] == "silo" { "AssignedAuthNPolicySilo" } else { "AssignedAuthNPolicy" };
+   for account in array(&object["Accounts"]) {
+      push(string(&account["ObjectIdentifier"]), string(&account["ObjectType"]), target.to_string(), assigned, false);
+   }
+   edges
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_paths_to_tier_zero() {
+   use serde_json::json;
+   let users = vec![
+      json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1105", "Properties": {"name": "JON.SNOW@NORTH.LOCAL"}, "Aces": [
+         {"RightName": "ForceChangePassword", "PrincipalSID": "S-1-5-21-1-2-3-513", "PrincipalType": "Group"}
+      ]}),
+   ];
+   let groups = vec![
+      json!({"ObjectIdentifier": "S-1-5-21-1-2-3-513", "Properties": {"name": "DOMAIN USERS@NORTH.LOCAL"}, "Members": [], "Aces": []}),
+      json!({"ObjectIdentifier": "S-1-5-21-1-2-3-512", "Properties": {"name": "DOMAIN ADMINS@NORTH.LOCAL", "highvalue": true}, "Members": [], "Aces": [
+         {"RightName": "AddMember", "PrincipalSID": "S-1-5-21-1-2-3-1105", "PrincipalType": "User"}
+      ]}),
+   ];
+   let graph = AdGraph::build(&[("User", &users), ("Group", &groups)]);
+   let paths = graph.paths_to_tier_zero(&graph.default_sources(), 10);
+   assert_eq!(paths.len(), 1);
+   assert_eq!(paths[0].edges, vec!["ForceChangePassword".to_string(), "AddMember".to_string()]);
+   assert_eq!(graph.graph[paths[0].nodes[2]].name, "DOMAIN ADMINS@NORTH.LOCAL");
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_inbound_subgraph() {
+   use serde_json::json;
+   let groups = vec![
+      json!({"ObjectIdentifier": "S-1-5-21-1-2-3-512", "Properties": {"name": "DOMAIN ADMINS@ESSOS.LOCAL"}, "Members": [
+         {"ObjectIdentifier": "S-1-5-21-1-2-3-1110", "ObjectType": "User"}
+      ], "Aces": [
+         {"RightName": "GenericAll", "PrincipalSID": "S-1-5-21-1-2-3-519", "PrincipalType": "Group"}
+      ]}),
+      json!({"ObjectIdentifier": "S-1-5-21-1-2-3-519", "Properties": {"name": "ENTERPRISE ADMINS@ESSOS.LOCAL"}, "Members": [
+         {"ObjectIdentifier": "S-1-5-21-1-2-3-1111", "ObjectType": "User"}
+      ], "Aces": []}),
+   ];
+   let graph = AdGraph::build(&[("Group", &groups)]);
+   let target = graph.find("Domain Admins").unwrap();
+   assert_eq!(graph.find("Domänen-Admins"), Some(target));
+   assert_eq!(graph.inbound_subgraph(target, 1).0.len(), 3);
+   let (nodes, edges) = graph.inbound_subgraph(target, 2);
+   assert_eq!(nodes.len(), 4);
+   assert_eq!(edges.len(), 3);
+}
@@ -0,0 +1,119 @@
+//! Attack paths reports in Markdown and HTML, subgraphs in DOT (Graphviz) and Mermaid
+use petgraph::graph::{EdgeIndex, NodeIndex};
+
+use crate::json::graph::{AdGraph, AttackPath};
+
+/// Function to make the attack paths report in Markdown.
+pub fn make_markdown(graph: &AdGraph, paths: &[AttackPath], domain: &str) -> String {
+   let mut markdown = format!("# Attack paths to Tier-0 on {}\n\n", domain);
+   if paths.is_empty() {
+      markdown.push_str("No attack path found from Domain Users, Authenticated Users or Everyone to Tier-0.\n");
+      return markdown
+   }
+   markdown.push_str(&format!("{} shortest attack paths from Domain Users, Authenticated Users and Everyone to Tier-0.\n\n", paths.len()));
+   markdown.push_str("| # | Length | Source | Target | Path |\n");
+   markdown.push_str("|---|--------|--------|--------|------|\n");
+   for (i, path) in paths.iter().enumerate() {
+      markdown.push_str(&format!("| {} | {} | {} | {} | {} |\n",
+         i + 1,
+         path.edges.len(),
+         escape_markdown(&graph.graph[path.nodes[0]].name),
+         escape_markdown(&graph.graph[*path.nodes.last().unwrap()].name),
+         escape_markdown(&format_path(graph, path)),
+      ));
+   }
+   markdown
+}
+
+/// Function to make the attack paths report in HTML, one self-contained file.
+pub fn make_html(graph: &AdGraph, paths: &[AttackPath], domain: &str) -> String {
+   let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+   html.push_str(&format!("<title>Attack paths to Tier-0 on {}</title>\n", escape_html(domain)));
+   html.push_str("<style>body{font-family:sans-serif}table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:4px 8px;text-align:left}.edge{color:#c0392b;font-weight:bold}</style>\n");
+   html.push_str("</head>\n<body>\n");
+   html.push_str(&format!("<h1>Attack paths to Tier-0 on {}</h1>\n", escape_html(domain)));
+   if paths.is_empty() {
+      html.push_str("<p>No attack path found from Domain Users, Authenticated Users or Everyone to Tier-0.</p>\n</body>\n</html>\n");
+      return html
+   }
+   html.push_str("<table>\n<tr><th>#</th><th>Length</th><th>Source</th><th>Target</th><th>Path</th></tr>\n");
+   for (i, path) in paths.iter().enumerate() {
+      let mut steps = escape_html(&graph.graph[path.nodes[0]].name);
+      for (edge, node) in path.edges.iter().zip(path.nodes.iter().skip(1)) {
+         steps.push_str(&format!(" <span class=\"edge\">-[{}]-&gt;</span> {}", escape_html(edge), escape_html(&graph.graph[*node].name)));
+      }
+      html.push_str(&format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+         i + 1,
+         path.edges.len(),
+         escape_html(&graph.graph[path.nodes[0]].name),
+         escape_html(&graph.graph[*path.nodes.last().unwrap()].name),
+         steps,
+      ));
+   }
+   html.push_str("</table>\n</body>\n</html>\n");
+   html
+}
+
+/// Function to format one path like `A -[MemberOf]-> B -[GenericAll]-> C`.
+pub fn format_path(graph: &AdGraph, path: &AttackPath) -> String {
+   let mut steps = graph.graph[path.nodes[0]].name.to_owned();
+   for (edge, node) in path.edges.iter().zip(path.nodes.iter().skip(1)) {
+      steps.push_str(&format!(" -[{}]-> {}", edge, graph.graph[*node].name));
+   }
+   steps
+}
+
+/// Function to render one subgraph in DOT, the first node is the target.
+pub fn make_dot(graph: &AdGraph, nodes: &[NodeIndex], edges: &[EdgeIndex]) -> String {
+   let mut dot = format!("digraph \"{}\" {{\n", escape_dot(&graph.graph[nodes[0]].name));
+   dot.push_str("   rankdir=LR;\n   node [shape=box];\n");
+   for (i, node) in nodes.iter().enumerate() {
+      let style = if i == 0 { ", style=filled, fillcolor=\"#e74c3c\"" } else { "" };
+      dot.push_str(&format!("   n{} [label=\"{}\\n({})\"{}];\n",
+         node.index(),
+         escape_dot(&graph.graph[*node].name),
+         escape_dot(&graph.graph[*node].kind),
+         style,
+      ));
+   }
+   for edge in edges {
+      let (source, target) = graph.graph.edge_endpoints(*edge).unwrap();
+      dot.push_str(&format!("   n{} -> n{} [label=\"{}\"];\n", source.index(), target.index(), escape_dot(&graph.graph[*edge])));
+   }
+   dot.push_str("}\n");
+   dot
+}
+
+/// Function to render one subgraph in Mermaid, the first node is the target.
+pub fn make_mermaid(graph: &AdGraph, nodes: &[NodeIndex], edges: &[EdgeIndex]) -> String {
+   let mut mermaid = String::from("graph LR\n");
+   for node in nodes {
+      mermaid.push_str(&format!("   n{}[\"{}<br>({})\"]\n",
+         node.index(),
+         escape_mermaid(&graph.graph[*node].name),
+         escape_mermaid(&graph.graph[*node].kind),
+      ));
+   }
+   for edge in edges {
+      let (source, target) = graph.graph.edge_endpoints(*edge).unwrap();
+      mermaid.push_str(&format!("   n{} -->|{}| n{}\n", source.index(), escape_mermaid(&graph.graph[*edge]), target.index()));
+   }
+   mermaid.push_str(&format!("   style n{} fill:#e74c3c\n", nodes[0].index()));
+   mermaid
+}
+
+fn escape_dot(value: &str) -> String {
+   value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(value: &str) -> String {
+   value.replace('"', "#quot;").replace('|', "#124;")
+}
+
+fn escape_markdown(value: &str) -> String {
+   value.replace('|', "\\|")
+}
+
+fn escape_html(value: &str) -> String {
+   value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,181 @@
+//! BloodHound CE upload sink.
+//!
+//! The .json files are uploaded in one file upload job with the BloodHound CE API (**--upload**, **--token**):
+//!
+//! 1. `POST /api/v2/file-upload/start` creates the job
+//! 2. `POST /api/v2/file-upload/<id>` uploads every .json file
+//! 3. `POST /api/v2/file-upload/<id>/end` closes the job
+//! 4. `GET /api/v2/file-upload?id=eq:<id>` is polled until the ingest is finished
+//!
+//! The token is a JWT sent as bearer token, API token id/key pairs (HMAC signed requests) are not supported.
+use log::{info,debug,warn};
+use colored::Colorize;
+
+use std::io;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::json::maker::http::HttpClient;
+use crate::json::maker::sink::Sink;
+
+/// Delay between two job status requests
+const POLL_DELAY: Duration = Duration::from_secs(5);
+/// Maximum wait for the ingest of one job
+const POLL_TIMEOUT: Duration = Duration::from_secs(1800);
+
+/// Upload the .json files in BloodHound CE and wait for the ingest.
+pub struct BloodHoundSink {
+   client: HttpClient,
+   authorization: String,
+   job_id: Option<i64>,
+   files: usize,
+}
+
+impl BloodHoundSink {
+   pub fn new(url: &String, token: &String) -> io::Result<Self> {
+      Ok(BloodHoundSink {
+         client: HttpClient::new(url, &"not set".to_string())?,
+         authorization: format!("Authorization: Bearer {}",token),
+         job_id: None,
+         files: 0,
+      })
+   }
+
+   fn api_path(&self, path: &str) -> String {
+      format!("{}{}", self.client.endpoint.path.trim_end_matches('/'), path)
+   }
+
+   /// Send one POST request to the API and return the `data` object of the response.
+   fn post(&self, path: &str, content: &[u8]) -> io::Result<serde_json::value::Value> {
+      let (status, response) = self.client.post(&self.api_path(path), &[self.authorization.to_owned()], "application/json", content)?;
+      check_response(status, &response)
+   }
+
+   /// Create the file upload job on first file.
+   fn get_job_id(&mut self) -> io::Result<i64> {
+      if let Some(job_id) = self.job_id {
+         return Ok(job_id)
+      }
+      let data = self.post("/api/v2/file-upload/start", b"")?;
+      let job_id = data["id"].as_i64()
+         .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No job id in BloodHound file upload response"))?;
+      info!("BloodHound file upload job {} created on {}",job_id.to_string().bold(),self.client.endpoint.host.bold());
+      self.job_id = Some(job_id);
+      Ok(job_id)
+   }
+
+   /// Poll the job status until the ingest is finished.
+   fn wait_job(&self, job_id: i64) -> io::Result<()> {
+      let start = Instant::now();
+      let mut polls = 0;
+      while start.elapsed() < POLL_TIMEOUT {
+         sleep(POLL_DELAY);
+         polls += 1;
+         let (status, response) = self.client.get(&self.api_path(&format!("/api/v2/file-upload?id=eq:{}",job_id)), &[self.authorization.to_owned()])?;
+         let data = check_response(status, &response)?;
+         let job = match find_job(&data, job_id) {
+            Some(job) => job,
+            // Job may not be listed yet on the first poll only
+            None if polls == 1 => {
+               debug!("BloodHound file upload job {} not listed yet",job_id);
+               continue
+            },
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("BloodHound file upload job {} not found",job_id))),
+         };
+         let job_status = job["status"].as_i64().unwrap_or(0);
+         debug!("BloodHound file upload job {} status: {} {}",job_id,job_status,job["status_message"].as_str().unwrap_or(""));
+         match get_job_state(job_status) {
+            JobState::Running => continue,
+            JobState::Complete => return Ok(()),
+            JobState::Partial => {
+               warn!("BloodHound ingest partially completed: {}",job["status_message"].as_str().unwrap_or(""));
+               return Ok(())
+            },
+            JobState::Failed => {
+               return Err(io::Error::new(io::ErrorKind::Other, format!("BloodHound ingest failed: {}",job["status_message"].as_str().unwrap_or(""))))
+            },
+         }
+      }
+      Err(io::Error::new(io::ErrorKind::TimedOut, format!("BloodHound ingest not finished after {} minutes",POLL_TIMEOUT.as_secs() / 60)))
+   }
+}
+
+impl Sink for BloodHoundSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> io::Result<()> {
+      // Empty files are rejected by the API
+      if final_json["data"].as_array().map(|data| data.is_empty()).unwrap_or(true) {
+         return Ok(())
+      }
+      let job_id = self.get_job_id()?;
+      let (status, response) = self.client.post_json(&self.api_path(&format!("/api/v2/file-upload/{}",job_id)), &[self.authorization.to_owned()], final_json)?;
+      check_response(status, &response)?;
+      info!("{}.json uploaded to BloodHound!",name.bold());
+      self.files += 1;
+      Ok(())
+   }
+
+   fn write_file(&mut self, _name: &str, _content: &str) -> io::Result<()> {
+      Ok(())
+   }
+
+   fn finish(&mut self) -> io::Result<()> {
+      let job_id = match self.job_id {
+         Some(job_id) => job_id,
+         None => return Ok(()),
+      };
+      self.post(&format!("/api/v2/file-upload/{}/end",job_id), b"")?;
+      info!("Waiting for BloodHound to ingest {} files...",self.files.to_string().bold());
+      self.wait_job(job_id)?;
+      info!("BloodHound ingest of job {} finished!",job_id.to_string().bold());
+      Ok(())
+   }
+}
+
+/// State of one file upload job.
+#[derive(Debug, PartialEq)]
+pub enum JobState {
+   Running,
+   Complete,
+   Partial,
+   Failed,
+}
+
+/// Function to get the job state from the BloodHound CE job status.
+/// -1 invalid, 0 ready, 1 running, 2 complete, 3 canceled, 4 timed out, 5 failed, 6 ingesting, 7 analyzing, 8 partially complete
+pub fn get_job_state(status: i64) -> JobState {
+   match status {
+      2 => JobState::Complete,
+      8 => JobState::Partial,
+      -1 | 3 | 4 | 5 => JobState::Failed,
+      _ => JobState::Running,
+   }
+}
+
+/// Function to find one job in the `data` list of the file upload jobs.
+fn find_job(data: &serde_json::value::Value, job_id: i64) -> Option<serde_json::value::Value> {
+   data.as_array()?.iter().find(|job| job["id"].as_i64() == Some(job_id)).cloned()
+}
+
+/// Function to check the HTTP status and get the `data` object of one API response.
+fn check_response(status: u16, response: &str) -> io::Result<serde_json::value::Value> {
+   if !(200..300).contains(&status) {
+      return Err(io::Error::new(io::ErrorKind::Other, format!("BloodHound API returned HTTP {}: {}",status,response.trim())))
+   }
+   let response: serde_json::value::Value = serde_json::from_str(response).unwrap_or_default();
+   Ok(response["data"].to_owned())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_job_state() {
+   assert_eq!(get_job_state(0), JobState::Running);
+   assert_eq!(get_job_state(6), JobState::Running);
+   assert_eq!(get_job_state(2), JobState::Complete);
+   assert_eq!(get_job_state(5), JobState::Failed);
+   assert_eq!(check_response(201, r#"{"data":{"id":12}}"#).unwrap()["id"], 12);
+   assert!(check_response(401, "").is_err());
+   let data = check_response(200, r#"{"data":[{"id":11,"status":5},{"id":12,"status":2}]}"#).unwrap();
+   assert_eq!(find_job(&data, 12).unwrap()["status"], 2);
+   assert!(find_job(&data, 13).is_none());
+   assert!(find_job(&serde_json::value::Value::Null, 12).is_none());
+}
@@ -0,0 +1,369 @@
+//! HTTP sink to send results to a remote endpoint without writing on disk.
+//!
+//! Every file is sent in one POST request with `X-RustHound-File` header, optional bearer token and TLS client certificate.
+//! JSON files are serialized while sent with chunked transfer encoding, they are never buffered whole.
+//! Server certificate is checked with the system root certificates.
+//! [`HttpClient`] is shared with the SIEM sinks, see [`crate::json::maker::siem`].
+//!
+use log::{info,debug};
+use colored::Colorize;
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::json::maker::sink::Sink;
+
+/// TCP connect, read and write timeout for every request
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Size of the chunks of the JSON bodies streamed
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Body of one request: bytes sent with Content-Length, or JSON serialized while sent with chunked transfer encoding.
+pub enum Body<'a> {
+   Bytes(&'a [u8]),
+   Json(&'a serde_json::value::Value),
+}
+
+/// Structure containing the endpoint parsed from the url.
+pub struct Endpoint {
+   pub tls: bool,
+   pub host: String,
+   pub port: u16,
+   pub path: String,
+}
+
+/// Minimal HTTP/1.1 client, used by HTTP, SIEM, Neo4j and BloodHound sinks.
+pub struct HttpClient {
+   pub endpoint: Endpoint,
+   tls_config: Option<Arc<rustls::ClientConfig>>,
+}
+
+impl HttpClient {
+   pub fn new(url: &String, client_cert: &String) -> io::Result<Self> {
+      let endpoint = parse_url(url)?;
+      let tls_config = if endpoint.tls { Some(Arc::new(prepare_tls_config(client_cert)?)) } else { None };
+      Ok(HttpClient { endpoint, tls_config })
+   }
+
+   /// Send one POST request to `path` with extra headers and return the HTTP status and the response body.
+   pub fn post(&self, path: &str, headers: &[String], content_type: &str, content: &[u8]) -> io::Result<(u16, String)> {
+      self.request("POST", path, headers, Some(content_type), Body::Bytes(content))
+   }
+
+   /// Send one POST request to `path` with one JSON body streamed and return the HTTP status and the response body.
+   pub fn post_json(&self, path: &str, headers: &[String], content: &serde_json::value::Value) -> io::Result<(u16, String)> {
+      self.request("POST", path, headers, Some("application/json"), Body::Json(content))
+   }
+
+   /// Send one GET request to `path` with extra headers and return the HTTP status and the response body.
+   pub fn get(&self, path: &str, headers: &[String]) -> io::Result<(u16, String)> {
+      self.request("GET", path, headers, None, Body::Bytes(&[]))
+   }
+
+   fn request(&self, method: &str, path: &str, headers: &[String], content_type: Option<&str>, body: Body) -> io::Result<(u16, String)> {
+      let length = match &body {
+         Body::Bytes(content) => format!("Content-Length: {}", content.len()),
+         Body::Json(_) => "Transfer-Encoding: chunked".to_string(),
+      };
+      let mut request = format!(
+         "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: RustHound\r\n{}\r\nConnection: close\r\n",
+         method, path, get_host_header(&self.endpoint.host), length,
+      ).into_bytes();
+      if let Some(content_type) = content_type {
+         request.extend(format!("Content-Type: {}\r\n",content_type).into_bytes());
+      }
+      for header in headers {
+         request.extend(format!("{}\r\n",header).into_bytes());
+      }
+      request.extend(b"\r\n");
+
+      let socket = connect(&self.endpoint.host, self.endpoint.port)?;
+      socket.set_read_timeout(Some(HTTP_TIMEOUT))?;
+      socket.set_write_timeout(Some(HTTP_TIMEOUT))?;
+      let mut response = Vec::new();
+      match &self.tls_config {
+         Some(config) => {
+            let server_name = rustls::ServerName::try_from(self.endpoint.host.as_str())
+               .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            let connection = rustls::ClientConnection::new(config.to_owned(), server_name)
+               .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+            let mut stream = rustls::StreamOwned::new(connection, socket);
+            send_request(&mut stream, &request, &body)?;
+            read_response(&mut stream, &mut response)?;
+         },
+         None => {
+            let mut stream = socket;
+            send_request(&mut stream, &request, &body)?;
+            read_response(&mut stream, &mut response)?;
+         },
+      }
+
+      let (status, body) = parse_response(&response);
+      debug!("{} {} response: {}",method,path,status);
+      Ok((status, body))
+   }
+}
+
+/// Connect to the first address of host answering before HTTP_TIMEOUT, unreachable endpoints don't wait for the OS timeout.
+fn connect(host: &str, port: u16) -> io::Result<TcpStream> {
+   let mut last_error = io::Error::new(io::ErrorKind::NotFound, format!("No address found for {}",host));
+   for address in (host, port).to_socket_addrs()? {
+      match TcpStream::connect_timeout(&address, HTTP_TIMEOUT) {
+         Ok(socket) => return Ok(socket),
+         Err(err) => last_error = err,
+      }
+   }
+   Err(last_error)
+}
+
+/// Host header value, IPv6 addresses are between brackets.
+fn get_host_header(host: &str) -> String {
+   if host.contains(':') { format!("[{}]",host) } else { host.to_string() }
+}
+
+/// Send every file to the endpoint with POST requests.
+pub struct HttpSink {
+   client: HttpClient,
+   prefix: String,
+   token: Option<String>,
+}
+
+impl HttpSink {
+   pub fn new(
+      url: &String,
+      token: &String,
+      client_cert: &String,
+      datetime: &String,
+      domain_format: &String,
+   ) -> io::Result<Self> {
+      let client = HttpClient::new(url, client_cert)?;
+      let token = if token.contains("not set") { None } else { Some(token.to_owned()) };
+      Ok(HttpSink { client, prefix: format!("{}_{}",datetime,domain_format), token })
+   }
+
+   /// Send one POST request and check the HTTP status.
+   fn post(&self, name: &str, content_type: &str, body: Body) -> io::Result<()> {
+      let filename = format!("{}_{}",self.prefix,name);
+      let mut headers = vec![format!("X-RustHound-File: {}",filename)];
+      if let Some(token) = &self.token {
+         headers.push(format!("Authorization: Bearer {}",token));
+      }
+      let (status, _) = self.client.request("POST", &self.client.endpoint.path, &headers, Some(content_type), body)?;
+      if !(200..300).contains(&status) {
+         return Err(io::Error::new(io::ErrorKind::Other, format!("HTTP sink returned {}",status)))
+      }
+      info!("{} sent to {}!",filename.bold(),self.client.endpoint.host.bold());
+      Ok(())
+   }
+}
+
+impl Sink for HttpSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> io::Result<()> {
+      self.post(&format!("{}.json",name), "application/json", Body::Json(final_json))
+   }
+
+   fn write_file(&mut self, name: &str, content: &str) -> io::Result<()> {
+      let content_type = if name.ends_with(".json") { "application/json" } else { "text/plain" };
+      self.post(name, content_type, Body::Bytes(content.as_bytes()))
+   }
+}
+
+/// Writer of one chunked transfer encoding body, every write is one chunk.
+struct ChunkedWriter<W: Write> {
+   inner: W,
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+   fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+      // One empty chunk would end the body
+      if buffer.is_empty() {
+         return Ok(0)
+      }
+      write!(self.inner, "{:x}\r\n", buffer.len())?;
+      self.inner.write_all(buffer)?;
+      self.inner.write_all(b"\r\n")?;
+      Ok(buffer.len())
+   }
+
+   fn flush(&mut self) -> io::Result<()> {
+      self.inner.flush()
+   }
+}
+
+/// Write the request head then the body, JSON bodies are serialized in chunks of CHUNK_SIZE.
+fn send_request<T: Write>(stream: &mut T, head: &[u8], body: &Body) -> io::Result<()> {
+   stream.write_all(head)?;
+   match body {
+      Body::Bytes(content) => stream.write_all(content)?,
+      Body::Json(content) => {
+         let mut chunked = BufWriter::with_capacity(CHUNK_SIZE, ChunkedWriter { inner: &mut *stream });
+         serde_json::to_writer(&mut chunked, content)?;
+         chunked.flush()?;
+         drop(chunked);
+         stream.write_all(b"0\r\n\r\n")?;
+      },
+   }
+   stream.flush()
+}
+
+/// Split the response in status and body on raw bytes, the body is decoded before being read as text.
+fn parse_response(response: &[u8]) -> (u16, String) {
+   let (head, body) = match response.windows(4).position(|window| window == b"\r\n\r\n") {
+      Some(position) => (&response[..position], &response[position + 4..]),
+      None => (response, &response[response.len()..]),
+   };
+   let head = String::from_utf8_lossy(head).to_lowercase();
+   let status = head.lines().next().and_then(|line| line.split_whitespace().nth(1)).and_then(|status| status.parse::<u16>().ok()).unwrap_or(0);
+   let body = match head.lines().any(|line| line.starts_with("transfer-encoding:") && line.contains("chunked")) {
+      true => decode_chunked(body),
+      false => body.to_vec(),
+   };
+   (status, String::from_utf8_lossy(&body).to_string())
+}
+
+/// Read the whole response, the connection is closed by the server.
+/// Servers closing TLS without close_notify are accepted once data is received.
+fn read_response<T: Read>(stream: &mut T, response: &mut Vec<u8>) -> io::Result<()> {
+   let mut buffer = [0u8; 4096];
+   loop {
+      match stream.read(&mut buffer) {
+         Ok(0) => break,
+         Ok(count) => response.extend_from_slice(&buffer[..count]),
+         Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && !response.is_empty() => break,
+         Err(err) => return Err(err),
+      }
+   }
+   Ok(())
+}
+
+/// Decode a chunked response body: `<size in hex>\r\n<data>\r\n`... until a zero size chunk.
+/// Chunk sizes are in bytes, the body is decoded on raw bytes.
+fn decode_chunked(body: &[u8]) -> Vec<u8> {
+   let mut decoded: Vec<u8> = Vec::new();
+   let mut rest = body;
+   while let Some(position) = rest.windows(2).position(|window| window == b"\r\n") {
+      let size = String::from_utf8_lossy(&rest[..position]).split(';').next().unwrap_or("").trim().to_string();
+      let size = usize::from_str_radix(&size, 16).unwrap_or(0);
+      let data = &rest[position + 2..];
+      if size == 0 || data.len() < size {
+         break
+      }
+      decoded.extend_from_slice(&data[..size]);
+      rest = data[size..].strip_prefix(b"\r\n").unwrap_or(&data[size..]);
+   }
+   decoded
+}
+
+/// Parse http(s)://host[:port][/path] url.
+pub fn parse_url(url: &String) -> io::Result<Endpoint> {
+   let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid HTTP sink url: {}",url));
+   let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+      (true, rest)
+   } else if let Some(rest) = url.strip_prefix("http://") {
+      (false, rest)
+   } else {
+      return Err(invalid())
+   };
+   let (authority, path) = match rest.find('/') {
+      Some(index) => (&rest[..index], rest[index..].to_string()),
+      None => (rest, "/".to_string()),
+   };
+   // IPv6 addresses between brackets: https://[::1]:8443/
+   let (host, port) = match authority.strip_prefix('[') {
+      Some(rest) => {
+         let (host, port) = rest.split_once(']').ok_or_else(invalid)?;
+         match port {
+            "" => (host, None),
+            port => (host, Some(port.strip_prefix(':').ok_or_else(invalid)?)),
+         }
+      },
+      None => match authority.rsplit_once(':') {
+         Some((host, port)) => (host, Some(port)),
+         None => (authority, None),
+      },
+   };
+   let port = match port {
+      Some(port) => port.parse::<u16>().map_err(|_| invalid())?,
+      None => if tls { 443 } else { 80 },
+   };
+   let host = host.to_string();
+   if host.is_empty() {
+      return Err(invalid())
+   }
+   Ok(Endpoint { tls, host, port, path })
+}
+
+/// Prepare rustls configuration with system roots and optional client certificate.
+/// The client certificate file is a PEM file with the certificate chain and the private key.
+fn prepare_tls_config(client_cert: &String) -> io::Result<rustls::ClientConfig> {
+   let mut roots = rustls::RootCertStore::empty();
+   for cert in rustls_native_certs::load_native_certs()? {
+      roots.add(&rustls::Certificate(cert.0)).ok();
+   }
+   let builder = rustls::ClientConfig::builder()
+      .with_safe_defaults()
+      .with_root_certificates(roots);
+
+   if client_cert.contains("not set") {
+      return Ok(builder.with_no_client_auth())
+   }
+
+   let mut reader = BufReader::new(File::open(client_cert)?);
+   let mut certs: Vec<rustls::Certificate> = Vec::new();
+   let mut key: Option<rustls::PrivateKey> = None;
+   while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+      match item {
+         rustls_pemfile::Item::X509Certificate(cert) => certs.push(rustls::Certificate(cert)),
+         rustls_pemfile::Item::PKCS8Key(der)
+         | rustls_pemfile::Item::RSAKey(der)
+         | rustls_pemfile::Item::ECKey(der) => key = Some(rustls::PrivateKey(der)),
+         _ => {},
+      }
+   }
+   let key = key.ok_or(io::Error::new(io::ErrorKind::InvalidData, "No private key found in client certificate file"))?;
+   builder.with_client_auth_cert(certs, key).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_url() {
+   let endpoint = parse_url(&"https://collector.essos.local:8443/upload".to_string()).unwrap();
+   assert!(endpoint.tls);
+   assert_eq!(endpoint.host, "collector.essos.local");
+   assert_eq!(endpoint.port, 8443);
+   assert_eq!(endpoint.path, "/upload");
+   let endpoint = parse_url(&"http://10.0.0.1".to_string()).unwrap();
+   assert_eq!(endpoint.port, 80);
+   assert_eq!(endpoint.path, "/");
+   assert!(parse_url(&"ftp://10.0.0.1".to_string()).is_err());
+   let endpoint = parse_url(&"https://[::1]:8443/".to_string()).unwrap();
+   assert_eq!(endpoint.host, "::1");
+   assert_eq!(endpoint.port, 8443);
+   assert_eq!(get_host_header(&endpoint.host), "[::1]");
+   assert_eq!(parse_url(&"http://[fe80::1]/neo4j".to_string()).unwrap().port, 80);
+   assert!(parse_url(&"https://[::1:8443/".to_string()).is_err());
+   assert!(parse_url(&"https://[::1]8443/".to_string()).is_err());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_send_request() {
+   assert_eq!(parse_response(b"HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\n\xc3\xa9"), (201, "\u{e9}".to_string()));
+   assert_eq!(parse_response(b"\xff\xfe"), (0, String::new()));
+
+   let mut sent = Vec::new();
+   send_request(&mut sent, b"POST / HTTP/1.1\r\n\r\n", &Body::Json(&serde_json::json!({"a": 1}))).unwrap();
+   assert_eq!(sent, b"POST / HTTP/1.1\r\n\r\n7\r\n{\"a\":1}\r\n0\r\n\r\n".to_vec());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_decode_chunked() {
+   assert_eq!(decode_chunked(b"7\r\n{\"a\":1,\r\n6\r\n\"b\":2}\r\n0\r\n\r\n"), b"{\"a\":1,\"b\":2}".to_vec());
+   // One UTF-8 character split by the chunks
+   assert_eq!(parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n1\r\n\xc3\r\n1\r\n\xa9\r\n0\r\n\r\n"), (200, "\u{e9}".to_string()));
+}
@@ -0,0 +1,96 @@
+//! Output directory layout (**--output-layout**)
+//!
+//! - **flat**: every file in the output directory, prefixed with the datetime and the domain (default)
+//! - **domain**: one directory per domain and per run, `<output>/<domain>/<datetime>/`, so several domains or
+//!   repeated runs collected in the same output directory are never mixed or overwritten.
+//!   `<output>/<domain>/latest` points to the last run and every run directory has a `run.json` with its metadata.
+use colored::Colorize;
+use log::info;
+use serde_json::json;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::args::Options;
+
+/// Link to the last run in the domain directory
+pub const LATEST_LINK: &str = "latest";
+/// Run metadata file in the run directory
+pub const RUN_METADATA: &str = "run.json";
+
+/// Function to get the output directory of this run.
+pub fn get_run_directory(common_args: &Options, datetime: &str) -> String {
+   match common_args.output_layout.as_str() {
+      "domain" => Path::new(&common_args.path)
+         .join(common_args.domain.to_lowercase())
+         .join(datetime)
+         .to_string_lossy()
+         .to_string(),
+      _ => common_args.path.to_owned(),
+   }
+}
+
+/// Function to finish one run directory: `run.json` with the files of this run and the `latest` link.
+/// Nothing to do with the flat layout or when no file was written on disk.
+pub fn finish_run_directory(common_args: &Options, run_directory: &str, datetime: &str) -> io::Result<()> {
+   if common_args.output_layout != "domain" || common_args.no_disk || common_args.stdout || !Path::new(run_directory).is_dir() {
+      return Ok(())
+   }
+   let mut files: Vec<String> = fs::read_dir(run_directory)?
+      .filter_map(|entry| entry.ok())
+      .map(|entry| entry.file_name().to_string_lossy().to_string())
+      .collect();
+   files.sort();
+   let metadata = make_run_metadata(common_args, datetime, files);
+   fs::write(Path::new(run_directory).join(RUN_METADATA), serde_json::to_string_pretty(&metadata)?)?;
+   update_latest_link(run_directory)?;
+   info!("Run saved in {}, {} updated!",run_directory.bold(),LATEST_LINK.bold());
+   Ok(())
+}
+
+/// Function to make the metadata of one run, the collection details are in stats.json.
+pub fn make_run_metadata(common_args: &Options, datetime: &str, files: Vec<String>) -> serde_json::value::Value {
+   json!({
+      "version": env!("CARGO_PKG_VERSION"),
+      "domain": common_args.domain.to_uppercase(),
+      "datetime": datetime,
+      "username": common_args.username,
+      "output_format": common_args.output_format,
+      "files": files,
+   })
+}
+
+/// Function to point the `latest` link of the domain directory to one run directory.
+/// The link is relative so the output directory can be moved or archived.
+pub fn update_latest_link(run_directory: &str) -> io::Result<()> {
+   let run_directory = Path::new(run_directory);
+   let (domain_directory, run_name) = match (run_directory.parent(), run_directory.file_name()) {
+      (Some(domain_directory), Some(run_name)) => (domain_directory, run_name),
+      _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("No domain directory for {}", run_directory.display()))),
+   };
+   let link = domain_directory.join(LATEST_LINK);
+   if fs::symlink_metadata(&link).is_ok() {
+      fs::remove_file(&link)?;
+   }
+   #[cfg(unix)]
+   std::os::unix::fs::symlink(run_name, &link)?;
+   // Symbolic links need privileges on Windows, latest is a file with the name of the last run
+   #[cfg(not(unix))]
+   fs::write(&link, run_name.to_string_lossy().as_bytes())?;
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_update_latest_link() {
+   let output = std::env::temp_dir().join("rusthound_test_output_layout");
+   let domain_directory = output.join("essos.local");
+   for run in ["20240101000000", "20240102000000"] {
+      fs::create_dir_all(domain_directory.join(run)).unwrap();
+      update_latest_link(&domain_directory.join(run).to_string_lossy()).unwrap();
+   }
+   #[cfg(unix)]
+   assert_eq!(fs::read_link(domain_directory.join(LATEST_LINK)).unwrap(), Path::new("20240102000000"));
+   fs::remove_dir_all(output).ok();
+}
@@ -1,85 +1,155 @@
-use std::collections::HashMap;
 use colored::Colorize;
-use log::{info,debug,trace};
-
-use std::fs;
-use std::fs::File;
-use std::io::{Seek, Write};
-use zip::result::ZipResult;
-use zip::write::{FileOptions, ZipWriter};
+use log::{info,debug};
 
 extern crate zip;
+pub mod sink;
+pub mod http;
+pub mod siem;
+pub mod neo4j;
+pub mod bloodhound;
+pub mod layout;
+pub mod targets;
+pub mod wordlists;
+use sink::{Sink, FileSink, ZipSink, NdjsonSink, CsvSink, StdoutSink};
+use http::HttpSink;
+use siem::{SiemSink, SiemKind};
+use neo4j::Neo4jSink;
+use bloodhound::BloodHoundSink;
 use crate::json::templates::*;
 use crate::args::Options;
+use crate::cancel::CancelToken;
 use crate::enums::date::return_current_fulldate;
+use crate::json::checker::netbios::DomainNames;
+use crate::json::graph::{AdGraph, render};
+use crate::json::report::sarif::make_sarif;
+use crate::json::stats::{RunStats, make_stats, warnings_count};
 
 /// Current Bloodhound version 4.2+
 pub const BLOODHOUND_VERSION_4: i8 = 5;
 
-/// This function will create json output and zip output
+/// This function will create json output in all sinks (files, zip, ndjson, csv)
 pub fn make_result(
    common_args: &Options,
-   vec_users: Vec<serde_json::value::Value>,
-   vec_groups: Vec<serde_json::value::Value>,
-   vec_computers: Vec<serde_json::value::Value>,
-   vec_ous: Vec<serde_json::value::Value>,
-   vec_domains: Vec<serde_json::value::Value>,
-   vec_gpos: Vec<serde_json::value::Value>,
-   vec_containers: Vec<serde_json::value::Value>,
+   mut vec_users: Vec<serde_json::value::Value>,
+   mut vec_groups: Vec<serde_json::value::Value>,
+   mut vec_computers: Vec<serde_json::value::Value>,
+   mut vec_ous: Vec<serde_json::value::Value>,
+   mut vec_domains: Vec<serde_json::value::Value>,
+   mut vec_gpos: Vec<serde_json::value::Value>,
+   mut vec_containers: Vec<serde_json::value::Value>,
+   mut vec_contacts: Vec<serde_json::value::Value>,
    vec_cas: &mut Vec<serde_json::value::Value>,
    vec_templates: &mut Vec<serde_json::value::Value>,
+   vec_sites: Vec<serde_json::value::Value>,
+   vec_authn: Vec<serde_json::value::Value>,
+   vec_shares: Vec<serde_json::value::Value>,
+   vec_services: Vec<serde_json::value::Value>,
+   vec_findings: Vec<serde_json::value::Value>,
+   vec_service_accounts: Vec<serde_json::value::Value>,
+   run_stats: &mut RunStats,
+   cancel: &CancelToken,
 ) -> std::io::Result<()>
 {
+   // Run summary before moving objects in sinks
+   let mut stats = make_stats(
+      common_args,
+      run_stats,
+      &[
+         ("users", &vec_users),
+         ("groups", &vec_groups),
+         ("computers", &vec_computers),
+         ("ous", &vec_ous),
+         ("domains", &vec_domains),
+         ("gpos", &vec_gpos),
+         ("containers", &vec_containers),
+         ("contacts", &vec_contacts),
+         ("cas", &*vec_cas),
+         ("templates", &*vec_templates),
+         ("sites", &vec_sites),
+         ("authnpolicies", &vec_authn),
+      ],
+      vec_findings.len(),
+   );
+
    // Format domain name
    let filename = common_args.domain.replace(".", "-").to_lowercase();
 
-   // Hashmap for json files
-   let mut json_result = HashMap::new();
-
    // Datetime for output file
    let datetime = return_current_fulldate();
 
-   // Add all in json files
-   add_file(
-      &datetime,
-      "users".to_string(),
-		&filename,
-      vec_users,
-      &mut json_result,
-      common_args,
-   )?;
-   add_file(
-      &datetime,
-      "groups".to_string(),
-		&filename,
-      vec_groups,
-      &mut json_result,
-      common_args,
-   )?;
-   add_file(
-      &datetime,
-      "computers".to_string(),
-		&filename,
-      vec_computers,
-      &mut json_result,
-      common_args,
-   )?;
-   add_file(
-      &datetime,
-      "ous".to_string(),
-		&filename,
-      vec_ous,
-      &mut json_result,
-      common_args,
-   )?;
-   add_file(
-      &datetime,
-      "domains".to_string(),
-		&filename,
-      vec_domains,
-      &mut json_result,
-      common_args,
-   )?;
+   // Output directory of this run, one directory per domain and run with --output-layout domain
+   let run_directory = layout::get_run_directory(common_args, &datetime);
+
+   // Output sinks for json files
+   let mut sinks = prepare_sinks(common_args, &run_directory, &datetime, &filename, cancel)?;
+
+   // Attack paths reports, before moving objects in json files
+   if common_args.attack_paths > 0 {
+      add_attack_paths(
+         common_args,
+         &[
+            ("User", &vec_users),
+            ("Group", &vec_groups),
+            ("Computer", &vec_computers),
+            ("OU", &vec_ous),
+            ("Domain", &vec_domains),
+            ("GPO", &vec_gpos),
+            ("Container", &vec_containers),
+            ("Base", &vec_contacts),
+            ("EnterpriseCA", &*vec_cas),
+            ("CertTemplate", &*vec_templates),
+         ],
+         &mut sinks,
+      )?;
+   }
+
+   // NTLM relay target lists, before moving CAs in json files
+   if common_args.relay_targets {
+      add_relay_target_lists(
+         &vec_computers,
+         &*vec_cas,
+         &mut sinks,
+      )?;
+   }
+
+   // Username and machine name lists, before moving users in json files
+   if common_args.export_users_format != "not set" {
+      add_wordlists(
+         &common_args.export_users_format,
+         &vec_users,
+         &vec_computers,
+         &DomainNames::new(&vec_domains),
+         &mut sinks,
+      )?;
+   }
+
+   // ACE provenance in its own file, removed from the Aces before moving objects in json files
+   if common_args.edge_provenance == "sidecar" {
+      let vec_provenance = take_edge_provenance(&mut [
+         &mut vec_users,
+         &mut vec_groups,
+         &mut vec_computers,
+         &mut vec_ous,
+         &mut vec_domains,
+         &mut vec_gpos,
+         &mut vec_containers,
+         &mut vec_contacts,
+         &mut *vec_cas,
+         &mut *vec_templates,
+      ]);
+      add_edge_provenance(
+         vec_provenance,
+         &mut sinks,
+      )?;
+   }
+
+   // Prepare all json files, they are serialized and written together
+   let mut files: Vec<(String, serde_json::value::Value)> = Vec::new();
+   files.push(make_json_file("users".to_string(), vec_users, common_args));
+   files.push(make_json_file("groups".to_string(), vec_groups, common_args));
+   files.push(make_json_file("computers".to_string(), vec_computers.to_owned(), common_args));
+   files.push(make_json_file("ous".to_string(), vec_ous, common_args));
+   files.push(make_json_file("domains".to_string(), vec_domains, common_args));
    // Not @ly4k BloodHound version?
    if common_args.old_bloodhound {
       let mut _vec_gpos_cas_templates = vec_gpos.to_owned();
@@ -90,138 +160,468 @@ pub fn make_result(
       }
       _vec_gpos_cas_templates.append(vec_templates);
       info!("{} {} parsed!", &vec_gpos.len().to_string().bold(),&"gpos");
-      add_file(
-         &datetime,
-         "gpos".to_string(),
-         &filename,
-         _vec_gpos_cas_templates.to_vec(),
-         &mut json_result,
-         common_args,
-      )?;
+      files.push(make_json_file("gpos".to_string(), _vec_gpos_cas_templates, common_args));
    } else {
       // Is @ly4k BloodHound version?
-      add_file(
-         &datetime,
-         "gpos".to_string(),
-         &filename,
-         vec_gpos,
-         &mut json_result,
-         common_args,
-      )?;
+      files.push(make_json_file("gpos".to_string(), vec_gpos, common_args));
+   }
+   files.push(make_json_file("containers".to_string(), vec_containers, common_args));
+   // Contacts as Base nodes, only the ACEs targeting them
+   if vec_contacts.len() > 0 {
+      files.push(make_json_file("contacts".to_string(), vec_contacts, common_args));
    }
-   add_file(
-      &datetime,
-      "containers".to_string(),
-		&filename,
-      vec_containers,
-      &mut json_result,
-      common_args,
-   )?;
    // ADCS and is @ly4k BloodHound version?
    if common_args.adcs && !common_args.old_bloodhound {
-      add_file(
-         &datetime,
-         "cas".to_string(),
-         &filename,
-         vec_cas.to_vec(),
-         &mut json_result,
-         common_args,
+      files.push(make_json_file("cas".to_string(), vec_cas.to_vec(), common_args));
+      files.push(make_json_file("templates".to_string(), vec_templates.to_vec(), common_args));
+   }
+   // Custom nodes from parser plugins
+   for (kind, vec_nodes) in crate::json::parser::plugin::take_all_nodes() {
+      files.push(make_json_file(kind, vec_nodes, common_args));
+   }
+   // Sites and subnets from the Configuration naming context
+   if vec_sites.len() > 0 {
+      files.push(make_json_file("sites".to_string(), vec_sites, common_args));
+   }
+   // Authentication policies, silos and claim types from the Configuration naming context
+   if vec_authn.len() > 0 {
+      files.push(make_json_file("authnpolicies".to_string(), vec_authn, common_args));
+   }
+   // Add all in json files
+   for sink in sinks.iter_mut() {
+      sink.write_jsons(&files)?;
+   }
+   drop(files);
+
+   // Relay targets from probe-smb module
+   if common_args.probe_smb {
+      add_relay_targets(
+         &vec_computers,
+         &mut sinks,
       )?;
-      add_file(
-         &datetime,
-         "templates".to_string(),
-         &filename,
-         vec_templates.to_vec(),
-         &mut json_result,
-         common_args,
+   }
+   // RDP survey from probe-rdp module
+   if common_args.probe_rdp {
+      add_rdp_survey(
+         &vec_computers,
+         &mut sinks,
       )?;
    }
-   // All in zip file
-   if common_args.zip {
-      make_a_zip(
-         &datetime,
-         &filename,
-         &common_args.path,
-         &json_result);
+   // Findings report in SARIF, even without findings for pipelines
+   if common_args.sarif {
+      add_sarif(
+         &vec_findings,
+         &common_args.domain,
+         &mut sinks,
+      )?;
    }
+   // Findings report
+   if vec_findings.len() > 0 {
+      add_report(
+         vec_findings,
+         &mut sinks,
+      )?;
+   }
+   // Service accounts inventory
+   if vec_service_accounts.len() > 0 {
+      add_service_accounts(
+         vec_service_accounts,
+         &mut sinks,
+      )?;
+   }
+   // DFS namespaces and shares inventory
+   if vec_shares.len() > 0 {
+      add_shares(
+         vec_shares,
+         &mut sinks,
+      )?;
+   }
+   // Service connection points and printers inventory
+   if vec_services.len() > 0 {
+      add_services(
+         vec_services,
+         &mut sinks,
+      )?;
+   }
+   // Run summary with output duration
+   run_stats.phase("output");
+   stats["duration"] = run_stats.durations();
+   stats["warnings"] = warnings_count().into();
+   for sink in sinks.iter_mut() {
+      sink.write_file("stats.json", &stats.to_string())?;
+   }
+   // What was collected and left when the collection is cancelled (Ctrl-C, --max-runtime)
+   if let Some(checkpoint) = cancel.make_checkpoint(&stats, &datetime) {
+      for sink in sinks.iter_mut() {
+         sink.write_file("checkpoint.json", &checkpoint.to_string())?;
+      }
+   }
+   // Flush all sinks (zip archive, ndjson file...)
+   for sink in sinks.iter_mut() {
+      sink.finish()?;
+   }
+   // Run metadata and latest link
+   layout::finish_run_directory(common_args, &run_directory, &datetime)?;
    Ok(())
 }
 
-/// Function to create the .json file.
-fn add_file(
+/// Function to prepare all output sinks requested.
+/// No file is written on disk with **--no-disk** or **--stdout**.
+fn prepare_sinks(
+   common_args: &Options,
+   path: &String,
    datetime: &String,
+   domain_format: &String,
+   cancel: &CancelToken,
+) -> std::io::Result<Vec<Box<dyn Sink>>>
+{
+   let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+   if common_args.stdout {
+      sinks.push(Box::new(StdoutSink::new(common_args.ndjson)));
+   } else if !common_args.no_disk {
+      if common_args.output_format == "csv" {
+         sinks.push(Box::new(CsvSink::new(path, datetime, domain_format)));
+      } else if common_args.zip {
+         sinks.push(Box::new(ZipSink::new(path, datetime, domain_format)));
+      } else {
+         sinks.push(Box::new(FileSink::new(path, datetime, domain_format)));
+      }
+      if common_args.ndjson {
+         sinks.push(Box::new(NdjsonSink::new(path, datetime, domain_format)));
+      }
+   }
+   if !common_args.http_url.contains("not set") {
+      sinks.push(Box::new(HttpSink::new(
+         &common_args.http_url,
+         &common_args.http_token,
+         &common_args.http_cert,
+         datetime,
+         domain_format,
+      )?));
+   }
+   if !common_args.splunk_url.contains("not set") {
+      sinks.push(Box::new(SiemSink::new(
+         SiemKind::Splunk,
+         &common_args.splunk_url,
+         &common_args.splunk_token,
+         datetime,
+         &common_args.domain,
+      )?));
+   }
+   if !common_args.elastic_url.contains("not set") {
+      sinks.push(Box::new(SiemSink::new(
+         SiemKind::Elastic,
+         &common_args.elastic_url,
+         &common_args.elastic_api_key,
+         datetime,
+         &common_args.domain,
+      )?));
+   }
+   if !common_args.neo4j_url.contains("not set") {
+      sinks.push(Box::new(Neo4jSink::new(
+         &common_args.neo4j_url,
+         &common_args.neo4j_user,
+         &common_args.neo4j_password,
+         common_args.neo4j_upsert,
+         datetime,
+         &common_args.domain,
+         cancel,
+      )?));
+   }
+   if !common_args.upload_url.contains("not set") {
+      if common_args.upload_token.contains("not set") {
+         return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "--upload needs a BloodHound CE --token"))
+      }
+      sinks.push(Box::new(BloodHoundSink::new(
+         &common_args.upload_url,
+         &common_args.upload_token,
+      )?));
+   }
+   if sinks.is_empty() {
+      return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "No output sink, --no-disk needs --http-url, --splunk-url, --elastic-url, --neo4j-url, --upload or --stdout"))
+   }
+   Ok(sinks)
+}
+
+/// Function to prepare one .json file for all sinks.
+fn make_json_file(
    name: String,
-	domain_format: &String,
    vec_json: Vec<serde_json::value::Value>,
-   json_result: &mut HashMap<String, String>,
    common_args: &Options, 
-) -> std::io::Result<()>
+) -> (String, serde_json::value::Value)
 {
    debug!("Making {}.json",&name);
 
-   let path = &common_args.path;
-   let zip = common_args.zip;
-
    // Prepare template and get result in const var
    let mut final_json = bh_41::prepare_final_json_file_template(BLOODHOUND_VERSION_4, name.to_owned());
     
-   // Add all object found
-   final_json["data"] = vec_json.to_owned().into();
    // change count number
    let count = vec_json.len();
    final_json["meta"]["count"] = count.into();
+   // Add all object found
+   final_json["data"] = vec_json.into();
 
    if &name != "gpos" || !common_args.old_bloodhound {
       info!("{} {} parsed!", count.to_string().bold(),&name);
    }
 
-   // result
-   fs::create_dir_all(path)?;
+   (name, final_json)
+}
 
-   // Create json file if isn't zip
-   if ! zip 
-   {
-      let final_path = format!("{}/{}_{}_{}.json",path,datetime,domain_format,name);
-      fs::write(&final_path, &final_json.to_string())?;
-      info!("{} created!",final_path.bold());
+/// Function to remove the provenance of every ACE and get it with the object and the edge.
+fn take_edge_provenance(objects: &mut [&mut Vec<serde_json::value::Value>]) -> Vec<serde_json::value::Value>
+{
+   let mut vec_provenance: Vec<serde_json::value::Value> = Vec::new();
+   for vec_objects in objects.iter_mut() {
+      for object in vec_objects.iter_mut() {
+         let objectid = object["ObjectIdentifier"].to_owned();
+         if let Some(aces) = object["Aces"].as_array_mut() {
+            for ace in aces.iter_mut() {
+               let mut provenance = match ace.as_object_mut().and_then(|ace| ace.remove("Provenance")) {
+                  Some(provenance) => provenance,
+                  None => continue,
+               };
+               provenance["objectid"] = objectid.to_owned();
+               provenance["principalsid"] = ace["PrincipalSID"].to_owned();
+               provenance["rightname"] = ace["RightName"].to_owned();
+               vec_provenance.push(provenance);
+            }
+         }
+      }
    }
-   else
-   {
-      json_result.insert(format!("{}_{}.json",datetime,name).to_string(),final_json.to_owned().to_string());
+   vec_provenance
+}
+
+/// Function to create the ACE edges provenance file.
+/// Always a .json file even if zip is set since BloodHound can't import it.
+fn add_edge_provenance(
+   vec_provenance: Vec<serde_json::value::Value>,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making edges_provenance.json");
+
+   let mut final_json = bh_41::prepare_final_json_file_template(BLOODHOUND_VERSION_4, "edges_provenance".to_string());
+   final_json["meta"]["count"] = vec_provenance.len().into();
+   final_json["data"] = vec_provenance.into();
+
+   for sink in sinks.iter_mut() {
+      sink.write_file("edges_provenance.json", &final_json.to_string())?;
    }
 
    Ok(())
 }
 
-/// Function to compress the JSON files into a zip archive
-fn make_a_zip(
-   datetime: &String,
-   domain: &String,
-   path: &String,
-   json_result: &HashMap<String, String>
-){
-   let final_path = format!("{}/{}_{}_rusthound.zip",path,datetime,domain);
-   let mut file = File::create(&final_path).expect("Couldn't create file");
-   create_zip_archive(&mut file, json_result).expect("Couldn't create archive");
+/// Function to create the findings report file.
+/// Always a .json file even if zip is set since BloodHound can't import it.
+fn add_report(
+   vec_findings: Vec<serde_json::value::Value>,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making report.json");
+
+   let mut final_json = bh_41::prepare_final_json_file_template(BLOODHOUND_VERSION_4, "report".to_string());
+   final_json["meta"]["count"] = vec_findings.len().into();
+   final_json["data"] = vec_findings.into();
+
+   for sink in sinks.iter_mut() {
+      sink.write_file("report.json", &final_json.to_string())?;
+   }
+
+   Ok(())
+}
+
+/// Function to create the findings report file in SARIF.
+fn add_sarif(
+   vec_findings: &Vec<serde_json::value::Value>,
+   domain: &str,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making findings.sarif");
+
+   let sarif = make_sarif(vec_findings, domain);
+   for sink in sinks.iter_mut() {
+      sink.write_file("findings.sarif", &sarif.to_string())?;
+   }
+
+   Ok(())
+}
+
+/// Function to create the service accounts inventory file.
+/// Always a .json file even if zip is set since BloodHound can't import it.
+fn add_service_accounts(
+   vec_service_accounts: Vec<serde_json::value::Value>,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making service_accounts.json");
+
+   let mut final_json = bh_41::prepare_final_json_file_template(BLOODHOUND_VERSION_4, "service_accounts".to_string());
+   final_json["meta"]["count"] = vec_service_accounts.len().into();
+   final_json["data"] = vec_service_accounts.into();
+
+   for sink in sinks.iter_mut() {
+      sink.write_file("service_accounts.json", &final_json.to_string())?;
+   }
+
+   Ok(())
+}
+
+/// Function to create the DFS namespaces and shares inventory file.
+/// Always a .json file even if zip is set since BloodHound can't import it.
+fn add_shares(
+   vec_shares: Vec<serde_json::value::Value>,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making shares.json");
+
+   let mut final_json = bh_41::prepare_final_json_file_template(BLOODHOUND_VERSION_4, "shares".to_string());
+   final_json["meta"]["count"] = vec_shares.len().into();
+   final_json["data"] = vec_shares.into();
+
+   for sink in sinks.iter_mut() {
+      sink.write_file("shares.json", &final_json.to_string())?;
+   }
+
+   Ok(())
+}
+
+/// Function to create the service connection points and printers inventory file.
+/// Always a .json file even if zip is set since BloodHound can't import it.
+fn add_services(
+   vec_services: Vec<serde_json::value::Value>,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making services.json");
+
+   let mut final_json = bh_41::prepare_final_json_file_template(BLOODHOUND_VERSION_4, "services".to_string());
+   final_json["meta"]["count"] = vec_services.len().into();
+   final_json["data"] = vec_services.into();
+
+   for sink in sinks.iter_mut() {
+      sink.write_file("services.json", &final_json.to_string())?;
+   }
+
+   Ok(())
+}
 
-   info!("{} created!",&final_path.bold());
+/// Function to create the attack paths reports from the in-memory graph.
+/// Markdown and HTML files with the shortest paths to Tier-0, no need of Neo4j to read them.
+fn add_attack_paths(
+   common_args: &Options,
+   objects: &[(&str, &Vec<serde_json::value::Value>)],
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making attack_paths.md and attack_paths.html");
+
+   let graph = AdGraph::build(objects);
+   let paths = graph.paths_to_tier_zero(&graph.default_sources(), common_args.attack_paths as usize);
+   info!("{} attack paths to Tier-0 found!", &paths.len().to_string().bold());
+   let markdown = render::make_markdown(&graph, &paths, &common_args.domain);
+   let html = render::make_html(&graph, &paths, &common_args.domain);
+
+   for sink in sinks.iter_mut() {
+      sink.write_file("attack_paths.md", &markdown)?;
+      sink.write_file("attack_paths.html", &html)?;
+   }
+
+   Ok(())
+}
+
+/// Function to create the NTLM relay target lists for ntlmrelayx.
+fn add_relay_target_lists(
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_cas: &Vec<serde_json::value::Value>,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making NTLM relay target lists");
+
+   for (name, content) in targets::make_relay_targets(vec_computers, vec_cas) {
+      for sink in sinks.iter_mut() {
+         sink.write_file(name, &content)?;
+      }
+   }
+
+   Ok(())
+}
+
+/// Function to create the username and machine name lists.
+fn add_wordlists(
+   formats: &str,
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   domain_names: &DomainNames,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making username and machine name lists");
+
+   for (name, content) in wordlists::make_wordlists(formats, vec_users, vec_computers, domain_names) {
+      for sink in sinks.iter_mut() {
+         sink.write_file(&name, &content)?;
+      }
+   }
+
+   Ok(())
 }
 
+/// Function to create the relay targets CSV file from probe-smb results.
+/// Only computers without SMB signing required are written.
+fn add_relay_targets(
+   vec_computers: &Vec<serde_json::value::Value>,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making relay_targets.csv");
 
-fn create_zip_archive<T: Seek + Write>(zip_filename: &mut T,json_result: &HashMap<String, String>) -> ZipResult<()> {
-   let mut writer = ZipWriter::new(zip_filename);
-   // json file by json file
-   trace!("Making the ZIP file");
+   let mut csv = String::from("name,dialect,smbv1\n");
+   for computer in vec_computers {
+      if computer["Properties"]["smbsigningrequired"] != false {
+         continue
+      }
+      csv.push_str(&format!("{},{},{}\n",
+         computer["Properties"]["name"].as_str().unwrap_or(""),
+         computer["Properties"]["smbdialect"].as_str().unwrap_or(""),
+         computer["Properties"]["smbv1"].as_bool().map(|b| b.to_string()).unwrap_or("".to_string()),
+      ));
+   }
 
-   for file in json_result
-   {
-      let filename = file.0;
-      let content = file.1;
-      trace!("Adding file {}",filename.bold());
-      writer.start_file(filename, FileOptions::default())?;
-      writer.write_all(content.as_bytes())?;
+   for sink in sinks.iter_mut() {
+      sink.write_file("relay_targets.csv", &csv)?;
    }
 
-   writer.finish()?;
    Ok(())
-}
\ No newline at end of file
+}
+
+/// Function to write rdp.csv with RDP availability, NLA and certificate CN of probed computers.
+fn add_rdp_survey(
+   vec_computers: &Vec<serde_json::value::Value>,
+   sinks: &mut Vec<Box<dyn Sink>>,
+) -> std::io::Result<()>
+{
+   debug!("Making rdp.csv");
+
+   let mut csv = String::from("name,nlarequired,certcn\n");
+   for computer in vec_computers {
+      if computer["Properties"]["rdpavailable"] != true {
+         continue
+      }
+      csv.push_str(&format!("{},{},{}\n",
+         computer["Properties"]["name"].as_str().unwrap_or(""),
+         computer["Properties"]["rdpnlarequired"].as_bool().map(|b| b.to_string()).unwrap_or("".to_string()),
+         computer["Properties"]["rdpcertcn"].as_str().unwrap_or(""),
+      ));
+   }
+
+   for sink in sinks.iter_mut() {
+      sink.write_file("rdp.csv", &csv)?;
+   }
+
+   Ok(())
+}
@@ -0,0 +1,208 @@
+//! Neo4j sink to import results without BloodHound GUI.
+//!
+//! Nodes and edges are sent with Cypher statements to the Neo4j HTTP API (`POST <url>/db/<database>/tx/commit`).
+//! By default every run is imported again (**--neo4j-url**).
+//! With **--neo4j-upsert** nodes and edges are merged on their objectid, tagged with the run id (`rusthound_run`),
+//! and edges of the collected domain not found in this run are removed at the end, so the graph stays fresh without duplicates.
+//! Edges are never removed after a partial or cancelled run, what wasn't collected again is still there.
+use log::{info,debug,warn};
+use colored::Colorize;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+use std::collections::HashMap;
+use std::io;
+
+use crate::json::graph::{get_edges, get_node_kind};
+use crate::json::maker::http::HttpClient;
+use crate::cancel::CancelToken;
+use crate::json::maker::sink::Sink;
+use crate::json::stats::partial_reasons;
+
+/// Rows sent in one statement
+const BATCH_SIZE: usize = 1000;
+/// Neo4j database
+const NEO4J_DATABASE: &str = "neo4j";
+
+/// Import nodes and edges in Neo4j with Cypher statements.
+pub struct Neo4jSink {
+   client: HttpClient,
+   authorization: String,
+   upsert: bool,
+   run: String,
+   domain: String,
+   cancel: CancelToken,
+   /// Node label to node rows
+   nodes: HashMap<String, Vec<serde_json::value::Value>>,
+   /// Relation type to edge rows
+   edges: HashMap<String, Vec<serde_json::value::Value>>,
+}
+
+impl Neo4jSink {
+   pub fn new(
+      url: &String,
+      user: &String,
+      password: &String,
+      upsert: bool,
+      datetime: &String,
+      domain: &String,
+      cancel: &CancelToken,
+   ) -> io::Result<Self> {
+      Ok(Neo4jSink {
+         client: HttpClient::new(url, &"not set".to_string())?,
+         authorization: format!("Authorization: Basic {}", STANDARD.encode(format!("{}:{}",user,password))),
+         upsert,
+         run: datetime.to_owned(),
+         domain: domain.to_uppercase(),
+         cancel: cancel.to_owned(),
+         nodes: HashMap::new(),
+         edges: HashMap::new(),
+      })
+   }
+
+   /// Run Cypher statements in one transaction and check errors.
+   fn commit(&self, statements: Vec<serde_json::value::Value>) -> io::Result<()> {
+      let path = format!("{}/db/{}/tx/commit", self.client.endpoint.path.trim_end_matches('/'), NEO4J_DATABASE);
+      let body = serde_json::json!({ "statements": statements }).to_string();
+      let (status, response) = self.client.post(&path, &[self.authorization.to_owned()], "application/json", body.as_bytes())?;
+      if !(200..300).contains(&status) {
+         return Err(io::Error::new(io::ErrorKind::Other, format!("Neo4j returned HTTP {}",status)))
+      }
+      let response: serde_json::value::Value = serde_json::from_str(&response).unwrap_or_default();
+      if let Some(error) = response["errors"].as_array().and_then(|errors| errors.first()) {
+         return Err(io::Error::new(io::ErrorKind::Other, format!("Neo4j error: {}",error["message"].as_str().unwrap_or(""))))
+      }
+      Ok(())
+   }
+}
+
+impl Sink for Neo4jSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> io::Result<()> {
+      let label = get_node_kind(name).unwrap_or("Base").to_string();
+      for object in final_json["data"].as_array().unwrap_or(&Vec::new()) {
+         let objectid = object["ObjectIdentifier"].as_str().unwrap_or("").to_uppercase();
+         if objectid.is_empty() {
+            continue
+         }
+         self.nodes.entry(label.to_owned()).or_default().push(serde_json::json!({
+            "objectid": objectid,
+            "properties": get_properties(&object["Properties"]),
+         }));
+         for edge in get_edges(object) {
+            self.edges.entry(get_relation_type(&edge.kind)).or_default().push(serde_json::json!({
+               "source": edge.source.to_uppercase(),
+               "target": edge.target.to_uppercase(),
+            }));
+         }
+      }
+      Ok(())
+   }
+
+   fn write_file(&mut self, _name: &str, _content: &str) -> io::Result<()> {
+      Ok(())
+   }
+
+   fn finish(&mut self) -> io::Result<()> {
+      let parameters = |rows: &[serde_json::value::Value], run: &str, domain: &str| serde_json::json!({ "rows": rows, "run": run, "domain": domain });
+      let mut count = 0;
+      // Nodes before edges, edges MATCH their nodes
+      for (label, rows) in self.nodes.iter() {
+         let statement = make_node_statement(label, self.upsert);
+         for batch in rows.chunks(BATCH_SIZE) {
+            debug!("Sending {} {} nodes to Neo4j",batch.len(),label);
+            self.commit(vec![serde_json::json!({ "statement": statement, "parameters": parameters(batch, &self.run, &self.domain) })])?;
+         }
+         count += rows.len();
+      }
+      for (relation, rows) in self.edges.iter() {
+         let statement = make_edge_statement(relation, self.upsert);
+         for batch in rows.chunks(BATCH_SIZE) {
+            debug!("Sending {} {} edges to Neo4j",batch.len(),relation);
+            self.commit(vec![serde_json::json!({ "statement": statement, "parameters": parameters(batch, &self.run, &self.domain) })])?;
+         }
+         count += rows.len();
+      }
+      if self.upsert {
+         match get_prune_skip_reason(&partial_reasons(), self.cancel.reason()) {
+            Some(reason) => warn!("Edges of previous runs kept in Neo4j, {}",reason),
+            // Edges of this domain from previous runs are not there anymore
+            None => self.commit(vec![serde_json::json!({
+               "statement": "MATCH ()-[r]->() WHERE r.rusthound_domain = $domain AND r.rusthound_run <> $run DELETE r",
+               "parameters": { "run": self.run, "domain": self.domain },
+            })])?,
+         }
+      }
+      info!("{} nodes and edges sent to Neo4j {}!",count.to_string().bold(),self.client.endpoint.host.bold());
+      Ok(())
+   }
+}
+
+/// Function to make the Cypher statement for one node label.
+pub fn make_node_statement(label: &str, upsert: bool) -> String {
+   if upsert {
+      format!("UNWIND $rows AS row MERGE (n:Base {{objectid: row.objectid}}) SET n += row.properties, n:{}, n.rusthound_run = $run", label)
+   } else {
+      format!("UNWIND $rows AS row CREATE (n:Base:{} {{objectid: row.objectid}}) SET n += row.properties", label)
+   }
+}
+
+/// Function to make the Cypher statement for one relation type.
+pub fn make_edge_statement(relation: &str, upsert: bool) -> String {
+   if upsert {
+      format!("UNWIND $rows AS row MERGE (s:Base {{objectid: row.source}}) MERGE (t:Base {{objectid: row.target}}) MERGE (s)-[r:{}]->(t) SET r.rusthound_run = $run, r.rusthound_domain = $domain", relation)
+   } else {
+      format!("UNWIND $rows AS row MATCH (s:Base {{objectid: row.source}}) MATCH (t:Base {{objectid: row.target}}) CREATE (s)-[r:{}]->(t)", relation)
+   }
+}
+
+/// Function to get why edges of previous runs can't be removed, None if this run collected everything.
+fn get_prune_skip_reason(partial: &[String], cancelled: Option<String>) -> Option<String> {
+   if let Some(reason) = cancelled {
+      return Some(format!("collection cancelled: {}", reason))
+   }
+   if !partial.is_empty() {
+      return Some(format!("partial collection: {}", partial.join(", ")))
+   }
+   None
+}
+
+/// Function to keep only properties Neo4j can store: strings, numbers, booleans and lists of them.
+fn get_properties(properties: &serde_json::value::Value) -> serde_json::value::Value {
+   let mut result = serde_json::Map::new();
+   for (key, value) in properties.as_object().unwrap_or(&serde_json::Map::new()) {
+      let keep = match value {
+         serde_json::value::Value::String(_) | serde_json::value::Value::Number(_) | serde_json::value::Value::Bool(_) => true,
+         serde_json::value::Value::Array(values) => values.iter().all(|value| value.is_string()),
+         _ => false,
+      };
+      if keep {
+         result.insert(key.to_owned(), value.to_owned());
+      }
+   }
+   result.into()
+}
+
+/// Function to get a relation type usable in Cypher, relation types can't be parameters.
+fn get_relation_type(kind: &str) -> String {
+   let relation: String = kind.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+   if relation.is_empty() { "Unknown".to_string() } else { relation }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_make_statements() {
+   assert_eq!(make_node_statement("User", true), "UNWIND $rows AS row MERGE (n:Base {objectid: row.objectid}) SET n += row.properties, n:User, n.rusthound_run = $run");
+   assert!(make_edge_statement("MemberOf", true).contains("MERGE (s)-[r:MemberOf]->(t) SET r.rusthound_run = $run"));
+   assert!(make_edge_statement("MemberOf", false).contains("CREATE (s)-[r:MemberOf]->(t)"));
+   assert_eq!(get_relation_type("Generic All`) DETACH"), "GenericAllDETACH");
+   let properties = get_properties(&serde_json::json!({"name": "JON@NORTH.LOCAL", "enabled": true, "description": null, "spns": ["HTTP/WEB"], "gpochanges": {}}));
+   assert_eq!(properties.as_object().unwrap().len(), 3);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_prune_skip_reason() {
+   assert!(get_prune_skip_reason(&[], None).is_none());
+   assert_eq!(get_prune_skip_reason(&[], Some("interrupted".to_string())).unwrap(), "collection cancelled: interrupted");
+   let partial = vec!["LDAP stopped: maximum runtime reached, 2 pages".to_string()];
+   assert_eq!(get_prune_skip_reason(&partial, None).unwrap(), "partial collection: LDAP stopped: maximum runtime reached, 2 pages");
+}
@@ -0,0 +1,217 @@
+//! SIEM sinks to index every run in Splunk or Elasticsearch.
+//!
+//! Objects are sent as node documents, their relations as edge documents and the findings as finding documents,
+//! all tagged with the run datetime and the domain so the AD state can be compared between scheduled runs.
+//!
+//! - **Splunk**: HTTP Event Collector, `POST <url>/services/collector/event` with `Authorization: Splunk <token>` (**--splunk-url**)
+//! - **Elasticsearch**: `POST <url>/_bulk` with `Authorization: ApiKey <key>` into `rusthound-nodes`, `rusthound-edges`
+//!   and `rusthound-findings` indices (**--elastic-url**)
+//!
+//! Documents are sent by batches and every batch is retried on network errors, HTTP 429 and 5xx responses.
+use log::{info,debug,warn};
+use colored::Colorize;
+
+use std::io;
+use std::thread::sleep;
+use std::time::Duration;
+
+use crate::json::graph::{get_edges, get_node_kind};
+use crate::json::maker::http::HttpClient;
+use crate::json::maker::sink::Sink;
+
+/// Documents sent in one request
+const BATCH_SIZE: usize = 500;
+/// Attempts for one batch before giving up
+const MAX_ATTEMPTS: u32 = 4;
+/// First retry delay, doubled after each attempt
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Elasticsearch indices prefix
+const ELASTIC_INDEX: &str = "rusthound";
+
+/// SIEM where documents are sent.
+pub enum SiemKind {
+   Splunk,
+   Elastic,
+}
+
+/// Send node, edge and finding documents to Splunk HEC or Elasticsearch bulk API.
+pub struct SiemSink {
+   kind: SiemKind,
+   client: HttpClient,
+   token: Option<String>,
+   run: String,
+   domain: String,
+   /// (document type, document) waiting for the next batch
+   documents: Vec<(&'static str, serde_json::value::Value)>,
+   sent: usize,
+}
+
+impl SiemSink {
+   pub fn new(
+      kind: SiemKind,
+      url: &String,
+      token: &String,
+      datetime: &String,
+      domain: &String,
+   ) -> io::Result<Self> {
+      Ok(SiemSink {
+         kind,
+         client: HttpClient::new(url, &"not set".to_string())?,
+         token: if token.contains("not set") { None } else { Some(token.to_owned()) },
+         run: datetime.to_owned(),
+         domain: domain.to_uppercase(),
+         documents: Vec::new(),
+         sent: 0,
+      })
+   }
+
+   /// Add one document and send the batch when it is full.
+   fn push(&mut self, doc_type: &'static str, mut document: serde_json::value::Value) -> io::Result<()> {
+      document["doc_type"] = doc_type.into();
+      document["rusthound_run"] = self.run.to_owned().into();
+      document["domain"] = self.domain.to_owned().into();
+      self.documents.push((doc_type, document));
+      if self.documents.len() >= BATCH_SIZE {
+         self.flush()?;
+      }
+      Ok(())
+   }
+
+   /// Send all waiting documents in one request.
+   fn flush(&mut self) -> io::Result<()> {
+      if self.documents.is_empty() {
+         return Ok(())
+      }
+      let (path, content_type, body, header) = match self.kind {
+         SiemKind::Splunk => (
+            format!("{}/services/collector/event", self.client.endpoint.path.trim_end_matches('/')),
+            "application/json",
+            make_splunk_body(&self.documents),
+            self.token.as_ref().map(|token| format!("Authorization: Splunk {}",token)),
+         ),
+         SiemKind::Elastic => (
+            format!("{}/_bulk", self.client.endpoint.path.trim_end_matches('/')),
+            "application/x-ndjson",
+            make_elastic_body(&self.documents),
+            self.token.as_ref().map(|token| format!("Authorization: ApiKey {}",token)),
+         ),
+      };
+      let headers: Vec<String> = header.into_iter().collect();
+      self.post_with_retry(&path, &headers, content_type, body.as_bytes())?;
+      self.sent += self.documents.len();
+      self.documents.clear();
+      Ok(())
+   }
+
+   /// Send one batch, retry on network errors, HTTP 429 and 5xx with exponential backoff.
+   fn post_with_retry(&self, path: &str, headers: &[String], content_type: &str, body: &[u8]) -> io::Result<()> {
+      let mut delay = RETRY_DELAY;
+      let mut last_error = String::new();
+      for attempt in 1..=MAX_ATTEMPTS {
+         match self.client.post(path, headers, content_type, body) {
+            Ok((status, response)) if (200..300).contains(&status) => {
+               // Bulk API returns 200 even if some documents are rejected
+               if let SiemKind::Elastic = self.kind {
+                  if response.contains("\"errors\":true") {
+                     warn!("Some documents were rejected by {}",self.client.endpoint.host.bold());
+                  }
+               }
+               return Ok(())
+            },
+            Ok((status, _)) if status == 429 || status >= 500 => last_error = format!("HTTP {}",status),
+            Ok((status, response)) => {
+               return Err(io::Error::new(io::ErrorKind::Other, format!("{} returned HTTP {}: {}",self.client.endpoint.host,status,response.trim())))
+            },
+            Err(err) => last_error = err.to_string(),
+         }
+         if attempt < MAX_ATTEMPTS {
+            debug!("Batch to {} failed ({}), retry in {}s",self.client.endpoint.host,last_error,delay.as_secs());
+            sleep(delay);
+            delay *= 2;
+         }
+      }
+      Err(io::Error::new(io::ErrorKind::Other, format!("{} failed after {} attempts: {}",self.client.endpoint.host,MAX_ATTEMPTS,last_error)))
+   }
+}
+
+impl Sink for SiemSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> io::Result<()> {
+      let kind = get_node_kind(name).unwrap_or(name).to_string();
+      for object in final_json["data"].as_array().unwrap_or(&Vec::new()) {
+         self.push("node", serde_json::json!({
+            "type": kind,
+            "objectid": object["ObjectIdentifier"],
+            "properties": object["Properties"],
+         }))?;
+         for edge in get_edges(object) {
+            self.push("edge", serde_json::json!({
+               "source": edge.source,
+               "source_type": edge.source_kind,
+               "target": edge.target,
+               "relation": edge.kind,
+            }))?;
+         }
+      }
+      Ok(())
+   }
+
+   fn write_file(&mut self, name: &str, content: &str) -> io::Result<()> {
+      // Only findings are indexed
+      if name != "report.json" {
+         return Ok(())
+      }
+      let report: serde_json::value::Value = serde_json::from_str(content).unwrap_or_default();
+      for finding in report["data"].as_array().unwrap_or(&Vec::new()) {
+         self.push("finding", finding.to_owned())?;
+      }
+      Ok(())
+   }
+
+   fn finish(&mut self) -> io::Result<()> {
+      self.flush()?;
+      info!("{} documents sent to {}!",self.sent.to_string().bold(),self.client.endpoint.host.bold());
+      Ok(())
+   }
+}
+
+/// Function to make one HEC request body, events are concatenated.
+pub fn make_splunk_body(documents: &[(&'static str, serde_json::value::Value)]) -> String {
+   documents.iter()
+      .map(|(doc_type, document)| serde_json::json!({
+         "sourcetype": format!("rusthound:{}",doc_type),
+         "source": "rusthound",
+         "event": document,
+      }).to_string())
+      .collect::<Vec<String>>()
+      .join("\n")
+}
+
+/// Function to make one bulk request body, one action line and one document line for each document.
+pub fn make_elastic_body(documents: &[(&'static str, serde_json::value::Value)]) -> String {
+   let mut body = String::new();
+   for (doc_type, document) in documents {
+      body.push_str(&serde_json::json!({ "index": { "_index": format!("{}-{}s",ELASTIC_INDEX,doc_type) } }).to_string());
+      body.push('\n');
+      body.push_str(&document.to_string());
+      body.push('\n');
+   }
+   body
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_make_bodies() {
+   let documents = vec![
+      ("node", serde_json::json!({"objectid": "S-1-5-21-1-2-3-512"})),
+      ("edge", serde_json::json!({"relation": "MemberOf"})),
+   ];
+   let body = make_elastic_body(&documents);
+   let lines: Vec<&str> = body.lines().collect();
+   assert_eq!(lines.len(), 4);
+   assert_eq!(lines[0], r#"{"index":{"_index":"rusthound-nodes"}}"#);
+   assert_eq!(lines[2], r#"{"index":{"_index":"rusthound-edges"}}"#);
+   assert!(body.ends_with('\n'));
+   let body = make_splunk_body(&documents);
+   assert_eq!(body.lines().count(), 2);
+   assert!(body.starts_with(r#"{"sourcetype":"rusthound:node""#));
+}
@@ -0,0 +1,449 @@
+//! Output sinks for RustHound results
+//!
+//! Every output backend implements the [`Sink`] trait, `make_result` writes all files in every active sink.
+//! The .json files are given together to [`Sink::write_jsons`] so FileSink and ZipSink serialize them on several threads.
+//! Adding a new backend doesn't need to touch the parser or the maker.
+//! Files on disk are written in a `.tmp` file by chunks of 512-byte sectors, synced and renamed once complete,
+//! so an interrupted run never leaves truncated files that BloodHound would fail to ingest silently.
+//! While a run is writing, every sink writing on disk keeps `<datetime>_<domain>_rusthound.<sink>.partial` with the files already complete,
+//! one per line, removed once all its files are written: a marker left behind means the run stopped while writing its output.
+//!
+//! - **FileSink**: one .json file per object type (default)
+//! - **ZipSink**: all .json files compressed into a zip archive (**--zip**)
+//! - **NdjsonSink**: all objects in one .ndjson file, one object per line (**--ndjson**)
+//! - **CsvSink**: all objects in nodes.csv and all relations in edges.csv (**--output-format csv**)
+//! - **StdoutSink**: every .json file on one line, or every object with **--ndjson**, in standard output (**--stdout**)
+//! - **HttpSink**: every file sent with a POST request (**--http-url**), see [`crate::json::maker::http`]
+//! - **BloodHoundSink**: .json files uploaded in one BloodHound CE file upload job (**--upload**), see [`crate::json::maker::bloodhound`]
+//! - **Neo4jSink**: nodes and edges imported in Neo4j, merged with **--neo4j-upsert** (**--neo4j-url**), see [`crate::json::maker::neo4j`]
+//! - **SiemSink**: node, edge and finding documents sent to Splunk or Elasticsearch (**--splunk-url**, **--elastic-url**), see [`crate::json::maker::siem`]
+//!
+use colored::Colorize;
+use log::{info,trace};
+
+use std::collections::HashMap;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use std::thread;
+use zip::result::ZipResult;
+use crate::json::graph::{get_edges, get_node_kind, Edge};
+use zip::write::{FileOptions, ZipWriter};
+
+/// Trait for every output backend.
+pub trait Sink {
+   /// Write one BloodHound .json file (users, groups, computers...).
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> std::io::Result<()>;
+   /// Write a file which isn't imported in BloodHound (report, csv...).
+   fn write_file(&mut self, name: &str, content: &str) -> std::io::Result<()>;
+   /// Write all BloodHound .json files, one after the other by default.
+   fn write_jsons(&mut self, files: &[(String, serde_json::value::Value)]) -> std::io::Result<()> {
+      for (name, final_json) in files {
+         self.write_json(name, final_json)?;
+      }
+      Ok(())
+   }
+   /// Flush everything at the end of the run.
+   fn finish(&mut self) -> std::io::Result<()> {
+      Ok(())
+   }
+}
+
+/// Size of the write buffer, a multiple of 512-byte sectors
+const WRITE_CHUNK_SIZE: usize = 128 * 512;
+
+/// Write plain files in the output directory.
+pub struct FileSink {
+   path: String,
+   prefix: String,
+   sink: &'static str,
+}
+
+impl FileSink {
+   pub fn new(path: &String, datetime: &String, domain_format: &String) -> Self {
+      FileSink::for_sink(path, datetime, domain_format, "files")
+   }
+
+   /// Plain files of another sink, with its own `.partial` marker so sinks active in the same run don't share it.
+   fn for_sink(path: &String, datetime: &String, domain_format: &String, sink: &'static str) -> Self {
+      FileSink { path: path.to_owned(), prefix: format!("{}_{}",datetime,domain_format), sink }
+   }
+
+   fn get_path(&self, name: &str) -> String {
+      format!("{}/{}_{}",self.path,self.prefix,name)
+   }
+
+   fn get_marker_path(&self) -> String {
+      self.get_path(&format!("rusthound.{}.partial",self.sink))
+   }
+
+   /// Add the files complete in the `.partial` marker, created with the first one.
+   fn mark_written(&self, names: &[String]) -> std::io::Result<()> {
+      let mut marker = OpenOptions::new().create(true).append(true).open(self.get_marker_path())?;
+      for name in names {
+         writeln!(marker, "{}", name)?;
+      }
+      marker.sync_data()
+   }
+}
+
+/// Function to write one file with a temporary file, synced on disk and renamed once complete.
+fn write_atomic<F>(final_path: &str, write: F) -> std::io::Result<()>
+where
+   F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+   let tmp_path = format!("{}.tmp",final_path);
+   let mut writer = BufWriter::with_capacity(WRITE_CHUNK_SIZE, File::create(&tmp_path)?);
+   write(&mut writer)?;
+   // Explicit flush, errors are lost when the BufWriter is dropped
+   let file = writer.into_inner().map_err(|err| err.into_error())?;
+   // Content on disk before the rename, else a crash can leave an empty file with the final name
+   file.sync_all()?;
+   drop(file);
+   fs::rename(&tmp_path, final_path)?;
+   sync_parent_directory(final_path);
+   info!("{} created!",final_path.bold());
+   Ok(())
+}
+
+/// Function to sync the directory of one renamed file so the rename survives a crash.
+/// Directories can't be opened on Windows, the rename is already durable there.
+fn sync_parent_directory(final_path: &str) {
+   let parent = Path::new(final_path).parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+   if let Err(err) = File::open(parent).and_then(|directory| directory.sync_all()) {
+      trace!("Can't sync directory {}: {}", parent.display(), err);
+   }
+}
+
+/// Function to serialize one .json file directly in a buffered file.
+fn write_json_file(final_path: &str, final_json: &serde_json::value::Value) -> std::io::Result<()> {
+   write_atomic(final_path, |writer| Ok(serde_json::to_writer(writer, final_json)?))
+}
+
+impl Sink for FileSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> std::io::Result<()> {
+      fs::create_dir_all(&self.path)?;
+      write_json_file(&self.get_path(&format!("{}.json",name)), final_json)?;
+      self.mark_written(&[format!("{}.json",name)])
+   }
+
+   fn write_file(&mut self, name: &str, content: &str) -> std::io::Result<()> {
+      fs::create_dir_all(&self.path)?;
+      write_atomic(&self.get_path(name), |writer| writer.write_all(content.as_bytes()))?;
+      self.mark_written(&[name.to_string()])
+   }
+
+   fn write_jsons(&mut self, files: &[(String, serde_json::value::Value)]) -> std::io::Result<()> {
+      fs::create_dir_all(&self.path)?;
+      // One thread per file, users and computers are the biggest ones
+      thread::scope(|scope| {
+         let handles: Vec<_> = files.iter()
+            .map(|(name, final_json)| {
+               let final_path = self.get_path(&format!("{}.json",name));
+               scope.spawn(move || write_json_file(&final_path, final_json))
+            })
+            .collect();
+         handles.into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err(std::io::Error::new(std::io::ErrorKind::Other, "JSON writer thread panicked"))))
+            .collect::<std::io::Result<Vec<()>>>()
+      })?;
+      self.mark_written(&files.iter().map(|(name, _)| format!("{}.json",name)).collect::<Vec<String>>())
+   }
+
+   fn finish(&mut self) -> std::io::Result<()> {
+      // All files written, nothing to resume
+      match fs::remove_file(self.get_marker_path()) {
+         Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+         _ => Ok(()),
+      }
+   }
+}
+
+/// Compress the .json files into a zip archive.
+/// Other files are still written in plain since BloodHound can't import them.
+pub struct ZipSink {
+   files: FileSink,
+   path: String,
+   datetime: String,
+   domain_format: String,
+   json_result: Vec<(String, String)>,
+}
+
+impl ZipSink {
+   pub fn new(path: &String, datetime: &String, domain_format: &String) -> Self {
+      ZipSink {
+         files: FileSink::for_sink(path, datetime, domain_format, "zip"),
+         path: path.to_owned(),
+         datetime: datetime.to_owned(),
+         domain_format: domain_format.to_owned(),
+         json_result: Vec::new(),
+      }
+   }
+}
+
+impl Sink for ZipSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> std::io::Result<()> {
+      self.json_result.push((format!("{}_{}.json",self.datetime,name), final_json.to_string()));
+      Ok(())
+   }
+
+   fn write_jsons(&mut self, files: &[(String, serde_json::value::Value)]) -> std::io::Result<()> {
+      // Serialize all files in parallel, the archive is written in finish()
+      let json_result: Vec<(String, String)> = thread::scope(|scope| {
+         let handles: Vec<_> = files.iter()
+            .map(|(name, final_json)| scope.spawn(move || (name.to_owned(), final_json.to_string())))
+            .collect();
+         handles.into_iter().filter_map(|handle| handle.join().ok()).collect()
+      });
+      if json_result.len() != files.len() {
+         return Err(std::io::Error::new(std::io::ErrorKind::Other, "JSON serialization thread panicked"))
+      }
+      for (name, content) in json_result {
+         self.json_result.push((format!("{}_{}.json",self.datetime,name), content));
+      }
+      Ok(())
+   }
+
+   fn write_file(&mut self, name: &str, content: &str) -> std::io::Result<()> {
+      self.files.write_file(name, content)
+   }
+
+   fn finish(&mut self) -> std::io::Result<()> {
+      fs::create_dir_all(&self.path)?;
+      let final_path = format!("{}/{}_{}_rusthound.zip",self.path,self.datetime,self.domain_format);
+      write_atomic(&final_path, |writer| Ok(create_zip_archive(writer, &self.json_result)?))?;
+      self.files.finish()
+   }
+}
+
+/// Write all objects in one .ndjson file, one line per object with its type.
+pub struct NdjsonSink {
+   files: FileSink,
+   lines: String,
+}
+
+impl NdjsonSink {
+   pub fn new(path: &String, datetime: &String, domain_format: &String) -> Self {
+      NdjsonSink { files: FileSink::for_sink(path, datetime, domain_format, "ndjson"), lines: String::new() }
+   }
+}
+
+impl Sink for NdjsonSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> std::io::Result<()> {
+      if let Some(objects) = final_json["data"].as_array() {
+         for object in objects {
+            let mut line = object.to_owned();
+            line["ObjectType"] = name.into();
+            self.lines.push_str(&line.to_string());
+            self.lines.push('\n');
+         }
+      }
+      Ok(())
+   }
+
+   fn write_file(&mut self, _name: &str, _content: &str) -> std::io::Result<()> {
+      Ok(())
+   }
+
+   fn finish(&mut self) -> std::io::Result<()> {
+      self.files.write_file("rusthound.ndjson", &self.lines)?;
+      self.files.finish()
+   }
+}
+
+/// Write all objects in nodes.csv and their relations in edges.csv instead of BloodHound .json files.
+/// Other files are still written in plain.
+pub struct CsvSink {
+   files: FileSink,
+   nodes: String,
+   edges: Vec<Edge>,
+   node_kind: HashMap<String, String>,
+}
+
+impl CsvSink {
+   pub fn new(path: &String, datetime: &String, domain_format: &String) -> Self {
+      CsvSink {
+         files: FileSink::for_sink(path, datetime, domain_format, "csv"),
+         nodes: String::from("objectid,type,name,domain,distinguishedname,enabled,highvalue,tierzero\n"),
+         edges: Vec::new(),
+         node_kind: HashMap::new(),
+      }
+   }
+}
+
+impl Sink for CsvSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> std::io::Result<()> {
+      let kind = get_node_kind(name).unwrap_or(name).to_string();
+      if let Some(objects) = final_json["data"].as_array() {
+         for object in objects {
+            let properties = &object["Properties"];
+            let objectid = object["ObjectIdentifier"].as_str().unwrap_or("");
+            let property = |key: &str| match &properties[key] {
+               serde_json::value::Value::String(value) => value.to_owned(),
+               serde_json::value::Value::Bool(value) => value.to_string(),
+               _ => String::new(),
+            };
+            self.nodes.push_str(&[
+               escape_csv(objectid),
+               escape_csv(&kind),
+               escape_csv(&property("name")),
+               escape_csv(&property("domain")),
+               escape_csv(&property("distinguishedname")),
+               property("enabled"),
+               property("highvalue"),
+               property("isTierZero"),
+            ].join(","));
+            self.nodes.push('\n');
+            self.node_kind.insert(objectid.to_string(), kind.to_owned());
+            self.edges.extend(get_edges(object));
+         }
+      }
+      Ok(())
+   }
+
+   fn write_file(&mut self, name: &str, content: &str) -> std::io::Result<()> {
+      self.files.write_file(name, content)
+   }
+
+   fn finish(&mut self) -> std::io::Result<()> {
+      let mut edges = String::from("source,source_type,target,target_type,relation\n");
+      for edge in self.edges.iter() {
+         edges.push_str(&[
+            escape_csv(&edge.source),
+            escape_csv(self.node_kind.get(&edge.source).unwrap_or(&edge.source_kind)),
+            escape_csv(&edge.target),
+            escape_csv(self.node_kind.get(&edge.target).map(|kind| kind.as_str()).unwrap_or("Base")),
+            escape_csv(&edge.kind),
+         ].join(","));
+         edges.push('\n');
+      }
+      self.files.write_file("nodes.csv", &self.nodes)?;
+      self.files.write_file("edges.csv", &edges)?;
+      self.files.finish()
+   }
+}
+
+/// Function to quote one CSV field if needed (RFC 4180).
+pub fn escape_csv(value: &str) -> String {
+   if value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r') {
+      format!("\"{}\"", value.replace('"', "\"\""))
+   } else {
+      value.to_string()
+   }
+}
+
+/// Write results in standard output, nothing is written on disk.
+pub struct StdoutSink {
+   ndjson: bool,
+}
+
+impl StdoutSink {
+   pub fn new(ndjson: bool) -> Self {
+      StdoutSink { ndjson }
+   }
+}
+
+impl Sink for StdoutSink {
+   fn write_json(&mut self, name: &str, final_json: &serde_json::value::Value) -> std::io::Result<()> {
+      let stdout = std::io::stdout();
+      let mut handle = stdout.lock();
+      if self.ndjson {
+         if let Some(objects) = final_json["data"].as_array() {
+            for object in objects {
+               let mut line = object.to_owned();
+               line["ObjectType"] = name.into();
+               writeln!(handle, "{}", line)?;
+            }
+         }
+      } else {
+         writeln!(handle, "{}", final_json)?;
+      }
+      handle.flush()
+   }
+
+   fn write_file(&mut self, name: &str, content: &str) -> std::io::Result<()> {
+      // Only json files can be mixed with the results
+      if name.ends_with(".json") {
+         let stdout = std::io::stdout();
+         let mut handle = stdout.lock();
+         writeln!(handle, "{}", content)?;
+         handle.flush()?;
+      }
+      Ok(())
+   }
+}
+
+fn create_zip_archive<T: Seek + Write>(zip_filename: &mut T,json_result: &Vec<(String, String)>) -> ZipResult<()> {
+   let mut writer = ZipWriter::new(zip_filename);
+   // json file by json file
+   trace!("Making the ZIP file");
+
+   for file in json_result
+   {
+      let filename = &file.0;
+      let content = &file.1;
+      trace!("Adding file {}",filename.bold());
+      writer.start_file(filename, FileOptions::default())?;
+      writer.write_all(content.as_bytes())?;
+   }
+
+   writer.finish()?;
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_escape_csv() {
+   assert_eq!(escape_csv("DOMAIN ADMINS@ESSOS.LOCAL"), "DOMAIN ADMINS@ESSOS.LOCAL");
+   assert_eq!(escape_csv("CN=JORAH,OU=ESSOS"), "\"CN=JORAH,OU=ESSOS\"");
+   assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_partial_marker() {
+   let path = std::env::temp_dir().join(format!("rusthound_test_partial_{}",std::process::id())).to_string_lossy().to_string();
+   let mut sink = FileSink::new(&path, &"20231231000000".to_string(), &"essos-local".to_string());
+   sink.write_file("edges.csv", "a,b").unwrap();
+   sink.write_json("users", &serde_json::json!({"data":[]})).unwrap();
+   assert!(sink.get_marker_path().ends_with("20231231000000_essos-local_rusthound.files.partial"));
+   let marker = fs::read_to_string(sink.get_marker_path()).unwrap();
+   assert_eq!(marker, "edges.csv\nusers.json\n");
+   assert!(!Path::new(&format!("{}.tmp",sink.get_path("users.json"))).exists());
+   sink.finish().unwrap();
+   assert!(!Path::new(&sink.get_marker_path()).exists());
+   assert_eq!(fs::read_to_string(sink.get_path("edges.csv")).unwrap(), "a,b");
+
+   // One sink finished doesn't remove the marker of another sink still writing
+   let mut files = FileSink::new(&path, &"20231231000000".to_string(), &"essos-local".to_string());
+   let mut csv = CsvSink::new(&path, &"20231231000000".to_string(), &"essos-local".to_string());
+   files.write_file("summary.md", "#").unwrap();
+   csv.write_file("report.md", "#").unwrap();
+   files.finish().unwrap();
+   assert_eq!(fs::read_to_string(csv.files.get_marker_path()).unwrap(), "report.md\n");
+   csv.finish().unwrap();
+   assert!(!Path::new(&csv.files.get_marker_path()).exists());
+   fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_several_sinks() {
+   let path = std::env::temp_dir().join(format!("rusthound_test_sinks_{}",std::process::id())).to_string_lossy().to_string();
+   let datetime = "20231231000000".to_string();
+   let domain_format = "essos-local".to_string();
+   let mut sinks: Vec<Box<dyn Sink>> = vec![
+      Box::new(ZipSink::new(&path, &datetime, &domain_format)),
+      Box::new(NdjsonSink::new(&path, &datetime, &domain_format)),
+   ];
+   let final_json = serde_json::json!({"data":[{"ObjectIdentifier":"S-1-5-21-1-1104"}]});
+   for sink in sinks.iter_mut() {
+      sink.write_json("users", &final_json).unwrap();
+      sink.finish().unwrap();
+   }
+   assert!(Path::new(&format!("{}/{}_{}_rusthound.zip",path,datetime,domain_format)).exists());
+   let lines = fs::read_to_string(format!("{}/{}_{}_rusthound.ndjson",path,datetime,domain_format)).unwrap();
+   assert_eq!(lines, "{\"ObjectIdentifier\":\"S-1-5-21-1-1104\",\"ObjectType\":\"users\"}\n");
+   fs::remove_dir_all(&path).unwrap();
+}
@@ -0,0 +1,75 @@
+//! NTLM relay target lists (**--relay-targets**)
+//!
+//! Plain text files with one target URL per line, ready for `ntlmrelayx.py -tf`:
+//! - **targets_smb.txt**: computers without SMB signing required from the probe-smb module,
+//!   or every enabled computer except domain controllers when SMB signing wasn't probed
+//! - **targets_ldap.txt**: domain controllers, LDAP signing and channel binding can't be read with LDAP
+//! - **targets_adcs.txt**: certificate authorities with web enrollment enabled (ESC8)
+use crate::json::checker::bh_41::is_domain_controller;
+
+/// Function to get one host name property in lowercase.
+fn get_hostname(object: &serde_json::value::Value, property: &str) -> String
+{
+   object["Properties"][property].as_str().unwrap_or("").to_lowercase()
+}
+
+/// Function to make the relay target lists, file name and content. Empty lists are not returned.
+pub fn make_relay_targets(
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_cas: &Vec<serde_json::value::Value>,
+) -> Vec<(&'static str, String)>
+{
+   let enabled: Vec<&serde_json::value::Value> = vec_computers.iter()
+      .filter(|computer| computer["Properties"]["enabled"].as_bool().unwrap_or(true))
+      .collect();
+
+   // Signing probed on at least one computer
+   let probed = enabled.iter().any(|computer| computer["Properties"]["smbsigningrequired"].is_boolean());
+   let smb: Vec<String> = enabled.iter()
+      .filter(|computer| match probed {
+         true => computer["Properties"]["smbsigningrequired"] == false,
+         false => !is_domain_controller(computer),
+      })
+      .map(|computer| format!("smb://{}", get_hostname(computer, "name")))
+      .collect();
+   let ldap: Vec<String> = enabled.iter()
+      .filter(|computer| is_domain_controller(computer) && !computer["Properties"]["isrodc"].as_bool().unwrap_or(false))
+      .map(|computer| format!("ldap://{}", get_hostname(computer, "name")))
+      .collect();
+   let adcs: Vec<String> = vec_cas.iter()
+      .filter(|ca| ca["Properties"]["Web Enrollment"] == "Enabled")
+      .map(|ca| format!("http://{}/certsrv/certfnsh.asp", get_hostname(ca, "DNS Name")))
+      .collect();
+
+   vec![("targets_smb.txt", smb), ("targets_ldap.txt", ldap), ("targets_adcs.txt", adcs)]
+      .into_iter()
+      .filter(|(_, targets)| !targets.is_empty())
+      .map(|(name, mut targets)| {
+         targets.sort();
+         targets.dedup();
+         (name, targets.join("\n") + "\n")
+      })
+      .collect()
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_make_relay_targets() {
+   let dc = serde_json::json!({"Properties": {"name": "MEEREEN.ESSOS.LOCAL", "enabled": true, "unconstraineddelegation": true, "smbsigningrequired": true}, "PrimaryGroupSID": "S-1-5-21-1-2-3-516"});
+   let server = serde_json::json!({"Properties": {"name": "BRAAVOS.ESSOS.LOCAL", "enabled": true, "smbsigningrequired": false}});
+   let disabled = serde_json::json!({"Properties": {"name": "OLD.ESSOS.LOCAL", "enabled": false, "smbsigningrequired": false}});
+   let ca = serde_json::json!({"Properties": {"DNS Name": "braavos.essos.local", "Web Enrollment": "Enabled"}});
+
+   let targets = make_relay_targets(&vec![dc.to_owned(), server.to_owned(), disabled], &vec![ca]);
+   assert_eq!(targets, vec![
+      ("targets_smb.txt", "smb://braavos.essos.local\n".to_string()),
+      ("targets_ldap.txt", "ldap://meereen.essos.local\n".to_string()),
+      ("targets_adcs.txt", "http://braavos.essos.local/certsrv/certfnsh.asp\n".to_string()),
+   ]);
+
+   // Without probe-smb every computer but domain controllers
+   let unprobed = serde_json::json!({"Properties": {"name": "BRAAVOS.ESSOS.LOCAL", "enabled": true}});
+   let dc = serde_json::json!({"Properties": {"name": "MEEREEN.ESSOS.LOCAL", "enabled": true}, "PrimaryGroupSID": "S-1-5-21-1-2-3-516"});
+   let targets = make_relay_targets(&vec![dc, unprobed], &Vec::new());
+   assert_eq!(targets[0], ("targets_smb.txt", "smb://braavos.essos.local\n".to_string()));
+}
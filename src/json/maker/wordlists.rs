@@ -0,0 +1,100 @@
+//! Username and machine name lists (**--export-users-format**)
+//!
+//! Plain text lists of the enabled accounts for password spraying and relay tools, one name per line:
+//! - **users_sam.txt**: `jon.snow`
+//! - **users_domain.txt**: `NORTH\jon.snow` with the NetBIOS name of the domain
+//! - **users_upn.txt**: `jon.snow@north.sevenkingdoms.local`, the implicit UPN if userPrincipalName is not set
+//! - **machines.txt**: `winterfell.north.sevenkingdoms.local`
+//!
+//! Trust accounts are not written.
+use crate::json::checker::netbios::DomainNames;
+
+/// Username formats of --export-users-format.
+pub const USERS_FORMATS: [&str; 3] = ["sam", "domain", "upn"];
+
+/// Function to check the comma separated formats of --export-users-format.
+pub fn parse_users_formats(value: &str) -> std::result::Result<String, String>
+{
+   let formats: Vec<String> = value.split(',').map(|format| format.trim().to_lowercase()).collect();
+   match formats.iter().find(|format| !USERS_FORMATS.contains(&format.as_str())) {
+      Some(format) => Err(format!("unknown format '{}', possible values: {}", format, USERS_FORMATS.join(", "))),
+      None => Ok(formats.join(",")),
+   }
+}
+
+/// Function to get one username in one format.
+fn format_username(user: &serde_json::value::Value, format: &str, domain_names: &DomainNames) -> Option<String>
+{
+   let sam = user["Properties"]["samaccountname"].as_str()?;
+   let domain = user["Properties"]["domain"].as_str().unwrap_or("");
+   match format {
+      "domain" => Some(format!("{}\\{}", domain_names.get_netbios(domain), sam)),
+      "upn" => Some(match user["Properties"]["userprincipalname"].as_str() {
+         Some(upn) => upn.to_string(),
+         None => format!("{}@{}", sam, domain.to_lowercase()),
+      }),
+      _ => Some(sam.to_string()),
+   }
+}
+
+/// Function to make the username and machine name lists, file name and content. Empty lists are not returned.
+pub fn make_wordlists(
+   formats: &str,
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   domain_names: &DomainNames,
+) -> Vec<(String, String)>
+{
+   let is_enabled = |object: &&serde_json::value::Value| {
+      object["Properties"]["enabled"].as_bool().unwrap_or(true)
+         && !object["Properties"]["trustaccount"].as_bool().unwrap_or(false)
+   };
+
+   let mut lists: Vec<(String, Vec<String>)> = formats.split(',')
+      .map(|format| (
+         format!("users_{}.txt", format),
+         vec_users.iter().filter(is_enabled).filter_map(|user| format_username(user, format, domain_names)).collect(),
+      ))
+      .collect();
+   lists.push((
+      "machines.txt".to_string(),
+      vec_computers.iter()
+         .filter(is_enabled)
+         .filter_map(|computer| computer["Properties"]["name"].as_str())
+         .map(|name| name.to_lowercase())
+         .collect(),
+   ));
+
+   lists.into_iter()
+      .filter(|(_, names)| !names.is_empty())
+      .map(|(name, mut names)| {
+         names.sort();
+         names.dedup();
+         (name, names.join("\n") + "\n")
+      })
+      .collect()
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_make_wordlists() {
+   assert_eq!(parse_users_formats("SAM, upn"), Ok("sam,upn".to_string()));
+   assert!(parse_users_formats("sam,email").is_err());
+
+   let vec_users = vec![
+      serde_json::json!({"Properties": {"samaccountname": "jon.snow", "domain": "NORTH.SEVENKINGDOMS.LOCAL", "enabled": true, "userprincipalname": "jon@sevenkingdoms.local"}}),
+      serde_json::json!({"Properties": {"samaccountname": "arya.stark", "domain": "NORTH.SEVENKINGDOMS.LOCAL", "enabled": true}}),
+      serde_json::json!({"Properties": {"samaccountname": "old", "domain": "NORTH.SEVENKINGDOMS.LOCAL", "enabled": false}}),
+      serde_json::json!({"Properties": {"samaccountname": "SEVENKINGDOMS$", "domain": "NORTH.SEVENKINGDOMS.LOCAL", "enabled": true, "trustaccount": true}}),
+   ];
+   let vec_computers = vec![
+      serde_json::json!({"Properties": {"name": "WINTERFELL.NORTH.SEVENKINGDOMS.LOCAL", "enabled": true}}),
+   ];
+   let domain_names = DomainNames::new(&vec![serde_json::json!({"Properties": {"name": "NORTH.SEVENKINGDOMS.LOCAL", "netbiosname": "STARK"}})]);
+   let lists = make_wordlists("domain,upn", &vec_users, &vec_computers, &domain_names);
+   assert_eq!(lists, vec![
+      ("users_domain.txt".to_string(), "STARK\\arya.stark\nSTARK\\jon.snow\n".to_string()),
+      ("users_upn.txt".to_string(), "arya.stark@north.sevenkingdoms.local\njon@sevenkingdoms.local\n".to_string()),
+      ("machines.txt".to_string(), "winterfell.north.sevenkingdoms.local\n".to_string()),
+   ]);
+}
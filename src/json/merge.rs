@@ -0,0 +1,155 @@
+//! Merge of BloodHound .json sets from several collectors (`rusthound merge`)
+//!
+//! Files are grouped by their `meta.type`, objects are deduplicated by ObjectIdentifier (case insensitive),
+//! properties are completed with the values missing in the first object found and relations (ACEs, members, sessions...)
+//! are merged without duplicates. Useful when SharpHound and RustHound covered different domains or collection methods.
+use log::{info,warn};
+use colored::Colorize;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use crate::args::MergeOptions;
+
+/// Structure containing the merged objects of one BloodHound file type.
+struct MergedFile {
+   meta: serde_json::value::Value,
+   /// Uppercase ObjectIdentifier to index in `data`
+   index: HashMap<String, usize>,
+   data: Vec<serde_json::value::Value>,
+}
+
+/// Function to merge the BloodHound .json files of all `--input` directories in the `--output` directory.
+pub fn merge_directories(merge_args: &MergeOptions) -> crate::errors::Result<()> {
+   let mut files: Vec<(String, MergedFile)> = Vec::new();
+   for input in &merge_args.inputs {
+      let mut count = 0;
+      for entry in fs::read_dir(input)? {
+         let path = entry?.path();
+         if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+            continue
+         }
+         let final_json: serde_json::value::Value = match serde_json::from_str(&fs::read_to_string(&path)?) {
+            Ok(final_json) => final_json,
+            Err(_) => {
+               warn!("{} is not a valid .json file, skipped", path.display());
+               continue
+            },
+         };
+         // Only BloodHound files, not report.json or stats.json
+         let (bh_type, data) = match (final_json["meta"]["type"].as_str(), final_json["data"].as_array()) {
+            (Some(bh_type), Some(data)) => (bh_type.to_lowercase(), data),
+            _ => continue,
+         };
+         let position = match files.iter().position(|(name, _)| name == &bh_type) {
+            Some(position) => position,
+            None => {
+               files.push((bh_type.to_owned(), MergedFile { meta: final_json["meta"].to_owned(), index: HashMap::new(), data: Vec::new() }));
+               files.len() - 1
+            },
+         };
+         let file = &mut files[position].1;
+         merge_meta(&mut file.meta, &final_json["meta"]);
+         for object in data {
+            let objectid = object["ObjectIdentifier"].as_str().unwrap_or("").to_uppercase();
+            match file.index.get(&objectid).copied() {
+               Some(i) if !objectid.is_empty() => merge_object(&mut file.data[i], object),
+               _ => {
+                  file.index.insert(objectid, file.data.len());
+                  file.data.push(object.to_owned());
+               },
+            }
+         }
+         count += 1;
+      }
+      info!("{} BloodHound files loaded from {}", count.to_string().bold(), input.bold());
+   }
+   if files.is_empty() {
+      return Err(Error::new(ErrorKind::NotFound, format!("No BloodHound .json file in {}", merge_args.inputs.join(", "))).into())
+   }
+
+   fs::create_dir_all(&merge_args.output)?;
+   for (bh_type, file) in files {
+      let count = file.data.len();
+      let mut final_json = serde_json::json!({ "data": file.data, "meta": file.meta });
+      final_json["meta"]["count"] = count.into();
+      let path = Path::new(&merge_args.output).join(format!("{}.json", bh_type));
+      fs::write(&path, final_json.to_string())?;
+      info!("{} {} merged in {}!", count.to_string().bold(), bh_type, path.display());
+   }
+   Ok(())
+}
+
+/// Function to merge the meta of one file: collection methods are added and the highest version is kept.
+fn merge_meta(meta: &mut serde_json::value::Value, other: &serde_json::value::Value) {
+   let methods = meta["methods"].as_i64().unwrap_or(0) | other["methods"].as_i64().unwrap_or(0);
+   meta["methods"] = methods.into();
+   let (version, other_version) = (meta["version"].as_i64().unwrap_or(0), other["version"].as_i64().unwrap_or(0));
+   if version != other_version {
+      warn!("{} files with versions {} and {}, version {} kept", meta["type"].as_str().unwrap_or(""), version, other_version, version.max(other_version));
+      meta["version"] = version.max(other_version).into();
+   }
+}
+
+/// Function to merge two collections of the same object.
+/// Values missing, null or empty in `object` are taken from `other`, arrays are merged without duplicates
+/// and properties are merged one by one.
+pub fn merge_object(object: &mut serde_json::value::Value, other: &serde_json::value::Value) {
+   let (object, other) = match (object.as_object_mut(), other.as_object()) {
+      (Some(object), Some(other)) => (object, other),
+      _ => return,
+   };
+   for (key, value) in other {
+      match object.get_mut(key) {
+         Some(serde_json::value::Value::Array(values)) => {
+            for value in value.as_array().unwrap_or(&Vec::new()) {
+               if !values.contains(value) {
+                  values.push(value.to_owned());
+               }
+            }
+         },
+         Some(current @ serde_json::value::Value::Object(_)) => merge_object(current, value),
+         // Collected by one of the collectors
+         Some(current) if key == "Collected" => *current = (current.as_bool().unwrap_or(false) || value.as_bool().unwrap_or(false)).into(),
+         Some(current) if is_empty(current) => *current = value.to_owned(),
+         Some(_) => {},
+         None => { object.insert(key.to_owned(), value.to_owned()); },
+      }
+   }
+}
+
+/// Function to check if one value was not collected.
+fn is_empty(value: &serde_json::value::Value) -> bool {
+   match value {
+      serde_json::value::Value::Null => true,
+      serde_json::value::Value::String(value) => value.is_empty(),
+      _ => false,
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_merge_object() {
+   let mut object = serde_json::json!({
+      "ObjectIdentifier": "S-1-5-21-1-2-3-1104",
+      "Properties": {"name": "JON@NORTH.LOCAL", "description": null, "enabled": true},
+      "Aces": [{"PrincipalSID": "S-1-5-21-1-2-3-512", "RightName": "GenericAll"}],
+      "Sessions": {"Results": [], "Collected": false},
+   });
+   let other = serde_json::json!({
+      "ObjectIdentifier": "s-1-5-21-1-2-3-1104",
+      "Properties": {"name": "JON@NORTH.LOCAL", "description": "Lord Commander", "enabled": false, "lastlogon": 1700000000},
+      "Aces": [{"PrincipalSID": "S-1-5-21-1-2-3-512", "RightName": "GenericAll"}, {"PrincipalSID": "S-1-5-21-1-2-3-519", "RightName": "WriteDacl"}],
+      "Sessions": {"Results": [{"UserSID": "S-1-5-21-1-2-3-1104", "ComputerSID": "S-1-5-21-1-2-3-1001"}], "Collected": true},
+   });
+   merge_object(&mut object, &other);
+   assert_eq!(object["ObjectIdentifier"], "S-1-5-21-1-2-3-1104");
+   assert_eq!(object["Properties"]["description"], "Lord Commander");
+   assert_eq!(object["Properties"]["enabled"], true);
+   assert_eq!(object["Properties"]["lastlogon"], 1700000000);
+   assert_eq!(object["Aces"].as_array().unwrap().len(), 2);
+   assert_eq!(object["Sessions"]["Results"].as_array().unwrap().len(), 1);
+   assert_eq!(object["Sessions"]["Collected"], true);
+}
@@ -5,6 +5,11 @@ pub use parser::*;
 pub use templates::*;
 
 pub mod checker;
+pub mod graph;
 pub mod maker;
+pub mod merge;
 pub mod parser;
+pub mod query;
+pub mod report;
+pub mod stats;
 pub mod templates;
\ No newline at end of file
@@ -1,23 +1,30 @@
 
 use colored::Colorize;
 use ldap3::SearchEntry;
-use log::{info, debug, trace, error};
+use log::{info, debug, trace, warn, error};
 use regex::Regex;
 use serde_json::json;
 use std::collections::HashMap;
 use x509_parser::prelude::*;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
 
 use crate::enums::acl::{parse_ntsecuritydescriptor,parse_gmsa};
-use crate::enums::date::{convert_timestamp,string_to_epoch};
-use crate::enums::forestlevel::get_forest_level;
+use crate::enums::date::{convert_timestamp,string_to_epoch,interval_to_seconds,interval_to_string};
+use crate::enums::dn::get_domain_from_dn;
+use crate::enums::encryption::SupportedEncryptionTypes;
+use crate::enums::forestlevel::{get_forest_level,get_schema_version};
 use crate::enums::gplink::parse_gplink;
+use crate::enums::os::OperatingSystem;
 use crate::enums::secdesc::LdapSid;
-use crate::enums::sid::{decode_guid, objectsid_to_vec8, sid_maker};
-use crate::enums::spntasks::check_spn;
+use crate::enums::sid::{decode_guid, get_domain_sid, objectsid_to_vec8, sid_maker};
+use crate::enums::spntasks::{check_spn,parse_mssql_spn};
 use crate::enums::uacflags::get_flag;
 use crate::enums::trusts::get_trust_flag;
 
 use crate::json::templates::bh_41::*;
+use crate::errors::RustHoundError;
+use crate::json::parser::schema::set_property;
+use crate::json::stats::mark_partial;
 //use crate::errors::{Error, Result};
 
 /*
@@ -31,6 +38,14 @@ function 7 : ForeignSecurityPrincipal
 function 8 : containers
 function 9 : trust domain
 function 10: unknown values
+function 11: sites
+function 12: subnets
+function 13: site links
+function 14: schema
+function 15: partitions
+function 16: service connection points
+function 17: Entra ID identifiers
+function 18: RODC attributes
 */
 
 /*****************************************
@@ -70,8 +85,8 @@ pub fn parse_user(
     let mut user_json = prepare_user_json_template();
 
     // Change all values...
-    user_json["Properties"]["domain"] = domain.to_uppercase().into();
-    user_json["Properties"]["distinguishedname"] = result_dn.into();
+    set_property(&mut user_json, "domain", domain.to_uppercase());
+    set_property(&mut user_json, "distinguishedname", result_dn);
 
     // With a check
     let mut group_id: String = "".to_owned();
@@ -80,32 +95,51 @@ pub fn parse_user(
             "sAMAccountName" => {
                 let name = &value[0];
                 let email = format!("{}@{}",name.to_owned(),domain);
-                user_json["Properties"]["name"] = email.to_uppercase().into();
-                user_json["Properties"]["samaccountname"] = name.to_owned().into();
+                set_property(&mut user_json, "name", email.to_uppercase());
+                set_property(&mut user_json, "samaccountname", name.to_owned());
+            }
+            "sAMAccountType" => {
+                set_property(&mut user_json, "samaccounttype", value[0].parse::<i64>().unwrap_or(0));
             }
             "description" => {
-                user_json["Properties"]["description"] = value[0].to_owned().into();
+                set_property(&mut user_json, "description", value[0].to_owned());
             }
             "mail" => {
-                user_json["Properties"]["email"] = value[0].to_owned().into();
+                set_property(&mut user_json, "email", value[0].to_owned());
+            }
+            "userPrincipalName" => {
+                set_property(&mut user_json, "userprincipalname", value[0].to_owned());
             }
             "title" => {
-                user_json["Properties"]["title"] = value[0].to_owned().into();
+                set_property(&mut user_json, "title", value[0].to_owned());
+            }
+            "department" => {
+                set_property(&mut user_json, "department", value[0].to_owned());
+            }
+            "company" => {
+                set_property(&mut user_json, "company", value[0].to_owned());
+            }
+            "manager" => {
+                // DN replaced by the manager name in checker
+                set_property(&mut user_json, "manager", value[0].to_uppercase());
+            }
+            "directReports" => {
+                set_property(&mut user_json, "directreports", value.len());
             }
             "userPassword" => {
-                user_json["Properties"]["userpassword"] = value[0].to_owned().into();
+                set_property(&mut user_json, "userpassword", value[0].to_owned());
             }
             "unixUserPassword" => {
-                user_json["Properties"]["unixpassword"] = value[0].to_owned().into();
+                set_property(&mut user_json, "unixpassword", value[0].to_owned());
             }
             "unicodepwd" => {
-                user_json["Properties"]["unicodepassword"] = value[0].to_owned().into();
+                set_property(&mut user_json, "unicodepassword", value[0].to_owned());
             }
             "sfupassword" => {
                 //user_json["Properties"]["sfupassword"] = value[0].to_owned().into();
             }
             "displayName" => {
-                user_json["Properties"]["displayname"] = value[0].to_owned().into();
+                set_property(&mut user_json, "displayname", value[0].to_owned());
             }
             "adminCount" => {
                 let isadmin = &value[0];
@@ -113,13 +147,13 @@ pub fn parse_user(
                 if isadmin == "1" {
                     admincount = true;
                 }
-                user_json["Properties"]["admincount"] = admincount.into();
+                set_property(&mut user_json, "admincount", admincount);
             }
             "homeDirectory" => {
-                user_json["Properties"]["homedirectory"] = value[0].to_owned().into();
+                set_property(&mut user_json, "homedirectory", value[0].to_owned());
             }
             "scriptpath" => {
-                user_json["Properties"]["logonscript"] = value[0].to_owned().into();
+                set_property(&mut user_json, "logonscript", value[0].to_owned());
             }
             "userAccountControl" => {
                 let uac = &value[0].parse::<u32>().unwrap_or(0);
@@ -127,35 +161,45 @@ pub fn parse_user(
                 //trace!("UAC : {:?}",uac_flags);
                 for flag in uac_flags {
                     if flag.contains("AccountDisable") {
-                        user_json["Properties"]["enabled"] = false.into();
+                        set_property(&mut user_json, "enabled", false);
                     };
                     //if flag.contains("Lockout") { let enabled = true; user_json["Properties"]["enabled"] = enabled.into(); };
                     if flag.contains("PasswordNotRequired") {
-                        user_json["Properties"]["passwordnotreqd"] = true.into();
+                        set_property(&mut user_json, "passwordnotreqd", true);
                     };
                     if flag.contains("DontExpirePassword") {
-                        user_json["Properties"]["pwdneverexpires"] = true.into();
+                        set_property(&mut user_json, "pwdneverexpires", true);
                     };
                     if flag.contains("DontReqPreauth") {
-                        user_json["Properties"]["dontreqpreauth"] = true.into();
+                        set_property(&mut user_json, "dontreqpreauth", true);
+                    };
+                    // Trust account (TDO) for a trusted domain
+                    if flag.contains("InterdomainTrustAccount") {
+                        set_property(&mut user_json, "trustaccount", true);
                     };
                     // KUD (Kerberos Unconstrained Delegation)
                     if flag.contains("TrustedForDelegation") {
-                        user_json["Properties"]["unconstraineddelegation"] = true.into();
+                        set_property(&mut user_json, "unconstraineddelegation", true);
                     };
                     if flag.contains("NotDelegated") {
-                        user_json["Properties"]["sensitive"] = true.into();
+                        set_property(&mut user_json, "sensitive", true);
                     };
                     //if flag.contains("PasswordExpired") { let password_expired = true; user_json["Properties"]["pwdneverexpires"] = password_expired.into(); };
                     if flag.contains("TrustedToAuthForDelegation") {
-                        user_json["Properties"]["trustedtoauth"] = true.into();
+                        set_property(&mut user_json, "trustedtoauth", true);
+                    };
+                    if flag.contains("UseDesKeyOnly") {
+                        set_property(&mut user_json, "desenabled", true);
                     };
                 }
             }
+            "msDS-SupportedEncryptionTypes" => {
+                set_encryption_properties(&mut user_json, &value[0]);
+            }
             "msDS-AllowedToDelegateTo"  => {
                 // KCD (Kerberos Constrained Delegation)
                 //trace!(" AllowToDelegateTo: {:?}",&value);
-                user_json["Properties"]["allowedtodelegate"] = value.to_owned().into();
+                set_property(&mut user_json, "allowedtodelegate", value.to_owned());
                 // AllowedToDelegate
                 let mut vec_members: Vec<serde_json::value::Value> = Vec::new();
                 let mut allowed_to_delegate = prepare_member_json_template();
@@ -180,27 +224,27 @@ pub fn parse_user(
                 let lastlogon = &value[0].parse::<i64>().unwrap_or(0);
                 if lastlogon.is_positive() {
                     let epoch = convert_timestamp(*lastlogon);
-                    user_json["Properties"]["lastlogon"] = epoch.into();
+                    set_property(&mut user_json, "lastlogon", epoch);
                 }
             }
             "lastLogonTimestamp" => {
                 let lastlogontimestamp = &value[0].parse::<i64>().unwrap_or(0);
                 if lastlogontimestamp.is_positive() {
                     let epoch = convert_timestamp(*lastlogontimestamp);
-                    user_json["Properties"]["lastlogontimestamp"] = epoch.into();
+                    set_property(&mut user_json, "lastlogontimestamp", epoch);
                 }
             }
             "pwdLastSet" => {
                 let pwdlastset = &value[0].parse::<i64>().unwrap_or(0);
                 if pwdlastset.is_positive() {
                     let epoch = convert_timestamp(*pwdlastset);
-                    user_json["Properties"]["pwdlastset"] = epoch.into();
+                    set_property(&mut user_json, "pwdlastset", epoch);
                 }
             }
             "whenCreated" => {
                let epoch = string_to_epoch(&value[0]);
                if epoch.is_positive() {
-                   user_json["Properties"]["whencreated"] = epoch.into();
+                   set_property(&mut user_json, "whencreated", epoch);
                }
            }
             "servicePrincipalName" => {
@@ -208,6 +252,9 @@ pub fn parse_user(
                 // SPNTargets values
                 let mut targets: Vec<serde_json::value::Value> = Vec::new();
 
+                // MSSQL instances values
+                let mut mssql_instances: Vec<serde_json::value::Value> = Vec::new();
+
                 let mut added: bool = false;
                 for v in value {
                     result.push(v.to_owned());
@@ -217,9 +264,13 @@ pub fn parse_user(
                         targets.push(target.to_owned());
                         added = true;
                     }
+                    if let Some(instance) = parse_mssql_spn(v) {
+                        mssql_instances.push(instance);
+                    }
                 }
-                user_json["Properties"]["serviceprincipalnames"] = result.to_owned().into();
-                user_json["Properties"]["hasspn"] = true.into();
+                set_property(&mut user_json, "serviceprincipalnames", result.to_owned());
+                set_property(&mut user_json, "hasspn", true);
+                set_property(&mut user_json, "mssqlinstances", mssql_instances);
                 user_json["SPNTargets"] = targets.into();
             }
             "primaryGroupID" => {
@@ -243,10 +294,8 @@ pub fn parse_user(
                 sid = sid_maker(LdapSid::parse(&value[0]).unwrap().1, domain);
                 user_json["ObjectIdentifier"] = sid.to_owned().into();
 
-                let re = Regex::new(r"^S-[0-9]{1}-[0-9]{1}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}").unwrap();
-                for domain_sid in re.captures_iter(&sid) 
-                {
-                    user_json["Properties"]["domainsid"] = domain_sid[0].to_owned().to_string().into();
+                if let Some(domain_sid) = get_domain_sid(&sid) {
+                    set_property(&mut user_json, "domainsid", domain_sid);
                 }
             }
             "nTSecurityDescriptor" => {
@@ -264,15 +313,7 @@ pub fn parse_user(
                 user_json["Aces"] = relations_ace.into();
             }
             "sIDHistory" => {
-                // not tested! #tocheck
-                //debug!("sIDHistory: {:?}",&value[0]);
-                let mut list_sid_history: Vec<String> = Vec::new();
-                for bsid in value {
-                    debug!("sIDHistory: {:?}", &bsid);
-                    list_sid_history.push(sid_maker(LdapSid::parse(&bsid).unwrap().1, domain));
-                    // Todo function to add the sid history in user_json['HasSIDHistory']
-                }
-                user_json["Properties"]["sidhistory"] = list_sid_history.into();
+                set_sid_history(&mut user_json, value, domain);
             }
             "msDS-GroupMSAMembership" => {
                 let entry_type = "user".to_string();
@@ -336,9 +377,20 @@ pub fn parse_user(
         "User".to_string(),
     );
 
+    // Entra ID identifiers to join with AzureHound data
+    add_entra_sync_ids(&mut user_json, &result_attrs, &result_bin);
+
     return user_json;
 }
 
+/// Function to report one attribute which can't be decoded with a [`RustHoundError::ParseError`].
+/// The object is kept without the attribute and the collection is partial, exit code 10.
+pub fn report_parse_error(dn: &str, attr: &str) {
+    let err = RustHoundError::ParseError { dn: dn.to_string(), attr: attr.to_string() };
+    warn!("{}", err);
+    mark_partial(err.to_string());
+}
+
 /*****************************************
 ******************************************
 2- Function to parse groups information
@@ -378,8 +430,8 @@ pub fn parse_group(
     let mut member_json = prepare_member_json_template();
 
     // Change all values...
-    group_json["Properties"]["domain"] = domain.to_uppercase().into();
-    group_json["Properties"]["distinguishedname"] = result_dn.into();
+    set_property(&mut group_json, "domain", domain.to_uppercase());
+    set_property(&mut group_json, "distinguishedname", result_dn);
 
     #[allow(unused_assignments)]
     let mut sid: String = "".to_owned();
@@ -389,10 +441,10 @@ pub fn parse_group(
             "name" => {
                 let name = &value[0];
                 let email = format!("{}@{}",name.to_owned(),domain);
-                group_json["Properties"]["name"] = email.to_uppercase().into();
+                set_property(&mut group_json, "name", email.to_uppercase());
             }
             "description" => {
-                group_json["Properties"]["description"] = value[0].to_owned().into();
+                set_property(&mut group_json, "description", value[0].to_owned());
             }
             "adminCount" => {
                 let isadmin = &value[0];
@@ -400,10 +452,10 @@ pub fn parse_group(
                 if isadmin == "1" {
                     admincount = true;
                 }
-                group_json["Properties"]["admincount"] = admincount.into();
+                set_property(&mut group_json, "admincount", admincount);
             }
             "sAMAccountName" => {
-                group_json["Properties"]["samaccountname"] = value[0].to_owned().into();
+                set_property(&mut group_json, "samaccountname", value[0].to_owned());
             }
             "member" => {
                 if value.len() > 0 {
@@ -425,7 +477,7 @@ pub fn parse_group(
                 /*let re = Regex::new(r"^S-[0-9]{1}-[0-9]{1}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}").unwrap();
                 for domain_sid in re.captures_iter(&sid) 
                 {
-                    group_json["Properties"]["domainsid"] = domain_sid[0].to_owned().to_string().into();
+                    set_property(&mut group_json, "domainsid", domain_sid[0].to_owned().to_string());
                 }*/
 
                 // highvalue
@@ -434,7 +486,7 @@ pub fn parse_group(
                 || sid.ends_with("-519") 
                 || sid.ends_with("-520") 
                 {
-                    group_json["Properties"]["highvalue"] = true.into();
+                    set_property(&mut group_json, "highvalue", true);
                 }
                 else if sid.ends_with("S-1-5-32-544") 
                 || sid.ends_with("S-1-5-32-548") 
@@ -442,16 +494,16 @@ pub fn parse_group(
                 || sid.ends_with("S-1-5-32-550") 
                 || sid.ends_with("S-1-5-32-551") 
                 {
-                    group_json["Properties"]["highvalue"] = true.into();
+                    set_property(&mut group_json, "highvalue", true);
                 }
                 else {
-                    group_json["Properties"]["highvalue"] = false.into();
+                    set_property(&mut group_json, "highvalue", false);
                 }
             }
             "whenCreated" => {
                 let epoch = string_to_epoch(&value[0]);
                 if epoch.is_positive() {
-                    group_json["Properties"]["whencreated"] = epoch.into();
+                    set_property(&mut group_json, "whencreated", epoch);
                 }
             }
             "IsDeleted" => {
@@ -469,10 +521,8 @@ pub fn parse_group(
                 sid = sid_maker(LdapSid::parse(&value[0]).unwrap().1, domain);
                 group_json["ObjectIdentifier"] = sid.to_owned().into();
 
-                let re = Regex::new(r"^S-[0-9]{1}-[0-9]{1}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}").unwrap();
-                for domain_sid in re.captures_iter(&sid) 
-                {
-                    group_json["Properties"]["domainsid"] = domain_sid[0].to_owned().to_string().into();
+                if let Some(domain_sid) = get_domain_sid(&sid) {
+                    set_property(&mut group_json, "domainsid", domain_sid);
                 }
                 
                 // highvalue
@@ -481,7 +531,7 @@ pub fn parse_group(
                 || sid.ends_with("-519") 
                 || sid.ends_with("-520") 
                 {
-                    group_json["Properties"]["highvalue"] = true.into();
+                    set_property(&mut group_json, "highvalue", true);
                 }
                 else if sid.ends_with("S-1-5-32-544") 
                 || sid.ends_with("S-1-5-32-548") 
@@ -489,10 +539,10 @@ pub fn parse_group(
                 || sid.ends_with("S-1-5-32-550") 
                 || sid.ends_with("S-1-5-32-551") 
                 {
-                    group_json["Properties"]["highvalue"] = true.into();
+                    set_property(&mut group_json, "highvalue", true);
                 }
                 else {
-                    group_json["Properties"]["highvalue"] = false.into();
+                    set_property(&mut group_json, "highvalue", false);
                 }
             }
             "nTSecurityDescriptor" => {
@@ -509,6 +559,9 @@ pub fn parse_group(
                 );
                 group_json["Aces"] = relations_ace.into();
             }
+            "sIDHistory" => {
+                set_sid_history(&mut group_json, value, domain);
+            }
             _ => {}
         }
     }
@@ -527,6 +580,9 @@ pub fn parse_group(
         "Group".to_string(),
     );
 
+    // Entra ID identifiers to join with AzureHound data
+    add_entra_sync_ids(&mut group_json, &result_attrs, &result_bin);
+
     return group_json;
 }
 
@@ -573,8 +629,8 @@ pub fn parse_computer(
     });
 
     // Change all values...
-    computer_json["Properties"]["domain"] = domain.to_uppercase().into();
-    computer_json["Properties"]["distinguishedname"] = result_dn.into();
+    set_property(&mut computer_json, "domain", domain.to_uppercase());
+    set_property(&mut computer_json, "distinguishedname", result_dn);
     let mut sid: String = "".to_owned();
     let mut group_id: String = "".to_owned();
     // With a check
@@ -583,19 +639,25 @@ pub fn parse_computer(
             "name" => {
                 let name = &value[0];
                 let email = format!("{}@{}",name.to_owned(),domain);
-                computer_json["Properties"]["name"] = email.to_uppercase().into();
+                set_property(&mut computer_json, "name", email.to_uppercase());
             }
             "sAMAccountName" => {
-                computer_json["Properties"]["samaccountname"] = value[0].to_owned().into();
+                set_property(&mut computer_json, "samaccountname", value[0].to_owned());
+            }
+            "sAMAccountType" => {
+                set_property(&mut computer_json, "samaccounttype", value[0].parse::<i64>().unwrap_or(0));
             }
             "dNSHostName" => {
-                computer_json["Properties"]["name"] = value[0].to_uppercase().into();
+                set_property(&mut computer_json, "name", value[0].to_uppercase());
             }
             "description" => {
-                computer_json["Properties"]["description"] = value[0].to_owned().into();
+                set_property(&mut computer_json, "description", value[0].to_owned());
             }
             "operatingSystem" => {
-                computer_json["Properties"]["operatingsystem"] = value[0].to_owned().into();
+                set_property(&mut computer_json, "operatingsystem", value[0].to_owned());
+            }
+            "operatingSystemVersion" => {
+                set_property(&mut computer_json, "operatingsystemversion", value[0].to_owned());
             }
             //"operatingSystemServicePack" => {
             //    //operatingsystem
@@ -618,36 +680,42 @@ pub fn parse_computer(
                 let lastlogon = &value[0].parse::<i64>().unwrap_or(0);
                 if lastlogon.is_positive() {
                     let epoch = convert_timestamp(*lastlogon);
-                    computer_json["Properties"]["lastlogon"] = epoch.into();
+                    set_property(&mut computer_json, "lastlogon", epoch);
                 }
             }
             "lastLogonTimestamp" => {
                 let lastlogontimestamp = &value[0].parse::<i64>().unwrap_or(0);
                 if lastlogontimestamp.is_positive() {
                     let epoch = convert_timestamp(*lastlogontimestamp);
-                    computer_json["Properties"]["lastlogontimestamp"] = epoch.into();
+                    set_property(&mut computer_json, "lastlogontimestamp", epoch);
                 }
             }
             "pwdLastSet" => {
                 let pwdlastset = &value[0].parse::<i64>().unwrap_or(0);
                 if pwdlastset.is_positive() {
                     let epoch = convert_timestamp(*pwdlastset);
-                    computer_json["Properties"]["pwdlastset"] = epoch.into();
+                    set_property(&mut computer_json, "pwdlastset", epoch);
                 }
             }
             "whenCreated" => {
                 let epoch = string_to_epoch(&value[0]);
                 if epoch.is_positive() {
-                    computer_json["Properties"]["whencreated"] = epoch.into();
+                    set_property(&mut computer_json, "whencreated", epoch);
                 }
             }
             "servicePrincipalName" => {
                 //servicePrincipalName and hasspn
                 let mut result: Vec<String> = Vec::new();
+                let mut mssql_instances: Vec<serde_json::value::Value> = Vec::new();
                 for value in &result_attrs["servicePrincipalName"] {
                     result.push(value.to_owned());
+                    // MSSQL running as NetworkService or virtual account
+                    if let Some(instance) = parse_mssql_spn(value) {
+                        mssql_instances.push(instance);
+                    }
                 }
-                computer_json["Properties"]["serviceprincipalnames"] = result.to_owned().into();
+                set_property(&mut computer_json, "serviceprincipalnames", result.to_owned());
+                set_property(&mut computer_json, "mssqlinstances", mssql_instances);
             }
             "userAccountControl" => {
                 //userAccountControl
@@ -656,23 +724,33 @@ pub fn parse_computer(
                 //trace!("UAC : {:?}",uac_flags);
                 for flag in uac_flags {
                     if flag.contains("AccountDisable") {
-                        computer_json["Properties"]["enabled"] = false.into();
+                        set_property(&mut computer_json, "enabled", false);
                     };
                     //if flag.contains("Lockout") { let enabled = true; computer_json["Properties"]["enabled"] = enabled.into(); };
                     // KUD (Kerberos Unconstrained Delegation)
                     if flag.contains("TrustedForDelegation") {
-                        computer_json["Properties"]["unconstraineddelegation"] = true.into();
+                        set_property(&mut computer_json, "unconstraineddelegation", true);
                     };
                     //if flag.contains("PasswordExpired") { let password_expired = true; computer_json["Properties"]["pwdneverexpires"] = password_expired.into(); };
                     if flag.contains("TrustedToAuthForDelegation") {
-                        computer_json["Properties"]["trustedtoauth"] = true.into();
+                        set_property(&mut computer_json, "trustedtoauth", true);
+                    };
+                    // RODC (Read-Only Domain Controller)
+                    if flag.contains("PartialSecretsAccount") {
+                        set_property(&mut computer_json, "isrodc", true);
+                    };
+                    if flag.contains("UseDesKeyOnly") {
+                        set_property(&mut computer_json, "desenabled", true);
                     };
                 }
             }
+            "msDS-SupportedEncryptionTypes" => {
+                set_encryption_properties(&mut computer_json, &value[0]);
+            }
             "msDS-AllowedToDelegateTo"  => {
                 // KCD (Kerberos Constrained Delegation)
                 //trace!(" AllowToDelegateTo: {:?}",&value);
-                computer_json["Properties"]["allowedtodelegate"] = value.to_owned().into();
+                set_property(&mut computer_json, "allowedtodelegate", value.to_owned());
                 // AllowedToDelegate
                 let mut vec_members: Vec<serde_json::value::Value> = Vec::new();
                 let mut allowed_to_delegate = prepare_member_json_template();
@@ -701,11 +779,15 @@ pub fn parse_computer(
                     &result_attrs["name"][0].yellow().bold(),
                     &result_attrs["ms-Mcs-AdmPwd"][0].yellow().bold()
                 );
-                computer_json["Properties"]["haslaps"] = true.into();
+                set_property(&mut computer_json, "haslaps", true);
             }
             "ms-Mcs-AdmPwdExpirationTime" => {
                 // LAPS is set, random password for local adminsitrator
-                computer_json["Properties"]["haslaps"] = true.into();
+                set_property(&mut computer_json, "haslaps", true);
+            }
+            "msLAPS-PasswordExpirationTime" => {
+                // Windows LAPS is set
+                set_property(&mut computer_json, "haslaps", true);
             }
             "primaryGroupID" => {
                 group_id = value[0].to_owned();
@@ -716,6 +798,7 @@ pub fn parse_computer(
             _ => {}
         }
     }
+    set_os_properties(&mut computer_json);
     // For all, bins attributs
     for (key, value) in &result_bin {
         match key.as_str() {
@@ -724,13 +807,22 @@ pub fn parse_computer(
                 sid = sid_maker(LdapSid::parse(&value[0]).unwrap().1, domain);
                 computer_json["ObjectIdentifier"] = sid.to_owned().into();
 
-                let re = Regex::new(r"^S-[0-9]{1}-[0-9]{1}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}").unwrap();
-                for domain_sid in re.captures_iter(&sid) 
-                {
-                    computer_json["Properties"]["domainsid"] = domain_sid[0].to_owned().to_string().into();
+                if let Some(domain_sid) = get_domain_sid(&sid) {
+                    set_property(&mut computer_json, "domainsid", domain_sid);
                 }
                 
             }
+            "sIDHistory" => {
+                set_sid_history(&mut computer_json, value, domain);
+            }
+            "mS-DS-CreatorSID" => {
+                // Set when the computer was created with MachineAccountQuota by a user without create child rights
+                // <https://learn.microsoft.com/en-us/windows/win32/adschema/a-ms-ds-creatorsid>
+                if let Ok((_, creator)) = LdapSid::parse(&value[0]) {
+                    set_property(&mut computer_json, "creatorsid", sid_maker(creator, domain));
+                    set_property(&mut computer_json, "precreated", true);
+                }
+            }
             "nTSecurityDescriptor" => {
                 // Needed with acl
                 let entry_type = "computer".to_string();
@@ -824,6 +916,12 @@ pub fn parse_computer(
         "".to_string(),
     );
 
+    // Entra ID identifiers to join with AzureHound data
+    add_entra_sync_ids(&mut computer_json, &result_attrs, &result_bin);
+
+    // Password Replication Policy for Read-Only Domain Controllers
+    add_rodc_attributes(&mut computer_json, &result_attrs);
+
     return computer_json;
 }
 
@@ -863,23 +961,23 @@ pub fn parse_ou(
     // json template for one ou
     let mut ou_json = prepare_ou_json_template();
 
-    ou_json["Properties"]["domain"] = domain.to_uppercase().into();
-    ou_json["Properties"]["distinguishedname"] = result_dn.into();
+    set_property(&mut ou_json, "domain", domain.to_uppercase());
+    set_property(&mut ou_json, "distinguishedname", result_dn);
     // Check and replace value
     for (key, value) in &result_attrs {
         match key.as_str() {
             "name" => {
                 let name = &value[0];
                 let email = format!("{}@{}",name.to_owned(),domain);
-                ou_json["Properties"]["name"] = email.to_uppercase().into();
+                set_property(&mut ou_json, "name", email.to_uppercase());
             }
             "description" => {
-                ou_json["Properties"]["description"] = value[0].to_owned().into();
+                set_property(&mut ou_json, "description", value[0].to_owned());
             }
             "whenCreated" => {
                 let epoch = string_to_epoch(&value[0]);
                 if epoch.is_positive() {
-                    ou_json["Properties"]["whencreated"] = epoch.into();
+                    set_property(&mut ou_json, "whencreated", epoch);
                 }
             }
             "gPLink" => {
@@ -950,8 +1048,8 @@ pub fn parse_domain(
     sid_type: &mut HashMap<String, String>,
 ) -> serde_json::value::Value {
 
-    let _result_dn: String;
-    _result_dn = result.dn.to_uppercase();
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
 
     let result_attrs: HashMap<String, Vec<String>>;
     result_attrs = result.attrs;
@@ -960,7 +1058,7 @@ pub fn parse_domain(
     result_bin = result.bin_attrs;
 
     // Debug for current object
-    //debug!("Parse domain: {}", _result_dn);
+    //debug!("Parse domain: {}", result_dn);
     //for (key, value) in &result_attrs {
     //    trace!("  {:?}:{:?}", key, value);
     //}
@@ -980,28 +1078,21 @@ pub fn parse_domain(
         match key.as_str() {
             "distinguishedName" => {
                 // name & domain & distinguishedname
-                domain_json["Properties"]["distinguishedname"] = value[0].to_owned().to_uppercase().into();
-                let split = value[0].split(",");
-                let vec = split.collect::<Vec<&str>>();
-                let first = vec[0].split("DC=");
-                let vec1 = first.collect::<Vec<&str>>();
-                let last = vec[1].split("DC=");
-                let vec2 = last.collect::<Vec<&str>>();
-                let mut name = "".to_string();
-                name.push_str(vec1[1]);
-                name.push_str(".");
-                name.push_str(vec2[1]);
-                domain_json["Properties"]["name"] = name.to_uppercase().into();
-                domain_json["Properties"]["domain"] = name.to_uppercase().into();
+                set_property(&mut domain_json, "distinguishedname", value[0].to_owned().to_uppercase());
+                // All DC components, CHILD.ESSOS.LOCAL has three
+                let name = get_domain_from_dn(&value[0]);
+                set_property(&mut domain_json, "name", name.to_uppercase());
+                set_property(&mut domain_json, "domain", name.to_uppercase());
             }
             "msDS-Behavior-Version" => {
                 let level = get_forest_level(value[0].to_string());
-                domain_json["Properties"]["functionallevel"] = level.into();
+                set_property(&mut domain_json, "functionallevel", level);
+                set_property(&mut domain_json, "behaviorversion", value[0].parse::<i32>().unwrap_or(-1));
             }
             "whenCreated" => {
                 let epoch = string_to_epoch(&value[0]);
                 if epoch.is_positive() {
-                    domain_json["Properties"]["whencreated"] = epoch.into();
+                    set_property(&mut domain_json, "whencreated", epoch);
                 }
             }
             "gPLink" => {
@@ -1012,7 +1103,7 @@ pub fn parse_domain(
                 if value[0].contains("TRUE") {
                     iscriticalsystemobject = true;
                 }
-                domain_json["Properties"]["highvalue"] = iscriticalsystemobject.into();
+                set_property(&mut domain_json, "highvalue", iscriticalsystemobject);
             }
             // The number of computer accounts that a user is allowed to create in a domain.
             "ms-DS-MachineAccountQuota" => {
@@ -1024,7 +1115,10 @@ pub fn parse_domain(
             "IsDeleted" => {
                 domain_json["IsDeleted"] = true.into();
             }
-            _ => {}
+            // maxPwdAge, minPwdAge, lockoutDuration and lockOutObservationWindow
+            attribute => {
+                set_interval_property(&mut domain_json, &result_dn, attribute, &value[0]);
+            }
         }
     }
     // For all, bins attributs
@@ -1035,10 +1129,8 @@ pub fn parse_domain(
                 sid = sid_maker(LdapSid::parse(&value[0]).unwrap().1, domain);
                 domain_json["ObjectIdentifier"] = sid.to_owned().into();
 
-                let re = Regex::new(r"^S-[0-9]{1}-[0-9]{1}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}").unwrap();
-                for domain_sid in re.captures_iter(&sid) 
-                {
-                    domain_json["Properties"]["domainsid"] = domain_sid[0].to_owned().to_string().into();
+                if let Some(domain_sid) = get_domain_sid(&sid) {
+                    set_property(&mut domain_json, "domainsid", domain_sid);
                 }
             }
             "nTSecurityDescriptor" => {
@@ -1116,8 +1208,8 @@ pub fn parse_gpo(
 
     // json template for one gpo
     let mut gpo_json = prepare_gpo_json_template();
-    gpo_json["Properties"]["domain"] = domain.to_uppercase().into();
-    gpo_json["Properties"]["distinguishedname"] = result_dn.into();
+    set_property(&mut gpo_json, "domain", domain.to_uppercase());
+    set_property(&mut gpo_json, "distinguishedname", result_dn);
 
     // Check and replace value
     for (key, value) in &result_attrs {
@@ -1125,19 +1217,19 @@ pub fn parse_gpo(
             "displayName" => {
                 let name = &value[0];
                 let email = format!("{}@{}",name.to_owned(),domain);
-                gpo_json["Properties"]["name"] = email.to_uppercase().into();
+                set_property(&mut gpo_json, "name", email.to_uppercase());
             }
             "description" => {
-                gpo_json["Properties"]["description"] = value[0].to_owned().into();
+                set_property(&mut gpo_json, "description", value[0].to_owned());
             }
             "whenCreated" => {
                 let epoch = string_to_epoch(&value[0]);
                 if epoch.is_positive() {
-                    gpo_json["Properties"]["whencreated"] = epoch.into();
+                    set_property(&mut gpo_json, "whencreated", epoch);
                 }
             }
             "gPCFileSysPath" => {
-                gpo_json["Properties"]["gpcpath"] = value[0].to_owned().into();
+                set_property(&mut gpo_json, "gpcpath", value[0].to_owned());
             }
             "IsDeleted" => {
                 gpo_json["IsDeleted"] = true.into();
@@ -1222,7 +1314,7 @@ pub fn parse_fsp(
 
     // json template for one fsp
     let mut fsp_json = prepare_fsp_json_template();
-    fsp_json["Properties"]["distinguishedname"] = result_dn.into();
+    set_property(&mut fsp_json, "distinguishedname", result_dn);
 
     #[allow(unused_assignments)]
     let mut sid: String = "".to_owned();
@@ -1231,7 +1323,7 @@ pub fn parse_fsp(
         match key.as_str() {
             "name" => {
                 let name = format!("{}-{}",domain,&value[0]);
-                fsp_json["Properties"]["name"] = name.to_uppercase().into();
+                set_property(&mut fsp_json, "name", name.to_uppercase());
 
                 // Type for group Member maker
                 // based on https://docs.microsoft.com/fr-fr/troubleshoot/windows-server/identity/security-identifiers-in-windows
@@ -1240,15 +1332,15 @@ pub fn parse_fsp(
                 let len = vec.len();
                 let last = vec[len - 1].parse::<i32>().unwrap_or(0);
                 if last >= 17 {
-                    fsp_json["Properties"]["type"] = "User".into();
+                    set_property(&mut fsp_json, "type", "User");
                 } else {
-                    fsp_json["Properties"]["type"] = "Group".into();
+                    set_property(&mut fsp_json, "type", "Group");
                 }
             }
             "whenCreated" => {
                 let epoch = string_to_epoch(&value[0]);
                 if epoch.is_positive() {
-                    fsp_json["Properties"]["whencreated"] = epoch.into();
+                    set_property(&mut fsp_json, "whencreated", epoch);
                 }
             }
             "objectSid" => {
@@ -1257,10 +1349,8 @@ pub fn parse_fsp(
                 sid = sid_maker(LdapSid::parse(&vec_sid).unwrap().1, domain);
                 fsp_json["ObjectIdentifier"] = sid.to_owned().into();
 
-                let re = Regex::new(r"^S-[0-9]{1}-[0-9]{1}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}-[0-9]{1,}").unwrap();
-                for domain_sid in re.captures_iter(&sid) 
-                {
-                    fsp_json["Properties"]["domainsid"] = domain_sid[0].to_owned().to_string().into();
+                if let Some(domain_sid) = get_domain_sid(&sid) {
+                    set_property(&mut fsp_json, "domainsid", domain_sid);
                 }
             }
             "IsDeleted" => {
@@ -1324,8 +1414,8 @@ pub fn parse_container(
 
     // json template for one container
     let mut container_json = prepare_container_json_template();
-    container_json["Properties"]["domain"] = domain.to_owned().to_uppercase().into();
-    container_json["Properties"]["distinguishedname"] = result_dn.into();
+    set_property(&mut container_json, "domain", domain.to_owned().to_uppercase());
+    set_property(&mut container_json, "distinguishedname", result_dn);
 
     // With a check
     for (key, value) in &result_attrs {
@@ -1333,7 +1423,7 @@ pub fn parse_container(
             "name" => {
                 let name = &value[0];
                 let email = format!("{}@{}",name.to_owned(),domain);
-                container_json["Properties"]["name"] = email.to_uppercase().into();
+                set_property(&mut container_json, "name", email.to_uppercase());
             }
             _ => {}
         }
@@ -1383,6 +1473,106 @@ pub fn parse_container(
     return container_json;
 }
 
+/// Function to parse and replace value in json template for contact object.
+/// Contacts have no SID, they are identified by their objectGUID like containers.
+pub fn parse_contact(
+    result: SearchEntry,
+    domain: &String,
+    dn_sid: &mut HashMap<String, String>,
+    sid_type: &mut HashMap<String, String>,
+) -> serde_json::value::Value {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    let result_bin: HashMap<String, Vec<Vec<u8>>>;
+    result_bin = result.bin_attrs;
+
+    // Debug for current object
+    debug!("Parse Contact: {}", result_dn.to_uppercase());
+
+    // json template for one contact
+    let mut contact_json = prepare_contact_json_template();
+    set_property(&mut contact_json, "domain", domain.to_owned().to_uppercase());
+    set_property(&mut contact_json, "distinguishedname", result_dn);
+
+    // With a check
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "name" => {
+                let name = &value[0];
+                let email = format!("{}@{}",name.to_owned(),domain);
+                set_property(&mut contact_json, "name", email.to_uppercase());
+            }
+            "displayName" => {
+                set_property(&mut contact_json, "displayname", value[0].to_owned());
+            }
+            "description" => {
+                set_property(&mut contact_json, "description", value[0].to_owned());
+            }
+            "mail" => {
+                set_property(&mut contact_json, "email", value[0].to_owned());
+            }
+            "targetAddress" => {
+                set_property(&mut contact_json, "targetaddress", value[0].to_owned());
+            }
+            "whenCreated" => {
+                let epoch = string_to_epoch(&value[0]);
+                if epoch.is_positive() {
+                    set_property(&mut contact_json, "whencreated", epoch);
+                }
+            }
+            _ => {}
+        }
+    }
+    // For all, bins attributs
+    for (key, value) in &result_bin {
+        match key.as_str() {
+            "objectGUID" => {
+                let guid = decode_guid(&value[0]);
+                contact_json["ObjectIdentifier"] = guid.to_owned().into();
+            }
+            "nTSecurityDescriptor" => {
+                // Needed with acl
+                let entry_type = "contact".to_string();
+                // nTSecurityDescriptor raw to string
+                let relations_ace = parse_ntsecuritydescriptor(
+                    &mut contact_json,
+                    &value[0],
+                    entry_type,
+                    &result_attrs,
+                    &result_bin,
+                    &domain,
+                );
+                contact_json["Aces"] = relations_ace.into();
+            }
+            "IsDeleted" => {
+                contact_json["IsDeleted"] = true.into();
+            }
+            _ => {}
+        }
+    }
+
+    // Push DN and GUID in HashMap
+    dn_sid.insert(
+        contact_json["Properties"]["distinguishedname"]
+            .as_str()
+            .unwrap()
+            .to_string(),
+        contact_json["ObjectIdentifier"].as_str().unwrap().to_string(),
+    );
+    // Push GUID and Type, Base for the ChildObjects of OUs and containers
+    sid_type.insert(
+        contact_json["ObjectIdentifier"].as_str().unwrap().to_string(),
+        "Base".to_string(),
+    );
+
+    return contact_json;
+}
+
 /*****************************************
 ******************************************
 9- Function to parse trust domain values
@@ -1418,6 +1608,9 @@ pub fn parse_trust(result: SearchEntry, domain: &String) -> serde_json::value::V
             "name" => {
                 trust_json["TargetDomainName"] = value[0].to_uppercase().into();
             }
+            "flatName" => {
+                trust_json["TargetDomainNetBIOSName"] = value[0].to_uppercase().into();
+            }
             "trustDirection" => {
                 let trustdirection: u8 = value[0].parse::<u8>().unwrap_or(0);
                 // <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/5026a939-44ba-47b2-99cf-386a9e674b04>
@@ -1432,9 +1625,20 @@ pub fn parse_trust(result: SearchEntry, domain: &String) -> serde_json::value::V
                 let trustflag: u32 = value[0].parse::<u32>().unwrap_or(0);
                 get_trust_flag(trustflag, &mut trust_json);
             }
+            "msDS-SupportedEncryptionTypes" => {
+                let etypes = SupportedEncryptionTypes::parse(&value[0]);
+                trust_json["SupportedEncryptionTypes"] = etypes.names().into();
+                // Trusts without AES use RC4 for referral tickets
+                trust_json["RC4Only"] = (!etypes.has_aes()).into();
+            }
             _ => {}
         }
     }
+    // Without encryption types the trust keys are RC4 only
+    if trust_json["RC4Only"].is_null() {
+        trust_json["SupportedEncryptionTypes"] = serde_json::value::Value::Array(Vec::new());
+        trust_json["RC4Only"] = true.into();
+    }
     // For all, bins attributs
     for (key, value) in &result_bin {
         match key.as_str() {
@@ -1481,3 +1685,898 @@ pub fn parse_unknown(result: SearchEntry, _domain: &String) -> serde_json::value
 
     return unknown_json
 }
+
+
+/*****************************************
+******************************************
+11- Function to parse sites values
+******************************************
+*****************************************/
+/// Function to parse and replace value in json template for site object.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/c-site>
+pub fn parse_site(result: SearchEntry, domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    let result_bin: HashMap<String, Vec<Vec<u8>>>;
+    result_bin = result.bin_attrs;
+
+    // Debug for current object
+    debug!("Parse Site: {}", result_dn);
+
+    // json template for one site
+    let mut site_json = prepare_site_json_template();
+    set_property(&mut site_json, "domain", domain.to_uppercase());
+    set_property(&mut site_json, "distinguishedname", result_dn);
+
+    // With a check
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "name" => {
+                let name = format!("{}@{}",&value[0],domain);
+                set_property(&mut site_json, "name", name.to_uppercase());
+            }
+            "description" => {
+                set_property(&mut site_json, "description", value[0].to_owned());
+            }
+            "location" => {
+                set_property(&mut site_json, "location", value[0].to_owned());
+            }
+            "whenCreated" => {
+                let epoch = string_to_epoch(&value[0]);
+                if epoch.is_positive() {
+                    set_property(&mut site_json, "whencreated", epoch);
+                }
+            }
+            _ => {}
+        }
+    }
+    // For all, bins attributs
+    for (key, value) in &result_bin {
+        match key.as_str() {
+            "objectGUID" => {
+                let guid = decode_guid(&value[0]);
+                site_json["ObjectIdentifier"] = guid.to_owned().into();
+            }
+            _ => {}
+        }
+    }
+    return site_json
+}
+
+/*****************************************
+******************************************
+12- Function to parse subnets values
+******************************************
+*****************************************/
+/// Function to parse and replace value in json template for subnet object.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/c-subnet>
+pub fn parse_subnet(result: SearchEntry, _domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    let result_bin: HashMap<String, Vec<Vec<u8>>>;
+    result_bin = result.bin_attrs;
+
+    // Debug for current object
+    debug!("Parse Subnet: {}", result_dn);
+
+    // json template for one subnet
+    let mut subnet_json = prepare_subnet_json_template();
+    set_property(&mut subnet_json, "distinguishedname", result_dn);
+
+    // With a check
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "name" => {
+                set_property(&mut subnet_json, "name", value[0].to_owned());
+            }
+            "siteObject" => {
+                set_property(&mut subnet_json, "site", value[0].to_uppercase());
+            }
+            "description" => {
+                set_property(&mut subnet_json, "description", value[0].to_owned());
+            }
+            "location" => {
+                set_property(&mut subnet_json, "location", value[0].to_owned());
+            }
+            _ => {}
+        }
+    }
+    // For all, bins attributs
+    for (key, value) in &result_bin {
+        match key.as_str() {
+            "objectGUID" => {
+                let guid = decode_guid(&value[0]);
+                subnet_json["ObjectIdentifier"] = guid.to_owned().into();
+            }
+            _ => {}
+        }
+    }
+    return subnet_json
+}
+
+/*****************************************
+******************************************
+13- Function to parse site links values
+******************************************
+*****************************************/
+/// Function to parse and replace value in json template for site link object.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/c-sitelink>
+pub fn parse_sitelink(result: SearchEntry, _domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    let result_bin: HashMap<String, Vec<Vec<u8>>>;
+    result_bin = result.bin_attrs;
+
+    // Debug for current object
+    debug!("Parse SiteLink: {}", result_dn);
+
+    // json template for one site link
+    let mut sitelink_json = prepare_sitelink_json_template();
+    set_property(&mut sitelink_json, "distinguishedname", result_dn);
+
+    // With a check
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "name" => {
+                set_property(&mut sitelink_json, "name", value[0].to_uppercase());
+            }
+            "cost" => {
+                set_property(&mut sitelink_json, "cost", value[0].parse::<i32>().unwrap_or(100));
+            }
+            "replInterval" => {
+                set_property(&mut sitelink_json, "replinterval", value[0].parse::<i32>().unwrap_or(180));
+            }
+            "description" => {
+                set_property(&mut sitelink_json, "description", value[0].to_owned());
+            }
+            "siteList" => {
+                let sites: Vec<String> = value.iter().map(|site| site.to_uppercase()).collect();
+                sitelink_json["Sites"] = sites.into();
+            }
+            _ => {}
+        }
+    }
+    // For all, bins attributs
+    for (key, value) in &result_bin {
+        match key.as_str() {
+            "objectGUID" => {
+                let guid = decode_guid(&value[0]);
+                sitelink_json["ObjectIdentifier"] = guid.to_owned().into();
+            }
+            _ => {}
+        }
+    }
+    return sitelink_json
+}
+
+/*****************************************
+******************************************
+14- Function to parse schema values
+******************************************
+*****************************************/
+/// Function to get the schema version from the Schema naming context head.
+/// Return the properties to add in domain object.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/a-objectversion>
+pub fn parse_schema(result: SearchEntry, _domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    // Debug for current object
+    debug!("Parse Schema: {}", result_dn);
+
+    let mut schema_json = json!({});
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "objectVersion" => {
+                schema_json["schemaversion"] = value[0].parse::<i32>().unwrap_or(-1).into();
+                schema_json["schemaname"] = get_schema_version(value[0].to_string()).into();
+            }
+            _ => {}
+        }
+    }
+    return schema_json
+}
+
+/// Function to parse one attribute of the schema in a property set (attributeSecurityGUID),
+/// to make the attribute edges of the writes to this property set.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/property-sets>
+pub fn parse_attribute_schema(result: SearchEntry, _domain: &String) -> serde_json::value::Value  {
+
+    // Debug for current object
+    debug!("Parse attribute schema: {}", result.dn.to_uppercase());
+
+    // GUIDs with only valid UTF-8 bytes are in attrs
+    let get_guid = |attribute: &str| -> Option<String> {
+        let value = match result.bin_attrs.get(attribute) {
+            Some(value) => value.first()?.to_owned(),
+            None => result.attrs.get(attribute)?.first()?.as_bytes().to_vec(),
+        };
+        match value.len() {
+            16 => Some(decode_guid(&value).to_lowercase()),
+            _ => None,
+        }
+    };
+
+    json!({
+        "name": result.attrs.get("lDAPDisplayName").and_then(|name| name.first()),
+        "schemaidguid": get_guid("schemaIDGUID"),
+        "propertyset": get_guid("attributeSecurityGUID"),
+    })
+}
+
+/*****************************************
+******************************************
+15- Function to parse partitions values
+******************************************
+*****************************************/
+/// Function to get the forest functional level from the Partitions container.
+/// Return the properties to add in domain object.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/d7422d35-448a-451a-8846-6a7def0044df>
+pub fn parse_partitions(result: SearchEntry, _domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    // Debug for current object
+    debug!("Parse Partitions: {}", result_dn);
+
+    let mut partitions_json = json!({});
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "msDS-Behavior-Version" => {
+                partitions_json["forestfunctionallevel"] = get_forest_level(value[0].to_string()).into();
+                partitions_json["forestbehaviorversion"] = value[0].parse::<i32>().unwrap_or(-1).into();
+            }
+            _ => {}
+        }
+    }
+    return partitions_json
+}
+
+/*****************************************
+******************************************
+16- Function to parse service connection points
+******************************************
+*****************************************/
+/// Function to parse service connection points published by services under their computer object.
+/// Return the DN of the computer with the SCP values, used to find Entra Connect (DirSync) servers.
+/// <https://learn.microsoft.com/en-us/windows/win32/ad/publishing-with-service-connection-points>
+pub fn parse_scp(result: SearchEntry, _domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    // Debug for current object
+    debug!("Parse Service Connection Point: {}", result_dn);
+
+    // Parent object is the computer hosting the service
+    let parent_dn = match result_dn.split_once(',') {
+        Some((_, parent)) => parent.to_string(),
+        None => "".to_string(),
+    };
+
+    let mut scp_json = json!({
+        "name": result_dn.split(',').next().unwrap_or("").trim_start_matches("CN="),
+        "distinguishedname": result_dn,
+        "parent": parent_dn,
+        "serviceclassname": null,
+        "servicednsname": null,
+        "keywords": [],
+        "servicebindinginformation": [],
+    });
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "serviceClassName" => {
+                scp_json["serviceclassname"] = value[0].to_owned().into();
+            }
+            "serviceDNSName" => {
+                scp_json["servicednsname"] = value[0].to_owned().into();
+            }
+            "keywords" => {
+                scp_json["keywords"] = value.to_owned().into();
+            }
+            "serviceBindingInformation" => {
+                scp_json["servicebindinginformation"] = value.to_owned().into();
+            }
+            _ => {}
+        }
+    }
+    return scp_json
+}
+
+/// Function to parse printers published in the directory, the printQueue object is a child of the print server.
+pub fn parse_print_queue(result: SearchEntry, _domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    // Debug for current object
+    debug!("Parse Print Queue: {}", result_dn);
+
+    // Parent object is the print server
+    let parent_dn = match result_dn.split_once(',') {
+        Some((_, parent)) => parent.to_string(),
+        None => "".to_string(),
+    };
+
+    let mut printer_json = json!({
+        "name": result_dn.split(',').next().unwrap_or("").trim_start_matches("CN="),
+        "distinguishedname": result_dn,
+        "parent": parent_dn,
+        "printername": null,
+        "servername": null,
+        "uncname": null,
+        "location": null,
+        "drivername": null,
+        "portname": [],
+    });
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "printerName" => {
+                printer_json["printername"] = value[0].to_owned().into();
+            }
+            "serverName" => {
+                printer_json["servername"] = value[0].to_owned().into();
+            }
+            "uNCName" => {
+                printer_json["uncname"] = value[0].to_owned().into();
+            }
+            "location" => {
+                printer_json["location"] = value[0].to_owned().into();
+            }
+            "driverName" => {
+                printer_json["drivername"] = value[0].to_owned().into();
+            }
+            "portName" => {
+                printer_json["portname"] = value.to_owned().into();
+            }
+            _ => {}
+        }
+    }
+    return printer_json
+}
+
+/*****************************************
+******************************************
+17- Function to add Entra ID identifiers
+******************************************
+*****************************************/
+/// Function to add the identifiers needed to join users, groups and computers with AzureHound data.
+/// AzureHound objects have `onPremisesSecurityIdentifier` (our ObjectIdentifier), the Entra objectid
+/// written back in `msDS-ExternalDirectoryObjectId` and `onPremisesImmutableId` (base64 of the source anchor).
+/// <https://learn.microsoft.com/en-us/entra/identity/hybrid/connect/plan-connect-design-concepts#sourceanchor>
+pub fn add_entra_sync_ids(
+    object_json: &mut serde_json::value::Value,
+    result_attrs: &HashMap<String, Vec<String>>,
+    result_bin: &HashMap<String, Vec<Vec<u8>>>,
+) {
+    // User_<guid>, Group_<guid> or Device_<guid>
+    if let Some(value) = result_attrs.get("msDS-ExternalDirectoryObjectId") {
+        let objectid = match value[0].split_once('_') {
+            Some((_, guid)) => guid.to_lowercase(),
+            None => value[0].to_lowercase(),
+        };
+        set_property(object_json, "entraobjectid", objectid);
+        set_property(object_json, "onpremsynctarget", true);
+    }
+    // mS-DS-ConsistencyGuid is the default source anchor, objectGUID for old installations
+    if let Some(value) = result_bin.get("mS-DS-ConsistencyGuid") {
+        set_property(object_json, "onpremimmutableid", STANDARD.encode(&value[0]));
+        set_property(object_json, "onpremsynctarget", true);
+    } else if object_json["Properties"]["onpremsynctarget"] == true {
+        if let Some(value) = result_bin.get("objectGUID") {
+            set_property(object_json, "onpremimmutableid", STANDARD.encode(&value[0]));
+        }
+    }
+}
+
+/*****************************************
+******************************************
+18- Function to add RODC attributes
+******************************************
+*****************************************/
+/// Function to add the Password Replication Policy of one RODC computer.
+/// Principals in `msDS-RevealOnDemandGroup` and not in `msDS-NeverRevealGroup` can have their secrets cached on the RODC,
+/// `msDS-RevealedList` and `msDS-RevealedUsers` list the principals with secrets already cached.
+/// `managedBy` of a RODC is local administrator of this RODC, `msDS-KrbTgtLink` is its own krbtgt account.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/a-msds-revealedlist>
+pub fn add_rodc_attributes(
+    computer_json: &mut serde_json::value::Value,
+    result_attrs: &HashMap<String, Vec<String>>,
+) {
+    if computer_json["Properties"]["isrodc"] != true {
+        return
+    }
+    if let Some(value) = result_attrs.get("managedBy") {
+        set_property(computer_json, "managedby", value[0].to_uppercase());
+    }
+    // krbtgt_<number> account used by this RODC to sign its tickets
+    if let Some(value) = result_attrs.get("msDS-KrbTgtLink") {
+        set_property(computer_json, "krbtgtlink", value[0].to_uppercase());
+    }
+    if let Some(value) = result_attrs.get("msDS-RevealOnDemandGroup") {
+        let groups: Vec<String> = value.iter().map(|dn| dn.to_uppercase()).collect();
+        set_property(computer_json, "revealondemandgroups", groups);
+    }
+    if let Some(value) = result_attrs.get("msDS-NeverRevealGroup") {
+        let groups: Vec<String> = value.iter().map(|dn| dn.to_uppercase()).collect();
+        set_property(computer_json, "neverrevealgroups", groups);
+    }
+    // DN-String and DN-Binary values, same principal can be in both and several times (one value by attribute)
+    let mut revealed: Vec<String> = Vec::new();
+    for attribute in ["msDS-RevealedList", "msDS-RevealedUsers"] {
+        if let Some(value) = result_attrs.get(attribute) {
+            for dn_with_data in value {
+                let dn = get_dn_from_dn_with_data(dn_with_data).to_uppercase();
+                if !revealed.contains(&dn) {
+                    revealed.push(dn);
+                }
+            }
+        }
+    }
+    set_property(computer_json, "revealedusers", revealed);
+}
+
+/*****************************************
+******************************************
+19- Function to parse authentication policies and silos
+******************************************
+*****************************************/
+/// Function to parse one authentication policy (msDS-AuthNPolicy) or authentication policy silo (msDS-AuthNPolicySilo).
+/// Accounts are in one silo when they are in `msDS-AuthNPolicySiloMembers` and assigned to it (`msDS-AssignedAuthNPolicySilo`).
+/// <https://learn.microsoft.com/en-us/windows-server/security/credentials-protection-and-management/authentication-policies-and-authentication-policy-silos>
+pub fn parse_authn_policy(result: SearchEntry, domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    let result_bin: HashMap<String, Vec<Vec<u8>>>;
+    result_bin = result.bin_attrs;
+
+    // Debug for current object
+    debug!("Parse Authentication Policy: {}", result_dn);
+
+    let is_silo = result_attrs.get("objectClass").map(|classes| classes.iter().any(|class| class == "msDS-AuthNPolicySilo")).unwrap_or(false);
+    let mut authn_json = json!({
+        "objectid": result_bin.get("objectGUID").map(|value| decode_guid(&value[0])),
+        "name": result_dn.split(',').next().unwrap_or("").trim_start_matches("CN="),
+        "domain": domain.to_uppercase(),
+        "distinguishedname": result_dn,
+        "description": null,
+        "whencreated": -1,
+        "type": if is_silo { "silo" } else { "policy" },
+        "enforced": false,
+        "members": [],
+        "assigned": [],
+        "userpolicy": null,
+        "computerpolicy": null,
+        "servicepolicy": null,
+    });
+    let uppercase = |value: &Vec<String>| -> Vec<String> { value.iter().map(|dn| dn.to_uppercase()).collect() };
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "msDS-AuthNPolicySiloEnforced" | "msDS-AuthNPolicyEnforced" => {
+                authn_json["enforced"] = value[0].eq_ignore_ascii_case("TRUE").into();
+            }
+            "msDS-AuthNPolicySiloMembers" => {
+                authn_json["members"] = uppercase(value).into();
+            }
+            "msDS-AssignedAuthNPolicySiloBL" | "msDS-AssignedAuthNPolicyBL" => {
+                authn_json["assigned"] = uppercase(value).into();
+            }
+            "msDS-UserAuthNPolicy" => {
+                authn_json["userpolicy"] = value[0].to_uppercase().into();
+            }
+            "msDS-ComputerAuthNPolicy" => {
+                authn_json["computerpolicy"] = value[0].to_uppercase().into();
+            }
+            "msDS-ServiceAuthNPolicy" => {
+                authn_json["servicepolicy"] = value[0].to_uppercase().into();
+            }
+            "description" => {
+                authn_json["description"] = value[0].to_owned().into();
+            }
+            "whenCreated" => {
+                let epoch = string_to_epoch(&value[0]);
+                if epoch.is_positive() {
+                    authn_json["whencreated"] = epoch.into();
+                }
+            }
+            _ => {}
+        }
+    }
+    return authn_json
+}
+
+/*****************************************
+******************************************
+20- Function to parse cross-references
+******************************************
+*****************************************/
+/// Function to parse one cross-reference of the Partitions container.
+/// Domain cross-references give the NetBIOS name (`nETBIOSName`) of one DNS domain (`dnsRoot`), null for other naming contexts.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/c-crossref>
+pub fn parse_crossref(result: SearchEntry, _domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    // Debug for current object
+    debug!("Parse Cross-Reference: {}", result_dn);
+
+    let mut crossref_json = json!({
+        "dnsroot": null,
+        "netbiosname": null,
+        "ncname": null,
+    });
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "dnsRoot" => {
+                crossref_json["dnsroot"] = value[0].to_uppercase().into();
+            }
+            "nETBIOSName" => {
+                crossref_json["netbiosname"] = value[0].to_uppercase().into();
+            }
+            "nCName" => {
+                crossref_json["ncname"] = value[0].to_uppercase().into();
+            }
+            _ => {}
+        }
+    }
+    return crossref_json
+}
+
+/*****************************************
+******************************************
+21- Function to parse shadow principals
+******************************************
+*****************************************/
+/// Function to parse one shadow principal (msDS-ShadowPrincipal) of a bastion forest (PAM trust).
+/// Its members get the SID of one principal of the production forest (`msDS-ShadowPrincipalSid`) in their Kerberos tickets.
+/// Members with a time-to-live are returned as `<TTL=seconds>,<dn>` with the LDAP_SERVER_LINK_TTL control.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/c-msds-shadowprincipal>
+pub fn parse_shadow_principal(result: SearchEntry, domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    let result_bin: HashMap<String, Vec<Vec<u8>>>;
+    result_bin = result.bin_attrs;
+
+    // Debug for current object
+    debug!("Parse Shadow Principal: {}", result_dn);
+
+    let mut shadow_json = json!({
+        "name": result_dn.split(',').next().unwrap_or("").trim_start_matches("CN="),
+        "distinguishedname": result_dn,
+        "shadowsid": null,
+        "members": [],
+    });
+    if let Some(value) = result_attrs.get("member") {
+        let members: Vec<String> = value.iter()
+            .map(|member| match member.strip_prefix("<TTL=").and_then(|member| member.split_once(">,")) {
+                Some((_, dn)) => dn.to_uppercase(),
+                None => member.to_uppercase(),
+            })
+            .collect();
+        shadow_json["members"] = members.into();
+    }
+    if let Some(value) = result_bin.get("msDS-ShadowPrincipalSid") {
+        match LdapSid::parse(&value[0]) {
+            Ok((_, sid)) => shadow_json["shadowsid"] = sid_maker(sid, domain).into(),
+            Err(_) => report_parse_error(&result_dn, "msDS-ShadowPrincipalSid"),
+        }
+    }
+    return shadow_json
+}
+
+/*****************************************
+******************************************
+22- Function to parse claim types
+******************************************
+*****************************************/
+/// Function to parse and replace value in json template for claim type object (msDS-ClaimType).
+/// Claim types of `AD` source are issued in Kerberos tickets from one attribute of the users or computers they apply to,
+/// and can be used in the access conditions of authentication policies and in central access rules.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/c-msds-claimtype>
+pub fn parse_claim_type(result: SearchEntry, domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    let result_bin: HashMap<String, Vec<Vec<u8>>>;
+    result_bin = result.bin_attrs;
+
+    // Debug for current object
+    debug!("Parse Claim Type: {}", result_dn);
+
+    // json template for one claim type
+    // CN=User,CN=Schema,CN=Configuration,DC=ESSOS,DC=LOCAL => User
+    let rdn_value = |dn: &String| dn.split(',').next().unwrap_or("").trim_start_matches("CN=").to_string();
+    let mut claim_json = prepare_claim_type_json_template();
+    set_property(&mut claim_json, "name", format!("{}@{}", rdn_value(&result_dn), domain).to_uppercase());
+    set_property(&mut claim_json, "domain", domain.to_uppercase());
+    set_property(&mut claim_json, "distinguishedname", result_dn);
+
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "displayName" => {
+                set_property(&mut claim_json, "name", format!("{}@{}", &value[0], domain).to_uppercase());
+            }
+            "description" => {
+                set_property(&mut claim_json, "description", value[0].to_owned());
+            }
+            "Enabled" => {
+                set_property(&mut claim_json, "enabled", value[0].eq_ignore_ascii_case("TRUE"));
+            }
+            "msDS-ClaimSourceType" => {
+                set_property(&mut claim_json, "claimsourcetype", value[0].to_owned());
+            }
+            "msDS-ClaimAttributeSource" => {
+                set_property(&mut claim_json, "claimattributesource", rdn_value(&value[0]));
+            }
+            "msDS-ClaimTypeAppliesToClass" => {
+                set_property(&mut claim_json, "claimappliesto", value.iter().map(|dn| rdn_value(dn)).collect::<Vec<String>>());
+            }
+            "whenCreated" => {
+                let epoch = string_to_epoch(&value[0]);
+                if epoch.is_positive() {
+                    set_property(&mut claim_json, "whencreated", epoch);
+                }
+            }
+            _ => {}
+        }
+    }
+    // For all, bins attributs
+    for (key, value) in &result_bin {
+        match key.as_str() {
+            "objectGUID" => {
+                let guid = decode_guid(&value[0]);
+                claim_json["ObjectIdentifier"] = guid.to_owned().into();
+            }
+            _ => {}
+        }
+    }
+    return claim_json
+}
+
+/*****************************************
+******************************************
+23- Function to parse DFS namespaces
+******************************************
+*****************************************/
+/// Function to parse one domain-based DFS namespace (fTDfs for DFS 1.0, msDFS-Namespacev2) or DFS link (msDFS-Linkv2).
+/// They are stored in `CN=Dfs-Configuration,CN=System` and give the servers and shares behind `\\domain\namespace` paths.
+/// <https://learn.microsoft.com/en-us/windows/win32/adschema/c-msdfs-namespacev2>
+pub fn parse_dfs(result: SearchEntry, domain: &String) -> serde_json::value::Value  {
+
+    let result_dn: String;
+    result_dn = result.dn.to_uppercase();
+
+    let result_attrs: HashMap<String, Vec<String>>;
+    result_attrs = result.attrs;
+
+    let result_bin: HashMap<String, Vec<Vec<u8>>>;
+    result_bin = result.bin_attrs;
+
+    // Debug for current object
+    debug!("Parse DFS: {}", result_dn);
+
+    // CN=Public,CN=Dfs-Configuration,CN=System,DC=ESSOS,DC=LOCAL => Public
+    let rdn_values: Vec<&str> = result.dn.split(',').map(|rdn| rdn.trim_start_matches("CN=")).collect();
+    let mut dfs_json = prepare_share_json_template();
+    dfs_json["Type"] = "DfsNamespace".into();
+    dfs_json["Name"] = format!("\\\\{}\\{}", domain.to_lowercase(), rdn_values[0]).into();
+
+    let mut targets: Vec<String> = Vec::new();
+    for (key, value) in &result_attrs {
+        match key.as_str() {
+            "objectClass" => {
+                if value.iter().any(|class| class == "msDFS-Linkv2") {
+                    dfs_json["Type"] = "DfsLink".into();
+                }
+            }
+            // Root targets of DFS 1.0 namespaces, "*" is the placeholder value
+            "remoteServerName" => {
+                targets.extend(value.iter().filter(|target| target.as_str() != "*").cloned());
+            }
+            "msDFS-Commentv2" => {
+                dfs_json["Remark"] = value[0].to_owned().into();
+            }
+            _ => {}
+        }
+    }
+    // Link path is relative to its namespace: CN=<guid>,CN=Public,CN=Dfs-Configuration...
+    if dfs_json["Type"] == "DfsLink" {
+        let link_path = result_attrs.get("msDFS-LinkPathv2").map(|value| value[0].to_owned()).unwrap_or_default();
+        dfs_json["Name"] = format!("\\\\{}\\{}{}", domain.to_lowercase(), rdn_values.get(1).unwrap_or(&""), link_path.replace('/', "\\")).into();
+    }
+    // For all, bins attributs
+    for (key, value) in &result_bin {
+        match key.as_str() {
+            "objectGUID" => {
+                dfs_json["ObjectIdentifier"] = decode_guid(&value[0]).into();
+            }
+            "msDFS-TargetListv2" => {
+                targets.extend(get_dfs_targets(&value[0]));
+            }
+            _ => {}
+        }
+    }
+    dfs_json["Targets"] = targets.into();
+    return dfs_json
+}
+
+/// Function to get the UNC paths of one msDFS-TargetListv2 value, a UTF-16LE XML document:
+/// `<targets><target state="online">\\server\share</target></targets>`.
+pub fn get_dfs_targets(value: &[u8]) -> Vec<String> {
+    let chars: Vec<u16> = value.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    let xml = String::from_utf16_lossy(&chars);
+    let re = Regex::new(r"<target[^>]*>([^<]+)</target>").unwrap();
+    re.captures_iter(&xml).map(|target| target[1].trim().to_string()).collect()
+}
+
+/// Interval attributes of domains and their names in password settings objects (PSO) to property name.
+pub const INTERVAL_ATTRIBUTES: [(&str, &str); 8] = [
+    ("maxPwdAge", "maxpwdage"),
+    ("msDS-MaximumPasswordAge", "maxpwdage"),
+    ("minPwdAge", "minpwdage"),
+    ("msDS-MinimumPasswordAge", "minpwdage"),
+    ("lockoutDuration", "lockoutduration"),
+    ("msDS-LockoutDuration", "lockoutduration"),
+    ("lockOutObservationWindow", "lockoutobservationwindow"),
+    ("msDS-LockoutObservationWindow", "lockoutobservationwindow"),
+];
+
+/// Function to set the friendly duration and the duration in seconds (-1 for never) of one interval attribute.
+/// Return false if the attribute isn't an interval attribute.
+pub fn set_interval_property(object_json: &mut serde_json::value::Value, dn: &str, attribute: &str, value: &str) -> bool {
+    let property = match INTERVAL_ATTRIBUTES.iter().find(|(name, _)| name.eq_ignore_ascii_case(attribute)) {
+        Some((_, property)) => *property,
+        None => return false,
+    };
+    let interval = value.trim().parse::<i64>().ok();
+    match interval.and_then(interval_to_string) {
+        Some(duration) => {
+            let seconds = interval.and_then(interval_to_seconds).unwrap_or(-1);
+            set_property(object_json, property, duration);
+            set_property(object_json, &format!("{}seconds", property), seconds);
+        },
+        None => {
+            report_parse_error(dn, attribute);
+            set_property(object_json, property, serde_json::value::Value::Null);
+            set_property(object_json, &format!("{}seconds", property), serde_json::value::Value::Null);
+        },
+    }
+    true
+}
+
+/// Function to set the Kerberos encryption types properties of one user or computer from "msDS-SupportedEncryptionTypes".
+/// Without any encryption type the KDC uses the domain default, RC4 included, so only the names are set.
+/// DES can be enabled by the UseDesKeyOnly UAC flag too, desenabled is never set back to false.
+pub fn set_encryption_properties(object_json: &mut serde_json::value::Value, value: &str) {
+    let etypes = SupportedEncryptionTypes::parse(value);
+    set_property(object_json, "supportedencryptiontypes", etypes.names());
+    if etypes.is_default() {
+        return
+    }
+    let des_enabled = etypes.has_des() || object_json["Properties"]["desenabled"].as_bool().unwrap_or(false);
+    set_property(object_json, "desenabled", des_enabled);
+    set_property(object_json, "rc4only", etypes.has_rc4() && !etypes.has_aes());
+    set_property(object_json, "aesmissing", !etypes.has_aes());
+}
+
+/// Function to set the normalized operating system properties of one computer from "operatingSystem" and "operatingSystemVersion".
+pub fn set_os_properties(computer_json: &mut serde_json::value::Value) {
+    let name = computer_json["Properties"]["operatingsystem"].as_str().unwrap_or("").to_string();
+    if name.is_empty() {
+        return
+    }
+    let version = computer_json["Properties"]["operatingsystemversion"].as_str().unwrap_or("").to_string();
+    let os = OperatingSystem::parse(&name, &version);
+    set_property(computer_json, "osfamily", os.family);
+    set_property(computer_json, "osrelease", os.release);
+    if os.build > 0 {
+        set_property(computer_json, "osbuild", os.build as i64);
+    }
+    set_property(computer_json, "oseol", os.eol);
+}
+
+/// Function to set the SIDs of "sIDHistory" in `sidhistory`, invalid SIDs are skipped.
+pub fn set_sid_history(object_json: &mut serde_json::value::Value, value: &Vec<Vec<u8>>, domain: &String) {
+    let mut list_sid_history: Vec<String> = Vec::new();
+    for bsid in value {
+        match LdapSid::parse(bsid) {
+            Ok((_, sid)) => list_sid_history.push(sid_maker(sid, domain)),
+            Err(_) => {
+                trace!("Invalid sIDHistory value: {:?}", &bsid);
+                report_parse_error(object_json["Properties"]["distinguishedname"].as_str().unwrap_or(""), "sIDHistory");
+            },
+        }
+    }
+    set_property(object_json, "sidhistory", list_sid_history);
+}
+
+/// Function to get the DN from one Object(DN-String) `S:<len>:<string>:<dn>` or Object(DN-Binary) `B:<len>:<hex>:<dn>` value.
+pub fn get_dn_from_dn_with_data(value: &str) -> String {
+    if value.starts_with("S:") || value.starts_with("B:") {
+        if let Some(dn) = value.splitn(4, ':').nth(3) {
+            return dn.to_string()
+        }
+    }
+    value.to_string()
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_set_interval_property() {
+    let mut domain_json = json!({"Properties": {}});
+    assert!(set_interval_property(&mut domain_json, "DC=ESSOS,DC=LOCAL", "maxPwdAge", "-36288000000000"));
+    assert!(set_interval_property(&mut domain_json, "DC=ESSOS,DC=LOCAL", "msDS-LockoutDuration", "-18000000000"));
+    assert!(set_interval_property(&mut domain_json, "DC=ESSOS,DC=LOCAL", "minPwdAge", "-9223372036854775808"));
+    assert!(set_interval_property(&mut domain_json, "DC=ESSOS,DC=LOCAL", "lockOutObservationWindow", "18000000000"));
+    assert!(!set_interval_property(&mut domain_json, "DC=ESSOS,DC=LOCAL", "description", "-1"));
+    assert_eq!(domain_json["Properties"]["maxpwdage"], "42 days");
+    assert_eq!(domain_json["Properties"]["maxpwdageseconds"], 3628800);
+    assert_eq!(domain_json["Properties"]["lockoutduration"], "30 minutes");
+    assert_eq!(domain_json["Properties"]["minpwdage"], "Never");
+    assert_eq!(domain_json["Properties"]["minpwdageseconds"], -1);
+    assert_eq!(domain_json["Properties"]["lockoutobservationwindow"], serde_json::value::Value::Null);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_dn_from_dn_with_data() {
+    assert_eq!(get_dn_from_dn_with_data("B:8:0A000000:CN=Alice,CN=Users,DC=ESSOS,DC=LOCAL"), "CN=Alice,CN=Users,DC=ESSOS,DC=LOCAL");
+    assert_eq!(get_dn_from_dn_with_data("S:3:abc:CN=Bob,CN=Users,DC=ESSOS,DC=LOCAL"), "CN=Bob,CN=Users,DC=ESSOS,DC=LOCAL");
+    assert_eq!(get_dn_from_dn_with_data("CN=Bob,CN=Users,DC=ESSOS,DC=LOCAL"), "CN=Bob,CN=Users,DC=ESSOS,DC=LOCAL");
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_dfs_targets() {
+    let xml = "\u{feff}<?xml version=\"1.0\"?><targets><target state=\"online\">\\\\braavos\\public</target><target>\\\\meereen\\public</target></targets>";
+    let value: Vec<u8> = xml.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+    assert_eq!(get_dfs_targets(&value), vec!["\\\\braavos\\public".to_string(), "\\\\meereen\\public".to_string()]);
+    assert!(get_dfs_targets(&[]).is_empty());
+}
@@ -4,18 +4,26 @@ use regex::Regex;
 use indicatif::ProgressBar;
 use std::convert::TryInto;
 
-use log::info;
+use log::{info,debug};
 use crate::args::Options;
 use crate::banner::progress_bar;
+use crate::enums::acl::add_property_set_edges;
 use crate::enums::ldaptype::*;
 use crate::modules::adcs::parser::{parse_adcs_ca,parse_adcs_template};
+use crate::json::checker::bh_41::{add_authn_policies,add_entra_sync_scp_flag,add_shadow_principals,make_authn_nodes,make_service_inventory};
+use crate::enums::secdesc::LdapSid;
+use crate::enums::sid::sid_maker;
+use crate::enums::dn::get_domain_from_dn;
 
 pub mod bh_41;
+pub mod pipeline;
+pub mod plugin;
+pub mod schema;
 
 /// Function to get type for object by object
-pub fn parse_result_type(
+pub fn parse_result_type<I>(
     common_args: &Options, 
-    result: Vec<SearchEntry>,
+    result: I,
     vec_users: &mut Vec<serde_json::value::Value>,
     vec_groups: &mut Vec<serde_json::value::Value>,
     vec_computers: &mut Vec<serde_json::value::Value>,
@@ -24,9 +32,16 @@ pub fn parse_result_type(
     vec_gpos: &mut Vec<serde_json::value::Value>,
     vec_fsps: &mut Vec<serde_json::value::Value>,
     vec_containers: &mut Vec<serde_json::value::Value>,
+    vec_contacts: &mut Vec<serde_json::value::Value>,
     vec_trusts: &mut Vec<serde_json::value::Value>,
     vec_cas: &mut Vec<serde_json::value::Value>,
     vec_templates: &mut Vec<serde_json::value::Value>,
+    vec_sites: &mut Vec<serde_json::value::Value>,
+    vec_subnets: &mut Vec<serde_json::value::Value>,
+    vec_sitelinks: &mut Vec<serde_json::value::Value>,
+    vec_authn: &mut Vec<serde_json::value::Value>,
+    vec_shares: &mut Vec<serde_json::value::Value>,
+    vec_services: &mut Vec<serde_json::value::Value>,
 
     dn_sid: &mut HashMap<String, String>,
     sid_type: &mut HashMap<String, String>,
@@ -34,137 +49,25 @@ pub fn parse_result_type(
     fqdn_ip: &mut HashMap<String, String>,
     adcs_templates: &mut HashMap<String, Vec<String>>,
 )
+where
+    // Vec<SearchEntry> or entries read from the on-disk store
+    I: IntoIterator<Item = SearchEntry>,
+    I::IntoIter: ExactSizeIterator,
 {
-    // Domain name
-    let domain = &common_args.domain;
-
     // Needed for progress bar stats
     let pb = ProgressBar::new(1);
     let mut count = 0;
+    let result = result.into_iter();
     let total = result.len();
 
+    // Parser plugins for custom object classes
+    let plugins = plugin::has_parsers();
+
     info!("Starting the LDAP objects parsing...");
+    let mut parsed: Vec<(Type, serde_json::value::Value)> = Vec::with_capacity(total);
     for entry in result {
-        // Start parsing with Type matching
-        let cloneresult = entry.clone();
-        //println!("{:?}",&entry);
-        let atype = get_type(entry).unwrap_or(Type::Unknown);
-        match atype {
-            Type::User => {
-                let user = parse_user(
-                    cloneresult,
-                    domain,
-                    dn_sid,
-                    sid_type,
-                    common_args.adcs,
-                );
-                vec_users.push(user);
-            }
-            Type::Group => {
-                let group = parse_group(
-                    cloneresult,
-                    domain,
-                    dn_sid,
-                    sid_type,
-                );
-                vec_groups.push(group);
-            }
-            Type::Computer => {
-                let computer = parse_computer(
-                    cloneresult,
-                    domain,
-                    dn_sid,
-                    sid_type,
-                    fqdn_sid,
-                    fqdn_ip,
-                );
-                vec_computers.push(computer);
-            }
-            Type::Ou => {
-                let ou = parse_ou(
-                    cloneresult,
-                    domain,
-                    dn_sid,
-                    sid_type,
-                );
-                vec_ous.push(ou);
-            }
-            Type::Domain => {
-                let domain = parse_domain(
-                    cloneresult,
-                    domain,
-                    dn_sid,
-                    sid_type,
-                );
-                vec_domains.push(domain);
-            }
-            Type::Gpo => {
-                let gpo = parse_gpo(
-                    cloneresult,
-                    domain,
-                    dn_sid,
-                    sid_type,
-                );
-                vec_gpos.push(gpo);
-            }
-            Type::ForeignSecurityPrincipal => {
-                let security_principal = parse_fsp(
-                    cloneresult,
-                    domain,
-                    dn_sid,
-                    sid_type,
-                );
-                vec_fsps.push(security_principal);
-            }
-            Type::Container => {
-                let re = Regex::new(r"[0-9a-z-A-Z]{1,}-[0-9a-z-A-Z]{1,}-[0-9a-z-A-Z]{1,}-[0-9a-z-A-Z]{1,}").unwrap();
-                if re.is_match(&cloneresult.dn.to_uppercase()) 
-                {
-                    //trace!("Container not to add: {}",&cloneresult.dn.to_uppercase());
-                    continue
-                }
-                let re = Regex::new(r"CN=DOMAINUPDATES,CN=SYSTEM,").unwrap();
-                if re.is_match(&cloneresult.dn.to_uppercase()) 
-                {
-                    //trace!("Container not to add: {}",&cloneresult.dn.to_uppercase());
-                    continue
-                }
-                //trace!("Container: {}",&cloneresult.dn.to_uppercase());
-                let container = parse_container(
-                    cloneresult,
-                    domain,
-                    dn_sid,
-                    sid_type,
-                );
-                vec_containers.push(container);
-            }
-            Type::Trust => {
-                let trust = parse_trust(
-                    cloneresult,
-                    domain
-                );
-                vec_trusts.push(trust);
-            }
-            Type::AdcsAuthority => {
-                let adcs_ca = parse_adcs_ca(
-                    cloneresult.to_owned(),
-                    domain,
-                    adcs_templates,
-                    common_args.old_bloodhound,
-                );
-                vec_cas.push(adcs_ca); 
-            }
-            Type::AdcsTemplate => {
-                let adcs_template = parse_adcs_template(
-                    cloneresult.to_owned(),
-                    domain,
-                    common_args.old_bloodhound,
-                );
-                vec_templates.push(adcs_template);
-            }
-            Type::Unknown => {
-                let _unknown = parse_unknown(cloneresult, domain);
-            }
+        if let Some(object) = parse_entry(common_args, entry, plugins, dn_sid, sid_type, fqdn_sid, fqdn_ip, adcs_templates) {
+            parsed.push(object);
         }
         // Manage progress bar
         // Pourcentage (%) = 100 x Valeur partielle/Valeur totale
@@ -173,9 +76,394 @@ pub fn parse_result_type(
         progress_bar(pb.to_owned(),"Parsing LDAP objects".to_string(),pourcentage.try_into().unwrap(),"%".to_string());
     }
     pb.finish_and_clear();
+
+    add_parsed_objects(
+        parsed,
+        vec_users,
+        vec_groups,
+        vec_computers,
+        vec_ous,
+        vec_domains,
+        vec_gpos,
+        vec_fsps,
+        vec_containers,
+        vec_contacts,
+        vec_trusts,
+        vec_cas,
+        vec_templates,
+        vec_sites,
+        vec_subnets,
+        vec_sitelinks,
+        vec_authn,
+        vec_shares,
+        vec_services,
+    );
+    info!("Parsing LDAP objects finished!");
+}
+
+/// Function to get the objects parsed by the pipeline workers while the LDAP entries were received,
+/// same outputs as [`parse_result_type`].
+pub fn parse_pipeline_result(
+    pipeline: pipeline::ParsePipeline,
+    vec_users: &mut Vec<serde_json::value::Value>,
+    vec_groups: &mut Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    vec_ous: &mut Vec<serde_json::value::Value>,
+    vec_domains: &mut Vec<serde_json::value::Value>,
+    vec_gpos: &mut Vec<serde_json::value::Value>,
+    vec_fsps: &mut Vec<serde_json::value::Value>,
+    vec_containers: &mut Vec<serde_json::value::Value>,
+    vec_contacts: &mut Vec<serde_json::value::Value>,
+    vec_trusts: &mut Vec<serde_json::value::Value>,
+    vec_cas: &mut Vec<serde_json::value::Value>,
+    vec_templates: &mut Vec<serde_json::value::Value>,
+    vec_sites: &mut Vec<serde_json::value::Value>,
+    vec_subnets: &mut Vec<serde_json::value::Value>,
+    vec_sitelinks: &mut Vec<serde_json::value::Value>,
+    vec_authn: &mut Vec<serde_json::value::Value>,
+    vec_shares: &mut Vec<serde_json::value::Value>,
+    vec_services: &mut Vec<serde_json::value::Value>,
+
+    dn_sid: &mut HashMap<String, String>,
+    sid_type: &mut HashMap<String, String>,
+    fqdn_sid: &mut HashMap<String, String>,
+    fqdn_ip: &mut HashMap<String, String>,
+    adcs_templates: &mut HashMap<String, Vec<String>>,
+)
+{
+    let parsed = pipeline.finish();
+    dn_sid.extend(parsed.dn_sid);
+    sid_type.extend(parsed.sid_type);
+    fqdn_sid.extend(parsed.fqdn_sid);
+    fqdn_ip.extend(parsed.fqdn_ip);
+    adcs_templates.extend(parsed.adcs_templates);
+
+    add_parsed_objects(
+        parsed.objects.into_iter().map(|(_, atype, object)| (atype, object)),
+        vec_users,
+        vec_groups,
+        vec_computers,
+        vec_ous,
+        vec_domains,
+        vec_gpos,
+        vec_fsps,
+        vec_containers,
+        vec_contacts,
+        vec_trusts,
+        vec_cas,
+        vec_templates,
+        vec_sites,
+        vec_subnets,
+        vec_sitelinks,
+        vec_authn,
+        vec_shares,
+        vec_services,
+    );
     info!("Parsing LDAP objects finished!");
 }
 
+/// Function to parse one LDAP entry with the parser of its type.
+/// None for the entries not kept: unknown types, DomainUpdates and GUID named containers.
+pub fn parse_entry(
+    common_args: &Options,
+    entry: SearchEntry,
+    plugins: bool,
+    dn_sid: &mut HashMap<String, String>,
+    sid_type: &mut HashMap<String, String>,
+    fqdn_sid: &mut HashMap<String, String>,
+    fqdn_ip: &mut HashMap<String, String>,
+    adcs_templates: &mut HashMap<String, Vec<String>>,
+) -> Option<(Type, serde_json::value::Value)>
+{
+    // Domain name
+    let domain = &common_args.domain;
+
+    if plugins {
+        plugin::run_parsers(&entry, domain);
+    }
+    // Start parsing with Type matching
+    let cloneresult = entry.clone();
+    //println!("{:?}",&entry);
+    let atype = get_type(entry).unwrap_or(Type::Unknown);
+    let object = match atype {
+        Type::User => parse_user(
+            cloneresult,
+            domain,
+            dn_sid,
+            sid_type,
+            common_args.adcs,
+        ),
+        Type::Group => parse_group(
+            cloneresult,
+            domain,
+            dn_sid,
+            sid_type,
+        ),
+        Type::Computer => parse_computer(
+            cloneresult,
+            domain,
+            dn_sid,
+            sid_type,
+            fqdn_sid,
+            fqdn_ip,
+        ),
+        Type::Ou => parse_ou(
+            cloneresult,
+            domain,
+            dn_sid,
+            sid_type,
+        ),
+        Type::Domain => parse_domain(
+            cloneresult,
+            domain,
+            dn_sid,
+            sid_type,
+        ),
+        Type::Gpo => parse_gpo(
+            cloneresult,
+            domain,
+            dn_sid,
+            sid_type,
+        ),
+        Type::ForeignSecurityPrincipal => parse_fsp(
+            cloneresult,
+            domain,
+            dn_sid,
+            sid_type,
+        ),
+        Type::Container => {
+            let re = Regex::new(r"[0-9a-z-A-Z]{1,}-[0-9a-z-A-Z]{1,}-[0-9a-z-A-Z]{1,}-[0-9a-z-A-Z]{1,}").unwrap();
+            if re.is_match(&cloneresult.dn.to_uppercase()) 
+            {
+                //trace!("Container not to add: {}",&cloneresult.dn.to_uppercase());
+                return None
+            }
+            let re = Regex::new(r"CN=DOMAINUPDATES,CN=SYSTEM,").unwrap();
+            if re.is_match(&cloneresult.dn.to_uppercase()) 
+            {
+                //trace!("Container not to add: {}",&cloneresult.dn.to_uppercase());
+                return None
+            }
+            //trace!("Container: {}",&cloneresult.dn.to_uppercase());
+            parse_container(
+                cloneresult,
+                domain,
+                dn_sid,
+                sid_type,
+            )
+        }
+        Type::Contact => parse_contact(
+            cloneresult,
+            domain,
+            dn_sid,
+            sid_type,
+        ),
+        Type::Trust => parse_trust(
+            cloneresult,
+            domain
+        ),
+        Type::AdcsAuthority => parse_adcs_ca(
+            cloneresult.to_owned(),
+            domain,
+            adcs_templates,
+            common_args.old_bloodhound,
+        ),
+        Type::AdcsTemplate => parse_adcs_template(
+            cloneresult.to_owned(),
+            domain,
+            common_args.old_bloodhound,
+        ),
+        Type::Site => parse_site(
+            cloneresult,
+            domain,
+        ),
+        Type::Subnet => parse_subnet(
+            cloneresult,
+            domain,
+        ),
+        Type::SiteLink => parse_sitelink(
+            cloneresult,
+            domain,
+        ),
+        Type::Schema => parse_schema(
+            cloneresult,
+            domain,
+        ),
+        Type::Partitions => parse_partitions(
+            cloneresult,
+            domain,
+        ),
+        Type::CrossRef => parse_crossref(
+            cloneresult,
+            domain,
+        ),
+        Type::ServiceConnectionPoint => parse_scp(
+            cloneresult,
+            domain,
+        ),
+        Type::PrintQueue => parse_print_queue(
+            cloneresult,
+            domain,
+        ),
+        Type::AuthNPolicySilo | Type::AuthNPolicy => parse_authn_policy(
+            cloneresult,
+            domain,
+        ),
+        Type::ShadowPrincipal => parse_shadow_principal(
+            cloneresult,
+            domain,
+        ),
+        Type::ClaimType => parse_claim_type(
+            cloneresult,
+            domain,
+        ),
+        Type::Dfs => parse_dfs(
+            cloneresult,
+            domain,
+        ),
+        Type::AttributeSchema => parse_attribute_schema(
+            cloneresult,
+            domain,
+        ),
+        Type::Unknown => {
+            let _unknown = parse_unknown(cloneresult, domain);
+            return None
+        }
+    };
+    Some((atype, object))
+}
+
+/// Function to add the parsed objects in the vector of their type, in the order of the LDAP entries,
+/// then the nodes of parser plugins, the Entra Connect flags and the forest properties.
+pub fn add_parsed_objects<I>(
+    parsed: I,
+    vec_users: &mut Vec<serde_json::value::Value>,
+    vec_groups: &mut Vec<serde_json::value::Value>,
+    vec_computers: &mut Vec<serde_json::value::Value>,
+    vec_ous: &mut Vec<serde_json::value::Value>,
+    vec_domains: &mut Vec<serde_json::value::Value>,
+    vec_gpos: &mut Vec<serde_json::value::Value>,
+    vec_fsps: &mut Vec<serde_json::value::Value>,
+    vec_containers: &mut Vec<serde_json::value::Value>,
+    vec_contacts: &mut Vec<serde_json::value::Value>,
+    vec_trusts: &mut Vec<serde_json::value::Value>,
+    vec_cas: &mut Vec<serde_json::value::Value>,
+    vec_templates: &mut Vec<serde_json::value::Value>,
+    vec_sites: &mut Vec<serde_json::value::Value>,
+    vec_subnets: &mut Vec<serde_json::value::Value>,
+    vec_sitelinks: &mut Vec<serde_json::value::Value>,
+    vec_authn: &mut Vec<serde_json::value::Value>,
+    vec_shares: &mut Vec<serde_json::value::Value>,
+    vec_services: &mut Vec<serde_json::value::Value>,
+)
+where
+    I: IntoIterator<Item = (Type, serde_json::value::Value)>,
+{
+    // Forest properties from Configuration and Schema naming contexts
+    let mut forest_properties: Vec<serde_json::value::Value> = Vec::new();
+    // Service connection points to find services hosted by computers
+    let mut vec_scps: Vec<serde_json::value::Value> = Vec::new();
+    // Printers published in the directory by print servers
+    let mut vec_printers: Vec<serde_json::value::Value> = Vec::new();
+    // Authentication policies and silos applied to users and computers
+    let mut authn_policies: Vec<serde_json::value::Value> = Vec::new();
+    // Shadow principals of a bastion forest giving SIDs of the production forest
+    let mut vec_shadows: Vec<serde_json::value::Value> = Vec::new();
+    // NetBIOS names of the forest domains
+    let mut vec_crossrefs: Vec<serde_json::value::Value> = Vec::new();
+    // Attributes of each property set from the schema
+    let mut property_sets: HashMap<String, Vec<String>> = HashMap::new();
+
+    for (atype, object) in parsed {
+        match atype {
+            Type::User => vec_users.push(object),
+            Type::Group => vec_groups.push(object),
+            Type::Computer => vec_computers.push(object),
+            Type::Ou => vec_ous.push(object),
+            Type::Domain => vec_domains.push(object),
+            Type::Gpo => vec_gpos.push(object),
+            Type::ForeignSecurityPrincipal => vec_fsps.push(object),
+            Type::Container => vec_containers.push(object),
+            Type::Contact => vec_contacts.push(object),
+            Type::Trust => vec_trusts.push(object),
+            Type::AdcsAuthority => vec_cas.push(object),
+            Type::AdcsTemplate => vec_templates.push(object),
+            Type::Site => vec_sites.push(object),
+            Type::Subnet => vec_subnets.push(object),
+            Type::SiteLink => vec_sitelinks.push(object),
+            Type::Schema | Type::Partitions => forest_properties.push(object),
+            Type::CrossRef => vec_crossrefs.push(object),
+            Type::ServiceConnectionPoint => vec_scps.push(object),
+            Type::PrintQueue => vec_printers.push(object),
+            Type::AuthNPolicySilo | Type::AuthNPolicy => authn_policies.push(object),
+            Type::ClaimType => vec_authn.push(object),
+            Type::ShadowPrincipal => vec_shadows.push(object),
+            Type::Dfs => vec_shares.push(object),
+            Type::AttributeSchema => add_property_set_attribute(&mut property_sets, &object),
+            Type::Unknown => {}
+        }
+    }
+
+    // Add nodes and ACEs from parser plugins
+    if plugin::has_parsers() {
+        vec_users.append(&mut plugin::take_nodes("users"));
+        vec_groups.append(&mut plugin::take_nodes("groups"));
+        vec_computers.append(&mut plugin::take_nodes("computers"));
+        vec_ous.append(&mut plugin::take_nodes("ous"));
+        vec_gpos.append(&mut plugin::take_nodes("gpos"));
+        vec_containers.append(&mut plugin::take_nodes("containers"));
+        for vec_objects in [
+            &mut *vec_users, &mut *vec_groups, &mut *vec_computers, &mut *vec_ous,
+            &mut *vec_domains, &mut *vec_gpos, &mut *vec_containers,
+        ] {
+            plugin::add_plugin_aces(vec_objects);
+        }
+    }
+
+    // Attribute edges of the writes to property sets, like Personal-Information
+    let mut count = 0;
+    count += add_property_set_edges(vec_users, "user", &property_sets);
+    count += add_property_set_edges(vec_groups, "group", &property_sets);
+    count += add_property_set_edges(vec_computers, "computer", &property_sets);
+    debug!("{} edges made from writes to property sets", count);
+
+    // Flag Entra Connect servers from their DirSync service connection point
+    add_entra_sync_scp_flag(vec_computers, &vec_scps);
+
+    // Inventory of the services and printers with their hosting computer
+    vec_services.append(&mut make_service_inventory(&vec_scps, &vec_printers, vec_computers));
+
+    // Add authentication policies and silos of users and computers
+    add_authn_policies(vec_users, vec_computers, &authn_policies);
+    vec_authn.append(&mut make_authn_nodes(vec_users, vec_computers, &authn_policies));
+
+    // Add SIDs of the production forest given by shadow principals
+    add_shadow_principals(vec_users, vec_computers, vec_groups, &vec_shadows);
+
+    // Add NetBIOS names in domain objects
+    for crossref in vec_crossrefs.iter().filter(|crossref| crossref["netbiosname"].is_string()) {
+        for domain_json in vec_domains.iter_mut().filter(|domain_json| domain_json["Properties"]["name"] == crossref["dnsroot"]) {
+            schema::set_property(domain_json, "netbiosname", crossref["netbiosname"].to_owned());
+        }
+    }
+
+    // Add forest properties in domain objects
+    for properties in forest_properties {
+        for domain_json in vec_domains.iter_mut() {
+            for (key, value) in properties.as_object().unwrap() {
+                domain_json["Properties"][key] = schema::coerce_property(key, value.to_owned());
+            }
+        }
+    }
+}
+
+
+/// Function to add one attribute of the schema in the list of its property set.
+fn add_property_set_attribute(property_sets: &mut HashMap<String, Vec<String>>, attribute: &serde_json::value::Value) {
+    if let (Some(property_set), Some(guid)) = (attribute["propertyset"].as_str(), attribute["schemaidguid"].as_str()) {
+        property_sets.entry(property_set.to_string()).or_insert_with(Vec::new).push(guid.to_string());
+    }
+}
 
 /// Parse user. Select parser based on BH version.
 pub fn parse_user(
@@ -260,6 +548,16 @@ pub fn parse_container(
     bh_41::parse_container(result, domain, dn_sid, sid_type)
 }
 
+/// Parse Contact object. Select parser based on BH version.
+pub fn parse_contact(
+    result: SearchEntry,
+    domain: &String,
+    dn_sid: &mut HashMap<String, String>,
+    sid_type: &mut HashMap<String, String>,
+) -> serde_json::value::Value {
+    bh_41::parse_contact(result, domain, dn_sid, sid_type)
+}
+
 /// Parse Trust domain object. Select parser based on BH version.
 pub fn parse_trust(
     result: SearchEntry, 
@@ -268,10 +566,208 @@ pub fn parse_trust(
     bh_41::parse_trust(result, _domain)
 }
 
+/// Parse site object. Select parser based on BH version.
+pub fn parse_site(
+    result: SearchEntry, 
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_site(result, domain)
+}
+
+/// Parse subnet object. Select parser based on BH version.
+pub fn parse_subnet(
+    result: SearchEntry, 
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_subnet(result, domain)
+}
+
+/// Parse site link object. Select parser based on BH version.
+pub fn parse_sitelink(
+    result: SearchEntry, 
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_sitelink(result, domain)
+}
+
+/// Parse schema naming context head. Select parser based on BH version.
+pub fn parse_schema(
+    result: SearchEntry, 
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_schema(result, domain)
+}
+
+/// Parse partitions container. Select parser based on BH version.
+pub fn parse_partitions(
+    result: SearchEntry, 
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_partitions(result, domain)
+}
+
+/// Parse cross-reference. Select parser based on BH version.
+pub fn parse_crossref(
+    result: SearchEntry,
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_crossref(result, domain)
+}
+
+/// Parse service connection point. Select parser based on BH version.
+pub fn parse_scp(
+    result: SearchEntry,
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_scp(result, domain)
+}
+
+/// Parse published printer. Select parser based on BH version.
+pub fn parse_print_queue(
+    result: SearchEntry,
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_print_queue(result, domain)
+}
+
+/// Parse authentication policy or silo. Select parser based on BH version.
+pub fn parse_authn_policy(
+    result: SearchEntry,
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_authn_policy(result, domain)
+}
+
+/// Parse shadow principal of a bastion forest. Select parser based on BH version.
+pub fn parse_shadow_principal(
+    result: SearchEntry,
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_shadow_principal(result, domain)
+}
+
+/// Parse claim type. Select parser based on BH version.
+pub fn parse_claim_type(
+    result: SearchEntry,
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_claim_type(result, domain)
+}
+
+/// Parse attribute of the schema in a property set. Select parser based on BH version.
+pub fn parse_attribute_schema(
+    result: SearchEntry,
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_attribute_schema(result, domain)
+}
+
+/// Parse DFS namespace or link. Select parser based on BH version.
+pub fn parse_dfs(
+    result: SearchEntry,
+    domain: &String,
+) -> serde_json::value::Value {
+    bh_41::parse_dfs(result, domain)
+}
+
 /// Parse unknown object. Select parser based on BH version.
 pub fn parse_unknown(
     result: SearchEntry, 
     _domain: &String,
 ) -> serde_json::value::Value {
     bh_41::parse_unknown(result, _domain)
-}
\ No newline at end of file
+}
+/// Parse universal groups of other domains from the Global Catalog with at least one member in the collected domain.
+/// Without them, memberships of this domain in groups like Enterprise Admins (collected from a child domain) are missing.
+/// Only the Global Catalog attributes are known, so these groups don't have ACEs.
+pub fn parse_universal_groups(
+    referred: &[SearchEntry],
+    domain: &String,
+    dn_sid: &mut HashMap<String, String>,
+    sid_type: &mut HashMap<String, String>,
+) -> Vec<serde_json::value::Value> {
+    let mut vec_groups: Vec<serde_json::value::Value> = Vec::new();
+    for entry in referred {
+        // ADS_GROUP_TYPE_UNIVERSAL_GROUP
+        let universal = entry.attrs.get("groupType")
+            .and_then(|value| value[0].parse::<i64>().ok())
+            .map(|group_type| group_type & 0x8 != 0)
+            .unwrap_or(false);
+        let has_member = entry.attrs.get("member")
+            .map(|members| members.iter().any(|member| get_domain_from_dn(member).eq_ignore_ascii_case(domain)))
+            .unwrap_or(false);
+        if !universal || !has_member {
+            continue
+        }
+        let group_domain = get_domain_from_dn(&entry.dn).to_uppercase();
+        vec_groups.push(parse_group(entry.to_owned(), &group_domain, dn_sid, sid_type));
+    }
+    info!("{} universal groups of other domains with members in {}", vec_groups.len(), domain.to_uppercase());
+    vec_groups
+}
+
+/// Add principals from followed referrals in DN to SID and SID to Type maps.
+/// They are not written in json files, only used to resolve cross-domain linked attributes (member, manager).
+/// Universal groups of other domains are written with [`parse_universal_groups`] when the Global Catalog is requested.
+pub fn add_referred_principals(
+    referred: Vec<SearchEntry>,
+    dn_sid: &mut HashMap<String, String>,
+    sid_type: &mut HashMap<String, String>,
+) {
+    let total = referred.len();
+    for entry in referred {
+        let dn = entry.dn.to_uppercase();
+        // DC=CHILD,DC=DOMAIN,DC=LOCAL => CHILD.DOMAIN.LOCAL
+        let domain = get_domain_from_dn(&dn);
+        let sid = match entry.bin_attrs.get("objectSid").and_then(|value| LdapSid::parse(&value[0]).ok()) {
+            Some((_, sid)) => sid_maker(sid, &domain),
+            None => continue,
+        };
+        let classes: Vec<String> = entry.attrs.get("objectClass").unwrap_or(&Vec::new()).iter().map(|class| class.to_lowercase()).collect();
+        let object_type = if classes.contains(&"computer".to_string()) {
+            "Computer"
+        } else if classes.contains(&"group".to_string()) {
+            "Group"
+        } else if classes.contains(&"user".to_string()) {
+            "User"
+        } else if classes.contains(&"domain".to_string()) {
+            "Domain"
+        } else {
+            continue
+        };
+        dn_sid.entry(dn).or_insert(sid.to_owned());
+        sid_type.entry(sid).or_insert(object_type.to_string());
+    }
+    debug!("{} principals from other domains added", total);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_universal_groups() {
+    // S-1-5-21-1-2-3-519
+    let sid = vec![1u8, 5, 0, 0, 0, 0, 0, 5, 21, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 7, 2, 0, 0];
+    let group = |dn: &str, group_type: &str, member: &str| SearchEntry {
+        dn: dn.to_string(),
+        attrs: HashMap::from([
+            ("objectClass".to_string(), vec!["top".to_string(), "group".to_string()]),
+            ("name".to_string(), vec!["Enterprise Admins".to_string()]),
+            ("groupType".to_string(), vec![group_type.to_string()]),
+            ("member".to_string(), vec![member.to_string()]),
+        ]),
+        bin_attrs: HashMap::from([("objectSid".to_string(), vec![sid.to_owned()])]),
+    };
+    let referred = vec![
+        group("CN=Enterprise Admins,CN=Users,DC=SEVENKINGDOMS,DC=LOCAL", "-2147483640", "CN=eddard.stark,CN=Users,DC=NORTH,DC=SEVENKINGDOMS,DC=LOCAL"),
+        // Global group, members are not in the Global Catalog
+        group("CN=Domain Admins,CN=Users,DC=SEVENKINGDOMS,DC=LOCAL", "-2147483646", "CN=eddard.stark,CN=Users,DC=NORTH,DC=SEVENKINGDOMS,DC=LOCAL"),
+        // Universal group without member in the collected domain
+        group("CN=Schema Admins,CN=Users,DC=SEVENKINGDOMS,DC=LOCAL", "-2147483640", "CN=Administrator,CN=Users,DC=SEVENKINGDOMS,DC=LOCAL"),
+    ];
+    let mut dn_sid = HashMap::new();
+    let mut sid_type = HashMap::new();
+    let vec_groups = parse_universal_groups(&referred, &"NORTH.SEVENKINGDOMS.LOCAL".to_string(), &mut dn_sid, &mut sid_type);
+    assert_eq!(vec_groups.len(), 1);
+    assert_eq!(vec_groups[0]["ObjectIdentifier"], "S-1-5-21-1-2-3-519");
+    assert_eq!(vec_groups[0]["Properties"]["name"], "ENTERPRISE ADMINS@SEVENKINGDOMS.LOCAL");
+    assert_eq!(vec_groups[0]["Members"][0]["ObjectIdentifier"], "CN=EDDARD.STARK,CN=USERS,DC=NORTH,DC=SEVENKINGDOMS,DC=LOCAL");
+}
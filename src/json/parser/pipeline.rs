@@ -0,0 +1,164 @@
+//! Parser worker pool fed while the LDAP entries are received
+//!
+//! The collection is a fetch → parse → write pipeline: every LDAP entry is sent in a bounded channel as soon as
+//! it is received and a pool of workers parses them (security descriptors, JSON objects) on all cores while the
+//! next pages are fetched. The channel is bounded so a slow parsing slows the fetch down instead of keeping
+//! every entry in memory. Entries are numbered so the objects keep the LDAP order whatever the worker.
+//!
+//! Disabled with **--parse-workers 0** or **--disk-store**, entries are then parsed after the collection.
+use ldap3::SearchEntry;
+use log::{info,debug,error};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use crate::args::Options;
+use crate::enums::ldaptype::Type;
+use crate::json::parser::{parse_entry, plugin};
+use crate::json::stats::mark_partial;
+
+/// Entries waiting in the channel for each worker
+const CHANNEL_BOUND_PER_WORKER: usize = 256;
+
+/// Sender of the LDAP entries with their number in the collection.
+pub type EntrySender = SyncSender<(usize, SearchEntry)>;
+
+/// Objects and maps made by the workers.
+#[derive(Default)]
+pub struct ParsedEntries {
+    /// Entry number, type and object
+    pub objects: Vec<(usize, Type, serde_json::value::Value)>,
+    pub dn_sid: HashMap<String, String>,
+    pub sid_type: HashMap<String, String>,
+    pub fqdn_sid: HashMap<String, String>,
+    pub fqdn_ip: HashMap<String, String>,
+    pub adcs_templates: HashMap<String, Vec<String>>,
+}
+
+impl ParsedEntries {
+    /// Function to add the objects and maps of another worker.
+    fn merge(&mut self, other: ParsedEntries) {
+        self.objects.extend(other.objects);
+        self.dn_sid.extend(other.dn_sid);
+        self.sid_type.extend(other.sid_type);
+        self.fqdn_sid.extend(other.fqdn_sid);
+        self.fqdn_ip.extend(other.fqdn_ip);
+        self.adcs_templates.extend(other.adcs_templates);
+    }
+}
+
+/// Pool of parser workers waiting for LDAP entries.
+pub struct ParsePipeline {
+    sender: EntrySender,
+    workers: Vec<JoinHandle<ParsedEntries>>,
+}
+
+impl ParsePipeline {
+    /// Function to start the workers, they stop once every sender is dropped.
+    pub fn start(common_args: &Options, workers: usize) -> Self {
+        // Parser plugins are registered before the collection
+        let plugins = plugin::has_parsers();
+        let common_args = Arc::new(common_args.to_owned());
+        info!("Parsing LDAP objects with {} workers while they are received...", workers.max(1));
+        ParsePipeline::start_with(workers, move |entry, parsed| parse_entry(
+            &common_args,
+            entry,
+            plugins,
+            &mut parsed.dn_sid,
+            &mut parsed.sid_type,
+            &mut parsed.fqdn_sid,
+            &mut parsed.fqdn_ip,
+            &mut parsed.adcs_templates,
+        ))
+    }
+
+    /// Function to start the workers with one parser of LDAP entries.
+    pub fn start_with<F>(workers: usize, parse: F) -> Self
+    where
+        F: Fn(SearchEntry, &mut ParsedEntries) -> Option<(Type, serde_json::value::Value)> + Send + Sync + 'static,
+    {
+        let workers = workers.max(1);
+        let (sender, receiver) = sync_channel(CHANNEL_BOUND_PER_WORKER * workers);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let parse = Arc::new(parse);
+        let workers = (0..workers)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                let parse = Arc::clone(&parse);
+                thread::spawn(move || run_worker(&receiver, &*parse))
+            })
+            .collect();
+        ParsePipeline { sender, workers }
+    }
+
+    /// Function to get one sender of LDAP entries, numbered from 0 in the order they are received.
+    pub fn sender(&self) -> EntrySender {
+        self.sender.to_owned()
+    }
+
+    /// Function to wait for the workers, every other sender must be dropped before.
+    /// Objects are sorted back in the order of the LDAP entries.
+    pub fn finish(self) -> ParsedEntries {
+        let ParsePipeline { sender, workers } = self;
+        drop(sender);
+        let mut parsed = ParsedEntries::default();
+        for worker in workers {
+            match worker.join() {
+                Ok(output) => parsed.merge(output),
+                Err(_) => {
+                    error!("One LDAP objects parser worker panicked, some objects are missing!");
+                    mark_partial("LDAP objects parser worker panicked".to_string());
+                }
+            }
+        }
+        parsed.objects.sort_by_key(|(index, _, _)| *index);
+        debug!("{} LDAP objects parsed by the workers", parsed.objects.len());
+        parsed
+    }
+}
+
+/// Function to parse the LDAP entries of the channel until it's closed.
+fn run_worker<F>(receiver: &Mutex<Receiver<(usize, SearchEntry)>>, parse: &F) -> ParsedEntries
+where
+    F: Fn(SearchEntry, &mut ParsedEntries) -> Option<(Type, serde_json::value::Value)>,
+{
+    let mut parsed = ParsedEntries::default();
+    loop {
+        // The lock is released before parsing so the other workers get the next entries
+        let next = receiver.lock().map(|receiver| receiver.recv());
+        let (index, entry) = match next {
+            Ok(Ok(next)) => next,
+            _ => break,
+        };
+        if let Some((atype, object)) = parse(entry, &mut parsed) {
+            parsed.objects.push((index, atype, object));
+        }
+    }
+    parsed
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_pipeline() {
+    let pipeline = ParsePipeline::start_with(4, |entry, parsed| {
+        parsed.dn_sid.insert(entry.dn.to_owned(), entry.attrs["name"][0].to_owned());
+        // Odd entries are not kept
+        if entry.attrs["name"][0].ends_with(&['1', '3', '5', '7', '9'][..]) {
+            return None
+        }
+        Some((Type::Ou, serde_json::json!({"Properties": {"name": entry.attrs["name"][0]}})))
+    });
+    let sender = pipeline.sender();
+    for index in 0..1000 {
+        let mut attrs = HashMap::new();
+        attrs.insert("name".to_string(), vec![index.to_string()]);
+        sender.send((index, SearchEntry { dn: format!("OU={},DC=ESSOS,DC=LOCAL", index), attrs, bin_attrs: HashMap::new() })).unwrap();
+    }
+    drop(sender);
+    let parsed = pipeline.finish();
+    assert_eq!(parsed.dn_sid.len(), 1000);
+    assert_eq!(parsed.objects.len(), 500);
+    assert!(parsed.objects.iter().enumerate().all(|(position, (index, _, object))| *index == position * 2 && object["Properties"]["name"] == index.to_string()));
+}
@@ -0,0 +1,161 @@
+//! Parser plugins for custom LDAP object classes
+//!
+//! Downstream users can register an [`ObjectParser`] to handle custom or third-party object classes
+//! (vendor PAM products, ...) without forking the dispatcher in `parse_result_type`.
+//! Every registered parser sees every LDAP entry after the built-in parser and can emit extra nodes and ACEs.
+//!
+//! ```ignore
+//! struct VaultParser;
+//! impl ObjectParser for VaultParser {
+//!    fn name(&self) -> &str { "vaults" }
+//!    fn matches(&self, entry: &SearchEntry) -> bool { has_object_class(entry, "vendorVault") }
+//!    fn parse(&self, entry: &SearchEntry, domain: &String) -> Result<Vec<PluginOutput>> { ... }
+//! }
+//! register_parser(Box::new(VaultParser));
+//! ```
+use lazy_static::lazy_static;
+use ldap3::SearchEntry;
+use log::{debug,error};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::errors::Result;
+use crate::json::parser::schema::coerce_properties;
+
+/// Trait for custom object class parsers.
+pub trait ObjectParser: Send + Sync {
+   /// Parser name, used in logs and as default output file name for its nodes.
+   fn name(&self) -> &str;
+   /// Return true if this parser handles the LDAP entry.
+   fn matches(&self, entry: &SearchEntry) -> bool;
+   /// Parse the LDAP entry and return the nodes and ACEs to add.
+   fn parse(&self, entry: &SearchEntry, domain: &String) -> Result<Vec<PluginOutput>>;
+}
+
+/// Values emitted by a parser plugin.
+pub enum PluginOutput {
+   /// New node written in `<kind>.json` file, `kind` can be a built-in one like "computers".
+   Node {
+      kind: String,
+      object: serde_json::value::Value,
+   },
+   /// New ACE added to the object with this ObjectIdentifier.
+   Ace {
+      object_identifier: String,
+      ace: serde_json::value::Value,
+   },
+}
+
+lazy_static! {
+   /// Registered parsers
+   static ref PARSERS: Mutex<Vec<Box<dyn ObjectParser>>> = Mutex::new(Vec::new());
+   /// Nodes emitted by parsers, by kind
+   static ref NODES: Mutex<HashMap<String, Vec<serde_json::value::Value>>> = Mutex::new(HashMap::new());
+   /// ACEs emitted by parsers, by ObjectIdentifier
+   static ref ACES: Mutex<HashMap<String, Vec<serde_json::value::Value>>> = Mutex::new(HashMap::new());
+}
+
+/// Register a new parser, must be called before `parse_result_type`.
+pub fn register_parser(parser: Box<dyn ObjectParser>) {
+   debug!("Registering parser plugin {}",parser.name());
+   PARSERS.lock().unwrap().push(parser);
+}
+
+/// Check if the LDAP entry has the object class (case insensitive).
+pub fn has_object_class(entry: &SearchEntry, class: &str) -> bool {
+   entry.attrs.get("objectClass")
+      .map(|classes| classes.iter().any(|c| c.eq_ignore_ascii_case(class)))
+      .unwrap_or(false)
+}
+
+/// Run all registered parsers matching the LDAP entry.
+pub fn run_parsers(entry: &SearchEntry, domain: &String) {
+   let parsers = PARSERS.lock().unwrap();
+   for parser in parsers.iter() {
+      if !parser.matches(entry) {
+         continue
+      }
+      match parser.parse(entry, domain) {
+         Ok(outputs) => {
+            for output in outputs {
+               match output {
+                  PluginOutput::Node { kind, mut object } => {
+                     // Plugins don't use the properties schema
+                     coerce_properties(&mut object);
+                     NODES.lock().unwrap().entry(kind).or_insert_with(Vec::new).push(object);
+                  }
+                  PluginOutput::Ace { object_identifier, ace } => {
+                     ACES.lock().unwrap().entry(object_identifier).or_insert_with(Vec::new).push(ace);
+                  }
+               }
+            }
+         }
+         Err(err) => error!("Parser plugin {} failed: {}",parser.name(),err),
+      }
+   }
+}
+
+/// Return true if at least one parser is registered.
+pub fn has_parsers() -> bool {
+   !PARSERS.lock().unwrap().is_empty()
+}
+
+/// Take the nodes emitted by parsers for this kind.
+pub fn take_nodes(kind: &str) -> Vec<serde_json::value::Value> {
+   NODES.lock().unwrap().remove(kind).unwrap_or_default()
+}
+
+/// Take all nodes emitted by parsers for custom kinds.
+pub fn take_all_nodes() -> HashMap<String, Vec<serde_json::value::Value>> {
+   std::mem::take(&mut *NODES.lock().unwrap())
+}
+
+/// Add the ACEs emitted by parsers to the objects.
+pub fn add_plugin_aces(vec_objects: &mut Vec<serde_json::value::Value>) {
+   let mut aces = ACES.lock().unwrap();
+   if aces.is_empty() {
+      return
+   }
+   for object in vec_objects.iter_mut() {
+      let identifier = object["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+      if let Some(new_aces) = aces.remove(&identifier) {
+         if let Some(object_aces) = object["Aces"].as_array_mut() {
+            object_aces.extend(new_aces);
+         }
+      }
+   }
+}
+
+#[cfg(test)]
+struct TestParser;
+
+#[cfg(test)]
+impl ObjectParser for TestParser {
+   fn name(&self) -> &str {
+      "vaults"
+   }
+   fn matches(&self, entry: &SearchEntry) -> bool {
+      has_object_class(entry, "vendorVault")
+   }
+   fn parse(&self, entry: &SearchEntry, _domain: &String) -> Result<Vec<PluginOutput>> {
+      Ok(vec![
+         PluginOutput::Node { kind: "vaults".to_string(), object: serde_json::json!({"Properties": {"distinguishedname": entry.dn}}) },
+         PluginOutput::Ace { object_identifier: "S-1-5-21-1-2-3-500".to_string(), ace: serde_json::json!({"RightName": "GenericAll"}) },
+      ])
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_plugin_parser() {
+   register_parser(Box::new(TestParser));
+   let mut attrs = HashMap::new();
+   attrs.insert("objectClass".to_string(), vec!["top".to_string(), "vendorVault".to_string()]);
+   let entry = SearchEntry { dn: "CN=VAULT01,DC=ESSOS,DC=LOCAL".to_string(), attrs, bin_attrs: HashMap::new() };
+   run_parsers(&entry, &"ESSOS.LOCAL".to_string());
+
+   assert_eq!(take_nodes("vaults").len(), 1);
+   let mut users = vec![serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-500", "Aces": []})];
+   add_plugin_aces(&mut users);
+   assert_eq!(users[0]["Aces"][0]["RightName"], "GenericAll");
+}
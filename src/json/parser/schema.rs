@@ -0,0 +1,230 @@
+//! Typed properties of the BloodHound objects
+//!
+//! Every property set by the parser goes through [`set_property`], the value is converted to the JSON type
+//! BloodHound expects for this property name in [`PROPERTY_SCHEMA`], so Cypher queries comparing
+//! numbers or booleans don't silently break on a string like `"1"`.
+//! Properties not in the schema are kept as they are.
+use std::convert::TryFrom;
+
+use crate::enums::date::convert_timestamp;
+
+/// JSON type of one BloodHound property.
+#[derive(Debug, PartialEq)]
+pub enum PropertyType {
+    String,
+    /// 32 bits integer
+    Int,
+    /// 64 bits integer
+    Long,
+    Bool,
+    /// Array of strings
+    Array,
+    /// Unix timestamp in seconds, -1 if never set
+    Epoch,
+}
+
+/// Property name to JSON type.
+pub const PROPERTY_SCHEMA: [(&str, PropertyType); 116] = [
+    ("name", PropertyType::String),
+    ("domain", PropertyType::String),
+    ("domainsid", PropertyType::String),
+    ("netbiosname", PropertyType::String),
+    ("distinguishedname", PropertyType::String),
+    ("samaccountname", PropertyType::String),
+    ("description", PropertyType::String),
+    ("displayname", PropertyType::String),
+    ("email", PropertyType::String),
+    ("userprincipalname", PropertyType::String),
+    ("title", PropertyType::String),
+    ("department", PropertyType::String),
+    ("company", PropertyType::String),
+    ("manager", PropertyType::String),
+    ("managedby", PropertyType::String),
+    ("homedirectory", PropertyType::String),
+    ("logonscript", PropertyType::String),
+    ("userpassword", PropertyType::String),
+    ("unixpassword", PropertyType::String),
+    ("unicodepassword", PropertyType::String),
+    ("sfupassword", PropertyType::String),
+    ("operatingsystem", PropertyType::String),
+    ("operatingsystemversion", PropertyType::String),
+    ("osfamily", PropertyType::String),
+    ("osrelease", PropertyType::String),
+    ("osbuild", PropertyType::Int),
+    ("oseol", PropertyType::Bool),
+    ("gpcpath", PropertyType::String),
+    ("functionallevel", PropertyType::String),
+    ("forestfunctionallevel", PropertyType::String),
+    ("schemaname", PropertyType::String),
+    ("type", PropertyType::String),
+    ("location", PropertyType::String),
+    ("site", PropertyType::String),
+    ("creatorsid", PropertyType::String),
+    ("creatorname", PropertyType::String),
+    ("krbtgtlink", PropertyType::String),
+    ("rodcname", PropertyType::String),
+    ("entraobjectid", PropertyType::String),
+    ("onpremimmutableid", PropertyType::String),
+    ("smbdialect", PropertyType::String),
+    ("winrmversion", PropertyType::String),
+    ("rdpcertcn", PropertyType::String),
+    ("authnpolicysilo", PropertyType::String),
+    ("authnpolicy", PropertyType::String),
+    ("maxpwdage", PropertyType::String),
+    ("minpwdage", PropertyType::String),
+    ("lockoutduration", PropertyType::String),
+    ("lockoutobservationwindow", PropertyType::String),
+    ("samaccounttype", PropertyType::Long),
+    ("maxpwdageseconds", PropertyType::Long),
+    ("minpwdageseconds", PropertyType::Long),
+    ("lockoutdurationseconds", PropertyType::Long),
+    ("lockoutobservationwindowseconds", PropertyType::Long),
+    ("directreports", PropertyType::Int),
+    ("behaviorversion", PropertyType::Int),
+    ("forestbehaviorversion", PropertyType::Int),
+    ("schemaversion", PropertyType::Int),
+    ("cost", PropertyType::Int),
+    ("replinterval", PropertyType::Int),
+    ("enabled", PropertyType::Bool),
+    ("admincount", PropertyType::Bool),
+    ("highvalue", PropertyType::Bool),
+    ("hasspn", PropertyType::Bool),
+    ("haslaps", PropertyType::Bool),
+    ("sensitive", PropertyType::Bool),
+    ("dontreqpreauth", PropertyType::Bool),
+    ("passwordnotreqd", PropertyType::Bool),
+    ("pwdneverexpires", PropertyType::Bool),
+    ("trustaccount", PropertyType::Bool),
+    ("trustedtoauth", PropertyType::Bool),
+    ("unconstraineddelegation", PropertyType::Bool),
+    ("blocksinheritance", PropertyType::Bool),
+    ("isrodc", PropertyType::Bool),
+    ("precreated", PropertyType::Bool),
+    ("onpremsynctarget", PropertyType::Bool),
+    ("smbsigningrequired", PropertyType::Bool),
+    ("spooleravailable", PropertyType::Bool),
+    ("webclientavailable", PropertyType::Bool),
+    ("psremote_reachable", PropertyType::Bool),
+    ("wmi_reachable", PropertyType::Bool),
+    ("rdpavailable", PropertyType::Bool),
+    ("rdpnlarequired", PropertyType::Bool),
+    ("gpppassword", PropertyType::Bool),
+    ("coercetotgt", PropertyType::Bool),
+    ("samaccountnameanomaly", PropertyType::Bool),
+    ("isaclprotected", PropertyType::Bool),
+    ("desenabled", PropertyType::Bool),
+    ("rc4only", PropertyType::Bool),
+    ("aesmissing", PropertyType::Bool),
+    ("protecteduser", PropertyType::Bool),
+    ("dormant", PropertyType::Bool),
+    ("authnpolicysiloenforced", PropertyType::Bool),
+    ("authnpolicyenforced", PropertyType::Bool),
+    ("allowedtodelegate", PropertyType::Array),
+    ("serviceprincipalnames", PropertyType::Array),
+    ("supportedencryptiontypes", PropertyType::Array),
+    ("sidhistory", PropertyType::Array),
+    ("sidhistorysamedomain", PropertyType::Bool),
+    ("sidhistoryprivileged", PropertyType::Bool),
+    ("shadowprincipals", PropertyType::Array),
+    ("enforced", PropertyType::Bool),
+    ("claimsourcetype", PropertyType::String),
+    ("claimattributesource", PropertyType::String),
+    ("claimappliesto", PropertyType::Array),
+    ("conflictobject", PropertyType::Bool),
+    ("orphanedmembers", PropertyType::Array),
+    ("revealedusers", PropertyType::Array),
+    ("revealondemandgroups", PropertyType::Array),
+    ("neverrevealgroups", PropertyType::Array),
+    ("whencreated", PropertyType::Epoch),
+    ("lastlogon", PropertyType::Epoch),
+    ("lastlogontimestamp", PropertyType::Epoch),
+    ("pwdlastset", PropertyType::Epoch),
+    ("krbtgtpasswordlastset", PropertyType::Epoch),
+    ("lastpasswordchange", PropertyType::Epoch),
+];
+
+/// Function to get the JSON type of one property, None if not in the schema.
+pub fn get_property_type(name: &str) -> Option<&'static PropertyType> {
+    PROPERTY_SCHEMA.iter().find(|(property, _)| *property == name).map(|(_, property_type)| property_type)
+}
+
+/// Function to set one property of one object with the JSON type of the schema.
+pub fn set_property<T: Into<serde_json::value::Value>>(object: &mut serde_json::value::Value, name: &str, value: T) {
+    object["Properties"][name] = coerce_property(name, value.into());
+}
+
+/// Function to convert all the properties of one object, used for objects not made by the parser (plugins, forest properties).
+pub fn coerce_properties(object: &mut serde_json::value::Value) {
+    if let Some(properties) = object["Properties"].as_object_mut() {
+        for (name, value) in properties.iter_mut() {
+            *value = coerce_property(name, value.take());
+        }
+    }
+}
+
+/// Function to convert one property value to the JSON type of the schema.
+/// Null stays null (not collected), values which can't be converted are replaced by null.
+pub fn coerce_property(name: &str, value: serde_json::value::Value) -> serde_json::value::Value {
+    use serde_json::value::Value;
+    let property_type = match get_property_type(name) {
+        Some(property_type) => property_type,
+        None => return value,
+    };
+    match (property_type, value) {
+        (_, Value::Null) => Value::Null,
+        (PropertyType::String, Value::String(value)) => value.into(),
+        (PropertyType::String, Value::Number(value)) => value.to_string().into(),
+        (PropertyType::String, Value::Bool(value)) => value.to_string().into(),
+        (PropertyType::Int, Value::Number(value)) => value.as_i64().and_then(|value| i32::try_from(value).ok()).map(Value::from).unwrap_or(Value::Null),
+        (PropertyType::Int, Value::String(value)) => value.trim().parse::<i32>().map(Value::from).unwrap_or(Value::Null),
+        (PropertyType::Long, Value::Number(value)) => value.as_i64().map(Value::from).unwrap_or(Value::Null),
+        (PropertyType::Long, Value::String(value)) => value.trim().parse::<i64>().map(Value::from).unwrap_or(Value::Null),
+        (PropertyType::Bool, Value::Bool(value)) => value.into(),
+        (PropertyType::Bool, Value::Number(value)) => (value.as_i64().unwrap_or(0) != 0).into(),
+        (PropertyType::Bool, Value::String(value)) => match value.trim().to_lowercase().as_str() {
+            "true" | "1" => true.into(),
+            "false" | "0" => false.into(),
+            _ => Value::Null,
+        },
+        (PropertyType::Array, Value::Array(values)) => values.into_iter()
+            .filter(|value| !value.is_null())
+            .map(|value| match value {
+                Value::String(value) => Value::String(value),
+                value => Value::String(value.to_string()),
+            })
+            .collect::<Vec<Value>>()
+            .into(),
+        (PropertyType::Array, Value::String(value)) => vec![value].into(),
+        (PropertyType::Epoch, Value::Number(value)) => value.as_i64().map(Value::from).unwrap_or(Value::Null),
+        (PropertyType::Epoch, Value::String(value)) => match value.trim().parse::<i64>() {
+            // Windows FILETIME, 100 nanoseconds since 1601
+            Ok(value) if value > 100_000_000_000_000 => convert_timestamp(value).into(),
+            Ok(value) => value.into(),
+            Err(_) => Value::Null,
+        },
+        (_, _) => Value::Null,
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_coerce_property() {
+    use serde_json::json;
+    assert_eq!(coerce_property("admincount", json!("1")), json!(true));
+    assert_eq!(coerce_property("enabled", json!("FALSE")), json!(false));
+    assert_eq!(coerce_property("samaccounttype", json!("805306368")), json!(805306368));
+    assert_eq!(coerce_property("behaviorversion", json!("7")), json!(7));
+    assert_eq!(coerce_property("behaviorversion", json!("unknown")), json!(null));
+    assert_eq!(coerce_property("serviceprincipalnames", json!("HTTP/WEB")), json!(["HTTP/WEB"]));
+    assert_eq!(coerce_property("whencreated", json!("1700000000")), json!(1700000000));
+    assert_eq!(coerce_property("pwdlastset", json!("133444736000000000")), json!(1700000000));
+    assert_eq!(coerce_property("description", json!(null)), json!(null));
+    assert_eq!(coerce_property("domain", json!(42)), json!("42"));
+    assert_eq!(coerce_property("vendorattribute", json!("1")), json!("1"));
+
+    let mut object = json!({"Properties": {"highvalue": "true", "name": "JON@NORTH.LOCAL"}});
+    set_property(&mut object, "directreports", 3usize);
+    coerce_properties(&mut object);
+    assert_eq!(object["Properties"]["highvalue"], true);
+    assert_eq!(object["Properties"]["directreports"], 3);
+}
@@ -0,0 +1,219 @@
+//! Custom query packs run against collected .json files (`rusthound query`)
+//!
+//! A pack is a TOML file of declarative queries, each one matching the collected objects of one type
+//! with property predicates and an optional nested group membership, so teams can keep their own checks
+//! without Neo4j:
+//!
+//! ```toml
+//! [[query]]
+//! name = "Kerberoastable admins"
+//! description = "Privileged users with a SPN"
+//! type = "User"
+//! where = [
+//!    { property = "hasspn", op = "eq", value = true },
+//!    { property = "enabled", op = "eq", value = true },
+//! ]
+//! memberof = "Domain Admins"
+//! ```
+//!
+//! Operators: `eq`, `ne`, `gt`, `lt`, `contains` (strings and arrays, case insensitive) and `exists`.
+use log::{info,warn};
+use colored::Colorize;
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+
+use crate::args::QueryOptions;
+use crate::enums::sid::get_well_known_rid;
+use crate::json::graph::load_directory;
+use crate::json::report::membership::MembershipResolver;
+
+/// Operators of the `where` predicates.
+const OPERATORS: [&str; 6] = ["eq", "ne", "gt", "lt", "contains", "exists"];
+
+/// Function to parse one TOML query pack, queries are returned as JSON values.
+pub fn parse_pack(content: &str) -> std::result::Result<Vec<serde_json::value::Value>, String>
+{
+   let pack: toml::Value = toml::from_str(content).map_err(|err| err.to_string())?;
+   let pack = serde_json::to_value(pack).map_err(|err| err.to_string())?;
+   let queries = pack["query"].as_array().cloned().unwrap_or_default();
+   if queries.is_empty() {
+      return Err("no [[query]] table".to_string())
+   }
+   for (i, query) in queries.iter().enumerate() {
+      let name = query["name"].as_str().ok_or(format!("query {} without name", i + 1))?;
+      for predicate in query["where"].as_array().unwrap_or(&Vec::new()) {
+         if predicate["property"].as_str().is_none() {
+            return Err(format!("{}: predicate without property", name))
+         }
+         let op = predicate["op"].as_str().unwrap_or("eq");
+         if !OPERATORS.contains(&op) {
+            return Err(format!("{}: unknown operator '{}', possible values: {}", name, op, OPERATORS.join(", ")))
+         }
+      }
+   }
+   Ok(queries)
+}
+
+/// Function to check one property predicate on one object.
+fn match_predicate(object: &serde_json::value::Value, predicate: &serde_json::value::Value) -> bool
+{
+   let property = predicate["property"].as_str().unwrap_or("");
+   let actual = &object["Properties"][property];
+   let expected = &predicate["value"];
+   let lowercase = |value: &serde_json::value::Value| value.as_str().map(|value| value.to_lowercase());
+   match predicate["op"].as_str().unwrap_or("eq") {
+      "exists" => !actual.is_null() == expected.as_bool().unwrap_or(true),
+      "eq" => actual == expected || (lowercase(actual).is_some() && lowercase(actual) == lowercase(expected)),
+      "ne" => !(actual == expected || (lowercase(actual).is_some() && lowercase(actual) == lowercase(expected))),
+      "gt" => matches!((actual.as_f64(), expected.as_f64()), (Some(actual), Some(expected)) if actual > expected),
+      "lt" => matches!((actual.as_f64(), expected.as_f64()), (Some(actual), Some(expected)) if actual < expected),
+      "contains" => {
+         let expected = lowercase(expected).unwrap_or_default();
+         match actual {
+            serde_json::value::Value::String(actual) => actual.to_lowercase().contains(&expected),
+            serde_json::value::Value::Array(values) => values.iter().any(|value| lowercase(value) == Some(expected.to_owned())),
+            _ => false,
+         }
+      },
+      _ => false,
+   }
+}
+
+/// Function to find the SID of one group from its name (`Domain Admins` or `DOMAIN ADMINS@ESSOS.LOCAL`) or SID.
+fn find_group_sid(vec_groups: &[&serde_json::value::Value], group: &str) -> Option<String>
+{
+   let rid = get_well_known_rid(group);
+   let group = group.to_uppercase();
+   vec_groups.iter()
+      .find(|object| {
+         let name = object["Properties"]["name"].as_str().unwrap_or("").to_uppercase();
+         name == group
+            || name.split('@').next() == Some(group.as_str())
+            || object["ObjectIdentifier"].as_str().unwrap_or("").to_uppercase() == group
+      })
+      // Well-known groups by RID whatever the language of the domain
+      .or_else(|| vec_groups.iter().find(|object| rid.map_or(false, |rid| object["ObjectIdentifier"].as_str().unwrap_or("").ends_with(rid))))
+      .and_then(|object| object["ObjectIdentifier"].as_str())
+      .map(|sid| sid.to_string())
+}
+
+/// Function to run the queries of one pack on the collected objects, `objects` is a list of (node kind, objects).
+/// Returns one result per query with the names of the matching objects.
+pub fn run_queries(
+   queries: &[serde_json::value::Value],
+   objects: &[(&str, &Vec<serde_json::value::Value>)],
+) -> Vec<serde_json::value::Value>
+{
+   let empty = Vec::new();
+   let get_objects = |kind: &str| -> Vec<&serde_json::value::Value> {
+      objects.iter()
+         .filter(|(object_kind, _)| kind.is_empty() || object_kind.eq_ignore_ascii_case(kind))
+         .flat_map(|(_, data)| data.iter())
+         .collect()
+   };
+   let get_kind = |kind: &str| objects.iter().find(|(object_kind, _)| *object_kind == kind).map(|(_, data)| *data).unwrap_or(&empty);
+   let resolver = MembershipResolver::new(get_kind("User"), get_kind("Group"), get_kind("Computer"));
+   let vec_groups = get_objects("Group");
+
+   queries.iter()
+      .map(|query| {
+         let name = query["name"].as_str().unwrap_or("");
+         // Nested members of the memberof group, None without memberof
+         let members = query["memberof"].as_str().map(|group| match find_group_sid(&vec_groups, group) {
+            Some(sid) => resolver.effective_accounts(&sid),
+            None => {
+               warn!("{}: group {} not found in collected objects", name, group);
+               Vec::new()
+            },
+         });
+         let mut matches: Vec<String> = get_objects(query["type"].as_str().unwrap_or(""))
+            .into_iter()
+            .filter(|object| query["where"].as_array().unwrap_or(&empty).iter().all(|predicate| match_predicate(object, predicate)))
+            .filter(|object| match &members {
+               Some(members) => members.iter().any(|sid| Some(sid.as_str()) == object["ObjectIdentifier"].as_str()),
+               None => true,
+            })
+            .map(|object| object["Properties"]["name"].as_str().or(object["ObjectIdentifier"].as_str()).unwrap_or("").to_string())
+            .collect();
+         matches.sort();
+         matches.dedup();
+         serde_json::json!({
+            "name": name,
+            "description": query["description"].as_str().unwrap_or(""),
+            "count": matches.len(),
+            "objects": matches,
+         })
+      })
+      .collect()
+}
+
+/// Function to run the `--pack` queries on the .json files of the `--input` directory (`rusthound query`).
+pub fn query_directory(query_args: &QueryOptions) -> crate::errors::Result<()> {
+   let queries = parse_pack(&fs::read_to_string(&query_args.pack)?)
+      .map_err(|err| Error::new(ErrorKind::InvalidData, format!("Invalid query pack {}: {}", query_args.pack, err)))?;
+   let objects = load_directory(&query_args.input)?;
+   if objects.is_empty() {
+      return Err(Error::new(ErrorKind::NotFound, format!("No BloodHound .json file in {}", query_args.input)).into())
+   }
+   let objects: Vec<(&str, &Vec<serde_json::value::Value>)> = objects.iter().map(|(kind, data)| (*kind, data)).collect();
+   let results = run_queries(&queries, &objects);
+
+   if query_args.output.contains("not set") {
+      for result in &results {
+         println!("{} ({})", result["name"].as_str().unwrap_or("").bold(), result["count"]);
+         for object in result["objects"].as_array().unwrap_or(&Vec::new()) {
+            println!("  {}", object.as_str().unwrap_or(""));
+         }
+      }
+   } else {
+      fs::write(&query_args.output, serde_json::to_string_pretty(&serde_json::json!({ "queries": results })).unwrap_or_default())?;
+      info!("{} created!", &query_args.output);
+   }
+   Ok(())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_run_queries() {
+   let pack = r#"
+      [[query]]
+      name = "Kerberoastable admins"
+      type = "User"
+      where = [
+         { property = "hasspn", op = "eq", value = true },
+         { property = "description", op = "exists", value = false },
+      ]
+      memberof = "Domain Admins"
+
+      [[query]]
+      name = "Old passwords"
+      type = "user"
+      where = [{ property = "pwdlastset", op = "lt", value = 1600000000 }]
+
+      [[query]]
+      name = "Servers"
+      where = [{ property = "operatingsystem", op = "contains", value = "server" }]
+   "#;
+   let queries = parse_pack(pack).unwrap();
+   assert_eq!(queries.len(), 3);
+   assert!(parse_pack("[[query]]\nname = \"x\"\nwhere = [{ property = \"a\", op = \"like\" }]").is_err());
+
+   let vec_users = vec![
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1001", "Properties": {"name": "SQL_SVC@ESSOS.LOCAL", "hasspn": true, "pwdlastset": 1500000000}}),
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1002", "Properties": {"name": "WEB_SVC@ESSOS.LOCAL", "hasspn": true, "pwdlastset": 1700000000}}),
+   ];
+   let vec_groups = vec![
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-512", "Properties": {"name": "DOMAIN ADMINS@ESSOS.LOCAL"}, "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1100"}]}),
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1100", "Properties": {"name": "SQL ADMINS@ESSOS.LOCAL"}, "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1001"}]}),
+   ];
+   let vec_computers = vec![
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1000", "Properties": {"name": "MEEREEN.ESSOS.LOCAL", "operatingsystem": "Windows Server 2019 Datacenter"}}),
+   ];
+   let objects: Vec<(&str, &Vec<serde_json::value::Value>)> = vec![("User", &vec_users), ("Group", &vec_groups), ("Computer", &vec_computers)];
+   let results = run_queries(&queries, &objects);
+   assert_eq!(results[0]["objects"], serde_json::json!(["SQL_SVC@ESSOS.LOCAL"]));
+   assert_eq!(results[1]["objects"], serde_json::json!(["SQL_SVC@ESSOS.LOCAL"]));
+   assert_eq!(results[2]["objects"], serde_json::json!(["MEEREEN.ESSOS.LOCAL"]));
+   assert_eq!(results[2]["count"], 1);
+}
@@ -0,0 +1,337 @@
+use crate::json::report::{get_array, make_finding};
+use crate::json::checker::bh_41::{get_duplicate_spns,is_domain_controller};
+
+/// Check spns set on several accounts.
+/// Kerberos can't choose the right account for a duplicate spn and the authentication fails or falls back to NTLM.
+pub fn check_duplicate_spns(vec_users: &Vec<serde_json::value::Value>, vec_computers: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let duplicates = get_duplicate_spns(vec_users, vec_computers);
+   if duplicates.len() == 0 {
+      return
+   }
+   let mut objects: Vec<String> = Vec::new();
+   for (spn, sids) in &duplicates {
+      let mut names: Vec<String> = Vec::new();
+      for object in vec_users.iter().chain(vec_computers.iter()) {
+         if sids.contains(&object["ObjectIdentifier"].as_str().unwrap_or("").to_string()) {
+            names.push(object["Properties"]["name"].as_str().unwrap_or("").to_string());
+         }
+      }
+      objects.push(format!("{}: {}", spn, names.join(", ")));
+   }
+   objects.sort();
+   vec_findings.push(make_finding(
+      "Low",
+      "Accounts",
+      format!("{} spns are set on several accounts", duplicates.len()),
+      "Duplicate spns break Kerberos authentication for the service and can hide a spn added to take over a service.".to_string(),
+      objects,
+   ));
+}
+
+/// Check sAMAccountName and sAMAccountType oddities.
+/// User accounts ending with '$' and computer accounts without '$' are noPac (CVE-2021-42278) indicators.
+pub fn check_samaccountname_anomalies(vec_users: &Vec<serde_json::value::Value>, vec_computers: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut objects: Vec<String> = Vec::new();
+   for object in vec_users.iter().chain(vec_computers.iter()) {
+      if object["Properties"]["samaccountnameanomaly"].as_bool().unwrap_or(false) {
+         objects.push(format!("{} ({})",
+            object["Properties"]["samaccountname"].as_str().unwrap_or(""),
+            object["Properties"]["distinguishedname"].as_str().unwrap_or("")
+         ));
+      }
+   }
+   if objects.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "Accounts",
+         format!("{} accounts with sAMAccountName anomalies", objects.len()),
+         "User accounts ending with '$' or computer accounts without '$' can be used to impersonate a domain controller (noPac, CVE-2021-42278 and CVE-2021-42287). Check that KB5008102 is installed on all domain controllers.".to_string(),
+         objects,
+      ));
+   }
+}
+
+/// Check computers created by non-admin users with MachineAccountQuota.
+/// The creator keeps write rights on the computer (password reset, spn, RBCD) and can take it over.
+pub fn check_precreated_computers(vec_computers: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut objects: Vec<String> = Vec::new();
+   for computer in vec_computers {
+      if computer["Properties"]["precreated"].as_bool().unwrap_or(false) {
+         objects.push(format!("{} (created by {})",
+            computer["Properties"]["name"].as_str().unwrap_or(""),
+            computer["Properties"]["creatorname"].as_str().unwrap_or("Unknown"),
+         ));
+      }
+   }
+   if objects.len() > 0 {
+      objects.sort();
+      vec_findings.push(make_finding(
+         "Medium",
+         "Accounts",
+         format!("{} computers created by non-admin users with MachineAccountQuota", objects.len()),
+         "The creator of these computer accounts can reset their password or configure delegation and take them over. Set ms-DS-MachineAccountQuota to 0 and re-create the accounts with a dedicated account.".to_string(),
+         objects,
+      ));
+   }
+}
+
+/// Check enabled Windows computers (not domain controllers) without LAPS.
+/// Without LAPS the local administrator password is likely the same on many computers and one compromised computer gives all of them.
+pub fn check_laps_absent(vec_computers: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut objects: Vec<String> = Vec::new();
+   for computer in vec_computers {
+      let operatingsystem = computer["Properties"]["operatingsystem"].as_str().unwrap_or("");
+      if computer["Properties"]["enabled"].as_bool().unwrap_or(true)
+      && !computer["Properties"]["haslaps"].as_bool().unwrap_or(false)
+      && (operatingsystem.is_empty() || operatingsystem.to_lowercase().contains("windows"))
+      && !is_domain_controller(computer)
+      {
+         objects.push(computer["Properties"]["name"].as_str().unwrap_or("").to_string());
+      }
+   }
+   if objects.len() > 0 {
+      objects.sort();
+      vec_findings.push(make_finding(
+         "Medium",
+         "Accounts",
+         format!("{} computers without LAPS", objects.len()),
+         "No LAPS password expiration time is set on these computers. Local administrator passwords are likely shared and can be reused for lateral movement. Deploy Windows LAPS.".to_string(),
+         objects,
+      ));
+   }
+}
+
+/// Check enabled computers with an end-of-life operating system (no security updates anymore).
+/// High if one of them is a domain controller.
+pub fn check_eol_operating_systems(vec_computers: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut objects: Vec<String> = Vec::new();
+   let mut dc_found = false;
+   for computer in vec_computers {
+      if computer["Properties"]["enabled"].as_bool().unwrap_or(true)
+      && computer["Properties"]["oseol"].as_bool().unwrap_or(false)
+      {
+         dc_found = dc_found || is_domain_controller(computer);
+         objects.push(format!("{} ({})",
+            computer["Properties"]["name"].as_str().unwrap_or(""),
+            computer["Properties"]["osrelease"].as_str().unwrap_or(""),
+         ));
+      }
+   }
+   if objects.len() > 0 {
+      objects.sort();
+      vec_findings.push(make_finding(
+         if dc_found { "High" } else { "Medium" },
+         "Accounts",
+         format!("{} computers with an end-of-life operating system", objects.len()),
+         "These operating systems don't get security updates anymore and are easy targets for known vulnerabilities. Upgrade or isolate them.".to_string(),
+         objects,
+      ));
+   }
+}
+
+/// Check principals flagged by the checker with SIDs of their own domain or privileged SIDs in sIDHistory.
+/// Both are signs of SID history injection, a persistence giving the rights of the SIDs without any group membership.
+pub fn check_sid_history(
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_groups: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_findings: &mut Vec<serde_json::value::Value>,
+)
+{
+   let checks = [
+      ("sidhistoryprivileged", "{} principals with privileged SIDs in sIDHistory", "These principals have the SID of Domain Admins, Schema Admins, Enterprise Admins or Administrators in sIDHistory and get their rights in every ticket. Check who added them and clear sIDHistory."),
+      ("sidhistorysamedomain", "{} principals with SIDs of their own domain in sIDHistory", "Migrations only add SIDs of other domains, a SID of the same domain in sIDHistory was likely injected to get the rights of another account. Check who added them and clear sIDHistory."),
+   ];
+   for (property, title, description) in checks.iter() {
+      let mut objects: Vec<String> = Vec::new();
+      for object in vec_users.iter().chain(vec_groups.iter()).chain(vec_computers.iter()) {
+         if object["Properties"][*property].as_bool().unwrap_or(false) {
+            let sid_history: Vec<&str> = get_array(&object["Properties"]["sidhistory"]).iter()
+               .filter_map(|sid| sid.as_str())
+               .collect();
+            objects.push(format!("{}: {}", object["Properties"]["name"].as_str().unwrap_or(""), sid_history.join(", ")));
+         }
+      }
+      if objects.len() > 0 {
+         objects.sort();
+         vec_findings.push(make_finding(
+            "High",
+            "Accounts",
+            title.replace("{}", &objects.len().to_string()),
+            description.to_string(),
+            objects,
+         ));
+      }
+   }
+}
+
+/// Check conflict objects (CNF) and groups with orphaned members found by the checker.
+/// Conflict objects are duplicates made by replication, their rights and memberships are easily missed during reviews.
+pub fn check_conflict_objects(
+   vec_objects: &[&Vec<serde_json::value::Value>],
+   vec_groups: &Vec<serde_json::value::Value>,
+   vec_findings: &mut Vec<serde_json::value::Value>,
+)
+{
+   let mut conflicts: Vec<String> = vec_objects.iter()
+      .flat_map(|vec_objects| vec_objects.iter())
+      .filter(|object| object["Properties"]["conflictobject"].as_bool().unwrap_or(false))
+      .map(|object| object["Properties"]["distinguishedname"].as_str().unwrap_or("").to_string())
+      .collect();
+   if conflicts.len() > 0 {
+      conflicts.sort();
+      vec_findings.push(make_finding(
+         "Low",
+         "Accounts",
+         format!("{} conflict objects (CNF)", conflicts.len()),
+         "These objects were renamed by replication because the same name was created on two domain controllers. Check their group memberships and rights, then delete the duplicates.".to_string(),
+         conflicts,
+      ));
+   }
+
+   let mut orphaned: Vec<String> = Vec::new();
+   for group in vec_groups {
+      for dn in group["Properties"]["orphanedmembers"].as_array().unwrap_or(&Vec::new()) {
+         orphaned.push(format!("{}: {}", group["Properties"]["name"].as_str().unwrap_or(""), dn.as_str().unwrap_or("")));
+      }
+   }
+   if orphaned.len() > 0 {
+      orphaned.sort();
+      vec_findings.push(make_finding(
+         "Info",
+         "Accounts",
+         format!("{} group members not found", orphaned.len()),
+         "These members of the collected domain were not found in the collection: deleted, unreadable or outside the collected partitions. They are not added as edges.".to_string(),
+         orphaned,
+      ));
+   }
+}
+
+/// Summary of Tier-0 accounts found: domain controllers, krbtgt accounts (RODC ones too) and high value accounts.
+pub fn check_tier0_summary(vec_users: &Vec<serde_json::value::Value>, vec_computers: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut objects: Vec<String> = Vec::new();
+   for computer in vec_computers {
+      let name = computer["Properties"]["name"].as_str().unwrap_or("");
+      if computer["Properties"]["isrodc"].as_bool().unwrap_or(false) {
+         objects.push(format!("{} (Read-Only Domain Controller)", name));
+      } else if is_domain_controller(computer) {
+         objects.push(format!("{} (Domain Controller)", name));
+      } else if computer["Properties"]["highvalue"].as_bool().unwrap_or(false) {
+         objects.push(format!("{} (high value)", name));
+      }
+   }
+   for user in vec_users {
+      let name = user["Properties"]["name"].as_str().unwrap_or("");
+      if user["Properties"]["rodckrbtgt"].as_bool().unwrap_or(false) {
+         objects.push(format!("{} (krbtgt of RODC {})", name, user["Properties"]["rodcname"].as_str().unwrap_or("not found")));
+      } else if user["ObjectIdentifier"].as_str().unwrap_or("").ends_with("-502") {
+         objects.push(format!("{} (krbtgt)", name));
+      } else if user["Properties"]["highvalue"].as_bool().unwrap_or(false) {
+         objects.push(format!("{} (high value)", name));
+      }
+   }
+   if objects.len() > 0 {
+      vec_findings.push(make_finding(
+         "Info",
+         "Accounts",
+         format!("{} Tier-0 accounts", objects.len()),
+         "Domain controllers, krbtgt accounts and high value accounts. RODC krbtgt accounts can forge tickets accepted by their RODC and must be managed as Tier-0 assets.".to_string(),
+         objects,
+      ));
+   }
+}
+
+/// Check the credential protections of admins: Protected Users and authentication policy silos.
+/// Admins out of both can have their credentials cached on every host they log on, delegated or relayed with NTLM.
+pub fn check_credential_protections(vec_users: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut unprotected: Vec<String> = Vec::new();
+   let mut silos: Vec<String> = Vec::new();
+   for user in vec_users {
+      let properties = &user["Properties"];
+      if let Some(silo) = properties["authnpolicysilo"].as_str() {
+         let silo = format!("{} ({})", silo, if properties["authnpolicysiloenforced"] == true { "enforced" } else { "audit only" });
+         if !silos.contains(&silo) {
+            silos.push(silo);
+         }
+      }
+      let is_admin = properties["isTierZero"].as_bool().unwrap_or(false) || properties["admincount"].as_bool().unwrap_or(false);
+      if !is_admin
+         || !properties["enabled"].as_bool().unwrap_or(true)
+         || properties["trustaccount"].as_bool().unwrap_or(false)
+         || user["ObjectIdentifier"].as_str().unwrap_or("").ends_with("-502")
+      {
+         continue
+      }
+      let in_enforced_silo = properties["authnpolicysilo"].is_string() && properties["authnpolicysiloenforced"] == true;
+      if !properties["protecteduser"].as_bool().unwrap_or(false) && !in_enforced_silo {
+         unprotected.push(properties["name"].as_str().unwrap_or("").to_string());
+      }
+   }
+   if unprotected.len() > 0 {
+      unprotected.sort();
+      vec_findings.push(make_finding(
+         "Medium",
+         "Accounts",
+         format!("{} admins not in Protected Users nor in an enforced authentication policy silo", unprotected.len()),
+         "Credentials of these admins can be cached on the hosts they log on to, used with NTLM, RC4 or delegated. Add them to Protected Users or to an enforced authentication policy silo limiting where they can log on.".to_string(),
+         unprotected,
+      ));
+   }
+   if silos.len() > 0 {
+      silos.sort();
+      vec_findings.push(make_finding(
+         "Info",
+         "Accounts",
+         format!("{} authentication policy silos with users", silos.len()),
+         "Silos in audit only mode log the logons they would block without blocking them.".to_string(),
+         silos,
+      ));
+   }
+}
+
+/// Check Tier-0 accounts exempt from authentication policy silos, when the domain has silos.
+/// Silos restrict where their accounts can get a TGT from, Tier-0 accounts left out of them can still log on to any host.
+pub fn check_silo_exempt_tier0(
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_authn: &Vec<serde_json::value::Value>,
+   vec_findings: &mut Vec<serde_json::value::Value>,
+)
+{
+   if !vec_authn.iter().any(|authn| authn["Properties"]["type"] == "silo") {
+      return
+   }
+   let mut exempt: Vec<String> = Vec::new();
+   for object in vec_users.iter().chain(vec_computers.iter()) {
+      let properties = &object["Properties"];
+      if !properties["isTierZero"].as_bool().unwrap_or(false)
+         || !properties["enabled"].as_bool().unwrap_or(true)
+         || properties["trustaccount"].as_bool().unwrap_or(false)
+         || object["ObjectIdentifier"].as_str().unwrap_or("").ends_with("-502")
+      {
+         continue
+      }
+      match properties["authnpolicysilo"].as_str() {
+         Some(silo) if properties["authnpolicysiloenforced"] != true => exempt.push(format!("{} (silo {} in audit only)", properties["name"].as_str().unwrap_or(""), silo)),
+         Some(_) => {},
+         None => exempt.push(properties["name"].as_str().unwrap_or("").to_string()),
+      }
+   }
+   if exempt.len() > 0 {
+      exempt.sort();
+      vec_findings.push(make_finding(
+         "Medium",
+         "Accounts",
+         format!("{} Tier-0 accounts exempt from authentication policy silos", exempt.len()),
+         "The domain has authentication policy silos but these Tier-0 accounts are not assigned to an enforced one, so their TGTs can be requested from any host. Add them to the Tier-0 silo with msDS-AuthNPolicySiloMembers and msDS-AssignedAuthNPolicySilo.".to_string(),
+         exempt,
+      ));
+   }
+}
@@ -0,0 +1,155 @@
+//! Tier-0 DACL audit (**--audit**)
+//!
+//! The ACEs of the domain head, AdminSDHolder and the Domain Controllers OU are compared with a known-good
+//! baseline of a default Active Directory. Every principal with control rights which isn't in the baseline
+//! is flagged: on AdminSDHolder it's copied on all protected accounts by SDProp, on the domain head it can DCSync.
+use crate::json::report::make_finding;
+use crate::json::report::membership::MembershipResolver;
+
+/// Rights giving control over the object or its secrets.
+pub const CONTROL_RIGHTS: [&str; 15] = [
+   "Owns",
+   "GenericAll",
+   "GenericWrite",
+   "WriteOwner",
+   "WriteDacl",
+   "AllExtendedRights",
+   "GetChanges",
+   "GetChangesAll",
+   "AddKeyCredentialLink",
+   "WriteSPN",
+   "WriteAccountRestrictions",
+   "WriteUserAccountControl",
+   "ForceChangePassword",
+   "AddMember",
+   "AddSelf",
+];
+
+/// Principals with control rights in a default Active Directory, RID of domain principals or well-known SID.
+const DEFAULT_PRINCIPALS: [&str; 3] = [
+   // Domain Admins, Enterprise Admins, Administrators
+   "-512", "-519", "S-1-5-32-544",
+];
+
+/// Known-good baseline of one Tier-0 object: principals expected with control rights.
+pub struct Baseline {
+   pub name: &'static str,
+   pub principals: &'static [&'static str],
+}
+
+/// Baseline of the domain head.
+pub const DOMAIN_BASELINE: Baseline = Baseline {
+   name: "domain head",
+   principals: &[
+      // Enterprise Domain Controllers, Domain Controllers, Read-only Domain Controllers, Enterprise Read-only Domain Controllers
+      "S-1-5-9", "-516", "-521", "-498",
+      // Key Admins, Enterprise Key Admins (msDS-KeyCredentialLink)
+      "-526", "-527",
+   ],
+};
+
+/// Baseline of CN=AdminSDHolder,CN=System.
+pub const ADMINSDHOLDER_BASELINE: Baseline = Baseline {
+   name: "AdminSDHolder",
+   principals: &[],
+};
+
+/// Baseline of OU=Domain Controllers.
+pub const DOMAIN_CONTROLLERS_OU_BASELINE: Baseline = Baseline {
+   name: "Domain Controllers OU",
+   principals: &[
+      // Enterprise Domain Controllers, Key Admins, Enterprise Key Admins
+      "S-1-5-9", "-526", "-527",
+   ],
+};
+
+/// Function to check if one principal is expected with control rights on one object.
+pub fn is_baseline_principal(baseline: &Baseline, sid: &str) -> bool {
+   DEFAULT_PRINCIPALS.iter().chain(baseline.principals.iter()).any(|principal| sid.ends_with(principal))
+}
+
+/// Function to get the control rights of principals not in the baseline, `PRINCIPAL: Right, Right`.
+pub fn diff_baseline(
+   baseline: &Baseline,
+   object: &serde_json::value::Value,
+   resolver: &MembershipResolver,
+) -> Vec<String>
+{
+   let mut unexpected: Vec<(String, Vec<String>)> = Vec::new();
+   for ace in object["Aces"].as_array().unwrap_or(&Vec::new()) {
+      let sid = ace["PrincipalSID"].as_str().unwrap_or("");
+      let right = ace["RightName"].as_str().unwrap_or("");
+      if sid.is_empty() || !CONTROL_RIGHTS.contains(&right) || is_baseline_principal(baseline, sid) {
+         continue
+      }
+      match unexpected.iter_mut().find(|(principal, _)| principal == sid) {
+         Some((_, rights)) => if !rights.contains(&right.to_string()) { rights.push(right.to_string()) },
+         None => unexpected.push((sid.to_string(), vec![right.to_string()])),
+      }
+   }
+   unexpected.into_iter()
+      .map(|(sid, rights)| format!("{}: {}", resolver.get_name(&sid), rights.join(", ")))
+      .collect()
+}
+
+/// Function to audit the DACL of Tier-0 objects against their baseline.
+pub fn check_tier0_acls(
+   vec_domains: &Vec<serde_json::value::Value>,
+   vec_ous: &Vec<serde_json::value::Value>,
+   vec_containers: &Vec<serde_json::value::Value>,
+   resolver: &MembershipResolver,
+   vec_findings: &mut Vec<serde_json::value::Value>,
+)
+{
+   let dn_starts_with = |object: &serde_json::value::Value, rdns: &str| {
+      object["Properties"]["distinguishedname"].as_str().unwrap_or("").to_uppercase().starts_with(rdns)
+   };
+   let mut objects: Vec<(&Baseline, &serde_json::value::Value)> = Vec::new();
+   objects.extend(vec_domains.iter().map(|domain| (&DOMAIN_BASELINE, domain)));
+   objects.extend(vec_containers.iter().filter(|container| dn_starts_with(container, "CN=ADMINSDHOLDER,CN=SYSTEM,")).map(|container| (&ADMINSDHOLDER_BASELINE, container)));
+   objects.extend(vec_ous.iter().filter(|ou| dn_starts_with(ou, "OU=DOMAIN CONTROLLERS,")).map(|ou| (&DOMAIN_CONTROLLERS_OU_BASELINE, ou)));
+
+   for (baseline, object) in objects {
+      let unexpected = diff_baseline(baseline, object, resolver);
+      if unexpected.is_empty() {
+         continue
+      }
+      let name = object["Properties"]["distinguishedname"].as_str()
+         .or(object["Properties"]["name"].as_str())
+         .unwrap_or("");
+      vec_findings.push(make_finding(
+         "High",
+         "ACL",
+         format!("{} unexpected principals with control rights on {} {}", unexpected.len(), baseline.name, name),
+         format!("These principals are not in the default ACL of the {}. Check they are Tier-0 or remove their rights.", baseline.name),
+         unexpected,
+      ));
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_check_tier0_acls() {
+   let domain = serde_json::json!({
+      "Properties": {"name": "ESSOS.LOCAL", "distinguishedname": "DC=ESSOS,DC=LOCAL"},
+      "Aces": [
+         {"PrincipalSID": "S-1-5-21-1-2-3-512", "RightName": "GenericAll"},
+         {"PrincipalSID": "S-1-5-21-1-2-3-516", "RightName": "GetChangesAll"},
+         {"PrincipalSID": "ESSOS.LOCAL-S-1-5-32-544", "RightName": "WriteDacl"},
+         {"PrincipalSID": "S-1-5-21-1-2-3-1104", "RightName": "GetChanges"},
+         {"PrincipalSID": "S-1-5-21-1-2-3-1104", "RightName": "GetChangesAll"},
+         {"PrincipalSID": "S-1-5-21-1-2-3-1105", "RightName": "ReadLAPSPassword"},
+      ],
+   });
+   let adminsdholder = serde_json::json!({
+      "Properties": {"distinguishedname": "CN=AdminSDHolder,CN=System,DC=ESSOS,DC=LOCAL"},
+      "Aces": [{"PrincipalSID": "S-1-5-21-1-2-3-516", "RightName": "GenericWrite"}],
+   });
+   let vec_users = vec![serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "Properties": {"name": "JORAH.MORMONT@ESSOS.LOCAL"}})];
+   let resolver = MembershipResolver::new(&vec_users, &Vec::new(), &Vec::new());
+   let mut vec_findings = Vec::new();
+   check_tier0_acls(&vec![domain], &Vec::new(), &vec![adminsdholder], &resolver, &mut vec_findings);
+   assert_eq!(vec_findings.len(), 2);
+   assert_eq!(vec_findings[0]["Objects"], serde_json::json!(["JORAH.MORMONT@ESSOS.LOCAL: GetChanges, GetChangesAll"]));
+   assert_eq!(vec_findings[1]["Objects"], serde_json::json!(["S-1-5-21-1-2-3-516: GenericWrite"]));
+}
@@ -0,0 +1,102 @@
+//! Inbound control of Tier-0 nodes
+//!
+//! For each Tier-0 group and domain, the principals with control rights in its ACL (first degree) and the
+//! users and computers behind them through nested groups (unrolled): the "who controls Domain Admins" table.
+use crate::json::report::audit::CONTROL_RIGHTS;
+use crate::json::report::make_finding;
+use crate::json::report::membership::MembershipResolver;
+
+/// Function to check if one object is tagged Tier-0 by the checker.
+fn is_tier_zero(object: &serde_json::value::Value) -> bool {
+   object["Properties"]["isTierZero"].as_bool().unwrap_or(false)
+      || object["Properties"]["highvalue"].as_bool().unwrap_or(false)
+}
+
+/// Function to get the principals with control rights on one object, with their rights.
+pub fn get_inbound_control(object: &serde_json::value::Value) -> Vec<(String, Vec<String>)> {
+   let mut principals: Vec<(String, Vec<String>)> = Vec::new();
+   for ace in object["Aces"].as_array().unwrap_or(&Vec::new()) {
+      let sid = ace["PrincipalSID"].as_str().unwrap_or("");
+      let right = ace["RightName"].as_str().unwrap_or("");
+      if sid.is_empty() || !CONTROL_RIGHTS.contains(&right) {
+         continue
+      }
+      match principals.iter_mut().find(|(principal, _)| principal == sid) {
+         Some((_, rights)) => if !rights.contains(&right.to_string()) { rights.push(right.to_string()) },
+         None => principals.push((sid.to_string(), vec![right.to_string()])),
+      }
+   }
+   principals
+}
+
+/// Function to summarize the inbound control of every Tier-0 group and domain.
+/// Direct principals are listed with their rights, the accounts only reached through groups are marked.
+pub fn check_inbound_control(
+   vec_groups: &Vec<serde_json::value::Value>,
+   vec_domains: &Vec<serde_json::value::Value>,
+   resolver: &MembershipResolver,
+   vec_findings: &mut Vec<serde_json::value::Value>,
+)
+{
+   for object in vec_groups.iter().chain(vec_domains.iter()).filter(|object| is_tier_zero(object)) {
+      let principals = get_inbound_control(object);
+      if principals.is_empty() {
+         continue
+      }
+      let direct: Vec<String> = principals.iter().map(|(sid, _)| sid.to_owned()).collect();
+      let accounts: Vec<String> = resolver.effective_names(&direct);
+      let mut objects: Vec<String> = principals.iter()
+         .map(|(sid, rights)| format!("{}: {}", resolver.get_name(sid), rights.join(", ")))
+         .collect();
+      let direct_names: Vec<String> = direct.iter().map(|sid| resolver.get_name(sid)).collect();
+      objects.extend(accounts.iter()
+         .filter(|name| !direct_names.contains(name))
+         .map(|name| format!("{} (through groups)", name)));
+
+      vec_findings.push(make_finding(
+         "Info",
+         "ACL",
+         format!("{} principals and {} effective accounts control {}",
+            principals.len(), accounts.len(), object["Properties"]["name"].as_str().unwrap_or("")),
+         "Principals with control rights on this Tier-0 object, then the users and computers only reached through nested groups. All of them are effectively Tier-0.".to_string(),
+         objects,
+      ));
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_check_inbound_control() {
+   let vec_users = vec![
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "Properties": {"name": "JON@NORTH.LOCAL"}}),
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1105", "Properties": {"name": "ARYA@NORTH.LOCAL"}}),
+   ];
+   let vec_groups = vec![
+      serde_json::json!({
+         "ObjectIdentifier": "S-1-5-21-1-2-3-512",
+         "Properties": {"name": "DOMAIN ADMINS@NORTH.LOCAL", "isTierZero": true},
+         "Aces": [
+            {"PrincipalSID": "S-1-5-21-1-2-3-1200", "RightName": "GenericAll"},
+            {"PrincipalSID": "S-1-5-21-1-2-3-1105", "RightName": "AddMember"},
+            {"PrincipalSID": "S-1-5-21-1-2-3-1105", "RightName": "WriteDacl"},
+            {"PrincipalSID": "S-1-5-21-1-2-3-1106", "RightName": "ReadLAPSPassword"},
+         ],
+      }),
+      serde_json::json!({
+         "ObjectIdentifier": "S-1-5-21-1-2-3-1200",
+         "Properties": {"name": "HELPDESK@NORTH.LOCAL", "isTierZero": false},
+         "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "ObjectType": "User"}],
+         "Aces": [{"PrincipalSID": "S-1-5-21-1-2-3-1105", "RightName": "GenericAll"}],
+      }),
+   ];
+   let resolver = MembershipResolver::new(&vec_users, &vec_groups, &Vec::new());
+   let mut vec_findings = Vec::new();
+   check_inbound_control(&vec_groups, &Vec::new(), &resolver, &mut vec_findings);
+   assert_eq!(vec_findings.len(), 1);
+   assert_eq!(vec_findings[0]["Title"], "2 principals and 2 effective accounts control DOMAIN ADMINS@NORTH.LOCAL");
+   assert_eq!(vec_findings[0]["Objects"], serde_json::json!([
+      "HELPDESK@NORTH.LOCAL: GenericAll",
+      "ARYA@NORTH.LOCAL: AddMember, WriteDacl",
+      "JON@NORTH.LOCAL (through groups)",
+   ]));
+}
@@ -0,0 +1,48 @@
+use crate::json::report::make_finding;
+use crate::json::checker::bh_41::is_domain_controller;
+
+/// Check users and computers (not domain controllers) with unconstrained delegation.
+/// Any TGT sent to these accounts can be reused, a coerced domain controller authentication gives a domain controller TGT.
+/// <https://adsecurity.org/?p=1667>
+pub fn check_unconstrained_delegation(vec_users: &Vec<serde_json::value::Value>, vec_computers: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut objects: Vec<String> = Vec::new();
+   let mut coercible: Vec<String> = Vec::new();
+   for user in vec_users {
+      if user["Properties"]["unconstraineddelegation"].as_bool().unwrap_or(false) && user["Properties"]["enabled"].as_bool().unwrap_or(true) {
+         objects.push(user["Properties"]["name"].as_str().unwrap_or("").to_string());
+         if user["Properties"]["coercetotgt"].as_bool().unwrap_or(false) {
+            coercible.push(user["Properties"]["name"].as_str().unwrap_or("").to_string());
+         }
+      }
+   }
+   for computer in vec_computers {
+      if computer["Properties"]["unconstraineddelegation"].as_bool().unwrap_or(false)
+      && computer["Properties"]["enabled"].as_bool().unwrap_or(true)
+      && !is_domain_controller(computer)
+      {
+         objects.push(computer["Properties"]["name"].as_str().unwrap_or("").to_string());
+         if computer["Properties"]["coercetotgt"].as_bool().unwrap_or(false) {
+            coercible.push(computer["Properties"]["name"].as_str().unwrap_or("").to_string());
+         }
+      }
+   }
+   if objects.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "Delegation",
+         format!("{} accounts with unconstrained delegation (Tier-0 adjacent)", objects.len()),
+         "These accounts receive forwardable TGTs of every user authenticating to them. They must be managed as Tier-0 assets or use constrained delegation.".to_string(),
+         objects,
+      ));
+   }
+   if coercible.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "Delegation",
+         format!("{} unconstrained delegation accounts can get a domain controller TGT", coercible.len()),
+         "Print spooler or WebClient service is available on a domain controller. Coercing the domain controller to authenticate to one of these accounts gives its TGT and DCSync rights.".to_string(),
+         coercible,
+      ));
+   }
+}
@@ -0,0 +1,183 @@
+use crate::json::report::make_finding;
+use crate::enums::date::return_current_epoch;
+use crate::json::checker::bh_41::is_domain_controller;
+use crate::json::report::membership::MembershipResolver;
+
+/// Check domain and forest functional levels.
+/// Legacy levels keep weak protocols available like DES/RC4 only krbtgt keys, NTLMv1 and FRS replication.
+/// <https://learn.microsoft.com/en-us/windows-server/identity/ad-ds/active-directory-functional-levels>
+pub fn check_functional_levels(vec_domains: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   for domain in vec_domains {
+      let name = domain["Properties"]["name"].as_str().unwrap_or("").to_string();
+      let domain_level = domain["Properties"]["behaviorversion"].as_i64().unwrap_or(-1);
+      let forest_level = domain["Properties"]["forestbehaviorversion"].as_i64().unwrap_or(-1);
+
+      // 3 = Windows Server 2008
+      if domain_level >= 0 && domain_level < 3 {
+         vec_findings.push(make_finding(
+            "High",
+            "FunctionalLevel",
+            format!("Domain functional level {} is below Windows Server 2008", domain["Properties"]["functionallevel"].as_str().unwrap_or("Unknown")),
+            "AES Kerberos keys are not available for the krbtgt account and SYSVOL can still be replicated with FRS. Legacy clients using NTLMv1 and DES/RC4 are likely still supported.".to_string(),
+            vec![name.to_owned()],
+         ));
+      }
+      // 6 = Windows Server 2012 R2
+      else if domain_level >= 0 && domain_level < 6 {
+         vec_findings.push(make_finding(
+            "Low",
+            "FunctionalLevel",
+            format!("Domain functional level {} is below Windows Server 2012 R2", domain["Properties"]["functionallevel"].as_str().unwrap_or("Unknown")),
+            "Domain controller side protections for Protected Users (no NTLM, no DES/RC4, 4 hours TGT) and authentication policies and silos require Windows Server 2012 R2 domain functional level.".to_string(),
+            vec![name.to_owned()],
+         ));
+      }
+      // 4 = Windows Server 2008 R2
+      if forest_level >= 0 && forest_level < 4 {
+         vec_findings.push(make_finding(
+            "Low",
+            "FunctionalLevel",
+            format!("Forest functional level {} is below Windows Server 2008 R2", domain["Properties"]["forestfunctionallevel"].as_str().unwrap_or("Unknown")),
+            "Active Directory Recycle Bin can't be enabled and all domains in the forest may still run legacy domain controllers.".to_string(),
+            vec![name.to_owned()],
+         ));
+      }
+   }
+}
+
+/// Check krbtgt password age.
+/// An old krbtgt password means every golden ticket forged since the last change is still valid.
+/// <https://learn.microsoft.com/en-us/windows-server/identity/ad-ds/manage/ad-forest-recovery-resetting-the-krbtgt-password>
+pub fn check_krbtgt_password_age(vec_domains: &Vec<serde_json::value::Value>, max_age: i64, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let now = return_current_epoch();
+   for domain in vec_domains {
+      let pwdlastset = domain["Properties"]["krbtgtpasswordlastset"].as_i64().unwrap_or(-1);
+      if pwdlastset <= 0 {
+         continue
+      }
+      let age = (now - pwdlastset) / 86400;
+      if age > max_age {
+         vec_findings.push(make_finding(
+            "High",
+            "Kerberos",
+            format!("krbtgt password last set {} days ago", age),
+            format!("The krbtgt password is older than {} days. Golden tickets forged with a krbtgt hash dumped since the last change are still valid. Reset the krbtgt password twice.", max_age),
+            vec![domain["Properties"]["name"].as_str().unwrap_or("").to_string()],
+         ));
+      }
+   }
+}
+
+/// Check trust accounts (TDO) password age.
+/// Trust passwords are automatically changed every 30 days, an old one means the rotation is broken.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/b645c125-a7da-4097-84a1-2fa7cea07714>
+pub fn check_trust_accounts_password_age(vec_users: &Vec<serde_json::value::Value>, max_age: i64, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let now = return_current_epoch();
+   let mut objects: Vec<String> = Vec::new();
+   for user in vec_users {
+      if !user["Properties"]["trustaccount"].as_bool().unwrap_or(false) {
+         continue
+      }
+      let pwdlastset = user["Properties"]["pwdlastset"].as_i64().unwrap_or(-1);
+      if pwdlastset > 0 && (now - pwdlastset) / 86400 > max_age {
+         objects.push(format!("{} ({} days)", user["Properties"]["samaccountname"].as_str().unwrap_or(""), (now - pwdlastset) / 86400));
+      }
+   }
+   if objects.len() > 0 {
+      vec_findings.push(make_finding(
+         "Medium",
+         "Trust",
+         format!("{} trust accounts with password older than {} days", objects.len(), max_age),
+         "Trust account passwords should be rotated every 30 days. An old trust key can be used to forge inter-realm tickets.".to_string(),
+         objects,
+      ));
+   }
+}
+
+/// Check trusts with other forests or external domains.
+/// Without SID filtering, SID history of principals from the trusted domain is accepted and gives any SID of this domain.
+pub fn check_trusts(vec_trusts: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut sid_filtering: Vec<String> = Vec::new();
+   let mut bidirectional: Vec<String> = Vec::new();
+   for trust in vec_trusts {
+      let trust_type = trust["TrustType"].as_str().unwrap_or("");
+      if trust_type != "Forest" && trust_type != "External" {
+         continue
+      }
+      let name = format!("{} ({} trust)", trust["TargetDomainName"].as_str().unwrap_or(""), trust_type);
+      if trust["SidFilteringEnabled"] == false {
+         sid_filtering.push(name.to_owned());
+      }
+      if trust["TrustDirection"] == "Bidirectional" {
+         bidirectional.push(name);
+      }
+   }
+   if sid_filtering.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "Trusts",
+         format!("{} trusts without SID filtering", sid_filtering.len()),
+         "Principals of the trusted domain can use SID history to get any SID of this domain, like Enterprise Admins. Enable SID filtering (quarantine) on these trusts.".to_string(),
+         sid_filtering,
+      ));
+   }
+   if bidirectional.len() > 0 {
+      vec_findings.push(make_finding(
+         "Low",
+         "Trusts",
+         format!("{} bidirectional trusts with other forests or external domains", bidirectional.len()),
+         "Users of both sides can authenticate and enumerate the other domain. Use one-way trusts when only one side needs to access resources.".to_string(),
+         bidirectional,
+      ));
+   }
+}
+
+/// Check the effective users and computers which can DCSync, trustees of the replication rights are unrolled
+/// through nested groups. Domain controllers are expected and not listed.
+pub fn check_dcsync_principals(
+   vec_domains: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   resolver: &MembershipResolver,
+   vec_findings: &mut Vec<serde_json::value::Value>,
+)
+{
+   let domain_controllers: Vec<&str> = vec_computers.iter()
+      .filter(|computer| is_domain_controller(computer))
+      .filter_map(|computer| computer["ObjectIdentifier"].as_str())
+      .collect();
+   for domain in vec_domains {
+      let aces = domain["Aces"].as_array().map(|aces| aces.to_owned()).unwrap_or_default();
+      let principals = |right: &str| -> Vec<String> {
+         aces.iter()
+            .filter(|ace| ace["RightName"] == right)
+            .filter_map(|ace| ace["PrincipalSID"].as_str().map(|sid| sid.to_string()))
+            .collect()
+      };
+      // GetChanges and GetChangesAll, or all the extended rights
+      let get_changes = principals("GetChanges");
+      let mut trustees: Vec<String> = principals("GetChangesAll").into_iter().filter(|sid| get_changes.contains(sid)).collect();
+      trustees.extend(principals("AllExtendedRights"));
+      trustees.extend(principals("GenericAll"));
+
+      let mut objects: Vec<String> = trustees.iter()
+         .flat_map(|sid| resolver.effective_accounts(sid))
+         .filter(|sid| !domain_controllers.contains(&sid.as_str()))
+         .map(|sid| resolver.get_name(&sid))
+         .collect();
+      objects.sort();
+      objects.dedup();
+      if objects.len() > 0 {
+         vec_findings.push(make_finding(
+            "Info",
+            "Accounts",
+            format!("{} accounts can DCSync {}", objects.len(), domain["Properties"]["name"].as_str().unwrap_or("")),
+            "Effective users and computers with the replication rights on the domain, directly or through nested groups. They can get the password hashes of every account, including krbtgt, and must be managed as Tier-0.".to_string(),
+            objects,
+         ));
+      }
+   }
+}
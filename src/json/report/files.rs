@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use crate::json::report::{get_array, make_finding};
+
+/// Check SYSVOL and NETLOGON files and folders writable by non-admin principals (from the sysvol-acls module).
+/// GPO files are applied by every computer or user linked to the GPO and logon scripts run at every logon,
+/// so their writers can run code on them.
+pub fn check_writable_files(
+   vec_shares: &Vec<serde_json::value::Value>,
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_groups: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_findings: &mut Vec<serde_json::value::Value>,
+)
+{
+   // SID to name of the writers
+   let sid_name: HashMap<&str, &str> = vec_users.iter().chain(vec_groups.iter()).chain(vec_computers.iter())
+      .filter_map(|object| Some((object["ObjectIdentifier"].as_str()?, object["Properties"]["name"].as_str()?)))
+      .collect();
+
+   let mut gpo_files: Vec<String> = Vec::new();
+   let mut netlogon_files: Vec<String> = Vec::new();
+   for entry in vec_shares {
+      let writers: Vec<&str> = get_array(&entry["WritableBy"]).iter()
+         .filter_map(|writer| writer["ObjectIdentifier"].as_str())
+         .map(|sid| *sid_name.get(sid).unwrap_or(&sid))
+         .collect();
+      if writers.is_empty() {
+         continue
+      }
+      let object = format!("{} ({})", entry["Name"].as_str().unwrap_or(""), writers.join(", "));
+      if entry["ObjectIdentifier"].is_string() {
+         gpo_files.push(object);
+      } else {
+         netlogon_files.push(object);
+      }
+   }
+
+   if gpo_files.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "GPO",
+         format!("GPO file writable by non-admin ({} files and folders)", gpo_files.len()),
+         "Non-admin principals can modify GPO files in SYSVOL (scripts, scheduled tasks, security templates) and run code on every computer or user the GPO applies to. Restore the default GPO permissions.".to_string(),
+         gpo_files,
+      ));
+   }
+   if netlogon_files.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "GPO",
+         format!("NETLOGON file writable by non-admin ({} files and folders)", netlogon_files.len()),
+         "Non-admin principals can modify logon scripts in NETLOGON and run code on every user running them. Only admins should be able to write in NETLOGON.".to_string(),
+         netlogon_files,
+      ));
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_check_writable_files() {
+   let vec_shares = vec![
+      serde_json::json!({"Name": "\\\\meereen\\SYSVOL\\essos.local\\Policies\\{A}", "ObjectIdentifier": "A", "WritableBy": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1104"}]}),
+      serde_json::json!({"Name": "\\\\meereen\\NETLOGON\\logon.bat", "ObjectIdentifier": null, "WritableBy": [{"ObjectIdentifier": "ESSOS.LOCAL-S-1-5-11"}]}),
+      serde_json::json!({"Name": "\\\\braavos\\Public", "ObjectIdentifier": "S-1-5-21-1-2-3-1105", "WritableBy": []}),
+   ];
+   let vec_users = vec![serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "Properties": {"name": "VISERYS.TARGARYEN@ESSOS.LOCAL"}})];
+   let mut vec_findings: Vec<serde_json::value::Value> = Vec::new();
+   check_writable_files(&vec_shares, &vec_users, &Vec::new(), &Vec::new(), &mut vec_findings);
+   assert_eq!(vec_findings.len(), 2);
+   assert_eq!(vec_findings[0]["Objects"][0], "\\\\meereen\\SYSVOL\\essos.local\\Policies\\{A} (VISERYS.TARGARYEN@ESSOS.LOCAL)");
+   assert_eq!(vec_findings[1]["Objects"][0], "\\\\meereen\\NETLOGON\\logon.bat (ESSOS.LOCAL-S-1-5-11)");
+}
@@ -0,0 +1,60 @@
+use crate::json::report::make_finding;
+use crate::enums::date::return_current_epoch;
+
+/// Microsoft recommends to roll over the AZUREADSSOACC$ Kerberos key every 30 days.
+const SEAMLESS_SSO_MAX_AGE: i64 = 30;
+
+/// Check Entra Connect accounts, servers and Seamless SSO computer account.
+/// All of them give a path from on-premises to Entra ID (or the opposite) and must be managed as Tier-0 assets.
+/// <https://learn.microsoft.com/en-us/entra/identity/hybrid/connect/how-to-connect-sso-faq>
+pub fn check_hybrid_identity(vec_users: &Vec<serde_json::value::Value>, vec_computers: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let sync_accounts: Vec<String> = vec_users.iter()
+      .filter(|user| user["Properties"]["entrasyncaccount"].as_bool().unwrap_or(false))
+      .map(|user| user["Properties"]["name"].as_str().unwrap_or("").to_string())
+      .collect();
+   let sync_servers: Vec<String> = vec_computers.iter()
+      .filter(|computer| computer["Properties"]["entrasyncserver"].as_bool().unwrap_or(false))
+      .map(|computer| computer["Properties"]["name"].as_str().unwrap_or("").to_string())
+      .collect();
+
+   if sync_accounts.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "Hybrid identity",
+         format!("{} Entra Connect sync accounts (MSOL_) with DCSync rights", sync_accounts.len()),
+         "Entra Connect connector accounts can replicate all password hashes. Their password is stored on the Entra Connect server.".to_string(),
+         sync_accounts,
+      ));
+   }
+   if sync_servers.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "Hybrid identity",
+         format!("{} Entra Connect servers must be managed as Tier-0", sync_servers.len()),
+         "Local administrators of an Entra Connect server can extract the sync account credentials and compromise the domain and the Entra ID tenant.".to_string(),
+         sync_servers,
+      ));
+   }
+
+   let now = return_current_epoch();
+   for computer in vec_computers {
+      if !computer["Properties"]["entraseamlesssso"].as_bool().unwrap_or(false) {
+         continue
+      }
+      let pwdlastset = computer["Properties"]["pwdlastset"].as_i64().unwrap_or(-1);
+      if pwdlastset <= 0 {
+         continue
+      }
+      let age = (now - pwdlastset) / 86400;
+      if age > SEAMLESS_SSO_MAX_AGE {
+         vec_findings.push(make_finding(
+            "Medium",
+            "Hybrid identity",
+            format!("Seamless SSO key not rolled over for {} days", age),
+            "The AZUREADSSOACC$ Kerberos key can forge tickets to Entra ID for any synced user. It should be rolled over every 30 days.".to_string(),
+            vec![computer["Properties"]["name"].as_str().unwrap_or("").to_string()],
+         ));
+      }
+   }
+}
@@ -0,0 +1,117 @@
+use crate::json::report::make_finding;
+
+/// Function to get the name of the enabled users and computers matching one filter.
+fn enabled_names<F>(objects: &Vec<serde_json::value::Value>, filter: F) -> Vec<String>
+where
+   F: Fn(&serde_json::value::Value) -> bool,
+{
+   objects.iter()
+      .filter(|object| object["Properties"]["enabled"].as_bool().unwrap_or(true))
+      .filter(|object| filter(object))
+      .map(|object| object["Properties"]["name"].as_str().unwrap_or("").to_string())
+      .collect()
+}
+
+/// Check the Kerberos encryption types of users, computers and trusts (msDS-SupportedEncryptionTypes).
+/// DES keys are broken, RC4 tickets of kerberoastable accounts are cracked much faster than AES tickets
+/// and accounts without AES can be downgraded to RC4.
+pub fn check_encryption_types(
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_trusts: &Vec<serde_json::value::Value>,
+   vec_findings: &mut Vec<serde_json::value::Value>,
+)
+{
+   let is_true = |object: &serde_json::value::Value, property: &str| object["Properties"][property].as_bool().unwrap_or(false);
+
+   let mut des: Vec<String> = enabled_names(vec_users, |user| is_true(user, "desenabled"));
+   des.extend(enabled_names(vec_computers, |computer| is_true(computer, "desenabled")));
+   if des.len() > 0 {
+      des.sort();
+      vec_findings.push(make_finding(
+         "High",
+         "Kerberos",
+         format!("{} accounts with DES encryption enabled", des.len()),
+         "DES keys can be cracked in hours and DES tickets can be forged. Remove the UseDesKeyOnly flag and the DES encryption types, then reset the passwords.".to_string(),
+         des,
+      ));
+   }
+
+   // Without encryption types the domain default is used, RC4 included
+   let accepts_rc4 = |user: &serde_json::value::Value| {
+      user["Properties"]["rc4only"].is_null()
+         || user["Properties"]["supportedencryptiontypes"].as_array().unwrap_or(&Vec::new()).iter().any(|etype| etype == "RC4-HMAC-MD5")
+   };
+   let mut roastable: Vec<String> = enabled_names(vec_users, |user| {
+      is_true(user, "hasspn")
+         && !user["Properties"]["name"].as_str().unwrap_or("").to_uppercase().starts_with("KRBTGT")
+         && accepts_rc4(user)
+   });
+   if roastable.len() > 0 {
+      roastable.sort();
+      vec_findings.push(make_finding(
+         "Medium",
+         "Kerberos",
+         format!("{} kerberoastable users accept RC4 service tickets", roastable.len()),
+         "Service tickets of these users can be requested in RC4, which is cracked much faster than AES. Set msDS-SupportedEncryptionTypes to AES only (24) and reset their passwords to create AES keys.".to_string(),
+         roastable.to_owned(),
+      ));
+   }
+
+   // Kerberoastable users are already in the RC4 finding
+   let mut aes_missing: Vec<String> = enabled_names(vec_users, |user| is_true(user, "aesmissing"))
+      .into_iter()
+      .filter(|name| !roastable.contains(name))
+      .collect();
+   aes_missing.extend(enabled_names(vec_computers, |computer| is_true(computer, "aesmissing")));
+   if aes_missing.len() > 0 {
+      aes_missing.sort();
+      vec_findings.push(make_finding(
+         "Low",
+         "Kerberos",
+         format!("{} accounts without AES encryption types", aes_missing.len()),
+         "Kerberos tickets of these accounts use RC4 or DES only and can be downgraded. Add the AES encryption types once all their services support AES.".to_string(),
+         aes_missing,
+      ));
+   }
+
+   let rc4_trusts: Vec<String> = vec_trusts.iter()
+      .filter(|trust| trust["RC4Only"].as_bool().unwrap_or(false))
+      .map(|trust| trust["TargetDomainName"].as_str().unwrap_or("").to_string())
+      .collect();
+   if rc4_trusts.len() > 0 {
+      vec_findings.push(make_finding(
+         "Low",
+         "Trusts",
+         format!("{} trusts using RC4 only", rc4_trusts.len()),
+         "Referral tickets between these domains are encrypted with RC4. Enable AES on both sides of the trusts (The other domain supports Kerberos AES Encryption).".to_string(),
+         rc4_trusts,
+      ));
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_check_encryption_types() {
+   let vec_users = vec![
+      // Domain default, RC4 included
+      serde_json::json!({"Properties": {"name": "SQLSVC@ESSOS.LOCAL", "enabled": true, "hasspn": true}}),
+      serde_json::json!({"Properties": {"name": "WEBSVC@ESSOS.LOCAL", "enabled": true, "hasspn": true, "supportedencryptiontypes": ["AES256-CTS-HMAC-SHA1-96"], "rc4only": false, "aesmissing": false}}),
+      serde_json::json!({"Properties": {"name": "KRBTGT@ESSOS.LOCAL", "enabled": true, "hasspn": true}}),
+      serde_json::json!({"Properties": {"name": "OLDAPP@ESSOS.LOCAL", "enabled": true, "desenabled": true, "supportedencryptiontypes": ["DES-CBC-MD5"], "rc4only": false, "aesmissing": true}}),
+   ];
+   let vec_computers = vec![
+      serde_json::json!({"Properties": {"name": "NAS.ESSOS.LOCAL", "enabled": true, "supportedencryptiontypes": ["RC4-HMAC-MD5"], "rc4only": true, "aesmissing": true}}),
+   ];
+   let vec_trusts = vec![
+      serde_json::json!({"TargetDomainName": "SEVENKINGDOMS.LOCAL", "RC4Only": true}),
+      serde_json::json!({"TargetDomainName": "NORTH.SEVENKINGDOMS.LOCAL", "RC4Only": false}),
+   ];
+   let mut vec_findings = Vec::new();
+   check_encryption_types(&vec_users, &vec_computers, &vec_trusts, &mut vec_findings);
+   assert_eq!(vec_findings.len(), 4);
+   assert_eq!(vec_findings[0]["Objects"], serde_json::json!(["OLDAPP@ESSOS.LOCAL"]));
+   assert_eq!(vec_findings[1]["Objects"], serde_json::json!(["SQLSVC@ESSOS.LOCAL"]));
+   assert_eq!(vec_findings[2]["Objects"], serde_json::json!(["NAS.ESSOS.LOCAL", "OLDAPP@ESSOS.LOCAL"]));
+   assert_eq!(vec_findings[3]["Objects"], serde_json::json!(["SEVENKINGDOMS.LOCAL"]));
+}
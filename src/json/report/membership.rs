@@ -0,0 +1,112 @@
+//! Transitive group membership
+//!
+//! ACE trustees are often groups, [`MembershipResolver`] unrolls them through nested groups and primary groups
+//! so findings can list the effective users and computers which have one right, like DCSync.
+use std::collections::{HashMap, HashSet};
+
+/// Resolver of the nested members of every collected group.
+pub struct MembershipResolver {
+   /// Group SID to direct members SID
+   members: HashMap<String, Vec<String>>,
+   /// User and computer SIDs to their name
+   accounts: HashMap<String, String>,
+   /// Group SIDs to their name
+   groups: HashMap<String, String>,
+}
+
+impl MembershipResolver {
+   /// Function to index the members of all groups, primary groups included.
+   pub fn new(
+      vec_users: &Vec<serde_json::value::Value>,
+      vec_groups: &Vec<serde_json::value::Value>,
+      vec_computers: &Vec<serde_json::value::Value>,
+   ) -> Self
+   {
+      let mut members: HashMap<String, Vec<String>> = HashMap::new();
+      let mut groups: HashMap<String, String> = HashMap::new();
+      for group in vec_groups {
+         let sid = group["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+         if let Some(name) = group["Properties"]["name"].as_str() {
+            groups.insert(sid.to_owned(), name.to_string());
+         }
+         let entry = members.entry(sid).or_default();
+         for member in group["Members"].as_array().unwrap_or(&Vec::new()) {
+            if let Some(member) = member["ObjectIdentifier"].as_str() {
+               entry.push(member.to_string());
+            }
+         }
+      }
+      let mut accounts: HashMap<String, String> = HashMap::new();
+      for account in vec_users.iter().chain(vec_computers.iter()) {
+         let sid = account["ObjectIdentifier"].as_str().unwrap_or("").to_string();
+         if sid.is_empty() {
+            continue
+         }
+         accounts.insert(sid.to_owned(), account["Properties"]["name"].as_str().unwrap_or(&sid).to_string());
+         // Primary group membership isn't in the member attribute
+         if let Some(primary_group) = account["PrimaryGroupSID"].as_str() {
+            let entry = members.entry(primary_group.to_string()).or_default();
+            if !entry.contains(&sid) {
+               entry.push(sid);
+            }
+         }
+      }
+      MembershipResolver { members, accounts, groups }
+   }
+
+   /// Function to get the users and computers SIDs of one trustee: itself for an account,
+   /// all nested members for a group. Cycles between groups are followed only once.
+   pub fn effective_accounts(&self, sid: &str) -> Vec<String> {
+      let mut accounts: Vec<String> = Vec::new();
+      let mut visited: HashSet<String> = HashSet::new();
+      let mut queue: Vec<String> = vec![sid.to_string()];
+      while let Some(sid) = queue.pop() {
+         if !visited.insert(sid.to_owned()) {
+            continue
+         }
+         if self.accounts.contains_key(&sid) {
+            accounts.push(sid.to_owned());
+         }
+         if let Some(members) = self.members.get(&sid) {
+            queue.extend(members.iter().cloned());
+         }
+      }
+      accounts.sort();
+      accounts
+   }
+
+   /// Function to get the name of one user, computer or group, the SID if not collected.
+   pub fn get_name(&self, sid: &str) -> String {
+      self.accounts.get(sid).or(self.groups.get(sid)).cloned().unwrap_or(sid.to_string())
+   }
+
+   /// Function to get the names of the users and computers of several trustees, without duplicates.
+   pub fn effective_names(&self, sids: &[String]) -> Vec<String> {
+      let mut names: Vec<String> = sids.iter()
+         .flat_map(|sid| self.effective_accounts(sid))
+         .map(|sid| self.get_name(&sid))
+         .collect();
+      names.sort();
+      names.dedup();
+      names
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_effective_accounts() {
+   let vec_users = vec![
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "PrimaryGroupSID": "S-1-5-21-1-2-3-513", "Properties": {"name": "JON@NORTH.LOCAL"}}),
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1105", "PrimaryGroupSID": "S-1-5-21-1-2-3-513", "Properties": {"name": "ARYA@NORTH.LOCAL"}}),
+   ];
+   let vec_groups = vec![
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1200", "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1201", "ObjectType": "Group"}]}),
+      // Cycle between both groups
+      serde_json::json!({"ObjectIdentifier": "S-1-5-21-1-2-3-1201", "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "ObjectType": "User"}, {"ObjectIdentifier": "S-1-5-21-1-2-3-1200", "ObjectType": "Group"}]}),
+   ];
+   let resolver = MembershipResolver::new(&vec_users, &vec_groups, &Vec::new());
+   assert_eq!(resolver.effective_accounts("S-1-5-21-1-2-3-1200"), vec!["S-1-5-21-1-2-3-1104"]);
+   assert_eq!(resolver.effective_accounts("S-1-5-21-1-2-3-513").len(), 2);
+   assert_eq!(resolver.effective_accounts("S-1-5-21-1-2-3-1105"), vec!["S-1-5-21-1-2-3-1105"]);
+   assert_eq!(resolver.effective_names(&["S-1-5-21-1-2-3-1200".to_string(), "S-1-5-21-1-2-3-1104".to_string()]), vec!["JON@NORTH.LOCAL"]);
+}
@@ -0,0 +1,153 @@
+//! Findings report
+//!
+//! Analysis passes over the collected objects to highlight weaknesses found in the Active Directory.
+//! Findings are written in the `<datetime>_<domain>_report.json` file next to the BloodHound json files.
+//! This file is never added in the zip archive since BloodHound can't import it.
+//! Findings can be written in SARIF too with **--sarif**, see [`crate::json::report::sarif`].
+//! With **--audit** the DACL of Tier-0 objects is compared with a known-good baseline, see [`crate::json::report::audit`].
+//! The service accounts inventory is written in the `<datetime>_<domain>_service_accounts.json` file.
+use colored::Colorize;
+use log::{info,debug};
+
+use crate::args::Options;
+use crate::json::templates::bh_41::prepare_finding_json_template;
+
+pub mod audit;
+pub mod control;
+pub mod domain;
+pub mod accounts;
+pub mod files;
+pub mod delegation;
+pub mod hybrid;
+pub mod kerberos;
+pub mod membership;
+pub mod pki;
+pub mod sarif;
+pub mod service;
+
+/// Function to run all analysis passes and return findings.
+pub fn make_report(
+   common_args: &Options,
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_groups: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_ous: &Vec<serde_json::value::Value>,
+   vec_domains: &Vec<serde_json::value::Value>,
+   vec_containers: &Vec<serde_json::value::Value>,
+   vec_trusts: &Vec<serde_json::value::Value>,
+   vec_templates: &Vec<serde_json::value::Value>,
+   vec_service_accounts: &Vec<serde_json::value::Value>,
+   vec_authn: &Vec<serde_json::value::Value>,
+   vec_shares: &Vec<serde_json::value::Value>,
+) -> Vec<serde_json::value::Value>
+{
+   info!("Starting analysis for findings report...");
+   let mut vec_findings: Vec<serde_json::value::Value> = Vec::new();
+
+   debug!("Checking functional levels");
+   domain::check_functional_levels(vec_domains, &mut vec_findings);
+
+   debug!("Checking krbtgt and trust accounts password age");
+   domain::check_krbtgt_password_age(vec_domains, common_args.krbtgt_max_age, &mut vec_findings);
+   domain::check_trust_accounts_password_age(vec_users, common_args.trust_max_age, &mut vec_findings);
+
+   debug!("Checking DCSync principals");
+   let resolver = membership::MembershipResolver::new(vec_users, vec_groups, vec_computers);
+   domain::check_dcsync_principals(vec_domains, vec_computers, &resolver, &mut vec_findings);
+
+   debug!("Checking trusts");
+   domain::check_trusts(vec_trusts, &mut vec_findings);
+
+   debug!("Checking duplicate spns and sAMAccountName anomalies");
+   accounts::check_duplicate_spns(vec_users, vec_computers, &mut vec_findings);
+   accounts::check_samaccountname_anomalies(vec_users, vec_computers, &mut vec_findings);
+
+   debug!("Checking computers created with MachineAccountQuota");
+   accounts::check_precreated_computers(vec_computers, &mut vec_findings);
+
+   debug!("Checking computers without LAPS");
+   accounts::check_laps_absent(vec_computers, &mut vec_findings);
+
+   debug!("Checking conflict objects and orphaned members");
+   accounts::check_conflict_objects(&[vec_users, vec_groups, vec_computers, vec_ous, vec_containers], vec_groups, &mut vec_findings);
+
+   debug!("Checking SID history injection");
+   accounts::check_sid_history(vec_users, vec_groups, vec_computers, &mut vec_findings);
+
+   debug!("Checking end-of-life operating systems");
+   accounts::check_eol_operating_systems(vec_computers, &mut vec_findings);
+
+   debug!("Checking service accounts");
+   service::check_service_accounts(vec_service_accounts, &mut vec_findings);
+
+   debug!("Making Tier-0 summary");
+   accounts::check_tier0_summary(vec_users, vec_computers, &mut vec_findings);
+
+   debug!("Checking credential protections of admins");
+   accounts::check_credential_protections(vec_users, &mut vec_findings);
+   accounts::check_silo_exempt_tier0(vec_users, vec_computers, vec_authn, &mut vec_findings);
+
+   debug!("Checking Kerberos encryption types");
+   kerberos::check_encryption_types(vec_users, vec_computers, vec_trusts, &mut vec_findings);
+
+   debug!("Checking unconstrained delegation");
+   delegation::check_unconstrained_delegation(vec_users, vec_computers, &mut vec_findings);
+
+   debug!("Checking vulnerable certificate templates");
+   pki::check_vulnerable_templates(vec_templates, &mut vec_findings);
+
+   debug!("Checking hybrid identity");
+   hybrid::check_hybrid_identity(vec_users, vec_computers, &mut vec_findings);
+
+   debug!("Checking SYSVOL and NETLOGON files writable by non-admins");
+   files::check_writable_files(vec_shares, vec_users, vec_groups, vec_computers, &mut vec_findings);
+
+   debug!("Making inbound control summary of Tier-0 groups and domains");
+   control::check_inbound_control(vec_groups, vec_domains, &resolver, &mut vec_findings);
+
+   if common_args.audit {
+      debug!("Auditing Tier-0 ACLs");
+      audit::check_tier0_acls(vec_domains, vec_ous, vec_containers, &resolver, &mut vec_findings);
+   }
+
+   for finding in &vec_findings {
+      info!("[{}] {}", severity_colored(finding["Severity"].as_str().unwrap_or("Info")), finding["Title"].as_str().unwrap_or(""));
+   }
+   info!("{} findings in report!", vec_findings.len().to_string().bold());
+   vec_findings
+}
+
+/// Function to make one finding.
+pub fn make_finding(
+   severity: &str,
+   category: &str,
+   title: String,
+   description: String,
+   objects: Vec<String>,
+) -> serde_json::value::Value
+{
+   let mut finding = prepare_finding_json_template();
+   finding["Severity"] = severity.into();
+   finding["Category"] = category.into();
+   finding["Title"] = title.into();
+   finding["Description"] = description.into();
+   finding["Objects"] = objects.into();
+   finding
+}
+
+/// Function to get the values of one JSON array, empty if the value isn't an array.
+/// The values borrow the object, not a temporary empty Vec.
+pub fn get_array(value: &serde_json::value::Value) -> &[serde_json::value::Value]
+{
+   value.as_array().map(|array| array.as_slice()).unwrap_or(&[])
+}
+
+/// Color severity for logs.
+fn severity_colored(severity: &str) -> colored::ColoredString {
+   match severity {
+      "High" => severity.red().bold(),
+      "Medium" => severity.yellow().bold(),
+      "Low" => severity.green().bold(),
+      _ => severity.normal(),
+   }
+}
@@ -0,0 +1,46 @@
+use crate::json::report::make_finding;
+
+/// Check enabled certificate templates vulnerable to ESC1, ESC2 and ESC3 without manager approval or authorized signatures.
+/// Enrollment rights are not checked here, BloodHound shows who can enroll.
+pub fn check_vulnerable_templates(vec_templates: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let mut esc1: Vec<String> = Vec::new();
+   let mut esc2: Vec<String> = Vec::new();
+   let mut esc3: Vec<String> = Vec::new();
+   for template in vec_templates {
+      let properties = &template["Properties"];
+      let flag = |key: &str| properties[key].as_bool().unwrap_or(false);
+      if !flag("Enabled")
+      || flag("Requires Manager Approval")
+      || properties["Authorized Signatures Required"].as_i64().unwrap_or(0) > 0
+      {
+         continue
+      }
+      let name = properties["name"].as_str().unwrap_or("").to_string();
+      if flag("Enrollee Supplies Subject") && flag("Client Authentication") {
+         esc1.push(name.to_owned());
+      }
+      // "Any purpose" is set by the ADCS parser
+      if flag("Any purpose") || flag("Any Purpose") {
+         esc2.push(name.to_owned());
+      }
+      if flag("Enrollment Agent") {
+         esc3.push(name);
+      }
+   }
+   for (esc, objects, description) in [
+      ("ESC1", esc1, "Enrollees can request a certificate for any user, like a domain admin, and use it for client authentication."),
+      ("ESC2", esc2, "Certificates with Any Purpose or without EKU can be used for client authentication or as enrollment agent certificates."),
+      ("ESC3", esc3, "Certificate Request Agent certificates can be used to request certificates on behalf of any user."),
+   ] {
+      if objects.len() > 0 {
+         vec_findings.push(make_finding(
+            "High",
+            "PKI",
+            format!("{} certificate templates vulnerable to {}", objects.len(), esc),
+            format!("{} Check enrollment rights on these templates and require manager approval or remove the vulnerable settings.", description),
+            objects,
+         ));
+      }
+   }
+}
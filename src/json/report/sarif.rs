@@ -0,0 +1,91 @@
+//! SARIF output of the findings report
+//!
+//! One rule per kind of finding and one result per finding, objects are logical locations.
+//! The rule id is made from the category and the title without counts,
+//! so the same weakness keeps the same id between runs.
+use serde_json::json;
+
+/// SARIF version written
+pub const SARIF_VERSION: &str = "2.1.0";
+
+/// Function to make the SARIF log from the findings.
+pub fn make_sarif(vec_findings: &Vec<serde_json::value::Value>, domain: &str) -> serde_json::value::Value
+{
+   let mut rules: Vec<serde_json::value::Value> = Vec::new();
+   let mut results: Vec<serde_json::value::Value> = Vec::new();
+   for finding in vec_findings {
+      let id = get_rule_id(finding);
+      let title = finding["Title"].as_str().unwrap_or("");
+      let level = get_level(finding["Severity"].as_str().unwrap_or("Info"));
+      if !rules.iter().any(|rule| rule["id"] == id.as_str()) {
+         rules.push(json!({
+            "id": id,
+            "name": title,
+            "shortDescription": { "text": title },
+            "fullDescription": { "text": finding["Description"] },
+            "defaultConfiguration": { "level": level },
+            "properties": { "category": finding["Category"], "severity": finding["Severity"] }
+         }));
+      }
+      let locations: Vec<serde_json::value::Value> = finding["Objects"].as_array().unwrap_or(&Vec::new()).iter()
+         .filter_map(|object| object.as_str())
+         .map(|object| json!({ "logicalLocations": [{ "name": object, "kind": "object" }] }))
+         .collect();
+      results.push(json!({
+         "ruleId": id,
+         "level": level,
+         "message": { "text": format!("{} on {}. {}", title, domain.to_uppercase(), finding["Description"].as_str().unwrap_or("")) },
+         "locations": locations,
+         "properties": { "domain": domain.to_uppercase() }
+      }));
+   }
+   json!({
+      "version": SARIF_VERSION,
+      "runs": [{
+         "tool": {
+            "driver": {
+               "name": "RustHound",
+               "version": env!("CARGO_PKG_VERSION"),
+               "rules": rules
+            }
+         },
+         "results": results
+      }]
+   })
+}
+
+/// Function to get a stable rule id: `Accounts/computers-without-laps`.
+pub fn get_rule_id(finding: &serde_json::value::Value) -> String {
+   let title: Vec<String> = finding["Title"].as_str().unwrap_or("").split_whitespace()
+      .filter(|word| !word.chars().all(|c| c.is_ascii_digit()))
+      .map(|word| word.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-').collect::<String>().to_lowercase())
+      .filter(|word| !word.is_empty())
+      .collect();
+   format!("{}/{}", finding["Category"].as_str().unwrap_or("Other"), title.join("-"))
+}
+
+/// Function to get the SARIF level from the finding severity.
+fn get_level(severity: &str) -> &'static str {
+   match severity {
+      "High" => "error",
+      "Medium" => "warning",
+      "Low" => "note",
+      _ => "none",
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_make_sarif() {
+   use crate::json::report::make_finding;
+   let vec_findings = vec![
+      make_finding("Medium", "Accounts", "2 computers without LAPS".to_string(), "No LAPS.".to_string(), vec!["WS01.ESSOS.LOCAL".to_string(), "WS02.ESSOS.LOCAL".to_string()]),
+      make_finding("High", "PKI", "1 certificate templates vulnerable to ESC1".to_string(), "ESC1.".to_string(), vec!["ESC1@ESSOS.LOCAL".to_string()]),
+   ];
+   assert_eq!(get_rule_id(&vec_findings[0]), "Accounts/computers-without-laps");
+   let sarif = make_sarif(&vec_findings, "essos.local");
+   assert_eq!(sarif["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+   assert_eq!(sarif["runs"][0]["results"][0]["level"], "warning");
+   assert_eq!(sarif["runs"][0]["results"][0]["locations"].as_array().unwrap().len(), 2);
+   assert_eq!(sarif["runs"][0]["results"][1]["ruleId"], "PKI/certificate-templates-vulnerable-to-esc1");
+}
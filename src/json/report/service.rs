@@ -0,0 +1,148 @@
+use regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::json::report::make_finding;
+use crate::json::templates::bh_41::prepare_service_account_json_template;
+use crate::enums::date::return_current_epoch;
+
+lazy_static! {
+   /// svc_sql, sql-svc, app_sa, srv_backup, serviceaccount...
+   static ref RE_SERVICE_NAME: Regex = Regex::new(r"(?i)(^svc|svc$|^srv[_-]|^sa[_-]|[_-]sa$|service)").unwrap();
+}
+
+/// Passwords older than one year are likely never rotated
+const PASSWORD_MAX_AGE: i64 = 365;
+
+/// Function to make the service accounts inventory with a risk score for each account.
+/// Service accounts are enabled users with spn or with a service account name.
+pub fn make_service_accounts(vec_users: &Vec<serde_json::value::Value>, vec_groups: &Vec<serde_json::value::Value>) -> Vec<serde_json::value::Value>
+{
+   let now = return_current_epoch();
+   let mut vec_service_accounts: Vec<serde_json::value::Value> = Vec::new();
+   for user in vec_users {
+      let properties = &user["Properties"];
+      let samaccountname = properties["samaccountname"].as_str().unwrap_or("");
+      let has_spn = properties["hasspn"].as_bool().unwrap_or(false);
+      let service_name = is_service_name(samaccountname);
+      if properties["enabled"] == false
+         || samaccountname.is_empty()
+         || samaccountname.to_lowercase().starts_with("krbtgt")
+         || properties["trustaccount"].as_bool().unwrap_or(false)
+         || (!has_spn && !service_name)
+      {
+         continue
+      }
+
+      let mut score = 0;
+      let mut reasons: Vec<String> = Vec::new();
+      if has_spn {
+         score += 2;
+         reasons.push("Kerberoastable (spn)".to_string());
+      }
+      if service_name {
+         reasons.push("Service account name".to_string());
+      }
+      let pwdlastset = properties["pwdlastset"].as_i64().unwrap_or(-1);
+      let password_age = if pwdlastset > 0 { (now - pwdlastset) / 86400 } else { -1 };
+      if password_age > PASSWORD_MAX_AGE {
+         score += 2;
+         reasons.push(format!("Password not changed for {} days", password_age));
+      }
+      if properties["pwdneverexpires"].as_bool().unwrap_or(false) {
+         score += 1;
+         reasons.push("Password never expires".to_string());
+      }
+      if properties["dontreqpreauth"].as_bool().unwrap_or(false) {
+         score += 3;
+         reasons.push("AS-REP roastable".to_string());
+      }
+      if properties["unconstraineddelegation"].as_bool().unwrap_or(false) {
+         score += 3;
+         reasons.push("Unconstrained delegation".to_string());
+      }
+      if properties["trustedtoauth"].as_bool().unwrap_or(false) {
+         score += 2;
+         reasons.push("Constrained delegation with protocol transition".to_string());
+      }
+      // Interactive use of a service account, lastlogon is not replicated so lastlogontimestamp is used too
+      let lastlogon = properties["lastlogon"].as_i64().unwrap_or(-1).max(properties["lastlogontimestamp"].as_i64().unwrap_or(-1));
+      if lastlogon <= 0 {
+         reasons.push("Never logged on".to_string());
+      }
+
+      let sid = user["ObjectIdentifier"].as_str().unwrap_or("");
+      let privileged_groups: Vec<String> = vec_groups.iter()
+         .filter(|group| group["Properties"]["highvalue"].as_bool().unwrap_or(false))
+         .filter(|group| group["Members"].as_array().unwrap_or(&Vec::new()).iter().any(|member| member["ObjectIdentifier"] == sid))
+         .map(|group| group["Properties"]["name"].as_str().unwrap_or("").to_string())
+         .collect();
+      let privileged = privileged_groups.len() > 0 || properties["admincount"].as_bool().unwrap_or(false);
+      if privileged {
+         score += 4;
+         if privileged_groups.len() > 0 {
+            reasons.push(format!("Member of {}", privileged_groups.join(", ")));
+         } else {
+            reasons.push("Protected by AdminSDHolder (admincount)".to_string());
+         }
+      }
+
+      let mut service_account = prepare_service_account_json_template();
+      service_account["Name"] = properties["name"].to_owned();
+      service_account["ObjectIdentifier"] = sid.into();
+      service_account["HasSPN"] = has_spn.into();
+      service_account["PasswordAge"] = password_age.into();
+      service_account["LastLogon"] = lastlogon.into();
+      service_account["Privileged"] = privileged.into();
+      service_account["Score"] = score.into();
+      service_account["Risk"] = risk_level(score).into();
+      service_account["Reasons"] = reasons.into();
+      vec_service_accounts.push(service_account);
+   }
+   vec_service_accounts.sort_by(|a, b| b["Score"].as_i64().cmp(&a["Score"].as_i64()));
+   vec_service_accounts
+}
+
+/// Check high risk service accounts from the inventory.
+pub fn check_service_accounts(vec_service_accounts: &Vec<serde_json::value::Value>, vec_findings: &mut Vec<serde_json::value::Value>)
+{
+   let objects: Vec<String> = vec_service_accounts.iter()
+      .filter(|service_account| service_account["Risk"] == "High")
+      .map(|service_account| format!("{} (score {})",
+         service_account["Name"].as_str().unwrap_or(""),
+         service_account["Score"].as_i64().unwrap_or(0),
+      ))
+      .collect();
+   if objects.len() > 0 {
+      vec_findings.push(make_finding(
+         "High",
+         "Accounts",
+         format!("{} high risk service accounts", objects.len()),
+         "Privileged service accounts with a spn or an old password can be kerberoasted and cracked offline. Use gMSA or long random passwords and remove them from privileged groups. See service_accounts.json for all service accounts.".to_string(),
+         objects,
+      ));
+   }
+}
+
+/// Function to check service account naming conventions.
+pub fn is_service_name(samaccountname: &str) -> bool {
+   RE_SERVICE_NAME.is_match(samaccountname)
+}
+
+fn risk_level(score: i64) -> &'static str {
+   match score {
+      s if s >= 8 => "High",
+      s if s >= 4 => "Medium",
+      _ => "Low",
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_is_service_name() {
+   assert!(is_service_name("svc_sql"));
+   assert!(is_service_name("sql-svc"));
+   assert!(is_service_name("backup_sa"));
+   assert!(is_service_name("SRV-Backup"));
+   assert!(!is_service_name("samwell.tarly"));
+   assert!(!is_service_name("jon.snow"));
+}
@@ -0,0 +1,224 @@
+//! Machine-readable run summary (stats.json)
+//!
+//! Written at the end of every run with the object counts per type, edge counts by kind,
+//! duration per phase, warnings count, bytes received from LDAP, domain controller used with its clock skew in seconds and the names and OS build disclosed by NTLM and options in effect.
+//! Pipelines can read it to know if a collection was complete without parsing stdout: `complete` is false and
+//! `partial` lists the reasons when some objects or attributes were lost (naming context search, referral,
+//! security descriptor, parser worker or SYSVOL failure, or phases stopped by **--max-runtime**), exit code 10 (nothing written with **--fail-on-partial**).
+//!
+use log::{Log, Metadata, Record, Level};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::args::Options;
+use crate::enums::date::get_clock_skew;
+use crate::ldap::{ntlm_info, received_bytes};
+
+/// Number of warnings and errors logged during the run
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// Logger counting warnings and errors before forwarding them to env_logger.
+struct CountingLogger {
+   inner: env_logger::Logger,
+}
+
+impl Log for CountingLogger {
+   fn enabled(&self, metadata: &Metadata) -> bool {
+      self.inner.enabled(metadata)
+   }
+
+   fn log(&self, record: &Record) {
+      if record.level() <= Level::Warn && record.target().starts_with("rusthound") {
+         WARNINGS.fetch_add(1, Ordering::SeqCst);
+      }
+      self.inner.log(record)
+   }
+
+   fn flush(&self) {
+      self.inner.flush()
+   }
+}
+
+/// Function to init env_logger wrapped in the counting logger.
+pub fn init_logger(verbose: log::LevelFilter) {
+   let inner = env_logger::Builder::new()
+      .filter(Some("rusthound"), verbose)
+      .filter_level(log::LevelFilter::Error)
+      .build();
+   log::set_max_level(inner.filter());
+   log::set_boxed_logger(Box::new(CountingLogger { inner })).expect("Logger already initialized");
+}
+
+/// Get the number of warnings and errors logged.
+pub fn warnings_count() -> usize {
+   WARNINGS.load(Ordering::SeqCst)
+}
+
+/// Reasons of a partial collection
+static PARTIAL: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Record that some objects or attributes are missing from the results.
+pub fn mark_partial(reason: String) {
+   if let Ok(mut partial) = PARTIAL.lock() {
+      partial.push(reason);
+   }
+}
+
+/// Get the reasons of a partial collection, empty if complete.
+pub fn partial_reasons() -> Vec<String> {
+   PARTIAL.lock().map(|partial| partial.to_owned()).unwrap_or_default()
+}
+
+/// Forget the reasons of the previous run (daemon mode).
+pub fn reset_partial() {
+   if let Ok(mut partial) = PARTIAL.lock() {
+      partial.clear();
+   }
+}
+
+/// Structure containing the duration of each phase.
+pub struct RunStats {
+   start: Instant,
+   last: Instant,
+   phases: Vec<(String, f64)>,
+}
+
+impl RunStats {
+   pub fn new() -> Self {
+      RunStats { start: Instant::now(), last: Instant::now(), phases: Vec::new() }
+   }
+
+   /// Close the current phase and save its duration.
+   pub fn phase(&mut self, name: &str) {
+      let now = Instant::now();
+      self.phases.push((name.to_string(), now.duration_since(self.last).as_secs_f64()));
+      self.last = now;
+   }
+
+   /// Get total duration and duration per phase in seconds.
+   pub fn durations(&self) -> serde_json::value::Value {
+      let mut phases = serde_json::Map::new();
+      for (name, duration) in &self.phases {
+         phases.insert(name.to_owned(), (*duration).into());
+      }
+      json!({
+         "total": self.start.elapsed().as_secs_f64(),
+         "phases": phases,
+      })
+   }
+}
+
+/// Count edges by kind for one object.
+fn count_edges(object: &serde_json::value::Value, edges: &mut BTreeMap<String, usize>) {
+   if let Some(aces) = object["Aces"].as_array() {
+      for ace in aces {
+         let right = ace["RightName"].as_str().unwrap_or("Unknown").to_string();
+         *edges.entry(right).or_insert(0) += 1;
+      }
+   }
+   // BloodHound edge names for other arrays
+   let arrays = [
+      ("Members", "MemberOf"),
+      ("AllowedToDelegate", "AllowedToDelegate"),
+      ("AllowedToAct", "AllowedToAct"),
+      ("HasSIDHistory", "HasSIDHistory"),
+      ("Links", "GPLink"),
+      ("ChildObjects", "Contains"),
+      ("Trusts", "TrustedBy"),
+   ];
+   for (field, edge) in arrays.iter() {
+      let count = object[*field].as_array().map(|a| a.len()).unwrap_or(0);
+      if count > 0 {
+         *edges.entry(edge.to_string()).or_insert(0) += count;
+      }
+   }
+}
+
+/// Count computers by operating system release and end-of-life ones.
+fn count_operating_systems(vec_computers: &Vec<serde_json::value::Value>) -> serde_json::value::Value {
+   let mut releases: BTreeMap<String, usize> = BTreeMap::new();
+   let mut eol: usize = 0;
+   for computer in vec_computers {
+      let release = computer["Properties"]["osrelease"].as_str().unwrap_or("Unknown").to_string();
+      *releases.entry(release).or_insert(0) += 1;
+      if computer["Properties"]["oseol"].as_bool().unwrap_or(false) {
+         eol += 1;
+      }
+   }
+   json!({
+      "releases": releases,
+      "eol": eol,
+   })
+}
+
+/// Function to prepare stats.json content.
+pub fn make_stats(
+   common_args: &Options,
+   run_stats: &RunStats,
+   objects: &[(&str, &Vec<serde_json::value::Value>)],
+   findings: usize,
+) -> serde_json::value::Value {
+   let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+   let mut edges: BTreeMap<String, usize> = BTreeMap::new();
+   let mut operating_systems = serde_json::value::Value::Null;
+   for (name, vec_objects) in objects {
+      counts.insert(name.to_string(), vec_objects.len());
+      if *name == "computers" {
+         operating_systems = count_operating_systems(vec_objects);
+      }
+      for object in vec_objects.iter() {
+         count_edges(object, &mut edges);
+      }
+   }
+
+   json!({
+      "version": env!("CARGO_PKG_VERSION"),
+      "domain": common_args.domain.to_uppercase(),
+      "dc": {
+         "fqdn": common_args.ldapfqdn,
+         "ip": common_args.ip,
+         "port": common_args.port,
+         "ldaps": common_args.ldaps || common_args.port == "636",
+         "clock_skew": get_clock_skew(),
+         "ntlm": ntlm_info(),
+      },
+      "complete": partial_reasons().is_empty(),
+      "partial": partial_reasons(),
+      "objects": counts,
+      "edges": edges,
+      "operating_systems": operating_systems,
+      "findings": findings,
+      "warnings": warnings_count(),
+      "ldap_bytes": received_bytes(),
+      "duration": run_stats.durations(),
+      "options": {
+         "kerberos": common_args.kerberos,
+         "dc_only": common_args.dc_only,
+         "collection_methods": common_args.collection_methods,
+         "adcs": common_args.adcs,
+         "old_bloodhound": common_args.old_bloodhound,
+         "fqdn_resolver": common_args.fqdn_resolver,
+         "mssql_probe": common_args.mssql_probe,
+         "probe_coercion": common_args.probe_coercion,
+         "probe_smb": common_args.probe_smb,
+         "probe_winrm": common_args.probe_winrm,
+         "probe_rdp": common_args.probe_rdp,
+         "shares": common_args.shares,
+         "dns_tcp": common_args.dns_tcp,
+         "dns_timeout": common_args.dns_timeout,
+         "zip": common_args.zip,
+         "ndjson": common_args.ndjson,
+         "no_disk": common_args.no_disk,
+         "stdout": common_args.stdout,
+         "http_sink": !common_args.http_url.contains("not set"),
+         "max_bandwidth": common_args.max_bandwidth,
+         "max_runtime": common_args.max_runtime,
+         "edge_provenance": common_args.edge_provenance,
+         "privileged_groups": common_args.privileged_groups,
+         "stale_days": common_args.stale_days,
+      },
+   })
+}
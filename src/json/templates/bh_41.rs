@@ -30,6 +30,10 @@ pub fn prepare_user_json_template() -> serde_json::value::Value
          "displayname": null,
          "email": null,
          "title": null,
+         "department": null,
+         "company": null,
+         "manager": null,
+         "directreports": 0,
          "homedirectory": null,
          "logonscript": null,
          "samaccountname": null,
@@ -39,7 +43,19 @@ pub fn prepare_user_json_template() -> serde_json::value::Value
          "sfupassword": null,
          "admincount": false,
          "sidhistory": [],
-         "allowedtodelegate": []
+         "allowedtodelegate": [],
+         "mssqlinstances": [],
+         "trustaccount": false,
+         "samaccounttype": null,
+         "duplicatespn": false,
+         "samaccountnameanomaly": false,
+         "coercetotgt": false,
+         "entrasyncaccount": false,
+         "rodckrbtgt": false,
+         "rodcname": null,
+         "entraobjectid": null,
+         "onpremimmutableid": null,
+         "onpremsynctarget": false
       },
       "PrimaryGroupSID": null,
       "SPNTargets": [],
@@ -66,7 +82,10 @@ pub fn prepare_group_json_template() -> serde_json::value::Value
          "highvalue": false,
          "admincount": false,
          "description": null,
-         "whencreated": -1
+         "whencreated": -1,
+         "entraobjectid": null,
+         "onpremimmutableid": null,
+         "onpremsynctarget": false
       },
       "Members": [],
       "Aces": [],
@@ -99,6 +118,37 @@ pub fn prepare_computer_json_template() -> serde_json::value::Value
          "serviceprincipalnames": [],
          "operatingsystem": null,
          "sidhistory": [],
+         "mssqlinstances": [],
+         "site": null,
+         "samaccounttype": null,
+         "duplicatespn": false,
+         "samaccountnameanomaly": false,
+         "coercetotgt": false,
+         "spooleravailable": null,
+         "webclientavailable": null,
+         "smbsigningrequired": null,
+         "smbv1": null,
+         "smbdialect": null,
+         "psremote_reachable": null,
+         "wmi_reachable": null,
+         "winrmversion": null,
+         "rdpavailable": null,
+         "rdpnlarequired": null,
+         "rdpcertcn": null,
+         "entrasyncserver": false,
+         "entraseamlesssso": false,
+         "creatorsid": null,
+         "creatorname": null,
+         "precreated": false,
+         "isrodc": false,
+         "managedby": null,
+         "revealondemandgroups": [],
+         "neverrevealgroups": [],
+         "revealedusers": [],
+         "krbtgtlink": null,
+         "entraobjectid": null,
+         "onpremimmutableid": null,
+         "onpremsynctarget": false,
       },
       "PrimaryGroupSID": "PGSID",
       "Aces": [],
@@ -192,6 +242,7 @@ pub fn prepare_gpo_json_template() -> serde_json::value::Value
          "highvalue": false,
          "description": null,
          "gpcpath": "GPO_PATH",
+         "gpppassword": false,
          "whencreated": -1
       },
       "ObjectIdentifier": "SID",
@@ -218,6 +269,12 @@ pub fn prepare_domain_json_template() -> serde_json::value::Value
          "highvalue": true,
          "whencreated": -1,
          "functionallevel": "Unknown",
+         "behaviorversion": -1,
+         "forestfunctionallevel": "Unknown",
+         "forestbehaviorversion": -1,
+         "schemaversion": -1,
+         "schemaname": "Unknown",
+         "krbtgtpasswordlastset": -1,
       },
       // Todo
       "GPOChanges": {
@@ -269,6 +326,25 @@ pub fn prepare_container_json_template() -> serde_json::value::Value
    });
 }
 
+/// Return the json template for one contact, a Base node since BloodHound has no contact kind
+pub fn prepare_contact_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "ObjectIdentifier": "GUID",
+      "IsDeleted": false,
+      "IsACLProtected": false,
+      "Properties": {
+         "name": "xyz@domain.com",
+         "domain": "domain.local",
+         "domainsid": "SID",
+         "distinguishedname": "DN",
+         "highvalue": false,
+         "contact": true,
+      },
+      "Aces": [],
+   });
+}
+
 /// Return the json template for one member
 pub fn prepare_member_json_template() -> serde_json::value::Value
 {
@@ -361,6 +437,153 @@ pub fn prepare_mssqlsvc_spn_json_template() -> serde_json::value::Value
    });
 }
 
+/// Return the json template for one MSSQL instance found in MSSQLSvc spn
+pub fn prepare_mssql_instance_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "Host": "",
+      "Instance": null,
+      "Port": 1433,
+      "ServiceAccountSID": null,
+      "Version": null
+   });
+}
+
+/// Return the json template for one site
+pub fn prepare_site_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "ObjectIdentifier": "GUID",
+      "Properties": {
+         "name": "name@domain.com",
+         "domain": "domain.com",
+         "distinguishedname": "DN",
+         "description": null,
+         "location": null,
+         "whencreated": -1
+      },
+      "Subnets": [],
+      "SiteLinks": [],
+      "Computers": [],
+   });
+}
+
+/// Return the json template for one subnet
+pub fn prepare_subnet_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "ObjectIdentifier": "GUID",
+      "Properties": {
+         "name": "0.0.0.0/0",
+         "distinguishedname": "DN",
+         "site": null,
+         "description": null,
+         "location": null
+      },
+   });
+}
+
+/// Return the json template for one site link
+pub fn prepare_sitelink_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "ObjectIdentifier": "GUID",
+      "Properties": {
+         "name": "name",
+         "distinguishedname": "DN",
+         "cost": 100,
+         "replinterval": 180,
+         "description": null
+      },
+      "Sites": [],
+   });
+}
+
+/// Return the json template for one authentication policy or silo
+pub fn prepare_authn_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "ObjectIdentifier": "GUID",
+      "Properties": {
+         "name": "name@domain.com",
+         "domain": "domain.com",
+         "distinguishedname": "DN",
+         "type": "silo",
+         "enforced": false,
+         "description": null,
+         "whencreated": -1
+      },
+      "Accounts": [],
+      "Policies": [],
+   });
+}
+
+/// Return the json template for one claim type
+pub fn prepare_claim_type_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "ObjectIdentifier": "GUID",
+      "Properties": {
+         "name": "name@domain.com",
+         "domain": "domain.com",
+         "distinguishedname": "DN",
+         "type": "claimtype",
+         "enabled": false,
+         "claimsourcetype": null,
+         "claimattributesource": null,
+         "claimappliesto": [],
+         "description": null,
+         "whencreated": -1
+      },
+   });
+}
+
+/// Return the json template for one finding in report
+pub fn prepare_finding_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "Severity": "Info",
+      "Category": "",
+      "Title": "",
+      "Description": "",
+      "Objects": []
+   });
+}
+
+/// Return the json template for one service account in service accounts report
+pub fn prepare_service_account_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "Name": "",
+      "ObjectIdentifier": "",
+      "Risk": "Low",
+      "Score": 0,
+      "HasSPN": false,
+      "PasswordAge": -1,
+      "LastLogon": -1,
+      "Privileged": false,
+      "Reasons": []
+   });
+}
+
+/// Return the json template for one share or DFS namespace in shares.json
+pub fn prepare_share_json_template() -> serde_json::value::Value
+{
+   return json!({
+      "Name": "",
+      "Type": "Share",
+      "Host": null,
+      "ObjectIdentifier": null,
+      "ShareType": null,
+      "Remark": null,
+      "Targets": [],
+      "Readable": null,
+      "Writable": null,
+      "MaximalAccess": null,
+      "WritableBy": []
+   });
+}
+
 /// Return the json template for one trust domain
 pub fn prepare_trust_json_template() -> serde_json::value::Value
 {
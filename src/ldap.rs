@@ -4,23 +4,63 @@
 //!
 //! rusthound sends only one request to the LDAP server, if the result of this one is higher than the limit of the LDAP server limit it will be split in several requests to avoid having an error 4 (LDAP_SIZELIMIT_EXCEED).
 //!
+//! The Configuration naming context is requested too, with a filter limited to sites, subnets, partitions, authentication policies,
+//! claim types, shadow principals, service connection points (Exchange...) and ADCS objects.
+//! Only the head of the Schema naming context and the attributes in a property set are requested,
+//! to get the schema version and to make attribute edges from the writes to property sets.
+//! In the domain naming context **--collection-methods** limits the attributes requested to the ones of the methods,
+//! like no nTSecurityDescriptor without acl or no member without group, to reduce the load of the DC for light runs.
+//!
+//! Referrals to other partitions (child domains) can be followed with the same credentials,
+//! only security principals are requested there to resolve cross-domain linked attributes.
+//! With **--global-catalog** the Global Catalog is requested too: it has the principals of the whole forest and the
+//! members of every universal group, so groups like Enterprise Admins are complete without a referral to each domain.
+//!
+//! Before a simple bind the NTLMSSP CHALLENGE of the DC is read with a SASL GSS-SPNEGO bind without credentials,
+//! it discloses the names of the DC, its domain and forest and its OS build, kept in stats.json.
+//! Before the bind the RootDSE `currentTime` is read to know the clock skew of the DC, see [`crate::enums::date`].
+//! The connection and the bind are limited by **--timeout**, every response of a search by **--search-timeout**,
+//! so a collection over a slow VPN or SOCKS link neither hangs forever nor stops on a slow page.
+//! Bytes received are counted for stats.json and can be limited with **--max-bandwidth**:
+//! the next entries (and so the next pages) are read later when the limit of the current second is reached.
+//! The wait for every search response is recorded for the LDAP latency metrics of the daemon mode.
+//! Once the collection is cancelled (**--max-runtime** or Ctrl-C) the current search is left at the end of the current page,
+//! the next naming contexts, referrals and the Global Catalog are skipped, see [`crate::cancel`].
+//! With **--disk-store** the entries are kept in an on-disk store, see [`crate::store`].
+//! Else with a parser pipeline they are sent to the parser workers as they are received, see [`crate::json::parser::pipeline`].
+//!
 //! Example in rust
 //!
 //! ```
 //! let search = ldap_search(...)
 //! ```
-use crate::errors::{Result};
+use crate::cancel::CancelToken;
+use crate::enums::date::{compute_clock_skew, set_clock_skew, MAX_CLOCK_SKEW};
+use crate::enums::dn::get_domain_from_dn;
+use crate::errors::{Result, RustHoundError};
+use crate::json::parser::pipeline::EntrySender;
+use crate::json::stats::mark_partial;
+use crate::modules::smb::ntlm::{der, parse_challenge, parse_target_info, version_negotiate_token};
+use crate::store::Entries;
 use colored::Colorize;
-use ldap3::adapters::{Adapter, EntriesOnly};
+use ldap3::adapters::Adapter;
 use ldap3::{adapters::PagedResults, controls::RawControl, LdapConnAsync, LdapConnSettings};
-use ldap3::{Scope, SearchEntry};
-use log::{info, debug, error};
-use std::process;
+use ldap3::{Scope, SearchEntry, parse_refs};
+use log::{info, debug, error, warn};
 use indicatif::ProgressBar;
 use crate::banner::progress_bar;
-use std::io::{self, Write, stdin};
+use std::io::{self, Read, Write, stdin};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Entries per page of the paged searches
+const PAGE_SIZE: u64 = 999;
 
 /// Function to request all AD values.
+/// Return the entries of the domain and the entries from followed referrals.
 pub async fn ldap_search(
     ldaps: bool,
     ip: &String,
@@ -31,73 +71,109 @@ pub async fn ldap_search(
     password: &String,
     adcs: bool,
     kerberos: bool,
-) -> Result<Vec<SearchEntry>> {
+    follow_referrals: bool,
+    global_catalog: bool,
+    collection_methods: &String,
+    timeout: u64,
+    search_timeout: u64,
+    max_bandwidth: u64,
+    disk_store: &String,
+    pipeline: Option<EntrySender>,
+    cancel: &CancelToken,
+) -> Result<(Entries, Vec<SearchEntry>)> {
     // Construct LDAP args
-    let ldap_args = ldap_constructor(ldaps, ip, port, domain, ldapfqdn, username, password, adcs, kerberos);
+    let ldap_args = ldap_constructor(ldaps, ip, port, domain, ldapfqdn, username, password, adcs, needs_configuration(collection_methods, adcs), kerberos);
 
     // LDAP connection
-    let consettings = LdapConnSettings::new().set_no_tls_verify(true);
+    let consettings = LdapConnSettings::new().set_no_tls_verify(true).set_conn_timeout(Duration::from_secs(timeout));
     let (conn, mut ldap) = LdapConnAsync::with_settings(consettings, &ldap_args.s_url).await?;
     ldap3::drive!(conn);
 
+    // Names and OS version of the DC from the NTLM exchange, before the bind
     if !kerberos {
-        debug!("Trying to connect with simple_bind() function (username:password)");
-        let res = ldap.simple_bind(&ldap_args.s_username, &ldap_args.s_password).await?.success();
-        match res {
-            Ok(_res) => {
-                info!("Connected to {} Active Directory!", domain.to_uppercase().bold().green());
-                info!("Starting data collection...");
+        let host = if ip.contains("not set") { domain } else { ip };
+        let ntlm_port = match port.parse::<u16>() {
+            Ok(port) if port != 636 && !ldaps => port,
+            _ => 389,
+        };
+        match capture_ntlm_info(host, ntlm_port, timeout) {
+            Some(info) => {
+                info!("NTLM: {} ({}) domain {} forest {} Windows {}",
+                    info["dns_computer"].as_str().unwrap_or("").bold(),
+                    info["netbios_computer"].as_str().unwrap_or(""),
+                    info["dns_domain"].as_str().unwrap_or(""),
+                    info["dns_forest"].as_str().unwrap_or(""),
+                    info["os_version"].as_str().unwrap_or("unknown"),
+                );
+                if let Ok(mut ntlm_info) = NTLM_INFO.lock() {
+                    *ntlm_info = Some(info);
+                }
             },
-            Err(err) => {
-                error!("Failed to authenticate to {} Active Directory. Reason: {err}\n", domain.to_uppercase().bold().red());
-                process::exit(0x0100);
-            }
-        }
-    }
-    else
-    {
-        debug!("Trying to connect with sasl_gssapi_bind() function (kerberos session)");
-        if !&ldapfqdn.contains("not set") {
-            #[cfg(not(feature = "nogssapi"))]
-            gssapi_connection(&mut ldap,&ldapfqdn,&domain).await?;
-            #[cfg(feature = "nogssapi")]{
-                error!("Kerberos auth and GSSAPI not compatible with current os!");
-                process::exit(0x0100);
-            }
-        } else {
-            error!("Need Domain Controler FQDN to bind GSSAPI connection. Please use '{}'\n", "-f DC01.DOMAIN.LAB".bold());
-            process::exit(0x0100);
+            None => debug!("No NTLM challenge received from {}:{}", host, ntlm_port),
         }
     }
 
-    // Prepare LDAP result vector
-    let mut rs: Vec<SearchEntry> = Vec::new();
+    // Clock skew from RootDSE, read anonymously so it's known even if the Kerberos bind fails
+    ldap.with_timeout(Duration::from_secs(timeout));
+    check_clock_skew(&mut ldap, kerberos).await;
+
+    // Timeout for the next operation only, the bind
+    ldap.with_timeout(Duration::from_secs(timeout));
+    bind(&mut ldap, &ldap_args, kerberos, ldapfqdn, domain).await?;
+    info!("Connected to {} Active Directory!", domain.to_uppercase().bold().green());
+    info!("Starting data collection...");
+
+    // Prepare LDAP result vector, in memory or on disk with --disk-store, or sent to the parser workers
+    let mut rs = match pipeline {
+        Some(sender) => Entries::Pipeline(sender, 0),
+        None => Entries::new(disk_store)?,
+    };
+    let mut shaper = BandwidthShaper::new(max_bandwidth);
+    // Referrals to other partitions
+    let mut referrals: Vec<String> = Vec::new();
 
     // For the following naming context 
     // namingContexts: DC=domain,DC=local
-    // namingContexts: CN=Configuration,DC=domain,DC=local (needed for AD CS datas)
+    // namingContexts: CN=Configuration,DC=domain,DC=local (needed for sites and AD CS datas)
+    // namingContexts: CN=Schema,CN=Configuration,DC=domain,DC=local (needed for schema version and property sets)
     for cn in &ldap_args.s_dc {
+        if !cancel.proceed(&format!("LDAP search of {}", cn)) {
+            continue
+        }
+        // Attributes of the collection methods in the domain naming context, all in the Configuration and Schema ones
+        let attributes = match cn.contains("Configuration") {
+            true => vec!["*", "nTSecurityDescriptor"],
+            false => get_search_attributes(collection_methods),
+        };
         // Set control LDAP_SERVER_SD_FLAGS_OID to get nTSecurityDescriptor
         // https://ldapwiki.com/wiki/LDAP_SERVER_SD_FLAGS_OID
-        let ctrls = RawControl {
-            ctype: String::from("1.2.840.113556.1.4.801"),
-            crit: true,
-            val: Some(vec![48,3,2,1,5]),
-        };
-        ldap.with_controls(ctrls.to_owned());
+        if attributes.contains(&"nTSecurityDescriptor") {
+            let ctrls = RawControl {
+                ctype: String::from("1.2.840.113556.1.4.801"),
+                crit: true,
+                val: Some(vec![48,3,2,1,5]),
+            };
+            ldap.with_controls(ctrls.to_owned());
+        }
+        // Applied to every response of the search stream
+        if search_timeout > 0 {
+            ldap.with_timeout(Duration::from_secs(search_timeout));
+        }
 
         // Prepare filter
-        let mut _s_filter: &str = "";
-        if cn.contains("Configuration") {
-            _s_filter = "(|(objectclass=pKIEnrollmentService)(objectclass=pkicertificatetemplate)(objectclass=subschema))";
+        let _s_filter: String;
+        if cn.starts_with("CN=Schema") {
+            _s_filter = "(|(objectclass=dMD)(&(objectclass=attributeSchema)(attributeSecurityGUID=*)))".to_string();
+        } else if cn.contains("Configuration") {
+            _s_filter = get_configuration_filter(collection_methods, adcs);
         } else {
-            _s_filter = "(objectClass=*)";
+            _s_filter = "(objectClass=*)".to_string();
         }
 
         // Every 999 max value in ldap response (err 4 ldap)
+        // Referrals are kept so no EntriesOnly adapter
         let adapters: Vec<Box<dyn Adapter<_,_>>> = vec![
-            Box::new(EntriesOnly::new()),
-            Box::new(PagedResults::new(999)),
+            Box::new(PagedResults::new(PAGE_SIZE as i32)),
         ];
 
         // Streaming search with adaptaters and filters
@@ -105,8 +181,8 @@ pub async fn ldap_search(
             adapters, // Adapter which fetches Search results with a Paged Results control.
             cn, 
             Scope::Subtree,
-            _s_filter,
-            vec!["*", "nTSecurityDescriptor"], 
+            &_s_filter,
+            attributes, 
             // Without the presence of this control, the server returns an SD only when the SD attribute name is explicitly mentioned in the requested attribute list.
             // https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/932a7a8d-8c93-4448-8093-c79b7d9ba499
         ).await?;
@@ -114,35 +190,618 @@ pub async fn ldap_search(
         // Wait and get next values
         let pb = ProgressBar::new(1);
         let mut count = 0;	
-        while let Some(entry) = search.next().await? {
-            let entry = SearchEntry::construct(entry);
+        let mut cancelled = false;
+        while let Some(entry) = timed(search.next()).await? {
+            // Continuation references like ldap://child.domain.local/DC=child,DC=domain,DC=local
+            if entry.is_ref() {
+                for url in parse_refs(entry.0) {
+                    if !referrals.contains(&url) {
+                        referrals.push(url);
+                    }
+                }
+                continue
+            }
+            if entry.is_intermediate() {
+                continue
+            }
+            let mut entry = SearchEntry::construct(entry);
+            shaper.record(entry_size(&entry)).await;
+            recover_text_attributes(&mut entry);
             //trace!("{:?}", &entry);
             // Manage progress bar
             count += 1;
             progress_bar(pb.to_owned(),"LDAP objects retreived".to_string(),count,"#".to_string());	
             // Push all result in rs vec()
-            rs.push(entry);
+            rs.push(entry)?;
+            // Next page isn't requested once cancelled
+            if count % PAGE_SIZE == 0 && cancel.is_cancelled() {
+                cancelled = true;
+                break
+            }
         }
         pb.finish_and_clear();
 
+        // Remaining pages are not requested, the connection is closed by unbind
+        if cancelled {
+            cancel.stopped(&format!("LDAP search of {}", cn), format!("{} entries received", count));
+            drop(search);
+            continue
+        }
         let res = search.finish().await.success();
         match res {
             Ok(_res) => info!("All data collected for NamingContext {}",&cn.bold()),
             Err(err) => {
                 error!("No data collected on {}! Reason: {err}",&cn.bold().red());
+                mark_partial(format!("LDAP search of {} failed: {}", cn, err));
             }
         }
     }
-    // If no result exit program
-    if rs.len() <= 0 {
-        process::exit(0x0100);
+    // If no result return an error
+    if rs.is_empty() {
+        return Err(RustHoundError::NoData { domain: domain.to_uppercase() })
     }
 
     // Terminate the connection to the server
     ldap.unbind().await?;
-    
+    info!("{} received from LDAP ({}/s)", format_bytes(shaper.total), format_bytes(shaper.average()));
+
+    // Follow referrals to other domains
+    let mut referred: Vec<SearchEntry> = Vec::new();
+    if follow_referrals && cancel.proceed("Referrals") {
+        referred = follow_all_referrals(&referrals, ldaps, &ldap_args.s_username, &ldap_args.s_password, kerberos, timeout, search_timeout, max_bandwidth).await;
+    } else if referrals.len() > 0 {
+        debug!("{} referrals not followed: {:?}", referrals.len(), referrals);
+    }
+
+    // Principals and universal groups of the other domains from the Global Catalog
+    if global_catalog && cancel.proceed("Global Catalog") {
+        let host = prepare_gc_host(ldaps, ip, domain, ldapfqdn, kerberos);
+        info!("Requesting Global Catalog {}", &host.bold());
+        match search_referral(ldaps, &host, &String::new(), &ldap_args.s_username, &ldap_args.s_password, kerberos, timeout, search_timeout, max_bandwidth, GC_ATTRIBUTES.to_vec()).await {
+            Ok(entries) => {
+                let mut entries: Vec<SearchEntry> = entries.into_iter()
+                    .filter(|entry| !get_domain_from_dn(&entry.dn).eq_ignore_ascii_case(domain))
+                    .collect();
+                info!("{} principals of other domains retrieved from the Global Catalog", entries.len().to_string().bold());
+                referred.append(&mut entries);
+            }
+            Err(err) => {
+                error!("Can't request Global Catalog {}! Reason: {err}", &host.bold().red());
+                mark_partial(format!("Global Catalog {} not requested: {}", host, err));
+            }
+        }
+    }
+
     // Return the vector with the result
-    return Ok(rs);
+    return Ok((rs, referred));
+}
+
+/// Function to bind with the username and password, or with the Kerberos ticket of the ccache.
+async fn bind(
+    ldap: &mut ldap3::Ldap,
+    ldap_args: &LdapArgs,
+    kerberos: bool,
+    ldapfqdn: &String,
+    domain: &String,
+) -> Result<()> {
+    if !kerberos {
+        debug!("Trying to connect with simple_bind() function (username:password)");
+        let res = ldap.simple_bind(&ldap_args.s_username, &ldap_args.s_password).await?.success();
+        if let Err(err) = res {
+            return Err(RustHoundError::AuthError {
+                domain: domain.to_uppercase(),
+                reason: err.to_string(),
+            })
+        }
+    }
+    else
+    {
+        debug!("Trying to connect with sasl_gssapi_bind() function (kerberos session)");
+        if !&ldapfqdn.contains("not set") {
+            #[cfg(not(feature = "nogssapi"))]
+            gssapi_connection(ldap,&ldapfqdn,&domain).await?;
+            #[cfg(feature = "nogssapi")]{
+                return Err(RustHoundError::AuthError {
+                    domain: domain.to_uppercase(),
+                    reason: "Kerberos auth and GSSAPI not compatible with current os!".to_string(),
+                })
+            }
+        } else {
+            return Err(RustHoundError::AuthError {
+                domain: domain.to_uppercase(),
+                reason: format!("Need Domain Controler FQDN to bind GSSAPI connection. Please use '{}'", "-f DC01.DOMAIN.LAB".bold()),
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Function to open one bound LDAP connection to the domain controller, used by the write helpers.
+/// LDAPS is used on port 636 unless another port is set: passwords can only be written on an encrypted connection.
+pub async fn ldap_connect(
+    ip: &String,
+    port: &String,
+    domain: &String,
+    ldapfqdn: &String,
+    username: &String,
+    password: &String,
+    kerberos: bool,
+    timeout: u64,
+) -> Result<ldap3::Ldap> {
+    let ldap_args = ldap_constructor(true, ip, port, domain, ldapfqdn, username, password, false, false, kerberos);
+    let consettings = LdapConnSettings::new().set_no_tls_verify(true).set_conn_timeout(Duration::from_secs(timeout));
+    let (conn, mut ldap) = LdapConnAsync::with_settings(consettings, &ldap_args.s_url).await?;
+    ldap3::drive!(conn);
+    ldap.with_timeout(Duration::from_secs(timeout));
+    bind(&mut ldap, &ldap_args, kerberos, ldapfqdn, domain).await?;
+    info!("Connected to {} Active Directory with LDAPS!", domain.to_uppercase().bold().green());
+    Ok(ldap)
+}
+
+/// Function to read the RootDSE currentTime and compare it with the local time.
+/// The skew is kept to compute the ages of AD timestamps with the DC time, a warning is logged
+/// over 5 minutes since Kerberos tickets are refused with KRB_AP_ERR_SKEW.
+async fn check_clock_skew(ldap: &mut ldap3::Ldap, kerberos: bool) {
+    let current_time = match ldap.search("", Scope::Base, "(objectClass=*)", vec!["currentTime"]).await.and_then(|res| res.success()) {
+        Ok((entries, _)) => entries.into_iter()
+            .map(SearchEntry::construct)
+            .find_map(|entry| entry.attrs.get("currentTime").and_then(|values| values.first().cloned())),
+        Err(err) => {
+            debug!("Can't read RootDSE currentTime: {err}");
+            None
+        }
+    };
+    let skew = match current_time.and_then(|current_time| compute_clock_skew(&current_time, chrono::Utc::now().timestamp())) {
+        Some(skew) => skew,
+        None => return,
+    };
+    set_clock_skew(skew);
+    if skew.abs() > MAX_CLOCK_SKEW {
+        warn!("Clock skew of {} seconds with the domain controller, more than the 5 minutes tolerated by Kerberos", skew.to_string().bold());
+        if kerberos {
+            warn!("The Kerberos bind may fail with KRB_AP_ERR_SKEW, sync the local clock with the DC or use faketime");
+        }
+    } else {
+        debug!("Clock skew of {} seconds with the domain controller", skew);
+    }
+}
+
+/// Function to follow referrals to other domains with the same credentials.
+/// DNS zones and Configuration referrals are skipped, they don't have security principals.
+async fn follow_all_referrals(
+    referrals: &Vec<String>,
+    ldaps: bool,
+    username: &String,
+    password: &String,
+    kerberos: bool,
+    timeout: u64,
+    search_timeout: u64,
+    max_bandwidth: u64,
+) -> Vec<SearchEntry> {
+    let mut referred: Vec<SearchEntry> = Vec::new();
+    let mut bases: Vec<String> = Vec::new();
+    for url in referrals {
+        let (host, base) = match parse_referral(url) {
+            Some(referral) => referral,
+            None => continue,
+        };
+        if !base.starts_with("DC=") || base.contains("DNSZONES") || bases.contains(&base) {
+            continue
+        }
+        bases.push(base.to_owned());
+        info!("Following referral to {}", &host.bold());
+        match search_referral(ldaps, &host, &base, username, password, kerberos, timeout, search_timeout, max_bandwidth, vec!["objectSid", "objectClass", "distinguishedName"]).await {
+            Ok(mut entries) => {
+                info!("{} principals retrieved from {}", entries.len().to_string().bold(), &base.bold());
+                referred.append(&mut entries);
+            }
+            Err(err) => {
+                error!("Can't follow referral to {}! Reason: {err}", &host.bold().red());
+                mark_partial(format!("Referral to {} not followed: {}", host, err));
+            }
+        }
+    }
+    referred
+}
+
+/// Function to request the security principals of one referred domain, or of the forest from the Global Catalog with an empty base.
+async fn search_referral(
+    ldaps: bool,
+    host: &String,
+    base: &String,
+    username: &String,
+    password: &String,
+    kerberos: bool,
+    timeout: u64,
+    search_timeout: u64,
+    max_bandwidth: u64,
+    attributes: Vec<&str>,
+) -> Result<Vec<SearchEntry>> {
+    let s_url = format!("{}://{}", if ldaps { "ldaps" } else { "ldap" }, host);
+    let consettings = LdapConnSettings::new().set_no_tls_verify(true).set_conn_timeout(Duration::from_secs(timeout));
+    let (conn, mut ldap) = LdapConnAsync::with_settings(consettings, &s_url).await?;
+    ldap3::drive!(conn);
+
+    ldap.with_timeout(Duration::from_secs(timeout));
+
+    if kerberos {
+        #[cfg(not(feature = "nogssapi"))]
+        ldap.sasl_gssapi_bind(host.split(':').next().unwrap_or(host)).await?.success()?;
+        #[cfg(feature = "nogssapi")]
+        return Err(RustHoundError::AuthError {
+            domain: host.to_uppercase(),
+            reason: "Kerberos auth and GSSAPI not compatible with current os!".to_string(),
+        })
+    } else {
+        ldap.simple_bind(username, password).await?.success()?;
+    }
+
+    if search_timeout > 0 {
+        ldap.with_timeout(Duration::from_secs(search_timeout));
+    }
+    let adapters: Vec<Box<dyn Adapter<_,_>>> = vec![
+        Box::new(ldap3::adapters::EntriesOnly::new()),
+        Box::new(PagedResults::new(PAGE_SIZE as i32)),
+    ];
+    let mut search = ldap.streaming_search_with(
+        adapters,
+        base,
+        Scope::Subtree,
+        "(objectSid=*)",
+        attributes,
+    ).await?;
+    let mut rs: Vec<SearchEntry> = Vec::new();
+    let mut shaper = BandwidthShaper::new(max_bandwidth);
+    while let Some(entry) = timed(search.next()).await? {
+        let entry = SearchEntry::construct(entry);
+        shaper.record(entry_size(&entry)).await;
+        rs.push(entry);
+    }
+    search.finish().await.success()?;
+    ldap.unbind().await?;
+    Ok(rs)
+}
+
+/// Bytes received from all LDAP searches, for stats.json
+static RECEIVED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Get the number of bytes received from LDAP.
+pub fn received_bytes() -> u64 {
+    RECEIVED_BYTES.load(Ordering::SeqCst)
+}
+
+/// Wait for every LDAP search response in seconds, for the metrics of the daemon mode
+static RESPONSE_LATENCIES: Mutex<Vec<f64>> = Mutex::new(Vec::new());
+
+/// Get the wait for every LDAP search response in seconds.
+pub fn response_latencies() -> Vec<f64> {
+    RESPONSE_LATENCIES.lock().map(|latencies| latencies.to_owned()).unwrap_or_default()
+}
+
+/// Names and OS version disclosed by the DC in the NTLM exchange, for stats.json
+static NTLM_INFO: Mutex<Option<serde_json::value::Value>> = Mutex::new(None);
+
+/// Get the names and OS version disclosed by the DC in the NTLM exchange, null if not captured.
+pub fn ntlm_info() -> serde_json::value::Value {
+    NTLM_INFO.lock().ok().and_then(|info| info.to_owned()).unwrap_or(serde_json::value::Value::Null)
+}
+
+/// Reset the LDAP counters before one more run in the same process (daemon mode).
+pub fn reset_ldap_counters() {
+    RECEIVED_BYTES.store(0, Ordering::SeqCst);
+    if let Ok(mut latencies) = RESPONSE_LATENCIES.lock() {
+        latencies.clear();
+    }
+    if let Ok(mut info) = NTLM_INFO.lock() {
+        *info = None;
+    }
+}
+
+/// Function to prepare one LDAP BindRequest with a SASL GSS-SPNEGO token.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-adts/8b9dbfb2-5b6a-497a-a533-7e709cb9a982>
+pub fn prepare_sasl_bind_request(token: &[u8]) -> Vec<u8> {
+    let mut credentials = der(0x04, b"GSS-SPNEGO");
+    credentials.extend(der(0x04, token));
+    let mut bind = vec![0x02, 0x01, 0x03];      // version 3
+    bind.extend(der(0x04, b""));                // name
+    bind.extend(der(0xa3, &credentials));       // authentication: sasl
+    let mut message = vec![0x02, 0x01, 0x01];   // messageID
+    message.extend(der(0x60, &bind));           // [APPLICATION 0] BindRequest
+    der(0x30, &message)
+}
+
+/// Function to send one NTLMSSP NEGOTIATE in a SASL bind and read the CHALLENGE of the DC, no credentials are sent.
+/// Return the names of the DC, its domain and forest and its OS version.
+fn capture_ntlm_info(host: &String, port: u16, timeout: u64) -> Option<serde_json::value::Value> {
+    let address = (host.as_str(), port).to_socket_addrs().ok()?.next()?;
+    let mut stream = TcpStream::connect_timeout(&address, Duration::from_secs(timeout)).ok()?;
+    stream.set_read_timeout(Some(Duration::from_secs(timeout))).ok()?;
+    stream.write_all(&prepare_sasl_bind_request(&version_negotiate_token())).ok()?;
+
+    // BindResponse with saslBindInProgress and the CHALLENGE in serverSaslCreds
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).ok()?;
+    let length = match header[1] {
+        length if length < 0x80 => length as usize,
+        bytes if bytes & 0x7f <= 4 => {
+            let mut length = vec![0u8; (bytes & 0x7f) as usize];
+            stream.read_exact(&mut length).ok()?;
+            length.iter().fold(0, |acc, byte| acc << 8 | *byte as usize)
+        },
+        _ => return None,
+    };
+    if header[0] != 0x30 || length > 0x10000 {
+        return None
+    }
+    let mut response = vec![0u8; length];
+    stream.read_exact(&mut response).ok()?;
+    let challenge = parse_challenge(&response)?;
+    let info = parse_target_info(&challenge.target_info);
+    Some(serde_json::json!({
+        "netbios_computer": info.netbios_computer,
+        "netbios_domain": info.netbios_domain,
+        "dns_computer": info.dns_computer,
+        "dns_domain": info.dns_domain,
+        "dns_forest": info.dns_forest,
+        "os_version": challenge.version,
+    }))
+}
+
+/// Function to wait for the next search response and record its latency.
+async fn timed<T>(response: impl Future<Output = T>) -> T {
+    let start = Instant::now();
+    let response = response.await;
+    if let Ok(mut latencies) = RESPONSE_LATENCIES.lock() {
+        latencies.push(start.elapsed().as_secs_f64());
+    }
+    response
+}
+
+/// Structure counting the bytes received by one search and keeping them under the limit in bytes per second.
+struct BandwidthShaper {
+    /// 0 for no limit
+    limit: u64,
+    start: Instant,
+    total: u64,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl BandwidthShaper {
+    fn new(limit: u64) -> Self {
+        BandwidthShaper { limit, start: Instant::now(), total: 0, window_start: Instant::now(), window_bytes: 0 }
+    }
+
+    /// Count the bytes of one entry and wait when the limit of the current second is reached.
+    async fn record(&mut self, bytes: u64) {
+        RECEIVED_BYTES.fetch_add(bytes, Ordering::SeqCst);
+        self.total += bytes;
+        self.window_bytes += bytes;
+        let elapsed = self.window_start.elapsed();
+        if self.limit > 0 && self.window_bytes >= self.limit {
+            // Time needed for these bytes at the limit rate
+            let needed = Duration::from_secs_f64(self.window_bytes as f64 / self.limit as f64);
+            if needed > elapsed {
+                tokio::time::sleep(needed - elapsed).await;
+            }
+        } else if elapsed < Duration::from_secs(1) {
+            return
+        }
+        debug!("LDAP bandwidth: {}/s", format_bytes((self.window_bytes as f64 / self.window_start.elapsed().as_secs_f64().max(0.001)) as u64));
+        self.window_start = Instant::now();
+        self.window_bytes = 0;
+    }
+
+    /// Average bytes per second since the beginning of the search.
+    fn average(&self) -> u64 {
+        (self.total as f64 / self.start.elapsed().as_secs_f64().max(0.001)) as u64
+    }
+}
+
+/// Function to get the size of one entry, DN, attribute names and values.
+/// BER encoding adds a few bytes for each value, close enough to the bytes on the network.
+pub fn entry_size(entry: &SearchEntry) -> u64 {
+    let attrs: usize = entry.attrs.iter().map(|(name, values)| name.len() + values.iter().map(|value| value.len()).sum::<usize>()).sum();
+    let bin_attrs: usize = entry.bin_attrs.iter().map(|(name, values)| name.len() + values.iter().map(|value| value.len()).sum::<usize>()).sum();
+    (entry.dn.len() + attrs + bin_attrs) as u64
+}
+
+/// Function to parse one bandwidth like 500K, 2M or 1048576 in bytes per second.
+pub fn parse_bandwidth(value: &str) -> std::result::Result<u64, String> {
+    let value = value.trim().to_uppercase();
+    let (number, multiplier) = match value.chars().last() {
+        Some('K') => (&value[..value.len() - 1], 1024),
+        Some('M') => (&value[..value.len() - 1], 1024 * 1024),
+        Some('G') => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (&value[..], 1),
+    };
+    number.parse::<u64>()
+        .map(|number| number * multiplier)
+        .map_err(|_| format!("invalid bandwidth {}, expected bytes per second like 500K or 2M", value))
+}
+
+/// Collection methods of --collection-methods, all for every method.
+pub const COLLECTION_METHODS: [&str; 5] = ["all", "group", "acl", "objectprops", "site"];
+
+/// Attributes requested by every collection method: identity, object type, trusts and GPO links.
+const BASE_ATTRIBUTES: [&str; 23] = [
+    "objectClass", "objectCategory", "objectSid", "objectGUID", "distinguishedName", "name", "sAMAccountName", "sAMAccountType",
+    "userAccountControl", "groupType", "dNSHostName", "displayName", "isDeleted", "isCriticalSystemObject",
+    "gPLink", "gPCFileSysPath", "trustAttributes", "trustDirection", "flatName", "securityIdentifier",
+    "nCName", "dnsRoot", "nETBIOSName",
+];
+
+/// Attributes of the group collection method.
+const GROUP_ATTRIBUTES: [&str; 2] = ["member", "primaryGroupID"];
+
+/// Attributes of the acl collection method, the security descriptors.
+const ACL_ATTRIBUTES: [&str; 3] = ["nTSecurityDescriptor", "msDS-GroupMSAMembership", "msDS-AllowedToActOnBehalfOfOtherIdentity"];
+
+/// Attributes of the objectprops collection method, the properties read by the parsers.
+const OBJECTPROPS_ATTRIBUTES: [&str; 77] = [
+    "adminCount", "company", "department", "description", "directReports", "homeDirectory", "keywords",
+    "lastLogon", "lastLogonTimestamp", "location", "mail", "targetAddress", "manager", "managedBy", "title", "whenCreated",
+    "pwdLastSet", "scriptPath", "servicePrincipalName", "userPrincipalName", "userCertificate", "sIDHistory",
+    "userPassword", "unixUserPassword", "unicodePwd", "sfuPassword", "operatingSystem", "operatingSystemVersion",
+    "operatingSystemServicePack", "msDS-SupportedEncryptionTypes", "msDS-AllowedToDelegateTo", "mS-DS-CreatorSID",
+    "ms-DS-MachineAccountQuota", "msDS-Behavior-Version", "objectVersion", "ms-Mcs-AdmPwd", "ms-Mcs-AdmPwdExpirationTime",
+    "msLAPS-PasswordExpirationTime", "msDS-KrbTgtLink", "msDS-NeverRevealGroup", "msDS-RevealOnDemandGroup",
+    "msDS-ExternalDirectoryObjectId", "mS-DS-ConsistencyGuid", "msDS-ShadowPrincipalSid", "msDS-AuthNPolicySiloMembers",
+    "msDS-AssignedAuthNPolicySiloBL", "msDS-AssignedAuthNPolicyBL", "msDS-UserAuthNPolicy", "msDS-ComputerAuthNPolicy",
+    "msDS-ServiceAuthNPolicy", "serviceBindingInformation", "serviceClassName", "serviceDNSName", "remoteServerName", "msDFS-Commentv2",
+    "msDFS-TargetListv2", "msDFS-LinkPathv2", "siteObject", "siteList", "cost", "replInterval", "maxPwdAge",
+    "msDS-MaximumPasswordAge", "minPwdAge", "msDS-MinimumPasswordAge", "lockoutDuration", "msDS-LockoutDuration",
+    "lockOutObservationWindow", "msDS-LockoutObservationWindow", "cACertificate", "certificateTemplates", "pKIExtendedKeyUsage",
+    "printerName", "serverName", "uNCName", "driverName", "portName",
+];
+
+/// Function to check the comma separated methods of --collection-methods.
+pub fn parse_collection_methods(value: &str) -> std::result::Result<String, String> {
+    let methods: Vec<String> = value.split(',').map(|method| method.trim().to_lowercase()).collect();
+    match methods.iter().find(|method| !COLLECTION_METHODS.contains(&method.as_str())) {
+        Some(method) => Err(format!("unknown collection method '{}', possible values: {}", method, COLLECTION_METHODS.join(", "))),
+        None => Ok(methods.join(",")),
+    }
+}
+
+/// Function to check if one collection method is requested, `all` requests every method.
+pub fn has_collection_method(collection_methods: &str, method: &str) -> bool {
+    collection_methods.split(',').any(|requested| requested == "all" || requested == method)
+}
+
+/// Function to check if the Configuration and Schema naming contexts must be searched:
+/// sites, object properties (partitions, authentication policies, shadow principals, claim types, service connection points),
+/// property sets of the ACLs or AD CS objects.
+pub fn needs_configuration(collection_methods: &str, adcs: bool) -> bool {
+    adcs || ["site", "objectprops", "acl"].iter().any(|method| has_collection_method(collection_methods, method))
+}
+
+/// Function to get the attributes requested in the domain naming context for the collection methods.
+/// LDAP can't remove one attribute from `*`, so the attributes are listed unless all the methods are requested.
+pub fn get_search_attributes(collection_methods: &str) -> Vec<&'static str> {
+    let methods: Vec<&str> = collection_methods.split(',').collect();
+    let has = |method: &str| methods.contains(&"all") || methods.contains(&method);
+    if has("group") && has("acl") && has("objectprops") {
+        return vec!["*", "nTSecurityDescriptor"]
+    }
+    // Security descriptors are returned only when requested by name
+    if has("group") && has("objectprops") {
+        return vec!["*"]
+    }
+    let mut attributes = BASE_ATTRIBUTES.to_vec();
+    if has("group") {
+        attributes.extend_from_slice(&GROUP_ATTRIBUTES);
+    }
+    if has("acl") {
+        attributes.extend_from_slice(&ACL_ATTRIBUTES);
+    }
+    if has("objectprops") {
+        attributes.extend_from_slice(&OBJECTPROPS_ATTRIBUTES);
+    }
+    attributes
+}
+
+/// Function to format bytes like 1.5 MB.
+pub fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1048575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1048576.0),
+    }
+}
+
+/// Text attributes read by the parsers.
+/// ldap3 moves every attribute with one non UTF-8 value in `bin_attrs`, they would be silently missing.
+const TEXT_ATTRIBUTES: [&str; 23] = [
+    "name", "distinguishedName", "sAMAccountName", "displayName", "description", "mail", "title",
+    "department", "company", "manager", "managedBy", "member", "homeDirectory", "scriptpath",
+    "dNSHostName", "operatingSystem", "servicePrincipalName", "gPCFileSysPath", "gPLink",
+    "location", "siteObject", "siteList", "certificateTemplates",
+];
+
+/// Function to move back text attributes with non UTF-8 bytes in `attrs`, invalid bytes are replaced by U+FFFD.
+pub fn recover_text_attributes(entry: &mut SearchEntry) {
+    for attribute in TEXT_ATTRIBUTES {
+        if let Some(values) = entry.bin_attrs.remove(attribute) {
+            warn!("{} of {} is not valid UTF-8, invalid bytes replaced", attribute, entry.dn);
+            let values = values.iter().map(|value| String::from_utf8_lossy(value).to_string()).collect();
+            entry.attrs.insert(attribute.to_string(), values);
+        }
+    }
+}
+
+/// Function to get the host and the base DN from a referral url.
+/// `ldap://child.domain.local/DC=child,DC=domain,DC=local` => (`child.domain.local`, `DC=CHILD,DC=DOMAIN,DC=LOCAL`)
+pub fn parse_referral(url: &str) -> Option<(String, String)> {
+    let url = url.strip_prefix("ldap://").or_else(|| url.strip_prefix("ldaps://"))?;
+    let (host, base) = url.split_once('/')?;
+    let base = base.split('?').next().unwrap_or("");
+    // Percent-encoded characters
+    let mut decoded: Vec<u8> = Vec::new();
+    let bytes = base.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Some(byte) = std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    let base = String::from_utf8_lossy(&decoded).to_uppercase();
+    if host.is_empty() || base.is_empty() {
+        return None
+    }
+    Some((host.to_string(), base))
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_prepare_sasl_bind_request() {
+    let request = prepare_sasl_bind_request(&[0x60, 0x00]);
+    assert_eq!(request[0], 0x30);
+    assert_eq!(request[1] as usize, request.len() - 2);
+    assert_eq!(&request[2..5], &[0x02, 0x01, 0x01]);
+    assert_eq!(request[5], 0x60);
+    assert!(request.windows(10).any(|w| w == b"GSS-SPNEGO"));
+    assert!(request.ends_with(&[0x04, 0x02, 0x60, 0x00]));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_referral() {
+    assert_eq!(parse_referral("ldap://north.sevenkingdoms.local/DC=north,DC=sevenkingdoms,DC=local").unwrap(), ("north.sevenkingdoms.local".to_string(), "DC=NORTH,DC=SEVENKINGDOMS,DC=LOCAL".to_string()));
+    assert_eq!(parse_referral("ldap://essos.local/CN=Some%20Name,DC=essos,DC=local??base").unwrap().1, "CN=SOME NAME,DC=ESSOS,DC=LOCAL");
+    assert!(parse_referral("http://essos.local/").is_none());
+}
+
+/// Attributes requested from the Global Catalog, all of them are in the partial attribute set.
+/// `member` is only replicated for universal groups.
+const GC_ATTRIBUTES: [&str; 7] = ["objectSid", "objectClass", "distinguishedName", "name", "sAMAccountName", "groupType", "member"];
+
+/// Function to get the Global Catalog host of one domain controller: 3268, or 3269 with LDAPS.
+/// Kerberos needs the FQDN of the domain controller for the service ticket.
+pub fn prepare_gc_host(ldaps: bool, ip: &String, domain: &String, ldapfqdn: &String, kerberos: bool) -> String {
+    let host = if kerberos && !ldapfqdn.contains("not set") {
+        ldapfqdn
+    } else if !ip.contains("not set") {
+        ip
+    } else {
+        domain
+    };
+    format!("{}:{}", host, if ldaps { 3269 } else { 3268 })
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_prepare_gc_host() {
+    let not_set = "not set".to_string();
+    assert_eq!(prepare_gc_host(false, &not_set, &"essos.local".to_string(), &not_set, false), "essos.local:3268");
+    assert_eq!(prepare_gc_host(true, &"192.168.56.12".to_string(), &"essos.local".to_string(), &not_set, false), "192.168.56.12:3269");
+    assert_eq!(prepare_gc_host(false, &"192.168.56.12".to_string(), &"essos.local".to_string(), &"meereen.essos.local".to_string(), true), "meereen.essos.local:3268");
 }
 
 /// Structure containing the LDAP connection arguments.
@@ -164,32 +823,18 @@ fn ldap_constructor(
     username: &String,
     password: &String,
     adcs: bool,
+    configuration: bool,
     kerberos: bool,
 ) -> LdapArgs {
     // Prepare ldap url
     let s_url = prepare_ldap_url(ldaps, ip, port, domain);
 
     // Prepare full DC chain
-    let s_dc = prepare_ldap_dc(domain,adcs);
+    // Configuration naming context only if one collection method or ADCS needs it
+    let s_dc = prepare_ldap_dc(domain,configuration);
 
-    // Username prompt
-    let mut s=String::new();
-    let mut _s_username: String;
-    if username.contains("not set") && !kerberos {
-        print!("Username: ");
-        io::stdout().flush().unwrap();
-        stdin().read_line(&mut s).expect("Did not enter a correct username");
-        io::stdout().flush().unwrap();
-        if let Some('\n')=s.chars().next_back() {
-            s.pop();
-        }
-        if let Some('\r')=s.chars().next_back() {
-            s.pop();
-        }
-        _s_username = s.to_owned();
-    } else {
-        _s_username = username.to_owned();
-    }
+    // Username and password prompt
+    let (mut _s_username, _s_password) = prompt_credentials(username, password, kerberos);
 
     // Format username and email
     let mut s_email: String = "".to_owned();
@@ -202,20 +847,6 @@ fn ldap_constructor(
         s_email = _s_username.to_string().to_lowercase();
     }
 
-    // Password prompt
-    let mut _s_password: String = String::new();
-    if !_s_username.contains("not set") && !kerberos {
-        if password.contains("not set") {
-            // Prompt for user password
-            let rpass: String = rpassword::prompt_password("Password: ").unwrap_or("not set".to_string());
-            _s_password = rpass;
-        } else {
-            _s_password = password.to_owned();
-        }
-    } else {
-        _s_password = password.to_owned();
-    }
-
     // Print infos if verbose mod is set
     debug!("IP: {}", ip);
     debug!("PORT: {}", port);
@@ -238,6 +869,95 @@ fn ldap_constructor(
     }
 }
 
+/// Function to prompt username and password if they are not set.
+/// Used before the LDAP connection to share the credentials with SMB modules.
+pub fn prompt_credentials(
+    username: &String,
+    password: &String,
+    kerberos: bool,
+) -> (String, String) {
+    // Username prompt
+    let mut s=String::new();
+    let _s_username: String;
+    if username.contains("not set") && !kerberos {
+        // stderr to keep stdout for results with --stdout
+        eprint!("Username: ");
+        io::stderr().flush().unwrap();
+        stdin().read_line(&mut s).expect("Did not enter a correct username");
+        io::stderr().flush().unwrap();
+        if let Some('\n')=s.chars().next_back() {
+            s.pop();
+        }
+        if let Some('\r')=s.chars().next_back() {
+            s.pop();
+        }
+        _s_username = s.to_owned();
+    } else {
+        _s_username = username.to_owned();
+    }
+
+    // Password prompt
+    let mut _s_password: String = String::new();
+    if !_s_username.contains("not set") && !kerberos {
+        if password.contains("not set") {
+            // Prompt for user password
+            let rpass: String = rpassword::prompt_password("Password: ").unwrap_or("not set".to_string());
+            _s_password = rpass;
+        } else {
+            _s_password = password.to_owned();
+        }
+    } else {
+        _s_password = password.to_owned();
+    }
+
+    (_s_username, _s_password)
+}
+
+/// Function to parse injected credentials, `username=` and `password=` lines.
+/// Content without these keys is only the password, like with --password-stdin.
+pub fn parse_credentials(content: &str) -> (Option<String>, Option<String>) {
+    let lines: Vec<&str> = content.lines().map(|line| line.trim_end_matches('\r')).collect();
+    let get = |key: &str| lines.iter().find_map(|line| line.strip_prefix(key)).map(|value| value.to_string());
+    let (username, password) = (get("username="), get("password="));
+    if username.is_none() && password.is_none() {
+        return (None, lines.first().map(|line| line.to_string()).filter(|line| !line.is_empty()))
+    }
+    (username, password)
+}
+
+/// Function to read the credentials injected with --password-stdin (first line of stdin)
+/// or --credentials-file (file, named pipe or file descriptor like /dev/fd/3), before any prompt.
+/// Values from the command line are replaced.
+pub fn read_injected_credentials(
+    password_stdin: bool,
+    credentials_file: &str,
+    username: &mut String,
+    password: &mut String,
+) -> io::Result<()> {
+    let content = if password_stdin {
+        let mut line = String::new();
+        stdin().read_line(&mut line)?;
+        line
+    } else if !credentials_file.contains("not set") {
+        std::fs::read_to_string(credentials_file)?
+    } else {
+        return Ok(())
+    };
+    let (injected_username, injected_password) = match password_stdin {
+        // Only the password, even if it starts with password=
+        true => (None, Some(content.trim_end_matches(|c| c == '\n' || c == '\r').to_string()).filter(|password| !password.is_empty())),
+        false => parse_credentials(&content),
+    };
+    if let Some(injected_username) = injected_username {
+        *username = injected_username;
+    }
+    match injected_password {
+        Some(injected_password) => *password = injected_password,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData, "No password in injected credentials")),
+    }
+    Ok(())
+}
+
 /// Function to prepare LDAP url.
 fn prepare_ldap_url(ldaps: bool, ip: &String, port: &String, domain: &String) -> String {
     let mut url: String = "".to_owned();
@@ -273,8 +993,23 @@ fn prepare_ldap_url(ldaps: bool, ip: &String, port: &String, domain: &String) ->
     }
 }
 
+/// Function to prepare the LDAP filter of the Configuration naming context,
+/// sites with the site collection method and AD CS objects with --adcs.
+pub fn get_configuration_filter(collection_methods: &str, adcs: bool) -> String {
+    let mut filter = String::from("(|");
+    if adcs {
+        filter.push_str("(objectclass=pKIEnrollmentService)(objectclass=pkicertificatetemplate)(objectclass=subschema)");
+    }
+    if has_collection_method(collection_methods, "site") {
+        filter.push_str("(objectclass=site)(objectclass=subnet)(objectclass=siteLink)");
+    }
+    filter.push_str("(objectclass=crossRefContainer)(objectclass=crossRef)(objectclass=msDS-AuthNPolicySilo)(objectclass=msDS-AuthNPolicy)(objectclass=msDS-ShadowPrincipal)(objectclass=msDS-ClaimType)(objectclass=serviceConnectionPoint))");
+    filter
+}
+
 /// Function to prepare LDAP DC from DOMAIN.LOCAL
-pub fn prepare_ldap_dc(domain: &String, adcs: bool) -> Vec<String> {
+/// Add the Configuration and Schema naming contexts if `configuration` is set.
+pub fn prepare_ldap_dc(domain: &String, configuration: bool) -> Vec<String> {
 
     let mut dc: String = "".to_owned();
     let mut naming_context: Vec<String> = Vec::new();
@@ -306,8 +1041,9 @@ pub fn prepare_ldap_dc(domain: &String, adcs: bool) -> Vec<String> {
         naming_context.push(dc[..].to_string());
     }
 
-    if adcs {
+    if configuration {
         naming_context.push(format!("{}{}","CN=Configuration,",dc[..].to_string())); 
+        naming_context.push(format!("{}{}","CN=Schema,CN=Configuration,",dc[..].to_string())); 
     }
 
     return naming_context
@@ -321,15 +1057,70 @@ async fn gssapi_connection(
     domain: &String,
 ) -> Result<()> {
     let res = ldap.sasl_gssapi_bind(ldapfqdn).await?.success();
-    match res {
-        Ok(_res) => {
-            info!("Connected to {} Active Directory!", domain.to_uppercase().bold().green());
-            info!("Starting data collection...");
-        },
-        Err(err) => {
-            error!("Failed to authenticate to {} Active Directory. Reason: {err}\n", domain.to_uppercase().bold().red());
-            process::exit(0x0100);
-        }
+    if let Err(err) = res {
+        return Err(RustHoundError::AuthError {
+            domain: domain.to_uppercase(),
+            reason: err.to_string(),
+        })
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_credentials() {
+    assert_eq!(parse_credentials("username=khal.drogo@essos.local\npassword=ho=rse\n"), (Some("khal.drogo@essos.local".to_string()), Some("ho=rse".to_string())));
+    assert_eq!(parse_credentials("password=horse\r\n"), (None, Some("horse".to_string())));
+    assert_eq!(parse_credentials("horse\n"), (None, Some("horse".to_string())));
+    assert_eq!(parse_credentials(""), (None, None));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_recover_text_attributes() {
+    let mut bin_attrs = std::collections::HashMap::new();
+    bin_attrs.insert("description".to_string(), vec![b"Caf\xe9".to_vec()]);
+    bin_attrs.insert("objectSid".to_string(), vec![vec![1, 5, 0, 0]]);
+    let mut entry = SearchEntry { dn: "CN=CAFE,DC=ESSOS,DC=LOCAL".to_string(), attrs: std::collections::HashMap::new(), bin_attrs };
+    recover_text_attributes(&mut entry);
+    assert_eq!(entry.attrs["description"], vec!["Caf\u{FFFD}".to_string()]);
+    assert!(entry.bin_attrs.contains_key("objectSid"));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_bandwidth() {
+    assert_eq!(parse_bandwidth("500K"), Ok(512000));
+    assert_eq!(parse_bandwidth("2m"), Ok(2097152));
+    assert_eq!(parse_bandwidth("4096"), Ok(4096));
+    assert!(parse_bandwidth("fast").is_err());
+    assert_eq!(format_bytes(1536), "1.5 KB");
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_search_attributes() {
+    assert_eq!(parse_collection_methods("Group, objectprops").unwrap(), "group,objectprops");
+    assert!(parse_collection_methods("group,session").is_err());
+    assert_eq!(get_search_attributes("all"), vec!["*", "nTSecurityDescriptor"]);
+    assert_eq!(get_search_attributes("group,acl,objectprops"), vec!["*", "nTSecurityDescriptor"]);
+    // Security descriptors are not requested without acl
+    assert_eq!(get_search_attributes("group,objectprops"), vec!["*"]);
+    let attributes = get_search_attributes("objectprops");
+    assert!(attributes.contains(&"servicePrincipalName") && attributes.contains(&"objectSid"));
+    assert!(!attributes.contains(&"member") && !attributes.contains(&"nTSecurityDescriptor"));
+    let attributes = get_search_attributes("group");
+    assert!(attributes.contains(&"member") && !attributes.contains(&"description"));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_configuration_filter() {
+    assert!(needs_configuration("all", false));
+    assert!(needs_configuration("site", false));
+    assert!(needs_configuration("group", true));
+    assert!(!needs_configuration("group", false));
+    assert!(get_configuration_filter("all", false).contains("(objectclass=subnet)"));
+    assert!(!get_configuration_filter("group,objectprops", false).contains("(objectclass=site)"));
+    assert!(get_configuration_filter("objectprops", true).starts_with("(|(objectclass=pKIEnrollmentService)"));
+}
@@ -32,7 +32,7 @@
 //!  -f, --ldapfqdn <ldapfqdn>          Domain Controler FQDN like: DC01.DOMAIN.LOCAL or just DC01
 //!  -i, --ldapip <ldapip>              Domain Controller IP address like: 192.168.1.10
 //!  -P, --ldapport <ldapport>          LDAP port [default: 389]
-//!  -n, --name-server <name-server>    Alternative IP address name server to use for DNS queries
+//!  -n, --name-server <name-server>    DNS server IP address (optionally with :port) used for DC discovery, FQDN resolution and probing modules instead of the system resolver [aliases: dns-server]
 //!  -o, --output <output>              Output directory where you would like to save JSON files [default: ./]
 //!
 //!OPTIONAL FLAGS:
@@ -63,11 +63,20 @@
 //! Here is an example of how to use rusthound:
 //! ![demo](https://raw.githubusercontent.com/OPENCYBER-FR/RustHound/main/img/demo.gif)
 //! 
+// The json! templates of users and computers are deeper than the default limit of 128
+#![recursion_limit = "256"]
+
 pub mod args;
+pub mod attack;
 pub mod banner;
+pub mod cancel;
 pub mod errors;
 pub mod ldap;
+pub mod store;
 pub mod exec;
+pub mod daemon;
+pub mod profile;
+pub mod selftest;
 
 pub mod enums;
 pub mod json;
@@ -79,8 +88,12 @@ extern crate regex;
 
 // Reimport key functions and structure
 #[doc(inline)]
-pub use crate::errors::Error;
+pub use crate::errors::{Error, RustHoundError};
 #[doc(inline)]
 pub use ldap::ldap_search;
 #[doc(inline)]
 pub use ldap3::SearchEntry;
+#[doc(inline)]
+pub use json::parser::plugin::{ObjectParser, PluginOutput, register_parser};
+#[doc(inline)]
+pub use enums::sid::Sid;
@@ -1,48 +1,292 @@
+// The json! templates of users and computers are deeper than the default limit of 128
+#![recursion_limit = "256"]
+
+pub mod attack;
 pub mod modules;
 pub mod enums;
 pub mod json;
 
 pub mod args;
 pub mod banner;
+pub mod cancel;
 pub mod errors;
 pub mod ldap;
+pub mod store;
 pub mod exec;
+pub mod daemon;
+pub mod profile;
+pub mod selftest;
 
-use log::{info,trace,error};
+use chrono::Local;
+use log::{info,trace,warn,error};
 use std::collections::HashMap;
+use std::path::Path;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use crate::errors::Result;
+use crate::errors::{Result, RustHoundError, EXIT_INTERRUPTED, EXIT_PARTIAL, EXIT_SELFTEST};
 use args::*;
 use banner::*;
-use env_logger::Builder;
+use cancel::CancelToken;
+use daemon::{prune_runs, serve_health, HealthState, Schedule};
+use profile::{load_profile, save_profile};
+use attack::computer::add_computer;
+use attack::rbcd::set_rbcd;
+use attack::keycredential::set_keycredential;
+use attack::password::set_password;
+use attack::acl::{acl_backup, acl_restore};
+use selftest::run_selftest;
 use ldap::*;
+use enums::acl::{log_explained_aces, set_edge_provenance, set_explain_target};
+use enums::date::return_current_epoch;
 
 use modules::*;
+use modules::resolver::resolv::DnsConfig;
+use modules::smb::coercion::add_coerce_to_tgt_flag;
 use json::checker::*;
+use json::checker::privileged::{add_privileged_tags,load_privileged_pack,PrivilegedPack};
+use json::checker::bh_41::{add_computers_sites,add_dormant_flag,add_tier_zero_tags,get_benign_trustees,normalize_identifiers,remove_benign_aces};
+use json::graph::render_graph;
+use json::merge::merge_directories;
+use json::query::query_directory;
 use json::maker::make_result;
+use json::report::make_report;
+use json::report::service::make_service_accounts;
+use json::stats::{init_logger, partial_reasons, reset_partial, RunStats};
 use json::parser::*;
+use json::parser::pipeline::ParsePipeline;
 
 /// Main of RustHound
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Banner
-    print_banner();
-
     // Get args
     #[cfg(not(feature = "noargs"))]
-    let common_args: Options = extract_args();
+    let mut common_args: Options = extract_args();
     #[cfg(feature = "noargs")]
-    let common_args = auto_args();
+    let mut common_args = auto_args();
+
+    // Banner, stdout is only used for results with --stdout or graph mode
+    if !common_args.stdout && common_args.graph.is_none() && common_args.merge.is_none() && common_args.query.is_none() && common_args.profile_save.is_none() {
+        print_banner();
+    }
 
-    // Build logger
-    Builder::new()
-        .filter(Some("rusthound"), common_args.verbose)
-        .filter_level(log::LevelFilter::Error)
-        .init();
+    // Build logger counting warnings for stats.json
+    init_logger(common_args.verbose);
 
     // Get verbose level
     info!("Verbosity level: {:?}", common_args.verbose);
 
+    // Graph mode, render one subgraph from collected files without any LDAP request
+    if let Some(graph_args) = &common_args.graph {
+        if let Err(err) = render_graph(graph_args) {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+
+    // Merge mode, merge collected files from several collectors
+    if let Some(merge_args) = &common_args.merge {
+        if let Err(err) = merge_directories(merge_args) {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+
+    // Query mode, run custom queries on collected files without Neo4j
+    if let Some(query_args) = &common_args.query {
+        if let Err(err) = query_directory(query_args) {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+
+    // Profile mode, save credentials protected by DPAPI
+    if let Some(profile_args) = &common_args.profile_save {
+        if let Err(err) = save_profile(profile_args) {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+
+    // Self-test mode, embedded fixture domain checked with golden files
+    if common_args.selftest {
+        match run_selftest(&common_args) {
+            Ok(failures) if failures.is_empty() => return Ok(()),
+            Ok(failures) => {
+                for failure in &failures {
+                    error!("{}", failure);
+                }
+                error!("Self-test failed, {} golden objects don't match", failures.len());
+                process::exit(EXIT_SELFTEST);
+            }
+            Err(err) => {
+                error!("{err}");
+                process::exit(err.exit_code());
+            }
+        }
+    }
+
+    // Saved profile, values from the command line are kept
+    if !common_args.profile.contains("not set") {
+        match load_profile(&common_args.profile) {
+            Ok((domain, username, password)) => {
+                if common_args.domain.contains("not set") {
+                    common_args.domain = domain;
+                } else if !common_args.domain.eq_ignore_ascii_case(&domain) {
+                    warn!("Profile {} saved for {}, collecting {}", common_args.profile, domain, common_args.domain);
+                }
+                if common_args.username.contains("not set") {
+                    common_args.username = username;
+                }
+                common_args.password = password;
+            }
+            Err(err) => {
+                let err = RustHoundError::from(err);
+                error!("Failed to load profile {}: {err}", common_args.profile);
+                process::exit(err.exit_code());
+            }
+        }
+    }
+
+    // Credentials injected by an orchestrator or a secret manager, not in argv or environment variables
+    if let Err(err) = read_injected_credentials(
+        common_args.password_stdin,
+        &common_args.credentials_file,
+        &mut common_args.username,
+        &mut common_args.password,
+    ) {
+        let err = RustHoundError::from(err);
+        error!("Failed to read credentials: {err}");
+        process::exit(err.exit_code());
+    }
+
+    // Prompt credentials once, they are needed by LDAP and SMB modules
+    (common_args.username, common_args.password) = prompt_credentials(
+        &common_args.username,
+        &common_args.password,
+        common_args.kerberos,
+    );
+
+    // Domain controller found with the DNS server of --dns-server, the system resolver may not see the internal DNS
+    if common_args.ip.contains("not set") && !common_args.name_server.contains("not set") {
+        match DnsConfig::new(&common_args).discover_dc(&common_args.domain).await {
+            Some((fqdn, ip)) => {
+                info!("Domain controller {} ({}) found with DNS server {}", fqdn, ip, common_args.name_server);
+                common_args.ip = ip;
+                if common_args.ldapfqdn.contains("not set") {
+                    common_args.ldapfqdn = fqdn;
+                }
+            }
+            None => warn!("No domain controller of {} found with DNS server {}", common_args.domain, common_args.name_server),
+        }
+    }
+
+    // Write helpers, the collection options are used for the LDAPS connection
+    if let Some(add_computer_args) = &common_args.add_computer {
+        if let Err(err) = add_computer(&common_args, add_computer_args).await {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+    if let Some(set_rbcd_args) = &common_args.set_rbcd {
+        if let Err(err) = set_rbcd(&common_args, set_rbcd_args).await {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+    if let Some(set_keycredential_args) = &common_args.set_keycredential {
+        if let Err(err) = set_keycredential(&common_args, set_keycredential_args).await {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+    if let Some(set_password_args) = &common_args.set_password {
+        if let Err(err) = set_password(&common_args, set_password_args).await {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+    if let Some(acl_args) = &common_args.acl_backup {
+        if let Err(err) = acl_backup(&common_args, acl_args).await {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+    if let Some(acl_args) = &common_args.acl_restore {
+        if let Err(err) = acl_restore(&common_args, acl_args).await {
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        return Ok(())
+    }
+
+    // Daemon mode, one collection per --schedule occurrence until the process is stopped
+    if !common_args.schedule.contains("not set") {
+        if common_args.output_layout != "domain" {
+            info!("--schedule saves every run with --output-layout domain");
+            common_args.output_layout = "domain".to_string();
+        }
+        run_schedule(&common_args).await;
+        return Ok(())
+    }
+
+    // Ctrl-C stops the collectors, results already collected are written
+    let cancel = CancelToken::new(common_args.max_runtime);
+    cancel.cancel_on_interrupt();
+    if let Err(err) = collect(&common_args, &cancel).await {
+        error!("{err}");
+        process::exit(err.exit_code());
+    }
+
+    // Partial results are written, automation decides with the exit code
+    let partial = partial_reasons();
+    if !partial.is_empty() {
+        warn!("Partial collection, {} failures, see stats.json: {}", partial.len(), partial.join(", "));
+    }
+    if cancel.is_interrupted() {
+        process::exit(EXIT_INTERRUPTED);
+    }
+    if !partial.is_empty() {
+        process::exit(EXIT_PARTIAL);
+    }
+
+    // End banner
+    if !common_args.stdout {
+        print_end_banner();
+    }
+    Ok(())
+}
+
+/// Collection of one domain: LDAP requests, parsing, modules, analysis and output files.
+/// Returns the number of objects collected, collectors stop early once cancel is cancelled.
+async fn collect(common_args: &Options, cancel: &CancelToken) -> Result<usize> {
+    // Phases duration for stats.json
+    let mut run_stats = RunStats::new();
+
+    // Object to explain and ACE provenance, set before the parser workers are started
+    set_edge_provenance(common_args.edge_provenance != "not set");
+    set_explain_target(match common_args.explain_aces.contains("not set") {
+        true => None,
+        false => Some(common_args.explain_aces.to_owned()),
+    });
+
+    // Parser workers fed while LDAP entries are received, entries in the on-disk store are parsed after the collection
+    let pipeline = if common_args.parse_workers > 0 && common_args.disk_store.contains("not set") {
+        Some(ParsePipeline::start(common_args, common_args.parse_workers))
+    } else {
+        None
+    };
+
     // LDAP request to get all informations in result
     let result = ldap_search(
         common_args.ldaps,
@@ -54,7 +298,18 @@ async fn main() -> Result<()> {
         &common_args.password,
         common_args.adcs,
         common_args.kerberos,
-    ).await?;
+        common_args.follow_referrals,
+        common_args.global_catalog,
+        &common_args.collection_methods,
+        common_args.timeout,
+        common_args.search_timeout,
+        common_args.max_bandwidth,
+        &common_args.disk_store,
+        pipeline.as_ref().map(|pipeline| pipeline.sender()),
+        cancel,
+    ).await;
+    let (result, referred) = result?;
+    run_stats.phase("ldap");
 
     // Vector for content all
     let mut vec_users: Vec<serde_json::value::Value> = Vec::new();
@@ -65,9 +320,16 @@ async fn main() -> Result<()> {
     let mut vec_gpos: Vec<serde_json::value::Value> = Vec::new();
     let mut vec_fsps: Vec<serde_json::value::Value> = Vec::new();
     let mut vec_containers: Vec<serde_json::value::Value> = Vec::new();
+    let mut vec_contacts: Vec<serde_json::value::Value> = Vec::new();
     let mut vec_trusts: Vec<serde_json::value::Value> = Vec::new();
     let mut vec_cas: Vec<serde_json::value::Value> = Vec::new();
     let mut vec_templates: Vec<serde_json::value::Value> = Vec::new();
+    let mut vec_sites: Vec<serde_json::value::Value> = Vec::new();
+    let mut vec_subnets: Vec<serde_json::value::Value> = Vec::new();
+    let mut vec_sitelinks: Vec<serde_json::value::Value> = Vec::new();
+    let mut vec_authn: Vec<serde_json::value::Value> = Vec::new();
+    let mut vec_shares: Vec<serde_json::value::Value> = Vec::new();
+    let mut vec_services: Vec<serde_json::value::Value> = Vec::new();
 
     // Hashmap to link DN to SID
     let mut dn_sid = HashMap::new();
@@ -82,26 +344,72 @@ async fn main() -> Result<()> {
 
     // Analyze object by object 
     // Get type and parse it to get values
-    parse_result_type(
-        &common_args,
-        result,
-        &mut vec_users,
-        &mut vec_groups,
-        &mut vec_computers,
-        &mut vec_ous,
-        &mut vec_domains,
-        &mut vec_gpos,
-        &mut vec_fsps,
-        &mut vec_containers,
-        &mut vec_trusts,
-        &mut vec_cas,
-        &mut vec_templates,
-        &mut dn_sid,
-        &mut sid_type,
-        &mut fqdn_sid,
-        &mut fqdn_ip,
-        &mut adcs_templates,
-    );
+    match pipeline {
+        Some(pipeline) => {
+            // Entries already sent to the workers, the sender in result must be dropped to stop them
+            drop(result);
+            parse_pipeline_result(
+                pipeline,
+                &mut vec_users,
+                &mut vec_groups,
+                &mut vec_computers,
+                &mut vec_ous,
+                &mut vec_domains,
+                &mut vec_gpos,
+                &mut vec_fsps,
+                &mut vec_containers,
+                &mut vec_contacts,
+                &mut vec_trusts,
+                &mut vec_cas,
+                &mut vec_templates,
+                &mut vec_sites,
+                &mut vec_subnets,
+                &mut vec_sitelinks,
+                &mut vec_authn,
+                &mut vec_shares,
+                &mut vec_services,
+                &mut dn_sid,
+                &mut sid_type,
+                &mut fqdn_sid,
+                &mut fqdn_ip,
+                &mut adcs_templates,
+            );
+        }
+        None => parse_result_type(
+            common_args,
+            result,
+            &mut vec_users,
+            &mut vec_groups,
+            &mut vec_computers,
+            &mut vec_ous,
+            &mut vec_domains,
+            &mut vec_gpos,
+            &mut vec_fsps,
+            &mut vec_containers,
+            &mut vec_contacts,
+            &mut vec_trusts,
+            &mut vec_cas,
+            &mut vec_templates,
+            &mut vec_sites,
+            &mut vec_subnets,
+            &mut vec_sitelinks,
+            &mut vec_authn,
+            &mut vec_shares,
+            &mut vec_services,
+            &mut dn_sid,
+            &mut sid_type,
+            &mut fqdn_sid,
+            &mut fqdn_ip,
+            &mut adcs_templates,
+        ),
+    }
+    // Universal groups of other domains with members in this domain, from the Global Catalog
+    if common_args.global_catalog {
+        vec_groups.append(&mut parse_universal_groups(&referred, &common_args.domain, &mut dn_sid, &mut sid_type));
+    }
+    // Principals from other domains to resolve cross-domain members
+    add_referred_principals(referred, &mut dn_sid, &mut sid_type);
+    run_stats.phase("parsing");
     
     // Functions to replace and add missing values
     check_all_result(
@@ -114,27 +422,119 @@ async fn main() -> Result<()> {
         &mut vec_gpos,
         &mut vec_fsps,
         &mut vec_containers,
+        &mut vec_contacts,
         &mut vec_trusts,
+        &mut vec_sites,
+        &mut vec_subnets,
+        &mut vec_sitelinks,
         &mut dn_sid,
         &mut sid_type,
         &mut fqdn_sid,
         &mut fqdn_ip,
      );
 
+    // Dormant users and computers, before network modules to skip them
+    if common_args.stale_days > 0 {
+        let count = add_dormant_flag(&mut vec_users, &mut vec_computers, common_args.stale_days, return_current_epoch());
+        info!("{} users and computers without logon for {} days", count, common_args.stale_days);
+    }
+    run_stats.phase("checking");
+
     // Running modules
     run_modules(
-        &common_args,
+        common_args,
         &mut fqdn_ip,
+        &vec_users,
+        &vec_groups,
         &mut vec_computers,
+        &mut vec_ous,
+        &mut vec_domains,
+        &mut vec_gpos,
         &mut vec_cas,
         &mut vec_templates,
+        &mut vec_shares,
         &mut adcs_templates,
         &mut sid_type,
+        cancel,
     ).await;
+    run_stats.phase("modules");
+
+    // ACEs of the object given to --explain-aces, names of the trustees are known once checked and ADCS objects parsed by the module
+    if !common_args.explain_aces.contains("not set") {
+        log_explained_aces(&[&vec_users, &vec_groups, &vec_computers, &vec_ous, &vec_domains, &vec_gpos, &vec_containers, &vec_contacts, &vec_cas, &vec_templates]);
+    }
+
+    // Map computers to sites with subnets, IP addresses are only resolved by the fqdn-resolver module
+    if common_args.fqdn_resolver {
+        add_computers_sites(&mut vec_sites, &mut vec_computers, &fqdn_ip);
+    } else if vec_sites.iter().any(|site| !site["Subnets"].as_array().map(|subnets| subnets.is_empty()).unwrap_or(true)) {
+        warn!("Computers not mapped to sites, their IP address is needed: use --fqdn-resolver");
+    }
+
+    // Unconstrained delegation with coercible domain controllers, spooler and WebClient from the coercion probing module
+    if common_args.probe_coercion {
+        add_coerce_to_tgt_flag(&mut vec_users, &mut vec_computers);
+    }
+
+    // Third-party privileged groups and servers, before Tier-0 tags so their members are Tier-0 too
+    let privileged_pack = load_privileged_pack(&common_args.privileged_groups).unwrap_or_else(|err| {
+        warn!("Failed to load privileged groups mapping {err}, built-in pack used");
+        PrivilegedPack::new()
+    });
+    add_privileged_tags(&mut vec_users, &mut vec_groups, &mut vec_computers, &vec_services, &privileged_pack);
+
+    // Tier-0 tags for BloodHound CE (need ADCS module for CAs)
+    add_tier_zero_tags(&mut vec_users, &mut vec_groups, &mut vec_computers, &mut vec_domains, &mut vec_cas);
+
+    // Same identifiers as SharpHound to merge both collections
+    if common_args.sharphound_ids {
+        for vec_objects in [&mut vec_users, &mut vec_groups, &mut vec_computers, &mut vec_ous, &mut vec_domains, &mut vec_gpos, &mut vec_containers, &mut vec_contacts, &mut vec_cas, &mut vec_templates] {
+            normalize_identifiers(vec_objects);
+        }
+    }
+
+    // Analysis passes for findings report
+    let vec_service_accounts = make_service_accounts(&vec_users, &vec_groups);
+    let vec_findings = make_report(
+        common_args,
+        &vec_users,
+        &vec_groups,
+        &vec_computers,
+        &vec_ous,
+        &vec_domains,
+        &vec_containers,
+        &vec_trusts,
+        &vec_templates,
+        &vec_service_accounts,
+        &vec_authn,
+        &vec_shares,
+    );
+    run_stats.phase("analysis");
+
+    // Drop ACEs of built-in admins once the findings report is made
+    if common_args.acl_skip_benign {
+        let benign_trustees = get_benign_trustees(&common_args.acl_benign_sids);
+        let mut count = 0;
+        for vec_objects in [&mut vec_users, &mut vec_groups, &mut vec_computers, &mut vec_ous, &mut vec_domains, &mut vec_gpos, &mut vec_containers, &mut vec_contacts, &mut vec_cas, &mut vec_templates] {
+            count += remove_benign_aces(vec_objects, &benign_trustees);
+        }
+        info!("{} ACEs of benign trustees dropped", count);
+    }
+
+    let objects: usize = [&vec_users, &vec_groups, &vec_computers, &vec_ous, &vec_domains, &vec_gpos, &vec_containers, &vec_contacts, &vec_cas, &vec_templates, &vec_sites, &vec_authn]
+        .iter()
+        .map(|vec_objects| vec_objects.len())
+        .sum();
+
+    // Nothing written or uploaded from a partial collection with --fail-on-partial
+    let partial = partial_reasons();
+    if common_args.fail_on_partial && !partial.is_empty() {
+        return Err(RustHoundError::PartialError { reasons: partial.join(", ") })
+    }
 
     // Add all in json files
     let res = make_result(
-        &common_args,
+        common_args,
         vec_users,
         vec_groups,
         vec_computers,
@@ -142,15 +542,90 @@ async fn main() -> Result<()> {
         vec_domains,
         vec_gpos,
         vec_containers,
+        vec_contacts,
         &mut vec_cas,
         &mut vec_templates,
+        vec_sites,
+        vec_authn,
+        vec_shares,
+        vec_services,
+        vec_findings,
+        vec_service_accounts,
+        &mut run_stats,
+        cancel,
     );
-    match res {
-        Ok(_res) => trace!("Making json/zip files finished!"),
-        Err(err) => error!("Error. Reason: {err}")
+    res?;
+    trace!("Making json/zip files finished!");
+    Ok(objects)
+}
+
+/// Daemon mode: collect on every occurrence of the --schedule expression, prune old runs and update the health and metrics endpoint.
+async fn run_schedule(common_args: &Options) {
+    let schedule = match Schedule::parse(&common_args.schedule) {
+        Ok(schedule) => schedule,
+        Err(err) => {
+            error!("Invalid schedule {}: {}", common_args.schedule, err);
+            process::exit(1);
+        }
+    };
+    let health = Arc::new(Mutex::new(HealthState::default()));
+    if common_args.health_port > 0 {
+        if let Err(err) = serve_health(common_args.health_port, health.clone()) {
+            let err = RustHoundError::from(err);
+            error!("{err}");
+            process::exit(err.exit_code());
+        }
+        info!("Health endpoint on http://127.0.0.1:{0}/health, metrics on http://127.0.0.1:{0}/metrics", common_args.health_port);
     }
+    let domain_directory = Path::new(&common_args.path).join(common_args.domain.to_lowercase());
 
-    // End banner
-    print_end_banner();
-    Ok(())
-}
\ No newline at end of file
+    loop {
+        let next = match schedule.next_after(Local::now()) {
+            Some(next) => next,
+            None => {
+                error!("No next run for schedule {}", common_args.schedule);
+                return
+            }
+        };
+        if let Ok(mut state) = health.lock() {
+            state.next_run = Some(next.to_rfc3339());
+        }
+        info!("Next collection at {}", next.format("%Y-%m-%d %H:%M"));
+        tokio::time::sleep((next - Local::now()).to_std().unwrap_or_default()).await;
+
+        let start = Instant::now();
+        let started = Local::now();
+        reset_ldap_counters();
+        reset_partial();
+        let res = collect(common_args, &CancelToken::new(common_args.max_runtime)).await;
+        if let Err(err) = &res {
+            error!("{err}");
+        }
+        if let Ok(mut state) = health.lock() {
+            state.runs += 1;
+            state.last_run = Some(started.to_rfc3339());
+            state.last_timestamp = started.timestamp();
+            state.last_duration = Some(start.elapsed().as_secs_f64());
+            state.last_ldap_bytes = received_bytes();
+            state.ldap_latencies = response_latencies();
+            state.ldap_latencies.sort_by(|a, b| a.total_cmp(b));
+            state.last_objects = *res.as_ref().unwrap_or(&0);
+            state.last_error = match res {
+                Ok(_) if !partial_reasons().is_empty() => Some(format!("Partial collection: {}", partial_reasons().join(", "))),
+                Ok(_) => None,
+                Err(err) => Some(err.to_string()),
+            };
+            if state.last_error.is_some() {
+                state.failures += 1;
+            }
+        }
+
+        if common_args.schedule_keep > 0 {
+            match prune_runs(&domain_directory, common_args.schedule_keep) {
+                Ok(pruned) if !pruned.is_empty() => info!("{} old runs removed from {}", pruned.len(), domain_directory.display()),
+                Ok(_) => {}
+                Err(err) => error!("Failed to prune runs in {}: {}", domain_directory.display(), err),
+            }
+        }
+    }
+}
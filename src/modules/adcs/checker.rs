@@ -6,7 +6,7 @@ use std::io::prelude::*;
 use std::net::TcpStream;
 use std::str;
 
-use crate::modules::resolver::resolv;
+use crate::modules::resolver::resolv::{self, DnsConfig};
 
 /// Check if template is enabled
 pub fn check_enabled_template(
@@ -47,8 +47,7 @@ pub fn check_enabled_template(
 pub async fn get_conf(
     vec_cas: &mut Vec<serde_json::value::Value>,
     dc_only: bool,
-    dns_tcp: bool,
-    name_server: &String,
+    dns: &DnsConfig,
 ) {
     for i in 0..vec_cas.len() {
         if dc_only {
@@ -59,8 +58,7 @@ pub async fn get_conf(
             // Checking if web enrollment is enabled
             let web_enrollment = web_enrollment(
                 vec_cas[i]["Properties"]["DNS Name"].as_str().unwrap().to_string(),
-                dns_tcp,
-                name_server,
+                dns,
             ).await;
             vec_cas[i]["Properties"]["Web Enrollment"] = web_enrollment.to_owned().into(); 
             vec_cas[i]["Properties"]["User Specified SAN"] = String::from("Unknown").into();
@@ -73,15 +71,13 @@ pub async fn get_conf(
 /// HEAD request on /certsrv/ to check web enrrollment
 async fn web_enrollment(
     target: String,
-    dns_tcp: bool,
-    name_server: &String,
+    dns: &DnsConfig,
 ) -> String {
 
     debug!("Checking web enrollment on {}",&target);
     let ip = resolv::resolver(
         target.to_owned(),
-        dns_tcp,
-        name_server).await;
+        dns).await;
     let url = format!("http://{}/certsrv/",target);
     trace!("Resolved {} to {}",&target,&ip);
 
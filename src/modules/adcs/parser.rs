@@ -1,6 +1,3 @@
-extern crate lazy_static;
-
-use lazy_static::lazy_static;
 use colored::Colorize;
 use ldap3::SearchEntry;
 use x509_parser::prelude::*;
@@ -9,6 +6,7 @@ use log::{info, debug, trace, error};
 
 use crate::enums::sid::decode_guid;
 use crate::enums::acl::parse_ntsecuritydescriptor;
+use crate::enums::pkiflags::decode_ekus;
 use crate::json::templates::bh_41::{prepare_adcs_ca_json_template,prepare_adcs_template_json_template};
 use crate::modules::adcs::utils::*;
 use crate::modules::adcs::flags::*;
@@ -273,7 +271,7 @@ pub fn parse_adcs_template(
                     template.certificate_name_flag = get_pki_cert_name_flags(
                         &mut template,
                         &mut template_json,
-                        value[0].parse::<i64>().unwrap_or(0) as u64
+                        &value[0]
                     );
                     template_json["Properties"]["Certificate Name Flag"] = template.certificate_name_flag.to_owned().into();
                 } else {
@@ -288,7 +286,7 @@ pub fn parse_adcs_template(
                     template.enrollment_flag = get_pki_enrollment_flags(
                         &mut template,
                         &mut template_json,
-                        value[0].parse::<i64>().unwrap_or(0) as u64
+                        &value[0]
                     );
                     template_json["Properties"]["Enrollment Flag"] = template.enrollment_flag.to_owned().into();
                 } else {
@@ -303,7 +301,7 @@ pub fn parse_adcs_template(
                     template.private_key_flag = get_pki_private_flags(
                         &mut template,
                         &mut template_json,
-                        value[0].parse::<i64>().unwrap_or(0) as u64
+                        &value[0]
                     );
                     template_json["Properties"]["Private Key Flag"] = template.private_key_flag.to_owned().into();
                 } else {
@@ -323,30 +321,14 @@ pub fn parse_adcs_template(
                 }
             }
             "msPKI-RA-Application-Policies" => {
-                // parsed but not use with ly4k BloodHound version
                 if value.len() != 0 {
-                    let application_policies = value;
-                    let mut values = Vec::new();
-                    for oid in application_policies {
-                        if OID_TO_STR_MAP.contains_key(oid){
-                            values.push(OID_TO_STR_MAP.get(oid).unwrap().to_string());
-                        }
-                        continue
-                    }
-                    template.application_policies = values;
+                    template.application_policies = decode_ekus(value);
+                    template_json["Properties"]["Application Policies"] = template.application_policies.to_owned().into();
                 }
             }
             "pKIExtendedKeyUsage" => {
                 if value.len() != 0 {
-                    let eku = value;
-                    let mut values = Vec::new();
-                    for oid in eku {
-                        if OID_TO_STR_MAP.contains_key(oid){
-                            values.push(OID_TO_STR_MAP.get(oid).unwrap().to_string());
-                        }
-                        continue
-                    }
-                    template.extended_key_usage = values;
+                    template.extended_key_usage = decode_ekus(value);
                     template_json["Properties"]["Extended Key Usage"] = template.extended_key_usage.to_owned().into();
                 } 
             }
@@ -386,6 +368,16 @@ pub fn parse_adcs_template(
         }
     }
 
+    // Enrollment Rights, principals with Enroll or AutoEnroll
+    let mut enrollment_rights: Vec<String> = Vec::new();
+    for ace in template_json["Aces"].as_array().unwrap_or(&Vec::new()) {
+        let sid = ace["PrincipalSID"].as_str().unwrap_or("").to_string();
+        if vec!["Enroll","AutoEnroll"].contains(&ace["RightName"].as_str().unwrap_or("")) && !enrollment_rights.contains(&sid) {
+            enrollment_rights.push(sid);
+        }
+    }
+    template_json["Properties"]["Enrollment Rights"] = enrollment_rights.into();
+
     // Other values
     // Any Purpose
     if template.extended_key_usage.contains(&"Any Purpose".to_string()) || template.extended_key_usage.len() == 0 {
@@ -431,257 +423,3 @@ pub fn parse_adcs_template(
     //trace!("TEMPLATE VALUE: {:?}",template_json);
     return template_json
 }
-
-// OID_TO_STR_MAP with all know guid
-// <https://www.pkisolutions.com/object-identifiers-oid-in-pki/>
-// <https://github.com/ly4k/Certipy/blob/main/certipy/lib/constants.py#L145>
-lazy_static! {
-    static ref OID_TO_STR_MAP: HashMap<String, String> = {
-        let mut map = HashMap::new();
-        map.insert(
-            "1.3.6.1.4.1.311.76.6.1".to_string(),
-            "Windows Update".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.11".to_string(),
-            "Key Recovery".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.25".to_string(),
-            "Windows Third Party Application Component".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.21.6".to_string(),
-            "Key Recovery Agent".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.6".to_string(),
-            "Windows System Component Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.61.4.1".to_string(),
-            "Early Launch Antimalware Drive".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.23".to_string(),
-            "Windows TCB Component".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.61.1.1".to_string(),
-            "Kernel Mode Code Signing".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.26".to_string(),
-            "Windows Software Extension Verification".to_string(),
-        );
-        map.insert(
-            "2.23.133.8.3".to_string(),
-            "Attestation Identity Key Certificate".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.76.3.1".to_string(),
-            "Windows Store".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.6.1".to_string(),
-            "Key Pack Licenses".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.20.2.2".to_string(),
-            "Smart Card Logon".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.2.3.5".to_string(),
-            "KDC Authentication".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.7".to_string(),
-            "IP security use".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.8".to_string(),
-            "Embedded Windows System Component Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.20".to_string(),
-            "Windows Kits Component".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.6".to_string(),
-            "IP security tunnel termination".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.5".to_string(),
-            "Windows Hardware Driver Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.8.2.2".to_string(),
-            "IP security IKE intermediate".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.39".to_string(),
-            "Windows Hardware Driver Extended Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.6.2".to_string(),
-            "License Server Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.5.1".to_string(),
-            "Windows Hardware Driver Attested Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.76.5.1".to_string(),
-            "Dynamic Code Generato".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.8".to_string(),
-            "Time Stamping".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.4.1".to_string(),
-            "File Recovery".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.2.6.1".to_string(),
-            "SpcRelaxedPEMarkerCheck".to_string(),
-        );
-        map.insert(
-            "2.23.133.8.1".to_string(),
-            "Endorsement Key Certificate".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.2.6.2".to_string(),
-            "SpcEncryptedDigestRetryCount".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.4".to_string(),
-            "Encrypting File System".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.1".to_string(),
-            "Server Authentication".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.61.5.1".to_string(),
-            "HAL Extension".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.4".to_string(),
-            "Secure Email".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.5".to_string(),
-            "IP security end system".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.9".to_string(),
-            "Root List Signe".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.30".to_string(),
-            "Disallowed List".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.19".to_string(),
-            "Revoked List Signe".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.21".to_string(),
-            "Windows RT Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.10".to_string(),
-            "Qualified Subordination".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.12".to_string(),
-            "Document Signing".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.24".to_string(),
-            "Protected Process Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.80.1".to_string(),
-            "Document Encryption".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.22".to_string(),
-            "Protected Process Light Verification".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.21.19".to_string(),
-            "Directory Service Email Replication".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.21.5".to_string(),
-            "Private Key Archival".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.5.1".to_string(),
-            "Digital Rights".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.27".to_string(),
-            "Preview Build Signing".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.20.2.1".to_string(),
-            "Certificate Request Agent".to_string(),
-        );
-        map.insert(
-            "2.23.133.8.2".to_string(),
-            "Platform Certificate".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.20.1".to_string(),
-            "CTL Usage".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.9".to_string(),
-            "OCSP Signing".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.3".to_string(),
-            "Code Signing".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.1".to_string(),
-            "Microsoft Trust List Signing".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.2".to_string(),
-            "Microsoft Time Stamping".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.76.8.1".to_string(),
-            "Microsoft Publishe".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.5.7.3.2".to_string(),
-            "Client Authentication".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.5.2.3.4".to_string(),
-            "PKIINIT Client Authentication".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.13".to_string(),
-            "Lifetime Signing".to_string(),
-        );
-        map.insert(
-            "2.5.29.37.0".to_string(),
-            "Any Purpose".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.64.1.1".to_string(),
-            "Server Trust".to_string(),
-        );
-        map.insert(
-            "1.3.6.1.4.1.311.10.3.7".to_string(),
-            "OEM Windows System Component Verification".to_string(),
-        );
-        map
-    };
-}
\ No newline at end of file
@@ -1,33 +1,51 @@
 //! List of RustHound add-on modules
 pub mod resolver;
 pub mod adcs;
+pub mod mssql;
+pub mod rdp;
+pub mod scheduler;
+pub mod smb;
+pub mod sysvol;
+pub mod winrm;
 
 use log::info;
 use std::collections::HashMap;
 use crate::args::*;
+use crate::cancel::CancelToken;
 use crate::json::checker::add_type_for_ace;
 
 /// Function to run all modules requested
 pub async fn run_modules(
    common_args: &Options, 
    fqdn_ip: &mut HashMap<String, String>, 
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_groups: &Vec<serde_json::value::Value>,
    vec_computers: &mut Vec<serde_json::value::Value>,
+   vec_ous: &mut Vec<serde_json::value::Value>,
+   vec_domains: &mut Vec<serde_json::value::Value>,
+   vec_gpos: &mut Vec<serde_json::value::Value>,
    vec_cas: &mut Vec<serde_json::value::Value>,
    vec_templates: &mut Vec<serde_json::value::Value>,
+   vec_shares: &mut Vec<serde_json::value::Value>,
    adcs_templates: &mut HashMap<String, Vec<String>>,
    sid_type: &mut HashMap<String, String>,
+   cancel: &CancelToken,
 ) {
+   // DNS settings of the modules resolving computers
+   let dns = resolver::resolv::DnsConfig::new(common_args);
+
    // [MODULE - RESOLVER] Running module to resolve FQDN to IP address?
-   if common_args.fqdn_resolver {
+   if common_args.fqdn_resolver && cancel.proceed("fqdn-resolver module") {
       resolver::resolv::resolving_all_fqdn(
-         common_args.dns_tcp,
-         &common_args.name_server,
-         fqdn_ip, &vec_computers
+         &dns,
+         fqdn_ip, &vec_computers,
+         common_args.stale_skip_probes,
+         cancel,
       ).await;
    }
 
    // [MODULE - ADCS] Running last function for adcs templates
-   if common_args.adcs {
+   if common_args.adcs && cancel.proceed("adcs module") {
       info!("Starting checker for ADCS values...");
       adcs::checker::check_enabled_template(
          vec_cas,
@@ -40,13 +58,123 @@ pub async fn run_modules(
       adcs::checker::get_conf(
             vec_cas,
             common_args.dc_only,
-            common_args.dns_tcp,
-            &common_args.name_server,
+            &dns,
       ).await;
       add_type_for_ace(vec_cas, &sid_type);
       add_type_for_ace(vec_templates, &sid_type);
       info!("Checking for ADCS values finished!");
    }
 
+   // [MODULE - MSSQL] Running module to get MSSQL instances version
+   if common_args.mssql_probe && cancel.proceed("mssql-probe module") {
+      mssql::tds::probe_all_instances(
+         vec_computers,
+         fqdn_ip,
+         &dns,
+         common_args.stale_skip_probes,
+         cancel,
+      ).await;
+   }
+
+   // [MODULE - SMB] Running module to check spooler and WebClient services
+   if common_args.probe_coercion && cancel.proceed("probe-coercion module") {
+      let credentials = smb::ntlm::NtlmCredentials::new(
+         &common_args.username,
+         &common_args.password,
+         &common_args.domain,
+      );
+      smb::coercion::probe_all_computers(
+         vec_computers,
+         fqdn_ip,
+         &dns,
+         &credentials,
+         common_args.stale_skip_probes,
+         cancel,
+      ).await;
+   }
+
+   // [MODULE - SMB] Running module to get SMB signing, SMBv1 and dialect
+   if common_args.probe_smb && cancel.proceed("probe-smb module") {
+      smb::survey::survey_all_computers(
+         vec_computers,
+         fqdn_ip,
+         &dns,
+         common_args.stale_skip_probes,
+         cancel,
+      ).await;
+   }
+
+   // [MODULE - WINRM] Running module to check WinRM and WMI reachability
+   if common_args.probe_winrm && cancel.proceed("probe-winrm module") {
+      let credentials = smb::ntlm::NtlmCredentials::new(
+         &common_args.username,
+         &common_args.password,
+         &common_args.domain,
+      );
+      winrm::wsman::survey_all_computers(
+         vec_computers,
+         fqdn_ip,
+         &dns,
+         &credentials,
+         common_args.stale_skip_probes,
+         cancel,
+      ).await;
+   }
+
+   // [MODULE - RDP] Running module to check RDP NLA and certificate CN
+   if common_args.probe_rdp && cancel.proceed("probe-rdp module") {
+      rdp::nla::survey_all_computers(
+         vec_computers,
+         fqdn_ip,
+         &dns,
+         common_args.stale_skip_probes,
+         cancel,
+      ).await;
+   }
+
+   // [MODULE - SMB] Running module to list shares of servers and their access
+   if common_args.shares && cancel.proceed("shares module") {
+      let credentials = smb::ntlm::NtlmCredentials::new(
+         &common_args.username,
+         &common_args.password,
+         &common_args.domain,
+      );
+      smb::shares::enumerate_all_shares(
+         vec_computers,
+         vec_shares,
+         fqdn_ip,
+         &dns,
+         &credentials,
+         common_args.stale_skip_probes,
+         cancel,
+      ).await;
+   }
+
+   // [MODULE - SYSVOL] Running module to get local groups changed by GPOs
+   if common_args.sysvol && cancel.proceed("sysvol module") {
+      sysvol::collect_gpo_changes(
+         common_args,
+         vec_users,
+         vec_groups,
+         vec_computers,
+         vec_gpos,
+         vec_ous,
+         vec_domains,
+         sid_type,
+         cancel,
+      ).await;
+   }
+
+   // [MODULE - SYSVOL] Running module to get SYSVOL and NETLOGON files writable by non-admins
+   if common_args.sysvol_acls && cancel.proceed("sysvol-acls module") {
+      sysvol::collect_sysvol_acls(
+         common_args,
+         vec_gpos,
+         vec_shares,
+         sid_type,
+         cancel,
+      ).await;
+   }
+
    // Other modules need to be add here...
 }
\ No newline at end of file
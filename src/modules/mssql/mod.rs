@@ -0,0 +1,9 @@
+//! MSSQL pre-login probe
+//!
+//! This module will send a TDS PRELOGIN packet to every MSSQL instance found in MSSQLSvc spn to get the server version.
+//! Only instances with a static port are probed, named instances without port need SQL Browser service (UDP 1434).
+//! Module can be used with **--mssql-probe** args
+//!
+//! <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-tds/60f56408-0188-4cd5-8b90-25c6f2423868>
+//!
+pub mod tds;
@@ -0,0 +1,149 @@
+use log::{info,debug,trace};
+use colored::Colorize;
+
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::net::{TcpStream, SocketAddr};
+use std::time::Duration;
+
+use crate::cancel::CancelToken;
+use crate::modules::resolver::resolv::{self, DnsConfig};
+use crate::modules::scheduler;
+
+/// TDS packet type for PRELOGIN message
+const TDS_PRELOGIN: u8 = 0x12;
+/// PRELOGIN option token VERSION
+const PL_OPTION_VERSION: u8 = 0x00;
+/// PRELOGIN option token TERMINATOR
+const PL_OPTION_TERMINATOR: u8 = 0xFF;
+
+/// Function to probe all MSSQL instances found for the computers
+pub async fn probe_all_instances(
+   vec_computers: &mut Vec<serde_json::value::Value>,
+   fqdn_ip: &HashMap<String, String>,
+   dns: &DnsConfig,
+   skip_dormant: bool,
+   cancel: &CancelToken,
+) {
+   info!("Probing MSSQL instances started...");
+   for i in 0..vec_computers.len()
+   {
+      if cancel.is_cancelled() {
+         cancel.stopped("MSSQL probe", format!("{} of {} computers checked", i, vec_computers.len()));
+         break
+      }
+      let count = vec_computers[i]["Properties"]["mssqlinstances"].as_array().map(|a| a.len()).unwrap_or(0);
+      if count == 0 || !scheduler::is_probe_target(&vec_computers[i], skip_dormant) {
+         continue
+      }
+      let fqdn = vec_computers[i]["Properties"]["name"].as_str().unwrap_or("").to_string();
+      // Use the IP address from fqdn-resolver module or resolve it
+      let mut ip = fqdn_ip.get(&fqdn).unwrap_or(&"".to_string()).to_owned();
+      if ip.is_empty() {
+         ip = resolv::resolver(fqdn.to_owned(), dns).await;
+      }
+      if ip.contains("Not found") {
+         debug!("Can't resolve {} to probe MSSQL instances",&fqdn);
+         continue
+      }
+      for j in 0..count
+      {
+         let port = match vec_computers[i]["Properties"]["mssqlinstances"][j]["Port"].as_u64() {
+            Some(port) => port as u16,
+            None => continue,
+         };
+         if let Some(version) = prelogin(&ip, port) {
+            info!("MSSQL {} on {}:{}",&version.yellow().bold(),&fqdn.bold(),port);
+            vec_computers[i]["Properties"]["mssqlinstances"][j]["Version"] = version.into();
+         }
+      }
+   }
+   info!("Probing MSSQL instances finished!");
+}
+
+/// Send TDS PRELOGIN packet and return the server version
+fn prelogin(ip: &String, port: u16) -> Option<String> {
+   let address = format!("{}:{}",ip,port).parse::<SocketAddr>().ok()?;
+   let mut stream = TcpStream::connect_timeout(&address, Duration::from_secs(3)).ok()?;
+   stream.set_read_timeout(Some(Duration::from_secs(3))).ok()?;
+   trace!("Connected to MSSQL {}",&address);
+
+   stream.write_all(&prepare_prelogin_packet()).ok()?;
+
+   let mut buffer = [0; 512];
+   let result = stream.read(&mut buffer[..]).ok()?;
+   trace!("PRELOGIN response: {:?}",&buffer[..result]);
+   parse_prelogin_version(&buffer[..result])
+}
+
+/// Build PRELOGIN packet with VERSION, ENCRYPTION, INSTOPT, THREADID and MARS options
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-tds/60f56408-0188-4cd5-8b90-25c6f2423868>
+fn prepare_prelogin_packet() -> Vec<u8> {
+   let payload: Vec<u8> = vec![
+      // VERSION offset 26 length 6
+      0x00, 0x00, 0x1A, 0x00, 0x06,
+      // ENCRYPTION offset 32 length 1
+      0x01, 0x00, 0x20, 0x00, 0x01,
+      // INSTOPT offset 33 length 1
+      0x02, 0x00, 0x21, 0x00, 0x01,
+      // THREADID offset 34 length 4
+      0x03, 0x00, 0x22, 0x00, 0x04,
+      // MARS offset 38 length 1
+      0x04, 0x00, 0x26, 0x00, 0x01,
+      // TERMINATOR
+      PL_OPTION_TERMINATOR,
+      // VERSION data
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+      // ENCRYPTION data: ENCRYPT_NOT_SUP
+      0x02,
+      // INSTOPT data
+      0x00,
+      // THREADID data
+      0x00, 0x00, 0x00, 0x00,
+      // MARS data
+      0x00,
+   ];
+   let length = (payload.len() + 8) as u16;
+   let mut packet: Vec<u8> = vec![TDS_PRELOGIN, 0x01];
+   packet.extend_from_slice(&length.to_be_bytes());
+   // SPID, PacketID, Window
+   packet.extend_from_slice(&[0x00, 0x00, 0x01, 0x00]);
+   packet.extend_from_slice(&payload);
+   packet
+}
+
+/// Parse PRELOGIN response to get VERSION option
+fn parse_prelogin_version(response: &[u8]) -> Option<String> {
+   if response.len() < 8 {
+      return None
+   }
+   let payload = &response[8..];
+   let mut i = 0;
+   while i + 5 <= payload.len() && payload[i] != PL_OPTION_TERMINATOR {
+      let offset = u16::from_be_bytes([payload[i+1], payload[i+2]]) as usize;
+      let length = u16::from_be_bytes([payload[i+3], payload[i+4]]) as usize;
+      if payload[i] == PL_OPTION_VERSION && length >= 4 && offset + 4 <= payload.len() {
+         let major = payload[offset];
+         let minor = payload[offset+1];
+         let build = u16::from_be_bytes([payload[offset+2], payload[offset+3]]);
+         return Some(format!("{} ({}.{}.{})",get_mssql_product(major),major,minor,build))
+      }
+      i += 5;
+   }
+   None
+}
+
+/// Get the MSSQL product name from the major version
+fn get_mssql_product(major: u8) -> String {
+   match major {
+      16 => "SQL Server 2022".to_string(),
+      15 => "SQL Server 2019".to_string(),
+      14 => "SQL Server 2017".to_string(),
+      13 => "SQL Server 2016".to_string(),
+      12 => "SQL Server 2014".to_string(),
+      11 => "SQL Server 2012".to_string(),
+      10 => "SQL Server 2008".to_string(),
+      9 => "SQL Server 2005".to_string(),
+      _ => "SQL Server".to_string(),
+   }
+}
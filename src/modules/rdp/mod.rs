@@ -0,0 +1,12 @@
+//! RDP NLA and certificate survey
+//!
+//! This module will send X.224 connection requests to the RDP port (3389) of every collected computer to check
+//! if Network Level Authentication (CredSSP) is enforced, then start the TLS handshake to read the certificate CN.
+//! RDP certificates are self-signed with the real host name by default, even when the DNS name differs.
+//! Module can be used with **--probe-rdp** args and writes **rdp.csv**
+//!
+//! - **rdpavailable**: RDP listening and X.224 connection confirmed
+//! - **rdpnlarequired**: TLS without CredSSP refused by the server (HYBRID_REQUIRED_BY_SERVER)
+//! - **rdpcertcn**: common name of the server TLS certificate
+//!
+pub mod nla;
@@ -0,0 +1,296 @@
+use log::{info,debug,trace};
+use colored::Colorize;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::prelude::*;
+use std::net::{TcpStream, SocketAddr};
+use std::time::Duration;
+use x509_parser::prelude::*;
+
+use crate::cancel::CancelToken;
+use crate::modules::resolver::resolv::DnsConfig;
+use crate::modules::scheduler;
+
+/// RDP port
+const RDP_PORT: u16 = 3389;
+/// TCP timeout for every RDP probe
+const RDP_TIMEOUT: Duration = Duration::from_secs(3);
+/// Maximum size of the TLS handshake read from the server
+const MAX_HANDSHAKE_SIZE: usize = 0x0001_0000;
+/// RDP_NEG_REQ requestedProtocols
+const PROTOCOL_SSL: u32 = 0x0000_0001;
+const PROTOCOL_HYBRID: u32 = 0x0000_0002;
+/// RDP_NEG_RSP and RDP_NEG_FAILURE types
+const TYPE_RDP_NEG_RSP: u8 = 0x02;
+const TYPE_RDP_NEG_FAILURE: u8 = 0x03;
+/// RDP_NEG_FAILURE failureCode
+const SSL_NOT_ALLOWED_BY_SERVER: u32 = 0x0000_0002;
+const HYBRID_REQUIRED_BY_SERVER: u32 = 0x0000_0005;
+/// TLS record and handshake types
+const TLS_HANDSHAKE: u8 = 0x16;
+const TLS_ALERT: u8 = 0x15;
+const HANDSHAKE_CERTIFICATE: u8 = 0x0B;
+const HANDSHAKE_SERVER_HELLO_DONE: u8 = 0x0E;
+/// TLS 1.2 cipher suites offered, ECDHE and RSA key exchange with AES
+const CIPHER_SUITES: [u16; 12] = [0xC02F, 0xC030, 0xC027, 0xC028, 0xC013, 0xC014, 0x009C, 0x009D, 0x003C, 0x003D, 0x002F, 0x0035];
+
+/// Structure containing the RDP survey result for one computer.
+pub struct RdpSurvey {
+   pub available: bool,
+   pub nla_required: Option<bool>,
+   pub cert_cn: Option<String>,
+}
+
+/// X.224 Connection Confirm negotiation result.
+#[derive(Debug, PartialEq)]
+pub enum Negotiation {
+   /// selectedProtocol
+   Selected(u32),
+   /// failureCode
+   Failure(u32),
+   /// No negotiation data, standard RDP security only
+   Legacy,
+}
+
+/// Function to probe RDP on all enabled computers
+pub async fn survey_all_computers(
+   vec_computers: &mut Vec<serde_json::value::Value>,
+   fqdn_ip: &HashMap<String, String>,
+   dns: &DnsConfig,
+   skip_dormant: bool,
+   cancel: &CancelToken,
+) {
+   info!("RDP NLA survey started...");
+   let targets = scheduler::prepare_targets(vec_computers, fqdn_ip, dns, skip_dormant).await;
+   let results = scheduler::run_probes(&targets, "Probing RDP", cancel, |target| {
+      survey(&target.ip, &target.fqdn)
+   }).await;
+
+   let mut without_nla = 0;
+   for (index, result) in results {
+      let name = vec_computers[index]["Properties"]["name"].as_str().unwrap_or("").to_string();
+      if result.nla_required == Some(false) {
+         without_nla += 1;
+         debug!("RDP without NLA on {}",&name.yellow().bold());
+      }
+      if let Some(cn) = &result.cert_cn {
+         if !name.to_lowercase().starts_with(&cn.to_lowercase()) {
+            debug!("RDP certificate CN {} on {}",&cn.yellow().bold(),&name);
+         }
+      }
+      vec_computers[index]["Properties"]["rdpavailable"] = result.available.into();
+      vec_computers[index]["Properties"]["rdpnlarequired"] = result.nla_required.into();
+      vec_computers[index]["Properties"]["rdpcertcn"] = result.cert_cn.into();
+   }
+   info!("{} computers with RDP without NLA",without_nla.to_string().yellow().bold());
+   info!("RDP NLA survey finished!");
+}
+
+/// Request TLS without CredSSP: refused if NLA is enforced, then read the certificate on a TLS or CredSSP connection.
+fn survey(ip: &String, fqdn: &String) -> Option<RdpSurvey> {
+   let mut stream = connect(ip)?;
+   let negotiation = negotiate(&mut stream, PROTOCOL_SSL)?;
+   let mut result = RdpSurvey { available: true, nla_required: None, cert_cn: None };
+   match negotiation {
+      Negotiation::Selected(_) => {
+         result.nla_required = Some(false);
+         result.cert_cn = get_certificate_cn(&mut stream, fqdn);
+      }
+      Negotiation::Failure(HYBRID_REQUIRED_BY_SERVER) => {
+         result.nla_required = Some(true);
+         result.cert_cn = connect(ip)
+            .and_then(|mut stream| match negotiate(&mut stream, PROTOCOL_SSL | PROTOCOL_HYBRID)? {
+               Negotiation::Selected(_) => get_certificate_cn(&mut stream, fqdn),
+               _ => None,
+            });
+      }
+      Negotiation::Failure(SSL_NOT_ALLOWED_BY_SERVER) | Negotiation::Legacy => {
+         result.nla_required = Some(false);
+      }
+      Negotiation::Failure(code) => {
+         trace!("RDP negotiation failure {} on {}",code,fqdn);
+      }
+   }
+   Some(result)
+}
+
+/// Open TCP connection with RDP timeouts.
+fn connect(ip: &String) -> Option<TcpStream> {
+   let address = format!("{}:{}",ip,RDP_PORT).parse::<SocketAddr>()
+      .or_else(|_| format!("[{}]:{}",ip,RDP_PORT).parse::<SocketAddr>()).ok()?;
+   let stream = TcpStream::connect_timeout(&address, RDP_TIMEOUT).ok()?;
+   stream.set_read_timeout(Some(RDP_TIMEOUT)).ok()?;
+   stream.set_write_timeout(Some(RDP_TIMEOUT)).ok()?;
+   Some(stream)
+}
+
+/// Send X.224 Connection Request with RDP_NEG_REQ and read the Connection Confirm.
+fn negotiate(stream: &mut TcpStream, protocols: u32) -> Option<Negotiation> {
+   stream.write_all(&connection_request(protocols)).ok()?;
+   let mut header = [0u8; 4];
+   stream.read_exact(&mut header).ok()?;
+   let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+   if header[0] != 3 || length < 11 {
+      return None
+   }
+   let mut data = vec![0u8; length - 4];
+   stream.read_exact(&mut data).ok()?;
+   parse_connection_confirm(&data)
+}
+
+/// TPKT header, X.224 Connection Request TPDU and RDP_NEG_REQ.
+fn connection_request(protocols: u32) -> Vec<u8> {
+   let mut packet: Vec<u8> = vec![3, 0];
+   packet.extend(19u16.to_be_bytes());                       // TPKT length
+   packet.extend([14u8, 0xE0, 0, 0, 0, 0, 0]);               // LI, CR CDT, DST-REF, SRC-REF, CLASS
+   packet.extend([0x01u8, 0]);                               // TYPE_RDP_NEG_REQ, flags
+   packet.extend(8u16.to_le_bytes());
+   packet.extend(protocols.to_le_bytes());
+   packet
+}
+
+/// Parse the X.224 Connection Confirm TPDU without TPKT header.
+pub fn parse_connection_confirm(data: &[u8]) -> Option<Negotiation> {
+   // LI and CC CDT
+   if data.len() < 7 || data[1] & 0xF0 != 0xD0 {
+      return None
+   }
+   let negotiation = match data.get(7..15) {
+      Some(negotiation) => negotiation,
+      None => return Some(Negotiation::Legacy),
+   };
+   let value = u32::from_le_bytes(negotiation[4..8].try_into().ok()?);
+   match negotiation[0] {
+      TYPE_RDP_NEG_RSP => Some(Negotiation::Selected(value)),
+      TYPE_RDP_NEG_FAILURE => Some(Negotiation::Failure(value)),
+      _ => None,
+   }
+}
+
+/// Send a TLS 1.2 ClientHello and get the CN of the server certificate, sent in clear before the key exchange.
+fn get_certificate_cn(stream: &mut TcpStream, fqdn: &String) -> Option<String> {
+   stream.write_all(&client_hello(fqdn)).ok()?;
+   let mut handshake: Vec<u8> = Vec::new();
+   loop {
+      if let Some(certificate) = find_certificate(&handshake) {
+         let (_, cert) = X509Certificate::from_der(&certificate).ok()?;
+         let cn = cert.subject().iter_common_name().next()?.as_str().ok()?.to_string();
+         return Some(cn)
+      }
+      if handshake.len() > MAX_HANDSHAKE_SIZE {
+         return None
+      }
+      let mut header = [0u8; 5];
+      stream.read_exact(&mut header).ok()?;
+      let mut record = vec![0u8; u16::from_be_bytes([header[3], header[4]]) as usize];
+      stream.read_exact(&mut record).ok()?;
+      match header[0] {
+         TLS_HANDSHAKE => handshake.extend(record),
+         TLS_ALERT => {
+            trace!("TLS alert {:?} from {}",record,fqdn);
+            return None
+         }
+         _ => return None,
+      }
+   }
+}
+
+/// TLS extension with its type and length.
+fn tls_extension(extension_type: u16, data: &[u8]) -> Vec<u8> {
+   let mut extension: Vec<u8> = Vec::new();
+   extension.extend(extension_type.to_be_bytes());
+   extension.extend((data.len() as u16).to_be_bytes());
+   extension.extend_from_slice(data);
+   extension
+}
+
+/// TLS 1.2 ClientHello record with SNI, groups and signature algorithms needed by Windows.
+fn client_hello(fqdn: &String) -> Vec<u8> {
+   let mut server_name: Vec<u8> = Vec::new();
+   server_name.extend(((fqdn.len() + 3) as u16).to_be_bytes());
+   server_name.push(0);                                      // host_name
+   server_name.extend((fqdn.len() as u16).to_be_bytes());
+   server_name.extend(fqdn.as_bytes());
+   let mut extensions: Vec<u8> = Vec::new();
+   extensions.extend(tls_extension(0x0000, &server_name));
+   extensions.extend(tls_extension(0x000A, &[0, 6, 0x00, 0x1D, 0x00, 0x17, 0x00, 0x18]));
+   extensions.extend(tls_extension(0x000B, &[1, 0]));
+   extensions.extend(tls_extension(0x000D, &[0, 12, 0x04, 0x01, 0x05, 0x01, 0x06, 0x01, 0x08, 0x04, 0x04, 0x03, 0x02, 0x01]));
+   extensions.extend(tls_extension(0xFF01, &[0]));
+
+   let mut hello: Vec<u8> = vec![0x03, 0x03];                // TLS 1.2
+   let now = chrono::Utc::now();
+   let seed = ((now.timestamp() as u64) << 32 | now.timestamp_subsec_nanos() as u64).to_be_bytes();
+   hello.extend((0..32).map(|i| seed[i % 8] ^ (i as u8)));   // Random
+   hello.push(0);                                            // SessionID
+   hello.extend(((CIPHER_SUITES.len() * 2) as u16).to_be_bytes());
+   for cipher in CIPHER_SUITES {
+      hello.extend(cipher.to_be_bytes());
+   }
+   hello.extend([1u8, 0]);                                   // Compression methods
+   hello.extend((extensions.len() as u16).to_be_bytes());
+   hello.extend(extensions);
+
+   let mut handshake: Vec<u8> = vec![0x01];                  // ClientHello
+   handshake.extend(&(hello.len() as u32).to_be_bytes()[1..]);
+   handshake.extend(hello);
+   let mut record: Vec<u8> = vec![TLS_HANDSHAKE, 0x03, 0x01];
+   record.extend((handshake.len() as u16).to_be_bytes());
+   record.extend(handshake);
+   record
+}
+
+/// Read one 24 bits length.
+fn read_u24(data: &[u8], offset: usize) -> Option<usize> {
+   let bytes = data.get(offset..offset + 3)?;
+   Some(((bytes[0] as usize) << 16) | ((bytes[1] as usize) << 8) | bytes[2] as usize)
+}
+
+/// Function to get the first certificate of the Certificate message in the TLS handshake messages received so far.
+pub fn find_certificate(handshake: &[u8]) -> Option<Vec<u8>> {
+   let mut offset = 0;
+   while offset + 4 <= handshake.len() {
+      let length = read_u24(handshake, offset + 1)?;
+      let message = handshake.get(offset + 4..offset + 4 + length)?;
+      match handshake[offset] {
+         HANDSHAKE_CERTIFICATE => {
+            // certificate_list length then the first ASN.1Cert
+            let cert_length = read_u24(message, 3)?;
+            return message.get(6..6 + cert_length).map(|cert| cert.to_vec())
+         }
+         HANDSHAKE_SERVER_HELLO_DONE => return None,
+         _ => {}
+      }
+      offset += 4 + length;
+   }
+   None
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_connection_confirm() {
+   // LI, CC CDT, DST-REF, SRC-REF, CLASS then RDP_NEG_RSP or RDP_NEG_FAILURE
+   let header = [14u8, 0xD0, 0, 0, 0x12, 0x34, 0];
+   let mut selected = header.to_vec();
+   selected.extend([0x02, 0x1F, 8, 0, 2, 0, 0, 0]);
+   assert_eq!(parse_connection_confirm(&selected), Some(Negotiation::Selected(PROTOCOL_HYBRID)));
+   let mut failure = header.to_vec();
+   failure.extend([0x03, 0, 8, 0, 5, 0, 0, 0]);
+   assert_eq!(parse_connection_confirm(&failure), Some(Negotiation::Failure(HYBRID_REQUIRED_BY_SERVER)));
+   assert_eq!(parse_connection_confirm(&[6u8, 0xD0, 0, 0, 0, 0, 0]), Some(Negotiation::Legacy));
+   assert_eq!(parse_connection_confirm(&[6u8, 0x80, 0, 0, 0, 0, 0]), None);
+   assert_eq!(connection_request(PROTOCOL_SSL).len(), 19);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_find_certificate() {
+   // ServerHello then Certificate with one certificate, split like the first TLS record
+   let mut handshake: Vec<u8> = vec![0x02, 0, 0, 2, 0x03, 0x03];
+   handshake.extend([HANDSHAKE_CERTIFICATE, 0, 0, 10, 0, 0, 7, 0, 0, 4, 0x30, 0x02, 0x05, 0x00]);
+   handshake.extend([HANDSHAKE_SERVER_HELLO_DONE, 0, 0, 0]);
+   assert_eq!(find_certificate(&handshake), Some(vec![0x30, 0x02, 0x05, 0x00]));
+   assert_eq!(find_certificate(&handshake[..12]), None);
+   assert_eq!(find_certificate(&[HANDSHAKE_SERVER_HELLO_DONE, 0, 0, 0]), None);
+   assert_eq!(client_hello(&"braavos.essos.local".to_string())[0], TLS_HANDSHAKE);
+}
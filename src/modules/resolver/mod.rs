@@ -2,7 +2,10 @@
 //!
 //! This module will resolve IP address from the ldap FQDN
 //! Resolver can be used with UDP or TCP DNS request with **--dns-tcp** args
-//! Resolver can be used with custome DNS name server with **-n 127.0.0.1** or **--name-server 127.0.0.1**
+//! Resolver can be used with custome DNS name server with **-n 127.0.0.1** or **--dns-server 127.0.0.1**
+//! and **--dns-timeout** for each query, else the system resolver is used.
+//! The same settings ([`resolv::DnsConfig`]) are used to find the domain controller without **--ldapip**
+//! and by the probing modules, so the collection works when the system resolver can't see the internal DNS (SOCKS pivots).
 //!
 //! <https://docs.rs/trust-dns-resolver/latest/trust_dns_resolver/index.html>
 //! <https://github.com/shadowsocks/shadowsocks-rust/blob/master/crates/shadowsocks-service/src/config.rs>
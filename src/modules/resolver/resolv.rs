@@ -8,26 +8,37 @@ use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::collections::HashMap;
 use std::time::Duration;
 
+use crate::args::Options;
+use crate::cancel::CancelToken;
+use crate::modules::scheduler;
+
 /// Function to resolve all IP address from the LDAP FQDN vector
 /// <https://docs.rs/trust-dns-resolver/latest/trust_dns_resolver/index.html>
 /// <https://github.com/shadowsocks/shadowsocks-rust/blob/master/crates/shadowsocks-service/src/config.rs>
 pub async fn resolving_all_fqdn(
-   dns_tcp: bool,
-   name_server: &String,
+   dns: &DnsConfig,
    fqdn_ip: &mut HashMap<String, String>,
-   vec_computer: &Vec<serde_json::value::Value>
+   vec_computer: &Vec<serde_json::value::Value>,
+   skip_dormant: bool,
+   cancel: &CancelToken,
 ) {
    info!("Resolving FQDN to IP address started...");
+   let mut resolved = 0;
    for value in fqdn_ip.to_owned()
    {
+      if cancel.is_cancelled() {
+         cancel.stopped("FQDN resolver", format!("{} computers resolved", resolved));
+         break
+      }
       for i in 0..vec_computer.len()
       {
-          if (vec_computer[i]["Properties"]["name"].as_str().unwrap().to_string() == value.0.to_owned().to_string()) && (vec_computer[i]["Properties"]["enabled"] == true) {
+          if (vec_computer[i]["Properties"]["name"].as_str().unwrap().to_string() == value.0.to_owned().to_string()) && scheduler::is_probe_target(&vec_computer[i], skip_dormant) {
             debug!("Trying to resolve FQDN: {}",value.0.to_string());
             // Resolve FQDN to IP address
-            let address = resolver(value.0.to_string(),dns_tcp,name_server).await;
+            let address = resolver(value.0.to_string(),dns).await;
             if !address.contains("Not found"){
                fqdn_ip.insert(value.0.to_owned().to_string(),address.to_owned().to_string());
+               resolved += 1;
                info!("IP address for {}: {}",&value.0.to_string().yellow().bold(),&address.yellow().bold());
             }
           }
@@ -37,26 +48,77 @@ pub async fn resolving_all_fqdn(
    info!("Resolving FQDN to IP address finished!");
 }
 
+/// DNS settings shared by DC discovery, host resolution and probing modules:
+/// name server of **--dns-server**, TCP with **--dns-tcp** and timeout of **--dns-timeout**.
+/// The system resolver is used without name server.
+#[derive(Clone, Debug)]
+pub struct DnsConfig {
+   pub name_server: String,
+   pub tcp: bool,
+   pub timeout: u64,
+}
+
+impl DnsConfig {
+   /// Function to get the DNS settings of RustHound args.
+   pub fn new(common_args: &Options) -> Self {
+      DnsConfig {
+         name_server: common_args.name_server.to_owned(),
+         tcp: common_args.dns_tcp,
+         timeout: common_args.dns_timeout,
+      }
+   }
+
+   /// Function to make the resolver of these settings.
+   fn make_resolver(&self) -> Option<TokioAsyncResolver> {
+      if self.name_server.contains("not set") {
+         return TokioAsyncResolver::tokio_from_system_conf().ok()
+      }
+      let (c,o) = make_resolver_conf(self);
+      TokioAsyncResolver::tokio(c,o).ok()
+   }
+
+   /// Function to find one domain controller of the domain with the _ldap._tcp.dc._msdcs SRV records.
+   /// Return the FQDN and the IPv4 address of the first domain controller resolved.
+   pub async fn discover_dc(&self, domain: &String) -> Option<(String, String)> {
+      let dns_resolver = self.make_resolver()?;
+      let lookup = dns_resolver.srv_lookup(format!("_ldap._tcp.dc._msdcs.{}.", domain.trim_end_matches('.'))).await;
+      let mut targets: Vec<String> = match lookup {
+         Ok(lookup) => lookup.iter().map(|srv| srv.target().to_utf8().trim_end_matches('.').to_string()).collect(),
+         Err(err) => {
+            debug!("No SRV record for the domain controllers of {}: {}",domain,err);
+            Vec::new()
+         }
+      };
+      // The domain name is resolved to the domain controllers too
+      targets.push(domain.to_owned());
+      for target in targets {
+         let address = resolver(target.to_owned(), self).await;
+         if !address.contains("Not found") {
+            return Some((target, address))
+         }
+      }
+      None
+   }
+}
+
 /// Asynchron function to resolve IP address from the ldap FQDN
 pub async fn resolver(
    fqdn: String,
-   dns_tcp: bool, 
-   name_server: &String,
+   dns: &DnsConfig,
 ) -> String
 {
-   // Get configuration and options for resolver
-   let (c,o) = make_resolver_conf(dns_tcp,name_server);
-
-   // Construct a new Resolver with default configuration options
-   let resolver = TokioAsyncResolver::tokio(c,o).unwrap();
+   // Construct a new Resolver with the configuration options
+   let resolver = match dns.make_resolver() {
+      Some(resolver) => resolver,
+      None => return "Not found".to_string(),
+   };
 
    // Resolver
    let result = resolver.lookup_ip(fqdn);
 
    match result.await{
       Ok(response) => {
-         let address = response.iter().next().expect("no addresses returned!");
-         if address.is_ipv4() {
+         if let Some(address) = response.iter().find(|address| address.is_ipv4()) {
             return address.to_string()
          }
       }
@@ -67,23 +129,23 @@ pub async fn resolver(
 
 /// Function to prepare resolver configuration
 pub fn make_resolver_conf(
-   dns_tcp: bool, 
-   name_server: &String,
+   dns: &DnsConfig,
 ) -> (ResolverConfig,ResolverOpts) {
    let mut c = ResolverConfig::new();
    let mut socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 53);
    let mut dns_protocol = Protocol::Udp;
-   if dns_tcp == true
+   if dns.tcp == true
    {
       dns_protocol = Protocol::Tcp;
    }
-   if !name_server.contains("127.0.0.1") {
-      let address = name_server.parse::<IpAddr>().unwrap_or(std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
+   if let Ok(address) = dns.name_server.parse::<IpAddr>() {
       socket.set_ip(address);
+   } else if let Ok(address) = dns.name_server.parse::<SocketAddr>() {
+      socket = address;
    }
 
    debug!("Protocol DNS: {:?}",&dns_protocol);
-   debug!("Name server DNS: {:?}",name_server.parse::<Ipv4Addr>());
+   debug!("Name server DNS: {:?}",&socket);
 
    c.add_name_server(NameServerConfig {
       socket_addr: socket,
@@ -94,6 +156,21 @@ pub fn make_resolver_conf(
    });
 
    let mut o = ResolverOpts::default();
-   o.timeout = Duration::new(0, 10);
+   o.timeout = Duration::from_secs(dns.timeout);
    return (c,o)
-}
\ No newline at end of file
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_make_resolver_conf() {
+   let dns = DnsConfig { name_server: "192.168.56.12".to_string(), tcp: true, timeout: 3 };
+   let (c,o) = make_resolver_conf(&dns);
+   assert_eq!(c.name_servers()[0].socket_addr, "192.168.56.12:53".parse::<SocketAddr>().unwrap());
+   assert_eq!(c.name_servers()[0].protocol, Protocol::Tcp);
+   assert_eq!(o.timeout, Duration::from_secs(3));
+   // Port of one DNS server reached through a SOCKS forward
+   let dns = DnsConfig { name_server: "127.0.0.1:5353".to_string(), tcp: false, timeout: 5 };
+   let (c,_) = make_resolver_conf(&dns);
+   assert_eq!(c.name_servers()[0].socket_addr.port(), 5353);
+   assert_eq!(c.name_servers()[0].protocol, Protocol::Udp);
+}
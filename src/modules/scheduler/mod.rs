@@ -0,0 +1,123 @@
+//! Network probe scheduler
+//!
+//! Shared by the modules probing every collected computer (SMB, ...).
+//! Targets are resolved once then probed by a pool of workers on the tokio blocking threads, every probe must set its own TCP timeouts.
+//! Workers stop taking targets once the collection is cancelled, see [`crate::cancel`].
+//!
+use log::{debug,trace};
+use indicatif::ProgressBar;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::banner::progress_bar;
+use crate::cancel::CancelToken;
+use crate::modules::resolver::resolv::{self, DnsConfig};
+
+/// Number of computers probed at the same time
+const MAX_WORKERS: usize = 20;
+
+/// Structure containing one computer to probe.
+#[derive(Clone)]
+pub struct ProbeTarget {
+   /// Index in vec_computers
+   pub index: usize,
+   pub fqdn: String,
+   pub ip: String,
+}
+
+/// Function to check if one computer must be probed: enabled and not dormant with --stale-skip-probes.
+pub fn is_probe_target(computer: &serde_json::value::Value, skip_dormant: bool) -> bool {
+   computer["Properties"]["enabled"] != false
+      && !(skip_dormant && computer["Properties"]["dormant"] == true)
+}
+
+/// Function to get all enabled computers with their IP address.
+/// Use the IP address from fqdn-resolver module or resolve it.
+pub async fn prepare_targets(
+   vec_computers: &Vec<serde_json::value::Value>,
+   fqdn_ip: &HashMap<String, String>,
+   dns: &DnsConfig,
+   skip_dormant: bool,
+) -> Vec<ProbeTarget> {
+   let mut targets: Vec<ProbeTarget> = Vec::new();
+   for (index, computer) in vec_computers.iter().enumerate()
+   {
+      if !is_probe_target(computer, skip_dormant) {
+         continue
+      }
+      let fqdn = computer["Properties"]["name"].as_str().unwrap_or("").to_string();
+      if fqdn.is_empty() {
+         continue
+      }
+      let mut ip = fqdn_ip.get(&fqdn).unwrap_or(&"".to_string()).to_owned();
+      if ip.is_empty() {
+         ip = resolv::resolver(fqdn.to_owned(), dns).await;
+      }
+      if ip.contains("Not found") {
+         debug!("Can't resolve {} to probe it",&fqdn);
+         continue
+      }
+      targets.push(ProbeTarget { index, fqdn, ip });
+   }
+   targets
+}
+
+/// Function to run a blocking probe on all targets with a pool of workers.
+/// Workers run with `tokio::task::spawn_blocking` so the probes never block the async runtime.
+/// Return the vec_computers index with the probe result.
+pub async fn run_probes<R, F>(targets: &Vec<ProbeTarget>, message: &str, cancel: &CancelToken, probe: F) -> Vec<(usize, R)>
+where
+   R: Send + 'static,
+   F: Fn(&ProbeTarget) -> Option<R> + Send + Sync + 'static,
+{
+   let targets = Arc::new(targets.to_owned());
+   let probe = Arc::new(probe);
+   let next = Arc::new(AtomicUsize::new(0));
+   let done = Arc::new(AtomicUsize::new(0));
+   let results: Arc<Mutex<Vec<(usize, R)>>> = Arc::new(Mutex::new(Vec::new()));
+   let pb = ProgressBar::new(1);
+
+   let mut workers = Vec::new();
+   for _ in 0..MAX_WORKERS.min(targets.len()) {
+      let targets = targets.clone();
+      let probe = probe.clone();
+      let next = next.clone();
+      let done = done.clone();
+      let results = results.clone();
+      let cancel = cancel.clone();
+      let pb = pb.clone();
+      let message = message.to_string();
+      workers.push(tokio::task::spawn_blocking(move || {
+         loop {
+            if cancel.is_cancelled() {
+               break
+            }
+            let i = next.fetch_add(1, Ordering::SeqCst);
+            if i >= targets.len() {
+               break
+            }
+            trace!("Probing {} ({})",&targets[i].fqdn,&targets[i].ip);
+            if let Some(result) = probe(&targets[i]) {
+               results.lock().unwrap().push((targets[i].index, result));
+            }
+            done.fetch_add(1, Ordering::SeqCst);
+            let pourcentage = 100 * (i + 1) / targets.len();
+            progress_bar(pb.to_owned(),message.to_owned(),pourcentage as u64,"%".to_string());
+         }
+      }));
+   }
+   for worker in workers {
+      if let Err(err) = worker.await {
+         debug!("Probe worker failed: {}",err);
+      }
+   }
+   pb.finish_and_clear();
+   let done = done.load(Ordering::SeqCst);
+   if done < targets.len() {
+      cancel.stopped(message, format!("{} of {} computers not probed", targets.len() - done, targets.len()));
+   }
+
+   let mut results = results.lock().unwrap();
+   std::mem::take(&mut *results)
+}
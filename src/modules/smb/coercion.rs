@@ -0,0 +1,86 @@
+use log::{info,debug};
+use colored::Colorize;
+use std::collections::HashMap;
+
+use crate::cancel::CancelToken;
+use crate::json::checker::bh_41::is_domain_controller;
+use crate::modules::resolver::resolv::DnsConfig;
+use crate::modules::scheduler;
+use crate::modules::smb::ntlm::NtlmCredentials;
+use crate::modules::smb::transport::{SmbConnection, SMB2_DIALECTS, STATUS_OBJECT_NAME_NOT_FOUND};
+
+/// MS-RPRN print spooler named pipe
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-rprn/d42db7d5-f141-4466-8f47-0a4be14e2fc1>
+const SPOOLER_PIPE: &str = "spoolss";
+/// Named pipe only present when WebClient service is running
+const WEBCLIENT_PIPE: &str = "DAV RPC SERVICE";
+
+/// Function to probe spooler and WebClient on all enabled computers
+pub async fn probe_all_computers(
+   vec_computers: &mut Vec<serde_json::value::Value>,
+   fqdn_ip: &HashMap<String, String>,
+   dns: &DnsConfig,
+   credentials: &NtlmCredentials,
+   skip_dormant: bool,
+   cancel: &CancelToken,
+) {
+   info!("Probing spooler and WebClient services started...");
+   let targets = scheduler::prepare_targets(vec_computers, fqdn_ip, dns, skip_dormant).await;
+   let credentials = credentials.to_owned();
+   let results = scheduler::run_probes(&targets, "Probing spooler and WebClient", cancel, move |target| {
+      probe_pipes(&target.ip, &target.fqdn, &credentials)
+   }).await;
+
+   for (index, (spooler, webclient)) in results {
+      let name = vec_computers[index]["Properties"]["name"].as_str().unwrap_or("").to_string();
+      if spooler == Some(true) {
+         info!("Spooler service available on {}",&name.yellow().bold());
+      }
+      if webclient == Some(true) {
+         info!("WebClient service running on {}",&name.yellow().bold());
+      }
+      vec_computers[index]["Properties"]["spooleravailable"] = spooler.into();
+      vec_computers[index]["Properties"]["webclientavailable"] = webclient.into();
+   }
+   info!("Probing spooler and WebClient services finished!");
+}
+
+/// Function to flag users and computers with unconstrained delegation which can get a domain controller TGT.
+/// Flag is only set if the print spooler or WebClient service was found on a domain controller by the probes.
+/// <https://adsecurity.org/?p=4056>
+pub fn add_coerce_to_tgt_flag(vec_users: &mut Vec<serde_json::value::Value>, vec_computers: &mut Vec<serde_json::value::Value>)
+{
+   let coercible_dc = vec_computers.iter().any(|computer| {
+      is_domain_controller(computer)
+      && (computer["Properties"]["spooleravailable"].as_bool().unwrap_or(false)
+      || computer["Properties"]["webclientavailable"].as_bool().unwrap_or(false))
+   });
+   if !coercible_dc {
+      return
+   }
+   for user in vec_users.iter_mut() {
+      if user["Properties"]["unconstraineddelegation"].as_bool().unwrap_or(false) {
+         user["Properties"]["coercetotgt"] = true.into();
+      }
+   }
+   for computer in vec_computers.iter_mut() {
+      if computer["Properties"]["unconstraineddelegation"].as_bool().unwrap_or(false) && !is_domain_controller(computer) {
+         computer["Properties"]["coercetotgt"] = true.into();
+      }
+   }
+}
+
+/// Open IPC$ and check if the named pipes exist.
+/// Any other status than STATUS_OBJECT_NAME_NOT_FOUND (like STATUS_ACCESS_DENIED) means the pipe exists.
+fn probe_pipes(ip: &String, fqdn: &String, credentials: &NtlmCredentials) -> Option<(Option<bool>, Option<bool>)> {
+   let mut connection = SmbConnection::connect(ip)?;
+   connection.negotiate(&SMB2_DIALECTS)?;
+   if connection.session_setup(credentials).is_none() {
+      debug!("SMB authentication failed on {}",fqdn);
+      return None
+   }
+   connection.tree_connect(fqdn, "IPC$")?;
+   let spooler = connection.open(SPOOLER_PIPE).map(|status| status != STATUS_OBJECT_NAME_NOT_FOUND);
+   let webclient = connection.open(WEBCLIENT_PIPE).map(|status| status != STATUS_OBJECT_NAME_NOT_FOUND);
+   Some((spooler, webclient))
+}
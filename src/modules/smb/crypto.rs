@@ -0,0 +1,60 @@
+//! Hash functions needed for NTLMv2 authentication and SMB2 signing (MD4, MD5, SHA256 and HMAC),
+//! from the RustCrypto crates.
+use hmac::{Hmac, Mac};
+use md4::{Digest, Md4};
+use md5::Md5;
+use sha2::Sha256;
+
+/// Copy one digest output in a fixed size array.
+fn to_array<const N: usize>(digest: &[u8]) -> [u8; N] {
+   let mut output = [0u8; N];
+   output.copy_from_slice(digest);
+   output
+}
+
+/// MD4 of the message, used for the NT hash.
+pub fn md4(message: &[u8]) -> [u8; 16] {
+   to_array(&Md4::digest(message))
+}
+
+/// MD5 of the message.
+pub fn md5(message: &[u8]) -> [u8; 16] {
+   to_array(&Md5::digest(message))
+}
+
+/// SHA256 of the message.
+pub fn sha256(message: &[u8]) -> [u8; 32] {
+   to_array(&Sha256::digest(message))
+}
+
+/// HMAC-MD5, used for NTLMv2 responses and session keys.
+pub fn hmac_md5(key: &[u8], message: &[u8]) -> [u8; 16] {
+   // HMAC accepts keys of any size
+   let mut mac = Hmac::<Md5>::new_from_slice(key).expect("HMAC key of any size");
+   mac.update(message);
+   to_array(&mac.finalize().into_bytes())
+}
+
+/// HMAC-SHA256, used for SMB2 signing.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+   let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC key of any size");
+   mac.update(message);
+   to_array(&mac.finalize().into_bytes())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_hash_functions() {
+   let hex = |v: &[u8]| v.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+   // RFC 1320 test suite
+   assert_eq!(hex(&md4(b"")), "31d6cfe0d16ae931b73c59d7e0c089c0");
+   assert_eq!(hex(&md4(b"abc")), "a448017aaf21d8525fc10ae87aa6729d");
+   // RFC 1321 test suite
+   assert_eq!(hex(&md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+   assert_eq!(hex(&md5(b"message digest")), "f96b697d7cb7938d525a2f31aaf161d0");
+   // FIPS 180-2
+   assert_eq!(hex(&sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+   // RFC 2202 and RFC 4231
+   assert_eq!(hex(&hmac_md5(b"Jefe", b"what do ya want for nothing?")), "750c783e6ab0b503eaa86e310a5db738");
+   assert_eq!(hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+}
@@ -0,0 +1,18 @@
+//! SMB probes on collected computers
+//!
+//! This module contains a minimal SMB2 transport (NTLM authentication and signing) shared by the SMB probes.
+//! Kerberos authentication isn't supported for SMB, a null session is used if no password is set.
+//!
+//! - **coercion**: check MS-RPRN spooler and WebClient named pipes, used with **--probe-coercion** args
+//! - **survey**: negotiate SMB to get signing, SMBv1 and dialect without authentication, used with **--probe-smb** args
+//! - **shares**: list shares of servers with MS-SRVS (**srvsvc**) and their share permissions, used with **--shares** args
+//!
+//! <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-smb2/5606ad47-5ee0-437a-817e-70c366052962>
+//!
+pub mod crypto;
+pub mod ntlm;
+pub mod transport;
+pub mod coercion;
+pub mod survey;
+pub mod srvsvc;
+pub mod shares;
@@ -0,0 +1,284 @@
+//! NTLMSSP messages wrapped in SPNEGO tokens for SMB2 SESSION_SETUP.
+//! <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-nlmp/b38c36ed-2804-4868-a9ff-8dd3182128e4>
+//! <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-spng/f377a379-c24f-4a0f-a3eb-0d835389e28a>
+use std::convert::TryInto;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::modules::smb::crypto::{md4, hmac_md5};
+
+/// NTLMSSP signature
+const NTLMSSP_SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+/// NTLMSSP OID 1.3.6.1.4.1.311.2.2.10
+const NTLMSSP_OID: [u8; 12] = [0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a];
+/// SPNEGO OID 1.3.6.1.5.5.2
+const SPNEGO_OID: [u8; 8] = [0x06, 0x06, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x02];
+/// NEGOTIATE_UNICODE | REQUEST_TARGET | NEGOTIATE_NTLM | ALWAYS_SIGN | EXTENDED_SESSIONSECURITY | TARGET_INFO | 128 | 56
+const NTLM_FLAGS: u32 = 0xA088_8205;
+/// NTLMSSP_NEGOTIATE_VERSION, the server adds its OS version to the CHALLENGE message
+const NTLMSSP_NEGOTIATE_VERSION: u32 = 0x0200_0000;
+
+/// Structure containing the credentials used for NTLM authentication.
+#[derive(Clone, Debug)]
+pub struct NtlmCredentials {
+   pub username: String,
+   pub domain: String,
+   pub password: String,
+}
+
+impl NtlmCredentials {
+   /// Build credentials from RustHound args, username can be `user` or `user@domain.local`.
+   /// Empty username means anonymous authentication.
+   pub fn new(username: &String, password: &String, domain: &String) -> Self {
+      if username.contains("not set") || password.contains("not set") {
+         return NtlmCredentials { username: String::new(), domain: String::new(), password: String::new() }
+      }
+      let (user, user_domain) = match username.split_once('@') {
+         Some((user, user_domain)) => (user.to_string(), user_domain.to_string()),
+         None => (username.to_owned(), domain.to_owned()),
+      };
+      NtlmCredentials { username: user, domain: user_domain.to_uppercase(), password: password.to_owned() }
+   }
+
+   /// Anonymous authentication (null session)?
+   pub fn is_anonymous(&self) -> bool {
+      self.username.is_empty()
+   }
+}
+
+/// Encode string to UTF-16LE bytes.
+pub fn utf16le(value: &str) -> Vec<u8> {
+   value.encode_utf16().flat_map(|c| c.to_le_bytes()).collect()
+}
+
+/// Encode DER length.
+fn der_length(length: usize) -> Vec<u8> {
+   if length < 0x80 {
+      vec![length as u8]
+   } else if length < 0x100 {
+      vec![0x81, length as u8]
+   } else {
+      vec![0x82, (length >> 8) as u8, length as u8]
+   }
+}
+
+/// Encode DER element with tag.
+pub fn der(tag: u8, value: &[u8]) -> Vec<u8> {
+   let mut element = vec![tag];
+   element.extend(der_length(value.len()));
+   element.extend_from_slice(value);
+   element
+}
+
+/// Build NTLMSSP NEGOTIATE message wrapped in SPNEGO NegTokenInit.
+pub fn negotiate_token() -> Vec<u8> {
+   spnego_negotiate_token(NTLM_FLAGS)
+}
+
+/// Build NTLMSSP NEGOTIATE message asking the server version, only to read the CHALLENGE message.
+pub fn version_negotiate_token() -> Vec<u8> {
+   spnego_negotiate_token(NTLM_FLAGS | NTLMSSP_NEGOTIATE_VERSION)
+}
+
+/// Build NTLMSSP NEGOTIATE message with these flags wrapped in SPNEGO NegTokenInit.
+fn spnego_negotiate_token(flags: u32) -> Vec<u8> {
+   let mut negotiate = NTLMSSP_SIGNATURE.to_vec();
+   negotiate.extend(1u32.to_le_bytes());
+   negotiate.extend(flags.to_le_bytes());
+   // DomainNameFields and WorkstationFields empty
+   negotiate.extend([0u8; 16]);
+
+   let mech_types = der(0xa0, &der(0x30, &NTLMSSP_OID));
+   let mech_token = der(0xa2, &der(0x04, &negotiate));
+   let mut neg_token_init = mech_types;
+   neg_token_init.extend(mech_token);
+   let neg_token_init = der(0xa0, &der(0x30, &neg_token_init));
+
+   let mut token = SPNEGO_OID.to_vec();
+   token.extend(neg_token_init);
+   der(0x60, &token)
+}
+
+/// Structure containing the values needed from NTLMSSP CHALLENGE message.
+pub struct NtlmChallenge {
+   pub server_challenge: [u8; 8],
+   pub target_info: Vec<u8>,
+   /// Server OS version like `10.0.17763`, if NTLMSSP_NEGOTIATE_VERSION was negotiated
+   pub version: Option<String>,
+}
+
+/// Find NTLMSSP CHALLENGE message in SPNEGO NegTokenResp and parse it.
+pub fn parse_challenge(token: &[u8]) -> Option<NtlmChallenge> {
+   let start = token.windows(8).position(|w| w == NTLMSSP_SIGNATURE)?;
+   let message = &token[start..];
+   if message.len() < 48 || u32::from_le_bytes(message[8..12].try_into().ok()?) != 2 {
+      return None
+   }
+   let server_challenge: [u8; 8] = message[24..32].try_into().ok()?;
+   let info_len = u16::from_le_bytes(message[40..42].try_into().ok()?) as usize;
+   let info_offset = u32::from_le_bytes(message[44..48].try_into().ok()?) as usize;
+   let target_info = message.get(info_offset..info_offset + info_len)?.to_vec();
+   let flags = u32::from_le_bytes(message[20..24].try_into().ok()?);
+   let version = match message.get(48..52) {
+      Some(version) if flags & NTLMSSP_NEGOTIATE_VERSION != 0 && info_offset >= 56 => {
+         Some(format!("{}.{}.{}", version[0], version[1], u16::from_le_bytes([version[2], version[3]])))
+      },
+      _ => None,
+   };
+   Some(NtlmChallenge { server_challenge, target_info, version })
+}
+
+/// Names disclosed by the server in the CHALLENGE message, from the AV_PAIRs of TargetInfo.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-nlmp/83f5e789-660d-4781-8491-5f8c6641f75e>
+#[derive(Clone, Debug, Default)]
+pub struct NtlmTargetInfo {
+   pub netbios_computer: String,
+   pub netbios_domain: String,
+   pub dns_computer: String,
+   pub dns_domain: String,
+   pub dns_forest: String,
+}
+
+/// Parse the AV_PAIRs of TargetInfo until MsvAvEOL.
+pub fn parse_target_info(target_info: &[u8]) -> NtlmTargetInfo {
+   let mut info = NtlmTargetInfo::default();
+   let mut offset = 0;
+   while offset + 4 <= target_info.len() {
+      let av_id = u16::from_le_bytes([target_info[offset], target_info[offset + 1]]);
+      let av_len = u16::from_le_bytes([target_info[offset + 2], target_info[offset + 3]]) as usize;
+      let value = match target_info.get(offset + 4..offset + 4 + av_len) {
+         Some(value) => value,
+         None => break,
+      };
+      let text = || {
+         let units: Vec<u16> = value.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+         String::from_utf16_lossy(&units)
+      };
+      match av_id {
+         0 => break,
+         1 => info.netbios_computer = text(),
+         2 => info.netbios_domain = text(),
+         3 => info.dns_computer = text(),
+         4 => info.dns_domain = text(),
+         5 => info.dns_forest = text(),
+         _ => {},
+      }
+      offset += 4 + av_len;
+   }
+   info
+}
+
+/// Get the current time as Windows FILETIME.
+pub fn filetime_now() -> u64 {
+   let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+   (since_epoch.as_secs() + 11644473600) * 10_000_000 + since_epoch.subsec_nanos() as u64 / 100
+}
+
+/// Random client challenge of the NTLMv2 response.
+fn client_challenge() -> [u8; 8] {
+   rand::random::<[u8; 8]>()
+}
+
+/// Build NTLMSSP AUTHENTICATE message wrapped in SPNEGO NegTokenResp.
+/// Return the token and the session key used for SMB2 signing.
+/// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-nlmp/5e550938-91d4-459f-b67d-75d70009e3f3>
+pub fn authenticate_token(credentials: &NtlmCredentials, challenge: &NtlmChallenge) -> (Vec<u8>, Vec<u8>) {
+   let mut lm_response: Vec<u8> = Vec::new();
+   let mut nt_response: Vec<u8> = Vec::new();
+   let mut session_key: Vec<u8> = Vec::new();
+
+   if !credentials.is_anonymous() {
+      // NTOWFv2
+      let nt_hash = md4(&utf16le(&credentials.password));
+      let identity = format!("{}{}", credentials.username.to_uppercase(), credentials.domain);
+      let ntowf = hmac_md5(&nt_hash, &utf16le(&identity));
+
+      // NTLMv2 client challenge blob
+      let mut blob: Vec<u8> = vec![0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+      blob.extend(filetime_now().to_le_bytes());
+      blob.extend(client_challenge());
+      blob.extend([0u8; 4]);
+      blob.extend_from_slice(&challenge.target_info);
+      blob.extend([0u8; 4]);
+
+      let mut proof_input = challenge.server_challenge.to_vec();
+      proof_input.extend_from_slice(&blob);
+      let nt_proof = hmac_md5(&ntowf, &proof_input);
+
+      nt_response.extend_from_slice(&nt_proof);
+      nt_response.extend(blob);
+      lm_response = vec![0u8; 24];
+      session_key = hmac_md5(&ntowf, &nt_proof).to_vec();
+   }
+
+   let domain = utf16le(&credentials.domain);
+   let user = utf16le(&credentials.username);
+   let payload_fields: [&[u8]; 6] = [&lm_response, &nt_response, &domain, &user, &[], &[]];
+
+   let mut authenticate = NTLMSSP_SIGNATURE.to_vec();
+   authenticate.extend(3u32.to_le_bytes());
+   let mut payload: Vec<u8> = Vec::new();
+   // Header size without Version and MIC
+   let mut offset: u32 = 64;
+   for field in payload_fields.iter() {
+      authenticate.extend((field.len() as u16).to_le_bytes());
+      authenticate.extend((field.len() as u16).to_le_bytes());
+      authenticate.extend(offset.to_le_bytes());
+      offset += field.len() as u32;
+      payload.extend_from_slice(field);
+   }
+   let mut flags = NTLM_FLAGS;
+   if credentials.is_anonymous() {
+      // NTLMSSP_NEGOTIATE_ANONYMOUS
+      flags |= 0x0000_0800;
+   }
+   authenticate.extend(flags.to_le_bytes());
+   authenticate.extend(payload);
+
+   let token = der(0xa1, &der(0x30, &der(0xa2, &der(0x04, &authenticate))));
+   (token, session_key)
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_ntlm_credentials() {
+   let credentials = NtlmCredentials::new(&"jdoe@essos.local".to_string(), &"Password1".to_string(), &"sevenkingdoms.local".to_string());
+   assert_eq!(credentials.username, "jdoe");
+   assert_eq!(credentials.domain, "ESSOS.LOCAL");
+   assert!(NtlmCredentials::new(&"not set".to_string(), &"not set".to_string(), &"essos.local".to_string()).is_anonymous());
+   // NTLMSSP NEGOTIATE must be found back in the SPNEGO token
+   assert!(negotiate_token().windows(8).any(|w| w == NTLMSSP_SIGNATURE));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_challenge_target_info() {
+   let mut target_info: Vec<u8> = Vec::new();
+   for (av_id, value) in [(2u16, "ESSOS"), (1, "MEEREEN"), (4, "essos.local"), (3, "meereen.essos.local"), (5, "essos.local")] {
+      target_info.extend(av_id.to_le_bytes());
+      target_info.extend((utf16le(value).len() as u16).to_le_bytes());
+      target_info.extend(utf16le(value));
+   }
+   target_info.extend([0u8; 4]);
+
+   // CHALLENGE message with Version 10.0.17763 before TargetInfo
+   let mut message = NTLMSSP_SIGNATURE.to_vec();
+   message.extend(2u32.to_le_bytes());
+   message.extend([0u8; 8]);
+   message.extend((NTLM_FLAGS | NTLMSSP_NEGOTIATE_VERSION).to_le_bytes());
+   message.extend([0x11u8; 8]);
+   message.extend([0u8; 8]);
+   message.extend((target_info.len() as u16).to_le_bytes());
+   message.extend((target_info.len() as u16).to_le_bytes());
+   message.extend(56u32.to_le_bytes());
+   message.extend([10u8, 0, 0x63, 0x45, 0, 0, 0, 15]);
+   message.extend(&target_info);
+
+   let challenge = parse_challenge(&message).unwrap();
+   assert_eq!(challenge.server_challenge, [0x11u8; 8]);
+   assert_eq!(challenge.version, Some("10.0.17763".to_string()));
+   let info = parse_target_info(&challenge.target_info);
+   assert_eq!(info.netbios_computer, "MEEREEN");
+   assert_eq!(info.netbios_domain, "ESSOS");
+   assert_eq!(info.dns_computer, "meereen.essos.local");
+   assert_eq!(info.dns_forest, "essos.local");
+}
@@ -0,0 +1,104 @@
+use log::{info,debug};
+use colored::Colorize;
+use std::collections::HashMap;
+
+use crate::json::templates::bh_41::prepare_share_json_template;
+use crate::cancel::CancelToken;
+use crate::modules::resolver::resolv::DnsConfig;
+use crate::modules::scheduler;
+use crate::modules::smb::ntlm::NtlmCredentials;
+use crate::modules::smb::srvsvc::{self, ShareInfo};
+use crate::modules::smb::transport::{SmbConnection, SMB2_DIALECTS};
+
+/// MaximalAccess rights to read files: FILE_READ_DATA, GENERIC_ALL and GENERIC_READ
+const READ_ACCESS: u32 = 0x0000_0001 | 0x1000_0000 | 0x8000_0000;
+/// MaximalAccess rights to write files: FILE_WRITE_DATA, FILE_APPEND_DATA, GENERIC_ALL and GENERIC_WRITE
+const WRITE_ACCESS: u32 = 0x0000_0002 | 0x0000_0004 | 0x1000_0000 | 0x4000_0000;
+
+/// Structure containing one share with the share permissions granted to the collector account.
+pub struct ShareAccess {
+   pub share: ShareInfo,
+   /// None if TREE_CONNECT is denied
+   pub maximal_access: Option<u32>,
+}
+
+/// Function to list the shares of all enabled servers and add them in shares.json
+pub async fn enumerate_all_shares(
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_shares: &mut Vec<serde_json::value::Value>,
+   fqdn_ip: &HashMap<String, String>,
+   dns: &DnsConfig,
+   credentials: &NtlmCredentials,
+   skip_dormant: bool,
+   cancel: &CancelToken,
+) {
+   info!("Shares enumeration started...");
+   let mut targets = scheduler::prepare_targets(vec_computers, fqdn_ip, dns, skip_dormant).await;
+   targets.retain(|target| is_server(&vec_computers[target.index]));
+   let credentials = credentials.to_owned();
+   let results = scheduler::run_probes(&targets, "Listing shares", cancel, move |target| {
+      list_shares(&target.ip, &target.fqdn, &credentials)
+   }).await;
+
+   let mut writable = 0;
+   for (index, shares) in results {
+      let name = vec_computers[index]["Properties"]["name"].as_str().unwrap_or("").to_string();
+      for access in shares {
+         let share_json = make_share_json(&vec_computers[index], &access);
+         if share_json["Writable"] == true && !access.share.name.ends_with('$') {
+            writable += 1;
+            debug!("Writable share {}",&share_json["Name"].as_str().unwrap_or("").yellow().bold());
+         }
+         vec_shares.push(share_json);
+      }
+      debug!("Shares of {} listed",&name);
+   }
+   info!("{} writable shares found",writable.to_string().yellow().bold());
+   info!("Shares enumeration finished!");
+}
+
+/// Function to check if one computer is a server from its operating system.
+fn is_server(computer: &serde_json::value::Value) -> bool {
+   computer["Properties"]["operatingsystem"].as_str().unwrap_or("").to_lowercase().contains("server")
+}
+
+/// Open IPC$ to list the shares with NetrShareEnum then connect to every disk share to get its MaximalAccess.
+fn list_shares(ip: &String, fqdn: &String, credentials: &NtlmCredentials) -> Option<Vec<ShareAccess>> {
+   let mut connection = SmbConnection::connect(ip)?;
+   connection.negotiate(&SMB2_DIALECTS)?;
+   if connection.session_setup(credentials).is_none() {
+      debug!("SMB authentication failed on {}",fqdn);
+      return None
+   }
+   connection.tree_connect(fqdn, "IPC$")?;
+   let shares = srvsvc::share_enum(&mut connection, fqdn)?;
+
+   let mut result: Vec<ShareAccess> = Vec::new();
+   for share in shares {
+      let maximal_access = match srvsvc::share_type_name(share.share_type).as_str() {
+         "Disk" => connection.tree_connect(fqdn, &share.name).map(|_| connection.maximal_access),
+         _ => None,
+      };
+      result.push(ShareAccess { share, maximal_access });
+   }
+   Some(result)
+}
+
+/// Function to make the shares.json entry of one share.
+pub fn make_share_json(computer: &serde_json::value::Value, access: &ShareAccess) -> serde_json::value::Value {
+   let host = computer["Properties"]["name"].as_str().unwrap_or("").to_lowercase();
+   let mut share_json = prepare_share_json_template();
+   share_json["Name"] = format!("\\\\{}\\{}",host,access.share.name).into();
+   share_json["Host"] = host.into();
+   share_json["ObjectIdentifier"] = computer["ObjectIdentifier"].to_owned();
+   share_json["ShareType"] = srvsvc::share_type_name(access.share.share_type).into();
+   if !access.share.remark.is_empty() {
+      share_json["Remark"] = access.share.remark.to_owned().into();
+   }
+   if srvsvc::share_type_name(access.share.share_type) == "Disk" {
+      share_json["Readable"] = access.maximal_access.map(|mask| mask & READ_ACCESS != 0).unwrap_or(false).into();
+      share_json["Writable"] = access.maximal_access.map(|mask| mask & WRITE_ACCESS != 0).unwrap_or(false).into();
+      share_json["MaximalAccess"] = access.maximal_access.map(|mask| format!("{:#010x}",mask)).into();
+   }
+   share_json
+}
@@ -0,0 +1,230 @@
+//! MS-SRVS NetrShareEnum over the `srvsvc` named pipe with a minimal DCE/RPC client (no authentication level, NDR only).
+//! <https://pubs.opengroup.org/onlinepubs/9629399/chap12.htm>
+use log::trace;
+use std::convert::TryInto;
+
+use crate::modules::smb::ntlm::utf16le;
+use crate::modules::smb::transport::{SmbConnection, STATUS_BUFFER_OVERFLOW};
+
+/// Server service named pipe
+const SRVSVC_PIPE: &str = "srvsvc";
+/// SRVSVC interface 4b324fc8-1670-01d3-1278-5a47bf6ee188 version 3.0
+const SRVSVC_UUID: [u8; 16] = [0xc8, 0x4f, 0x32, 0x4b, 0x70, 0x16, 0xd3, 0x01, 0x12, 0x78, 0x5a, 0x47, 0xbf, 0x6e, 0xe1, 0x88];
+/// NDR transfer syntax 8a885d04-1ceb-11c9-9fe8-08002b104860 version 2
+const NDR_UUID: [u8; 16] = [0x04, 0x5d, 0x88, 0x8a, 0xeb, 0x1c, 0xc9, 0x11, 0x9f, 0xe8, 0x08, 0x00, 0x2b, 0x10, 0x48, 0x60];
+/// DCE/RPC packet types
+const PTYPE_REQUEST: u8 = 0;
+const PTYPE_RESPONSE: u8 = 2;
+const PTYPE_BIND: u8 = 11;
+const PTYPE_BIND_ACK: u8 = 12;
+/// PFC_FIRST_FRAG | PFC_LAST_FRAG
+const PFC_FIRST_LAST: u8 = 0x03;
+const PFC_LAST_FRAG: u8 = 0x02;
+/// NetrShareEnum operation number
+const OPNUM_NETR_SHARE_ENUM: u16 = 15;
+/// Maximum fragment size
+const MAX_FRAG: u16 = 4280;
+
+/// Structure containing one share returned by NetrShareEnum (SHARE_INFO_1).
+#[derive(Debug, PartialEq)]
+pub struct ShareInfo {
+   pub name: String,
+   pub share_type: u32,
+   pub remark: String,
+}
+
+/// Function to list the shares of the connected server, the session must be connected to IPC$.
+pub fn share_enum(connection: &mut SmbConnection, host: &str) -> Option<Vec<ShareInfo>> {
+   let file_id = connection.open_pipe(SRVSVC_PIPE)?;
+   let shares = bind(connection, &file_id)
+      .and_then(|_| call(connection, &file_id, OPNUM_NETR_SHARE_ENUM, &share_enum_request(host)))
+      .and_then(|stub| parse_share_enum(&stub));
+   connection.close(&file_id);
+   shares
+}
+
+/// DCE/RPC common header of 16 bytes.
+fn rpc_header(ptype: u8, flags: u8, frag_length: usize, call_id: u32) -> Vec<u8> {
+   let mut header: Vec<u8> = vec![5, 0, ptype, flags];
+   header.extend([0x10u8, 0, 0, 0]);                         // Data representation (little-endian, ASCII, IEEE)
+   header.extend((frag_length as u16).to_le_bytes());
+   header.extend(0u16.to_le_bytes());                        // AuthLength
+   header.extend(call_id.to_le_bytes());
+   header
+}
+
+/// Bind to the SRVSVC interface with the NDR transfer syntax.
+fn bind(connection: &mut SmbConnection, file_id: &[u8]) -> Option<()> {
+   let mut body: Vec<u8> = Vec::new();
+   body.extend(MAX_FRAG.to_le_bytes());                      // MaxXmitFrag
+   body.extend(MAX_FRAG.to_le_bytes());                      // MaxRecvFrag
+   body.extend(0u32.to_le_bytes());                          // AssocGroup
+   body.extend([1u8, 0, 0, 0]);                              // NumContextItems and padding
+   body.extend(0u16.to_le_bytes());                          // ContextId
+   body.extend([1u8, 0]);                                    // NumTransItems and padding
+   body.extend(SRVSVC_UUID);
+   body.extend(3u16.to_le_bytes());                          // Major version
+   body.extend(0u16.to_le_bytes());                          // Minor version
+   body.extend(NDR_UUID);
+   body.extend(2u32.to_le_bytes());                          // Version
+   let mut packet = rpc_header(PTYPE_BIND, PFC_FIRST_LAST, 16 + body.len(), 1);
+   packet.extend(body);
+
+   let (_, response) = connection.transceive(file_id, &packet)?;
+   if response.get(2) != Some(&PTYPE_BIND_ACK) {
+      trace!("DCE/RPC bind to srvsvc rejected");
+      return None
+   }
+   Some(())
+}
+
+/// Send one request and return the stub data of the response, fragments are joined.
+fn call(connection: &mut SmbConnection, file_id: &[u8], opnum: u16, stub: &[u8]) -> Option<Vec<u8>> {
+   let mut packet = rpc_header(PTYPE_REQUEST, PFC_FIRST_LAST, 24 + stub.len(), 2);
+   packet.extend((stub.len() as u32).to_le_bytes());         // AllocHint
+   packet.extend(0u16.to_le_bytes());                        // ContextId
+   packet.extend(opnum.to_le_bytes());
+   packet.extend_from_slice(stub);
+
+   let (status, mut data) = connection.transceive(file_id, &packet)?;
+   if status == STATUS_BUFFER_OVERFLOW {
+      data.extend(connection.read_pipe(file_id)?);
+   }
+   let mut result: Vec<u8> = Vec::new();
+   loop {
+      // Complete fragment needed before reading the next one
+      while data.len() < 16 || data.len() < u16::from_le_bytes(data[8..10].try_into().ok()?) as usize {
+         data.extend(connection.read_pipe(file_id)?);
+      }
+      let frag_length = u16::from_le_bytes(data[8..10].try_into().ok()?) as usize;
+      if data[2] != PTYPE_RESPONSE || frag_length < 24 {
+         trace!("DCE/RPC fault or unexpected packet type {}",data[2]);
+         return None
+      }
+      result.extend_from_slice(&data[24..frag_length]);
+      let last = data[3] & PFC_LAST_FRAG != 0;
+      data.drain(..frag_length);
+      if last {
+         return Some(result)
+      }
+   }
+}
+
+/// NDR conformant and varying string, null terminated and aligned on 4 bytes.
+fn ndr_string(value: &str) -> Vec<u8> {
+   let count = value.encode_utf16().count() as u32 + 1;
+   let mut data: Vec<u8> = Vec::new();
+   data.extend(count.to_le_bytes());                         // MaximumCount
+   data.extend(0u32.to_le_bytes());                          // Offset
+   data.extend(count.to_le_bytes());                         // ActualCount
+   data.extend(utf16le(value));
+   data.extend([0u8, 0]);
+   while data.len() % 4 != 0 {
+      data.push(0);
+   }
+   data
+}
+
+/// NetrShareEnum request stub with level 1 (name, type and remark).
+fn share_enum_request(host: &str) -> Vec<u8> {
+   let mut stub: Vec<u8> = Vec::new();
+   stub.extend(0x0002_0000u32.to_le_bytes());                // ServerName referent
+   stub.extend(ndr_string(&format!("\\\\{}",host)));
+   stub.extend(1u32.to_le_bytes());                          // Level
+   stub.extend(1u32.to_le_bytes());                          // Union switch
+   stub.extend(0x0002_0004u32.to_le_bytes());                // SHARE_INFO_1_CONTAINER referent
+   stub.extend(0u32.to_le_bytes());                          // EntriesRead
+   stub.extend(0u32.to_le_bytes());                          // Buffer (null)
+   stub.extend(0xFFFF_FFFFu32.to_le_bytes());                // PreferedMaximumLength
+   stub.extend(0u32.to_le_bytes());                          // ResumeHandle (null)
+   stub
+}
+
+/// Read one little-endian u32 and move the offset.
+fn read_u32(stub: &[u8], offset: &mut usize) -> Option<u32> {
+   let value = u32::from_le_bytes(stub.get(*offset..*offset + 4)?.try_into().ok()?);
+   *offset += 4;
+   Some(value)
+}
+
+/// Read one NDR conformant and varying string and move the offset.
+fn read_ndr_string(stub: &[u8], offset: &mut usize) -> Option<String> {
+   let _maximum_count = read_u32(stub, offset)?;
+   let _offset = read_u32(stub, offset)?;
+   let count = read_u32(stub, offset)? as usize;
+   let data = stub.get(*offset..*offset + count * 2)?;
+   *offset += (count * 2 + 3) & !3;
+   let chars: Vec<u16> = data.chunks(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+   Some(String::from_utf16_lossy(&chars).trim_end_matches('\0').to_string())
+}
+
+/// Parse the NetrShareEnum response stub with level 1.
+pub fn parse_share_enum(stub: &[u8]) -> Option<Vec<ShareInfo>> {
+   let mut offset = 0;
+   let level = read_u32(stub, &mut offset)?;
+   let _switch = read_u32(stub, &mut offset)?;
+   if level != 1 || read_u32(stub, &mut offset)? == 0 {
+      return None
+   }
+   let entries = read_u32(stub, &mut offset)? as usize;
+   if read_u32(stub, &mut offset)? == 0 {
+      return Some(Vec::new())
+   }
+   let _maximum_count = read_u32(stub, &mut offset)?;
+   // Fixed part of every SHARE_INFO_1, pointers are followed by the deferred strings
+   let mut fixed: Vec<(u32, u32, u32)> = Vec::new();
+   for _ in 0..entries {
+      fixed.push((read_u32(stub, &mut offset)?, read_u32(stub, &mut offset)?, read_u32(stub, &mut offset)?));
+   }
+   let mut shares: Vec<ShareInfo> = Vec::new();
+   for (name_referent, share_type, remark_referent) in fixed {
+      let name = if name_referent != 0 { read_ndr_string(stub, &mut offset)? } else { String::new() };
+      let remark = if remark_referent != 0 { read_ndr_string(stub, &mut offset)? } else { String::new() };
+      shares.push(ShareInfo { name, share_type, remark });
+   }
+   // TotalEntries, ResumeHandle and return value
+   let _total = read_u32(stub, &mut offset)?;
+   let resume_referent = read_u32(stub, &mut offset)?;
+   if resume_referent != 0 {
+      read_u32(stub, &mut offset)?;
+   }
+   let status = read_u32(stub, &mut offset)?;
+   if status != 0 {
+      trace!("NetrShareEnum failed with status {:#010x}",status);
+      return None
+   }
+   Some(shares)
+}
+
+/// Get the share type name from shi1_type, special shares (ADMIN$, C$, IPC$) have the STYPE_SPECIAL bit.
+pub fn share_type_name(share_type: u32) -> String {
+   match share_type & 0x0FFF_FFFF {
+      0 => "Disk",
+      1 => "PrintQueue",
+      2 => "Device",
+      3 => "IPC",
+      _ => "Unknown",
+   }.to_string()
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_share_enum() {
+   let mut stub: Vec<u8> = Vec::new();
+   for value in [1u32, 1, 0x20000, 2, 0x20004, 2, 0x20008, 0x8000_0000, 0x2000c, 0x20010, 0, 0x20014] {
+      stub.extend(value.to_le_bytes());
+   }
+   stub.extend(ndr_string("ADMIN$"));
+   stub.extend(ndr_string("Remote Admin"));
+   stub.extend(ndr_string("Finance"));
+   stub.extend(ndr_string("Finance share"));
+   for value in [2u32, 0, 0] {
+      stub.extend(value.to_le_bytes());
+   }
+   let shares = parse_share_enum(&stub).unwrap();
+   assert_eq!(shares.len(), 2);
+   assert_eq!(shares[0], ShareInfo { name: "ADMIN$".to_string(), share_type: 0x8000_0000, remark: "Remote Admin".to_string() });
+   assert_eq!(shares[1].name, "Finance");
+   assert_eq!(share_type_name(shares[0].share_type), "Disk");
+   assert!(parse_share_enum(&stub[..20]).is_none());
+}
@@ -0,0 +1,73 @@
+use log::info;
+use colored::Colorize;
+use std::collections::HashMap;
+
+use crate::cancel::CancelToken;
+use crate::modules::resolver::resolv::DnsConfig;
+use crate::modules::scheduler;
+use crate::modules::smb::transport::{
+   SmbConnection,
+   SMB2_DIALECT_311,
+   SMB2_NEGOTIATE_SIGNING_REQUIRED,
+};
+
+/// All SMB2 dialects offered to get the highest one supported
+const DIALECTS: [u16; 5] = [0x0202, 0x0210, 0x0300, 0x0302, SMB2_DIALECT_311];
+
+/// Structure containing the SMB negotiation result for one computer.
+pub struct SmbSurvey {
+   pub signing_required: Option<bool>,
+   pub smbv1: Option<bool>,
+   pub dialect: Option<String>,
+}
+
+/// Function to negotiate SMB on all enabled computers
+pub async fn survey_all_computers(
+   vec_computers: &mut Vec<serde_json::value::Value>,
+   fqdn_ip: &HashMap<String, String>,
+   dns: &DnsConfig,
+   skip_dormant: bool,
+   cancel: &CancelToken,
+) {
+   info!("SMB signing and protocol survey started...");
+   let targets = scheduler::prepare_targets(vec_computers, fqdn_ip, dns, skip_dormant).await;
+   let results = scheduler::run_probes(&targets, "Negotiating SMB", cancel, |target| {
+      survey(&target.ip)
+   }).await;
+
+   let mut relay_targets = 0;
+   for (index, result) in results {
+      if result.signing_required == Some(false) {
+         relay_targets += 1;
+      }
+      vec_computers[index]["Properties"]["smbsigningrequired"] = result.signing_required.into();
+      vec_computers[index]["Properties"]["smbv1"] = result.smbv1.into();
+      vec_computers[index]["Properties"]["smbdialect"] = result.dialect.into();
+   }
+   info!("{} computers without SMB signing required",relay_targets.to_string().yellow().bold());
+   info!("SMB signing and protocol survey finished!");
+}
+
+/// Negotiate SMB2 to get signing mode and dialect then try SMB1 on a new connection.
+fn survey(ip: &String) -> Option<SmbSurvey> {
+   let mut connection = SmbConnection::connect(ip)?;
+   let mut result = SmbSurvey { signing_required: None, smbv1: None, dialect: None };
+   if connection.negotiate(&DIALECTS).is_some() {
+      result.signing_required = Some(connection.security_mode & SMB2_NEGOTIATE_SIGNING_REQUIRED != 0);
+      result.dialect = Some(dialect_name(connection.dialect));
+   }
+   result.smbv1 = SmbConnection::connect(ip).and_then(|mut connection| connection.negotiate_smb1());
+   Some(result)
+}
+
+/// Get the dialect name from SMB2 DialectRevision.
+pub fn dialect_name(dialect: u16) -> String {
+   match dialect {
+      0x0202 => "2.0.2",
+      0x0210 => "2.1",
+      0x0300 => "3.0",
+      0x0302 => "3.0.2",
+      0x0311 => "3.1.1",
+      _ => "Unknown",
+   }.to_string()
+}
@@ -0,0 +1,540 @@
+//! Minimal blocking SMB2 client over TCP 445.
+//!
+//! Only the commands needed by RustHound modules are implemented: NEGOTIATE, SESSION_SETUP, TREE_CONNECT, CREATE, READ, IOCTL, QUERY_DIRECTORY, QUERY_INFO and CLOSE.
+//! Dialects are limited to SMB 2.0.2 and 2.1 so signing stays HMAC-SHA256 with the NTLM session key.
+//! <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-smb2/5606ad47-5ee0-437a-817e-70c366052962>
+use log::trace;
+use std::convert::TryInto;
+use std::io::prelude::*;
+use std::net::{TcpStream, SocketAddr};
+use std::time::Duration;
+
+use crate::modules::smb::crypto::hmac_sha256;
+use crate::modules::smb::ntlm::{self, NtlmCredentials};
+
+/// SMB2 commands
+const SMB2_NEGOTIATE: u16 = 0x0000;
+const SMB2_SESSION_SETUP: u16 = 0x0001;
+const SMB2_TREE_CONNECT: u16 = 0x0003;
+const SMB2_CREATE: u16 = 0x0005;
+const SMB2_CLOSE: u16 = 0x0006;
+const SMB2_READ: u16 = 0x0008;
+const SMB2_IOCTL: u16 = 0x000B;
+const SMB2_QUERY_DIRECTORY: u16 = 0x000E;
+const SMB2_QUERY_INFO: u16 = 0x0010;
+/// FSCTL to write then read a named pipe in one request
+const FSCTL_PIPE_TRANSCEIVE: u32 = 0x0011_C017;
+/// DesiredAccess to read and write a named pipe
+const PIPE_ACCESS: u32 = 0x0012_019F;
+/// SMB2 dialects supported by the transport (2.0.2 and 2.1)
+pub const SMB2_DIALECTS: [u16; 2] = [0x0202, 0x0210];
+/// SMB 3.1.1 dialect, negotiation only
+pub const SMB2_DIALECT_311: u16 = 0x0311;
+/// SMB2 header flag for signed packet
+const SMB2_FLAGS_SIGNED: u32 = 0x0000_0008;
+/// NEGOTIATE SecurityMode flags
+pub const SMB2_NEGOTIATE_SIGNING_ENABLED: u16 = 0x0001;
+pub const SMB2_NEGOTIATE_SIGNING_REQUIRED: u16 = 0x0002;
+/// SESSION_SETUP SessionFlags for guest or null session (no signing possible)
+const SMB2_SESSION_FLAG_IS_GUEST_OR_NULL: u16 = 0x0003;
+/// NTSTATUS values
+pub const STATUS_SUCCESS: u32 = 0x0000_0000;
+pub const STATUS_MORE_PROCESSING_REQUIRED: u32 = 0xC000_0016;
+pub const STATUS_OBJECT_NAME_NOT_FOUND: u32 = 0xC000_0034;
+pub const STATUS_BUFFER_OVERFLOW: u32 = 0x8000_0005;
+/// DesiredAccess to read a file or list a directory (FILE_READ_DATA, FILE_READ_EA, FILE_READ_ATTRIBUTES, READ_CONTROL, SYNCHRONIZE)
+const READ_ACCESS: u32 = 0x0012_0089;
+/// FILE_ATTRIBUTE_DIRECTORY
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0000_0010;
+/// READ length, 64KiB is supported by every SMB2 server without multi-credit
+const SMB2_MAX_READ: u32 = 0x0001_0000;
+/// Files bigger than this limit are truncated
+const MAX_FILE_SIZE: u64 = 0x0100_0000;
+/// TCP timeout for every SMB probe
+const SMB_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Structure containing one SMB2 response.
+pub struct SmbResponse {
+   pub status: u32,
+   pub header: Vec<u8>,
+   pub body: Vec<u8>,
+}
+
+/// Structure containing the SMB2 connection state.
+pub struct SmbConnection {
+   stream: TcpStream,
+   message_id: u64,
+   session_id: u64,
+   tree_id: u32,
+   signing_key: Option<Vec<u8>>,
+   pub dialect: u16,
+   pub security_mode: u16,
+   /// Share permissions granted by the last TREE_CONNECT
+   pub maximal_access: u32,
+}
+
+impl SmbConnection {
+   /// Open TCP connection to the SMB server.
+   pub fn connect(ip: &String) -> Option<SmbConnection> {
+      let address = format!("{}:445",ip).parse::<SocketAddr>()
+         .or_else(|_| format!("[{}]:445",ip).parse::<SocketAddr>()).ok()?;
+      let stream = TcpStream::connect_timeout(&address, SMB_TIMEOUT).ok()?;
+      stream.set_read_timeout(Some(SMB_TIMEOUT)).ok()?;
+      stream.set_write_timeout(Some(SMB_TIMEOUT)).ok()?;
+      trace!("Connected to SMB {}",&address);
+      Some(SmbConnection {
+         stream,
+         message_id: 0,
+         session_id: 0,
+         tree_id: 0,
+         signing_key: None,
+         dialect: 0,
+         security_mode: 0,
+         maximal_access: 0,
+      })
+   }
+
+   /// Send SMB2 request and read the response.
+   fn request(&mut self, command: u16, body: &[u8]) -> Option<SmbResponse> {
+      let mut packet: Vec<u8> = vec![0xFE, b'S', b'M', b'B'];
+      packet.extend(64u16.to_le_bytes());         // StructureSize
+      packet.extend(1u16.to_le_bytes());          // CreditCharge
+      packet.extend(0u32.to_le_bytes());          // Status
+      packet.extend(command.to_le_bytes());       // Command
+      packet.extend(1u16.to_le_bytes());          // CreditRequest
+      let flags: u32 = if self.signing_key.is_some() { SMB2_FLAGS_SIGNED } else { 0 };
+      packet.extend(flags.to_le_bytes());         // Flags
+      packet.extend(0u32.to_le_bytes());          // NextCommand
+      packet.extend(self.message_id.to_le_bytes());
+      packet.extend(0xFEFFu32.to_le_bytes());     // Reserved (ProcessId)
+      packet.extend(self.tree_id.to_le_bytes());
+      packet.extend(self.session_id.to_le_bytes());
+      packet.extend([0u8; 16]);                   // Signature
+      packet.extend_from_slice(body);
+      self.message_id += 1;
+
+      if let Some(key) = &self.signing_key {
+         let signature = hmac_sha256(key, &packet);
+         packet[48..64].copy_from_slice(&signature[..16]);
+      }
+
+      // Direct TCP transport header
+      let mut frame: Vec<u8> = (packet.len() as u32).to_be_bytes().to_vec();
+      frame[0] = 0;
+      frame.extend(packet);
+      self.stream.write_all(&frame).ok()?;
+
+      loop {
+         let response = self.read_packet()?;
+         if response.len() < 64 || &response[0..4] != b"\xFESMB" {
+            return None
+         }
+         let status = u32::from_le_bytes(response[8..12].try_into().ok()?);
+         // STATUS_PENDING, the final response will follow
+         if status == 0x0000_0103 {
+            continue
+         }
+         return Some(SmbResponse {
+            status,
+            header: response[..64].to_vec(),
+            body: response[64..].to_vec(),
+         })
+      }
+   }
+
+   /// Read one Direct TCP transport frame.
+   fn read_packet(&mut self) -> Option<Vec<u8>> {
+      let mut length = [0u8; 4];
+      self.stream.read_exact(&mut length).ok()?;
+      let length = u32::from_be_bytes([0, length[1], length[2], length[3]]) as usize;
+      let mut packet = vec![0u8; length];
+      self.stream.read_exact(&mut packet).ok()?;
+      Some(packet)
+   }
+
+   /// SMB1 NEGOTIATE with only the "NT LM 0.12" dialect, return true if the server answers in SMB1.
+   /// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-cifs/25c8c3c9-58fc-4bb8-aa8f-0272dede84c5>
+   pub fn negotiate_smb1(&mut self) -> Option<bool> {
+      let mut packet: Vec<u8> = vec![0xFF, b'S', b'M', b'B', 0x72];
+      packet.extend(0u32.to_le_bytes());                     // Status
+      packet.push(0x18);                                     // Flags
+      packet.extend(0xC801u16.to_le_bytes());                // Flags2
+      packet.extend([0u8; 12]);                              // PIDHigh, SecurityFeatures, Reserved
+      packet.extend(0u16.to_le_bytes());                     // TID
+      packet.extend(0xFEFFu16.to_le_bytes());                // PIDLow
+      packet.extend(0u16.to_le_bytes());                     // UID
+      packet.extend(0u16.to_le_bytes());                     // MID
+      let dialect = b"\x02NT LM 0.12\x00";
+      packet.push(0);                                        // WordCount
+      packet.extend((dialect.len() as u16).to_le_bytes());   // ByteCount
+      packet.extend_from_slice(dialect);
+
+      let mut frame: Vec<u8> = (packet.len() as u32).to_be_bytes().to_vec();
+      frame[0] = 0;
+      frame.extend(packet);
+      self.stream.write_all(&frame).ok()?;
+
+      // Servers without SMB1 close the connection
+      match self.read_packet() {
+         Some(response) => Some(response.starts_with(b"\xFFSMB")),
+         None => Some(false),
+      }
+   }
+
+   /// SMB2 NEGOTIATE request with the given dialects.
+   /// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-smb2/e14db7ff-763a-4263-8b10-0c3944f52fc5>
+   pub fn negotiate(&mut self, dialects: &[u16]) -> Option<()> {
+      let mut body: Vec<u8> = Vec::new();
+      body.extend(36u16.to_le_bytes());                      // StructureSize
+      body.extend((dialects.len() as u16).to_le_bytes());    // DialectCount
+      body.extend(SMB2_NEGOTIATE_SIGNING_ENABLED.to_le_bytes());
+      body.extend(0u16.to_le_bytes());                       // Reserved
+      body.extend(0u32.to_le_bytes());                       // Capabilities
+      body.extend([0x52u8; 16]);                             // ClientGuid
+      // SMB 3.1.1 needs a preauth integrity context
+      let smb311 = dialects.contains(&SMB2_DIALECT_311);
+      let contexts_offset = (64 + 36 + 2 * dialects.len() + 7) & !7;
+      if smb311 {
+         body.extend((contexts_offset as u32).to_le_bytes()); // NegotiateContextOffset
+         body.extend(1u16.to_le_bytes());                   // NegotiateContextCount
+         body.extend(0u16.to_le_bytes());                   // Reserved2
+      } else {
+         body.extend(0u64.to_le_bytes());                   // ClientStartTime
+      }
+      for dialect in dialects {
+         body.extend(dialect.to_le_bytes());
+      }
+      if smb311 {
+         body.resize(contexts_offset - 64, 0);
+         body.extend(1u16.to_le_bytes());                   // SMB2_PREAUTH_INTEGRITY_CAPABILITIES
+         body.extend(38u16.to_le_bytes());                  // DataLength
+         body.extend(0u32.to_le_bytes());                   // Reserved
+         body.extend(1u16.to_le_bytes());                   // HashAlgorithmCount
+         body.extend(32u16.to_le_bytes());                  // SaltLength
+         body.extend(1u16.to_le_bytes());                   // SHA-512
+         body.extend([0x52u8; 32]);                         // Salt
+      }
+      let response = self.request(SMB2_NEGOTIATE, &body)?;
+      if response.status != STATUS_SUCCESS || response.body.len() < 6 {
+         return None
+      }
+      self.security_mode = u16::from_le_bytes(response.body[2..4].try_into().ok()?);
+      self.dialect = u16::from_le_bytes(response.body[4..6].try_into().ok()?);
+      trace!("SMB2 dialect {:#06x} security mode {:#x}",self.dialect,self.security_mode);
+      Some(())
+   }
+
+   /// SMB2 SESSION_SETUP with NTLM authentication (two round trips).
+   /// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-smb2/5a3c2c28-d6b0-48ed-b917-a86b2ca4575f>
+   pub fn session_setup(&mut self, credentials: &NtlmCredentials) -> Option<()> {
+      let response = self.session_setup_request(&ntlm::negotiate_token())?;
+      if response.status != STATUS_MORE_PROCESSING_REQUIRED {
+         return None
+      }
+      self.session_id = u64::from_le_bytes(response.header[40..48].try_into().ok()?);
+      let challenge = ntlm::parse_challenge(&response.body)?;
+
+      let (token, session_key) = ntlm::authenticate_token(credentials, &challenge);
+      let response = self.session_setup_request(&token)?;
+      if response.status != STATUS_SUCCESS {
+         trace!("SMB2 SESSION_SETUP failed with status {:#010x}",response.status);
+         return None
+      }
+      let session_flags = u16::from_le_bytes(response.body.get(2..4)?.try_into().ok()?);
+      if session_flags & SMB2_SESSION_FLAG_IS_GUEST_OR_NULL == 0 && !session_key.is_empty() {
+         self.signing_key = Some(session_key);
+      }
+      Some(())
+   }
+
+   fn session_setup_request(&mut self, token: &[u8]) -> Option<SmbResponse> {
+      let mut body: Vec<u8> = Vec::new();
+      body.extend(25u16.to_le_bytes());                      // StructureSize
+      body.push(0);                                          // Flags
+      body.push(SMB2_NEGOTIATE_SIGNING_ENABLED as u8);       // SecurityMode
+      body.extend(0u32.to_le_bytes());                       // Capabilities
+      body.extend(0u32.to_le_bytes());                       // Channel
+      body.extend((64u16 + 24).to_le_bytes());               // SecurityBufferOffset
+      body.extend((token.len() as u16).to_le_bytes());       // SecurityBufferLength
+      body.extend(0u64.to_le_bytes());                       // PreviousSessionId
+      body.extend_from_slice(token);
+      self.request(SMB2_SESSION_SETUP, &body)
+   }
+
+   /// SMB2 TREE_CONNECT to the given share.
+   /// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-smb2/832d2130-22e8-4afb-aafd-b30bb0901798>
+   pub fn tree_connect(&mut self, host: &String, share: &str) -> Option<()> {
+      let path = ntlm::utf16le(&format!("\\\\{}\\{}",host,share));
+      let mut body: Vec<u8> = Vec::new();
+      body.extend(9u16.to_le_bytes());                       // StructureSize
+      body.extend(0u16.to_le_bytes());                       // Flags
+      body.extend((64u16 + 8).to_le_bytes());                // PathOffset
+      body.extend((path.len() as u16).to_le_bytes());        // PathLength
+      body.extend(path);
+      let response = self.request(SMB2_TREE_CONNECT, &body)?;
+      if response.status != STATUS_SUCCESS {
+         trace!("SMB2 TREE_CONNECT {} failed with status {:#010x}",share,response.status);
+         return None
+      }
+      self.tree_id = u32::from_le_bytes(response.header[36..40].try_into().ok()?);
+      self.maximal_access = u32::from_le_bytes(response.body.get(12..16)?.try_into().ok()?);
+      Some(())
+   }
+
+   /// SMB2 CREATE to open an existing file or named pipe, return the NTSTATUS.
+   pub fn open(&mut self, name: &str) -> Option<u32> {
+      let response = self.create(name, READ_ACCESS)?;
+      Some(response.status)
+   }
+
+   /// SMB2 CREATE to open a named pipe for reading and writing, return the FileId.
+   pub fn open_pipe(&mut self, name: &str) -> Option<Vec<u8>> {
+      let response = self.create(name, PIPE_ACCESS)?;
+      if response.status != STATUS_SUCCESS || response.body.len() < 80 {
+         trace!("SMB2 CREATE pipe {} failed with status {:#010x}",name,response.status);
+         return None
+      }
+      Some(response.body[64..80].to_vec())
+   }
+
+   /// SMB2 CREATE request to open an existing file or named pipe with the given DesiredAccess.
+   /// <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-smb2/e8fb45c1-a03d-44ca-b7ae-47385cfd7997>
+   fn create(&mut self, name: &str, desired_access: u32) -> Option<SmbResponse> {
+      let name = ntlm::utf16le(name);
+      let mut body: Vec<u8> = Vec::new();
+      body.extend(57u16.to_le_bytes());                      // StructureSize
+      body.push(0);                                          // SecurityFlags
+      body.push(0);                                          // RequestedOplockLevel
+      body.extend(2u32.to_le_bytes());                       // ImpersonationLevel
+      body.extend(0u64.to_le_bytes());                       // SmbCreateFlags
+      body.extend(0u64.to_le_bytes());                       // Reserved
+      body.extend(desired_access.to_le_bytes());             // DesiredAccess
+      body.extend(0u32.to_le_bytes());                       // FileAttributes
+      body.extend(7u32.to_le_bytes());                       // ShareAccess
+      body.extend(1u32.to_le_bytes());                       // CreateDisposition (FILE_OPEN)
+      body.extend(0u32.to_le_bytes());                       // CreateOptions
+      body.extend((64u16 + 56).to_le_bytes());               // NameOffset
+      body.extend((name.len() as u16).to_le_bytes());        // NameLength
+      body.extend(0u32.to_le_bytes());                       // CreateContextsOffset
+      body.extend(0u32.to_le_bytes());                       // CreateContextsLength
+      body.extend(name);
+      self.request(SMB2_CREATE, &body)
+   }
+
+   /// Read a whole file from the connected share, path is relative to the share root.
+   /// Return None if the file doesn't exist or can't be read.
+   pub fn read_file(&mut self, name: &str) -> Option<Vec<u8>> {
+      let response = self.create(name, READ_ACCESS)?;
+      if response.status != STATUS_SUCCESS || response.body.len() < 80 {
+         trace!("SMB2 CREATE {} failed with status {:#010x}",name,response.status);
+         return None
+      }
+      let size = u64::from_le_bytes(response.body[48..56].try_into().ok()?).min(MAX_FILE_SIZE);
+      let file_id = response.body[64..80].to_vec();
+
+      let mut content: Vec<u8> = Vec::new();
+      while (content.len() as u64) < size {
+         let length = (size - content.len() as u64).min(SMB2_MAX_READ as u64) as u32;
+         match self.read(&file_id, content.len() as u64, length) {
+            Some(data) if !data.is_empty() => content.extend(data),
+            _ => break,
+         }
+      }
+      self.close(&file_id);
+      Some(content)
+   }
+
+   /// SMB2 READ request, return the data read.
+   fn read(&mut self, file_id: &[u8], offset: u64, length: u32) -> Option<Vec<u8>> {
+      let mut body: Vec<u8> = Vec::new();
+      body.extend(49u16.to_le_bytes());                      // StructureSize
+      body.push(0x50);                                       // Padding
+      body.push(0);                                          // Flags
+      body.extend(length.to_le_bytes());                     // Length
+      body.extend(offset.to_le_bytes());                     // Offset
+      body.extend_from_slice(file_id);                       // FileId
+      body.extend(0u32.to_le_bytes());                       // MinimumCount
+      body.extend(0u32.to_le_bytes());                       // Channel
+      body.extend(0u32.to_le_bytes());                       // RemainingBytes
+      body.extend(0u16.to_le_bytes());                       // ReadChannelInfoOffset
+      body.extend(0u16.to_le_bytes());                       // ReadChannelInfoLength
+      body.push(0);                                          // Buffer
+      let response = self.request(SMB2_READ, &body)?;
+      if response.status != STATUS_SUCCESS || response.body.len() < 16 {
+         return None
+      }
+      let data_offset = (response.body[2] as usize).checked_sub(64)?;
+      let data_length = u32::from_le_bytes(response.body[4..8].try_into().ok()?) as usize;
+      Some(response.body.get(data_offset..data_offset + data_length)?.to_vec())
+   }
+
+   /// SMB2 IOCTL FSCTL_PIPE_TRANSCEIVE to write one message in a named pipe and read the answer.
+   /// With STATUS_BUFFER_OVERFLOW the rest of the message is read with [`SmbConnection::read_pipe`].
+   pub fn transceive(&mut self, file_id: &[u8], data: &[u8]) -> Option<(u32, Vec<u8>)> {
+      let mut body: Vec<u8> = Vec::new();
+      body.extend(57u16.to_le_bytes());                      // StructureSize
+      body.extend(0u16.to_le_bytes());                       // Reserved
+      body.extend(FSCTL_PIPE_TRANSCEIVE.to_le_bytes());      // CtlCode
+      body.extend_from_slice(file_id);                       // FileId
+      body.extend((64u32 + 56).to_le_bytes());               // InputOffset
+      body.extend((data.len() as u32).to_le_bytes());        // InputCount
+      body.extend(0u32.to_le_bytes());                       // MaxInputResponse
+      body.extend(0u32.to_le_bytes());                       // OutputOffset
+      body.extend(0u32.to_le_bytes());                       // OutputCount
+      body.extend(SMB2_MAX_READ.to_le_bytes());              // MaxOutputResponse
+      body.extend(1u32.to_le_bytes());                       // Flags (SMB2_0_IOCTL_IS_FSCTL)
+      body.extend(0u32.to_le_bytes());                       // Reserved2
+      body.extend_from_slice(data);
+      let response = self.request(SMB2_IOCTL, &body)?;
+      if (response.status != STATUS_SUCCESS && response.status != STATUS_BUFFER_OVERFLOW) || response.body.len() < 48 {
+         trace!("SMB2 IOCTL failed with status {:#010x}",response.status);
+         return None
+      }
+      let output_offset = (u32::from_le_bytes(response.body[32..36].try_into().ok()?) as usize).checked_sub(64)?;
+      let output_count = u32::from_le_bytes(response.body[36..40].try_into().ok()?) as usize;
+      Some((response.status, response.body.get(output_offset..output_offset + output_count)?.to_vec()))
+   }
+
+   /// Read the next message of an opened named pipe.
+   pub fn read_pipe(&mut self, file_id: &[u8]) -> Option<Vec<u8>> {
+      self.read(file_id, 0, SMB2_MAX_READ)
+   }
+
+   /// List one directory of the connected share with SMB2 QUERY_DIRECTORY (FileDirectoryInformation).
+   /// Return the names with true for directories, without `.` and `..`.
+   pub fn list_directory(&mut self, name: &str) -> Option<Vec<(String, bool)>> {
+      let response = self.create(name, READ_ACCESS)?;
+      if response.status != STATUS_SUCCESS || response.body.len() < 80 {
+         trace!("SMB2 CREATE {} failed with status {:#010x}",name,response.status);
+         return None
+      }
+      let file_id = response.body[64..80].to_vec();
+      let pattern = ntlm::utf16le("*");
+
+      let mut entries: Vec<(String, bool)> = Vec::new();
+      loop {
+         let mut body: Vec<u8> = Vec::new();
+         body.extend(33u16.to_le_bytes());                   // StructureSize
+         body.push(0x01);                                    // FileInformationClass (FileDirectoryInformation)
+         body.push(0);                                       // Flags
+         body.extend(0u32.to_le_bytes());                    // FileIndex
+         body.extend_from_slice(&file_id);                   // FileId
+         body.extend((64u16 + 32).to_le_bytes());            // FileNameOffset
+         body.extend((pattern.len() as u16).to_le_bytes());  // FileNameLength
+         body.extend(SMB2_MAX_READ.to_le_bytes());           // OutputBufferLength
+         body.extend_from_slice(&pattern);
+         let response = match self.request(SMB2_QUERY_DIRECTORY, &body) {
+            Some(response) if response.status == STATUS_SUCCESS && response.body.len() >= 8 => response,
+            _ => break,
+         };
+         // A malformed response stops the listing, the handle is still closed
+         match get_output_buffer(&response.body) {
+            Some(buffer) => entries.extend(parse_directory_information(buffer)),
+            None => {
+               trace!("SMB2 QUERY_DIRECTORY {} returned a malformed response",name);
+               break
+            },
+         }
+      }
+      self.close(&file_id);
+      Some(entries)
+   }
+
+   /// Read the security descriptor (owner, group and DACL) of one file or directory with SMB2 QUERY_INFO.
+   pub fn query_security(&mut self, name: &str) -> Option<Vec<u8>> {
+      let response = self.create(name, READ_ACCESS)?;
+      if response.status != STATUS_SUCCESS || response.body.len() < 80 {
+         trace!("SMB2 CREATE {} failed with status {:#010x}",name,response.status);
+         return None
+      }
+      let file_id = response.body[64..80].to_vec();
+      let mut body: Vec<u8> = Vec::new();
+      body.extend(41u16.to_le_bytes());                      // StructureSize
+      body.push(0x03);                                       // InfoType (SMB2_0_INFO_SECURITY)
+      body.push(0);                                          // FileInfoClass
+      body.extend(SMB2_MAX_READ.to_le_bytes());              // OutputBufferLength
+      body.extend(0u16.to_le_bytes());                       // InputBufferOffset
+      body.extend(0u16.to_le_bytes());                       // Reserved
+      body.extend(0u32.to_le_bytes());                       // InputBufferLength
+      body.extend(0x0000_0007u32.to_le_bytes());             // AdditionalInformation (OWNER, GROUP and DACL)
+      body.extend(0u32.to_le_bytes());                       // Flags
+      body.extend_from_slice(&file_id);                      // FileId
+      body.push(0);                                          // Buffer
+      let response = self.request(SMB2_QUERY_INFO, &body);
+      self.close(&file_id);
+      let response = response?;
+      if response.status != STATUS_SUCCESS || response.body.len() < 8 {
+         trace!("SMB2 QUERY_INFO {} failed with status {:#010x}",name,response.status);
+         return None
+      }
+      let offset = (u16::from_le_bytes(response.body[2..4].try_into().ok()?) as usize).checked_sub(64)?;
+      let length = u32::from_le_bytes(response.body[4..8].try_into().ok()?) as usize;
+      Some(response.body.get(offset..offset + length)?.to_vec())
+   }
+
+   /// SMB2 CLOSE request for an opened file.
+   pub fn close(&mut self, file_id: &[u8]) {
+      let mut body: Vec<u8> = Vec::new();
+      body.extend(24u16.to_le_bytes());                      // StructureSize
+      body.extend(0u16.to_le_bytes());                       // Flags
+      body.extend(0u32.to_le_bytes());                       // Reserved
+      body.extend_from_slice(file_id);                       // FileId
+      let _ = self.request(SMB2_CLOSE, &body);
+   }
+}
+
+/// Get the output buffer of one QUERY_DIRECTORY response body from its offset and length.
+fn get_output_buffer(body: &[u8]) -> Option<&[u8]> {
+   let offset = (u16::from_le_bytes(body.get(2..4)?.try_into().ok()?) as usize).checked_sub(64)?;
+   let length = u32::from_le_bytes(body.get(4..8)?.try_into().ok()?) as usize;
+   body.get(offset..offset.checked_add(length)?)
+}
+
+/// Parse FILE_DIRECTORY_INFORMATION entries, return the names with true for directories.
+pub fn parse_directory_information(buffer: &[u8]) -> Vec<(String, bool)> {
+   let mut entries: Vec<(String, bool)> = Vec::new();
+   let mut offset = 0;
+   while let Some(entry) = buffer.get(offset..) {
+      if entry.len() < 64 {
+         break
+      }
+      let next = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]) as usize;
+      let attributes = u32::from_le_bytes([entry[56], entry[57], entry[58], entry[59]]);
+      let name_length = u32::from_le_bytes([entry[60], entry[61], entry[62], entry[63]]) as usize;
+      if let Some(name) = entry.get(64..64 + name_length) {
+         let chars: Vec<u16> = name.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+         let name = String::from_utf16_lossy(&chars);
+         if name != "." && name != ".." {
+            entries.push((name, attributes & FILE_ATTRIBUTE_DIRECTORY != 0));
+         }
+      }
+      if next == 0 {
+         break
+      }
+      offset += next;
+   }
+   entries
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_directory_information() {
+   let mut buffer: Vec<u8> = Vec::new();
+   for (name, attributes, last) in [(".", FILE_ATTRIBUTE_DIRECTORY, false), ("scripts", FILE_ATTRIBUTE_DIRECTORY, false), ("logon.bat", 0x20, true)] {
+      let name = ntlm::utf16le(name);
+      let size = (64 + name.len() + 7) & !7;
+      let mut entry = vec![0u8; size];
+      if !last {
+         entry[0..4].copy_from_slice(&(size as u32).to_le_bytes());
+      }
+      entry[56..60].copy_from_slice(&attributes.to_le_bytes());
+      entry[60..64].copy_from_slice(&(name.len() as u32).to_le_bytes());
+      entry[64..64 + name.len()].copy_from_slice(&name);
+      buffer.extend(entry);
+   }
+   assert_eq!(parse_directory_information(&buffer), vec![("scripts".to_string(), true), ("logon.bat".to_string(), false)]);
+   assert!(parse_directory_information(&[0u8; 10]).is_empty());
+   assert_eq!(get_output_buffer(&[9, 0, 72, 0, 3, 0, 0, 0, 1, 2, 3]), Some(&[1u8, 2, 3][..]));
+   assert_eq!(get_output_buffer(&[9, 0, 72, 0, 255, 0, 0, 0, 1]), None);
+   assert_eq!(get_output_buffer(&[9, 0, 8, 0]), None);
+}
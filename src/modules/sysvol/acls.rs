@@ -0,0 +1,205 @@
+//! NTFS permissions of SYSVOL and NETLOGON files
+//!
+//! GPO folders and logon scripts are read and run by every computer and user applying them,
+//! so write access for non-admin principals is a privilege escalation path not visible in LDAP.
+//! Only the files and folders with such writers are added in shares.json.
+use log::{info,debug,trace};
+use colored::Colorize;
+use std::collections::HashMap;
+
+use crate::cancel::CancelToken;
+use crate::enums::constants::{ACCESS_ALLOWED_ACE_TYPE,INHERIT_ONLY_ACE};
+use crate::enums::secdesc::{SecurityDescriptor, AceFormat};
+use crate::enums::sid::sid_maker;
+use crate::json::checker::bh_41::BENIGN_TRUSTEES;
+use crate::json::templates::bh_41::{prepare_share_json_template,prepare_member_json_template};
+use crate::modules::smb::transport::SmbConnection;
+use super::get_gpo_path;
+
+/// Folder depth and number of files read for every GPO folder or NETLOGON share
+const MAX_DEPTH: usize = 8;
+const MAX_ENTRIES: usize = 500;
+/// FILE_WRITE_DATA, FILE_APPEND_DATA, DELETE, WRITE_DAC, WRITE_OWNER, GENERIC_ALL and GENERIC_WRITE
+const WRITE_RIGHTS: u32 = 0x0000_0002 | 0x0000_0004 | 0x0001_0000 | 0x0004_0000 | 0x0008_0000 | 0x1000_0000 | 0x4000_0000;
+/// Trustees allowed to write GPO files besides the built-in admins: CREATOR OWNER, Schema Admins and Group Policy Creator Owners
+const GPO_WRITERS: [&str; 3] = ["S-1-3-0", "-518", "-520"];
+
+/// Function to read the NTFS permissions of GPO folders in SYSVOL and of NETLOGON,
+/// and add the files and folders writable by non-admin principals in shares.json.
+pub fn collect_file_acls(
+   connection: &mut SmbConnection,
+   host: &String,
+   domain: &String,
+   vec_gpos: &Vec<serde_json::value::Value>,
+   vec_shares: &mut Vec<serde_json::value::Value>,
+   sid_type: &HashMap<String, String>,
+   cancel: &CancelToken,
+) {
+   info!("Reading SYSVOL and NETLOGON permissions started...");
+   let mut count = 0;
+   for (read, gpo) in vec_gpos.iter().enumerate() {
+      if cancel.is_cancelled() {
+         cancel.stopped("SYSVOL permissions", format!("{} of {} GPOs read", read, vec_gpos.len()));
+         break
+      }
+      let gpo_path = match get_gpo_path(gpo["Properties"]["gpcpath"].as_str().unwrap_or("")) {
+         Some(path) => path,
+         None => continue,
+      };
+      for (path, is_directory, writers) in audit_tree(connection, &gpo_path, domain) {
+         let mut file_json = make_file_json(host, "SYSVOL", &path, is_directory, &writers, sid_type);
+         file_json["ObjectIdentifier"] = gpo["ObjectIdentifier"].to_owned();
+         info!("{} writable by non-admin principals in {}",&path.yellow().bold(),gpo["Properties"]["name"].as_str().unwrap_or(""));
+         vec_shares.push(file_json);
+         count += 1;
+      }
+   }
+   if !cancel.proceed("NETLOGON permissions") {
+      return
+   }
+   if connection.tree_connect(host, "NETLOGON").is_some() {
+      for (path, is_directory, writers) in audit_tree(connection, "", domain) {
+         info!("NETLOGON\\{} writable by non-admin principals",&path.yellow().bold());
+         vec_shares.push(make_file_json(host, "NETLOGON", &path, is_directory, &writers, sid_type));
+         count += 1;
+      }
+   } else {
+      debug!("Can't connect to NETLOGON share on {}",host);
+   }
+   info!("{} files and folders writable by non-admin principals",count.to_string().yellow().bold());
+   info!("Reading SYSVOL and NETLOGON permissions finished!");
+}
+
+/// Walk one folder of the connected share and return the paths with their non-admin writers.
+fn audit_tree(connection: &mut SmbConnection, root: &str, domain: &String) -> Vec<(String, bool, Vec<String>)> {
+   let mut paths: Vec<(String, bool)> = vec![(root.to_string(), true)];
+   walk(connection, root, 0, &mut paths);
+
+   let mut result: Vec<(String, bool, Vec<String>)> = Vec::new();
+   for (path, is_directory) in paths {
+      let writers = match connection.query_security(&path) {
+         Some(security_descriptor) => get_non_admin_writers(&security_descriptor, domain),
+         None => {
+            trace!("Can't read security descriptor of {}",&path);
+            continue
+         }
+      };
+      if !writers.is_empty() {
+         result.push((path, is_directory, writers));
+      }
+   }
+   result
+}
+
+/// List files and folders recursively, limited by MAX_DEPTH and MAX_ENTRIES.
+fn walk(connection: &mut SmbConnection, path: &str, depth: usize, paths: &mut Vec<(String, bool)>) {
+   if depth >= MAX_DEPTH {
+      return
+   }
+   for (name, is_directory) in connection.list_directory(path).unwrap_or_default() {
+      if paths.len() >= MAX_ENTRIES {
+         return
+      }
+      let child = if path.is_empty() { name } else { format!("{}\\{}",path,name) };
+      paths.push((child.to_owned(), is_directory));
+      if is_directory {
+         walk(connection, &child, depth + 1, paths);
+      }
+   }
+}
+
+/// Function to get the SIDs of the owner and the trustees with write rights in one security descriptor,
+/// without built-in admins and GPO writers. Inherit-only ACEs don't apply to the object itself.
+pub fn get_non_admin_writers(security_descriptor: &[u8], domain: &String) -> Vec<String> {
+   let secdesc = match SecurityDescriptor::parse(security_descriptor) {
+      Ok((_, secdesc)) => secdesc,
+      Err(_) => return Vec::new(),
+   };
+   let mut writers: Vec<String> = Vec::new();
+   // Owner can always change the DACL
+   if let Ok(Some(owner)) = secdesc.parse_owner(security_descriptor) {
+      writers.push(sid_maker(owner, domain));
+   }
+   if let Ok(Some(dacl)) = secdesc.parse_dacl(security_descriptor) {
+      for ace in dacl.data {
+         if ace.ace_type != ACCESS_ALLOWED_ACE_TYPE || ace.ace_flags & INHERIT_ONLY_ACE != 0 {
+            continue
+         }
+         let mask = AceFormat::get_mask(ace.data.to_owned()).unwrap_or(0);
+         if mask & WRITE_RIGHTS == 0 {
+            continue
+         }
+         if let Some(sid) = AceFormat::get_sid(ace.data) {
+            writers.push(sid_maker(sid, domain));
+         }
+      }
+   }
+   writers.retain(|sid| !BENIGN_TRUSTEES.iter().chain(GPO_WRITERS.iter()).any(|trustee| sid.ends_with(trustee)));
+   writers.sort();
+   writers.dedup();
+   writers
+}
+
+/// Function to make the shares.json entry of one file or folder.
+fn make_file_json(
+   host: &String,
+   share: &str,
+   path: &str,
+   is_directory: bool,
+   writers: &Vec<String>,
+   sid_type: &HashMap<String, String>,
+) -> serde_json::value::Value {
+   let mut file_json = prepare_share_json_template();
+   file_json["Name"] = format!("\\\\{}\\{}\\{}",host.to_lowercase(),share,path).trim_end_matches('\\').into();
+   file_json["Type"] = if is_directory { "Directory" } else { "File" }.into();
+   file_json["Host"] = host.to_lowercase().into();
+   let writable_by: Vec<serde_json::value::Value> = writers.iter()
+      .map(|sid| {
+         let mut member = prepare_member_json_template();
+         member["ObjectIdentifier"] = sid.to_owned().into();
+         member["ObjectType"] = sid_type.get(sid).unwrap_or(&"Group".to_string()).to_owned().into();
+         member
+      })
+      .collect();
+   file_json["WritableBy"] = writable_by.into();
+   file_json
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_non_admin_writers() {
+   // Owner Domain Admins, DACL: Authenticated Users modify, SYSTEM full control, Domain Users read, CREATOR OWNER inherit-only full control
+   let sid = |authority: u8, sub: &[u32]| -> Vec<u8> {
+      let mut sid = vec![1u8, sub.len() as u8, 0, 0, 0, 0, 0, authority];
+      for value in sub {
+         sid.extend(value.to_le_bytes());
+      }
+      sid
+   };
+   let ace = |flags: u8, mask: u32, sid: Vec<u8>| -> Vec<u8> {
+      let mut ace = vec![ACCESS_ALLOWED_ACE_TYPE, flags];
+      ace.extend(((8 + sid.len()) as u16).to_le_bytes());
+      ace.extend(mask.to_le_bytes());
+      ace.extend(sid);
+      ace
+   };
+   let mut aces: Vec<u8> = Vec::new();
+   aces.extend(ace(0, 0x0013_01BF, sid(5, &[11])));
+   aces.extend(ace(0, 0x001F_01FF, sid(5, &[18])));
+   aces.extend(ace(0, 0x0012_00A9, sid(5, &[21, 1, 2, 3, 513])));
+   aces.extend(ace(INHERIT_ONLY_ACE, 0x1000_0000, sid(3, &[0])));
+   let owner = sid(5, &[21, 1, 2, 3, 512]);
+   let mut security_descriptor: Vec<u8> = vec![1, 0, 0x04, 0x80];
+   security_descriptor.extend(20u32.to_le_bytes());                           // Owner
+   security_descriptor.extend(0u32.to_le_bytes());                            // Group
+   security_descriptor.extend(0u32.to_le_bytes());                            // Sacl
+   security_descriptor.extend(((20 + owner.len()) as u32).to_le_bytes());     // Dacl
+   security_descriptor.extend(owner);
+   security_descriptor.extend([2u8, 0]);
+   security_descriptor.extend(((8 + aces.len()) as u16).to_le_bytes());
+   security_descriptor.extend(4u16.to_le_bytes());
+   security_descriptor.extend(0u16.to_le_bytes());
+   security_descriptor.extend(aces);
+   assert_eq!(get_non_admin_writers(&security_descriptor, &"essos.local".to_string()), vec!["ESSOS.LOCAL-S-1-5-11".to_string()]);
+   assert!(get_non_admin_writers(&[0u8; 4], &"essos.local".to_string()).is_empty());
+}
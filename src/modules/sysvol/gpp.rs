@@ -0,0 +1,177 @@
+//! Group Policy Preferences parsing
+//!
+//! Only local groups changes are kept: members added with Groups.xml (Local Users and Groups)
+//! and `net localgroup` / `Add-LocalGroupMember` commands run by ScheduledTasks.xml.
+use regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::enums::sid::get_well_known_rid;
+
+lazy_static! {
+   static ref RE_TAG: Regex = Regex::new(r"<(Group|User|Properties|Member)\b([^>]*)>").unwrap();
+   static ref RE_ATTRIBUTE: Regex = Regex::new(r#"([A-Za-z]+)="([^"]*)""#).unwrap();
+   static ref RE_CPASSWORD: Regex = Regex::new(r#"cpassword="[^"]+""#).unwrap();
+   static ref RE_EXEC: Regex = Regex::new(r"(?s)<Command>(.*?)</Command>\s*(?:<Arguments>(.*?)</Arguments>)?").unwrap();
+   static ref RE_NET_LOCALGROUP: Regex = Regex::new(r#"(?i)\bnet(?:\.exe)?"?\s+localgroup\s+(?:"([^"]+)"|(\S+))\s+(?:"([^"]+)"|(\S+))\s+/add"#).unwrap();
+   static ref RE_ADD_LOCALGROUPMEMBER: Regex = Regex::new(r#"(?i)Add-LocalGroupMember\s+-(?:Group|Name)\s+(?:"([^"]+)"|'([^']+)'|(\S+))\s+-Member\s+(?:"([^"]+)"|'([^']+)'|(\S+))"#).unwrap();
+}
+
+/// Structure containing one member added to a local group by a GPO.
+#[derive(Debug, PartialEq)]
+pub struct GppMember {
+   /// GPOChanges key: LocalAdmins, RemoteDesktopUsers, DcomUsers or PSRemoteUsers
+   pub group: &'static str,
+   pub name: String,
+   pub sid: Option<String>,
+}
+
+/// Function to get the GPOChanges key from the local group SID or name.
+/// Without SID the name is resolved in any supported language, `Administratoren (integriert)` => `S-1-5-32-544`.
+pub fn local_group_kind(sid: &str, name: &str) -> Option<&'static str> {
+   get_builtin_group_kind(&sid.to_uppercase()).or_else(|| {
+      // Without the "(built-in)" suffix of the group picker, localized too
+      let name = name.split(" (").next().unwrap_or("");
+      get_builtin_group_kind(get_well_known_rid(name)?)
+   })
+}
+
+/// Function to get the GPOChanges key from the built-in group SID.
+fn get_builtin_group_kind(sid: &str) -> Option<&'static str> {
+   match sid {
+      "S-1-5-32-544" => Some("LocalAdmins"),
+      "S-1-5-32-555" => Some("RemoteDesktopUsers"),
+      "S-1-5-32-562" => Some("DcomUsers"),
+      "S-1-5-32-580" => Some("PSRemoteUsers"),
+      _ => None,
+   }
+}
+
+/// Function to parse Groups.xml and get members added to local groups.
+pub fn parse_groups_xml(content: &str) -> Vec<GppMember> {
+   let mut members: Vec<GppMember> = Vec::new();
+   let mut group: Option<&'static str> = None;
+   for tag in RE_TAG.captures_iter(content) {
+      let attributes = get_attributes(&tag[2]);
+      let attribute = |name: &str| -> String {
+         attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.to_owned()).unwrap_or_default()
+      };
+      match &tag[1] {
+         "Group" | "User" => group = None,
+         "Properties" => group = local_group_kind(&attribute("groupSid"), &attribute("groupName")),
+         "Member" => {
+            if let Some(kind) = group {
+               if attribute("action").to_uppercase() != "ADD" {
+                  continue
+               }
+               let sid = attribute("sid");
+               members.push(GppMember {
+                  group: kind,
+                  name: attribute("name"),
+                  sid: if sid.is_empty() { None } else { Some(sid) },
+               });
+            }
+         }
+         _ => {}
+      }
+   }
+   members
+}
+
+/// Function to parse ScheduledTasks.xml and get members added to local groups by the task commands.
+pub fn parse_scheduled_tasks_xml(content: &str) -> Vec<GppMember> {
+   let mut commands: Vec<String> = Vec::new();
+   // TaskV2 and ImmediateTaskV2
+   for exec in RE_EXEC.captures_iter(content) {
+      commands.push(format!("{} {}",
+         unescape(&exec[1]),
+         unescape(exec.get(2).map(|m| m.as_str()).unwrap_or(""))
+      ));
+   }
+   // Task and ImmediateTask (Windows XP)
+   for tag in RE_TAG.captures_iter(content) {
+      if &tag[1] != "Properties" {
+         continue
+      }
+      let attributes = get_attributes(&tag[2]);
+      let app_name = attributes.iter().find(|(key, _)| key == "appName").map(|(_, value)| value.to_owned());
+      if let Some(app_name) = app_name {
+         let args = attributes.iter().find(|(key, _)| key == "args").map(|(_, value)| value.to_owned()).unwrap_or_default();
+         commands.push(format!("{} {}", app_name, args));
+      }
+   }
+   commands.iter().filter_map(|command| parse_command(command)).collect()
+}
+
+/// Function to get the member added to a local group by one command line.
+pub fn parse_command(command: &str) -> Option<GppMember> {
+   let (group, name) = if let Some(captures) = RE_NET_LOCALGROUP.captures(command) {
+      (first_match(&captures, &[1, 2])?, first_match(&captures, &[3, 4])?)
+   } else if let Some(captures) = RE_ADD_LOCALGROUPMEMBER.captures(command) {
+      (first_match(&captures, &[1, 2, 3])?, first_match(&captures, &[4, 5, 6])?)
+   } else {
+      return None
+   };
+   Some(GppMember {
+      group: local_group_kind("", &group)?,
+      name,
+      sid: None,
+   })
+}
+
+/// Function to check if a GPP file has a cpassword (password encrypted with the published AES key, MS14-025).
+pub fn has_cpassword(content: &str) -> bool {
+   RE_CPASSWORD.is_match(content)
+}
+
+fn get_attributes(tag: &str) -> Vec<(String, String)> {
+   RE_ATTRIBUTE.captures_iter(tag).map(|attribute| (attribute[1].to_string(), unescape(&attribute[2]))).collect()
+}
+
+fn first_match(captures: &regex::Captures, indexes: &[usize]) -> Option<String> {
+   indexes.iter().find_map(|i| captures.get(*i)).map(|m| m.as_str().to_string())
+}
+
+fn unescape(value: &str) -> String {
+   value.replace("&quot;", "\"").replace("&apos;", "'").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&")
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_groups_xml() {
+   let content = r#"<?xml version="1.0" encoding="utf-8"?>
+<Groups clsid="{3125E937-EB16-4b4c-9934-544FC6D24D26}">
+<User clsid="{DF5F1855-51E5-4d24-8B1A-D9BDE98BA1D1}" name="localadm"><Properties action="U" userName="localadm" cpassword="j1Uyj3Vx8TY9LtLZil2uAuZkFQA/4latT76ZwgdHdhw"/></User>
+<Group clsid="{6D4A79E4-529C-4481-ABD0-F5BD7EA93BA7}" name="Administrators (built-in)"><Properties action="U" groupSid="S-1-5-32-544" groupName="Administrators (built-in)"><Members><Member name="ESSOS\jorah.mormont" action="ADD" sid="S-1-5-21-1-2-3-1113"/><Member name="ESSOS\viserys" action="REMOVE" sid=""/></Members></Properties></Group>
+<Group clsid="{6D4A79E4-529C-4481-ABD0-F5BD7EA93BA7}" name="Remote Desktop Users (built-in)"><Properties action="U" groupSid="" groupName="Remote Desktop Users (built-in)"><Members><Member name="ESSOS\khal.drogo" action="ADD" sid=""/></Members></Properties></Group>
+</Groups>"#;
+   let members = parse_groups_xml(content);
+   assert_eq!(members.len(), 2);
+   assert_eq!(members[0], GppMember { group: "LocalAdmins", name: "ESSOS\\jorah.mormont".to_string(), sid: Some("S-1-5-21-1-2-3-1113".to_string()) });
+   assert_eq!(members[1], GppMember { group: "RemoteDesktopUsers", name: "ESSOS\\khal.drogo".to_string(), sid: None });
+   assert!(has_cpassword(content));
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_scheduled_tasks_xml() {
+   let content = r#"<ScheduledTasks><ImmediateTaskV2 name="add"><Properties action="C" name="add"><Task version="1.2"><Actions><Exec><Command>cmd.exe</Command><Arguments>/c net localgroup administrators ESSOS\daenerys.targaryen /add</Arguments></Exec></Actions></Task></Properties></ImmediateTaskV2>
+<Task name="old"><Properties action="C" appName="powershell.exe" args="-c Add-LocalGroupMember -Group &quot;Remote Management Users&quot; -Member missandei"/></Task></ScheduledTasks>"#;
+   let members = parse_scheduled_tasks_xml(content);
+   assert_eq!(members.len(), 2);
+   assert_eq!(members[0], GppMember { group: "LocalAdmins", name: "ESSOS\\daenerys.targaryen".to_string(), sid: None });
+   assert_eq!(members[1], GppMember { group: "PSRemoteUsers", name: "missandei".to_string(), sid: None });
+   assert_eq!(parse_command("net localgroup \"Remote Desktop Users\" jorah /add").unwrap().group, "RemoteDesktopUsers");
+   assert!(parse_command("net localgroup administrators jorah /delete").is_none());
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_local_group_kind() {
+   assert_eq!(local_group_kind("S-1-5-32-544", "Administrators (built-in)"), Some("LocalAdmins"));
+   assert_eq!(local_group_kind("", "Administratoren (integriert)"), Some("LocalAdmins"));
+   assert_eq!(local_group_kind("", "Utilisateurs du Bureau à distance (intégré)"), Some("RemoteDesktopUsers"));
+   assert_eq!(local_group_kind("", "Remoteverwaltungsbenutzer"), Some("PSRemoteUsers"));
+   assert_eq!(local_group_kind("", "Domänen-Admins"), None);
+   let content = r#"<Groups><Group name="Administrateurs (intégré)"><Properties action="U" groupSid="" groupName="Administrateurs (intégré)"><Members><Member name="ESSOS\jorah.mormont" action="ADD" sid=""/></Members></Properties></Group></Groups>"#;
+   assert_eq!(parse_groups_xml(content), vec![GppMember { group: "LocalAdmins", name: "ESSOS\\jorah.mormont".to_string(), sid: None }]);
+}
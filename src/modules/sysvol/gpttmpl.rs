@@ -0,0 +1,67 @@
+//! Security template parsing
+//!
+//! Only the `[Group Membership]` section (Restricted Groups) of GptTmpl.inf is kept:
+//! `<group>__Members = <member>,...` sets the members of a local group,
+//! `<member>__Memberof = <group>,...` adds a principal in local groups.
+//! Principals are `*<SID>` or names.
+use crate::modules::sysvol::gpp::{local_group_kind, GppMember};
+
+/// Function to parse GptTmpl.inf and get members added to local groups by Restricted Groups.
+pub fn parse_group_membership(content: &str) -> Vec<GppMember> {
+   let mut members: Vec<GppMember> = Vec::new();
+   let mut in_section = false;
+   for line in content.lines() {
+      let line = line.trim();
+      if line.starts_with('[') {
+         in_section = line.eq_ignore_ascii_case("[Group Membership]");
+         continue
+      }
+      if !in_section {
+         continue
+      }
+      let (key, values) = match line.split_once('=') {
+         Some((key, values)) => (key.trim(), values.trim()),
+         None => continue,
+      };
+      let values: Vec<&str> = values.split(',').map(|value| value.trim()).filter(|value| !value.is_empty()).collect();
+      if let Some(group) = key.strip_suffix("__Members").or_else(|| key.strip_suffix("__members")) {
+         if let Some(kind) = get_group_kind(group) {
+            for member in values {
+               members.push(make_member(kind, member));
+            }
+         }
+      } else if let Some(member) = key.strip_suffix("__Memberof").or_else(|| key.strip_suffix("__memberof")) {
+         for group in values {
+            if let Some(kind) = get_group_kind(group) {
+               members.push(make_member(kind, member));
+            }
+         }
+      }
+   }
+   members
+}
+
+fn get_group_kind(group: &str) -> Option<&'static str> {
+   match group.strip_prefix('*') {
+      Some(sid) => local_group_kind(sid, ""),
+      None => local_group_kind("", group),
+   }
+}
+
+fn make_member(kind: &'static str, principal: &str) -> GppMember {
+   match principal.strip_prefix('*') {
+      Some(sid) => GppMember { group: kind, name: sid.to_string(), sid: Some(sid.to_uppercase()) },
+      None => GppMember { group: kind, name: principal.to_string(), sid: None },
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_group_membership() {
+   let content = "[Unicode]\r\nUnicode=yes\r\n[Group Membership]\r\n*S-1-5-32-544__Memberof =\r\n*S-1-5-32-544__Members = *S-1-5-21-1-2-3-1105,ESSOS\\jorah.mormont\r\n*S-1-5-21-1-2-3-1106__Memberof = *S-1-5-32-555,*S-1-5-32-551\r\n[Version]\r\nsignature=\"$CHICAGO$\"\r\n";
+   let members = parse_group_membership(content);
+   assert_eq!(members.len(), 3);
+   assert_eq!(members[0], GppMember { group: "LocalAdmins", name: "S-1-5-21-1-2-3-1105".to_string(), sid: Some("S-1-5-21-1-2-3-1105".to_string()) });
+   assert_eq!(members[1], GppMember { group: "LocalAdmins", name: "ESSOS\\jorah.mormont".to_string(), sid: None });
+   assert_eq!(members[2], GppMember { group: "RemoteDesktopUsers", name: "S-1-5-21-1-2-3-1106".to_string(), sid: Some("S-1-5-21-1-2-3-1106".to_string()) });
+}
@@ -0,0 +1,238 @@
+//! SYSVOL GPO module
+//!
+//! Read the GPO files from the SYSVOL share of one domain controller to get local groups changed by each GPO,
+//! with Group Policy Preferences (Groups.xml, ScheduledTasks.xml) or Restricted Groups (GptTmpl.inf).
+//! Changes are pushed in `GPOChanges` of OUs and domains linking these GPOs,
+//! BloodHound adds `AdminTo`, `CanRDP`, `ExecuteDCOM` and `CanPSRemote` edges to their `AffectedComputers`.
+//! With **--sysvol-acls** the NTFS permissions of GPO folders and NETLOGON are read too, see [`acls`].
+pub mod acls;
+pub mod gpp;
+pub mod gpttmpl;
+
+use log::{info,debug,error};
+use colored::Colorize;
+use std::collections::HashMap;
+
+use crate::args::Options;
+use crate::cancel::CancelToken;
+use crate::json::checker::netbios::DomainNames;
+use crate::json::stats::mark_partial;
+use crate::json::templates::bh_41::prepare_member_json_template;
+use crate::modules::resolver::resolv::{self, DnsConfig};
+use crate::modules::smb::ntlm::NtlmCredentials;
+use crate::modules::smb::transport::{SmbConnection, SMB2_DIALECTS};
+use gpp::GppMember;
+
+/// GPP files with local groups changes, relative to the GPO folder
+const GPP_GROUPS: &str = "Machine\\Preferences\\Groups\\Groups.xml";
+const GPP_SCHEDULED_TASKS: &str = "Machine\\Preferences\\ScheduledTasks\\ScheduledTasks.xml";
+const SECURITY_TEMPLATE: &str = "Machine\\Microsoft\\Windows NT\\SecEdit\\GptTmpl.inf";
+
+/// Function to read GPO files in SYSVOL and add local groups changes in OUs and domains.
+pub async fn collect_gpo_changes(
+   common_args: &Options,
+   vec_users: &Vec<serde_json::value::Value>,
+   vec_groups: &Vec<serde_json::value::Value>,
+   vec_computers: &Vec<serde_json::value::Value>,
+   vec_gpos: &mut Vec<serde_json::value::Value>,
+   vec_ous: &mut Vec<serde_json::value::Value>,
+   vec_domains: &mut Vec<serde_json::value::Value>,
+   sid_type: &HashMap<String, String>,
+   cancel: &CancelToken,
+) {
+   info!("Reading GPO files in SYSVOL started...");
+   let mut connection = match connect_sysvol(common_args).await {
+      Some(connection) => connection,
+      None => {
+         error!("Can't read SYSVOL share on {}, GPO local groups are not collected!",&common_args.domain.bold().red());
+         mark_partial(format!("SYSVOL share on {} not read", common_args.domain));
+         return
+      }
+   };
+
+   // sAMAccountName and NAME@DOMAIN.LOCAL to SID, for members without SID in GPP files
+   let mut name_sid: HashMap<String, String> = HashMap::new();
+   for object in vec_users.iter().chain(vec_groups.iter()).chain(vec_computers.iter()) {
+      if let (Some(name), Some(sid)) = (object["Properties"]["samaccountname"].as_str(), object["ObjectIdentifier"].as_str()) {
+         name_sid.insert(name.to_uppercase(), sid.to_string());
+         if let Some(domain) = object["Properties"]["domain"].as_str() {
+            name_sid.insert(format!("{}@{}", name.to_uppercase(), domain.to_uppercase()), sid.to_string());
+         }
+      }
+   }
+   let domain_names = DomainNames::new(vec_domains);
+
+   // GPO GUID to local groups changes (GPOChanges key, member SID)
+   let mut gpo_changes: HashMap<String, Vec<(&'static str, String)>> = HashMap::new();
+   let total = vec_gpos.len();
+   for (read, gpo) in vec_gpos.iter_mut().enumerate() {
+      if cancel.is_cancelled() {
+         cancel.stopped("SYSVOL GPO files", format!("{} of {} GPOs read", read, total));
+         break
+      }
+      let gpo_name = gpo["Properties"]["name"].as_str().unwrap_or("").to_string();
+      let gpo_path = match get_gpo_path(gpo["Properties"]["gpcpath"].as_str().unwrap_or("")) {
+         Some(path) => path,
+         None => continue,
+      };
+      let mut members: Vec<GppMember> = Vec::new();
+      if let Some(content) = connection.read_file(&format!("{}\\{}",gpo_path,GPP_GROUPS)).map(|c| decode_file(&c)) {
+         if gpp::has_cpassword(&content) {
+            info!("GPP password (cpassword) found in {}",&gpo_name.yellow().bold());
+            gpo["Properties"]["gpppassword"] = true.into();
+         }
+         members.extend(gpp::parse_groups_xml(&content));
+      }
+      if let Some(content) = connection.read_file(&format!("{}\\{}",gpo_path,GPP_SCHEDULED_TASKS)).map(|c| decode_file(&c)) {
+         members.extend(gpp::parse_scheduled_tasks_xml(&content));
+      }
+      if let Some(content) = connection.read_file(&format!("{}\\{}",gpo_path,SECURITY_TEMPLATE)).map(|c| decode_file(&c)) {
+         members.extend(gpttmpl::parse_group_membership(&content));
+      }
+
+      let mut changes: Vec<(&'static str, String)> = Vec::new();
+      for member in members {
+         let sid = match member.sid {
+            Some(sid) => Some(sid),
+            None => resolve_member(&member.name, &name_sid, &domain_names),
+         };
+         match sid {
+            Some(sid) => changes.push((member.group, sid)),
+            None => debug!("Can't resolve {} from {}",&member.name,&gpo_name),
+         }
+      }
+      if !changes.is_empty() {
+         info!("{} local groups changes found in {}",changes.len(),&gpo_name.yellow().bold());
+         gpo_changes.insert(gpo["ObjectIdentifier"].as_str().unwrap_or("").to_string(), changes);
+      }
+   }
+
+   add_gpo_changes(vec_ous, &gpo_changes, sid_type);
+   add_gpo_changes(vec_domains, &gpo_changes, sid_type);
+   info!("Reading GPO files in SYSVOL finished!");
+}
+
+/// Function to read the NTFS permissions of GPO folders and NETLOGON, files writable by non-admins are added in shares.json.
+pub async fn collect_sysvol_acls(
+   common_args: &Options,
+   vec_gpos: &Vec<serde_json::value::Value>,
+   vec_shares: &mut Vec<serde_json::value::Value>,
+   sid_type: &HashMap<String, String>,
+   cancel: &CancelToken,
+) {
+   let mut connection = match connect_sysvol(common_args).await {
+      Some(connection) => connection,
+      None => {
+         error!("Can't read SYSVOL share on {}, SYSVOL permissions are not collected!",&common_args.domain.bold().red());
+         mark_partial(format!("SYSVOL permissions on {} not read", common_args.domain));
+         return
+      }
+   };
+   acls::collect_file_acls(
+      &mut connection,
+      get_sysvol_host(common_args),
+      &common_args.domain,
+      vec_gpos,
+      vec_shares,
+      sid_type,
+      cancel,
+   );
+}
+
+/// Domain controller used for LDAP, or the domain name.
+fn get_sysvol_host(common_args: &Options) -> &String {
+   if common_args.ldapfqdn.contains("not set") { &common_args.domain } else { &common_args.ldapfqdn }
+}
+
+/// Connect to SYSVOL share on the domain controller used for LDAP.
+async fn connect_sysvol(common_args: &Options) -> Option<SmbConnection> {
+   let host = get_sysvol_host(common_args);
+   let ip = if common_args.ip.contains("not set") {
+      resolv::resolver(host.to_owned(), &DnsConfig::new(common_args)).await
+   } else {
+      common_args.ip.to_owned()
+   };
+   let credentials = NtlmCredentials::new(&common_args.username, &common_args.password, &common_args.domain);
+   let mut connection = SmbConnection::connect(&ip)?;
+   connection.negotiate(&SMB2_DIALECTS)?;
+   connection.session_setup(&credentials)?;
+   connection.tree_connect(host, "SYSVOL")?;
+   Some(connection)
+}
+
+/// Function to get the GPO folder relative to the SYSVOL share from gPCFileSysPath.
+/// `\\essos.local\SysVol\essos.local\Policies\{GUID}` => `essos.local\Policies\{GUID}`
+pub fn get_gpo_path(gpcpath: &str) -> Option<String> {
+   let position = gpcpath.to_ascii_lowercase().find("\\sysvol\\")?;
+   Some(gpcpath[position + 8..].trim_end_matches('\\').to_string())
+}
+
+/// Function to decode GPO files, UTF-16LE with BOM (GptTmpl.inf) or UTF-8.
+pub fn decode_file(content: &[u8]) -> String {
+   if content.starts_with(&[0xFF, 0xFE]) {
+      let utf16: Vec<u16> = content[2..].chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+      return String::from_utf16_lossy(&utf16)
+   }
+   String::from_utf8_lossy(content.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(content)).to_string()
+}
+
+/// Function to get the member SID from `DOMAIN\name` or `name`.
+/// The NetBIOS domain name gives the account of this domain, the name alone can match one account of another domain.
+fn resolve_member(name: &str, name_sid: &HashMap<String, String>, domain_names: &DomainNames) -> Option<String> {
+   if let Some(principal) = domain_names.get_principal_name(name) {
+      let (account, domain) = principal.split_once('@').unwrap_or((principal.as_str(), ""));
+      if let Some(sid) = name_sid.get(&principal).or_else(|| name_sid.get(&format!("{}$@{}",account,domain))) {
+         return Some(sid.to_owned())
+      }
+   }
+   let name = name.rsplit('\\').next().unwrap_or(name).to_uppercase();
+   name_sid.get(&name).or_else(|| name_sid.get(&format!("{}$",name))).cloned()
+}
+
+/// Function to push local groups changes in GPOChanges of objects linking the GPOs.
+fn add_gpo_changes(
+   vec_objects: &mut Vec<serde_json::value::Value>,
+   gpo_changes: &HashMap<String, Vec<(&'static str, String)>>,
+   sid_type: &HashMap<String, String>,
+) {
+   for object in vec_objects.iter_mut() {
+      let links: Vec<String> = object["Links"].as_array().unwrap_or(&Vec::new()).iter()
+         .filter_map(|link| link["GUID"].as_str().map(|guid| guid.to_string()))
+         .collect();
+      for guid in links {
+         for (group, sid) in gpo_changes.get(&guid).unwrap_or(&Vec::new()) {
+            let mut member = prepare_member_json_template();
+            member["ObjectIdentifier"] = sid.to_owned().into();
+            member["ObjectType"] = sid_type.get(sid).unwrap_or(&"Group".to_string()).to_owned().into();
+            if let Some(results) = object["GPOChanges"][*group].as_array_mut() {
+               if !results.contains(&member) {
+                  results.push(member);
+               }
+            }
+         }
+      }
+   }
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_get_gpo_path() {
+   assert_eq!(get_gpo_path("\\\\essos.local\\SysVol\\essos.local\\Policies\\{6AC1786C-016F-11D2-945F-00C04FB984F9}").unwrap(), "essos.local\\Policies\\{6AC1786C-016F-11D2-945F-00C04FB984F9}");
+   assert!(get_gpo_path("").is_none());
+   assert_eq!(decode_file(&[0xFF, 0xFE, b'[', 0, b'U', 0]), "[U");
+   assert_eq!(decode_file(&[0xEF, 0xBB, 0xBF, b'<', b'G']), "<G");
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_resolve_member() {
+   let mut name_sid: HashMap<String, String> = HashMap::new();
+   name_sid.insert("ADMINS".to_string(), "S-1-5-21-9-9-9-1200".to_string());
+   name_sid.insert("ADMINS@FABRIKAM.LOCAL".to_string(), "S-1-5-21-9-9-9-1200".to_string());
+   name_sid.insert("ADMINS@CORP.CONTOSO-GROUP.COM".to_string(), "S-1-5-21-1-2-3-1200".to_string());
+   name_sid.insert("SRV01$@CORP.CONTOSO-GROUP.COM".to_string(), "S-1-5-21-1-2-3-1300".to_string());
+   let domain_names = DomainNames::new(&vec![serde_json::json!({"Properties": {"name": "CORP.CONTOSO-GROUP.COM", "netbiosname": "CONTOSO"}})]);
+   assert_eq!(resolve_member("CONTOSO\\admins", &name_sid, &domain_names), Some("S-1-5-21-1-2-3-1200".to_string()));
+   assert_eq!(resolve_member("CONTOSO\\srv01", &name_sid, &domain_names), Some("S-1-5-21-1-2-3-1300".to_string()));
+   assert_eq!(resolve_member("admins", &name_sid, &domain_names), Some("S-1-5-21-9-9-9-1200".to_string()));
+}
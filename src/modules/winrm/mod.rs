@@ -0,0 +1,11 @@
+//! WinRM and WMI reachability survey
+//!
+//! This module will connect to WinRM (5985/5986) and RPC endpoint mapper (135) ports of every collected computer,
+//! then send an authenticated WSMan Identify request over HTTP with NTLM to check if the credentials are accepted.
+//! Without credentials the Identify request is sent unauthenticated, only to get the WinRM version.
+//! Module can be used with **--probe-winrm** args
+//!
+//! - **psremote_reachable**: WinRM listening and credentials accepted (port only if Identify can't be sent, like HTTPS only)
+//! - **wmi_reachable**: RPC endpoint mapper listening, needed by WMI over DCOM
+//!
+pub mod wsman;
@@ -0,0 +1,175 @@
+use log::{info,debug,trace};
+use colored::Colorize;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use std::collections::HashMap;
+use std::io::{prelude::*, BufReader};
+use std::net::{TcpStream, SocketAddr};
+use std::time::Duration;
+
+use crate::cancel::CancelToken;
+use crate::modules::resolver::resolv::DnsConfig;
+use crate::modules::scheduler;
+use crate::modules::smb::ntlm::{self, NtlmCredentials};
+
+/// WinRM HTTP and HTTPS ports, RPC endpoint mapper port
+const WINRM_HTTP_PORT: u16 = 5985;
+const WINRM_HTTPS_PORT: u16 = 5986;
+const RPC_PORT: u16 = 135;
+/// TCP timeout for every WinRM probe
+const WINRM_TIMEOUT: Duration = Duration::from_secs(3);
+/// Maximum size of one HTTP response body
+const MAX_BODY_SIZE: usize = 0x0001_0000;
+/// WSMan Identify request
+const IDENTIFY_REQUEST: &str = "<s:Envelope xmlns:s=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:wsmid=\"http://schemas.dmtf.org/wbem/wsman/identity/1/wsmanidentity.xsd\"><s:Header/><s:Body><wsmid:Identify/></s:Body></s:Envelope>";
+
+/// Structure containing the WinRM and WMI survey result for one computer.
+pub struct WinrmSurvey {
+   pub psremote_reachable: Option<bool>,
+   pub wmi_reachable: Option<bool>,
+   pub version: Option<String>,
+}
+
+/// Structure containing one HTTP response.
+pub struct HttpResponse {
+   pub status: u16,
+   pub headers: HashMap<String, String>,
+   pub body: String,
+}
+
+/// Function to probe WinRM and WMI ports on all enabled computers
+pub async fn survey_all_computers(
+   vec_computers: &mut Vec<serde_json::value::Value>,
+   fqdn_ip: &HashMap<String, String>,
+   dns: &DnsConfig,
+   credentials: &NtlmCredentials,
+   skip_dormant: bool,
+   cancel: &CancelToken,
+) {
+   info!("WinRM and WMI survey started...");
+   let targets = scheduler::prepare_targets(vec_computers, fqdn_ip, dns, skip_dormant).await;
+   let credentials = credentials.to_owned();
+   let results = scheduler::run_probes(&targets, "Probing WinRM and WMI", cancel, move |target| {
+      Some(survey(&target.ip, &target.fqdn, &credentials))
+   }).await;
+
+   let mut psremote = 0;
+   for (index, result) in results {
+      if result.psremote_reachable == Some(true) {
+         psremote += 1;
+         debug!("PSRemoting reachable on {}",vec_computers[index]["Properties"]["name"].as_str().unwrap_or("").yellow().bold());
+      }
+      vec_computers[index]["Properties"]["psremote_reachable"] = result.psremote_reachable.into();
+      vec_computers[index]["Properties"]["wmi_reachable"] = result.wmi_reachable.into();
+      vec_computers[index]["Properties"]["winrmversion"] = result.version.into();
+   }
+   info!("{} computers reachable with PSRemoting",psremote.to_string().yellow().bold());
+   info!("WinRM and WMI survey finished!");
+}
+
+/// Connect to WinRM and RPC ports then send WSMan Identify on WinRM HTTP.
+fn survey(ip: &String, fqdn: &String, credentials: &NtlmCredentials) -> WinrmSurvey {
+   let winrm_open = connect(ip, WINRM_HTTP_PORT).is_some() || connect(ip, WINRM_HTTPS_PORT).is_some();
+   let mut result = WinrmSurvey {
+      psremote_reachable: Some(winrm_open),
+      wmi_reachable: Some(connect(ip, RPC_PORT).is_some()),
+      version: None,
+   };
+   if let Some(mut stream) = connect(ip, WINRM_HTTP_PORT) {
+      match identify(&mut stream, fqdn, credentials) {
+         Some(response) if response.status == 200 => {
+            result.version = get_product_version(&response.body);
+         }
+         // Credentials refused
+         Some(response) if response.status == 401 && !credentials.is_anonymous() => {
+            trace!("WSMan Identify refused on {}",fqdn);
+            result.psremote_reachable = Some(false);
+         }
+         _ => {}
+      }
+   }
+   result
+}
+
+/// Open TCP connection with WinRM timeouts.
+fn connect(ip: &String, port: u16) -> Option<TcpStream> {
+   let address = format!("{}:{}",ip,port).parse::<SocketAddr>()
+      .or_else(|_| format!("[{}]:{}",ip,port).parse::<SocketAddr>()).ok()?;
+   let stream = TcpStream::connect_timeout(&address, WINRM_TIMEOUT).ok()?;
+   stream.set_read_timeout(Some(WINRM_TIMEOUT)).ok()?;
+   stream.set_write_timeout(Some(WINRM_TIMEOUT)).ok()?;
+   Some(stream)
+}
+
+/// Send WSMan Identify, with NTLM in HTTP Negotiate authentication if credentials are set.
+/// NTLM authenticates the connection so both requests must use the same TCP stream.
+fn identify(stream: &mut TcpStream, fqdn: &String, credentials: &NtlmCredentials) -> Option<HttpResponse> {
+   if credentials.is_anonymous() {
+      return http_post(stream, fqdn, &[("WSMANIDENTIFY", "unauthenticated".to_string())], IDENTIFY_REQUEST)
+   }
+   let negotiate = format!("Negotiate {}", STANDARD.encode(ntlm::negotiate_token()));
+   let response = http_post(stream, fqdn, &[("Authorization", negotiate)], "")?;
+   let challenge = response.headers.get("www-authenticate")?
+      .split(',')
+      .find_map(|value| value.trim().strip_prefix("Negotiate "))
+      .and_then(|token| STANDARD.decode(token.trim()).ok())
+      .and_then(|token| ntlm::parse_challenge(&token))?;
+   let (token, _) = ntlm::authenticate_token(credentials, &challenge);
+   let authenticate = format!("Negotiate {}", STANDARD.encode(token));
+   http_post(stream, fqdn, &[("Authorization", authenticate)], IDENTIFY_REQUEST)
+}
+
+/// Send one HTTP POST request to /wsman and read the response.
+fn http_post(stream: &mut TcpStream, fqdn: &String, headers: &[(&str, String)], body: &str) -> Option<HttpResponse> {
+   let mut request = format!("POST /wsman HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/soap+xml;charset=UTF-8\r\nContent-Length: {}\r\n",fqdn,WINRM_HTTP_PORT,body.len());
+   for (name, value) in headers {
+      request.push_str(&format!("{}: {}\r\n",name,value));
+   }
+   request.push_str("\r\n");
+   request.push_str(body);
+   stream.write_all(request.as_bytes()).ok()?;
+   read_response(&mut BufReader::new(stream))
+}
+
+/// Read one HTTP response with Content-Length, header names are lowercase.
+pub fn read_response<R: BufRead>(reader: &mut R) -> Option<HttpResponse> {
+   let mut line = String::new();
+   reader.read_line(&mut line).ok()?;
+   let status = line.split_whitespace().nth(1)?.parse::<u16>().ok()?;
+   let mut headers: HashMap<String, String> = HashMap::new();
+   loop {
+      line.clear();
+      if reader.read_line(&mut line).ok()? == 0 || line.trim().is_empty() {
+         break
+      }
+      if let Some((name, value)) = line.split_once(':') {
+         headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+      }
+   }
+   let length = headers.get("content-length").and_then(|length| length.parse::<usize>().ok()).unwrap_or(0).min(MAX_BODY_SIZE);
+   let mut body = vec![0u8; length];
+   reader.read_exact(&mut body).ok()?;
+   Some(HttpResponse { status, headers, body: String::from_utf8_lossy(&body).to_string() })
+}
+
+/// Function to get the ProductVersion of one WSMan Identify response, like `OS: 10.0.17763 SP: 0.0 Stack: 3.0`.
+pub fn get_product_version(body: &str) -> Option<String> {
+   let start = body.find("ProductVersion>")? + "ProductVersion>".len();
+   let end = body[start..].find('<')?;
+   Some(body[start..start + end].trim().to_string())
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_read_response() {
+   let body = "<s:Envelope><s:Body><wsmid:IdentifyResponse><wsmid:ProductVendor>Microsoft Corporation</wsmid:ProductVendor><wsmid:ProductVersion>OS: 10.0.17763 SP: 0.0 Stack: 3.0</wsmid:ProductVersion></wsmid:IdentifyResponse></s:Body></s:Envelope>";
+   let raw = format!("HTTP/1.1 200 \r\nContent-Type: application/soap+xml;charset=UTF-8\r\nServer: Microsoft-HTTPAPI/2.0\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+   let response = read_response(&mut raw.as_bytes()).unwrap();
+   assert_eq!(response.status, 200);
+   assert_eq!(response.headers.get("server").unwrap(), "Microsoft-HTTPAPI/2.0");
+   assert_eq!(get_product_version(&response.body).unwrap(), "OS: 10.0.17763 SP: 0.0 Stack: 3.0");
+   let raw = "HTTP/1.1 401 \r\nWWW-Authenticate: Negotiate TlRMTVNTUAACAAAA\r\nContent-Length: 0\r\n\r\n";
+   let response = read_response(&mut raw.as_bytes()).unwrap();
+   assert_eq!(response.status, 401);
+   assert_eq!(response.headers.get("www-authenticate").unwrap(), "Negotiate TlRMTVNTUAACAAAA");
+   assert!(get_product_version("").is_none());
+}
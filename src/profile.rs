@@ -0,0 +1,158 @@
+//! Collection profiles with protected credentials (`rusthound profile save`, **--profile**)
+//!
+//! A profile keeps the domain, the username and the password of one recurring collection, so a scheduled task on a
+//! dedicated box doesn't need a plaintext password in its command line or in a config file.
+//! The password is encrypted with DPAPI for the current Windows user: only this user on this machine can read it back.
+//! Profiles are saved in `%APPDATA%\rusthound\profiles\<name>.json`, DPAPI is only available on Windows.
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use colored::Colorize;
+use log::info;
+
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::PathBuf;
+
+use crate::args::ProfileOptions;
+
+/// Function to get the directory of the saved profiles.
+pub fn profiles_directory() -> PathBuf
+{
+   let base = std::env::var("APPDATA")
+      .map(PathBuf::from)
+      .unwrap_or_else(|_| PathBuf::from(std::env::var("HOME").unwrap_or(".".to_string())).join(".config"));
+   base.join("rusthound").join("profiles")
+}
+
+/// Function to check one profile name, it is used as file name.
+pub fn is_valid_name(name: &str) -> bool
+{
+   !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') && !name.starts_with('.')
+}
+
+/// Function to encrypt data with DPAPI for the current user.
+#[cfg(windows)]
+pub fn protect(data: &[u8]) -> std::io::Result<Vec<u8>>
+{
+   use windows_sys::Win32::Foundation::LocalFree;
+   use windows_sys::Win32::Security::Cryptography::{CryptProtectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB};
+
+   let input = CRYPT_INTEGER_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 };
+   let mut output = CRYPT_INTEGER_BLOB { cbData: 0, pbData: std::ptr::null_mut() };
+   // Safety: input points to data for the call, output is allocated by DPAPI and freed with LocalFree
+   unsafe {
+      if CryptProtectData(&input, std::ptr::null(), std::ptr::null(), std::ptr::null(), std::ptr::null(), CRYPTPROTECT_UI_FORBIDDEN, &mut output) == 0 {
+         return Err(Error::last_os_error())
+      }
+      let protected = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+      LocalFree(output.pbData as _);
+      Ok(protected)
+   }
+}
+
+/// Function to decrypt data encrypted with DPAPI by the current user.
+#[cfg(windows)]
+pub fn unprotect(data: &[u8]) -> std::io::Result<Vec<u8>>
+{
+   use windows_sys::Win32::Foundation::LocalFree;
+   use windows_sys::Win32::Security::Cryptography::{CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB};
+
+   let input = CRYPT_INTEGER_BLOB { cbData: data.len() as u32, pbData: data.as_ptr() as *mut u8 };
+   let mut output = CRYPT_INTEGER_BLOB { cbData: 0, pbData: std::ptr::null_mut() };
+   // Safety: same as protect()
+   unsafe {
+      if CryptUnprotectData(&input, std::ptr::null_mut(), std::ptr::null(), std::ptr::null(), std::ptr::null(), CRYPTPROTECT_UI_FORBIDDEN, &mut output) == 0 {
+         return Err(Error::last_os_error())
+      }
+      let unprotected = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+      LocalFree(output.pbData as _);
+      Ok(unprotected)
+   }
+}
+
+#[cfg(not(windows))]
+pub fn protect(_data: &[u8]) -> std::io::Result<Vec<u8>>
+{
+   Err(Error::new(ErrorKind::Unsupported, "Profiles with DPAPI protected credentials are only available on Windows"))
+}
+
+#[cfg(not(windows))]
+pub fn unprotect(_data: &[u8]) -> std::io::Result<Vec<u8>>
+{
+   Err(Error::new(ErrorKind::Unsupported, "Profiles with DPAPI protected credentials are only available on Windows"))
+}
+
+/// Function to make the content of one profile file, the password is already protected.
+pub fn make_profile(domain: &str, username: &str, protected_password: &[u8]) -> serde_json::value::Value
+{
+   serde_json::json!({
+      "domain": domain.to_uppercase(),
+      "username": username,
+      "password": STANDARD.encode(protected_password),
+      "protection": "dpapi-user",
+   })
+}
+
+/// Function to read one profile file content: domain, username and protected password.
+pub fn parse_profile(profile: &serde_json::value::Value) -> Option<(String, String, Vec<u8>)>
+{
+   Some((
+      profile["domain"].as_str()?.to_string(),
+      profile["username"].as_str()?.to_string(),
+      STANDARD.decode(profile["password"].as_str()?).ok()?,
+   ))
+}
+
+/// Function to save one profile (`rusthound profile save`), the password is prompted if not read from stdin.
+pub fn save_profile(profile_args: &ProfileOptions) -> crate::errors::Result<()> {
+   if !is_valid_name(&profile_args.name) {
+      return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid profile name {}, only letters, digits, '-', '_' and '.'", profile_args.name)).into())
+   }
+   let password = if profile_args.password_stdin {
+      let mut line = String::new();
+      std::io::stdin().read_line(&mut line)?;
+      line.trim_end_matches(|c| c == '\n' || c == '\r').to_string()
+   } else {
+      rpassword::prompt_password("Password: ")?
+   };
+   let profile = make_profile(&profile_args.domain, &profile_args.username, &protect(password.as_bytes())?);
+
+   let directory = profiles_directory();
+   fs::create_dir_all(&directory)?;
+   let path = directory.join(format!("{}.json", profile_args.name));
+   fs::write(&path, serde_json::to_string_pretty(&profile).unwrap_or_default())?;
+   info!("Profile {} saved in {}", profile_args.name.bold(), path.display());
+   Ok(())
+}
+
+/// Function to load one saved profile (**--profile**): domain, username and password.
+pub fn load_profile(name: &str) -> std::io::Result<(String, String, String)>
+{
+   if !is_valid_name(name) {
+      return Err(Error::new(ErrorKind::InvalidInput, format!("Invalid profile name {}", name)))
+   }
+   let path = profiles_directory().join(format!("{}.json", name));
+   let profile: serde_json::value::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+   let (domain, username, protected_password) = parse_profile(&profile)
+      .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Invalid profile file {}", path.display())))?;
+   let password = String::from_utf8(unprotect(&protected_password)?)
+      .map_err(|_| Error::new(ErrorKind::InvalidData, format!("Invalid password in profile {}", name)))?;
+   Ok((domain, username, password))
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_profile() {
+   assert!(is_valid_name("essos-weekly_1"));
+   assert!(!is_valid_name("../essos"));
+   assert!(!is_valid_name(".hidden"));
+   assert!(!is_valid_name(""));
+
+   let profile = make_profile("essos.local", "khal.drogo@essos.local", &[1, 2, 3]);
+   assert_eq!(profile["password"], "AQID");
+   assert_eq!(parse_profile(&profile), Some(("ESSOS.LOCAL".to_string(), "khal.drogo@essos.local".to_string(), vec![1, 2, 3])));
+
+   #[cfg(windows)]
+   assert_eq!(unprotect(&protect(b"horse").unwrap()).unwrap(), b"horse".to_vec());
+   #[cfg(not(windows))]
+   assert_eq!(protect(b"horse").unwrap_err().kind(), ErrorKind::Unsupported);
+}
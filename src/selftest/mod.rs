@@ -0,0 +1,298 @@
+//! Self-test of the parsing and output pipeline (`rusthound selftest`)
+//!
+//! An embedded ESSOS.LOCAL fixture (LDIF with binary objectSid, objectGUID and nTSecurityDescriptor values) goes through
+//! the same steps as a collection: parser, checker, findings report and .json files in a temporary directory.
+//! The files are read back and compared with the embedded golden file: every golden object must be found,
+//! with the same values for the properties, ACEs and members it lists. Operators can check a cross-compiled
+//! binary on the target platform (endianness, alignment, libc) before an engagement, no DC is needed.
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use colored::Colorize;
+use ldap3::SearchEntry;
+use log::info;
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::args::Options;
+use crate::cancel::CancelToken;
+use crate::json::checker::bh_41::add_tier_zero_tags;
+use crate::json::checker::check_all_result;
+use crate::json::maker::make_result;
+use crate::json::parser::parse_result_type;
+use crate::json::report::make_report;
+use crate::json::report::service::make_service_accounts;
+use crate::json::stats::RunStats;
+
+/// Domain of the fixture
+pub const FIXTURE_DOMAIN: &str = "ESSOS.LOCAL";
+/// LDAP entries of the fixture domain
+const FIXTURE_LDIF: &str = include_str!("fixtures/essos.ldif");
+/// Objects expected in the .json files, by BloodHound file type
+const FIXTURE_GOLDEN: &str = include_str!("fixtures/essos.golden.json");
+
+/// Function to parse LDIF entries, values with `::` are base64 encoded binary values.
+pub fn parse_ldif(content: &str) -> Vec<SearchEntry>
+{
+   let mut entries: Vec<SearchEntry> = Vec::new();
+   // Folded lines start with one space
+   let mut lines: Vec<String> = Vec::new();
+   for line in content.lines() {
+      match (line.strip_prefix(' '), lines.last_mut()) {
+         (Some(folded), Some(last)) => last.push_str(folded),
+         _ => lines.push(line.to_string()),
+      }
+   }
+   let mut entry: Option<SearchEntry> = None;
+   for line in lines.iter().filter(|line| !line.starts_with('#')) {
+      if line.trim().is_empty() {
+         entries.extend(entry.take());
+         continue
+      }
+      let (attribute, value) = match line.split_once(':') {
+         Some(attribute_value) => attribute_value,
+         None => continue,
+      };
+      if attribute == "dn" {
+         entries.extend(entry.take());
+         entry = Some(SearchEntry { dn: value.trim().to_string(), attrs: HashMap::new(), bin_attrs: HashMap::new() });
+         continue
+      }
+      let entry = match entry.as_mut() {
+         Some(entry) => entry,
+         None => continue,
+      };
+      match value.strip_prefix(':') {
+         Some(encoded) => {
+            if let Ok(value) = STANDARD.decode(encoded.trim()) {
+               entry.bin_attrs.entry(attribute.to_string()).or_default().push(value);
+            }
+         },
+         None => entry.attrs.entry(attribute.to_string()).or_default().push(value.trim_start().to_string()),
+      }
+   }
+   entries.extend(entry);
+   entries
+}
+
+/// Function to check that one expected value is included in the actual value:
+/// same keys and values for objects, one matching element for every expected element of arrays.
+pub fn is_subset(expected: &serde_json::value::Value, actual: &serde_json::value::Value) -> bool
+{
+   match (expected, actual) {
+      (serde_json::value::Value::Object(expected), actual) => expected.iter()
+         .all(|(key, value)| is_subset(value, &actual[key])),
+      (serde_json::value::Value::Array(expected), serde_json::value::Value::Array(actual)) => expected.iter()
+         .all(|value| actual.iter().any(|element| is_subset(value, element))),
+      (expected, actual) => expected == actual,
+   }
+}
+
+/// Function to compare the objects of the .json files with the golden objects, returns the failures.
+pub fn compare_golden(
+   golden: &serde_json::value::Value,
+   files: &HashMap<String, Vec<serde_json::value::Value>>,
+) -> Vec<String>
+{
+   let mut failures: Vec<String> = Vec::new();
+   let empty = Vec::new();
+   for (bh_type, expected_objects) in golden.as_object().cloned().unwrap_or_default() {
+      let objects = files.get(&bh_type).unwrap_or(&empty);
+      for expected in expected_objects.as_array().unwrap_or(&empty) {
+         if !objects.iter().any(|object| is_subset(expected, object)) {
+            let name = expected["ObjectIdentifier"].as_str().or(expected["Properties"]["name"].as_str()).unwrap_or("");
+            failures.push(format!("{} {} doesn't match the golden object", bh_type, name));
+         }
+      }
+   }
+   failures
+}
+
+/// Function to run the self-test, returns the failures.
+pub fn run_selftest(common_args: &Options) -> crate::errors::Result<Vec<String>> {
+   let directory = std::env::temp_dir().join(format!("rusthound_selftest_{}", std::process::id()));
+   fs::create_dir_all(&directory)?;
+
+   // Fixture collection written in the temporary directory only
+   let mut selftest_args = common_args.to_owned();
+   selftest_args.domain = FIXTURE_DOMAIN.to_string();
+   selftest_args.path = directory.to_string_lossy().to_string();
+   selftest_args.output_format = "json".to_string();
+   selftest_args.output_layout = "flat".to_string();
+   selftest_args.zip = false;
+   selftest_args.ndjson = false;
+   selftest_args.sarif = false;
+   selftest_args.relay_targets = false;
+   selftest_args.stdout = false;
+   selftest_args.no_disk = false;
+   selftest_args.sharphound_ids = false;
+   selftest_args.export_users_format = "not set".to_string();
+   for sink in [&mut selftest_args.http_url, &mut selftest_args.splunk_url, &mut selftest_args.elastic_url, &mut selftest_args.neo4j_url, &mut selftest_args.upload_url] {
+      *sink = "not set".to_string();
+   }
+
+   let entries = parse_ldif(FIXTURE_LDIF);
+   info!("Self-test with {} fixture LDAP entries", entries.len());
+
+   let mut vec_users: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_groups: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_computers: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_ous: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_domains: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_gpos: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_fsps: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_containers: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_contacts: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_trusts: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_cas: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_templates: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_sites: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_subnets: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_sitelinks: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_authn: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_shares: Vec<serde_json::value::Value> = Vec::new();
+   let mut vec_services: Vec<serde_json::value::Value> = Vec::new();
+   let mut dn_sid = HashMap::new();
+   let mut sid_type = HashMap::new();
+   let mut fqdn_sid = HashMap::new();
+   let mut fqdn_ip = HashMap::new();
+   let mut adcs_templates = HashMap::new();
+
+   parse_result_type(
+      &selftest_args,
+      entries,
+      &mut vec_users,
+      &mut vec_groups,
+      &mut vec_computers,
+      &mut vec_ous,
+      &mut vec_domains,
+      &mut vec_gpos,
+      &mut vec_fsps,
+      &mut vec_containers,
+      &mut vec_contacts,
+      &mut vec_trusts,
+      &mut vec_cas,
+      &mut vec_templates,
+      &mut vec_sites,
+      &mut vec_subnets,
+      &mut vec_sitelinks,
+      &mut vec_authn,
+      &mut vec_shares,
+      &mut vec_services,
+      &mut dn_sid,
+      &mut sid_type,
+      &mut fqdn_sid,
+      &mut fqdn_ip,
+      &mut adcs_templates,
+   );
+   check_all_result(
+      &selftest_args.domain,
+      &mut vec_users,
+      &mut vec_groups,
+      &mut vec_computers,
+      &mut vec_ous,
+      &mut vec_domains,
+      &mut vec_gpos,
+      &mut vec_fsps,
+      &mut vec_containers,
+      &mut vec_contacts,
+      &mut vec_trusts,
+      &mut vec_sites,
+      &mut vec_subnets,
+      &mut vec_sitelinks,
+      &mut dn_sid,
+      &mut sid_type,
+      &mut fqdn_sid,
+      &mut fqdn_ip,
+   );
+   add_tier_zero_tags(&mut vec_users, &mut vec_groups, &mut vec_computers, &mut vec_domains, &mut vec_cas);
+   let vec_service_accounts = make_service_accounts(&vec_users, &vec_groups);
+   let vec_findings = make_report(
+      &selftest_args,
+      &vec_users,
+      &vec_groups,
+      &vec_computers,
+      &vec_ous,
+      &vec_domains,
+      &vec_containers,
+      &vec_trusts,
+      &vec_templates,
+      &vec_service_accounts,
+      &vec_authn,
+      &vec_shares,
+   );
+   let mut run_stats = RunStats::new();
+   make_result(
+      &selftest_args,
+      vec_users,
+      vec_groups,
+      vec_computers,
+      vec_ous,
+      vec_domains,
+      vec_gpos,
+      vec_containers,
+      vec_contacts,
+      &mut vec_cas,
+      &mut vec_templates,
+      vec_sites,
+      vec_authn,
+      vec_shares,
+      vec_services,
+      vec_findings,
+      vec_service_accounts,
+      &mut run_stats,
+      &CancelToken::new(0),
+   )?;
+
+   // Read back the BloodHound files
+   let mut files: HashMap<String, Vec<serde_json::value::Value>> = HashMap::new();
+   for entry in fs::read_dir(&directory)? {
+      let path = entry?.path();
+      if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+         continue
+      }
+      let final_json: serde_json::value::Value = serde_json::from_str(&fs::read_to_string(&path)?).unwrap_or_default();
+      if let (Some(bh_type), Some(data)) = (final_json["meta"]["type"].as_str(), final_json["data"].as_array()) {
+         files.entry(bh_type.to_lowercase()).or_default().extend(data.iter().cloned());
+      }
+   }
+   fs::remove_dir_all(&directory).ok();
+
+   let golden: serde_json::value::Value = serde_json::from_str(FIXTURE_GOLDEN).unwrap_or_default();
+   let failures = compare_golden(&golden, &files);
+   if failures.is_empty() {
+      info!("Self-test {}, {} golden objects found", "passed".green().bold(), golden.as_object().map(|golden| golden.values().filter_map(|objects| objects.as_array()).map(|objects| objects.len()).sum::<usize>()).unwrap_or(0));
+   }
+   Ok(failures)
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_parse_ldif() {
+   let entries = parse_ldif(FIXTURE_LDIF);
+   assert_eq!(entries.len(), 6);
+   assert_eq!(entries[1].dn, "CN=Domain Admins,CN=Users,DC=ESSOS,DC=LOCAL");
+   assert_eq!(entries[1].attrs["objectClass"], vec!["top".to_string(), "group".to_string()]);
+   assert_eq!(entries[1].bin_attrs["objectSid"][0].len(), 28);
+
+   let entries = parse_ldif("dn: CN=jorah,DC=ESSOS,DC=LOCAL\ndescription: long\n  value\nobjectSid:: AQE=\n");
+   assert_eq!(entries[0].attrs["description"], vec!["long value".to_string()]);
+   assert_eq!(entries[0].bin_attrs["objectSid"], vec![vec![1u8, 1]]);
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_compare_golden() {
+   let golden: serde_json::value::Value = serde_json::from_str(FIXTURE_GOLDEN).unwrap();
+   assert!(golden["users"].is_array());
+
+   let expected = serde_json::json!({"groups": [{"ObjectIdentifier": "S-1-5-21-1-2-3-512", "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1104"}]}]});
+   let mut files: HashMap<String, Vec<serde_json::value::Value>> = HashMap::new();
+   files.insert("groups".to_string(), vec![serde_json::json!({
+      "ObjectIdentifier": "S-1-5-21-1-2-3-512",
+      "Properties": {"name": "DOMAIN ADMINS@ESSOS.LOCAL"},
+      "Members": [{"ObjectIdentifier": "S-1-5-21-1-2-3-1105", "ObjectType": "User"}, {"ObjectIdentifier": "S-1-5-21-1-2-3-1104", "ObjectType": "User"}],
+   })]);
+   assert!(compare_golden(&expected, &files).is_empty());
+   files.get_mut("groups").unwrap()[0]["Members"] = serde_json::json!([]);
+   assert_eq!(compare_golden(&expected, &files), vec!["groups S-1-5-21-1-2-3-512 doesn't match the golden object".to_string()]);
+}
@@ -0,0 +1,314 @@
+//! On-disk store of the LDAP entries for very large domains
+//!
+//! With **--disk-store** the entries received from LDAP are appended to one file instead of staying in memory,
+//! with their raw security descriptors they are the biggest part of the memory used by a collection.
+//! The file is memory-mapped for the parsing, so the OS pages entries in and out as needed and forests
+//! with millions of objects can be collected on small hosts. The file is removed once parsed.
+//!
+//! Without **--disk-store** the entries are sent to the parser workers as they are received,
+//! see [`crate::json::parser::pipeline`].
+//!
+//! Record format, all lengths are u32 little-endian:
+//! `dn_len dn attrs_count (name_len name values_count (value_len value)*)* bin_attrs_count (same)*`
+use ldap3::SearchEntry;
+use log::{info,debug};
+use memmap2::Mmap;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::json::parser::pipeline::EntrySender;
+
+/// LDAP entries kept in memory or in the on-disk store, or sent to the parser workers.
+pub enum Entries {
+   Memory(Vec<SearchEntry>),
+   Disk(EntryStore),
+   /// Sender and number of entries sent
+   Pipeline(EntrySender, usize),
+}
+
+impl Entries {
+   /// In memory if `disk_store` is "not set", else in one store file in this directory.
+   pub fn new(disk_store: &String) -> io::Result<Self> {
+      if disk_store.contains("not set") {
+         return Ok(Entries::Memory(Vec::new()))
+      }
+      Ok(Entries::Disk(EntryStore::create(disk_store)?))
+   }
+
+   pub fn push(&mut self, entry: SearchEntry) -> io::Result<()> {
+      match self {
+         Entries::Memory(entries) => {
+            entries.push(entry);
+            Ok(())
+         },
+         Entries::Disk(store) => store.push(&entry),
+         Entries::Pipeline(sender, count) => {
+            // Blocks while the channel is full so the fetch waits for the parsing
+            sender.send((*count, entry)).map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "LDAP objects parser workers stopped"))?;
+            *count += 1;
+            Ok(())
+         },
+      }
+   }
+
+   pub fn len(&self) -> usize {
+      match self {
+         Entries::Memory(entries) => entries.len(),
+         Entries::Disk(store) => store.len(),
+         Entries::Pipeline(_, count) => *count,
+      }
+   }
+
+   pub fn is_empty(&self) -> bool {
+      self.len() == 0
+   }
+}
+
+impl IntoIterator for Entries {
+   type Item = SearchEntry;
+   type IntoIter = EntriesIter;
+
+   fn into_iter(self) -> EntriesIter {
+      match self {
+         Entries::Memory(entries) => EntriesIter::Memory(entries.into_iter()),
+         Entries::Disk(store) => EntriesIter::Disk(store.into_reader()),
+         // Already parsed by the workers
+         Entries::Pipeline(..) => EntriesIter::Memory(Vec::new().into_iter()),
+      }
+   }
+}
+
+/// Iterator over the entries, in memory or read from the store file.
+pub enum EntriesIter {
+   Memory(std::vec::IntoIter<SearchEntry>),
+   Disk(io::Result<StoreReader>),
+}
+
+impl Iterator for EntriesIter {
+   type Item = SearchEntry;
+
+   fn next(&mut self) -> Option<SearchEntry> {
+      match self {
+         EntriesIter::Memory(entries) => entries.next(),
+         EntriesIter::Disk(Ok(reader)) => reader.next(),
+         EntriesIter::Disk(Err(err)) => {
+            log::error!("Can't read the LDAP entries store: {}", err);
+            crate::json::stats::mark_partial(format!("LDAP entries store not read: {}", err));
+            None
+         },
+      }
+   }
+
+   fn size_hint(&self) -> (usize, Option<usize>) {
+      let len = match self {
+         EntriesIter::Memory(entries) => entries.len(),
+         EntriesIter::Disk(Ok(reader)) => reader.index.len() - reader.next,
+         EntriesIter::Disk(Err(_)) => 0,
+      };
+      (len, Some(len))
+   }
+}
+
+impl ExactSizeIterator for EntriesIter {}
+
+/// Append-only file of LDAP entries with the offset of each entry.
+pub struct EntryStore {
+   path: PathBuf,
+   writer: BufWriter<File>,
+   offset: u64,
+   index: Vec<u64>,
+}
+
+impl EntryStore {
+   /// Create the store file in the directory.
+   pub fn create(directory: &str) -> io::Result<Self> {
+      fs::create_dir_all(directory)?;
+      let path = Path::new(directory).join(format!(".rusthound_{}.store", std::process::id()));
+      let file = OpenOptions::new().create(true).write(true).truncate(true).read(true).open(&path)?;
+      info!("LDAP entries stored in {}", path.display());
+      Ok(EntryStore { path, writer: BufWriter::new(file), offset: 0, index: Vec::new() })
+   }
+
+   /// Append one entry.
+   pub fn push(&mut self, entry: &SearchEntry) -> io::Result<()> {
+      let record = encode_entry(entry);
+      self.writer.write_all(&record)?;
+      self.index.push(self.offset);
+      self.offset += record.len() as u64;
+      Ok(())
+   }
+
+   pub fn len(&self) -> usize {
+      self.index.len()
+   }
+
+   pub fn is_empty(&self) -> bool {
+      self.index.is_empty()
+   }
+
+   /// Flush the file and map it in memory for reading.
+   pub fn into_reader(mut self) -> io::Result<StoreReader> {
+      self.writer.flush()?;
+      let file = File::open(&self.path)?;
+      debug!("Reading {} LDAP entries ({} bytes) from {}", self.index.len(), self.offset, self.path.display());
+      // Empty files can't be mapped
+      let mmap = if self.offset > 0 {
+         // The file is private to this process and not modified while mapped
+         Some(unsafe { Mmap::map(&file)? })
+      } else {
+         None
+      };
+      // The reader removes the file now
+      let path = std::mem::take(&mut self.path);
+      Ok(StoreReader { path, mmap, index: std::mem::take(&mut self.index), next: 0 })
+   }
+}
+
+impl Drop for EntryStore {
+   fn drop(&mut self) {
+      if !self.path.as_os_str().is_empty() {
+         fs::remove_file(&self.path).ok();
+      }
+   }
+}
+
+/// Memory-mapped store file, the file is removed when the reader is dropped.
+pub struct StoreReader {
+   path: PathBuf,
+   mmap: Option<Mmap>,
+   index: Vec<u64>,
+   next: usize,
+}
+
+impl StoreReader {
+   /// Get one entry by its position in the store.
+   pub fn get(&self, position: usize) -> Option<SearchEntry> {
+      let offset = *self.index.get(position)? as usize;
+      decode_entry(&self.mmap.as_ref()?[offset..]).map(|(entry, _)| entry)
+   }
+}
+
+impl Iterator for StoreReader {
+   type Item = SearchEntry;
+
+   fn next(&mut self) -> Option<SearchEntry> {
+      let entry = self.get(self.next)?;
+      self.next += 1;
+      Some(entry)
+   }
+}
+
+impl Drop for StoreReader {
+   fn drop(&mut self) {
+      // Unmap before removing, needed on Windows
+      self.mmap.take();
+      fs::remove_file(&self.path).ok();
+   }
+}
+
+fn push_bytes(record: &mut Vec<u8>, bytes: &[u8]) {
+   record.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+   record.extend_from_slice(bytes);
+}
+
+fn push_attrs<T: AsRef<[u8]>>(record: &mut Vec<u8>, attrs: &HashMap<String, Vec<T>>) {
+   record.extend_from_slice(&(attrs.len() as u32).to_le_bytes());
+   for (name, values) in attrs {
+      push_bytes(record, name.as_bytes());
+      record.extend_from_slice(&(values.len() as u32).to_le_bytes());
+      for value in values {
+         push_bytes(record, value.as_ref());
+      }
+   }
+}
+
+/// Function to encode one entry in one record.
+pub fn encode_entry(entry: &SearchEntry) -> Vec<u8> {
+   let mut record: Vec<u8> = Vec::new();
+   push_bytes(&mut record, entry.dn.as_bytes());
+   push_attrs(&mut record, &entry.attrs);
+   push_attrs(&mut record, &entry.bin_attrs);
+   record
+}
+
+/// Cursor reading one record.
+struct Cursor<'a> {
+   data: &'a [u8],
+   position: usize,
+}
+
+impl<'a> Cursor<'a> {
+   fn read_u32(&mut self) -> Option<usize> {
+      let bytes = self.data.get(self.position..self.position + 4)?;
+      self.position += 4;
+      Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+   }
+
+   fn read_bytes(&mut self) -> Option<&'a [u8]> {
+      let len = self.read_u32()?;
+      let bytes = self.data.get(self.position..self.position + len)?;
+      self.position += len;
+      Some(bytes)
+   }
+
+   fn read_attrs(&mut self) -> Option<Vec<(String, Vec<&'a [u8]>)>> {
+      let count = self.read_u32()?;
+      let mut attrs = Vec::with_capacity(count);
+      for _ in 0..count {
+         let name = String::from_utf8_lossy(self.read_bytes()?).to_string();
+         let values_count = self.read_u32()?;
+         let mut values = Vec::with_capacity(values_count);
+         for _ in 0..values_count {
+            values.push(self.read_bytes()?);
+         }
+         attrs.push((name, values));
+      }
+      Some(attrs)
+   }
+}
+
+/// Function to decode one record, return the entry and the record length.
+pub fn decode_entry(data: &[u8]) -> Option<(SearchEntry, usize)> {
+   let mut cursor = Cursor { data, position: 0 };
+   let dn = String::from_utf8_lossy(cursor.read_bytes()?).to_string();
+   let attrs = cursor.read_attrs()?.into_iter()
+      .map(|(name, values)| (name, values.iter().map(|value| String::from_utf8_lossy(value).to_string()).collect()))
+      .collect();
+   let bin_attrs = cursor.read_attrs()?.into_iter()
+      .map(|(name, values)| (name, values.iter().map(|value| value.to_vec()).collect()))
+      .collect();
+   Some((SearchEntry { dn, attrs, bin_attrs }, cursor.position))
+}
+
+#[test]
+#[rustfmt::skip]
+pub fn test_entry_store() {
+   let mut attrs = HashMap::new();
+   attrs.insert("name".to_string(), vec!["Société".to_string()]);
+   attrs.insert("member".to_string(), vec!["CN=A,DC=ESSOS,DC=LOCAL".to_string(), "CN=B,DC=ESSOS,DC=LOCAL".to_string()]);
+   let mut bin_attrs = HashMap::new();
+   bin_attrs.insert("objectSid".to_string(), vec![vec![1, 5, 0, 0, 0, 0, 0, 5]]);
+   let entry = SearchEntry { dn: "OU=SOCIÉTÉ,DC=ESSOS,DC=LOCAL".to_string(), attrs, bin_attrs };
+
+   let record = encode_entry(&entry);
+   let (decoded, len) = decode_entry(&record).unwrap();
+   assert_eq!(len, record.len());
+   assert_eq!(decoded.dn, entry.dn);
+   assert_eq!(decoded.attrs, entry.attrs);
+   assert_eq!(decoded.bin_attrs, entry.bin_attrs);
+   assert!(decode_entry(&record[..record.len() - 1]).is_none());
+
+   let directory = std::env::temp_dir().join("rusthound_test_entry_store");
+   let mut entries = Entries::new(&directory.to_string_lossy().to_string()).unwrap();
+   entries.push(entry.to_owned()).unwrap();
+   entries.push(entry).unwrap();
+   assert_eq!(entries.len(), 2);
+   let entries: Vec<SearchEntry> = entries.into_iter().collect();
+   assert_eq!(entries.len(), 2);
+   assert_eq!(entries[1].attrs["member"].len(), 2);
+   fs::remove_dir_all(directory).ok();
+}